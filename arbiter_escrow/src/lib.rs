@@ -0,0 +1,262 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EscrowState {
+    AwaitingFunding,
+    Funded,
+    Delivered,
+    Disputed,
+    Settled,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct EscrowStateChangedEvent {
+    pub previous_state: EscrowState,
+    pub new_state: EscrowState,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct EscrowSettledEvent {
+    pub seller_amount: Decimal,
+    pub buyer_amount: Decimal,
+    pub arbiter_fee: Decimal,
+}
+
+#[blueprint]
+#[events(EscrowStateChangedEvent, EscrowSettledEvent)]
+pub mod arbiter_escrow {
+
+    enable_method_auth! {
+        roles {
+            buyer => updatable_by: [];
+            seller => updatable_by: [];
+            arbiter => updatable_by: [];
+        },
+        methods {
+
+            fund => restrict_to :[buyer];
+            release_to_seller => restrict_to :[buyer];
+            withdraw_buyer_funds => restrict_to :[buyer];
+
+            mark_delivered => restrict_to :[seller];
+            refund_buyer => restrict_to :[seller];
+            withdraw_seller_funds => restrict_to :[seller];
+
+            dispute => restrict_to :[buyer, seller];
+
+            resolve => restrict_to :[arbiter];
+            withdraw_arbiter_fee => restrict_to :[arbiter];
+
+            get_state => PUBLIC;
+
+        }
+    }
+
+    pub struct ArbiterEscrow {
+        state: EscrowState,
+
+        /// Amount the buyer must fund
+        amount: Decimal,
+
+        /// Funds held until settlement
+        escrow: Vault,
+
+        /// Settled funds, withdrawable by each party
+        seller_funds: Vault,
+        buyer_funds: Vault,
+        arbiter_funds: Vault,
+
+        /// Share of the escrowed amount paid to the arbiter when resolving a dispute
+        arbiter_fee_rate: Decimal,
+    }
+
+    impl ArbiterEscrow {
+        pub fn instantiate(
+            payment_res_address: ResourceAddress,
+            amount: Decimal,
+            arbiter_fee_rate: Decimal,
+            buyer_rule: AccessRule,
+            seller_rule: AccessRule,
+            arbiter_badge: ResourceAddress,
+        ) -> Global<ArbiterEscrow> {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                payment_res_address,
+                Some("Payment resource must be fungible".to_string()),
+            );
+            assert!(amount > Decimal::ZERO, "Amount must be greater than zero!");
+            assert!(
+                arbiter_fee_rate >= Decimal::ZERO && arbiter_fee_rate <= Decimal::ONE,
+                "Arbiter fee rate must be between 0 and 1"
+            );
+
+            Self {
+                state: EscrowState::AwaitingFunding,
+                amount,
+                escrow: Vault::new(payment_res_address),
+                seller_funds: Vault::new(payment_res_address),
+                buyer_funds: Vault::new(payment_res_address),
+                arbiter_funds: Vault::new(payment_res_address),
+                arbiter_fee_rate,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .roles(roles!(
+                buyer => buyer_rule;
+                seller => seller_rule;
+                arbiter => rule!(require(arbiter_badge));
+            ))
+            .globalize()
+        }
+
+        /* BUYER METHODS */
+
+        /// Fund the escrow with the agreed amount. Returns the change.
+        pub fn fund(&mut self, mut payment: Bucket) -> Bucket {
+            self._assert_state(&[EscrowState::AwaitingFunding]);
+            assert!(
+                payment.resource_address() == self.escrow.resource_address(),
+                "Payment resource address mismatch"
+            );
+
+            self.escrow.put(payment.take(self.amount));
+            self._set_state(EscrowState::Funded);
+
+            payment
+        }
+
+        /// Release the escrowed funds to the seller
+        pub fn release_to_seller(&mut self) {
+            self._assert_state(&[EscrowState::Funded, EscrowState::Delivered]);
+
+            self._settle(self.escrow.amount(), Decimal::ZERO);
+        }
+
+        pub fn withdraw_buyer_funds(&mut self) -> Bucket {
+            self.buyer_funds.take_all()
+        }
+
+        /* SELLER METHODS */
+
+        pub fn mark_delivered(&mut self) {
+            self._assert_state(&[EscrowState::Funded]);
+
+            self._set_state(EscrowState::Delivered);
+        }
+
+        /// Give the escrowed funds back to the buyer
+        pub fn refund_buyer(&mut self) {
+            self._assert_state(&[EscrowState::Funded, EscrowState::Delivered]);
+
+            self._settle(Decimal::ZERO, Decimal::ZERO);
+        }
+
+        pub fn withdraw_seller_funds(&mut self) -> Bucket {
+            self.seller_funds.take_all()
+        }
+
+        /* SHARED METHODS */
+
+        /// Block the escrow until the arbiter resolves the dispute
+        pub fn dispute(&mut self) {
+            self._assert_state(&[EscrowState::Funded, EscrowState::Delivered]);
+
+            self._set_state(EscrowState::Disputed);
+        }
+
+        /* ARBITER METHODS */
+
+        /// Resolve a dispute. The arbiter fee is taken from the escrowed funds and the rest is
+        /// split between the parties, `seller_share` going to the seller.
+        pub fn resolve(&mut self, seller_share: Decimal) {
+            self._assert_state(&[EscrowState::Disputed]);
+            assert!(
+                seller_share >= Decimal::ZERO && seller_share <= Decimal::ONE,
+                "Seller share must be between 0 and 1"
+            );
+
+            let arbiter_fee = self.escrow.amount() * self.arbiter_fee_rate;
+            let seller_amount = (self.escrow.amount() - arbiter_fee) * seller_share;
+
+            self._settle(seller_amount, arbiter_fee);
+        }
+
+        pub fn withdraw_arbiter_fee(&mut self) -> Bucket {
+            self.arbiter_funds.take_all()
+        }
+
+        /* GETTERS */
+
+        pub fn get_state(&self) -> EscrowState {
+            self.state
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        /// Distribute the escrowed funds, the buyer receiving what is left after the seller and
+        /// arbiter amounts
+        fn _settle(&mut self, seller_amount: Decimal, arbiter_fee: Decimal) {
+            let arbiter_funds = self
+                .escrow
+                .take_advanced(arbiter_fee, WithdrawStrategy::Rounded(RoundingMode::ToZero));
+            let seller_funds = self.escrow.take_advanced(
+                seller_amount,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+            let buyer_funds = self.escrow.take_all();
+
+            Runtime::emit_event(EscrowSettledEvent {
+                seller_amount: seller_funds.amount(),
+                buyer_amount: buyer_funds.amount(),
+                arbiter_fee: arbiter_funds.amount(),
+            });
+
+            self.arbiter_funds.put(arbiter_funds);
+            self.seller_funds.put(seller_funds);
+            self.buyer_funds.put(buyer_funds);
+
+            self._set_state(EscrowState::Settled);
+        }
+
+        fn _assert_state(&self, expected_states: &[EscrowState]) {
+            assert!(
+                expected_states.contains(&self.state),
+                "Invalid escrow state: {:?}",
+                self.state
+            );
+        }
+
+        fn _set_state(&mut self, new_state: EscrowState) {
+            Runtime::emit_event(EscrowStateChangedEvent {
+                previous_state: self.state,
+                new_state,
+            });
+
+            self.state = new_state;
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use arbiter_escrow::EscrowState;
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Holds 1000 payment tokens
+const BUYER: usize = 0;
+const SELLER: usize = 1;
+/// Holds the arbiter badge
+const ARBITER: usize = 2;
+
+/// Escrow of 100 payment tokens with an arbiter fee of 10%
+struct TestEscrow {
+    env: TestEnv,
+    escrow: ComponentAddress,
+    arbiter_badge: ResourceAddress,
+    payment: ResourceAddress,
+}
+
+impl TestEscrow {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        let payment = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, env.accounts[BUYER]);
+        let arbiter_badge =
+            env.test_runner
+                .create_fungible_resource(dec!(1), 0, env.accounts[ARBITER]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "ArbiterEscrow",
+                "instantiate",
+                manifest_args!(
+                    payment,
+                    dec!(100),
+                    dec!("0.1"),
+                    rule!(require(env.badges[BUYER].clone())),
+                    rule!(require(env.badges[SELLER].clone())),
+                    arbiter_badge
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            escrow: commit.new_component_addresses()[0],
+            env,
+            arbiter_badge,
+            payment,
+        }
+    }
+
+    /// Escrow funded by the buyer
+    fn funded() -> Self {
+        let mut escrow = Self::new();
+
+        escrow.fund(BUYER, dec!(100)).expect_commit_success();
+
+        escrow
+    }
+
+    fn fund(&mut self, account: usize, amount: Decimal) -> TransactionReceipt {
+        let builder =
+            ManifestBuilder::new()
+                .withdraw_from_account(self.env.accounts[account], self.payment, amount)
+                .take_all_from_worktop(self.payment, "payment")
+                .call_method_with_name_lookup(self.escrow, "fund", |lookup| {
+                    (lookup.bucket("payment"),)
+                });
+
+        self.env.execute(account, builder)
+    }
+
+    /// Call a method with the key of the account, adding the arbiter badge for the arbiter
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        let mut builder = ManifestBuilder::new();
+        if account == ARBITER {
+            builder = builder.create_proof_from_account_of_amount(
+                self.env.accounts[ARBITER],
+                self.arbiter_badge,
+                dec!(1),
+            );
+        }
+        let builder = builder.call_method(self.escrow, method, args);
+
+        self.env.execute(account, builder)
+    }
+
+    fn get_state(&mut self) -> EscrowState {
+        self.call(BUYER, "get_state", manifest_args!())
+            .expect_commit_success()
+            .output(0)
+    }
+
+    fn balance(&mut self, account: usize) -> Decimal {
+        self.env.balance(account, self.payment)
+    }
+}
+
+#[test]
+fn test_fund_returns_the_change() {
+    let mut escrow = TestEscrow::new();
+
+    escrow.fund(BUYER, dec!(99)).expect_commit_failure();
+    escrow.fund(BUYER, dec!(150)).expect_commit_success();
+
+    assert_eq!(escrow.balance(BUYER), dec!(900));
+    assert_eq!(escrow.get_state(), EscrowState::Funded);
+
+    escrow.fund(BUYER, dec!(100)).expect_commit_failure();
+}
+
+#[test]
+fn test_fund_requires_the_buyer() {
+    let mut escrow = TestEscrow::new();
+
+    // Move the payment to the seller with the buyer key
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(escrow.env.accounts[BUYER], escrow.payment, dec!(100))
+        .take_all_from_worktop(escrow.payment, "payment")
+        .call_method_with_name_lookup(
+            escrow.env.accounts[SELLER],
+            "try_deposit_or_abort",
+            |lookup| (lookup.bucket("payment"), None::<ResourceOrNonFungible>),
+        );
+    escrow.env.execute(BUYER, builder).expect_commit_success();
+
+    escrow.fund(SELLER, dec!(100)).expect_commit_failure();
+}
+
+#[test]
+fn test_buyer_releases_the_funds_to_the_seller() {
+    let mut escrow = TestEscrow::new();
+
+    escrow
+        .call(BUYER, "release_to_seller", manifest_args!())
+        .expect_commit_failure();
+
+    escrow.fund(BUYER, dec!(100)).expect_commit_success();
+    escrow
+        .call(BUYER, "mark_delivered", manifest_args!())
+        .expect_commit_failure();
+    escrow
+        .call(SELLER, "mark_delivered", manifest_args!())
+        .expect_commit_success();
+    assert_eq!(escrow.get_state(), EscrowState::Delivered);
+
+    escrow
+        .call(SELLER, "release_to_seller", manifest_args!())
+        .expect_commit_failure();
+    escrow
+        .call(BUYER, "release_to_seller", manifest_args!())
+        .expect_commit_success();
+    assert_eq!(escrow.get_state(), EscrowState::Settled);
+
+    escrow
+        .call(SELLER, "withdraw_seller_funds", manifest_args!())
+        .expect_commit_success();
+    assert_eq!(escrow.balance(SELLER), dec!(100));
+}
+
+#[test]
+fn test_seller_refunds_the_buyer() {
+    let mut escrow = TestEscrow::funded();
+
+    escrow
+        .call(SELLER, "refund_buyer", manifest_args!())
+        .expect_commit_success();
+    escrow
+        .call(BUYER, "withdraw_buyer_funds", manifest_args!())
+        .expect_commit_success();
+
+    assert_eq!(escrow.balance(BUYER), dec!(1000));
+}
+
+#[test]
+fn test_arbiter_resolves_a_dispute() {
+    let mut escrow = TestEscrow::funded();
+
+    escrow
+        .call(ARBITER, "dispute", manifest_args!())
+        .expect_commit_failure();
+    escrow
+        .call(SELLER, "dispute", manifest_args!())
+        .expect_commit_success();
+
+    // The parties can not settle a disputed escrow
+    escrow
+        .call(BUYER, "release_to_seller", manifest_args!())
+        .expect_commit_failure();
+    escrow
+        .call(BUYER, "resolve", manifest_args!(dec!("0.5")))
+        .expect_commit_failure();
+    escrow
+        .call(ARBITER, "resolve", manifest_args!(dec!("1.1")))
+        .expect_commit_failure();
+
+    escrow
+        .call(ARBITER, "resolve", manifest_args!(dec!("0.5")))
+        .expect_commit_success();
+
+    escrow
+        .call(SELLER, "withdraw_seller_funds", manifest_args!())
+        .expect_commit_success();
+    escrow
+        .call(BUYER, "withdraw_buyer_funds", manifest_args!())
+        .expect_commit_success();
+    escrow
+        .call(ARBITER, "withdraw_arbiter_fee", manifest_args!())
+        .expect_commit_success();
+
+    assert_eq!(escrow.balance(SELLER), dec!(45));
+    assert_eq!(escrow.balance(BUYER), dec!(945));
+    assert_eq!(escrow.balance(ARBITER), dec!(10));
+}
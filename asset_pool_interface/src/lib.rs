@@ -0,0 +1,343 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+
+mod types;
+
+pub use types::*;
+
+/// Methods shared by single resource pools. Implemented by the global components of every
+/// `SingleResourcePool` blueprint, and by `AssetPoolStub` for callers that do not depend on the
+/// pool packages.
+pub trait SingleResourcePoolInterface {
+    fn contribute(&self, assets: Bucket) -> (Bucket, Bucket);
+
+    fn redeem(&self, pool_units: Bucket) -> (Bucket, Bucket);
+
+    fn get_pool_unit_ratio(&self) -> PreciseDecimal;
+
+    /// Liquidity held by the pool and liquidity used outside of it
+    fn get_pooled_amount(&self) -> (Decimal, Decimal);
+
+    fn get_units_for_amount(&self, amount: Decimal) -> Decimal;
+
+    fn get_amount_for_units(&self, units: Decimal) -> Decimal;
+
+    /// Minimum fee rate charged on flashloans
+    fn get_flashloan_fee_rate(&self) -> Decimal;
+
+    /// Maximum amount of a flashloan, `None` when loans are minted on demand
+    fn get_flashloan_capacity(&self) -> Option<Decimal>;
+
+    /// Returns the loan and the loan term, which must be handed back to `repay_flashloan` in
+    /// the same transaction
+    fn take_flashloan(&self, loan_amount: Decimal, fee_amount: Decimal) -> (Bucket, Bucket);
+
+    fn repay_flashloan(&self, loan_repayment: Bucket, loan_terms: Bucket) -> Bucket;
+}
+
+/// Blueprints exposing the `SingleResourcePoolInterface` methods: AssetPool, including its
+/// flash-mint variant, and NativePoolAdapter, so that `Global` components of them can be used
+/// wherever a `SingleResourcePoolInterface` is expected. Implemented with
+/// `impl_single_resource_pool!`, which checks the blueprint methods against the interface.
+pub trait SingleResourcePool: HasStub {}
+
+/// Calls go through `AssetPoolStub`, whose signatures the blueprint was checked against by
+/// `impl_single_resource_pool!`
+impl<T: SingleResourcePool> SingleResourcePoolInterface for Global<T> {
+    fn contribute(&self, assets: Bucket) -> (Bucket, Bucket) {
+        AssetPoolStub::from(self.address()).contribute(assets)
+    }
+
+    fn redeem(&self, pool_units: Bucket) -> (Bucket, Bucket) {
+        AssetPoolStub::from(self.address()).redeem(pool_units)
+    }
+
+    fn get_pool_unit_ratio(&self) -> PreciseDecimal {
+        AssetPoolStub::from(self.address()).get_pool_unit_ratio()
+    }
+
+    fn get_pooled_amount(&self) -> (Decimal, Decimal) {
+        AssetPoolStub::from(self.address()).get_pooled_amount()
+    }
+
+    fn get_units_for_amount(&self, amount: Decimal) -> Decimal {
+        AssetPoolStub::from(self.address()).get_units_for_amount(amount)
+    }
+
+    fn get_amount_for_units(&self, units: Decimal) -> Decimal {
+        AssetPoolStub::from(self.address()).get_amount_for_units(units)
+    }
+
+    fn get_flashloan_fee_rate(&self) -> Decimal {
+        AssetPoolStub::from(self.address()).get_flashloan_fee_rate()
+    }
+
+    fn get_flashloan_capacity(&self) -> Option<Decimal> {
+        AssetPoolStub::from(self.address()).get_flashloan_capacity()
+    }
+
+    fn take_flashloan(&self, loan_amount: Decimal, fee_amount: Decimal) -> (Bucket, Bucket) {
+        AssetPoolStub::from(self.address()).take_flashloan(loan_amount, fee_amount)
+    }
+
+    fn repay_flashloan(&self, loan_repayment: Bucket, loan_terms: Bucket) -> Bucket {
+        AssetPoolStub::from(self.address()).repay_flashloan(loan_repayment, loan_terms)
+    }
+}
+
+/// Signatures of the `SingleResourcePoolInterface` methods, passed to `$callback` as
+/// `fn name(args) -> output;` items. `AssetPoolStub` implements the interface from them and
+/// `impl_single_resource_pool!` checks the blueprints against them.
+#[macro_export]
+macro_rules! single_resource_pool_methods {
+    ($callback:path $(, $($args:tt)*)?) => {
+        $callback! {
+            [$($($args)*)?]
+
+            fn contribute(assets: Bucket) -> (Bucket, Bucket);
+
+            fn redeem(pool_units: Bucket) -> (Bucket, Bucket);
+
+            fn get_pool_unit_ratio() -> PreciseDecimal;
+
+            fn get_pooled_amount() -> (Decimal, Decimal);
+
+            fn get_units_for_amount(amount: Decimal) -> Decimal;
+
+            fn get_amount_for_units(units: Decimal) -> Decimal;
+
+            fn get_flashloan_fee_rate() -> Decimal;
+
+            fn get_flashloan_capacity() -> Option<Decimal>;
+
+            fn take_flashloan(loan_amount: Decimal, fee_amount: Decimal) -> (Bucket, Bucket);
+
+            fn repay_flashloan(loan_repayment: Bucket, loan_terms: Bucket) -> Bucket;
+        }
+    };
+}
+
+/// Signatures of the other AssetPool methods, in the same form as
+/// `single_resource_pool_methods!`. `AssetPoolStub` exposes them and
+/// `assert_asset_pool_signatures!` checks the AssetPool blueprint against them.
+#[macro_export]
+macro_rules! asset_pool_methods {
+    ($callback:path $(, $($args:tt)*)?) => {
+        $callback! {
+            [$($($args)*)?]
+
+            /* ADMIN METHODS */
+
+            fn redeem_advanced(
+                pool_units: Bucket,
+                withdraw_strategy: WithdrawStrategy,
+            ) -> (Bucket, Bucket);
+
+            fn grant_allowance(
+                holder: NonFungibleGlobalId,
+                spender: NonFungibleGlobalId,
+                pool_units: Bucket,
+            ) -> u64;
+
+            fn redeem_with_allowance(allowance_id: u64, units: Decimal) -> Bucket;
+
+            fn revoke_allowance(allowance_id: u64) -> Bucket;
+
+            fn contribute_soulbound(assets: Bucket, holder: NonFungibleGlobalId) -> Bucket;
+
+            fn redeem_soulbound(holder: NonFungibleGlobalId, units: Decimal) -> Bucket;
+
+            fn protected_withdraw(
+                amount: Decimal,
+                withdraw_type: $crate::WithdrawType,
+                withdraw_strategy: WithdrawStrategy,
+            ) -> Bucket;
+
+            fn protected_deposit(assets: Bucket, deposit_type: $crate::DepositType);
+
+            fn deposit_other(assets: Bucket);
+
+            fn withdraw_other(res_address: ResourceAddress, amount: Decimal) -> Bucket;
+
+            fn increase_external_liquidity(amount: Decimal);
+
+            fn decrease_external_liquidity(amount: Decimal);
+
+            fn flashloan_and_call(
+                borrower: ComponentAddress,
+                loan_amount: Decimal,
+                fee_amount: Decimal,
+                callback_args: ScryptoValue,
+            ) -> Bucket;
+
+            /* OWNER METHODS */
+
+            fn queue_config_change(change: $crate::ConfigChange) -> u64;
+
+            fn apply_config_change(change_id: u64);
+
+            fn cancel_config_change(change_id: u64);
+
+            fn pause();
+
+            fn resume();
+
+            /* PUBLIC METHODS */
+
+            fn accrue_interest();
+
+            /* GETTERS */
+
+            fn get_current_pool_unit_ratio() -> PreciseDecimal;
+
+            fn get_pool_unit_supply() -> Decimal;
+
+            fn get_other_amount(res_address: ResourceAddress) -> Decimal;
+
+            fn is_paused() -> bool;
+
+            fn get_ratio_twap(window_epochs: u64) -> Option<PreciseDecimal>;
+
+            fn get_pending_config_changes() -> IndexMap<u64, $crate::PendingConfigChange>;
+
+            /// Holder, spender and pool units remaining in an allowance
+            fn get_allowance(
+                allowance_id: u64,
+            ) -> (NonFungibleGlobalId, NonFungibleGlobalId, Decimal);
+
+            /// Pool units in the position of a holder badge
+            fn get_soulbound_units(holder: NonFungibleGlobalId) -> Decimal;
+        }
+    };
+}
+
+/// Implements `SingleResourcePool` for a blueprint, failing to compile when one of its methods
+/// does not match the `SingleResourcePoolInterface` signature called by `AssetPoolStub`
+#[macro_export]
+macro_rules! impl_single_resource_pool {
+    ($blueprint:ty) => {
+        $crate::single_resource_pool_methods!($crate::__assert_signatures, $blueprint);
+
+        impl $crate::SingleResourcePool for $blueprint {}
+    };
+}
+
+/// Fails to compile when one of the AssetPool blueprint methods does not match the signature
+/// called by `AssetPoolStub`
+#[macro_export]
+macro_rules! assert_asset_pool_signatures {
+    ($blueprint:ty) => {
+        $crate::asset_pool_methods!($crate::__assert_signatures, $blueprint);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __assert_signatures {
+    (
+        [$blueprint:ty]
+        $($(#[$attr:meta])* fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $output:ty)?;)*
+    ) => {
+        const _: () = {
+            use ::scrypto::prelude::*;
+
+            // Never called: the closures only type check the method calls. Methods without
+            // output must return `()`.
+            #[allow(dead_code)]
+            fn assert_signatures() {
+                $(
+                    let _ = |state: &mut $blueprint, $($arg: $ty),*| -> ($($output)?) {
+                        state.$name($($arg),*)
+                    };
+                )*
+            }
+        };
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __stub_methods {
+    (
+        []
+        $($(#[$attr:meta])* fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $output:ty)?;)*
+    ) => {
+        $(
+            $(#[$attr])*
+            pub fn $name(&self, $($arg: $ty),*) $(-> $output)? {
+                self.call(stringify!($name), scrypto_args!($($arg),*))
+            }
+        )*
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __stub_trait_methods {
+    (
+        []
+        $($(#[$attr:meta])* fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $output:ty)?;)*
+    ) => {
+        $(
+            $(#[$attr])*
+            fn $name(&self, $($arg: $ty),*) $(-> $output)? {
+                self.call(stringify!($name), scrypto_args!($($arg),*))
+            }
+        )*
+    };
+}
+
+/// Stub of a global AssetPool component, for integrating blueprints that should not depend on
+/// the pool package, which would also compile the AssetPool blueprint into their own package.
+/// Methods are called by name with `call_raw`: their signatures are generated from
+/// `single_resource_pool_methods!` and `asset_pool_methods!`, and the AssetPool blueprint is
+/// checked against the same lists at compile time. Method calls still go through the pool
+/// method auth: admin methods require the admin role to be satisfied by the caller.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AssetPoolStub(pub ComponentAddress);
+
+impl From<ComponentAddress> for AssetPoolStub {
+    fn from(address: ComponentAddress) -> Self {
+        Self(address)
+    }
+}
+
+impl AssetPoolStub {
+    pub fn address(&self) -> ComponentAddress {
+        self.0
+    }
+
+    asset_pool_methods!(__stub_methods);
+
+    /* PRIVATE UTILITY METHODS */
+
+    fn call<T: ScryptoDecode>(&self, method: &str, args: Vec<u8>) -> T {
+        Global::<AnyComponent>::from(self.0).call_raw(method, args)
+    }
+}
+
+impl SingleResourcePoolInterface for AssetPoolStub {
+    single_resource_pool_methods!(__stub_trait_methods);
+}
@@ -0,0 +1,51 @@
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct FlashloanTerm {
+    pub loan_amount: Decimal,
+    pub fee_amount: Decimal,
+}
+
+#[derive(ScryptoSbor, PartialEq)]
+pub enum WithdrawType {
+    ForTemporaryUse,
+    LiquidityWithdrawal,
+}
+
+#[derive(ScryptoSbor, PartialEq)]
+pub enum DepositType {
+    FromTemporaryUse,
+    LiquidityAddition,
+}
+
+/// Method called on the borrower component by `flashloan_and_call`. It receives the loan, the
+/// fee amount and the callback arguments, and must return the repayment:
+/// `on_flashloan(loan: Bucket, fee_amount: Decimal, callback_args: ScryptoValue) -> Bucket`
+pub const FLASHLOAN_CALLBACK_METHOD: &str = "on_flashloan";
+
+/// Pool parameter update. Changes are queued and only applied once the pool's config
+/// timelock has elapsed, so LPs get notice before the pool economics change.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq)]
+pub enum ConfigChange {
+    MaxExternalLiquidityRatio(Option<Decimal>),
+    CircuitBreakerThreshold(Option<Decimal>),
+    ConfigTimelockEpochs(u64),
+    ProtectedWithdrawEpochLimit(Option<Decimal>),
+    MaxPoolUnitSupply(Option<Decimal>),
+    ContributionBadge(Option<ResourceAddress>),
+    /// Minimum flashloan fee, as a share of the loan amount
+    FlashloanFeeRate(Decimal),
+    /// Badge letting its holders use the pool while it is paused, e.g. a liquidation component
+    /// that must keep repaying its loans
+    PauseExemptBadge(Option<ResourceAddress>),
+    /// Interest rate per epoch accrued on the liquidity used outside the pool
+    ExternalLiquidityInterestRate(Decimal),
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct PendingConfigChange {
+    pub change: ConfigChange,
+
+    /// Epoch from which the change can be applied
+    pub executable_at_epoch: u64,
+}
@@ -0,0 +1,49 @@
+use asset_pool_interface::*;
+use scrypto::prelude::*;
+
+/// Discriminator of the SBOR encoding of an enum variant
+fn discriminator<T: ScryptoEncode>(value: &T) -> u8 {
+    let bytes = scrypto_encode(value).unwrap();
+
+    match scrypto_decode::<ScryptoValue>(&bytes).unwrap() {
+        ScryptoValue::Enum { discriminator, .. } => discriminator,
+        _ => panic!("Not an enum"),
+    }
+}
+
+#[test]
+fn test_stub_wraps_the_component_address() {
+    let address = ComponentAddress::new_or_panic([192; NodeId::LENGTH]);
+    let stub = AssetPoolStub::from(address);
+
+    assert_eq!(stub.address(), address);
+    assert_eq!(stub, AssetPoolStub(address));
+}
+
+#[test]
+fn test_variants_keep_the_discriminators_of_the_pool() {
+    // Manifests build these enums by discriminator, so variants must not be reordered
+    assert_eq!(discriminator(&WithdrawType::ForTemporaryUse), 0);
+    assert_eq!(discriminator(&WithdrawType::LiquidityWithdrawal), 1);
+    assert_eq!(discriminator(&DepositType::FromTemporaryUse), 0);
+    assert_eq!(discriminator(&DepositType::LiquidityAddition), 1);
+    assert_eq!(
+        discriminator(&ConfigChange::MaxExternalLiquidityRatio(None)),
+        0
+    );
+    assert_eq!(discriminator(&ConfigChange::ContributionBadge(None)), 5);
+    assert_eq!(discriminator(&ConfigChange::FlashloanFeeRate(dec!(0))), 6);
+    assert_eq!(discriminator(&ConfigChange::PauseExemptBadge(None)), 7);
+    assert_eq!(
+        discriminator(&ConfigChange::ExternalLiquidityInterestRate(dec!(0))),
+        8
+    );
+}
+
+#[test]
+fn test_config_changes_round_trip() {
+    let change = ConfigChange::ProtectedWithdrawEpochLimit(Some(dec!(100)));
+    let bytes = scrypto_encode(&change).unwrap();
+
+    assert_eq!(scrypto_decode::<ConfigChange>(&bytes).unwrap(), change);
+}
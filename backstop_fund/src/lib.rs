@@ -0,0 +1,418 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct BackstopReceipt {
+    pub created_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub struct BackstopPosition {
+    /// Shares of the backstop vault. Their value decreases when the fund is slashed.
+    pub shares: Decimal,
+
+    /// Value of the fee per share accumulator when the fees were last computed
+    pub fee_per_share_paid: PreciseDecimal,
+
+    /// Fees computed but not claimed yet
+    pub pending_fees: Decimal,
+
+    /// Shares requested for withdrawal and epoch from which they can be withdrawn
+    pub withdrawal_request: Option<(Decimal, u64)>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SlashEvent {
+    pub amount: Decimal,
+    pub incident: String,
+    pub remaining_backstop: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct FeesDepositedEvent {
+    pub amount: Decimal,
+}
+
+#[blueprint]
+#[events(SlashEvent, FeesDepositedEvent)]
+pub mod backstop_fund {
+
+    enable_method_auth! {
+        roles {
+            protocol => updatable_by: [OWNER];
+        },
+        methods {
+
+            set_max_slash_ratio => restrict_to :[OWNER];
+            set_cooldown => restrict_to :[OWNER];
+
+            slash => restrict_to :[protocol];
+            deposit_fees => PUBLIC;
+
+            stake => PUBLIC;
+            add_stake => PUBLIC;
+            request_withdrawal => PUBLIC;
+            withdraw => PUBLIC;
+            claim_fees => PUBLIC;
+
+            get_position => PUBLIC;
+            get_position_value => PUBLIC;
+            get_pending_fees => PUBLIC;
+            get_total_backstop => PUBLIC;
+
+        }
+    }
+
+    pub struct BackstopFund {
+        backstop: Vault,
+        total_shares: Decimal,
+
+        /// Protocol fees distributed to the stakers and not claimed yet
+        fees: Vault,
+
+        /// Fees distributed per share since instantiation
+        fee_per_share: PreciseDecimal,
+
+        /// Maximum share of the backstop the protocol can slash per incident
+        max_slash_ratio: Decimal,
+
+        /// Number of epochs between a withdrawal request and the withdrawal. Shares remain
+        /// slashable in between, so stakers cannot exit ahead of a known incident.
+        cooldown_epochs: u64,
+
+        /// Backstop receipt non-fungible resource manager
+        receipt_res_manager: ResourceManager,
+        positions: KeyValueStore<NonFungibleLocalId, BackstopPosition>,
+    }
+
+    impl BackstopFund {
+        pub fn instantiate(
+            backstop_res_address: ResourceAddress,
+            fee_res_address: ResourceAddress,
+            max_slash_ratio: Decimal,
+            cooldown_epochs: u64,
+            owner_role: OwnerRole,
+            protocol_rule: AccessRule,
+        ) -> (Global<BackstopFund>, ResourceAddress) {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                backstop_res_address,
+                Some("Backstop resource must be fungible".to_string()),
+            );
+            assert_fungible_res_address(
+                fee_res_address,
+                Some("Fee resource must be fungible".to_string()),
+            );
+            BackstopFund::_assert_valid_slash_ratio(max_slash_ratio);
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(BackstopFund::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let receipt_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<BackstopReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule;
+                        minter_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let backstop_component = Self {
+                backstop: Vault::new(backstop_res_address),
+                total_shares: Decimal::ZERO,
+                fees: Vault::new(fee_res_address),
+                fee_per_share: PreciseDecimal::ZERO,
+                max_slash_ratio,
+                cooldown_epochs,
+                receipt_res_manager,
+                positions: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                protocol => protocol_rule;
+            ))
+            .with_address(address_reservation)
+            .globalize();
+
+            (backstop_component, receipt_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn set_max_slash_ratio(&mut self, max_slash_ratio: Decimal) {
+            BackstopFund::_assert_valid_slash_ratio(max_slash_ratio);
+
+            self.max_slash_ratio = max_slash_ratio;
+        }
+
+        /// Change the cooldown. Pending withdrawal requests keep their unlock epoch.
+        pub fn set_cooldown(&mut self, cooldown_epochs: u64) {
+            self.cooldown_epochs = cooldown_epochs;
+        }
+
+        /* PROTOCOL METHODS */
+
+        /// Take funds from the backstop to cover bad debt, up to the maximum slash ratio of
+        /// the backstop
+        pub fn slash(&mut self, amount: Decimal, incident: String) -> Bucket {
+            /* CHECK INPUT */
+            assert!(amount > Decimal::ZERO, "Amount must be greater than zero!");
+            assert!(
+                amount <= self.backstop.amount() * self.max_slash_ratio,
+                "Amount exceeds the slash cap"
+            );
+
+            let slashed = self.backstop.take(amount);
+
+            Runtime::emit_event(SlashEvent {
+                amount,
+                incident,
+                remaining_backstop: self.backstop.amount(),
+            });
+
+            slashed
+        }
+
+        /// Distribute protocol fees to the stakers, pro-rata to their shares. Anyone can
+        /// deposit fees.
+        pub fn deposit_fees(&mut self, fees: Bucket) {
+            /* CHECK INPUT */
+            assert!(
+                fees.resource_address() == self.fees.resource_address(),
+                "Fee resource address mismatch"
+            );
+            assert!(
+                self.total_shares > Decimal::ZERO,
+                "No staker to distribute the fees to"
+            );
+
+            Runtime::emit_event(FeesDepositedEvent {
+                amount: fees.amount(),
+            });
+
+            self.fee_per_share +=
+                PreciseDecimal::from(fees.amount()) / PreciseDecimal::from(self.total_shares);
+            self.fees.put(fees);
+        }
+
+        /* STAKER METHODS */
+
+        /// Stake tokens in a new position. Returns the backstop receipt.
+        pub fn stake(&mut self, tokens: Bucket) -> Bucket {
+            let receipt = self
+                .receipt_res_manager
+                .mint_ruid_non_fungible(BackstopReceipt {
+                    created_at_epoch: Runtime::current_epoch().number(),
+                });
+
+            self.positions.insert(
+                receipt.as_non_fungible().non_fungible_local_id(),
+                BackstopPosition {
+                    shares: Decimal::ZERO,
+                    fee_per_share_paid: self.fee_per_share,
+                    pending_fees: Decimal::ZERO,
+                    withdrawal_request: None,
+                },
+            );
+
+            self._add_stake(receipt.as_non_fungible().non_fungible_local_id(), tokens);
+
+            receipt
+        }
+
+        pub fn add_stake(&mut self, receipt_proof: Proof, tokens: Bucket) {
+            let position_id = self._check_receipt_proof(receipt_proof);
+
+            self._add_stake(position_id, tokens);
+        }
+
+        /// Request the withdrawal of shares, replacing any previous request. The shares can
+        /// be withdrawn once the cooldown has elapsed.
+        pub fn request_withdrawal(&mut self, receipt_proof: Proof, shares: Decimal) {
+            let position_id = self._check_receipt_proof(receipt_proof);
+
+            /* CHECK INPUT */
+            assert!(shares > Decimal::ZERO, "Shares must be greater than zero!");
+
+            let mut position = self.positions.get_mut(&position_id).unwrap();
+            assert!(shares <= position.shares, "Not enough shares");
+
+            position.withdrawal_request = Some((
+                shares,
+                Runtime::current_epoch().number() + self.cooldown_epochs,
+            ));
+        }
+
+        /// Withdraw the shares of the pending withdrawal request, at their current value
+        pub fn withdraw(&mut self, receipt_proof: Proof) -> Bucket {
+            let position_id = self._check_receipt_proof(receipt_proof);
+
+            self._update_position_fees(&position_id);
+
+            let shares = {
+                let mut position = self.positions.get_mut(&position_id).unwrap();
+
+                let (shares, unlock_epoch) = position
+                    .withdrawal_request
+                    .take()
+                    .expect("No withdrawal request");
+
+                assert!(
+                    Runtime::current_epoch().number() >= unlock_epoch,
+                    "Cooldown has not elapsed"
+                );
+
+                // Shares may have been reduced since the request
+                let shares = shares.min(position.shares);
+                position.shares -= shares;
+                shares
+            };
+
+            let amount = self._shares_to_amount(shares);
+            self.total_shares -= shares;
+
+            self.backstop
+                .take_advanced(amount, WithdrawStrategy::Rounded(RoundingMode::ToZero))
+        }
+
+        pub fn claim_fees(&mut self, receipt_proof: Proof) -> Bucket {
+            let position_id = self._check_receipt_proof(receipt_proof);
+
+            self._update_position_fees(&position_id);
+
+            let amount = {
+                let mut position = self.positions.get_mut(&position_id).unwrap();
+                let amount = position.pending_fees;
+                position.pending_fees = Decimal::ZERO;
+                amount
+            };
+
+            self.fees.take(amount)
+        }
+
+        /* GETTERS */
+
+        pub fn get_position(&self, position_id: NonFungibleLocalId) -> BackstopPosition {
+            self.positions
+                .get(&position_id)
+                .expect("Position not found")
+                .clone()
+        }
+
+        /// Current value of the shares of a position, in backstop resource
+        pub fn get_position_value(&self, position_id: NonFungibleLocalId) -> Decimal {
+            self._shares_to_amount(self.get_position(position_id).shares)
+        }
+
+        pub fn get_pending_fees(&self, position_id: NonFungibleLocalId) -> Decimal {
+            let position = self.get_position(position_id);
+
+            position.pending_fees
+                + BackstopFund::_compute_fees(
+                    position.shares,
+                    self.fee_per_share - position.fee_per_share_paid,
+                )
+        }
+
+        pub fn get_total_backstop(&self) -> Decimal {
+            self.backstop.amount()
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _add_stake(&mut self, position_id: NonFungibleLocalId, tokens: Bucket) {
+            /* CHECK INPUT */
+            assert!(
+                tokens.resource_address() == self.backstop.resource_address(),
+                "Backstop resource address mismatch"
+            );
+            assert!(
+                tokens.amount() > Decimal::ZERO,
+                "Amount must be greater than zero!"
+            );
+
+            self._update_position_fees(&position_id);
+
+            let shares = if self.total_shares == Decimal::ZERO {
+                tokens.amount()
+            } else {
+                (PreciseDecimal::from(tokens.amount()) * self.total_shares / self.backstop.amount())
+                    .checked_truncate(RoundingMode::ToZero)
+                    .unwrap()
+            };
+
+            self.positions.get_mut(&position_id).unwrap().shares += shares;
+            self.total_shares += shares;
+            self.backstop.put(tokens);
+        }
+
+        fn _shares_to_amount(&self, shares: Decimal) -> Decimal {
+            if self.total_shares == Decimal::ZERO {
+                return Decimal::ZERO;
+            }
+
+            (PreciseDecimal::from(shares) * self.backstop.amount() / self.total_shares)
+                .checked_truncate(RoundingMode::ToZero)
+                .unwrap()
+        }
+
+        /// Move the fees earned since the last update of a position to its pending fees
+        fn _update_position_fees(&mut self, position_id: &NonFungibleLocalId) {
+            let fee_per_share = self.fee_per_share;
+            let mut position = self.positions.get_mut(position_id).unwrap();
+
+            position.pending_fees += BackstopFund::_compute_fees(
+                position.shares,
+                fee_per_share - position.fee_per_share_paid,
+            );
+            position.fee_per_share_paid = fee_per_share;
+        }
+
+        fn _compute_fees(shares: Decimal, fee_per_share_delta: PreciseDecimal) -> Decimal {
+            (PreciseDecimal::from(shares) * fee_per_share_delta)
+                .checked_truncate(RoundingMode::ToZero)
+                .unwrap()
+        }
+
+        fn _assert_valid_slash_ratio(max_slash_ratio: Decimal) {
+            // A fully slashed backstop would leave shares without value, breaking the share
+            // math for new stakers
+            assert!(
+                max_slash_ratio > Decimal::ZERO && max_slash_ratio < Decimal::ONE,
+                "Max slash ratio must be between 0 and 1"
+            );
+        }
+
+        fn _check_receipt_proof(&self, receipt_proof: Proof) -> NonFungibleLocalId {
+            receipt_proof
+                .check(self.receipt_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use backstop_fund::BackstopPosition;
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+const OWNER: usize = 0;
+/// Holds 1000 fee tokens
+const PROTOCOL: usize = 1;
+/// Stakers holding 1000 backstop tokens each
+const ALICE: usize = 2;
+const BOB: usize = 3;
+
+/// Backstop fund with a max slash ratio of 50% and a cooldown of 5 epochs, at epoch 10
+struct TestBackstop {
+    env: TestEnv,
+    backstop: ComponentAddress,
+    receipt: ResourceAddress,
+    token: ResourceAddress,
+    fee: ResourceAddress,
+}
+
+impl TestBackstop {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 4);
+        env.set_epoch(10);
+        let token = env
+            .test_runner
+            .create_fungible_resource(dec!(2000), 18, env.accounts[ALICE]);
+        let fee = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, env.accounts[PROTOCOL]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "BackstopFund",
+                "instantiate",
+                manifest_args!(
+                    token,
+                    fee,
+                    dec!("0.5"),
+                    5u64,
+                    OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone()))),
+                    rule!(require(env.badges[PROTOCOL].clone()))
+                ),
+            )
+            .withdraw_from_account(env.accounts[ALICE], token, dec!(1000))
+            .take_all_from_worktop(token, "bob")
+            .call_method_with_name_lookup(env.accounts[BOB], "deposit", |lookup| {
+                (lookup.bucket("bob"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            backstop: commit.new_component_addresses()[0],
+            receipt: commit.new_resource_addresses()[0],
+            env,
+            token,
+            fee,
+        }
+    }
+
+    fn stake(&mut self, staker: usize, amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[staker], self.token, amount)
+            .take_all_from_worktop(self.token, "tokens")
+            .call_method_with_name_lookup(self.backstop, "stake", |lookup| {
+                (lookup.bucket("tokens"),)
+            });
+
+        self.env.execute(staker, builder)
+    }
+
+    /// Proof of the backstop receipt of the staker, popped as "receipt"
+    fn with_receipt_proof(&self, staker: usize) -> ManifestBuilder {
+        ManifestBuilder::new()
+            .create_proof_from_account_of_amount(self.env.accounts[staker], self.receipt, dec!(1))
+            .pop_from_auth_zone("receipt")
+    }
+
+    fn add_stake(&mut self, staker: usize, amount: Decimal) -> TransactionReceipt {
+        let builder = self
+            .with_receipt_proof(staker)
+            .withdraw_from_account(self.env.accounts[staker], self.token, amount)
+            .take_all_from_worktop(self.token, "tokens")
+            .call_method_with_name_lookup(self.backstop, "add_stake", |lookup| {
+                (lookup.proof("receipt"), lookup.bucket("tokens"))
+            });
+
+        self.env.execute(staker, builder)
+    }
+
+    fn request_withdrawal(&mut self, staker: usize, shares: Decimal) -> TransactionReceipt {
+        let builder = self
+            .with_receipt_proof(staker)
+            .call_method_with_name_lookup(self.backstop, "request_withdrawal", |lookup| {
+                (lookup.proof("receipt"), shares)
+            });
+
+        self.env.execute(staker, builder)
+    }
+
+    /// Call `withdraw` or `claim_fees`
+    fn call_with_receipt(&mut self, staker: usize, method: &str) -> TransactionReceipt {
+        let builder = self
+            .with_receipt_proof(staker)
+            .call_method_with_name_lookup(self.backstop, method, |lookup| {
+                (lookup.proof("receipt"),)
+            });
+
+        self.env.execute(staker, builder)
+    }
+
+    fn deposit_fees(&mut self, amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[PROTOCOL], self.fee, amount)
+            .take_all_from_worktop(self.fee, "fees")
+            .call_method_with_name_lookup(self.backstop, "deposit_fees", |lookup| {
+                (lookup.bucket("fees"),)
+            });
+
+        self.env.execute(PROTOCOL, builder)
+    }
+
+    fn slash(&mut self, caller: usize, amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_method(
+            self.backstop,
+            "slash",
+            manifest_args!(amount, "Bad debt".to_string()),
+        );
+
+        self.env.execute(caller, builder)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str, staker: usize) -> T {
+        let position_id = self.position_id(staker);
+        self.env
+            .call(staker, self.backstop, method, manifest_args!(position_id))
+            .expect_commit_success()
+            .output(0)
+    }
+
+    /// Id of the backstop receipt of the staker
+    fn position_id(&mut self, staker: usize) -> NonFungibleLocalId {
+        let vault_id = self
+            .env
+            .test_runner
+            .get_component_vaults(self.env.accounts[staker], self.receipt)[0];
+        let (_, mut ids) = self
+            .env
+            .test_runner
+            .inspect_non_fungible_vault(vault_id)
+            .unwrap();
+
+        ids.next().unwrap()
+    }
+}
+
+#[test]
+fn test_fees_are_distributed_pro_rata() {
+    let mut backstop = TestBackstop::new();
+    let fee = backstop.fee;
+
+    backstop.deposit_fees(dec!(10)).expect_commit_failure();
+
+    backstop.stake(ALICE, dec!(300)).expect_commit_success();
+    backstop.stake(BOB, dec!(100)).expect_commit_success();
+    backstop.deposit_fees(dec!(40)).expect_commit_success();
+
+    assert_eq!(backstop.get::<Decimal>("get_pending_fees", ALICE), dec!(30));
+
+    // Fees deposited before a stake are not earned by it
+    backstop.add_stake(BOB, dec!(200)).expect_commit_success();
+    backstop.deposit_fees(dec!(60)).expect_commit_success();
+
+    backstop
+        .call_with_receipt(ALICE, "claim_fees")
+        .expect_commit_success();
+    backstop
+        .call_with_receipt(BOB, "claim_fees")
+        .expect_commit_success();
+
+    assert_eq!(backstop.env.balance(ALICE, fee), dec!(60));
+    assert_eq!(backstop.env.balance(BOB, fee), dec!(40));
+}
+
+#[test]
+fn test_slash_reduces_the_value_of_the_shares() {
+    let mut backstop = TestBackstop::new();
+    let token = backstop.token;
+
+    backstop.stake(ALICE, dec!(400)).expect_commit_success();
+
+    backstop.slash(OWNER, dec!(100)).expect_commit_failure();
+    backstop.slash(PROTOCOL, dec!(201)).expect_commit_failure();
+    backstop.slash(PROTOCOL, dec!(200)).expect_commit_success();
+    assert_eq!(backstop.env.balance(PROTOCOL, token), dec!(200));
+
+    // The cap applies to the remaining backstop
+    backstop.slash(PROTOCOL, dec!(101)).expect_commit_failure();
+
+    assert_eq!(
+        backstop.get::<Decimal>("get_position_value", ALICE),
+        dec!(200)
+    );
+
+    // New stakers get shares at the slashed value
+    backstop.stake(BOB, dec!(100)).expect_commit_success();
+    let position: BackstopPosition = backstop.get("get_position", BOB);
+    assert_eq!(position.shares, dec!(200));
+    assert_eq!(
+        backstop.get::<Decimal>("get_position_value", BOB),
+        dec!(100)
+    );
+}
+
+#[test]
+fn test_withdraw_after_the_cooldown() {
+    let mut backstop = TestBackstop::new();
+    let token = backstop.token;
+
+    backstop.stake(ALICE, dec!(300)).expect_commit_success();
+
+    backstop
+        .call_with_receipt(ALICE, "withdraw")
+        .expect_commit_failure();
+    backstop
+        .request_withdrawal(ALICE, dec!(301))
+        .expect_commit_failure();
+    backstop
+        .request_withdrawal(ALICE, dec!(200))
+        .expect_commit_success();
+
+    backstop.env.set_epoch(14);
+    backstop
+        .call_with_receipt(ALICE, "withdraw")
+        .expect_commit_failure();
+
+    // Shares stay slashable during the cooldown
+    backstop.slash(PROTOCOL, dec!(150)).expect_commit_success();
+
+    backstop.env.set_epoch(15);
+    backstop
+        .call_with_receipt(ALICE, "withdraw")
+        .expect_commit_success();
+    assert_eq!(backstop.env.balance(ALICE, token), dec!(800));
+
+    backstop
+        .call_with_receipt(ALICE, "withdraw")
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_set_max_slash_ratio_requires_the_owner() {
+    let mut backstop = TestBackstop::new();
+    let backstop_address = backstop.backstop;
+
+    for (caller, ratio) in [(PROTOCOL, dec!("0.2")), (OWNER, dec!(1)), (OWNER, dec!(0))] {
+        let builder = ManifestBuilder::new().call_method(
+            backstop_address,
+            "set_max_slash_ratio",
+            manifest_args!(ratio),
+        );
+        backstop
+            .env
+            .execute(caller, builder)
+            .expect_commit_failure();
+    }
+
+    let builder = ManifestBuilder::new().call_method(
+        backstop_address,
+        "set_max_slash_ratio",
+        manifest_args!(dec!("0.2")),
+    );
+    backstop.env.execute(OWNER, builder).expect_commit_success();
+}
@@ -0,0 +1,236 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+
+/// Maximum number of badges returned by a single enumeration call
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+/// Badge used to wire authorization between components, e.g. the admin rule of a pool
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct ProtocolBadge {
+    pub label: String,
+    pub issued_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BadgeStatus {
+    Active,
+    /// Revoked by the owner, the badge should be recalled and burned
+    Revoked {
+        revoked_at_epoch: u64,
+    },
+    /// Recalled and burned
+    Burned,
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct BadgeRecord {
+    pub label: String,
+    pub issued_at_epoch: u64,
+    pub status: BadgeStatus,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BadgeIssuedEvent {
+    pub badge_id: u64,
+    pub label: String,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BadgeRevokedEvent {
+    pub badge_id: u64,
+}
+
+#[blueprint]
+#[events(BadgeIssuedEvent, BadgeRevokedEvent)]
+pub mod badge_manager {
+
+    enable_method_auth! {
+        methods {
+
+            issue => restrict_to :[OWNER];
+            revoke => restrict_to :[OWNER];
+            burn_revoked => restrict_to :[OWNER];
+
+            get_badge_res_address => PUBLIC;
+            get_badge_rule => PUBLIC;
+            get_badge => PUBLIC;
+            get_badges => PUBLIC;
+            get_badge_count => PUBLIC;
+
+        }
+    }
+
+    pub struct BadgeManager {
+        /// Badge non-fungible resource manager. Badges can be recalled by the owner.
+        badge_res_manager: ResourceManager,
+
+        badges: KeyValueStore<u64, BadgeRecord>,
+        badge_count: u64,
+    }
+
+    impl BadgeManager {
+        pub fn instantiate(owner_role: OwnerRole) -> (Global<BadgeManager>, ResourceAddress) {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(BadgeManager::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let badge_res_manager =
+                ResourceBuilder::new_integer_non_fungible::<ProtocolBadge>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule;
+                        burner_updater => rule!(deny_all);
+                    })
+                    .recall_roles(recall_roles! {
+                        recaller => OWNER;
+                        recaller_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let manager_component = Self {
+                badge_res_manager,
+                badges: KeyValueStore::new(),
+                badge_count: 0,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (manager_component, badge_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        /// Mint a new badge. Its access rule is given by `get_badge_rule`.
+        pub fn issue(&mut self, label: String) -> Bucket {
+            self.badge_count += 1;
+            let badge_id = self.badge_count;
+            let issued_at_epoch = Runtime::current_epoch().number();
+
+            let badge = self.badge_res_manager.mint_non_fungible(
+                &NonFungibleLocalId::integer(badge_id),
+                ProtocolBadge {
+                    label: label.clone(),
+                    issued_at_epoch,
+                },
+            );
+
+            self.badges.insert(
+                badge_id,
+                BadgeRecord {
+                    label: label.clone(),
+                    issued_at_epoch,
+                    status: BadgeStatus::Active,
+                },
+            );
+
+            Runtime::emit_event(BadgeIssuedEvent { badge_id, label });
+
+            badge
+        }
+
+        /// Mark a badge as revoked. The badge is then recalled from its vault by the owner and
+        /// burned with `burn_revoked`.
+        pub fn revoke(&mut self, badge_id: u64) {
+            let mut record = self.badges.get_mut(&badge_id).expect("Badge not found");
+
+            assert!(record.status == BadgeStatus::Active, "Badge is not active");
+
+            record.status = BadgeStatus::Revoked {
+                revoked_at_epoch: Runtime::current_epoch().number(),
+            };
+
+            Runtime::emit_event(BadgeRevokedEvent { badge_id });
+        }
+
+        /// Burn recalled badges. Only revoked badges can be burned.
+        pub fn burn_revoked(&mut self, badges: Bucket) {
+            /* CHECK INPUT */
+            assert!(
+                badges.resource_address() == self.badge_res_manager.address(),
+                "Badge resource address mismatch"
+            );
+
+            for local_id in badges.as_non_fungible().non_fungible_local_ids() {
+                let badge_id = match local_id {
+                    NonFungibleLocalId::Integer(badge_id) => badge_id.value(),
+                    _ => panic!("Invalid badge id"),
+                };
+
+                let mut record = self.badges.get_mut(&badge_id).unwrap();
+
+                assert!(
+                    matches!(record.status, BadgeStatus::Revoked { .. }),
+                    "Badge has not been revoked"
+                );
+
+                record.status = BadgeStatus::Burned;
+            }
+
+            badges.burn();
+        }
+
+        /* GETTERS */
+
+        pub fn get_badge_res_address(&self) -> ResourceAddress {
+            self.badge_res_manager.address()
+        }
+
+        /// Access rule requiring a specific badge, e.g. to be used as the admin rule of a pool
+        pub fn get_badge_rule(&self, badge_id: u64) -> AccessRule {
+            assert!(self.badges.get(&badge_id).is_some(), "Badge not found");
+
+            rule!(require(NonFungibleGlobalId::new(
+                self.badge_res_manager.address(),
+                NonFungibleLocalId::integer(badge_id)
+            )))
+        }
+
+        pub fn get_badge(&self, badge_id: u64) -> Option<BadgeRecord> {
+            self.badges.get(&badge_id).map(|record| record.clone())
+        }
+
+        /// Enumerate badges by id, starting from `start_id`
+        pub fn get_badges(&self, start_id: u64, count: u64) -> Vec<(u64, BadgeRecord)> {
+            assert!(count <= MAX_PAGE_SIZE, "Page size too large");
+
+            let start_id = start_id.max(1);
+            let end_id = start_id.saturating_add(count).min(self.badge_count + 1);
+
+            (start_id..end_id)
+                .map(|badge_id| (badge_id, self.badges.get(&badge_id).unwrap().clone()))
+                .collect()
+        }
+
+        pub fn get_badge_count(&self) -> u64 {
+            self.badge_count
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use badge_manager::{BadgeRecord, BadgeStatus};
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the manager, and recalls revoked badges
+const OWNER: usize = 0;
+/// Holds the issued badges
+const ALICE: usize = 1;
+
+/// Badge manager at epoch 10
+struct TestManager {
+    env: TestEnv,
+    manager: ComponentAddress,
+    badge: ResourceAddress,
+}
+
+impl TestManager {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 2);
+        env.set_epoch(10);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "BadgeManager",
+                "instantiate",
+                manifest_args!(OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())))),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let (manager, badge): (ComponentAddress, ResourceAddress) =
+            receipt.expect_commit_success().output(0);
+
+        Self {
+            env,
+            manager,
+            badge,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.manager, method, args)
+    }
+
+    /// Issue a badge to Alice
+    fn issue(&mut self, label: &str) -> TransactionReceipt {
+        let badge = self.badge;
+        let manifest = ManifestBuilder::new()
+            .call_method(self.manager, "issue", manifest_args!(label.to_string()))
+            .take_all_from_worktop(badge, "badge")
+            .call_method_with_name_lookup(self.env.accounts[ALICE], "deposit", |lookup| {
+                (lookup.bucket("badge"),)
+            })
+            .build();
+
+        self.env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, self.env.badges.clone())
+    }
+
+    /// Burn one badge of the owner
+    fn burn(&mut self, res_address: ResourceAddress) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[OWNER], res_address, dec!(1))
+            .take_all_from_worktop(res_address, "badges")
+            .call_method_with_name_lookup(self.manager, "burn_revoked", |lookup| {
+                (lookup.bucket("badges"),)
+            });
+
+        self.env.execute(OWNER, builder)
+    }
+
+    fn get_badge(&mut self, badge_id: u64) -> Option<BadgeRecord> {
+        self.call(OWNER, "get_badge", manifest_args!(badge_id))
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_revoked_badge_is_recalled_and_burned() {
+    let mut manager = TestManager::new();
+    let badge = manager.badge;
+
+    manager.issue("pool admin").expect_commit_success();
+    assert_eq!(manager.env.balance(ALICE, badge), dec!(1));
+
+    let record = manager.get_badge(1).unwrap();
+    assert_eq!(record.label, "pool admin");
+    assert_eq!(record.issued_at_epoch, 10);
+    assert_eq!(record.status, BadgeStatus::Active);
+
+    let badge_rule: AccessRule = manager
+        .call(ALICE, "get_badge_rule", manifest_args!(1u64))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(
+        badge_rule,
+        rule!(require(NonFungibleGlobalId::new(
+            badge,
+            NonFungibleLocalId::integer(1)
+        )))
+    );
+
+    manager.env.set_epoch(12);
+    manager
+        .call(OWNER, "revoke", manifest_args!(1u64))
+        .expect_commit_success();
+    assert_eq!(
+        manager.get_badge(1).unwrap().status,
+        BadgeStatus::Revoked {
+            revoked_at_epoch: 12
+        }
+    );
+    manager
+        .call(OWNER, "revoke", manifest_args!(1u64))
+        .expect_commit_failure();
+
+    // The owner recalls the badge from the vault of Alice
+    let vault_id = manager
+        .env
+        .test_runner
+        .get_component_vaults(manager.env.accounts[ALICE], badge)[0];
+    let builder = ManifestBuilder::new()
+        .recall(InternalAddress::new_or_panic(vault_id.into()), dec!(1))
+        .take_all_from_worktop(badge, "badges")
+        .call_method_with_name_lookup(manager.manager, "burn_revoked", |lookup| {
+            (lookup.bucket("badges"),)
+        });
+    manager.env.execute(OWNER, builder).expect_commit_success();
+
+    assert_eq!(manager.env.balance(ALICE, badge), dec!(0));
+    assert_eq!(manager.get_badge(1).unwrap().status, BadgeStatus::Burned);
+}
+
+#[test]
+fn test_badges_are_issued_and_burned_by_the_owner() {
+    let mut manager = TestManager::new();
+    let badge = manager.badge;
+
+    manager
+        .call(ALICE, "issue", manifest_args!("pool admin".to_string()))
+        .expect_commit_failure();
+    manager
+        .call(OWNER, "issue", manifest_args!("pool admin".to_string()))
+        .expect_commit_success();
+    manager.issue("treasury").expect_commit_success();
+
+    manager
+        .call(ALICE, "revoke", manifest_args!(2u64))
+        .expect_commit_failure();
+    manager
+        .call(OWNER, "revoke", manifest_args!(3u64))
+        .expect_commit_failure();
+
+    // Only revoked badges are burned
+    manager.burn(badge).expect_commit_failure();
+    let other_badge = manager
+        .env
+        .test_runner
+        .create_non_fungible_resource(manager.env.accounts[OWNER]);
+    manager.burn(other_badge).expect_commit_failure();
+
+    manager
+        .call(OWNER, "revoke", manifest_args!(1u64))
+        .expect_commit_success();
+    manager.burn(badge).expect_commit_success();
+    assert_eq!(manager.get_badge(1).unwrap().status, BadgeStatus::Burned);
+    assert_eq!(manager.get_badge(2).unwrap().status, BadgeStatus::Active);
+
+    let badges: Vec<(u64, BadgeRecord)> = manager
+        .call(ALICE, "get_badges", manifest_args!(0u64, 10u64))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(
+        badges
+            .iter()
+            .map(|(badge_id, record)| (*badge_id, record.label.as_str()))
+            .collect::<Vec<_>>(),
+        vec![(1, "pool admin"), (2, "treasury")]
+    );
+    manager
+        .call(ALICE, "get_badges", manifest_args!(1u64, 101u64))
+        .expect_commit_failure();
+    manager
+        .call(ALICE, "get_badge_rule", manifest_args!(3u64))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_badge_enumeration_stops_at_the_last_badge() {
+    let mut manager = TestManager::new();
+
+    let get_badge_ids = |manager: &mut TestManager, start_id: u64, count: u64| -> Vec<u64> {
+        let badges: Vec<(u64, BadgeRecord)> = manager
+            .call(ALICE, "get_badges", manifest_args!(start_id, count))
+            .expect_commit_success()
+            .output(0);
+
+        badges.into_iter().map(|(badge_id, _)| badge_id).collect()
+    };
+
+    assert!(get_badge_ids(&mut manager, 0, 10).is_empty());
+    assert!(manager.get_badge(1).is_none());
+
+    for label in ["pool admin", "treasury", "oracle"] {
+        manager.issue(label).expect_commit_success();
+    }
+    let badge_count: u64 = manager
+        .call(ALICE, "get_badge_count", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(badge_count, 3);
+
+    assert_eq!(get_badge_ids(&mut manager, 2, 1), vec![2]);
+    assert_eq!(get_badge_ids(&mut manager, 3, 10), vec![3]);
+    assert_eq!(get_badge_ids(&mut manager, 1, 100), vec![1, 2, 3]);
+    assert!(get_badge_ids(&mut manager, 1, 0).is_empty());
+    assert!(get_badge_ids(&mut manager, 4, 10).is_empty());
+    assert!(get_badge_ids(&mut manager, u64::MAX, 100).is_empty());
+    assert!(manager.get_badge(4).is_none());
+}
+
+#[test]
+fn test_invalid_badge_operations_are_rejected() {
+    let mut manager = TestManager::new();
+    let badge = manager.badge;
+
+    expect_failure_containing(
+        manager.call(OWNER, "revoke", manifest_args!(1u64)),
+        "Badge not found",
+    );
+
+    // The owner holds the badge 1
+    manager
+        .call(OWNER, "issue", manifest_args!("pool admin".to_string()))
+        .expect_commit_success();
+    expect_failure_containing(
+        manager.call(ALICE, "get_badge_rule", manifest_args!(2u64)),
+        "Badge not found",
+    );
+    expect_failure_containing(manager.burn(badge), "Badge has not been revoked");
+
+    let other_badge = manager
+        .env
+        .test_runner
+        .create_non_fungible_resource(manager.env.accounts[OWNER]);
+    expect_failure_containing(manager.burn(other_badge), "Badge resource address mismatch");
+
+    manager
+        .call(OWNER, "revoke", manifest_args!(1u64))
+        .expect_commit_success();
+    manager.burn(badge).expect_commit_success();
+    expect_failure_containing(
+        manager.call(OWNER, "revoke", manifest_args!(1u64)),
+        "Badge is not active",
+    );
+
+    expect_failure_containing(
+        manager.call(ALICE, "get_badges", manifest_args!(1u64, 101u64)),
+        "Page size too large",
+    );
+}
@@ -0,0 +1,147 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct Invocation {
+    pub component_address: ComponentAddress,
+    pub method_name: String,
+    /// SBOR encoded arguments of the method
+    pub args: Vec<u8>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BatchExecutedEvent {
+    pub invocations: Vec<Invocation>,
+}
+
+#[blueprint]
+#[events(BatchExecutedEvent)]
+pub mod batch_executor {
+
+    enable_method_auth! {
+        roles {
+            operator => updatable_by: [OWNER];
+        },
+        methods {
+
+            deposit_badge => restrict_to :[OWNER];
+            withdraw_badge => restrict_to :[OWNER];
+
+            execute => restrict_to :[operator, OWNER];
+
+            get_badge => PUBLIC;
+
+        }
+    }
+
+    pub struct BatchExecutor {
+        /// Fungible badge presented to the called components, e.g. the admin badge of the
+        /// administered protocol
+        badge: Option<Vault>,
+    }
+
+    impl BatchExecutor {
+        /// Calls are made by the executor component, so target components can also allow it
+        /// through its global caller rule instead of a badge
+        pub fn instantiate(
+            owner_role: OwnerRole,
+            operator_rule: AccessRule,
+        ) -> Global<BatchExecutor> {
+            Self { badge: None }
+                .instantiate()
+                .prepare_to_globalize(owner_role)
+                .roles(roles!(
+                    operator => operator_rule;
+                ))
+                .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn deposit_badge(&mut self, badge: Bucket) {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                badge.resource_address(),
+                Some("Badge must be fungible".to_string()),
+            );
+
+            match &mut self.badge {
+                Some(vault) => vault.put(badge),
+                None => self.badge = Some(Vault::with_bucket(badge)),
+            }
+        }
+
+        pub fn withdraw_badge(&mut self) -> Bucket {
+            self.badge.as_mut().expect("No badge deposited").take_all()
+        }
+
+        /* OPERATOR METHODS */
+
+        /// Execute the invocations in order, with the badge in the auth zone. Any failing
+        /// invocation aborts the whole transaction, so either every call succeeds or none
+        /// does. Returns the output of each invocation, including the buckets it returned.
+        pub fn execute(&mut self, invocations: Vec<Invocation>) -> Vec<ScryptoValue> {
+            /* CHECK INPUT */
+            assert!(!invocations.is_empty(), "No invocation to execute");
+
+            let outputs = match &self.badge {
+                Some(vault) if !vault.is_empty() => vault
+                    .as_fungible()
+                    .authorize_with_amount(vault.amount(), || {
+                        BatchExecutor::_call_all(&invocations)
+                    }),
+                _ => BatchExecutor::_call_all(&invocations),
+            };
+
+            Runtime::emit_event(BatchExecutedEvent { invocations });
+
+            outputs
+        }
+
+        /* GETTERS */
+
+        pub fn get_badge(&self) -> Option<(ResourceAddress, Decimal)> {
+            self.badge
+                .as_ref()
+                .map(|vault| (vault.resource_address(), vault.amount()))
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _call_all(invocations: &[Invocation]) -> Vec<ScryptoValue> {
+            invocations
+                .iter()
+                .map(|invocation| {
+                    let component: Global<AnyComponent> =
+                        Global::from(invocation.component_address);
+
+                    component
+                        .call_raw::<ScryptoValue>(&invocation.method_name, invocation.args.clone())
+                })
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owner of the executor, holding the admin badge and 5 vault tokens
+const OWNER: usize = 0;
+const OPERATOR: usize = 1;
+const ALICE: usize = 2;
+
+/// Manifest encoding of an `Invocation` without argument
+fn invocation(component_address: ComponentAddress, method_name: &str) -> ManifestValue {
+    let args = scrypto_encode(&()).unwrap();
+
+    ManifestValue::Tuple {
+        fields: vec![
+            to_manifest_value_and_unwrap!(&component_address),
+            to_manifest_value_and_unwrap!(&method_name.to_string()),
+            to_manifest_value_and_unwrap!(&args),
+        ],
+    }
+}
+
+/// Executor with an operator role, and a target executor owned by the admin badge, holding
+/// 5 vault tokens withdrawable with the admin badge
+struct TestExecutor {
+    env: TestEnv,
+    executor: ComponentAddress,
+    target: ComponentAddress,
+    admin_badge: ResourceAddress,
+    vault_token: ResourceAddress,
+}
+
+impl TestExecutor {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        let admin_badge = env
+            .test_runner
+            .create_fungible_resource(dec!(1), 0, env.accounts[OWNER]);
+        let vault_token =
+            env.test_runner
+                .create_fungible_resource(dec!(5), 18, env.accounts[OWNER]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "BatchExecutor",
+                "instantiate",
+                manifest_args!(
+                    OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone()))),
+                    rule!(require(env.badges[OPERATOR].clone()))
+                ),
+            )
+            .call_function(
+                env.package_address,
+                "BatchExecutor",
+                "instantiate",
+                manifest_args!(
+                    OwnerRole::Fixed(rule!(require(admin_badge))),
+                    rule!(deny_all)
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        let mut executor = Self {
+            executor: commit.new_component_addresses()[0],
+            target: commit.new_component_addresses()[1],
+            env,
+            admin_badge,
+            vault_token,
+        };
+
+        let (owner, target) = (executor.env.accounts[OWNER], executor.target);
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(owner, admin_badge, dec!(1))
+            .withdraw_from_account(owner, vault_token, dec!(5))
+            .take_all_from_worktop(vault_token, "tokens")
+            .call_method_with_name_lookup(target, "deposit_badge", |lookup| {
+                (lookup.bucket("tokens"),)
+            });
+        executor.env.execute(OWNER, builder).expect_commit_success();
+
+        executor
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.executor, method, args)
+    }
+
+    fn execute_batch(
+        &mut self,
+        caller: usize,
+        invocations: Vec<ManifestValue>,
+    ) -> TransactionReceipt {
+        self.call(caller, "execute", manifest_args!(invocations))
+    }
+
+    /// Move the admin badge into the executor
+    fn deposit_admin_badge(&mut self) {
+        let (owner, admin_badge, executor) =
+            (self.env.accounts[OWNER], self.admin_badge, self.executor);
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(owner, admin_badge, dec!(1))
+            .take_all_from_worktop(admin_badge, "badge")
+            .call_method_with_name_lookup(executor, "deposit_badge", |lookup| {
+                (lookup.bucket("badge"),)
+            });
+
+        self.env.execute(OWNER, builder).expect_commit_success();
+    }
+
+    fn get_badge(
+        &mut self,
+        component_address: ComponentAddress,
+    ) -> Option<(ResourceAddress, Decimal)> {
+        let builder =
+            ManifestBuilder::new().call_method(component_address, "get_badge", manifest_args!());
+
+        self.env
+            .execute(ALICE, builder)
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_execute_presents_the_badge() {
+    let mut executor = TestExecutor::new();
+    let (executor_address, target) = (executor.executor, executor.target);
+    let (admin_badge, vault_token) = (executor.admin_badge, executor.vault_token);
+
+    executor
+        .execute_batch(OPERATOR, vec![invocation(target, "withdraw_badge")])
+        .expect_commit_failure();
+
+    executor.deposit_admin_badge();
+    assert_eq!(
+        executor.get_badge(executor_address),
+        Some((admin_badge, dec!(1)))
+    );
+
+    executor
+        .execute_batch(
+            OPERATOR,
+            vec![
+                invocation(target, "get_badge"),
+                invocation(target, "withdraw_badge"),
+            ],
+        )
+        .expect_commit_success();
+
+    // Buckets returned by the invocations are returned to the caller
+    assert_eq!(executor.env.balance(OPERATOR, vault_token), dec!(5));
+    assert_eq!(executor.get_badge(target), Some((vault_token, dec!(0))));
+}
+
+#[test]
+fn test_failing_invocation_aborts_the_batch() {
+    let mut executor = TestExecutor::new();
+    let (target, vault_token) = (executor.target, executor.vault_token);
+
+    executor.deposit_admin_badge();
+
+    executor
+        .execute_batch(
+            OPERATOR,
+            vec![
+                invocation(target, "withdraw_badge"),
+                invocation(target, "unknown_method"),
+            ],
+        )
+        .expect_commit_failure();
+
+    assert_eq!(executor.env.balance(OPERATOR, vault_token), dec!(0));
+    assert_eq!(executor.get_badge(target), Some((vault_token, dec!(5))));
+
+    executor
+        .execute_batch(OPERATOR, vec![])
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_methods_require_their_role() {
+    let mut executor = TestExecutor::new();
+    let (target, admin_badge) = (executor.target, executor.admin_badge);
+
+    executor
+        .execute_batch(ALICE, vec![invocation(target, "get_badge")])
+        .expect_commit_failure();
+    executor
+        .execute_batch(OWNER, vec![invocation(target, "get_badge")])
+        .expect_commit_success();
+
+    executor.deposit_admin_badge();
+
+    executor
+        .call(OPERATOR, "withdraw_badge", manifest_args!())
+        .expect_commit_failure();
+    executor
+        .call(OWNER, "withdraw_badge", manifest_args!())
+        .expect_commit_success();
+    assert_eq!(executor.env.balance(OWNER, admin_badge), dec!(1));
+}
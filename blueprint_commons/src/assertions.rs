@@ -0,0 +1,21 @@
+use scrypto::prelude::*;
+
+pub fn assert_fungible_res_address(address: ResourceAddress, message: Option<String>) {
+    assert!(
+        ResourceManager::from_address(address)
+            .resource_type()
+            .is_fungible(),
+        "{}",
+        message.unwrap_or("Resource must be fungible".to_string())
+    );
+}
+
+pub fn assert_non_fungible_res_address(address: ResourceAddress, message: Option<String>) {
+    assert!(
+        !ResourceManager::from_address(address)
+            .resource_type()
+            .is_fungible(),
+        "{}",
+        message.unwrap_or("Resource must be non fungible".to_string())
+    );
+}
@@ -0,0 +1,89 @@
+use scrypto::prelude::*;
+
+/// Index of the last of `count` checkpoints sorted by epoch that is strictly before `epoch`,
+/// where `epoch_at` returns the epoch of the checkpoint at an index
+pub fn index_before(count: u64, epoch: u64, epoch_at: impl Fn(u64) -> u64) -> Option<u64> {
+    let (mut low, mut high) = (0, count);
+
+    while low < high {
+        let middle = low + (high - low) / 2;
+
+        if epoch_at(middle) < epoch {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+
+    low.checked_sub(1)
+}
+
+/// History of an amount, recorded at most once per epoch. Checkpoints are stored in a key
+/// value store indexed in epoch order, so the history grows without growing the state of the
+/// component, and past values are found with a binary search.
+#[derive(ScryptoSbor)]
+pub struct Checkpoints {
+    /// `(epoch, amount)` checkpoints, indexed from 0
+    checkpoints: KeyValueStore<u64, (u64, Decimal)>,
+    count: u64,
+}
+
+impl Checkpoints {
+    pub fn new() -> Self {
+        Self {
+            checkpoints: KeyValueStore::new(),
+            count: 0,
+        }
+    }
+
+    /// Amount recorded strictly before the given epoch, zero without such checkpoint
+    pub fn value_before(&self, epoch: u64) -> Decimal {
+        index_before(self.count, epoch, |index| self.get(index).0)
+            .map_or(Decimal::ZERO, |index| self.get(index).1)
+    }
+
+    /// Latest recorded amount, zero without checkpoint
+    pub fn latest(&self) -> Decimal {
+        self.count
+            .checked_sub(1)
+            .map_or(Decimal::ZERO, |index| self.get(index).1)
+    }
+
+    /// Record the amount at the given epoch, which must not precede the latest checkpoint. The
+    /// checkpoint of the same epoch is overwritten.
+    pub fn push(&mut self, epoch: u64, amount: Decimal) {
+        match self.count.checked_sub(1) {
+            Some(last_index) if self.get(last_index).0 == epoch => {
+                self.checkpoints.insert(last_index, (epoch, amount));
+            }
+            last_index => {
+                assert!(
+                    last_index.map_or(true, |index| self.get(index).0 < epoch),
+                    "Checkpoints must be recorded in epoch order"
+                );
+
+                self.checkpoints.insert(self.count, (epoch, amount));
+                self.count += 1;
+            }
+        }
+    }
+
+    /// Add a change to the latest amount at the given epoch. Returns the new amount.
+    pub fn push_change(&mut self, epoch: u64, change: Decimal) -> Decimal {
+        let amount = self.latest() + change;
+
+        self.push(epoch, amount);
+
+        amount
+    }
+
+    fn get(&self, index: u64) -> (u64, Decimal) {
+        *self.checkpoints.get(&index).unwrap()
+    }
+}
+
+impl Default for Checkpoints {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,61 @@
+use scrypto::prelude::*;
+
+/// Reentrancy guard to be stored in the state of a component. The flag is kept in a key value
+/// store entry rather than in a plain field: component state is only written back when the
+/// method returns, while key value store entries are written as soon as the entry is released,
+/// so the flag is visible to nested calls made before the method returns.
+#[derive(ScryptoSbor)]
+pub struct ReentrancyGuard {
+    entered: KeyValueStore<u8, bool>,
+}
+
+impl ReentrancyGuard {
+    pub fn new() -> Self {
+        let entered = KeyValueStore::new();
+        entered.insert(0, false);
+
+        Self { entered }
+    }
+
+    pub fn is_entered(&self) -> bool {
+        *self.entered.get(&0).unwrap()
+    }
+
+    /// Panics if a guarded method is being executed
+    pub fn assert_not_entered(&self) {
+        assert!(!self.is_entered(), "Reentrant call");
+    }
+
+    /// Mark the start of a guarded method, panicking if a guarded method is already being
+    /// executed. A panic aborts the transaction, so `exit` only needs to be called on the
+    /// successful paths.
+    pub fn enter(&self) {
+        let mut entered = self.entered.get_mut(&0).unwrap();
+
+        assert!(!*entered, "Reentrant call");
+
+        *entered = true;
+    }
+
+    pub fn exit(&self) {
+        *self.entered.get_mut(&0).unwrap() = false;
+    }
+
+    /// Run `f` as a guarded method. Meant to wrap the calls to other components, such as
+    /// callbacks, that could call back into the guarded methods.
+    pub fn scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        self.enter();
+
+        let output = f();
+
+        self.exit();
+
+        output
+    }
+}
+
+impl Default for ReentrancyGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
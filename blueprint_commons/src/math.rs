@@ -0,0 +1,58 @@
+use scrypto::prelude::*;
+
+/// Computes `amount * ratio` rounded to `divisibility` decimal places. Panics with the
+/// operands and the operation on overflow so failures are debuggable from the receipt.
+pub fn checked_mul_to_decimal(
+    amount: Decimal,
+    ratio: PreciseDecimal,
+    divisibility: u8,
+    rounding_mode: RoundingMode,
+) -> Decimal {
+    let result = PreciseDecimal::from(amount)
+        .checked_mul(ratio)
+        .and_then(|value| to_decimal(value, divisibility, rounding_mode));
+
+    result
+        .unwrap_or_else(|| math_panic("multiplication", amount, ratio, divisibility, rounding_mode))
+}
+
+/// Computes `amount / ratio` rounded to `divisibility` decimal places. Panics with the
+/// operands and the operation on overflow or division by zero.
+pub fn checked_div_to_decimal(
+    amount: Decimal,
+    ratio: PreciseDecimal,
+    divisibility: u8,
+    rounding_mode: RoundingMode,
+) -> Decimal {
+    let result = PreciseDecimal::from(amount)
+        .checked_div(ratio)
+        .and_then(|value| to_decimal(value, divisibility, rounding_mode));
+
+    result.unwrap_or_else(|| math_panic("division", amount, ratio, divisibility, rounding_mode))
+}
+
+// Rounds the precise result once: the rounded value fits in a Decimal, so the conversion is
+// exact. Truncating to a Decimal first would round twice, which is off by one unit in the
+// nearest and midpoint modes when the truncation lands on a midpoint.
+fn to_decimal(
+    value: PreciseDecimal,
+    divisibility: u8,
+    rounding_mode: RoundingMode,
+) -> Option<Decimal> {
+    value
+        .checked_round(divisibility, rounding_mode)
+        .and_then(|value| value.checked_truncate(RoundingMode::ToZero))
+}
+
+fn math_panic(
+    operation: &str,
+    amount: Decimal,
+    ratio: PreciseDecimal,
+    divisibility: u8,
+    rounding_mode: RoundingMode,
+) -> ! {
+    panic!(
+        "Checked math error: {} of amount {} by unit to asset ratio {} failed (divisibility: {}, rounding: {:?})",
+        operation, amount, ratio, divisibility, rounding_mode
+    )
+}
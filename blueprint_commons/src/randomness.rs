@@ -0,0 +1,41 @@
+use scrypto::prelude::*;
+
+// COMMIT-REVEAL RANDOMNESS
+//
+// Values readable on ledger (epoch, transaction hash, RUIDs...) are known or can be influenced
+// by whoever submits the transaction, so they cannot be used alone to draw a winner: the
+// submitter could simulate the draw and only commit the transaction when it wins.
+//
+// Draws use a commit-reveal scheme instead:
+// 1. The operator commits to `hash(secret)` without revealing the secret.
+// 2. Every user transaction mixes one of its RUIDs into a public entropy accumulator with
+//    `mix_entropy`. The operator cannot predict it when committing.
+// 3. Once the entropy is frozen, the operator reveals the secret. The seed is
+//    `hash(secret || entropy)`, which users cannot predict since they don't know the secret,
+//    and the operator cannot choose since the secret is bound by the commitment.
+//
+// The operator still knows the outcome before revealing and could withhold an unfavourable
+// reveal: every blueprint using the scheme must penalize a missed reveal deadline.
+
+/// Commitment to a secret, published before the entropy is accumulated
+pub fn commitment(secret: &[u8]) -> Hash {
+    hash(secret)
+}
+
+/// Entropy accumulator after mixing a RUID of the current transaction
+pub fn mix_entropy(entropy: Hash) -> Hash {
+    hash([&entropy.0[..], &Runtime::generate_ruid()[..]].concat())
+}
+
+/// Seed of the draws, from the revealed secret and the frozen entropy
+pub fn reveal_seed(secret: &[u8], entropy: Hash) -> Hash {
+    hash([secret, &entropy.0[..]].concat())
+}
+
+/// Number between 0 and `count - 1` drawn from the seed
+pub fn draw(seed: Hash, count: u64) -> u64 {
+    let mut seed_bytes = [0u8; 8];
+    seed_bytes.copy_from_slice(&seed.0[..8]);
+
+    u64::from_le_bytes(seed_bytes) % count
+}
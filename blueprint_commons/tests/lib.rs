@@ -0,0 +1,152 @@
+use blueprint_commons::*;
+use scrypto::prelude::*;
+
+#[test]
+fn test_checked_mul_to_decimal_rounds_to_divisibility() {
+    let ratio = PreciseDecimal::from(dec!("1.5"));
+
+    assert_eq!(
+        checked_mul_to_decimal(dec!("1.3"), ratio, 0, RoundingMode::ToZero),
+        dec!(1)
+    );
+    assert_eq!(
+        checked_mul_to_decimal(dec!("1.3"), ratio, 0, RoundingMode::AwayFromZero),
+        dec!(2)
+    );
+    assert_eq!(
+        checked_mul_to_decimal(dec!("1.3"), ratio, 18, RoundingMode::ToZero),
+        dec!("1.95")
+    );
+}
+
+#[test]
+fn test_checked_div_to_decimal_rounds_to_divisibility() {
+    let ratio = PreciseDecimal::from(3);
+
+    assert_eq!(
+        checked_div_to_decimal(dec!(10), ratio, 2, RoundingMode::ToZero),
+        dec!("3.33")
+    );
+    assert_eq!(
+        checked_div_to_decimal(dec!(10), ratio, 2, RoundingMode::AwayFromZero),
+        dec!("3.34")
+    );
+}
+
+#[test]
+fn test_checked_math_truncates_precise_result_before_rounding() {
+    // 1 / 3 has more decimal places than a Decimal can hold
+    let ratio = PreciseDecimal::from(3);
+
+    assert_eq!(
+        checked_div_to_decimal(dec!(1), ratio, 18, RoundingMode::AwayFromZero),
+        dec!("0.333333333333333334")
+    );
+    assert_eq!(
+        checked_div_to_decimal(dec!(1), ratio, 18, RoundingMode::ToZero),
+        dec!("0.333333333333333333")
+    );
+}
+
+#[test]
+fn test_checked_math_rounds_the_precise_result_once() {
+    // 0.4999999999999999995 would be rounded to 0.5 by a truncation to 18 decimal places
+    let half = PreciseDecimal::from(dec!("0.5"));
+
+    assert_eq!(
+        checked_mul_to_decimal(
+            dec!("0.999999999999999999"),
+            half,
+            0,
+            RoundingMode::ToNearestMidpointAwayFromZero
+        ),
+        dec!(0)
+    );
+    assert_eq!(
+        checked_mul_to_decimal(
+            dec!("0.999999999999999999"),
+            half,
+            0,
+            RoundingMode::ToNearestMidpointToEven
+        ),
+        dec!(0)
+    );
+    assert_eq!(
+        checked_div_to_decimal(
+            dec!(1),
+            pdec!("2.000000000000000002"),
+            0,
+            RoundingMode::ToNearestMidpointAwayFromZero
+        ),
+        dec!(0)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Checked math error: division")]
+fn test_checked_div_to_decimal_panics_on_zero_ratio() {
+    checked_div_to_decimal(dec!(1), PreciseDecimal::ZERO, 18, RoundingMode::ToZero);
+}
+
+#[test]
+#[should_panic(expected = "Checked math error: multiplication")]
+fn test_checked_mul_to_decimal_panics_on_overflow() {
+    checked_mul_to_decimal(
+        Decimal::MAX,
+        PreciseDecimal::from(2),
+        18,
+        RoundingMode::ToZero,
+    );
+}
+
+#[test]
+fn test_index_before_ignores_the_checkpoints_of_the_epoch() {
+    let epochs = [10, 12, 15];
+    let epoch_at = |index: u64| epochs[index as usize];
+
+    assert_eq!(index_before(3, 10, epoch_at), None);
+    assert_eq!(index_before(3, 11, epoch_at), Some(0));
+    assert_eq!(index_before(3, 12, epoch_at), Some(0));
+    assert_eq!(index_before(3, 13, epoch_at), Some(1));
+    assert_eq!(index_before(3, 15, epoch_at), Some(1));
+    assert_eq!(index_before(3, 100, epoch_at), Some(2));
+    assert_eq!(index_before(0, 13, epoch_at), None);
+}
+
+#[test]
+fn test_index_before_searches_long_histories() {
+    let epoch_at = |index: u64| 10 * index;
+
+    for epoch in 1..1000 {
+        assert_eq!(index_before(100, epoch, epoch_at), Some((epoch - 1) / 10));
+    }
+}
+
+#[test]
+fn test_reveal_seed_binds_the_secret_and_the_entropy() {
+    let entropy = hash("entropy");
+
+    assert_eq!(
+        reveal_seed(b"secret", entropy),
+        reveal_seed(b"secret", entropy)
+    );
+    assert_ne!(
+        reveal_seed(b"secret", entropy),
+        reveal_seed(b"other", entropy)
+    );
+    assert_ne!(
+        reveal_seed(b"secret", entropy),
+        reveal_seed(b"secret", hash("other"))
+    );
+    assert_eq!(commitment(b"secret"), hash(b"secret"));
+}
+
+#[test]
+fn test_draw_is_in_range() {
+    for i in 0..100u8 {
+        let seed = hash([i]);
+
+        assert!(draw(seed, 7) < 7);
+        assert_eq!(draw(seed, 1), 0);
+    }
+}
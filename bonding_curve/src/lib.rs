@@ -0,0 +1,386 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+pub use fixed_point_math::*;
+
+/// Maximum spread fee
+pub const MAX_SPREAD_FEE: Decimal = dec!("0.1");
+
+#[derive(ScryptoSbor, Clone, Copy, Debug)]
+pub enum CurveType {
+    /// `price = initial_price + slope * supply`
+    Linear {
+        initial_price: Decimal,
+        slope: Decimal,
+    },
+    /// `price = initial_price * e^(growth_rate * supply)`
+    Exponential {
+        initial_price: Decimal,
+        growth_rate: Decimal,
+    },
+}
+
+impl CurveType {
+    /// Reserve amount backing the given supply, i.e. the integral of the price from zero to
+    /// the supply
+    pub fn reserve_for_supply(&self, supply: PreciseDecimal) -> PreciseDecimal {
+        match *self {
+            CurveType::Linear {
+                initial_price,
+                slope,
+            } => {
+                PreciseDecimal::from(initial_price) * supply
+                    + PreciseDecimal::from(slope) * supply * supply / PreciseDecimal::from(2)
+            }
+            CurveType::Exponential {
+                initial_price,
+                growth_rate,
+            } => {
+                let growth_rate = PreciseDecimal::from(growth_rate);
+
+                PreciseDecimal::from(initial_price)
+                    * (exp(growth_rate * supply) - PreciseDecimal::ONE)
+                    / growth_rate
+            }
+        }
+    }
+
+    /// Supply backed by the given reserve amount, inverse of `reserve_for_supply`
+    pub fn supply_for_reserve(&self, reserve: PreciseDecimal) -> PreciseDecimal {
+        match *self {
+            CurveType::Linear {
+                initial_price,
+                slope,
+            } => {
+                let initial_price = PreciseDecimal::from(initial_price);
+
+                if slope == Decimal::ZERO {
+                    return reserve / initial_price;
+                }
+
+                let slope = PreciseDecimal::from(slope);
+                let discriminant =
+                    initial_price * initial_price + PreciseDecimal::from(2) * slope * reserve;
+
+                (discriminant.sqrt().expect("Invalid curve reserve") - initial_price) / slope
+            }
+            CurveType::Exponential {
+                initial_price,
+                growth_rate,
+            } => {
+                let growth_rate = PreciseDecimal::from(growth_rate);
+
+                ln(reserve * growth_rate / PreciseDecimal::from(initial_price)
+                    + PreciseDecimal::ONE)
+                    / growth_rate
+            }
+        }
+    }
+
+    fn assert_valid(&self) {
+        match *self {
+            CurveType::Linear {
+                initial_price,
+                slope,
+            } => {
+                assert!(
+                    initial_price > Decimal::ZERO && slope >= Decimal::ZERO,
+                    "Invalid linear curve parameters"
+                );
+            }
+            CurveType::Exponential {
+                initial_price,
+                growth_rate,
+            } => {
+                assert!(
+                    initial_price > Decimal::ZERO && growth_rate > Decimal::ZERO,
+                    "Invalid exponential curve parameters"
+                );
+            }
+        }
+    }
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BuyEvent {
+    pub reserve_amount: Decimal,
+    pub token_amount: Decimal,
+    pub fee_amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SellEvent {
+    pub token_amount: Decimal,
+    pub reserve_amount: Decimal,
+    pub fee_amount: Decimal,
+}
+
+#[blueprint]
+#[events(BuyEvent, SellEvent)]
+pub mod bonding_curve {
+
+    enable_method_auth! {
+        methods {
+
+            set_spread_fee => restrict_to :[OWNER];
+            withdraw_fees => restrict_to :[OWNER];
+
+            buy => PUBLIC;
+            sell => PUBLIC;
+
+            get_price => PUBLIC;
+            get_buy_amount => PUBLIC;
+            get_sell_amount => PUBLIC;
+            get_supply => PUBLIC;
+
+        }
+    }
+
+    pub struct BondingCurve {
+        curve: CurveType,
+
+        /// Reserve backing the token supply along the curve
+        reserve: Vault,
+
+        /// Spread fees taken on buys and sells
+        fees: Vault,
+        spread_fee: Decimal,
+
+        token_res_manager: ResourceManager,
+    }
+
+    impl BondingCurve {
+        pub fn instantiate(
+            reserve_res_address: ResourceAddress,
+            curve: CurveType,
+            spread_fee: Decimal,
+            token_divisibility: u8,
+            owner_role: OwnerRole,
+        ) -> (Global<BondingCurve>, ResourceAddress) {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                reserve_res_address,
+                Some("Reserve resource must be fungible".to_string()),
+            );
+            curve.assert_valid();
+            BondingCurve::_assert_valid_spread_fee(spread_fee);
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(BondingCurve::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let token_res_manager = ResourceBuilder::new_fungible(owner_role.clone())
+                .divisibility(token_divisibility)
+                .mint_roles(mint_roles! {
+                    minter => component_rule.clone();
+                    minter_updater => rule!(deny_all);
+                })
+                .burn_roles(burn_roles! {
+                    burner => component_rule;
+                    burner_updater => rule!(deny_all);
+                })
+                .create_with_no_initial_supply();
+
+            let curve_component = Self {
+                curve,
+                reserve: Vault::new(reserve_res_address),
+                fees: Vault::new(reserve_res_address),
+                spread_fee,
+                token_res_manager,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (curve_component, token_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn set_spread_fee(&mut self, spread_fee: Decimal) {
+            BondingCurve::_assert_valid_spread_fee(spread_fee);
+
+            self.spread_fee = spread_fee;
+        }
+
+        pub fn withdraw_fees(&mut self) -> Bucket {
+            self.fees.take_all()
+        }
+
+        /* TRADER METHODS */
+
+        /// Mint tokens along the curve against the reserve resource, minus the spread fee
+        pub fn buy(&mut self, mut payment: Bucket) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                payment.resource_address() == self.reserve.resource_address(),
+                "Reserve resource address mismatch"
+            );
+
+            let fee_amount = payment.amount() * self.spread_fee;
+            let reserve_amount = payment.amount() - fee_amount;
+            let token_amount = self.get_buy_amount(payment.amount());
+
+            assert!(token_amount > Decimal::ZERO, "Payment is too small");
+
+            self.fees.put(
+                payment.take_advanced(fee_amount, WithdrawStrategy::Rounded(RoundingMode::ToZero)),
+            );
+            self.reserve.put(payment);
+
+            Runtime::emit_event(BuyEvent {
+                reserve_amount,
+                token_amount,
+                fee_amount,
+            });
+
+            self.token_res_manager.mint(token_amount)
+        }
+
+        /// Burn tokens and receive the reserve released along the curve, minus the spread fee
+        pub fn sell(&mut self, tokens: Bucket) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                tokens.resource_address() == self.token_res_manager.address(),
+                "Token resource address mismatch"
+            );
+
+            let token_amount = tokens.amount();
+            let released_amount = self._get_released_reserve(token_amount);
+            tokens.burn();
+
+            let mut released = self.reserve.take_advanced(
+                released_amount,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+
+            let fee_amount = released.amount() * self.spread_fee;
+            let fees = released.take_advanced(
+                fee_amount,
+                WithdrawStrategy::Rounded(RoundingMode::AwayFromZero),
+            );
+
+            Runtime::emit_event(SellEvent {
+                token_amount,
+                reserve_amount: released.amount(),
+                fee_amount: fees.amount(),
+            });
+
+            self.fees.put(fees);
+
+            released
+        }
+
+        /* GETTERS */
+
+        /// Marginal price of the token at the current supply
+        pub fn get_price(&self) -> Decimal {
+            let supply = self.get_supply();
+
+            match self.curve {
+                CurveType::Linear {
+                    initial_price,
+                    slope,
+                } => initial_price + slope * supply,
+                CurveType::Exponential {
+                    initial_price,
+                    growth_rate,
+                } => BondingCurve::_to_decimal(
+                    PreciseDecimal::from(initial_price)
+                        * exp(PreciseDecimal::from(growth_rate) * supply),
+                ),
+            }
+        }
+
+        /// Tokens minted for the given payment
+        pub fn get_buy_amount(&self, payment_amount: Decimal) -> Decimal {
+            let reserve_amount = payment_amount - payment_amount * self.spread_fee;
+            let supply = PreciseDecimal::from(self.get_supply());
+
+            let current_reserve = self.curve.reserve_for_supply(supply);
+            let new_supply = self
+                .curve
+                .supply_for_reserve(current_reserve + reserve_amount);
+
+            BondingCurve::_to_decimal(new_supply - supply)
+                .checked_round(
+                    self.token_res_manager
+                        .resource_type()
+                        .divisibility()
+                        .unwrap(),
+                    RoundingMode::ToZero,
+                )
+                .unwrap()
+        }
+
+        /// Reserve received for selling the given token amount
+        pub fn get_sell_amount(&self, token_amount: Decimal) -> Decimal {
+            let released_amount = self._get_released_reserve(token_amount);
+
+            released_amount - released_amount * self.spread_fee
+        }
+
+        pub fn get_supply(&self) -> Decimal {
+            self.token_res_manager
+                .total_supply()
+                .unwrap_or(Decimal::ZERO)
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_spread_fee(spread_fee: Decimal) {
+            assert!(
+                spread_fee >= Decimal::ZERO && spread_fee <= MAX_SPREAD_FEE,
+                "Spread fee must be between 0 and {}",
+                MAX_SPREAD_FEE
+            );
+        }
+
+        /// Reserve released by burning the given token amount, capped by the reserve vault so
+        /// rounding can never make the last sellers fail
+        fn _get_released_reserve(&self, token_amount: Decimal) -> Decimal {
+            let supply = PreciseDecimal::from(self.get_supply());
+
+            assert!(
+                token_amount > Decimal::ZERO && PreciseDecimal::from(token_amount) <= supply,
+                "Invalid token amount"
+            );
+
+            let released = self.curve.reserve_for_supply(supply)
+                - self.curve.reserve_for_supply(supply - token_amount);
+
+            BondingCurve::_to_decimal(released).min(self.reserve.amount())
+        }
+
+        fn _to_decimal(value: PreciseDecimal) -> Decimal {
+            value
+                .checked_truncate(RoundingMode::ToZero)
+                .expect("Bonding curve math overflow")
+        }
+    }
+}
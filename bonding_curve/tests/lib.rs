@@ -0,0 +1,295 @@
+use bonding_curve::*;
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+fn assert_close(value: PreciseDecimal, expected: PreciseDecimal, tolerance: PreciseDecimal) {
+    let error = if value > expected {
+        value - expected
+    } else {
+        expected - value
+    };
+
+    assert!(
+        error <= tolerance,
+        "Expected {} but got {} (error: {})",
+        expected,
+        value,
+        error
+    );
+}
+
+const LINEAR: CurveType = CurveType::Linear {
+    initial_price: dec!(1),
+    slope: dec!("0.01"),
+};
+
+const EXPONENTIAL: CurveType = CurveType::Exponential {
+    initial_price: dec!(1),
+    growth_rate: dec!("0.01"),
+};
+
+#[test]
+fn test_linear_reserve_for_supply() {
+    assert_eq!(LINEAR.reserve_for_supply(pdec!(0)), pdec!(0));
+    assert_eq!(LINEAR.reserve_for_supply(pdec!(100)), pdec!(150));
+    assert_eq!(LINEAR.reserve_for_supply(pdec!(200)), pdec!(400));
+
+    let flat = CurveType::Linear {
+        initial_price: dec!(2),
+        slope: dec!(0),
+    };
+    assert_eq!(flat.reserve_for_supply(pdec!(100)), pdec!(200));
+    assert_eq!(flat.supply_for_reserve(pdec!(200)), pdec!(100));
+}
+
+#[test]
+fn test_linear_supply_for_reserve_round_trip() {
+    let tolerance = pdec!("0.000000000000000001");
+
+    assert_eq!(LINEAR.supply_for_reserve(pdec!(150)), pdec!(100));
+
+    for supply in [
+        pdec!(0),
+        pdec!("0.5"),
+        pdec!(1),
+        pdec!("12345.678"),
+        pdec!(1000000),
+    ] {
+        assert_close(
+            LINEAR.supply_for_reserve(LINEAR.reserve_for_supply(supply)),
+            supply,
+            tolerance,
+        );
+    }
+}
+
+#[test]
+fn test_exponential_reserve_for_supply() {
+    let tolerance = pdec!("0.000000000000000001");
+
+    assert_eq!(EXPONENTIAL.reserve_for_supply(pdec!(0)), pdec!(0));
+    // (e - 1) / 0.01
+    assert_close(
+        EXPONENTIAL.reserve_for_supply(pdec!(100)),
+        pdec!("171.828182845904523536028747135266249775"),
+        tolerance,
+    );
+}
+
+#[test]
+fn test_exponential_supply_for_reserve_round_trip() {
+    let tolerance = pdec!("0.000000000000000001");
+
+    for supply in [pdec!(0), pdec!("0.5"), pdec!(1), pdec!(100), pdec!(2500)] {
+        assert_close(
+            EXPONENTIAL.supply_for_reserve(EXPONENTIAL.reserve_for_supply(supply)),
+            supply,
+            tolerance,
+        );
+    }
+}
+
+/// Manifest encoding of `CurveType::Linear`
+fn linear_curve(initial_price: Decimal, slope: Decimal) -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 0,
+        fields: vec![
+            to_manifest_value_and_unwrap!(&initial_price),
+            to_manifest_value_and_unwrap!(&slope),
+        ],
+    }
+}
+
+/// Linear curve with an initial price of 1 and a slope of 0.01, owned by the account which
+/// also holds 1000 reserve tokens
+struct TestCurve {
+    env: TestEnv,
+    account: ComponentAddress,
+    curve: ComponentAddress,
+    token: ResourceAddress,
+    reserve: ResourceAddress,
+}
+
+impl TestCurve {
+    fn new(spread_fee: Decimal) -> Self {
+        let mut env = TestEnv::new(this_package!(), 1);
+        let account = env.accounts[0];
+
+        let reserve = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, account);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "BondingCurve",
+                "instantiate",
+                manifest_args!(
+                    reserve,
+                    linear_curve(dec!(1), dec!("0.01")),
+                    spread_fee,
+                    18u8,
+                    OwnerRole::Fixed(rule!(require(env.badges[0].clone())))
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            curve: commit.new_component_addresses()[0],
+            token: commit.new_resource_addresses()[0],
+            env,
+            account,
+            reserve,
+        }
+    }
+
+    fn execute(&mut self, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute(0, builder)
+    }
+
+    fn trade(
+        &mut self,
+        method: &str,
+        res_address: ResourceAddress,
+        amount: Decimal,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, res_address, amount)
+            .take_all_from_worktop(res_address, "input")
+            .call_method_with_name_lookup(self.curve, method, |lookup| (lookup.bucket("input"),));
+
+        self.execute(builder)
+    }
+
+    fn get(&mut self, method: &str, args: impl ResolvableArguments) -> Decimal {
+        let builder = ManifestBuilder::new().call_method(self.curve, method, args);
+
+        self.execute(builder).expect_commit_success().output(0)
+    }
+
+    fn balance(&mut self, res_address: ResourceAddress) -> Decimal {
+        self.env.balance(0, res_address)
+    }
+}
+
+#[test]
+fn test_buy_and_sell_along_the_curve() {
+    let mut curve = TestCurve::new(dec!(0));
+    let (token, reserve) = (curve.token, curve.reserve);
+
+    assert_eq!(
+        curve.get("get_buy_amount", manifest_args!(dec!(150))),
+        dec!(100)
+    );
+    curve
+        .trade("buy", reserve, dec!(150))
+        .expect_commit_success();
+
+    assert_eq!(curve.balance(token), dec!(100));
+    assert_eq!(curve.get("get_supply", manifest_args!()), dec!(100));
+    assert_eq!(curve.get("get_price", manifest_args!()), dec!(2));
+
+    assert_eq!(
+        curve.get("get_sell_amount", manifest_args!(dec!(100))),
+        dec!(150)
+    );
+    curve
+        .trade("sell", token, dec!(100))
+        .expect_commit_success();
+
+    assert_eq!(curve.balance(reserve), dec!(1000));
+    assert_eq!(curve.get("get_supply", manifest_args!()), dec!(0));
+}
+
+#[test]
+fn test_spread_fee_is_kept_on_buys_and_sells() {
+    let mut curve = TestCurve::new(dec!("0.1"));
+    let (token, reserve, curve_address) = (curve.token, curve.reserve, curve.curve);
+
+    curve
+        .trade("buy", reserve, dec!(150))
+        .expect_commit_success();
+    assert_eq!(curve.balance(reserve), dec!(850));
+
+    let tokens = curve.balance(token);
+    assert!(tokens < dec!(100));
+
+    let sell_amount = curve.get("get_sell_amount", manifest_args!(tokens));
+    curve.trade("sell", token, tokens).expect_commit_success();
+    assert_eq!(curve.balance(reserve), dec!(850) + sell_amount);
+
+    assert_close(
+        PreciseDecimal::from(curve.balance(reserve)),
+        pdec!("971.5"),
+        pdec!("0.00000000000000001"),
+    );
+
+    let builder =
+        ManifestBuilder::new().call_method(curve_address, "withdraw_fees", manifest_args!());
+    curve.execute(builder).expect_commit_success();
+
+    // The fees of both trades are withdrawn by the owner
+    assert_close(
+        PreciseDecimal::from(curve.balance(reserve)),
+        pdec!(1000),
+        pdec!("0.00000000000000001"),
+    );
+}
+
+#[test]
+fn test_trade_with_another_resource_fails() {
+    let mut curve = TestCurve::new(dec!(0));
+    let (token, reserve, account) = (curve.token, curve.reserve, curve.account);
+    let other = curve
+        .env
+        .test_runner
+        .create_fungible_resource(dec!(100), 18, account);
+
+    curve.trade("buy", other, dec!(10)).expect_commit_failure();
+    curve
+        .trade("buy", reserve, dec!(10))
+        .expect_commit_success();
+    curve.trade("sell", other, dec!(10)).expect_commit_failure();
+    curve.trade("buy", token, dec!(1)).expect_commit_failure();
+}
+
+#[test]
+fn test_set_spread_fee() {
+    let mut curve = TestCurve::new(dec!(0));
+    let curve_address = curve.curve;
+
+    let builder = ManifestBuilder::new().call_method(
+        curve_address,
+        "set_spread_fee",
+        manifest_args!(dec!("0.11")),
+    );
+    curve.execute(builder).expect_commit_failure();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            curve_address,
+            "set_spread_fee",
+            manifest_args!(dec!("0.05")),
+        )
+        .build();
+    curve
+        .env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![])
+        .expect_commit_failure();
+
+    let builder = ManifestBuilder::new().call_method(
+        curve_address,
+        "set_spread_fee",
+        manifest_args!(dec!("0.05")),
+    );
+    curve.execute(builder).expect_commit_success();
+    assert!(curve.get("get_buy_amount", manifest_args!(dec!(150))) < dec!(100));
+}
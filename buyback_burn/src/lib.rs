@@ -0,0 +1,277 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use price_oracle::price_oracle::PriceOracle;
+use scrypto::prelude::*;
+use swap_router::swap_router::SwapRouter;
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct BuybackRoute {
+    /// Pairs registered in the swap router, from the revenue resource to the target token
+    pub path: Vec<ComponentAddress>,
+
+    /// Maximum revenue amount swapped by a single execution
+    pub max_amount_per_execution: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BuybackEvent {
+    pub res_address: ResourceAddress,
+    pub amount_in: Decimal,
+    pub amount_burned: Decimal,
+}
+
+#[blueprint]
+#[events(BuybackEvent)]
+pub mod buyback_burn {
+
+    enable_method_auth! {
+        roles {
+            keeper => updatable_by: [OWNER];
+        },
+        methods {
+
+            set_route => restrict_to :[OWNER];
+            remove_route => restrict_to :[OWNER];
+            set_max_slippage => restrict_to :[OWNER];
+            set_router => restrict_to :[OWNER];
+            set_oracle => restrict_to :[OWNER];
+
+            execute => restrict_to :[keeper, OWNER];
+
+            deposit_revenue => PUBLIC;
+
+            get_revenue => PUBLIC;
+            get_route => PUBLIC;
+            get_total_burned => PUBLIC;
+
+        }
+    }
+
+    pub struct BuybackBurn {
+        /// Token bought back and burned. Its burn rule must allow this component.
+        target_res_address: ResourceAddress,
+
+        /// Revenue waiting to be swapped, indexed by resource
+        revenue: KeyValueStore<ResourceAddress, Vault>,
+
+        routes: KeyValueStore<ResourceAddress, BuybackRoute>,
+
+        router: Global<SwapRouter>,
+
+        /// Maximum loss of a swap compared to the oracle price
+        max_slippage: Decimal,
+
+        oracle: Global<PriceOracle>,
+        max_price_age_seconds: i64,
+
+        total_burned: Decimal,
+    }
+
+    impl BuybackBurn {
+        pub fn instantiate(
+            target_res_address: ResourceAddress,
+            router: Global<SwapRouter>,
+            max_slippage: Decimal,
+            oracle: Global<PriceOracle>,
+            max_price_age_seconds: i64,
+            owner_role: OwnerRole,
+            keeper_rule: AccessRule,
+        ) -> Global<BuybackBurn> {
+            /* CHECK INPUT */
+            BuybackBurn::_assert_valid_slippage(max_slippage);
+            assert!(
+                max_price_age_seconds > 0,
+                "Max price age must be greater than zero"
+            );
+
+            Self {
+                target_res_address,
+                revenue: KeyValueStore::new(),
+                routes: KeyValueStore::new(),
+                router,
+                max_slippage,
+                oracle,
+                max_price_age_seconds,
+                total_burned: Decimal::ZERO,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                keeper => keeper_rule;
+            ))
+            .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        /// Set the swap route and the per-execution cap of a revenue resource. Revenue in the
+        /// target token is burned directly and only needs a cap.
+        pub fn set_route(
+            &mut self,
+            res_address: ResourceAddress,
+            path: Vec<ComponentAddress>,
+            max_amount_per_execution: Decimal,
+        ) {
+            /* CHECK INPUT */
+            assert!(
+                path.is_empty() == (res_address == self.target_res_address),
+                "Only revenue in the target token has an empty path"
+            );
+            assert!(
+                max_amount_per_execution > Decimal::ZERO,
+                "Max amount per execution must be greater than zero"
+            );
+
+            self.routes.insert(
+                res_address,
+                BuybackRoute {
+                    path,
+                    max_amount_per_execution,
+                },
+            );
+        }
+
+        /// Remove the route of a revenue resource. Its revenue is kept until a new route is
+        /// set.
+        pub fn remove_route(&mut self, res_address: ResourceAddress) {
+            assert!(self.routes.get(&res_address).is_some(), "Route not found");
+
+            self.routes.remove(&res_address);
+        }
+
+        pub fn set_max_slippage(&mut self, max_slippage: Decimal) {
+            BuybackBurn::_assert_valid_slippage(max_slippage);
+
+            self.max_slippage = max_slippage;
+        }
+
+        pub fn set_router(&mut self, router: Global<SwapRouter>) {
+            self.router = router;
+        }
+
+        pub fn set_oracle(&mut self, oracle: Global<PriceOracle>, max_price_age_seconds: i64) {
+            assert!(
+                max_price_age_seconds > 0,
+                "Max price age must be greater than zero"
+            );
+
+            self.oracle = oracle;
+            self.max_price_age_seconds = max_price_age_seconds;
+        }
+
+        /* KEEPER METHODS */
+
+        /// Swap the revenue of a resource for the target token, up to its per-execution cap,
+        /// and burn the output. The swap fails if its output is below the oracle price minus
+        /// the max slippage.
+        pub fn execute(&mut self, res_address: ResourceAddress) {
+            let route = self
+                .routes
+                .get(&res_address)
+                .map(|route| route.clone())
+                .expect("Route not found");
+
+            let input = {
+                let mut vault = self
+                    .revenue
+                    .get_mut(&res_address)
+                    .expect("No revenue for this resource");
+                let amount = vault.amount().min(route.max_amount_per_execution);
+
+                vault.take(amount)
+            };
+
+            let amount_in = input.amount();
+            assert!(amount_in > Decimal::ZERO, "No revenue to buy back");
+
+            let output = if res_address == self.target_res_address {
+                input
+            } else {
+                let price = self.oracle.get_price(
+                    res_address,
+                    self.target_res_address,
+                    self.max_price_age_seconds,
+                );
+                let min_out = amount_in * price * (Decimal::ONE - self.max_slippage);
+
+                self.router.swap_via(input, route.path, min_out)
+            };
+
+            assert!(
+                output.resource_address() == self.target_res_address,
+                "Route does not end with the target token"
+            );
+
+            let amount_burned = output.amount();
+            output.burn();
+
+            self.total_burned += amount_burned;
+
+            Runtime::emit_event(BuybackEvent {
+                res_address,
+                amount_in,
+                amount_burned,
+            });
+        }
+
+        /* PUBLIC METHODS */
+
+        /// Deposit protocol revenue, e.g. fees withdrawn from a pool
+        pub fn deposit_revenue(&mut self, revenue: Bucket) {
+            let res_address = revenue.resource_address();
+
+            if self.revenue.get(&res_address).is_some() {
+                self.revenue.get_mut(&res_address).unwrap().put(revenue);
+            } else {
+                self.revenue
+                    .insert(res_address, Vault::with_bucket(revenue));
+            }
+        }
+
+        /* GETTERS */
+
+        pub fn get_revenue(&self, res_address: ResourceAddress) -> Decimal {
+            self.revenue
+                .get(&res_address)
+                .map_or(Decimal::ZERO, |vault| vault.amount())
+        }
+
+        pub fn get_route(&self, res_address: ResourceAddress) -> Option<BuybackRoute> {
+            self.routes.get(&res_address).map(|route| route.clone())
+        }
+
+        pub fn get_total_burned(&self) -> Decimal {
+            self.total_burned
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_slippage(max_slippage: Decimal) {
+            assert!(
+                max_slippage >= Decimal::ZERO && max_slippage < Decimal::ONE,
+                "Max slippage must be between 0 and 1"
+            );
+        }
+    }
+}
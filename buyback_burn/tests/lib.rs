@@ -0,0 +1,405 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the oracle, the router and the buyback, and provides the pair liquidity
+const OWNER: usize = 0;
+const KEEPER: usize = 1;
+/// Holds 30 USD and 5 target tokens of revenue
+const ALICE: usize = 2;
+
+/// Ledger time of the tests, in seconds, rounded to the minute
+const NOW: i64 = 1_700_000_040;
+
+/// Buyback of a target token only burnable by the buyback blueprint, with a max slippage of
+/// 10%. USD is priced 1 target token by the oracle, and swapped through a router over a
+/// Radiswap pair without fee holding 990 USD and 1000 target tokens. The oracle, router and
+/// pair blueprints are linked into the buyback package, so they are instantiated from the same
+/// package.
+struct TestBuyback {
+    env: TestEnv,
+    buyback: ComponentAddress,
+    oracle: ComponentAddress,
+    pair: ComponentAddress,
+    usd: ResourceAddress,
+    target: ResourceAddress,
+}
+
+impl TestBuyback {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.test_runner
+            .advance_to_round_at_timestamp(Round::of(1), NOW * 1000);
+        let usd = env
+            .test_runner
+            .create_fungible_resource(dec!(1020), 18, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+
+        let manifest = ManifestBuilder::new()
+            .create_fungible_resource(
+                OwnerRole::None,
+                true,
+                18,
+                FungibleResourceRoles {
+                    burn_roles: burn_roles! {
+                        burner => rule!(require(global_caller(BlueprintId::new(
+                            &env.package_address,
+                            "BuybackBurn"
+                        ))));
+                        burner_updater => rule!(deny_all);
+                    },
+                    ..Default::default()
+                },
+                metadata!(),
+                Some(dec!(1005)),
+            )
+            .call_function(
+                env.package_address,
+                "PriceOracle",
+                "instantiate",
+                manifest_args!(
+                    owner_role.clone(),
+                    rule!(require(env.badges[OWNER].clone())),
+                    2u32
+                ),
+            )
+            .call_function(
+                env.package_address,
+                "SwapRouter",
+                "instantiate",
+                manifest_args!(owner_role.clone()),
+            )
+            .deposit_batch(env.accounts[OWNER])
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()]);
+        let commit = receipt.expect_commit_success();
+        let target = commit.new_resource_addresses()[0];
+        let (oracle, router) = (
+            commit.new_component_addresses()[0],
+            commit.new_component_addresses()[1],
+        );
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "RadiswapPair",
+                "instantiate",
+                manifest_args!(usd, target, dec!(0), owner_role.clone()),
+            )
+            .withdraw_from_account(env.accounts[OWNER], usd, dec!(30))
+            .withdraw_from_account(env.accounts[OWNER], target, dec!(5))
+            .deposit_batch(env.accounts[ALICE])
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let pair = receipt.expect_commit_success().new_component_addresses()[0];
+
+        // Manifest encoding of `AmmPool::Radiswap`
+        let pool = ManifestValue::Enum {
+            discriminator: 0,
+            fields: vec![to_manifest_value_and_unwrap!(&pair)],
+        };
+
+        let manifest = ManifestBuilder::new()
+            .call_method(
+                oracle,
+                "push_price",
+                manifest_args!(usd, target, dec!(1), NOW - 60),
+            )
+            .withdraw_from_account(env.accounts[OWNER], usd, dec!(990))
+            .withdraw_from_account(env.accounts[OWNER], target, dec!(1000))
+            .take_all_from_worktop(usd, "bucket_usd")
+            .take_all_from_worktop(target, "bucket_target")
+            .call_method_with_name_lookup(pair, "add_liquidity", |lookup| {
+                (lookup.bucket("bucket_usd"), lookup.bucket("bucket_target"))
+            })
+            .call_method(router, "register_pair", manifest_args!(pool, usd, target))
+            .call_function(
+                env.package_address,
+                "BuybackBurn",
+                "instantiate",
+                manifest_args!(
+                    target,
+                    router,
+                    dec!("0.1"),
+                    oracle,
+                    3600i64,
+                    owner_role,
+                    rule!(require(env.badges[KEEPER].clone()))
+                ),
+            )
+            .deposit_batch(env.accounts[OWNER])
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()]);
+        let buyback = receipt.expect_commit_success().new_component_addresses()[0];
+
+        Self {
+            env,
+            buyback,
+            oracle,
+            pair,
+            usd,
+            target,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.buyback, method, args)
+    }
+
+    fn set_route(
+        &mut self,
+        caller: usize,
+        res_address: ResourceAddress,
+        path: Vec<ComponentAddress>,
+        max_amount_per_execution: Decimal,
+    ) -> TransactionReceipt {
+        self.call(
+            caller,
+            "set_route",
+            manifest_args!(res_address, path, max_amount_per_execution),
+        )
+    }
+
+    /// Deposit the given revenue of Alice
+    fn deposit_revenue(
+        &mut self,
+        res_address: ResourceAddress,
+        amount: Decimal,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[ALICE], res_address, amount)
+            .take_all_from_worktop(res_address, "revenue")
+            .call_method_with_name_lookup(self.buyback, "deposit_revenue", |lookup| {
+                (lookup.bucket("revenue"),)
+            });
+
+        self.env.execute(ALICE, builder)
+    }
+
+    fn execute_buyback(
+        &mut self,
+        caller: usize,
+        res_address: ResourceAddress,
+    ) -> TransactionReceipt {
+        self.call(caller, "execute", manifest_args!(res_address))
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str, args: impl ResolvableArguments) -> T {
+        self.call(OWNER, method, args)
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_revenue_is_bought_back_and_burned() {
+    let mut buyback = TestBuyback::new();
+    let (pair, usd, target) = (buyback.pair, buyback.usd, buyback.target);
+
+    buyback
+        .set_route(OWNER, usd, vec![pair], dec!(10))
+        .expect_commit_success();
+    buyback
+        .set_route(OWNER, target, vec![], dec!(100))
+        .expect_commit_success();
+
+    buyback
+        .deposit_revenue(usd, dec!(30))
+        .expect_commit_success();
+    buyback
+        .deposit_revenue(target, dec!(5))
+        .expect_commit_success();
+    assert_eq!(
+        buyback.get::<Decimal>("get_revenue", manifest_args!(usd)),
+        dec!(30)
+    );
+
+    buyback.execute_buyback(ALICE, usd).expect_commit_failure();
+
+    // 10 USD are swapped for 10 target tokens, above the oracle minimum of 9
+    buyback.execute_buyback(KEEPER, usd).expect_commit_success();
+    assert_eq!(
+        buyback.get::<Decimal>("get_revenue", manifest_args!(usd)),
+        dec!(20)
+    );
+    assert_eq!(
+        buyback.get::<Decimal>("get_total_burned", manifest_args!()),
+        dec!(10)
+    );
+
+    // The target token revenue is burned without swap, by the owner
+    buyback
+        .execute_buyback(OWNER, target)
+        .expect_commit_success();
+    assert_eq!(
+        buyback.get::<Decimal>("get_total_burned", manifest_args!()),
+        dec!(15)
+    );
+    buyback
+        .execute_buyback(KEEPER, target)
+        .expect_commit_failure();
+
+    // At a price of 2 target tokens, the swap output is below the oracle minimum of 18
+    let builder = ManifestBuilder::new().call_method(
+        buyback.oracle,
+        "push_price",
+        manifest_args!(usd, target, dec!(2), NOW),
+    );
+    buyback.env.execute(OWNER, builder).expect_commit_success();
+    buyback.execute_buyback(KEEPER, usd).expect_commit_failure();
+    assert_eq!(
+        buyback.get::<Decimal>("get_revenue", manifest_args!(usd)),
+        dec!(20)
+    );
+}
+
+#[test]
+fn test_routes_and_slippage_are_checked() {
+    let mut buyback = TestBuyback::new();
+    let (pair, usd, target) = (buyback.pair, buyback.usd, buyback.target);
+
+    buyback.execute_buyback(KEEPER, usd).expect_commit_failure();
+
+    buyback
+        .set_route(ALICE, usd, vec![pair], dec!(10))
+        .expect_commit_failure();
+    buyback
+        .set_route(OWNER, usd, vec![], dec!(10))
+        .expect_commit_failure();
+    buyback
+        .set_route(OWNER, target, vec![pair], dec!(10))
+        .expect_commit_failure();
+    buyback
+        .set_route(OWNER, usd, vec![pair], dec!(0))
+        .expect_commit_failure();
+    buyback
+        .set_route(OWNER, usd, vec![pair], dec!(10))
+        .expect_commit_success();
+
+    let route: Option<(Vec<ComponentAddress>, Decimal)> =
+        buyback.get("get_route", manifest_args!(usd));
+    assert_eq!(route, Some((vec![pair], dec!(10))));
+
+    // No revenue has been deposited yet
+    buyback.execute_buyback(KEEPER, usd).expect_commit_failure();
+
+    buyback
+        .call(OWNER, "set_max_slippage", manifest_args!(dec!(1)))
+        .expect_commit_failure();
+    buyback
+        .call(OWNER, "set_max_slippage", manifest_args!(dec!("0.5")))
+        .expect_commit_success();
+
+    buyback
+        .call(OWNER, "remove_route", manifest_args!(usd))
+        .expect_commit_success();
+    buyback
+        .call(OWNER, "remove_route", manifest_args!(usd))
+        .expect_commit_failure();
+
+    // The revenue is kept without route
+    buyback
+        .deposit_revenue(usd, dec!(10))
+        .expect_commit_success();
+    buyback.execute_buyback(KEEPER, usd).expect_commit_failure();
+    assert_eq!(
+        buyback.get::<Decimal>("get_revenue", manifest_args!(usd)),
+        dec!(10)
+    );
+}
+
+#[test]
+fn test_stale_oracle_price_blocks_the_buyback() {
+    let mut buyback = TestBuyback::new();
+    let (oracle, pair, usd) = (buyback.oracle, buyback.pair, buyback.usd);
+
+    buyback
+        .set_route(OWNER, usd, vec![pair], dec!(10))
+        .expect_commit_success();
+    buyback
+        .deposit_revenue(usd, dec!(30))
+        .expect_commit_success();
+
+    // The price pushed a minute before the start is 3660 seconds old
+    buyback
+        .env
+        .test_runner
+        .advance_to_round_at_timestamp(Round::of(2), (NOW + 3600) * 1000);
+    expect_failure_containing(buyback.execute_buyback(KEEPER, usd), "Price is stale");
+    assert_eq!(
+        buyback.get::<Decimal>("get_revenue", manifest_args!(usd)),
+        dec!(30)
+    );
+
+    expect_failure_containing(
+        buyback.call(OWNER, "set_oracle", manifest_args!(oracle, 0i64)),
+        "Max price age must be greater than zero",
+    );
+    buyback
+        .call(OWNER, "set_oracle", manifest_args!(oracle, 7200i64))
+        .expect_commit_success();
+    buyback.execute_buyback(KEEPER, usd).expect_commit_success();
+    assert_eq!(
+        buyback.get::<Decimal>("get_revenue", manifest_args!(usd)),
+        dec!(20)
+    );
+}
+
+#[test]
+fn test_failures_carry_buyback_messages() {
+    let mut buyback = TestBuyback::new();
+    let (pair, usd, target) = (buyback.pair, buyback.usd, buyback.target);
+
+    expect_failure_containing(buyback.execute_buyback(KEEPER, usd), "Route not found");
+    expect_failure_containing(
+        buyback.call(OWNER, "remove_route", manifest_args!(usd)),
+        "Route not found",
+    );
+    expect_failure_containing(
+        buyback.set_route(OWNER, usd, vec![], dec!(10)),
+        "Only revenue in the target token has an empty path",
+    );
+    expect_failure_containing(
+        buyback.set_route(OWNER, target, vec![], dec!(-1)),
+        "Max amount per execution must be greater than zero",
+    );
+    expect_failure_containing(
+        buyback.call(OWNER, "set_max_slippage", manifest_args!(dec!(-1))),
+        "Max slippage must be between 0 and 1",
+    );
+
+    buyback
+        .set_route(OWNER, usd, vec![pair], dec!(10))
+        .expect_commit_success();
+    expect_failure_containing(
+        buyback.execute_buyback(KEEPER, usd),
+        "No revenue for this resource",
+    );
+
+    // The revenue is exactly the cap, so the second execution finds an empty vault
+    buyback
+        .deposit_revenue(usd, dec!(10))
+        .expect_commit_success();
+    buyback.execute_buyback(KEEPER, usd).expect_commit_success();
+    expect_failure_containing(
+        buyback.execute_buyback(KEEPER, usd),
+        "No revenue to buy back",
+    );
+    assert_eq!(
+        buyback.get::<Decimal>("get_total_burned", manifest_args!()),
+        dec!(10)
+    );
+}
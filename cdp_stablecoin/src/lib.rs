@@ -0,0 +1,642 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use liquidation_auction::liquidation_auction::LiquidationAuction;
+use price_oracle::price_oracle::PriceOracle;
+use scrypto::prelude::*;
+
+/// Start price of a liquidation auction, as a share of the oracle price
+pub const AUCTION_START_PRICE_RATIO: Decimal = dec!("1.2");
+
+#[derive(ScryptoSbor, Clone)]
+pub struct CollateralType {
+    /// Minimum collateral value to debt ratio, below which a vault can be liquidated
+    pub min_collateral_ratio: Decimal,
+
+    /// Linear fee applied to the debt every epoch
+    pub stability_fee_per_epoch: Decimal,
+
+    /// Share of the debt added as a penalty when a vault is liquidated
+    pub liquidation_penalty: Decimal,
+
+    /// Maximum debt backed by this collateral
+    pub debt_ceiling: Decimal,
+
+    /// Growth of one unit of debt since the collateral type was added
+    pub debt_index: Decimal,
+
+    /// Sum of the normalized debts of all vaults of this collateral type
+    pub total_normalized_debt: Decimal,
+
+    pub last_accrual_epoch: u64,
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct CdpReceipt {
+    pub collateral_res_address: ResourceAddress,
+}
+
+#[derive(ScryptoSbor)]
+pub struct Cdp {
+    pub collateral: Vault,
+
+    /// Debt of the vault divided by the debt index of its collateral type
+    pub normalized_debt: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CdpLiquidatedEvent {
+    pub cdp_id: NonFungibleLocalId,
+    pub auction_id: u64,
+    pub collateral_amount: Decimal,
+    pub debt_amount: Decimal,
+}
+
+#[blueprint]
+#[events(CdpLiquidatedEvent)]
+pub mod cdp_stablecoin {
+
+    enable_method_auth! {
+        methods {
+
+            add_collateral_type => restrict_to :[OWNER];
+            update_collateral_type => restrict_to :[OWNER];
+            set_oracle => restrict_to :[OWNER];
+            collect_fees => restrict_to :[OWNER];
+
+            open_cdp => PUBLIC;
+            deposit_collateral => PUBLIC;
+            withdraw_collateral => PUBLIC;
+            mint => PUBLIC;
+            repay => PUBLIC;
+            liquidate => PUBLIC;
+            settle_auction_proceeds => PUBLIC;
+
+            accrue_fees => PUBLIC;
+
+            get_collateral_type => PUBLIC;
+            get_cdp => PUBLIC;
+            get_collateral_ratio => PUBLIC;
+
+        }
+    }
+
+    pub struct CdpStablecoin {
+        /// Stable token fungible resource manager
+        stable_res_manager: ResourceManager,
+
+        /// Parameters and debt accounting, indexed by collateral resource
+        collateral_types: IndexMap<ResourceAddress, CollateralType>,
+
+        /// Collateral debt position receipt non-fungible resource manager
+        cdp_res_manager: ResourceManager,
+        cdps: KeyValueStore<NonFungibleLocalId, Cdp>,
+
+        /// Oracle pricing collateral in the quote resource, the stable token peg
+        oracle: Global<PriceOracle>,
+        quote_res_address: ResourceAddress,
+        max_price_age_seconds: i64,
+
+        /// Auction engine selling the collateral of liquidated vaults
+        auction: Global<LiquidationAuction>,
+
+        /// Debt of liquidated vaults not covered by auction proceeds yet
+        debt_in_auction: Decimal,
+
+        /// Stability fees accrued and not minted yet
+        accrued_fees: Decimal,
+    }
+
+    impl CdpStablecoin {
+        /// Instantiate the CDP engine. The auction engine must grant the market role to the
+        /// returned component.
+        pub fn instantiate(
+            oracle: Global<PriceOracle>,
+            quote_res_address: ResourceAddress,
+            max_price_age_seconds: i64,
+            auction: Global<LiquidationAuction>,
+            owner_role: OwnerRole,
+        ) -> (Global<CdpStablecoin>, ResourceAddress, ResourceAddress) {
+            /* CHECK INPUT */
+            assert!(
+                max_price_age_seconds > 0,
+                "Max price age must be greater than zero"
+            );
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(CdpStablecoin::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let stable_res_manager = ResourceBuilder::new_fungible(owner_role.clone())
+                .mint_roles(mint_roles! {
+                    minter => component_rule.clone();
+                    minter_updater => rule!(deny_all);
+                })
+                .burn_roles(burn_roles! {
+                    burner => component_rule.clone();
+                    burner_updater => rule!(deny_all);
+                })
+                .create_with_no_initial_supply();
+
+            let cdp_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<CdpReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule;
+                        minter_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let cdp_component = Self {
+                stable_res_manager,
+                collateral_types: IndexMap::default(),
+                cdp_res_manager,
+                cdps: KeyValueStore::new(),
+                oracle,
+                quote_res_address,
+                max_price_age_seconds,
+                auction,
+                debt_in_auction: Decimal::ZERO,
+                accrued_fees: Decimal::ZERO,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (
+                cdp_component,
+                stable_res_manager.address(),
+                cdp_res_manager.address(),
+            )
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn add_collateral_type(
+            &mut self,
+            collateral_res_address: ResourceAddress,
+            min_collateral_ratio: Decimal,
+            stability_fee_per_epoch: Decimal,
+            liquidation_penalty: Decimal,
+            debt_ceiling: Decimal,
+        ) {
+            /* CHECK INPUT */
+            assert!(
+                !self.collateral_types.contains_key(&collateral_res_address),
+                "Collateral type already added"
+            );
+            assert!(
+                collateral_res_address != self.stable_res_manager.address(),
+                "Stable token can not be used as collateral"
+            );
+            CdpStablecoin::_assert_valid_parameters(
+                min_collateral_ratio,
+                stability_fee_per_epoch,
+                liquidation_penalty,
+                debt_ceiling,
+            );
+
+            self.collateral_types.insert(
+                collateral_res_address,
+                CollateralType {
+                    min_collateral_ratio,
+                    stability_fee_per_epoch,
+                    liquidation_penalty,
+                    debt_ceiling,
+                    debt_index: Decimal::ONE,
+                    total_normalized_debt: Decimal::ZERO,
+                    last_accrual_epoch: Runtime::current_epoch().number(),
+                },
+            );
+        }
+
+        pub fn update_collateral_type(
+            &mut self,
+            collateral_res_address: ResourceAddress,
+            min_collateral_ratio: Decimal,
+            stability_fee_per_epoch: Decimal,
+            liquidation_penalty: Decimal,
+            debt_ceiling: Decimal,
+        ) {
+            /* CHECK INPUT */
+            CdpStablecoin::_assert_valid_parameters(
+                min_collateral_ratio,
+                stability_fee_per_epoch,
+                liquidation_penalty,
+                debt_ceiling,
+            );
+
+            self.accrue_fees(collateral_res_address);
+
+            let collateral_type = self._get_collateral_type_mut(&collateral_res_address);
+
+            collateral_type.min_collateral_ratio = min_collateral_ratio;
+            collateral_type.stability_fee_per_epoch = stability_fee_per_epoch;
+            collateral_type.liquidation_penalty = liquidation_penalty;
+            collateral_type.debt_ceiling = debt_ceiling;
+        }
+
+        pub fn set_oracle(
+            &mut self,
+            oracle: Global<PriceOracle>,
+            quote_res_address: ResourceAddress,
+            max_price_age_seconds: i64,
+        ) {
+            assert!(
+                max_price_age_seconds > 0,
+                "Max price age must be greater than zero"
+            );
+
+            self.oracle = oracle;
+            self.quote_res_address = quote_res_address;
+            self.max_price_age_seconds = max_price_age_seconds;
+        }
+
+        /// Mint the stability fees accrued so far
+        pub fn collect_fees(&mut self) -> Bucket {
+            self._accrue_all_fees();
+
+            let fees = self.accrued_fees;
+            self.accrued_fees = Decimal::ZERO;
+
+            self.stable_res_manager.mint(fees)
+        }
+
+        /* USER METHODS */
+
+        /// Open a collateral debt position with the given collateral. Returns the position
+        /// receipt.
+        pub fn open_cdp(&mut self, collateral: Bucket) -> Bucket {
+            let collateral_res_address = collateral.resource_address();
+
+            /* CHECK INPUT */
+            self._get_collateral_type(&collateral_res_address);
+
+            let receipt = self.cdp_res_manager.mint_ruid_non_fungible(CdpReceipt {
+                collateral_res_address,
+            });
+
+            self.cdps.insert(
+                receipt.as_non_fungible().non_fungible_local_id(),
+                Cdp {
+                    collateral: Vault::with_bucket(collateral),
+                    normalized_debt: Decimal::ZERO,
+                },
+            );
+
+            receipt
+        }
+
+        pub fn deposit_collateral(&mut self, cdp_proof: Proof, collateral: Bucket) {
+            let cdp_id = self._check_cdp_proof(cdp_proof);
+
+            self.cdps
+                .get_mut(&cdp_id)
+                .unwrap()
+                .collateral
+                .put(collateral);
+        }
+
+        pub fn withdraw_collateral(&mut self, cdp_proof: Proof, amount: Decimal) -> Bucket {
+            let cdp_id = self._check_cdp_proof(cdp_proof);
+
+            /* INPUT CHECK */
+            assert!(amount > 0.into(), "Amount must be greater than zero!");
+
+            let collateral_res_address = self._get_collateral_res_address(&cdp_id);
+            self.accrue_fees(collateral_res_address);
+
+            let collateral = self
+                .cdps
+                .get_mut(&cdp_id)
+                .unwrap()
+                .collateral
+                .take_advanced(amount, WithdrawStrategy::Rounded(RoundingMode::ToZero));
+
+            self._assert_safe(&cdp_id, collateral_res_address);
+
+            collateral
+        }
+
+        /// Mint stable tokens against the collateral of a position
+        pub fn mint(&mut self, cdp_proof: Proof, amount: Decimal) -> Bucket {
+            let cdp_id = self._check_cdp_proof(cdp_proof);
+
+            /* INPUT CHECK */
+            assert!(amount > 0.into(), "Amount must be greater than zero!");
+
+            let collateral_res_address = self._get_collateral_res_address(&cdp_id);
+            self.accrue_fees(collateral_res_address);
+
+            let collateral_type = self._get_collateral_type_mut(&collateral_res_address);
+            let normalized_amount = amount / collateral_type.debt_index;
+            collateral_type.total_normalized_debt += normalized_amount;
+
+            assert!(
+                collateral_type.total_normalized_debt * collateral_type.debt_index
+                    <= collateral_type.debt_ceiling,
+                "Debt ceiling reached"
+            );
+
+            self.cdps.get_mut(&cdp_id).unwrap().normalized_debt += normalized_amount;
+
+            self._assert_safe(&cdp_id, collateral_res_address);
+
+            self.stable_res_manager.mint(amount)
+        }
+
+        /// Burn stable tokens to repay part or all of the debt of a position. Returns the
+        /// payment change.
+        pub fn repay(&mut self, cdp_proof: Proof, mut payment: Bucket) -> Bucket {
+            let cdp_id = self._check_cdp_proof(cdp_proof);
+
+            /* INPUT CHECK */
+            assert!(
+                payment.resource_address() == self.stable_res_manager.address(),
+                "Stable token resource address mismatch"
+            );
+
+            let collateral_res_address = self._get_collateral_res_address(&cdp_id);
+            self.accrue_fees(collateral_res_address);
+
+            let debt_index = self
+                ._get_collateral_type(&collateral_res_address)
+                .debt_index;
+
+            let mut cdp = self.cdps.get_mut(&cdp_id).unwrap();
+
+            let debt_amount = cdp.normalized_debt * debt_index;
+            let repay_amount = payment.amount().min(debt_amount);
+
+            let normalized_repay_amount = if repay_amount == debt_amount {
+                cdp.normalized_debt
+            } else {
+                repay_amount / debt_index
+            };
+
+            cdp.normalized_debt -= normalized_repay_amount;
+
+            drop(cdp);
+
+            self._get_collateral_type_mut(&collateral_res_address)
+                .total_normalized_debt -= normalized_repay_amount;
+
+            payment
+                .take_advanced(
+                    repay_amount,
+                    WithdrawStrategy::Rounded(RoundingMode::AwayFromZero),
+                )
+                .burn();
+
+            payment
+        }
+
+        /// Seize the collateral of an undercollateralized position and sell it through the
+        /// auction engine to cover its debt plus the liquidation penalty. Returns the auction
+        /// id.
+        pub fn liquidate(&mut self, cdp_id: NonFungibleLocalId) -> u64 {
+            let collateral_res_address = self._get_collateral_res_address(&cdp_id);
+            self.accrue_fees(collateral_res_address);
+
+            let (collateral_ratio, _) = self._get_cdp_health(&cdp_id, collateral_res_address);
+            let collateral_type = self._get_collateral_type(&collateral_res_address).clone();
+
+            assert!(
+                collateral_ratio < collateral_type.min_collateral_ratio,
+                "Position is sufficiently collateralized"
+            );
+
+            let mut cdp = self.cdps.get_mut(&cdp_id).unwrap();
+
+            let normalized_debt = cdp.normalized_debt;
+            let debt_amount = normalized_debt * collateral_type.debt_index;
+            let collateral = cdp.collateral.take_all();
+            cdp.normalized_debt = Decimal::ZERO;
+
+            drop(cdp);
+
+            self._get_collateral_type_mut(&collateral_res_address)
+                .total_normalized_debt -= normalized_debt;
+
+            let auction_debt = debt_amount * (Decimal::ONE + collateral_type.liquidation_penalty);
+            self.debt_in_auction += auction_debt;
+
+            let collateral_amount = collateral.amount();
+            let start_price = self._get_price(collateral_res_address) * AUCTION_START_PRICE_RATIO;
+
+            let auction_id = self.auction.start_auction(
+                collateral,
+                self.stable_res_manager.address(),
+                auction_debt,
+                start_price,
+                NonFungibleGlobalId::new(self.cdp_res_manager.address(), cdp_id.clone()),
+            );
+
+            Runtime::emit_event(CdpLiquidatedEvent {
+                cdp_id,
+                auction_id,
+                collateral_amount,
+                debt_amount: auction_debt,
+            });
+
+            auction_id
+        }
+
+        /// Burn the stable tokens raised by the liquidation auctions
+        pub fn settle_auction_proceeds(&mut self) {
+            let proceeds = self
+                .auction
+                .withdraw_proceeds(self.stable_res_manager.address());
+
+            self.debt_in_auction = (self.debt_in_auction - proceeds.amount()).max(Decimal::ZERO);
+
+            proceeds.burn();
+        }
+
+        /// Accrue the stability fees of a collateral type since the last accrual
+        pub fn accrue_fees(&mut self, collateral_res_address: ResourceAddress) {
+            let current_epoch = Runtime::current_epoch().number();
+
+            let collateral_type = self._get_collateral_type_mut(&collateral_res_address);
+
+            let elapsed_epochs = current_epoch - collateral_type.last_accrual_epoch;
+            if elapsed_epochs == 0 {
+                return;
+            }
+
+            let fee_factor = collateral_type.stability_fee_per_epoch * elapsed_epochs;
+            let fees =
+                collateral_type.total_normalized_debt * collateral_type.debt_index * fee_factor;
+
+            collateral_type.debt_index *= Decimal::ONE + fee_factor;
+            collateral_type.last_accrual_epoch = current_epoch;
+
+            self.accrued_fees += fees;
+        }
+
+        /* GETTERS */
+
+        pub fn get_collateral_type(
+            &self,
+            collateral_res_address: ResourceAddress,
+        ) -> CollateralType {
+            self._get_collateral_type(&collateral_res_address).clone()
+        }
+
+        /// Collateral amount and debt amount of a position, using the last accrued debt index
+        pub fn get_cdp(&self, cdp_id: NonFungibleLocalId) -> (Decimal, Decimal) {
+            let collateral_res_address = self._get_collateral_res_address(&cdp_id);
+            let debt_index = self
+                ._get_collateral_type(&collateral_res_address)
+                .debt_index;
+
+            let cdp = self.cdps.get(&cdp_id).expect("Position not found");
+
+            (cdp.collateral.amount(), cdp.normalized_debt * debt_index)
+        }
+
+        /// Collateral value to debt ratio of a position, using the last accrued debt index
+        pub fn get_collateral_ratio(&self, cdp_id: NonFungibleLocalId) -> Decimal {
+            let collateral_res_address = self._get_collateral_res_address(&cdp_id);
+
+            self._get_cdp_health(&cdp_id, collateral_res_address).0
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_parameters(
+            min_collateral_ratio: Decimal,
+            stability_fee_per_epoch: Decimal,
+            liquidation_penalty: Decimal,
+            debt_ceiling: Decimal,
+        ) {
+            assert!(
+                min_collateral_ratio > Decimal::ONE,
+                "Min collateral ratio must be greater than 1"
+            );
+            assert!(
+                stability_fee_per_epoch >= Decimal::ZERO,
+                "Stability fee must not be negative"
+            );
+            assert!(
+                liquidation_penalty >= Decimal::ZERO,
+                "Liquidation penalty must not be negative"
+            );
+            assert!(
+                debt_ceiling >= Decimal::ZERO,
+                "Debt ceiling must not be negative"
+            );
+        }
+
+        fn _accrue_all_fees(&mut self) {
+            let collateral_res_addresses: Vec<ResourceAddress> =
+                self.collateral_types.keys().cloned().collect();
+
+            for collateral_res_address in collateral_res_addresses {
+                self.accrue_fees(collateral_res_address);
+            }
+        }
+
+        fn _assert_safe(
+            &self,
+            cdp_id: &NonFungibleLocalId,
+            collateral_res_address: ResourceAddress,
+        ) {
+            let (collateral_ratio, debt_amount) =
+                self._get_cdp_health(cdp_id, collateral_res_address);
+
+            assert!(
+                debt_amount == Decimal::ZERO
+                    || collateral_ratio
+                        >= self
+                            ._get_collateral_type(&collateral_res_address)
+                            .min_collateral_ratio,
+                "Position would be undercollateralized"
+            );
+        }
+
+        /// Returns the collateral ratio and the debt amount of a position. The ratio is
+        /// `Decimal::MAX` when the position has no debt.
+        fn _get_cdp_health(
+            &self,
+            cdp_id: &NonFungibleLocalId,
+            collateral_res_address: ResourceAddress,
+        ) -> (Decimal, Decimal) {
+            let debt_index = self
+                ._get_collateral_type(&collateral_res_address)
+                .debt_index;
+
+            let cdp = self.cdps.get(cdp_id).expect("Position not found");
+            let debt_amount = cdp.normalized_debt * debt_index;
+
+            if debt_amount == Decimal::ZERO {
+                return (Decimal::MAX, debt_amount);
+            }
+
+            let collateral_value =
+                cdp.collateral.amount() * self._get_price(collateral_res_address);
+
+            (collateral_value / debt_amount, debt_amount)
+        }
+
+        fn _get_price(&self, collateral_res_address: ResourceAddress) -> Decimal {
+            self.oracle.get_price(
+                collateral_res_address,
+                self.quote_res_address,
+                self.max_price_age_seconds,
+            )
+        }
+
+        fn _check_cdp_proof(&self, cdp_proof: Proof) -> NonFungibleLocalId {
+            cdp_proof
+                .check(self.cdp_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+
+        fn _get_collateral_res_address(&self, cdp_id: &NonFungibleLocalId) -> ResourceAddress {
+            self.cdp_res_manager
+                .get_non_fungible_data::<CdpReceipt>(cdp_id)
+                .collateral_res_address
+        }
+
+        fn _get_collateral_type(
+            &self,
+            collateral_res_address: &ResourceAddress,
+        ) -> &CollateralType {
+            self.collateral_types
+                .get(collateral_res_address)
+                .expect("Collateral type not found")
+        }
+
+        fn _get_collateral_type_mut(
+            &mut self,
+            collateral_res_address: &ResourceAddress,
+        ) -> &mut CollateralType {
+            self.collateral_types
+                .get_mut(collateral_res_address)
+                .expect("Collateral type not found")
+        }
+    }
+}
@@ -0,0 +1,385 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the engine, the auction engine and feeds the oracle
+const OWNER: usize = 0;
+/// Holds 1000 collateral tokens
+const ALICE: usize = 1;
+/// Holds 1000 collateral tokens, and bids in liquidation auctions
+const KEEPER: usize = 2;
+
+/// Ledger time of the tests, in seconds, rounded to the minute
+const NOW: i64 = 1_700_000_040;
+
+/// Engine accepting a collateral priced 2 by the oracle, with a min collateral ratio of 150%,
+/// a stability fee of 1% per epoch, a liquidation penalty of 10% and a debt ceiling of 1000,
+/// at epoch 10. Liquidations are sold by auctions decaying to 50% of their start price over
+/// 10 epochs. The oracle and auction blueprints are linked into the engine package, so they
+/// are instantiated from the same package.
+struct TestEngine {
+    env: TestEnv,
+    engine: ComponentAddress,
+    stable: ResourceAddress,
+    cdp_receipt: ResourceAddress,
+    oracle: ComponentAddress,
+    auction: ComponentAddress,
+    collateral: ResourceAddress,
+    quote: ResourceAddress,
+}
+
+impl TestEngine {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        env.test_runner
+            .advance_to_round_at_timestamp(Round::of(1), NOW * 1000);
+        let collateral =
+            env.test_runner
+                .create_fungible_resource(dec!(2000), 18, env.accounts[ALICE]);
+        let quote = env
+            .test_runner
+            .create_fungible_resource(dec!(1), 18, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "PriceOracle",
+                "instantiate",
+                manifest_args!(
+                    owner_role.clone(),
+                    rule!(require(env.badges[OWNER].clone())),
+                    2u32
+                ),
+            )
+            .call_function(
+                env.package_address,
+                "LiquidationAuction",
+                "instantiate",
+                manifest_args!(
+                    owner_role.clone(),
+                    rule!(deny_all),
+                    10u64,
+                    dec!("0.5"),
+                    quote,
+                    dec!(0)
+                ),
+            )
+            .withdraw_from_account(env.accounts[ALICE], collateral, dec!(1000))
+            .take_all_from_worktop(collateral, "keeper")
+            .call_method_with_name_lookup(env.accounts[KEEPER], "deposit", |lookup| {
+                (lookup.bucket("keeper"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+        let (oracle, auction) = (
+            commit.new_component_addresses()[0],
+            commit.new_component_addresses()[1],
+        );
+
+        let manifest = ManifestBuilder::new()
+            .call_method(
+                oracle,
+                "push_price",
+                manifest_args!(collateral, quote, dec!(2), NOW - 60),
+            )
+            .call_function(
+                env.package_address,
+                "CdpStablecoin",
+                "instantiate",
+                manifest_args!(oracle, quote, 3600i64, auction, owner_role),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()]);
+        let (engine, stable, cdp_receipt): (ComponentAddress, ResourceAddress, ResourceAddress) =
+            receipt.expect_commit_success().output(1);
+
+        let mut engine = Self {
+            env,
+            engine,
+            stable,
+            cdp_receipt,
+            oracle,
+            auction,
+            collateral,
+            quote,
+        };
+
+        let builder = ManifestBuilder::new().set_role(
+            auction,
+            ObjectModuleId::Main,
+            RoleKey::new("market"),
+            rule!(require(global_caller(engine.engine))),
+        );
+        engine.env.execute(OWNER, builder).expect_commit_success();
+        engine
+            .add_collateral_type(OWNER, collateral, dec!("1.5"))
+            .expect_commit_success();
+
+        engine
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.engine, method, args)
+    }
+
+    /// Add a collateral type with a stability fee of 1% per epoch, a liquidation penalty of
+    /// 10% and a debt ceiling of 1000
+    fn add_collateral_type(
+        &mut self,
+        caller: usize,
+        collateral_res_address: ResourceAddress,
+        min_collateral_ratio: Decimal,
+    ) -> TransactionReceipt {
+        self.call(
+            caller,
+            "add_collateral_type",
+            manifest_args!(
+                collateral_res_address,
+                min_collateral_ratio,
+                dec!("0.01"),
+                dec!("0.1"),
+                dec!(1000)
+            ),
+        )
+    }
+
+    fn open_cdp(&mut self, account: usize, amount: Decimal) -> TransactionReceipt {
+        let collateral = self.collateral;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[account], collateral, amount)
+            .take_all_from_worktop(collateral, "collateral")
+            .call_method_with_name_lookup(self.engine, "open_cdp", |lookup| {
+                (lookup.bucket("collateral"),)
+            });
+
+        self.env.execute(account, builder)
+    }
+
+    /// Id of the only position of the account
+    fn cdp_id(&mut self, account: usize) -> NonFungibleLocalId {
+        let vault_id = self
+            .env
+            .test_runner
+            .get_component_vaults(self.env.accounts[account], self.cdp_receipt)[0];
+        let (_, mut ids) = self
+            .env
+            .test_runner
+            .inspect_non_fungible_vault(vault_id)
+            .unwrap();
+
+        ids.next().unwrap()
+    }
+
+    /// Call a method with a proof of the position of the account, prepending the manifest
+    /// of the builder
+    fn call_with_cdp<A: ResolvableArguments>(
+        &mut self,
+        account: usize,
+        builder: ManifestBuilder,
+        method: &str,
+        args: impl FnOnce(&ManifestNameLookup) -> A,
+    ) -> TransactionReceipt {
+        let builder = builder
+            .create_proof_from_account_of_amount(
+                self.env.accounts[account],
+                self.cdp_receipt,
+                dec!(1),
+            )
+            .pop_from_auth_zone("cdp")
+            .call_method_with_name_lookup(self.engine, method, |lookup| args(lookup));
+
+        self.env.execute(account, builder)
+    }
+
+    fn mint(&mut self, account: usize, amount: Decimal) -> TransactionReceipt {
+        self.call_with_cdp(account, ManifestBuilder::new(), "mint", |lookup| {
+            (lookup.proof("cdp"), amount)
+        })
+    }
+
+    fn withdraw_collateral(&mut self, account: usize, amount: Decimal) -> TransactionReceipt {
+        self.call_with_cdp(
+            account,
+            ManifestBuilder::new(),
+            "withdraw_collateral",
+            |lookup| (lookup.proof("cdp"), amount),
+        )
+    }
+
+    fn repay(&mut self, account: usize, amount: Decimal) -> TransactionReceipt {
+        let stable = self.stable;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[account], stable, amount)
+            .take_all_from_worktop(stable, "payment");
+
+        self.call_with_cdp(account, builder, "repay", |lookup| {
+            (lookup.proof("cdp"), lookup.bucket("payment"))
+        })
+    }
+
+    fn get_cdp(&mut self, account: usize) -> (Decimal, Decimal) {
+        let cdp_id = self.cdp_id(account);
+
+        self.call(OWNER, "get_cdp", manifest_args!(cdp_id))
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_mint_and_repay_with_stability_fees() {
+    let mut engine = TestEngine::new();
+    let (stable, collateral) = (engine.stable, engine.collateral);
+
+    // 150 collateral are worth 300, backing up to 200 stable tokens
+    engine.open_cdp(ALICE, dec!(150)).expect_commit_success();
+    engine.mint(ALICE, dec!(200)).expect_commit_success();
+    engine.mint(ALICE, dec!(1)).expect_commit_failure();
+    engine
+        .withdraw_collateral(ALICE, dec!(1))
+        .expect_commit_failure();
+    assert_eq!(engine.env.balance(ALICE, stable), dec!(200));
+
+    engine.repay(ALICE, dec!(100)).expect_commit_success();
+    engine
+        .withdraw_collateral(ALICE, dec!(75))
+        .expect_commit_success();
+    engine
+        .withdraw_collateral(ALICE, dec!(1))
+        .expect_commit_failure();
+    assert_eq!(engine.env.balance(ALICE, collateral), dec!(925));
+
+    engine.env.set_epoch(20);
+    engine
+        .call(ALICE, "accrue_fees", manifest_args!(collateral))
+        .expect_commit_success();
+    assert_eq!(engine.get_cdp(ALICE), (dec!(75), dec!(110)));
+
+    engine
+        .call(ALICE, "collect_fees", manifest_args!())
+        .expect_commit_failure();
+    engine
+        .call(OWNER, "collect_fees", manifest_args!())
+        .expect_commit_success();
+    assert_eq!(engine.env.balance(OWNER, stable), dec!(10));
+
+    // The remaining debt still locks the collateral
+    engine.repay(ALICE, dec!(100)).expect_commit_success();
+    assert_eq!(engine.env.balance(ALICE, stable), dec!(0));
+    engine
+        .withdraw_collateral(ALICE, dec!(75))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_undercollateralized_position_is_auctioned() {
+    let mut engine = TestEngine::new();
+    let (oracle, auction) = (engine.oracle, engine.auction);
+    let (stable, collateral, quote) = (engine.stable, engine.collateral, engine.quote);
+
+    engine.open_cdp(ALICE, dec!(150)).expect_commit_success();
+    engine.mint(ALICE, dec!(200)).expect_commit_success();
+    engine.open_cdp(KEEPER, dec!(1000)).expect_commit_success();
+    engine.mint(KEEPER, dec!(500)).expect_commit_success();
+
+    let alice_cdp = engine.cdp_id(ALICE);
+    engine
+        .call(KEEPER, "liquidate", manifest_args!(alice_cdp.clone()))
+        .expect_commit_failure();
+
+    // 150 collateral are now worth 270, below 150% of the debt
+    let builder = ManifestBuilder::new().call_method(
+        oracle,
+        "push_price",
+        manifest_args!(collateral, quote, dec!("1.8"), NOW),
+    );
+    engine.env.execute(OWNER, builder).expect_commit_success();
+
+    let auction_id: u64 = engine
+        .call(KEEPER, "liquidate", manifest_args!(alice_cdp.clone()))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(engine.get_cdp(ALICE), (dec!(0), dec!(0)));
+
+    // The auction sells the collateral for the debt and the penalty, starting at 120% of the
+    // oracle price
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(engine.env.accounts[KEEPER], stable, dec!(300))
+        .take_all_from_worktop(stable, "payment")
+        .call_method_with_name_lookup(auction, "bid", |lookup| {
+            (auction_id, lookup.bucket("payment"), dec!("2.16"))
+        });
+    engine.env.execute(KEEPER, builder).expect_commit_success();
+    assert_eq!(engine.env.balance(KEEPER, stable), dec!(280));
+
+    engine
+        .call(KEEPER, "settle_auction_proceeds", manifest_args!())
+        .expect_commit_success();
+
+    let builder = ManifestBuilder::new()
+        .create_proof_from_account_of_amount(
+            engine.env.accounts[ALICE],
+            engine.cdp_receipt,
+            dec!(1),
+        )
+        .pop_from_auth_zone("cdp")
+        .call_method_with_name_lookup(auction, "claim_surplus", |lookup| {
+            (auction_id, lookup.proof("cdp"))
+        });
+    engine.env.execute(ALICE, builder).expect_commit_success();
+    assert!(engine.env.balance(ALICE, collateral) > dec!(850));
+}
+
+#[test]
+fn test_collateral_types_are_managed_by_the_owner() {
+    let mut engine = TestEngine::new();
+    let (stable, collateral, quote) = (engine.stable, engine.collateral, engine.quote);
+
+    engine
+        .add_collateral_type(ALICE, quote, dec!("1.5"))
+        .expect_commit_failure();
+    engine
+        .add_collateral_type(OWNER, quote, dec!(1))
+        .expect_commit_failure();
+    engine
+        .add_collateral_type(OWNER, stable, dec!("1.5"))
+        .expect_commit_failure();
+    engine
+        .add_collateral_type(OWNER, collateral, dec!(2))
+        .expect_commit_failure();
+
+    engine
+        .call(
+            OWNER,
+            "update_collateral_type",
+            manifest_args!(collateral, dec!(2), dec!(0), dec!(0), dec!(100)),
+        )
+        .expect_commit_success();
+
+    engine.open_cdp(ALICE, dec!(500)).expect_commit_success();
+    engine.mint(ALICE, dec!(101)).expect_commit_failure();
+    engine.mint(ALICE, dec!(100)).expect_commit_success();
+
+    // Only accepted collateral can open a position
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(engine.env.accounts[OWNER], quote, dec!(1))
+        .take_all_from_worktop(quote, "collateral")
+        .call_method_with_name_lookup(engine.engine, "open_cdp", |lookup| {
+            (lookup.bucket("collateral"),)
+        });
+    engine.env.execute(OWNER, builder).expect_commit_failure();
+}
@@ -0,0 +1,249 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, NonFungibleData, Clone, Debug)]
+pub struct Credential {
+    /// Kind of credential, e.g. `kyc`, `member` or a role name
+    pub kind: String,
+    pub holder: ComponentAddress,
+    pub issued_at_epoch: u64,
+
+    /// Last epoch at which the credential is valid, if it expires
+    #[mutable]
+    pub expires_at_epoch: Option<u64>,
+
+    #[mutable]
+    pub revoked: bool,
+}
+
+impl Credential {
+    pub fn is_valid(&self, epoch: u64) -> bool {
+        !self.revoked
+            && self
+                .expires_at_epoch
+                .map_or(true, |expires_at_epoch| epoch <= expires_at_epoch)
+    }
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CredentialIssuedEvent {
+    pub credential_id: NonFungibleLocalId,
+    pub kind: String,
+    pub holder: ComponentAddress,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CredentialRevokedEvent {
+    pub credential_id: NonFungibleLocalId,
+}
+
+#[blueprint]
+#[events(CredentialIssuedEvent, CredentialRevokedEvent)]
+pub mod credential_issuer {
+
+    enable_method_auth! {
+        roles {
+            issuer => updatable_by: [OWNER];
+        },
+        methods {
+
+            issue => restrict_to :[issuer, OWNER];
+            revoke => restrict_to :[issuer, OWNER];
+            set_expiry => restrict_to :[issuer, OWNER];
+
+            check_credential => PUBLIC;
+
+            get_credential => PUBLIC;
+            is_valid => PUBLIC;
+            get_credential_res_address => PUBLIC;
+
+        }
+    }
+
+    pub struct CredentialIssuer {
+        /// Credential non-fungible resource manager. Credentials can not be withdrawn from the
+        /// account they are issued to.
+        credential_res_manager: ResourceManager,
+    }
+
+    impl CredentialIssuer {
+        /// Returns the issuer component and the credential resource address, to be used as
+        /// the badge of permissioned components
+        pub fn instantiate(
+            name: String,
+            owner_role: OwnerRole,
+            issuer_rule: AccessRule,
+        ) -> (Global<CredentialIssuer>, ResourceAddress) {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(CredentialIssuer::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let credential_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<Credential>(owner_role.clone())
+                    .metadata(metadata! {
+                        init {
+                            "name" => name, updatable;
+                        }
+                    })
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .withdraw_roles(withdraw_roles! {
+                        withdrawer => rule!(deny_all);
+                        withdrawer_updater => rule!(deny_all);
+                    })
+                    .non_fungible_data_update_roles(non_fungible_data_update_roles! {
+                        non_fungible_data_updater => component_rule;
+                        non_fungible_data_updater_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let issuer_component = Self {
+                credential_res_manager,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                issuer => issuer_rule;
+            ))
+            .with_address(address_reservation)
+            .globalize();
+
+            (issuer_component, credential_res_manager.address())
+        }
+
+        /* ISSUER METHODS */
+
+        /// Mint a credential directly to the holder account. Returns the credential id.
+        pub fn issue(
+            &mut self,
+            mut holder: Global<Account>,
+            kind: String,
+            expires_at_epoch: Option<u64>,
+        ) -> NonFungibleLocalId {
+            let current_epoch = Runtime::current_epoch().number();
+
+            /* CHECK INPUT */
+            assert!(!kind.is_empty(), "Credential kind must not be empty");
+            if let Some(expires_at_epoch) = expires_at_epoch {
+                assert!(
+                    expires_at_epoch >= current_epoch,
+                    "Expiry must not be in the past"
+                );
+            }
+
+            let credential = self
+                .credential_res_manager
+                .mint_ruid_non_fungible(Credential {
+                    kind: kind.clone(),
+                    holder: holder.address(),
+                    issued_at_epoch: current_epoch,
+                    expires_at_epoch,
+                    revoked: false,
+                });
+            let credential_id = credential.as_non_fungible().non_fungible_local_id();
+
+            Runtime::emit_event(CredentialIssuedEvent {
+                credential_id: credential_id.clone(),
+                kind,
+                holder: holder.address(),
+            });
+
+            holder.try_deposit_or_abort(credential, None);
+
+            credential_id
+        }
+
+        pub fn revoke(&mut self, credential_id: NonFungibleLocalId) {
+            let credential = self.get_credential(credential_id.clone());
+            assert!(!credential.revoked, "Credential already revoked");
+
+            self.credential_res_manager
+                .update_non_fungible_data(&credential_id, "revoked", true);
+
+            Runtime::emit_event(CredentialRevokedEvent { credential_id });
+        }
+
+        /// Renew or change the expiry of a credential
+        pub fn set_expiry(
+            &mut self,
+            credential_id: NonFungibleLocalId,
+            expires_at_epoch: Option<u64>,
+        ) {
+            let credential = self.get_credential(credential_id.clone());
+            assert!(!credential.revoked, "Credential is revoked");
+
+            self.credential_res_manager.update_non_fungible_data(
+                &credential_id,
+                "expires_at_epoch",
+                expires_at_epoch,
+            );
+        }
+
+        /* CONSUMER METHODS */
+
+        /// Check that a proof holds a valid credential of the given kind, failing otherwise.
+        /// Permissioned components requiring more than holding the resource should call this
+        /// method with the presented proof.
+        pub fn check_credential(
+            &self,
+            credential_proof: Proof,
+            kind: String,
+        ) -> NonFungibleLocalId {
+            let credential = credential_proof
+                .check(self.credential_res_manager.address())
+                .as_non_fungible()
+                .non_fungible::<Credential>();
+
+            assert!(credential.data().kind == kind, "Credential kind mismatch");
+            assert!(
+                credential
+                    .data()
+                    .is_valid(Runtime::current_epoch().number()),
+                "Credential is revoked or expired"
+            );
+
+            credential.local_id().clone()
+        }
+
+        /* GETTERS */
+
+        pub fn get_credential(&self, credential_id: NonFungibleLocalId) -> Credential {
+            self.credential_res_manager
+                .get_non_fungible_data(&credential_id)
+        }
+
+        pub fn is_valid(&self, credential_id: NonFungibleLocalId) -> bool {
+            self.get_credential(credential_id)
+                .is_valid(Runtime::current_epoch().number())
+        }
+
+        pub fn get_credential_res_address(&self) -> ResourceAddress {
+            self.credential_res_manager.address()
+        }
+    }
+}
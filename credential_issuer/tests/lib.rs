@@ -0,0 +1,200 @@
+use credential_issuer::Credential;
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+const OWNER: usize = 0;
+const ISSUER: usize = 1;
+const ALICE: usize = 2;
+
+#[test]
+fn test_credential_validity() {
+    let mut credential = Credential {
+        kind: "kyc".to_string(),
+        holder: FAUCET,
+        issued_at_epoch: 10,
+        expires_at_epoch: Some(20),
+        revoked: false,
+    };
+
+    assert!(credential.is_valid(20));
+    assert!(!credential.is_valid(21));
+
+    credential.expires_at_epoch = None;
+    assert!(credential.is_valid(1000));
+
+    credential.revoked = true;
+    assert!(!credential.is_valid(10));
+}
+
+/// Issuer with an issuer role, at epoch 10
+struct TestIssuer {
+    env: TestEnv,
+    issuer: ComponentAddress,
+    credential: ResourceAddress,
+}
+
+impl TestIssuer {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "CredentialIssuer",
+                "instantiate",
+                manifest_args!(
+                    "Test credentials".to_string(),
+                    OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone()))),
+                    rule!(require(env.badges[ISSUER].clone()))
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            issuer: commit.new_component_addresses()[0],
+            credential: commit.new_resource_addresses()[0],
+            env,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.issuer, method, args)
+    }
+
+    /// Issue a credential of the given kind to Alice
+    fn issue(
+        &mut self,
+        caller: usize,
+        kind: &str,
+        expires_at_epoch: Option<u64>,
+    ) -> TransactionReceipt {
+        let alice = self.env.accounts[ALICE];
+
+        self.call(
+            caller,
+            "issue",
+            manifest_args!(alice, kind.to_string(), expires_at_epoch),
+        )
+    }
+
+    /// Check the credential of Alice against the given kind
+    fn check_credential(&mut self, kind: &str) -> TransactionReceipt {
+        let kind = kind.to_string();
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(self.env.accounts[ALICE], self.credential, dec!(1))
+            .pop_from_auth_zone("credential")
+            .call_method_with_name_lookup(self.issuer, "check_credential", |lookup| {
+                (lookup.proof("credential"), kind)
+            });
+
+        self.env.execute(ALICE, builder)
+    }
+}
+
+#[test]
+fn test_credential_is_checked_until_it_expires() {
+    let mut issuer = TestIssuer::new();
+
+    let credential_id: NonFungibleLocalId = issuer
+        .issue(ISSUER, "kyc", Some(20))
+        .expect_commit_success()
+        .output(0);
+
+    issuer.check_credential("kyc").expect_commit_success();
+    issuer.check_credential("member").expect_commit_failure();
+
+    issuer.env.set_epoch(21);
+    issuer.check_credential("kyc").expect_commit_failure();
+
+    let is_valid: bool = issuer
+        .call(ALICE, "is_valid", manifest_args!(credential_id.clone()))
+        .expect_commit_success()
+        .output(0);
+    assert!(!is_valid);
+
+    // Renewed credentials are valid again
+    issuer
+        .call(
+            ISSUER,
+            "set_expiry",
+            manifest_args!(credential_id.clone(), None::<u64>),
+        )
+        .expect_commit_success();
+    issuer.check_credential("kyc").expect_commit_success();
+
+    let credential: Credential = issuer
+        .call(ALICE, "get_credential", manifest_args!(credential_id))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(credential.holder, issuer.env.accounts[ALICE]);
+    assert_eq!(credential.issued_at_epoch, 10);
+    assert_eq!(credential.expires_at_epoch, None);
+}
+
+#[test]
+fn test_revoked_credential_is_rejected() {
+    let mut issuer = TestIssuer::new();
+
+    let credential_id: NonFungibleLocalId = issuer
+        .issue(ISSUER, "kyc", None)
+        .expect_commit_success()
+        .output(0);
+
+    issuer
+        .call(ALICE, "revoke", manifest_args!(credential_id.clone()))
+        .expect_commit_failure();
+    issuer
+        .call(OWNER, "revoke", manifest_args!(credential_id.clone()))
+        .expect_commit_success();
+
+    issuer.check_credential("kyc").expect_commit_failure();
+
+    issuer
+        .call(ISSUER, "revoke", manifest_args!(credential_id.clone()))
+        .expect_commit_failure();
+    issuer
+        .call(
+            ISSUER,
+            "set_expiry",
+            manifest_args!(credential_id, Some(30u64)),
+        )
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_credential_can_not_be_withdrawn() {
+    let mut issuer = TestIssuer::new();
+    let (credential, alice) = (issuer.credential, issuer.env.accounts[ALICE]);
+
+    issuer.issue(ISSUER, "kyc", None).expect_commit_success();
+
+    let builder = ManifestBuilder::new().withdraw_from_account(alice, credential, dec!(1));
+    issuer.env.execute(ALICE, builder).expect_commit_failure();
+}
+
+#[test]
+fn test_issue_requires_the_issuer_role() {
+    let mut issuer = TestIssuer::new();
+
+    issuer.issue(ALICE, "kyc", None).expect_commit_failure();
+    issuer.issue(ISSUER, "", None).expect_commit_failure();
+    issuer.issue(ISSUER, "kyc", Some(9)).expect_commit_failure();
+
+    issuer
+        .issue(OWNER, "member", Some(10))
+        .expect_commit_success();
+    issuer.check_credential("member").expect_commit_success();
+}
@@ -0,0 +1,349 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_non_fungible_res_address;
+use scrypto::prelude::*;
+use single_asset_pool::pool::AssetPool;
+use single_asset_pool::{DepositType, WithdrawType};
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct CreditLineState {
+    /// Maximum debt of the credit line
+    pub limit: Decimal,
+
+    /// Debt of the credit line divided by the borrow index
+    pub normalized_debt: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CreditDrawnEvent {
+    pub badge_id: NonFungibleLocalId,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CreditRepaidEvent {
+    pub badge_id: NonFungibleLocalId,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct DebtWrittenOffEvent {
+    pub badge_id: NonFungibleLocalId,
+    pub amount: Decimal,
+}
+
+#[blueprint]
+#[events(CreditDrawnEvent, CreditRepaidEvent, DebtWrittenOffEvent)]
+pub mod credit_line {
+
+    enable_method_auth! {
+        methods {
+
+            set_limit => restrict_to :[OWNER];
+            set_interest_rate => restrict_to :[OWNER];
+            write_off => restrict_to :[OWNER];
+
+            supply => PUBLIC;
+            withdraw => PUBLIC;
+
+            draw => PUBLIC;
+            repay => PUBLIC;
+
+            accrue_interest => PUBLIC;
+
+            get_credit_line => PUBLIC;
+            get_debt => PUBLIC;
+
+        }
+    }
+
+    pub struct CreditLine {
+        /// Pool lending to the credit lines
+        pool: Owned<AssetPool>,
+
+        /// Trusted-borrower badge non-fungible resource
+        borrower_badge_res_address: ResourceAddress,
+
+        /// Credit line of each borrower badge
+        credit_lines: KeyValueStore<NonFungibleLocalId, CreditLineState>,
+
+        /// Linear interest rate applied to drawn amounts every epoch
+        interest_rate_per_epoch: Decimal,
+
+        /// Growth of one unit of debt since the instantiation
+        borrow_index: Decimal,
+
+        /// Sum of the normalized debts of all credit lines
+        total_normalized_debt: Decimal,
+
+        last_accrual_epoch: u64,
+    }
+
+    impl CreditLine {
+        pub fn instantiate(
+            pool_res_address: ResourceAddress,
+            borrower_badge_res_address: ResourceAddress,
+            interest_rate_per_epoch: Decimal,
+            owner_role: OwnerRole,
+        ) -> (Global<CreditLine>, ResourceAddress) {
+            /* CHECK INPUT */
+            assert_non_fungible_res_address(
+                borrower_badge_res_address,
+                Some("Borrower badge must be non fungible".to_string()),
+            );
+            CreditLine::_assert_valid_interest_rate(interest_rate_per_epoch);
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(CreditLine::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let (pool, pool_unit_res_address, _) = AssetPool::instantiate_locally(
+                pool_res_address,
+                owner_role.clone(),
+                component_rule,
+            );
+
+            let credit_line_component = Self {
+                pool,
+                borrower_badge_res_address,
+                credit_lines: KeyValueStore::new(),
+                interest_rate_per_epoch,
+                borrow_index: Decimal::ONE,
+                total_normalized_debt: Decimal::ZERO,
+                last_accrual_epoch: Runtime::current_epoch().number(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (credit_line_component, pool_unit_res_address)
+        }
+
+        /* ADMIN METHODS */
+
+        /// Set the credit limit of a borrower badge. Lowering the limit below the current debt
+        /// prevents further draws without affecting the debt.
+        pub fn set_limit(&mut self, badge_id: NonFungibleLocalId, limit: Decimal) {
+            /* CHECK INPUT */
+            assert!(limit >= Decimal::ZERO, "Limit must not be negative");
+
+            let line_exists = self.credit_lines.get(&badge_id).is_some();
+
+            if line_exists {
+                self.credit_lines.get_mut(&badge_id).unwrap().limit = limit;
+            } else {
+                self.credit_lines.insert(
+                    badge_id,
+                    CreditLineState {
+                        limit,
+                        normalized_debt: Decimal::ZERO,
+                    },
+                );
+            }
+        }
+
+        pub fn set_interest_rate(&mut self, interest_rate_per_epoch: Decimal) {
+            CreditLine::_assert_valid_interest_rate(interest_rate_per_epoch);
+
+            self.accrue_interest();
+
+            self.interest_rate_per_epoch = interest_rate_per_epoch;
+        }
+
+        /// Write off the debt of a defaulted credit line. The loss is borne by the lenders and
+        /// the credit limit is set to zero.
+        pub fn write_off(&mut self, badge_id: NonFungibleLocalId) {
+            self.accrue_interest();
+
+            let mut credit_line = self
+                .credit_lines
+                .get_mut(&badge_id)
+                .expect("Credit line not found");
+
+            let amount = credit_line.normalized_debt * self.borrow_index;
+
+            self.total_normalized_debt -= credit_line.normalized_debt;
+            credit_line.normalized_debt = Decimal::ZERO;
+            credit_line.limit = Decimal::ZERO;
+
+            drop(credit_line);
+
+            // Debt can slightly exceed the external liquidity because of rounding
+            let (_, external_liquidity) = self.pool.get_pooled_amount();
+            self.pool
+                .decrease_external_liquidity(amount.min(external_liquidity));
+
+            Runtime::emit_event(DebtWrittenOffEvent { badge_id, amount });
+        }
+
+        /* LENDER METHODS */
+
+        pub fn supply(&mut self, assets: Bucket) -> (Bucket, Bucket) {
+            self.accrue_interest();
+
+            self.pool.contribute(assets)
+        }
+
+        pub fn withdraw(&mut self, pool_units: Bucket) -> (Bucket, Bucket) {
+            self.accrue_interest();
+
+            self.pool.redeem(pool_units)
+        }
+
+        /* BORROWER METHODS */
+
+        pub fn draw(&mut self, badge_proof: Proof, amount: Decimal) -> Bucket {
+            let badge_id = self._check_badge_proof(badge_proof);
+
+            /* INPUT CHECK */
+            assert!(amount > 0.into(), "Amount must be greater than zero!");
+
+            self.accrue_interest();
+
+            let mut credit_line = self
+                .credit_lines
+                .get_mut(&badge_id)
+                .expect("Credit line not found");
+
+            let normalized_amount = amount / self.borrow_index;
+            credit_line.normalized_debt += normalized_amount;
+
+            assert!(
+                credit_line.normalized_debt * self.borrow_index <= credit_line.limit,
+                "Credit limit exceeded"
+            );
+
+            drop(credit_line);
+
+            self.total_normalized_debt += normalized_amount;
+
+            Runtime::emit_event(CreditDrawnEvent { badge_id, amount });
+
+            self.pool.protected_withdraw(
+                amount,
+                WithdrawType::ForTemporaryUse,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            )
+        }
+
+        /// Repay part or all of the debt of a credit line. Returns the payment change.
+        pub fn repay(&mut self, badge_proof: Proof, mut payment: Bucket) -> Bucket {
+            let badge_id = self._check_badge_proof(badge_proof);
+
+            self.accrue_interest();
+
+            let mut credit_line = self
+                .credit_lines
+                .get_mut(&badge_id)
+                .expect("Credit line not found");
+
+            let debt_amount = credit_line.normalized_debt * self.borrow_index;
+            let repay_amount = payment.amount().min(debt_amount);
+
+            let normalized_repay_amount = if repay_amount == debt_amount {
+                credit_line.normalized_debt
+            } else {
+                repay_amount / self.borrow_index
+            };
+
+            credit_line.normalized_debt -= normalized_repay_amount;
+
+            drop(credit_line);
+
+            self.total_normalized_debt -= normalized_repay_amount;
+
+            self.pool.protected_deposit(
+                payment.take_advanced(
+                    repay_amount,
+                    WithdrawStrategy::Rounded(RoundingMode::AwayFromZero),
+                ),
+                DepositType::FromTemporaryUse,
+            );
+
+            Runtime::emit_event(CreditRepaidEvent {
+                badge_id,
+                amount: repay_amount,
+            });
+
+            payment
+        }
+
+        /// Accrue the interest since the last accrual. Accrued interest is added to the pool
+        /// external liquidity so it is reflected in the pool unit ratio.
+        pub fn accrue_interest(&mut self) {
+            let current_epoch = Runtime::current_epoch().number();
+
+            let elapsed_epochs = current_epoch - self.last_accrual_epoch;
+            if elapsed_epochs == 0 {
+                return;
+            }
+
+            let interest_factor = self.interest_rate_per_epoch * elapsed_epochs;
+            let interest = self.total_normalized_debt * self.borrow_index * interest_factor;
+
+            self.borrow_index *= Decimal::ONE + interest_factor;
+            self.last_accrual_epoch = current_epoch;
+
+            if interest > 0.into() {
+                self.pool.increase_external_liquidity(interest);
+            }
+        }
+
+        /* GETTERS */
+
+        pub fn get_credit_line(&self, badge_id: NonFungibleLocalId) -> Option<CreditLineState> {
+            self.credit_lines.get(&badge_id).map(|line| line.clone())
+        }
+
+        /// Current debt of a credit line, including the interest not accrued yet
+        pub fn get_debt(&self, badge_id: NonFungibleLocalId) -> Decimal {
+            let elapsed_epochs = Runtime::current_epoch().number() - self.last_accrual_epoch;
+            let borrow_index =
+                self.borrow_index * (Decimal::ONE + self.interest_rate_per_epoch * elapsed_epochs);
+
+            self.credit_lines
+                .get(&badge_id)
+                .map(|line| line.normalized_debt * borrow_index)
+                .unwrap_or(0.into())
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_interest_rate(interest_rate_per_epoch: Decimal) {
+            assert!(
+                interest_rate_per_epoch >= 0.into(),
+                "Interest rate must not be negative"
+            );
+        }
+
+        fn _check_badge_proof(&self, badge_proof: Proof) -> NonFungibleLocalId {
+            badge_proof
+                .check(self.borrower_badge_res_address)
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+const OWNER: usize = 0;
+/// Holds 1000 assets
+const LENDER: usize = 1;
+/// Holds 10 assets and the borrower badges 1 to 3
+const BORROWER: usize = 2;
+
+/// Credit line charging 1% per epoch, with 1000 assets supplied by the lender and a limit of
+/// 100 for the borrower badge 1, at epoch 10
+struct TestCreditLine {
+    env: TestEnv,
+    credit_line: ComponentAddress,
+    pool_unit: ResourceAddress,
+    asset: ResourceAddress,
+    borrower_badge: ResourceAddress,
+}
+
+impl TestCreditLine {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let asset = env
+            .test_runner
+            .create_fungible_resource(dec!(1010), 18, env.accounts[LENDER]);
+        let borrower_badge = env
+            .test_runner
+            .create_non_fungible_resource(env.accounts[BORROWER]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "CreditLine",
+                "instantiate",
+                manifest_args!(
+                    asset,
+                    borrower_badge,
+                    dec!("0.01"),
+                    OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())))
+                ),
+            )
+            .withdraw_from_account(env.accounts[LENDER], asset, dec!(10))
+            .take_all_from_worktop(asset, "borrower")
+            .call_method_with_name_lookup(env.accounts[BORROWER], "deposit", |lookup| {
+                (lookup.bucket("borrower"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let (credit_line, pool_unit): (ComponentAddress, ResourceAddress) =
+            receipt.expect_commit_success().output(0);
+
+        let mut credit_line = Self {
+            env,
+            credit_line,
+            pool_unit,
+            asset,
+            borrower_badge,
+        };
+
+        credit_line.supply(dec!(1000)).expect_commit_success();
+        credit_line
+            .call(
+                OWNER,
+                "set_limit",
+                manifest_args!(NonFungibleLocalId::integer(1), dec!(100)),
+            )
+            .expect_commit_success();
+
+        credit_line
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.credit_line, method, args)
+    }
+
+    fn supply(&mut self, amount: Decimal) -> TransactionReceipt {
+        let asset = self.asset;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[LENDER], asset, amount)
+            .take_all_from_worktop(asset, "assets")
+            .call_method_with_name_lookup(self.credit_line, "supply", |lookup| {
+                (lookup.bucket("assets"),)
+            });
+
+        self.env.execute(LENDER, builder)
+    }
+
+    fn withdraw(&mut self, units: Decimal) -> TransactionReceipt {
+        let pool_unit = self.pool_unit;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[LENDER], pool_unit, units)
+            .take_all_from_worktop(pool_unit, "units")
+            .call_method_with_name_lookup(self.credit_line, "withdraw", |lookup| {
+                (lookup.bucket("units"),)
+            });
+
+        self.env.execute(LENDER, builder)
+    }
+
+    /// Create a proof of the given borrower badge, then call the method with the returned
+    /// arguments
+    fn call_with_badge<A: ResolvableArguments>(
+        &mut self,
+        badge_id: u64,
+        builder: ManifestBuilder,
+        method: &str,
+        args: impl FnOnce(&ManifestNameLookup) -> A,
+    ) -> TransactionReceipt {
+        let builder = builder
+            .create_proof_from_account_of_non_fungibles(
+                self.env.accounts[BORROWER],
+                self.borrower_badge,
+                &BTreeSet::from([NonFungibleLocalId::integer(badge_id)]),
+            )
+            .pop_from_auth_zone("badge")
+            .call_method_with_name_lookup(self.credit_line, method, |lookup| args(lookup));
+
+        self.env.execute(BORROWER, builder)
+    }
+
+    fn draw(&mut self, badge_id: u64, amount: Decimal) -> TransactionReceipt {
+        self.call_with_badge(badge_id, ManifestBuilder::new(), "draw", |lookup| {
+            (lookup.proof("badge"), amount)
+        })
+    }
+
+    fn repay(&mut self, badge_id: u64, amount: Decimal) -> TransactionReceipt {
+        let asset = self.asset;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[BORROWER], asset, amount)
+            .take_all_from_worktop(asset, "payment");
+
+        self.call_with_badge(badge_id, builder, "repay", |lookup| {
+            (lookup.proof("badge"), lookup.bucket("payment"))
+        })
+    }
+
+    fn get_debt(&mut self, badge_id: u64) -> Decimal {
+        self.call(
+            OWNER,
+            "get_debt",
+            manifest_args!(NonFungibleLocalId::integer(badge_id)),
+        )
+        .expect_commit_success()
+        .output(0)
+    }
+}
+
+#[test]
+fn test_draw_and_repay_with_interest() {
+    let mut credit_line = TestCreditLine::new();
+    let asset = credit_line.asset;
+
+    credit_line.draw(1, dec!(100)).expect_commit_success();
+    credit_line.draw(1, dec!(1)).expect_commit_failure();
+    assert_eq!(credit_line.env.balance(BORROWER, asset), dec!(110));
+
+    credit_line.env.set_epoch(20);
+    assert_eq!(credit_line.get_debt(1), dec!(110));
+
+    // Accrued interest counts against the limit
+    credit_line.draw(1, dec!(1)).expect_commit_failure();
+
+    credit_line.repay(1, dec!(110)).expect_commit_success();
+    assert_eq!(credit_line.get_debt(1), dec!(0));
+    assert_eq!(credit_line.env.balance(BORROWER, asset), dec!(0));
+
+    // The interest is paid to the lender
+    credit_line.withdraw(dec!(1000)).expect_commit_success();
+    assert_eq!(credit_line.env.balance(LENDER, asset), dec!(1010));
+}
+
+#[test]
+fn test_write_off_is_borne_by_the_lenders() {
+    let mut credit_line = TestCreditLine::new();
+    let asset = credit_line.asset;
+    let badge_id = NonFungibleLocalId::integer(1);
+
+    credit_line.draw(1, dec!(100)).expect_commit_success();
+
+    credit_line
+        .call(LENDER, "write_off", manifest_args!(badge_id.clone()))
+        .expect_commit_failure();
+    credit_line
+        .call(OWNER, "write_off", manifest_args!(badge_id))
+        .expect_commit_success();
+
+    assert_eq!(credit_line.get_debt(1), dec!(0));
+    credit_line.draw(1, dec!(1)).expect_commit_failure();
+
+    credit_line.withdraw(dec!(1000)).expect_commit_success();
+    assert_eq!(credit_line.env.balance(LENDER, asset), dec!(900));
+}
+
+#[test]
+fn test_limits_are_set_by_the_owner() {
+    let mut credit_line = TestCreditLine::new();
+    let badge_id = NonFungibleLocalId::integer(2);
+
+    credit_line.draw(2, dec!(10)).expect_commit_failure();
+
+    credit_line
+        .call(
+            BORROWER,
+            "set_limit",
+            manifest_args!(badge_id.clone(), dec!(50)),
+        )
+        .expect_commit_failure();
+    credit_line
+        .call(
+            OWNER,
+            "set_limit",
+            manifest_args!(badge_id.clone(), dec!(-1)),
+        )
+        .expect_commit_failure();
+    credit_line
+        .call(OWNER, "set_limit", manifest_args!(badge_id, dec!(50)))
+        .expect_commit_success();
+
+    credit_line.draw(2, dec!(50)).expect_commit_success();
+
+    credit_line
+        .call(OWNER, "set_interest_rate", manifest_args!(dec!(-1)))
+        .expect_commit_failure();
+    credit_line
+        .call(BORROWER, "set_interest_rate", manifest_args!(dec!(0)))
+        .expect_commit_failure();
+}
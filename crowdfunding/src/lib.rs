@@ -0,0 +1,243 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct BackerReceipt {
+    pub amount: Decimal,
+    pub epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PledgeEvent {
+    pub receipt_id: NonFungibleLocalId,
+    pub amount: Decimal,
+    pub total_raised: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RefundEvent {
+    pub receipt_id: NonFungibleLocalId,
+    pub amount: Decimal,
+}
+
+#[blueprint]
+#[events(PledgeEvent, RefundEvent)]
+pub mod crowdfunding {
+
+    enable_method_auth! {
+        methods {
+
+            withdraw_funds => restrict_to :[OWNER];
+
+            pledge => PUBLIC;
+            refund => PUBLIC;
+
+            get_total_raised => PUBLIC;
+            get_goal => PUBLIC;
+            get_deadline_epoch => PUBLIC;
+            is_successful => PUBLIC;
+
+        }
+    }
+
+    pub struct Crowdfunding {
+        /// Pledged funds, escrowed until the deadline
+        funds: Vault,
+
+        /// Total amount pledged, kept after the creator has withdrawn the funds
+        total_raised: Decimal,
+
+        /// Minimum amount to raise for the campaign to succeed
+        goal: Decimal,
+
+        /// Last epoch at which pledges are accepted
+        deadline_epoch: u64,
+
+        /// Backer receipt non-fungible resource manager
+        receipt_res_manager: ResourceManager,
+    }
+
+    impl Crowdfunding {
+        /// Instantiate a campaign open until the deadline epoch. Returns the campaign
+        /// component, the creator badge, which is required to withdraw the funds if the goal
+        /// is met, and the backer receipt resource address.
+        pub fn instantiate(
+            payment_res_address: ResourceAddress,
+            goal: Decimal,
+            deadline_epoch: u64,
+        ) -> (Global<Crowdfunding>, Bucket, ResourceAddress) {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                payment_res_address,
+                Some("Payment resource must be fungible".to_string()),
+            );
+            assert!(goal > Decimal::ZERO, "Goal must be greater than zero");
+            assert!(
+                deadline_epoch >= Runtime::current_epoch().number(),
+                "Deadline must not be in the past"
+            );
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(Crowdfunding::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let creator_badge = ResourceBuilder::new_fungible(OwnerRole::None)
+                .divisibility(DIVISIBILITY_NONE)
+                .mint_initial_supply(1);
+
+            let owner_role = OwnerRole::Fixed(rule!(require(creator_badge.resource_address())));
+
+            let receipt_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<BackerReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule;
+                        burner_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let campaign_component = Self {
+                funds: Vault::new(payment_res_address),
+                total_raised: Decimal::ZERO,
+                goal,
+                deadline_epoch,
+                receipt_res_manager,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (
+                campaign_component,
+                creator_badge.into(),
+                receipt_res_manager.address(),
+            )
+        }
+
+        /* CREATOR METHODS */
+
+        /// Withdraw the pledged funds once the deadline has passed and the goal has been met
+        pub fn withdraw_funds(&mut self) -> Bucket {
+            assert!(
+                Runtime::current_epoch().number() > self.deadline_epoch,
+                "Campaign has not ended yet"
+            );
+            assert!(self.is_successful(), "Campaign goal has not been met");
+
+            self.funds.take_all()
+        }
+
+        /* BACKER METHODS */
+
+        /// Pledge funds to the campaign. Returns a receipt redeemable for a refund if the
+        /// campaign fails.
+        pub fn pledge(&mut self, payment: Bucket) -> Bucket {
+            let current_epoch = Runtime::current_epoch().number();
+            let amount = payment.amount();
+
+            /* CHECK INPUT */
+            assert!(current_epoch <= self.deadline_epoch, "Campaign has ended");
+            assert!(
+                payment.resource_address() == self.funds.resource_address(),
+                "Payment resource address mismatch"
+            );
+            assert!(amount > Decimal::ZERO, "Pledge must be greater than zero");
+
+            self.funds.put(payment);
+            self.total_raised += amount;
+
+            let receipt = self
+                .receipt_res_manager
+                .mint_ruid_non_fungible(BackerReceipt {
+                    amount,
+                    epoch: current_epoch,
+                });
+
+            Runtime::emit_event(PledgeEvent {
+                receipt_id: receipt.as_non_fungible().non_fungible_local_id(),
+                amount,
+                total_raised: self.total_raised,
+            });
+
+            receipt
+        }
+
+        /// Burn backer receipts in exchange for the pledged funds, once the deadline has
+        /// passed without the goal being met
+        pub fn refund(&mut self, receipts: Bucket) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                receipts.resource_address() == self.receipt_res_manager.address(),
+                "Receipt resource address mismatch"
+            );
+            assert!(
+                Runtime::current_epoch().number() > self.deadline_epoch,
+                "Campaign has not ended yet"
+            );
+            assert!(!self.is_successful(), "Campaign goal has been met");
+
+            let mut refund = Bucket::new(self.funds.resource_address());
+
+            for receipt in receipts.as_non_fungible().non_fungibles::<BackerReceipt>() {
+                let amount = receipt.data().amount;
+
+                refund.put(self.funds.take(amount));
+
+                Runtime::emit_event(RefundEvent {
+                    receipt_id: receipt.local_id().clone(),
+                    amount,
+                });
+            }
+
+            receipts.burn();
+
+            refund
+        }
+
+        /* GETTERS */
+
+        pub fn get_total_raised(&self) -> Decimal {
+            self.total_raised
+        }
+
+        pub fn get_goal(&self) -> Decimal {
+            self.goal
+        }
+
+        pub fn get_deadline_epoch(&self) -> u64 {
+            self.deadline_epoch
+        }
+
+        pub fn is_successful(&self) -> bool {
+            self.total_raised >= self.goal
+        }
+    }
+}
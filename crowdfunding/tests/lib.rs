@@ -0,0 +1,183 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Holds the creator badge
+const CREATOR: usize = 0;
+/// Backers holding 1000 payment tokens each
+const ALICE: usize = 1;
+const BOB: usize = 2;
+
+/// Campaign with a goal of 100 tokens and a deadline at epoch 20, instantiated at epoch 10
+struct TestCampaign {
+    env: TestEnv,
+    campaign: ComponentAddress,
+    creator_badge: ResourceAddress,
+    receipt: ResourceAddress,
+    payment: ResourceAddress,
+}
+
+impl TestCampaign {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let payment = env
+            .test_runner
+            .create_fungible_resource(dec!(2000), 18, env.accounts[ALICE]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "Crowdfunding",
+                "instantiate",
+                manifest_args!(payment, dec!(100), 20u64),
+            )
+            .withdraw_from_account(env.accounts[ALICE], payment, dec!(1000))
+            .take_all_from_worktop(payment, "bob")
+            .call_method_with_name_lookup(env.accounts[BOB], "deposit", |lookup| {
+                (lookup.bucket("bob"),)
+            })
+            .deposit_batch(env.accounts[CREATOR])
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            campaign: commit.new_component_addresses()[0],
+            creator_badge: commit.new_resource_addresses()[0],
+            receipt: commit.new_resource_addresses()[1],
+            env,
+            payment,
+        }
+    }
+
+    /// Execute the manifest with the keys of every account, then deposit the worktop into the
+    /// given account
+    fn execute(&mut self, account: usize, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute_with_all_keys(account, builder)
+    }
+
+    fn pledge(&mut self, backer: usize, amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[backer], self.payment, amount)
+            .take_all_from_worktop(self.payment, "payment")
+            .call_method_with_name_lookup(self.campaign, "pledge", |lookup| {
+                (lookup.bucket("payment"),)
+            });
+
+        self.execute(backer, builder)
+    }
+
+    /// Refund every receipt held by the backer
+    fn refund(&mut self, backer: usize) -> TransactionReceipt {
+        let receipts = self.env.balance(backer, self.receipt);
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[backer], self.receipt, receipts)
+            .take_all_from_worktop(self.receipt, "receipts")
+            .call_method_with_name_lookup(self.campaign, "refund", |lookup| {
+                (lookup.bucket("receipts"),)
+            });
+
+        self.execute(backer, builder)
+    }
+
+    fn withdraw_funds(&mut self) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(
+                self.env.accounts[CREATOR],
+                self.creator_badge,
+                dec!(1),
+            )
+            .call_method(self.campaign, "withdraw_funds", manifest_args!());
+
+        self.execute(CREATOR, builder)
+    }
+
+    fn is_successful(&mut self) -> bool {
+        let builder =
+            ManifestBuilder::new().call_method(self.campaign, "is_successful", manifest_args!());
+
+        self.execute(CREATOR, builder)
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_creator_withdraws_the_funds_of_a_successful_campaign() {
+    let mut campaign = TestCampaign::new();
+    let (payment, receipt) = (campaign.payment, campaign.receipt);
+
+    campaign.pledge(ALICE, dec!(60)).expect_commit_success();
+    assert!(!campaign.is_successful());
+    campaign.pledge(BOB, dec!(50)).expect_commit_success();
+    assert!(campaign.is_successful());
+    assert_eq!(campaign.env.balance(ALICE, receipt), dec!(1));
+
+    campaign.withdraw_funds().expect_commit_failure();
+
+    campaign.env.set_epoch(21);
+    campaign.refund(ALICE).expect_commit_failure();
+    campaign.withdraw_funds().expect_commit_success();
+
+    assert_eq!(campaign.env.balance(CREATOR, payment), dec!(110));
+}
+
+#[test]
+fn test_withdraw_funds_requires_the_creator_badge() {
+    let mut campaign = TestCampaign::new();
+    let campaign_address = campaign.campaign;
+
+    campaign.pledge(ALICE, dec!(100)).expect_commit_success();
+    campaign.env.set_epoch(21);
+
+    let builder =
+        ManifestBuilder::new().call_method(campaign_address, "withdraw_funds", manifest_args!());
+    campaign.execute(ALICE, builder).expect_commit_failure();
+}
+
+#[test]
+fn test_backers_are_refunded_when_the_goal_is_not_met() {
+    let mut campaign = TestCampaign::new();
+    let (payment, receipt) = (campaign.payment, campaign.receipt);
+
+    campaign.pledge(ALICE, dec!(30)).expect_commit_success();
+    campaign.pledge(ALICE, dec!(20)).expect_commit_success();
+    campaign.pledge(BOB, dec!(40)).expect_commit_success();
+    assert_eq!(campaign.env.balance(ALICE, receipt), dec!(2));
+
+    campaign.refund(ALICE).expect_commit_failure();
+
+    campaign.env.set_epoch(21);
+    campaign.pledge(BOB, dec!(10)).expect_commit_failure();
+    campaign.withdraw_funds().expect_commit_failure();
+
+    campaign.refund(ALICE).expect_commit_success();
+    campaign.refund(BOB).expect_commit_success();
+
+    assert_eq!(campaign.env.balance(ALICE, payment), dec!(1000));
+    assert_eq!(campaign.env.balance(BOB, payment), dec!(1000));
+    assert_eq!(campaign.env.balance(ALICE, receipt), dec!(0));
+}
+
+#[test]
+fn test_pledge_with_another_resource_fails() {
+    let mut campaign = TestCampaign::new();
+    let (account, campaign_address) = (campaign.env.accounts[ALICE], campaign.campaign);
+    let other = campaign
+        .env
+        .test_runner
+        .create_fungible_resource(dec!(100), 18, account);
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(account, other, dec!(10))
+        .take_all_from_worktop(other, "payment")
+        .call_method_with_name_lookup(campaign_address, "pledge", |lookup| {
+            (lookup.bucket("payment"),)
+        });
+    campaign.execute(ALICE, builder).expect_commit_failure();
+}
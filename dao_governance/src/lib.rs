@@ -0,0 +1,475 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::{assert_fungible_res_address, Checkpoints};
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct VoterBadge {
+    pub created_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct GovernanceParameters {
+    /// Number of epochs during which a proposal can be voted
+    pub voting_period_epochs: u64,
+    /// Number of epochs between the end of the vote and the execution of a passed proposal
+    pub execution_delay_epochs: u64,
+    /// Share of the voting power at the snapshot that must take part in the vote
+    pub quorum: Decimal,
+    /// Share of the for and against votes that must be for the proposal
+    pub approval_threshold: Decimal,
+    /// Voting power required to create a proposal
+    pub proposal_threshold: Decimal,
+}
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VoteChoice {
+    For,
+    Against,
+    Abstain,
+}
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProposalStatus {
+    Active,
+    Defeated,
+    /// Passed, waiting for the execution delay
+    Queued,
+    Executable,
+    Executed,
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct Proposal {
+    pub description: String,
+    pub component_address: ComponentAddress,
+    pub method_name: String,
+    /// SBOR encoded arguments of the method
+    pub args: Vec<u8>,
+    /// Voting power is measured at the last checkpoint before this epoch
+    pub snapshot_epoch: u64,
+    pub voting_end_epoch: u64,
+    pub for_votes: Decimal,
+    pub against_votes: Decimal,
+    pub abstain_votes: Decimal,
+    pub executed: bool,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ProposalCreatedEvent {
+    pub proposal_id: u64,
+    pub proposal: Proposal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct VoteCastEvent {
+    pub proposal_id: u64,
+    pub voter_id: NonFungibleLocalId,
+    pub choice: VoteChoice,
+    pub voting_power: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ProposalExecutedEvent {
+    pub proposal_id: u64,
+}
+
+#[blueprint]
+#[events(ProposalCreatedEvent, VoteCastEvent, ProposalExecutedEvent)]
+pub mod dao_governance {
+
+    enable_method_auth! {
+        methods {
+
+            set_parameters => restrict_to :[OWNER];
+
+            create_voter => PUBLIC;
+            lock => PUBLIC;
+            unlock => PUBLIC;
+
+            propose => PUBLIC;
+            vote => PUBLIC;
+            execute => PUBLIC;
+
+            get_proposal => PUBLIC;
+            get_proposal_status => PUBLIC;
+            get_voting_power => PUBLIC;
+            get_parameters => PUBLIC;
+
+        }
+    }
+
+    pub struct DaoGovernance {
+        parameters: GovernanceParameters,
+
+        /// Governance tokens locked by the voters
+        locked: Vault,
+
+        /// History of the total locked amount
+        total_checkpoints: Checkpoints,
+
+        /// Voter badge non-fungible resource manager
+        voter_res_manager: ResourceManager,
+
+        /// History of the locked amount of each voter
+        voter_checkpoints: KeyValueStore<NonFungibleLocalId, Checkpoints>,
+
+        proposals: KeyValueStore<u64, Proposal>,
+        next_proposal_id: u64,
+
+        /// Voters that have voted on each proposal
+        votes: KeyValueStore<(u64, NonFungibleLocalId), VoteChoice>,
+    }
+
+    impl DaoGovernance {
+        /// Instantiate a DAO owning itself: its parameters can only be changed by executing a
+        /// proposal calling `set_parameters`.
+        pub fn instantiate(
+            governance_res_address: ResourceAddress,
+            parameters: GovernanceParameters,
+        ) -> (Global<DaoGovernance>, ResourceAddress) {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                governance_res_address,
+                Some("Governance resource must be fungible".to_string()),
+            );
+            DaoGovernance::_assert_valid_parameters(&parameters);
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(DaoGovernance::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let voter_res_manager = ResourceBuilder::new_ruid_non_fungible::<VoterBadge>(
+                OwnerRole::Fixed(component_rule.clone()),
+            )
+            .mint_roles(mint_roles! {
+                minter => component_rule.clone();
+                minter_updater => rule!(deny_all);
+            })
+            .create_with_no_initial_supply();
+
+            let dao_component = Self {
+                parameters,
+                locked: Vault::new(governance_res_address),
+                total_checkpoints: Checkpoints::new(),
+                voter_res_manager,
+                voter_checkpoints: KeyValueStore::new(),
+                proposals: KeyValueStore::new(),
+                next_proposal_id: 1,
+                votes: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::Fixed(component_rule))
+            .with_address(address_reservation)
+            .globalize();
+
+            (dao_component, voter_res_manager.address())
+        }
+
+        /* GOVERNANCE METHODS */
+
+        pub fn set_parameters(&mut self, parameters: GovernanceParameters) {
+            DaoGovernance::_assert_valid_parameters(&parameters);
+
+            self.parameters = parameters;
+        }
+
+        /* VOTER METHODS */
+
+        /// Create a voter badge. Tokens locked with the badge give voting power on proposals
+        /// created from the next epoch.
+        pub fn create_voter(&mut self) -> Bucket {
+            let badge = self.voter_res_manager.mint_ruid_non_fungible(VoterBadge {
+                created_at_epoch: Runtime::current_epoch().number(),
+            });
+
+            self.voter_checkpoints.insert(
+                badge.as_non_fungible().non_fungible_local_id(),
+                Checkpoints::new(),
+            );
+
+            badge
+        }
+
+        pub fn lock(&mut self, voter_proof: Proof, tokens: Bucket) {
+            let voter_id = self._check_voter_proof(voter_proof);
+
+            /* CHECK INPUT */
+            assert!(
+                tokens.resource_address() == self.locked.resource_address(),
+                "Governance resource address mismatch"
+            );
+
+            self._update_locked_amount(&voter_id, tokens.amount());
+            self.locked.put(tokens);
+        }
+
+        /// Unlock tokens. Votes already cast keep the voting power of their snapshot.
+        pub fn unlock(&mut self, voter_proof: Proof, amount: Decimal) -> Bucket {
+            let voter_id = self._check_voter_proof(voter_proof);
+
+            /* CHECK INPUT */
+            assert!(amount > Decimal::ZERO, "Amount must be greater than zero!");
+
+            self._update_locked_amount(&voter_id, -amount);
+
+            self.locked.take(amount)
+        }
+
+        /// Create a proposal calling a method of a component as the DAO. Proposals targeting
+        /// the DAO itself can only call `set_parameters`. Returns the proposal id.
+        pub fn propose(
+            &mut self,
+            voter_proof: Proof,
+            description: String,
+            component_address: ComponentAddress,
+            method_name: String,
+            args: Vec<u8>,
+        ) -> u64 {
+            let voter_id = self._check_voter_proof(voter_proof);
+            let current_epoch = Runtime::current_epoch().number();
+
+            assert!(
+                self._get_voting_power(&voter_id, current_epoch)
+                    >= self.parameters.proposal_threshold,
+                "Not enough voting power to create a proposal"
+            );
+
+            if component_address == Runtime::global_address() {
+                DaoGovernance::_decode_self_call(&method_name, &args);
+            }
+
+            let proposal = Proposal {
+                description,
+                component_address,
+                method_name,
+                args,
+                snapshot_epoch: current_epoch,
+                voting_end_epoch: current_epoch + self.parameters.voting_period_epochs,
+                for_votes: Decimal::ZERO,
+                against_votes: Decimal::ZERO,
+                abstain_votes: Decimal::ZERO,
+                executed: false,
+            };
+
+            let proposal_id = self.next_proposal_id;
+            self.next_proposal_id += 1;
+
+            self.proposals.insert(proposal_id, proposal.clone());
+
+            Runtime::emit_event(ProposalCreatedEvent {
+                proposal_id,
+                proposal,
+            });
+
+            proposal_id
+        }
+
+        /// Vote on an active proposal with the voting power of the voter at the proposal
+        /// snapshot
+        pub fn vote(&mut self, voter_proof: Proof, proposal_id: u64, choice: VoteChoice) {
+            let voter_id = self._check_voter_proof(voter_proof);
+
+            assert!(
+                self.get_proposal_status(proposal_id) == ProposalStatus::Active,
+                "Proposal is not active"
+            );
+            assert!(
+                self.votes.get(&(proposal_id, voter_id.clone())).is_none(),
+                "Already voted on this proposal"
+            );
+
+            let snapshot_epoch = self.proposals.get(&proposal_id).unwrap().snapshot_epoch;
+            let voting_power = self._get_voting_power(&voter_id, snapshot_epoch);
+
+            assert!(
+                voting_power > Decimal::ZERO,
+                "No voting power at the snapshot"
+            );
+
+            let mut proposal = self.proposals.get_mut(&proposal_id).unwrap();
+            match choice {
+                VoteChoice::For => proposal.for_votes += voting_power,
+                VoteChoice::Against => proposal.against_votes += voting_power,
+                VoteChoice::Abstain => proposal.abstain_votes += voting_power,
+            }
+            drop(proposal);
+
+            self.votes.insert((proposal_id, voter_id.clone()), choice);
+
+            Runtime::emit_event(VoteCastEvent {
+                proposal_id,
+                voter_id,
+                choice,
+                voting_power,
+            });
+        }
+
+        /// Execute a passed proposal once the execution delay is over. Anyone can execute it.
+        pub fn execute(&mut self, proposal_id: u64) {
+            assert!(
+                self.get_proposal_status(proposal_id) == ProposalStatus::Executable,
+                "Proposal is not executable"
+            );
+
+            let proposal = {
+                let mut proposal = self.proposals.get_mut(&proposal_id).unwrap();
+                proposal.executed = true;
+                proposal.clone()
+            };
+
+            // The DAO is locked by this method, so a call back into it would fail: proposals
+            // targeting the DAO itself are applied directly
+            if proposal.component_address == Runtime::global_address() {
+                let parameters =
+                    DaoGovernance::_decode_self_call(&proposal.method_name, &proposal.args);
+
+                self.set_parameters(parameters);
+            } else {
+                let component: Global<AnyComponent> = Global::from(proposal.component_address);
+                component.call_raw::<ScryptoValue>(&proposal.method_name, proposal.args);
+            }
+
+            Runtime::emit_event(ProposalExecutedEvent { proposal_id });
+        }
+
+        /* GETTERS */
+
+        pub fn get_proposal(&self, proposal_id: u64) -> Proposal {
+            self.proposals
+                .get(&proposal_id)
+                .expect("Proposal not found")
+                .clone()
+        }
+
+        pub fn get_proposal_status(&self, proposal_id: u64) -> ProposalStatus {
+            let proposal = self
+                .proposals
+                .get(&proposal_id)
+                .expect("Proposal not found");
+            let current_epoch = Runtime::current_epoch().number();
+
+            if proposal.executed {
+                return ProposalStatus::Executed;
+            }
+            if current_epoch <= proposal.voting_end_epoch {
+                return ProposalStatus::Active;
+            }
+
+            let total_voting_power = self.total_checkpoints.value_before(proposal.snapshot_epoch);
+            let participation =
+                proposal.for_votes + proposal.against_votes + proposal.abstain_votes;
+            let decisive_votes = proposal.for_votes + proposal.against_votes;
+
+            let passed = total_voting_power > Decimal::ZERO
+                && participation >= total_voting_power * self.parameters.quorum
+                && decisive_votes > Decimal::ZERO
+                && proposal.for_votes >= decisive_votes * self.parameters.approval_threshold;
+
+            if !passed {
+                ProposalStatus::Defeated
+            } else if current_epoch
+                <= proposal.voting_end_epoch + self.parameters.execution_delay_epochs
+            {
+                ProposalStatus::Queued
+            } else {
+                ProposalStatus::Executable
+            }
+        }
+
+        pub fn get_voting_power(&self, voter_id: NonFungibleLocalId) -> Decimal {
+            self._get_voting_power(&voter_id, Runtime::current_epoch().number() + 1)
+        }
+
+        pub fn get_parameters(&self) -> GovernanceParameters {
+            self.parameters.clone()
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_parameters(parameters: &GovernanceParameters) {
+            assert!(
+                parameters.voting_period_epochs > 0,
+                "Voting period must be greater than zero"
+            );
+            assert!(
+                parameters.quorum >= Decimal::ZERO && parameters.quorum <= Decimal::ONE,
+                "Quorum must be between 0 and 1"
+            );
+            assert!(
+                parameters.approval_threshold > Decimal::ZERO
+                    && parameters.approval_threshold <= Decimal::ONE,
+                "Approval threshold must be between 0 and 1"
+            );
+            assert!(
+                parameters.proposal_threshold >= Decimal::ZERO,
+                "Proposal threshold must not be negative"
+            );
+        }
+
+        /// Parameters set by a proposal targeting the DAO itself
+        fn _decode_self_call(method_name: &str, args: &[u8]) -> GovernanceParameters {
+            assert!(
+                method_name == "set_parameters",
+                "Proposals on the DAO can only call set_parameters"
+            );
+
+            let (parameters,): (GovernanceParameters,) =
+                scrypto_decode(args).expect("Invalid set_parameters arguments");
+
+            DaoGovernance::_assert_valid_parameters(&parameters);
+
+            parameters
+        }
+
+        fn _get_voting_power(&self, voter_id: &NonFungibleLocalId, snapshot_epoch: u64) -> Decimal {
+            self.voter_checkpoints
+                .get(voter_id)
+                .unwrap()
+                .value_before(snapshot_epoch)
+        }
+
+        fn _update_locked_amount(&mut self, voter_id: &NonFungibleLocalId, change: Decimal) {
+            let current_epoch = Runtime::current_epoch().number();
+
+            let locked_amount = self
+                .voter_checkpoints
+                .get_mut(voter_id)
+                .unwrap()
+                .push_change(current_epoch, change);
+
+            assert!(locked_amount >= Decimal::ZERO, "Not enough locked tokens");
+
+            self.total_checkpoints.push_change(current_epoch, change);
+        }
+
+        fn _check_voter_proof(&self, voter_proof: Proof) -> NonFungibleLocalId {
+            voter_proof
+                .check(self.voter_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+    }
+}
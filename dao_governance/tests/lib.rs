@@ -0,0 +1,365 @@
+use dao_governance::*;
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Holds 750 governance tokens
+const ALICE: usize = 0;
+/// Holds 200 governance tokens
+const BOB: usize = 1;
+/// Holds 50 governance tokens
+const CAROL: usize = 2;
+
+fn test_parameters(voting_period_epochs: u64) -> GovernanceParameters {
+    GovernanceParameters {
+        voting_period_epochs,
+        execution_delay_epochs: 2,
+        quorum: dec!("0.5"),
+        approval_threshold: dec!("0.5"),
+        proposal_threshold: dec!(100),
+    }
+}
+
+/// Manifest encoding of `GovernanceParameters`
+fn manifest_parameters(parameters: &GovernanceParameters) -> ManifestValue {
+    ManifestValue::Tuple {
+        fields: vec![
+            to_manifest_value_and_unwrap!(&parameters.voting_period_epochs),
+            to_manifest_value_and_unwrap!(&parameters.execution_delay_epochs),
+            to_manifest_value_and_unwrap!(&parameters.quorum),
+            to_manifest_value_and_unwrap!(&parameters.approval_threshold),
+            to_manifest_value_and_unwrap!(&parameters.proposal_threshold),
+        ],
+    }
+}
+
+/// Manifest encoding of a `VoteChoice`
+fn manifest_choice(choice: VoteChoice) -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: choice as u8,
+        fields: vec![],
+    }
+}
+
+/// DAO with a voting period of 5 epochs, an execution delay of 2 epochs, a quorum and approval
+/// threshold of 50% and a proposal threshold of 100. At epoch 10, Alice locks 300 tokens and
+/// Bob 200, then the epoch moves to 11.
+struct TestDao {
+    env: TestEnv,
+    dao: ComponentAddress,
+    voter_badge: ResourceAddress,
+    token: ResourceAddress,
+}
+
+impl TestDao {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let token = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, env.accounts[ALICE]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "DaoGovernance",
+                "instantiate",
+                manifest_args!(token, manifest_parameters(&test_parameters(5))),
+            )
+            .withdraw_from_account(env.accounts[ALICE], token, dec!(250))
+            .take_from_worktop(token, dec!(200), "bob")
+            .call_method_with_name_lookup(env.accounts[BOB], "deposit", |lookup| {
+                (lookup.bucket("bob"),)
+            })
+            .take_from_worktop(token, dec!(50), "carol")
+            .call_method_with_name_lookup(env.accounts[CAROL], "deposit", |lookup| {
+                (lookup.bucket("carol"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+
+        let mut dao = Self {
+            dao: commit.new_component_addresses()[0],
+            voter_badge: commit.new_resource_addresses()[0],
+            env,
+            token,
+        };
+
+        for voter in [ALICE, BOB, CAROL] {
+            let builder =
+                ManifestBuilder::new().call_method(dao.dao, "create_voter", manifest_args!());
+            dao.execute(voter, builder).expect_commit_success();
+        }
+        dao.lock(ALICE, dec!(300)).expect_commit_success();
+        dao.lock(BOB, dec!(200)).expect_commit_success();
+
+        dao.env.set_epoch(11);
+
+        dao
+    }
+
+    /// Execute the manifest with the keys of every account, then deposit the worktop into the
+    /// given account
+    fn execute(&mut self, account: usize, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute_with_all_keys(account, builder)
+    }
+
+    /// Proof of the voter badge of the account, popped as "voter"
+    fn with_voter_proof(&self, voter: usize) -> ManifestBuilder {
+        ManifestBuilder::new()
+            .create_proof_from_account_of_amount(
+                self.env.accounts[voter],
+                self.voter_badge,
+                dec!(1),
+            )
+            .pop_from_auth_zone("voter")
+    }
+
+    fn lock(&mut self, voter: usize, amount: Decimal) -> TransactionReceipt {
+        let builder = self
+            .with_voter_proof(voter)
+            .withdraw_from_account(self.env.accounts[voter], self.token, amount)
+            .take_all_from_worktop(self.token, "tokens")
+            .call_method_with_name_lookup(self.dao, "lock", |lookup| {
+                (lookup.proof("voter"), lookup.bucket("tokens"))
+            });
+
+        self.execute(voter, builder)
+    }
+
+    fn unlock(&mut self, voter: usize, amount: Decimal) -> TransactionReceipt {
+        let builder = self.with_voter_proof(voter).call_method_with_name_lookup(
+            self.dao,
+            "unlock",
+            |lookup| (lookup.proof("voter"), amount),
+        );
+
+        self.execute(voter, builder)
+    }
+
+    /// Propose to change the voting period of the DAO
+    fn propose(&mut self, voter: usize, voting_period_epochs: u64) -> TransactionReceipt {
+        let (dao, args) = (
+            self.dao,
+            scrypto_encode(&(test_parameters(voting_period_epochs),)).unwrap(),
+        );
+
+        self.propose_call(voter, dao, "set_parameters", args)
+    }
+
+    /// Propose to call a method of a component with SBOR encoded arguments
+    fn propose_call(
+        &mut self,
+        voter: usize,
+        component_address: ComponentAddress,
+        method_name: &str,
+        args: Vec<u8>,
+    ) -> TransactionReceipt {
+        let builder = self.with_voter_proof(voter).call_method_with_name_lookup(
+            self.dao,
+            "propose",
+            |lookup| {
+                (
+                    lookup.proof("voter"),
+                    "Call a method as the DAO".to_string(),
+                    component_address,
+                    method_name.to_string(),
+                    args,
+                )
+            },
+        );
+
+        self.execute(voter, builder)
+    }
+
+    fn vote(&mut self, voter: usize, proposal_id: u64, choice: VoteChoice) -> TransactionReceipt {
+        let builder =
+            self.with_voter_proof(voter)
+                .call_method_with_name_lookup(self.dao, "vote", |lookup| {
+                    (lookup.proof("voter"), proposal_id, manifest_choice(choice))
+                });
+
+        self.execute(voter, builder)
+    }
+
+    fn execute_proposal(&mut self, proposal_id: u64) -> TransactionReceipt {
+        let builder =
+            ManifestBuilder::new().call_method(self.dao, "execute", manifest_args!(proposal_id));
+
+        self.execute(ALICE, builder)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str, args: impl ResolvableArguments) -> T {
+        let builder = ManifestBuilder::new().call_method(self.dao, method, args);
+
+        self.execute(ALICE, builder)
+            .expect_commit_success()
+            .output(0)
+    }
+
+    fn status(&mut self, proposal_id: u64) -> ProposalStatus {
+        self.get("get_proposal_status", manifest_args!(proposal_id))
+    }
+
+    /// Id of the voter badge of the account
+    fn voter_id(&mut self, voter: usize) -> NonFungibleLocalId {
+        let vault_id = self
+            .env
+            .test_runner
+            .get_component_vaults(self.env.accounts[voter], self.voter_badge)[0];
+        let (_, mut ids) = self
+            .env
+            .test_runner
+            .inspect_non_fungible_vault(vault_id)
+            .unwrap();
+
+        ids.next().unwrap()
+    }
+}
+
+#[test]
+fn test_passed_proposal_is_executed_after_the_delay() {
+    let mut dao = TestDao::new();
+
+    dao.propose(ALICE, 10).expect_commit_success();
+    dao.vote(ALICE, 1, VoteChoice::For).expect_commit_success();
+    dao.vote(BOB, 1, VoteChoice::Against)
+        .expect_commit_success();
+
+    let proposal: Proposal = dao.get("get_proposal", manifest_args!(1u64));
+    assert_eq!(proposal.for_votes, dec!(300));
+    assert_eq!(proposal.against_votes, dec!(200));
+    assert_eq!(dao.status(1), ProposalStatus::Active);
+
+    dao.env.set_epoch(17);
+    assert_eq!(dao.status(1), ProposalStatus::Queued);
+    dao.execute_proposal(1).expect_commit_failure();
+
+    dao.env.set_epoch(19);
+    assert_eq!(dao.status(1), ProposalStatus::Executable);
+    dao.execute_proposal(1).expect_commit_success();
+
+    assert_eq!(dao.status(1), ProposalStatus::Executed);
+    let parameters: GovernanceParameters = dao.get("get_parameters", manifest_args!());
+    assert_eq!(parameters.voting_period_epochs, 10);
+
+    dao.execute_proposal(1).expect_commit_failure();
+}
+
+#[test]
+fn test_proposal_without_quorum_is_defeated() {
+    let mut dao = TestDao::new();
+
+    dao.propose(ALICE, 10).expect_commit_success();
+    dao.vote(BOB, 1, VoteChoice::For).expect_commit_success();
+
+    dao.env.set_epoch(19);
+    assert_eq!(dao.status(1), ProposalStatus::Defeated);
+    dao.execute_proposal(1).expect_commit_failure();
+}
+
+#[test]
+fn test_proposal_without_approval_is_defeated() {
+    let mut dao = TestDao::new();
+
+    dao.propose(ALICE, 10).expect_commit_success();
+    dao.vote(ALICE, 1, VoteChoice::Abstain)
+        .expect_commit_success();
+    dao.vote(BOB, 1, VoteChoice::Against)
+        .expect_commit_success();
+
+    dao.env.set_epoch(19);
+    assert_eq!(dao.status(1), ProposalStatus::Defeated);
+}
+
+#[test]
+fn test_propose_requires_the_proposal_threshold() {
+    let mut dao = TestDao::new();
+
+    dao.lock(CAROL, dec!(50)).expect_commit_success();
+    dao.env.set_epoch(12);
+
+    dao.propose(CAROL, 10).expect_commit_failure();
+}
+
+#[test]
+fn test_tokens_locked_after_the_snapshot_can_not_vote() {
+    let mut dao = TestDao::new();
+
+    dao.propose(ALICE, 10).expect_commit_success();
+    dao.lock(CAROL, dec!(50)).expect_commit_success();
+
+    dao.vote(CAROL, 1, VoteChoice::For).expect_commit_failure();
+}
+
+#[test]
+fn test_vote_only_once_while_the_proposal_is_active() {
+    let mut dao = TestDao::new();
+
+    dao.propose(ALICE, 10).expect_commit_success();
+    dao.vote(ALICE, 1, VoteChoice::For).expect_commit_success();
+    dao.vote(ALICE, 1, VoteChoice::For).expect_commit_failure();
+
+    dao.env.set_epoch(17);
+    dao.vote(BOB, 1, VoteChoice::For).expect_commit_failure();
+}
+
+#[test]
+fn test_unlock_keeps_the_cast_votes() {
+    let mut dao = TestDao::new();
+    let (token, alice_id) = (dao.token, dao.voter_id(ALICE));
+
+    dao.propose(ALICE, 10).expect_commit_success();
+    dao.vote(ALICE, 1, VoteChoice::For).expect_commit_success();
+
+    dao.unlock(ALICE, dec!(301)).expect_commit_failure();
+    dao.unlock(ALICE, dec!(300)).expect_commit_success();
+    assert_eq!(dao.env.balance(ALICE, token), dec!(750));
+    assert_eq!(
+        dao.get::<Decimal>("get_voting_power", manifest_args!(alice_id)),
+        dec!(0)
+    );
+
+    dao.env.set_epoch(19);
+    assert_eq!(dao.status(1), ProposalStatus::Executable);
+}
+
+#[test]
+fn test_parameters_can_only_be_set_by_a_proposal() {
+    let mut dao = TestDao::new();
+    let dao_address = dao.dao;
+
+    let builder = ManifestBuilder::new().call_method(
+        dao_address,
+        "set_parameters",
+        manifest_args!(manifest_parameters(&test_parameters(10))),
+    );
+    dao.execute(ALICE, builder).expect_commit_failure();
+}
+
+#[test]
+fn test_proposals_on_the_dao_can_only_set_valid_parameters() {
+    let mut dao = TestDao::new();
+    let dao_address = dao.dao;
+
+    let args = scrypto_encode(&(dec!(100),)).unwrap();
+    dao.propose_call(ALICE, dao_address, "unlock", args)
+        .expect_commit_failure();
+
+    let args = scrypto_encode(&(dec!(100),)).unwrap();
+    dao.propose_call(ALICE, dao_address, "set_parameters", args)
+        .expect_commit_failure();
+
+    let mut parameters = test_parameters(10);
+    parameters.quorum = dec!(2);
+    let args = scrypto_encode(&(parameters,)).unwrap();
+    dao.propose_call(ALICE, dao_address, "set_parameters", args)
+        .expect_commit_failure();
+
+    dao.propose(ALICE, 10).expect_commit_success();
+}
@@ -0,0 +1,282 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+use swap_router::swap_router::SwapRouter;
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct PlanReceipt {
+    pub source_res_address: ResourceAddress,
+    pub target_res_address: ResourceAddress,
+}
+
+#[derive(ScryptoSbor)]
+pub struct DcaPlan {
+    /// Source assets not swapped yet
+    pub source: Vault,
+    /// Accumulated target assets
+    pub target: Vault,
+    /// Source amount swapped at each execution
+    pub amount_per_interval: Decimal,
+    pub interval_epochs: u64,
+    /// First epoch at which the next execution can be triggered
+    pub next_execution_epoch: u64,
+    /// Pairs registered in the swap router, from the source to the target resource
+    pub path: Vec<ComponentAddress>,
+    /// Minimum amount of target assets received per source asset swapped
+    pub min_price: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PlanExecutedEvent {
+    pub plan_id: NonFungibleLocalId,
+    pub source_amount: Decimal,
+    pub target_amount: Decimal,
+}
+
+#[blueprint]
+#[events(PlanExecutedEvent)]
+pub mod dca_scheduler {
+
+    enable_method_auth! {
+        roles {
+            keeper => updatable_by: [OWNER];
+        },
+        methods {
+
+            set_router => restrict_to :[OWNER];
+
+            execute => restrict_to :[keeper, OWNER];
+
+            create_plan => PUBLIC;
+            top_up => PUBLIC;
+            set_min_price => PUBLIC;
+            withdraw => PUBLIC;
+            close_plan => PUBLIC;
+
+            get_plan => PUBLIC;
+
+        }
+    }
+
+    pub struct DcaScheduler {
+        /// Router through which plans are executed
+        router: Global<SwapRouter>,
+
+        /// Plan receipt non-fungible resource manager
+        plan_res_manager: ResourceManager,
+        plans: KeyValueStore<NonFungibleLocalId, DcaPlan>,
+    }
+
+    impl DcaScheduler {
+        pub fn instantiate(
+            router: Global<SwapRouter>,
+            owner_role: OwnerRole,
+            keeper_rule: AccessRule,
+        ) -> (Global<DcaScheduler>, ResourceAddress) {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(DcaScheduler::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let plan_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<PlanReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule;
+                        burner_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let dca_component = Self {
+                router,
+                plan_res_manager,
+                plans: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                keeper => keeper_rule;
+            ))
+            .with_address(address_reservation)
+            .globalize();
+
+            (dca_component, plan_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn set_router(&mut self, router: Global<SwapRouter>) {
+            self.router = router;
+        }
+
+        /* KEEPER METHODS */
+
+        /// Swap the amount of the current interval of a plan through its path. A plan can be
+        /// executed at most once per interval. The last execution swaps whatever is left.
+        pub fn execute(&mut self, plan_id: NonFungibleLocalId) {
+            let (input, path, min_price) = {
+                let mut plan = self.plans.get_mut(&plan_id).expect("Plan not found");
+
+                assert!(
+                    Runtime::current_epoch().number() >= plan.next_execution_epoch,
+                    "Plan already executed for the current interval"
+                );
+                assert!(!plan.source.is_empty(), "Plan has no source assets left");
+
+                let amount = plan.amount_per_interval.min(plan.source.amount());
+                plan.next_execution_epoch =
+                    Runtime::current_epoch().number() + plan.interval_epochs;
+
+                (plan.source.take(amount), plan.path.clone(), plan.min_price)
+            };
+
+            let source_amount = input.amount();
+            let output = self.router.swap_via(input, path, source_amount * min_price);
+            let target_amount = output.amount();
+
+            self.plans.get_mut(&plan_id).unwrap().target.put(output);
+
+            Runtime::emit_event(PlanExecutedEvent {
+                plan_id,
+                source_amount,
+                target_amount,
+            });
+        }
+
+        /* USER METHODS */
+
+        /// Create a plan swapping `amount_per_interval` of the funds into the target resource
+        /// every `interval_epochs`, through the given swap router path. The first execution
+        /// can be triggered immediately. Returns the plan receipt.
+        pub fn create_plan(
+            &mut self,
+            funds: Bucket,
+            target_res_address: ResourceAddress,
+            amount_per_interval: Decimal,
+            interval_epochs: u64,
+            path: Vec<ComponentAddress>,
+            min_price: Decimal,
+        ) -> Bucket {
+            let source_res_address = funds.resource_address();
+
+            /* CHECK INPUT */
+            assert!(
+                source_res_address != target_res_address,
+                "Source and target resources must be different"
+            );
+            assert!(
+                amount_per_interval > Decimal::ZERO,
+                "Amount per interval must be greater than zero!"
+            );
+            assert!(interval_epochs > 0, "Interval must be greater than zero");
+            assert!(!path.is_empty(), "Path must not be empty");
+            assert!(min_price >= Decimal::ZERO, "Min price must not be negative");
+
+            let receipt = self.plan_res_manager.mint_ruid_non_fungible(PlanReceipt {
+                source_res_address,
+                target_res_address,
+            });
+
+            self.plans.insert(
+                receipt.as_non_fungible().non_fungible_local_id(),
+                DcaPlan {
+                    source: Vault::with_bucket(funds),
+                    target: Vault::new(target_res_address),
+                    amount_per_interval,
+                    interval_epochs,
+                    next_execution_epoch: Runtime::current_epoch().number(),
+                    path,
+                    min_price,
+                },
+            );
+
+            receipt
+        }
+
+        pub fn top_up(&mut self, plan_proof: Proof, funds: Bucket) {
+            let plan_id = self._check_receipt(plan_proof);
+
+            self.plans.get_mut(&plan_id).unwrap().source.put(funds);
+        }
+
+        pub fn set_min_price(&mut self, plan_proof: Proof, min_price: Decimal) {
+            let plan_id = self._check_receipt(plan_proof);
+
+            /* CHECK INPUT */
+            assert!(min_price >= Decimal::ZERO, "Min price must not be negative");
+
+            self.plans.get_mut(&plan_id).unwrap().min_price = min_price;
+        }
+
+        /// Withdraw the target assets accumulated by a plan
+        pub fn withdraw(&mut self, plan_proof: Proof) -> Bucket {
+            let plan_id = self._check_receipt(plan_proof);
+
+            self.plans.get_mut(&plan_id).unwrap().target.take_all()
+        }
+
+        /// Close a plan, burning its receipt. Returns the remaining source assets and the
+        /// accumulated target assets.
+        pub fn close_plan(&mut self, plan_receipt: Bucket) -> (Bucket, Bucket) {
+            /* CHECK INPUT */
+            assert!(
+                plan_receipt.resource_address() == self.plan_res_manager.address(),
+                "Plan receipt resource address mismatch"
+            );
+
+            let plan_id = plan_receipt.as_non_fungible().non_fungible_local_id();
+            plan_receipt.burn();
+
+            let mut plan = self.plans.get_mut(&plan_id).unwrap();
+
+            (plan.source.take_all(), plan.target.take_all())
+        }
+
+        /* GETTERS */
+
+        /// Remaining source amount, accumulated target amount and next execution epoch of a
+        /// plan
+        pub fn get_plan(&self, plan_id: NonFungibleLocalId) -> (Decimal, Decimal, u64) {
+            let plan = self.plans.get(&plan_id).expect("Plan not found");
+
+            (
+                plan.source.amount(),
+                plan.target.amount(),
+                plan.next_execution_epoch,
+            )
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _check_receipt(&self, plan_proof: Proof) -> NonFungibleLocalId {
+            plan_proof
+                .check(self.plan_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+    }
+}
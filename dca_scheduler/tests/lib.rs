@@ -0,0 +1,326 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the router and provides the pair liquidity
+const OWNER: usize = 0;
+const KEEPER: usize = 1;
+/// Holds 1000 A tokens
+const ALICE: usize = 2;
+
+/// Scheduler with a keeper role, executing plans through a router over a Radiswap pair without
+/// fee holding 1000 A and 1000 B, at epoch 10. The router and pair blueprints are linked into
+/// the scheduler package, so they are instantiated from the same package.
+struct TestScheduler {
+    env: TestEnv,
+    scheduler: ComponentAddress,
+    router: ComponentAddress,
+    plan_receipt: ResourceAddress,
+    pair: ComponentAddress,
+    res_a: ResourceAddress,
+    res_b: ResourceAddress,
+}
+
+impl TestScheduler {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let res_a = env
+            .test_runner
+            .create_fungible_resource(dec!(2000), 18, env.accounts[OWNER]);
+        let res_b = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "SwapRouter",
+                "instantiate",
+                manifest_args!(owner_role.clone()),
+            )
+            .call_function(
+                env.package_address,
+                "RadiswapPair",
+                "instantiate",
+                manifest_args!(res_a, res_b, dec!(0), owner_role.clone()),
+            )
+            .withdraw_from_account(env.accounts[OWNER], res_a, dec!(1000))
+            .take_all_from_worktop(res_a, "alice")
+            .call_method_with_name_lookup(env.accounts[ALICE], "deposit", |lookup| {
+                (lookup.bucket("alice"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+        let (router, pair) = (
+            commit.new_component_addresses()[0],
+            commit.new_component_addresses()[1],
+        );
+
+        // Manifest encoding of `AmmPool::Radiswap`
+        let pool = ManifestValue::Enum {
+            discriminator: 0,
+            fields: vec![to_manifest_value_and_unwrap!(&pair)],
+        };
+
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account(env.accounts[OWNER], res_a, dec!(1000))
+            .withdraw_from_account(env.accounts[OWNER], res_b, dec!(1000))
+            .take_all_from_worktop(res_a, "bucket_a")
+            .take_all_from_worktop(res_b, "bucket_b")
+            .call_method_with_name_lookup(pair, "add_liquidity", |lookup| {
+                (lookup.bucket("bucket_a"), lookup.bucket("bucket_b"))
+            })
+            .call_method(router, "register_pair", manifest_args!(pool, res_a, res_b))
+            .call_function(
+                env.package_address,
+                "DcaScheduler",
+                "instantiate",
+                manifest_args!(
+                    router,
+                    owner_role,
+                    rule!(require(env.badges[KEEPER].clone()))
+                ),
+            )
+            .deposit_batch(env.accounts[OWNER])
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            scheduler: commit.new_component_addresses()[0],
+            plan_receipt: commit.new_resource_addresses()[0],
+            env,
+            router,
+            pair,
+            res_a,
+            res_b,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.scheduler, method, args)
+    }
+
+    /// Create a plan for Alice, swapping 100 of the funds every 5 epochs without min price
+    fn create_plan(
+        &mut self,
+        funds: Decimal,
+        target_res_address: ResourceAddress,
+        interval_epochs: u64,
+        path: Vec<ComponentAddress>,
+    ) -> TransactionReceipt {
+        let res_a = self.res_a;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[ALICE], res_a, funds)
+            .take_all_from_worktop(res_a, "funds")
+            .call_method_with_name_lookup(self.scheduler, "create_plan", |lookup| {
+                (
+                    lookup.bucket("funds"),
+                    target_res_address,
+                    dec!(100),
+                    interval_epochs,
+                    path,
+                    dec!(0),
+                )
+            });
+
+        self.env.execute(ALICE, builder)
+    }
+
+    /// Create a plan for Alice swapping 250 A into B, and return its id
+    fn create_default_plan(&mut self) -> NonFungibleLocalId {
+        let (res_b, pair) = (self.res_b, self.pair);
+        self.create_plan(dec!(250), res_b, 5, vec![pair])
+            .expect_commit_success();
+
+        let vault_id = self
+            .env
+            .test_runner
+            .get_component_vaults(self.env.accounts[ALICE], self.plan_receipt)[0];
+        let (_, mut ids) = self
+            .env
+            .test_runner
+            .inspect_non_fungible_vault(vault_id)
+            .unwrap();
+
+        ids.next().unwrap()
+    }
+
+    /// Call a plan method of Alice with a proof of her plan receipt
+    fn call_with_receipt<A: ResolvableArguments>(
+        &mut self,
+        method: &str,
+        args: impl FnOnce(ManifestProof) -> A,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(
+                self.env.accounts[ALICE],
+                self.plan_receipt,
+                dec!(1),
+            )
+            .pop_from_auth_zone("receipt")
+            .call_method_with_name_lookup(self.scheduler, method, |lookup| {
+                args(lookup.proof("receipt"))
+            });
+
+        self.env.execute(ALICE, builder)
+    }
+
+    fn get_plan(&mut self, plan_id: &NonFungibleLocalId) -> (Decimal, Decimal, u64) {
+        self.call(ALICE, "get_plan", manifest_args!(plan_id.clone()))
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_plan_is_executed_once_per_interval() {
+    let mut scheduler = TestScheduler::new();
+    let (res_a, res_b, plan_receipt) = (scheduler.res_a, scheduler.res_b, scheduler.plan_receipt);
+
+    let plan_id = scheduler.create_default_plan();
+    assert_eq!(scheduler.env.balance(ALICE, res_a), dec!(750));
+
+    scheduler
+        .call(KEEPER, "execute", manifest_args!(plan_id.clone()))
+        .expect_commit_success();
+    scheduler
+        .call(KEEPER, "execute", manifest_args!(plan_id.clone()))
+        .expect_commit_failure();
+
+    let (source, first_target, next_execution_epoch) = scheduler.get_plan(&plan_id);
+    assert_eq!(source, dec!(150));
+    assert!(first_target > dec!(0));
+    assert_eq!(next_execution_epoch, 15);
+
+    scheduler.env.set_epoch(15);
+    scheduler
+        .call(KEEPER, "execute", manifest_args!(plan_id.clone()))
+        .expect_commit_success();
+
+    // The last execution swaps the remaining 50
+    scheduler.env.set_epoch(20);
+    scheduler
+        .call(KEEPER, "execute", manifest_args!(plan_id.clone()))
+        .expect_commit_success();
+
+    scheduler.env.set_epoch(25);
+    scheduler
+        .call(KEEPER, "execute", manifest_args!(plan_id.clone()))
+        .expect_commit_failure();
+
+    let (source, target, _) = scheduler.get_plan(&plan_id);
+    assert_eq!(source, dec!(0));
+    assert!(target > first_target);
+
+    scheduler
+        .call_with_receipt("withdraw", |proof| (proof,))
+        .expect_commit_success();
+    assert_eq!(scheduler.env.balance(ALICE, res_b), target);
+
+    // Top ups are executed at the next interval
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(scheduler.env.accounts[ALICE], res_a, dec!(50))
+        .take_all_from_worktop(res_a, "funds")
+        .create_proof_from_account_of_amount(scheduler.env.accounts[ALICE], plan_receipt, dec!(1))
+        .pop_from_auth_zone("receipt")
+        .call_method_with_name_lookup(scheduler.scheduler, "top_up", |lookup| {
+            (lookup.proof("receipt"), lookup.bucket("funds"))
+        });
+    scheduler
+        .env
+        .execute(ALICE, builder)
+        .expect_commit_success();
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(scheduler.env.accounts[ALICE], plan_receipt, dec!(1))
+        .take_all_from_worktop(plan_receipt, "receipt")
+        .call_method_with_name_lookup(scheduler.scheduler, "close_plan", |lookup| {
+            (lookup.bucket("receipt"),)
+        });
+    scheduler
+        .env
+        .execute(ALICE, builder)
+        .expect_commit_success();
+    assert_eq!(scheduler.env.balance(ALICE, res_a), dec!(750));
+    assert_eq!(scheduler.env.balance(ALICE, plan_receipt), dec!(0));
+}
+
+#[test]
+fn test_min_price_protects_executions() {
+    let mut scheduler = TestScheduler::new();
+
+    let plan_id = scheduler.create_default_plan();
+
+    // 100 A give less than 100 B
+    scheduler
+        .call_with_receipt("set_min_price", |proof| (proof, dec!(1)))
+        .expect_commit_success();
+    scheduler
+        .call(KEEPER, "execute", manifest_args!(plan_id.clone()))
+        .expect_commit_failure();
+
+    scheduler
+        .call_with_receipt("set_min_price", |proof| (proof, dec!(-1)))
+        .expect_commit_failure();
+    scheduler
+        .call_with_receipt("set_min_price", |proof| (proof, dec!("0.5")))
+        .expect_commit_success();
+    scheduler
+        .call(KEEPER, "execute", manifest_args!(plan_id.clone()))
+        .expect_commit_success();
+
+    assert_eq!(scheduler.get_plan(&plan_id).0, dec!(150));
+}
+
+#[test]
+fn test_create_plan_rejects_invalid_plans() {
+    let mut scheduler = TestScheduler::new();
+    let (res_a, res_b, pair) = (scheduler.res_a, scheduler.res_b, scheduler.pair);
+
+    scheduler
+        .create_plan(dec!(250), res_a, 5, vec![pair])
+        .expect_commit_failure();
+    scheduler
+        .create_plan(dec!(250), res_b, 0, vec![pair])
+        .expect_commit_failure();
+    scheduler
+        .create_plan(dec!(250), res_b, 5, vec![])
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_execute_requires_the_keeper() {
+    let mut scheduler = TestScheduler::new();
+
+    let plan_id = scheduler.create_default_plan();
+
+    scheduler
+        .call(ALICE, "execute", manifest_args!(plan_id.clone()))
+        .expect_commit_failure();
+    scheduler
+        .call(OWNER, "execute", manifest_args!(plan_id))
+        .expect_commit_success();
+
+    let router = scheduler.router;
+    scheduler
+        .call(KEEPER, "set_router", manifest_args!(router))
+        .expect_commit_failure();
+    scheduler
+        .call(OWNER, "set_router", manifest_args!(router))
+        .expect_commit_success();
+}
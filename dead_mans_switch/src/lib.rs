@@ -0,0 +1,268 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+/// Maximum number of beneficiaries
+pub const MAX_BENEFICIARIES: usize = 20;
+
+/// Maximum number of different deposited resources
+pub const MAX_ASSETS: usize = 20;
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PingEvent {
+    pub ping_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct InheritanceClaimedEvent {
+    pub account: ComponentAddress,
+    pub amounts: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[blueprint]
+#[events(PingEvent, InheritanceClaimedEvent)]
+pub mod dead_mans_switch {
+
+    enable_method_auth! {
+        methods {
+
+            ping => restrict_to :[OWNER];
+            deposit => restrict_to :[OWNER];
+            withdraw => restrict_to :[OWNER];
+            set_beneficiaries => restrict_to :[OWNER];
+            set_ping_window => restrict_to :[OWNER];
+
+            claim => PUBLIC;
+
+            get_deadline_epoch => PUBLIC;
+            is_triggered => PUBLIC;
+            get_beneficiaries => PUBLIC;
+            get_assets => PUBLIC;
+
+        }
+    }
+
+    pub struct DeadMansSwitch {
+        assets: IndexMap<ResourceAddress, Vault>,
+
+        /// Shares of the assets of each beneficiary account
+        beneficiaries: IndexMap<ComponentAddress, Decimal>,
+
+        ping_window_epochs: u64,
+        last_ping_epoch: u64,
+
+        /// Asset amounts when the switch triggered, taken on the first claim
+        triggered_amounts: Option<IndexMap<ResourceAddress, Decimal>>,
+
+        claimed: KeyValueStore<ComponentAddress, ()>,
+    }
+
+    impl DeadMansSwitch {
+        pub fn instantiate(
+            owner_role: OwnerRole,
+            beneficiaries: IndexMap<ComponentAddress, Decimal>,
+            ping_window_epochs: u64,
+        ) -> Global<DeadMansSwitch> {
+            /* CHECK INPUT */
+            DeadMansSwitch::_assert_valid_beneficiaries(&beneficiaries);
+            assert!(
+                ping_window_epochs > 0,
+                "Ping window must be greater than zero"
+            );
+
+            Self {
+                assets: IndexMap::new(),
+                beneficiaries,
+                ping_window_epochs,
+                last_ping_epoch: Runtime::current_epoch().number(),
+                triggered_amounts: None,
+                claimed: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .globalize()
+        }
+
+        /* OWNER METHODS */
+
+        /// Prove the owner is still active, pushing the deadline back
+        pub fn ping(&mut self) {
+            self._ping();
+        }
+
+        /// Deposit fungible assets. Depositing also pings.
+        pub fn deposit(&mut self, assets: Bucket) {
+            self._ping();
+
+            /* CHECK INPUT */
+            let res_address = assets.resource_address();
+
+            assert_fungible_res_address(res_address, Some("Assets must be fungible".to_string()));
+
+            match self.assets.get_mut(&res_address) {
+                Some(vault) => vault.put(assets),
+                None => {
+                    assert!(self.assets.len() < MAX_ASSETS, "Too many assets");
+                    self.assets.insert(res_address, Vault::with_bucket(assets));
+                }
+            }
+        }
+
+        /// Withdraw assets. Withdrawing also pings.
+        pub fn withdraw(&mut self, res_address: ResourceAddress, amount: Decimal) -> Bucket {
+            self._ping();
+
+            self.assets
+                .get_mut(&res_address)
+                .expect("Asset not found")
+                .take(amount)
+        }
+
+        pub fn set_beneficiaries(&mut self, beneficiaries: IndexMap<ComponentAddress, Decimal>) {
+            self._ping();
+
+            /* CHECK INPUT */
+            DeadMansSwitch::_assert_valid_beneficiaries(&beneficiaries);
+
+            self.beneficiaries = beneficiaries;
+        }
+
+        pub fn set_ping_window(&mut self, ping_window_epochs: u64) {
+            /* CHECK INPUT */
+            assert!(
+                ping_window_epochs > 0,
+                "Ping window must be greater than zero"
+            );
+
+            self._ping();
+
+            self.ping_window_epochs = ping_window_epochs;
+        }
+
+        /* BENEFICIARY METHODS */
+
+        /// Claim the share of a beneficiary once the switch has triggered. The assets are
+        /// deposited to the beneficiary account, so anyone can submit the claim.
+        pub fn claim(&mut self, mut account: Global<Account>) {
+            let account_address = account.address();
+
+            /* CHECK INPUT */
+            assert!(self.is_triggered(), "Switch has not triggered");
+
+            let share = *self
+                .beneficiaries
+                .get(&account_address)
+                .expect("Account is not a beneficiary");
+
+            assert!(
+                self.claimed.get(&account_address).is_none(),
+                "Inheritance already claimed"
+            );
+
+            if self.triggered_amounts.is_none() {
+                self.triggered_amounts = Some(
+                    self.assets
+                        .iter()
+                        .map(|(res_address, vault)| (*res_address, vault.amount()))
+                        .collect(),
+                );
+            }
+
+            self.claimed.insert(account_address, ());
+
+            let total_shares = self
+                .beneficiaries
+                .values()
+                .fold(Decimal::ZERO, |total, share| total + *share);
+
+            let mut amounts = IndexMap::new();
+            let mut inheritance = Vec::new();
+
+            for (res_address, triggered_amount) in self.triggered_amounts.as_ref().unwrap() {
+                let bucket = self.assets.get_mut(res_address).unwrap().take_advanced(
+                    *triggered_amount * share / total_shares,
+                    WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                );
+
+                amounts.insert(*res_address, bucket.amount());
+                inheritance.push(bucket);
+            }
+
+            Runtime::emit_event(InheritanceClaimedEvent {
+                account: account_address,
+                amounts,
+            });
+
+            account.try_deposit_batch_or_abort(inheritance, None);
+        }
+
+        /* GETTERS */
+
+        pub fn get_deadline_epoch(&self) -> u64 {
+            self.last_ping_epoch + self.ping_window_epochs
+        }
+
+        /// Whether the owner has failed to ping in time
+        pub fn is_triggered(&self) -> bool {
+            Runtime::current_epoch().number() >= self.get_deadline_epoch()
+        }
+
+        pub fn get_beneficiaries(&self) -> IndexMap<ComponentAddress, Decimal> {
+            self.beneficiaries.clone()
+        }
+
+        pub fn get_assets(&self) -> IndexMap<ResourceAddress, Decimal> {
+            self.assets
+                .iter()
+                .map(|(res_address, vault)| (*res_address, vault.amount()))
+                .collect()
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        /// Once the switch has triggered, the owner can no longer act on the assets
+        fn _ping(&mut self) {
+            assert!(!self.is_triggered(), "Switch has triggered");
+
+            self.last_ping_epoch = Runtime::current_epoch().number();
+
+            Runtime::emit_event(PingEvent {
+                ping_epoch: self.last_ping_epoch,
+            });
+        }
+
+        fn _assert_valid_beneficiaries(beneficiaries: &IndexMap<ComponentAddress, Decimal>) {
+            assert!(!beneficiaries.is_empty(), "No beneficiary");
+            assert!(
+                beneficiaries.len() <= MAX_BENEFICIARIES,
+                "Too many beneficiaries"
+            );
+            assert!(
+                beneficiaries.values().all(|share| *share > Decimal::ZERO),
+                "Shares must be greater than zero"
+            );
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the switch, and holds 20 tokens left after the deposit
+const OWNER: usize = 0;
+/// Beneficiary with 3 shares
+const ALICE: usize = 1;
+/// Beneficiary with 1 share
+const BOB: usize = 2;
+
+/// Switch holding 80 tokens of the owner at epoch 10, with a ping window of 10 epochs
+struct TestSwitch {
+    env: TestEnv,
+    switch: ComponentAddress,
+    token: ResourceAddress,
+}
+
+impl TestSwitch {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let token = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[OWNER]);
+
+        let mut beneficiaries = IndexMap::<ComponentAddress, Decimal>::new();
+        beneficiaries.insert(env.accounts[ALICE], dec!(3));
+        beneficiaries.insert(env.accounts[BOB], dec!(1));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "DeadMansSwitch",
+                "instantiate",
+                manifest_args!(
+                    OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone()))),
+                    beneficiaries,
+                    10u64
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let switch = receipt.expect_commit_success().new_component_addresses()[0];
+
+        let mut switch = Self { env, switch, token };
+
+        switch.deposit(token, dec!(80)).expect_commit_success();
+
+        switch
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.switch, method, args)
+    }
+
+    /// Deposit the given resource of the owner
+    fn deposit(&mut self, res_address: ResourceAddress, amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[OWNER], res_address, amount)
+            .take_all_from_worktop(res_address, "assets")
+            .call_method_with_name_lookup(self.switch, "deposit", |lookup| {
+                (lookup.bucket("assets"),)
+            });
+
+        self.env.execute(OWNER, builder)
+    }
+
+    /// Claim the inheritance of the beneficiary, submitted by Bob
+    fn claim(&mut self, beneficiary: usize) -> TransactionReceipt {
+        let account_address = self.env.accounts[beneficiary];
+
+        self.call(BOB, "claim", manifest_args!(account_address))
+    }
+}
+
+#[test]
+fn test_beneficiaries_claim_after_the_deadline() {
+    let mut switch = TestSwitch::new();
+    let token = switch.token;
+
+    // Pinging at epoch 15 pushes the deadline to epoch 25
+    switch.env.set_epoch(15);
+    switch
+        .call(OWNER, "ping", manifest_args!())
+        .expect_commit_success();
+
+    switch.env.set_epoch(20);
+    switch.claim(ALICE).expect_commit_failure();
+
+    switch.env.set_epoch(25);
+    let is_triggered: bool = switch
+        .call(BOB, "is_triggered", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert!(is_triggered);
+
+    switch
+        .call(OWNER, "ping", manifest_args!())
+        .expect_commit_failure();
+    switch
+        .call(OWNER, "withdraw", manifest_args!(token, dec!(10)))
+        .expect_commit_failure();
+
+    switch.claim(ALICE).expect_commit_success();
+    assert_eq!(switch.env.balance(ALICE, token), dec!(60));
+    switch.claim(ALICE).expect_commit_failure();
+
+    switch.claim(BOB).expect_commit_success();
+    assert_eq!(switch.env.balance(BOB, token), dec!(20));
+
+    switch.claim(OWNER).expect_commit_failure();
+}
+
+#[test]
+fn test_owner_manages_the_switch() {
+    let mut switch = TestSwitch::new();
+    let token = switch.token;
+    let (alice, bob) = (switch.env.accounts[ALICE], switch.env.accounts[BOB]);
+
+    switch
+        .call(ALICE, "ping", manifest_args!())
+        .expect_commit_failure();
+    switch
+        .call(OWNER, "set_ping_window", manifest_args!(0u64))
+        .expect_commit_failure();
+
+    switch
+        .call(
+            OWNER,
+            "set_beneficiaries",
+            manifest_args!(IndexMap::<ComponentAddress, Decimal>::new()),
+        )
+        .expect_commit_failure();
+
+    let mut beneficiaries = IndexMap::<ComponentAddress, Decimal>::new();
+    beneficiaries.insert(alice, dec!(0));
+    beneficiaries.insert(bob, dec!(1));
+    switch
+        .call(
+            OWNER,
+            "set_beneficiaries",
+            manifest_args!(beneficiaries.clone()),
+        )
+        .expect_commit_failure();
+
+    beneficiaries.remove(&alice);
+    switch
+        .call(
+            OWNER,
+            "set_beneficiaries",
+            manifest_args!(beneficiaries.clone()),
+        )
+        .expect_commit_success();
+
+    let current_beneficiaries: IndexMap<ComponentAddress, Decimal> = switch
+        .call(BOB, "get_beneficiaries", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(current_beneficiaries, beneficiaries);
+
+    // Only fungible assets are held by the switch
+    let nft = switch
+        .env
+        .test_runner
+        .create_non_fungible_resource(switch.env.accounts[OWNER]);
+    switch.deposit(nft, dec!(1)).expect_commit_failure();
+
+    switch
+        .call(OWNER, "withdraw", manifest_args!(token, dec!(30)))
+        .expect_commit_success();
+    assert_eq!(switch.env.balance(OWNER, token), dec!(50));
+
+    switch.env.set_epoch(20);
+    switch.claim(ALICE).expect_commit_failure();
+    switch.claim(BOB).expect_commit_success();
+    assert_eq!(switch.env.balance(BOB, token), dec!(50));
+}
+
+#[test]
+fn test_claims_share_every_asset_held_at_the_trigger() {
+    let mut switch = TestSwitch::new();
+    let token = switch.token;
+
+    // 10 indivisible tokens can not be shared exactly between 3 and 1 shares
+    let whole_token =
+        switch
+            .env
+            .test_runner
+            .create_fungible_resource(dec!(10), 0, switch.env.accounts[OWNER]);
+    switch
+        .deposit(whole_token, dec!(10))
+        .expect_commit_success();
+
+    let deadline_epoch: u64 = switch
+        .call(BOB, "get_deadline_epoch", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(deadline_epoch, 20);
+
+    switch.env.set_epoch(19);
+    let is_triggered: bool = switch
+        .call(BOB, "is_triggered", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert!(!is_triggered);
+
+    // The shares are computed on the amounts held at the first claim
+    switch.env.set_epoch(20);
+    switch.claim(ALICE).expect_commit_success();
+    assert_eq!(switch.env.balance(ALICE, token), dec!(60));
+    assert_eq!(switch.env.balance(ALICE, whole_token), dec!(7));
+
+    switch.claim(BOB).expect_commit_success();
+    assert_eq!(switch.env.balance(BOB, token), dec!(20));
+    assert_eq!(switch.env.balance(BOB, whole_token), dec!(2));
+
+    let assets: IndexMap<ResourceAddress, Decimal> = switch
+        .call(BOB, "get_assets", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(assets.get(&token), Some(&dec!(0)));
+    assert_eq!(assets.get(&whole_token), Some(&dec!(1)));
+}
+
+#[test]
+fn test_failures_carry_switch_messages() {
+    let mut switch = TestSwitch::new();
+    let token = switch.token;
+
+    expect_failure_containing(switch.claim(ALICE), "Switch has not triggered");
+
+    let nft = switch
+        .env
+        .test_runner
+        .create_non_fungible_resource(switch.env.accounts[OWNER]);
+    expect_failure_containing(switch.deposit(nft, dec!(1)), "Assets must be fungible");
+    expect_failure_containing(
+        switch.call(OWNER, "withdraw", manifest_args!(nft, dec!(1))),
+        "Asset not found",
+    );
+    expect_failure_containing(
+        switch.call(OWNER, "set_ping_window", manifest_args!(0u64)),
+        "Ping window must be greater than zero",
+    );
+
+    expect_failure_containing(
+        switch.call(
+            OWNER,
+            "set_beneficiaries",
+            manifest_args!(IndexMap::<ComponentAddress, Decimal>::new()),
+        ),
+        "No beneficiary",
+    );
+    let too_many_beneficiaries: IndexMap<ComponentAddress, Decimal> = (1..=21)
+        .map(|key| {
+            let public_key = Secp256k1PrivateKey::from_u64(key).unwrap().public_key();
+
+            (
+                ComponentAddress::virtual_account_from_public_key(&public_key),
+                dec!(1),
+            )
+        })
+        .collect();
+    expect_failure_containing(
+        switch.call(
+            OWNER,
+            "set_beneficiaries",
+            manifest_args!(too_many_beneficiaries),
+        ),
+        "Too many beneficiaries",
+    );
+    let mut beneficiaries = IndexMap::<ComponentAddress, Decimal>::new();
+    beneficiaries.insert(switch.env.accounts[ALICE], dec!(-1));
+    expect_failure_containing(
+        switch.call(OWNER, "set_beneficiaries", manifest_args!(beneficiaries)),
+        "Shares must be greater than zero",
+    );
+
+    switch.env.set_epoch(20);
+    expect_failure_containing(
+        switch.call(OWNER, "ping", manifest_args!()),
+        "Switch has triggered",
+    );
+    expect_failure_containing(switch.deposit(token, dec!(10)), "Switch has triggered");
+    expect_failure_containing(switch.claim(OWNER), "Account is not a beneficiary");
+
+    switch.claim(ALICE).expect_commit_success();
+    expect_failure_containing(switch.claim(ALICE), "Inheritance already claimed");
+}
@@ -0,0 +1,182 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct LotSoldEvent {
+    pub price: Decimal,
+    pub epoch: u64,
+}
+
+#[blueprint]
+#[events(LotSoldEvent)]
+pub mod dutch_auction {
+
+    enable_method_auth! {
+        methods {
+
+            withdraw_proceeds => restrict_to :[OWNER];
+            reclaim_lot => restrict_to :[OWNER];
+
+            bid => PUBLIC;
+
+            get_current_price => PUBLIC;
+            is_sold => PUBLIC;
+
+        }
+    }
+
+    pub struct DutchAuction {
+        /// Auctioned fungible or non fungible resources, sold as a single lot
+        lot: Vault,
+
+        /// Payment of the winning bid
+        proceeds: Vault,
+
+        /// Price of the whole lot at the start epoch
+        start_price: Decimal,
+
+        /// Price of the whole lot at the end epoch
+        end_price: Decimal,
+
+        start_epoch: u64,
+        end_epoch: u64,
+
+        sold: bool,
+    }
+
+    impl DutchAuction {
+        /// Instantiate an auction starting at the current epoch. Returns the auction component
+        /// and the seller badge, which is required to withdraw the proceeds or reclaim the
+        /// unsold lot.
+        pub fn instantiate(
+            lot: Bucket,
+            payment_res_address: ResourceAddress,
+            start_price: Decimal,
+            end_price: Decimal,
+            duration_epochs: u64,
+        ) -> (Global<DutchAuction>, Bucket) {
+            /* CHECK INPUT */
+            assert!(!lot.is_empty(), "Lot must not be empty");
+            assert_fungible_res_address(
+                payment_res_address,
+                Some("Payment resource must be fungible".to_string()),
+            );
+            assert!(
+                end_price >= Decimal::ZERO && start_price > end_price,
+                "Start price must be greater than the end price"
+            );
+            assert!(duration_epochs > 0, "Duration must be greater than zero");
+
+            let seller_badge = ResourceBuilder::new_fungible(OwnerRole::None)
+                .divisibility(DIVISIBILITY_NONE)
+                .mint_initial_supply(1);
+
+            let start_epoch = Runtime::current_epoch().number();
+
+            let auction_component = Self {
+                lot: Vault::with_bucket(lot),
+                proceeds: Vault::new(payment_res_address),
+                start_price,
+                end_price,
+                start_epoch,
+                end_epoch: start_epoch + duration_epochs,
+                sold: false,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::Fixed(rule!(require(
+                seller_badge.resource_address()
+            ))))
+            .globalize();
+
+            (auction_component, seller_badge.into())
+        }
+
+        /* SELLER METHODS */
+
+        pub fn withdraw_proceeds(&mut self) -> Bucket {
+            self.proceeds.take_all()
+        }
+
+        /// Reclaim the lot once the auction has ended without a bid
+        pub fn reclaim_lot(&mut self) -> Bucket {
+            assert!(!self.sold, "Lot has been sold");
+            assert!(
+                Runtime::current_epoch().number() > self.end_epoch,
+                "Auction has not ended yet"
+            );
+
+            self.lot.take_all()
+        }
+
+        /* BIDDER METHODS */
+
+        /// Buy the lot at the current price. Returns the lot and the change of the payment.
+        pub fn bid(&mut self, mut payment: Bucket) -> (Bucket, Bucket) {
+            /* CHECK INPUT */
+            assert!(!self.sold, "Lot has already been sold");
+            assert!(
+                Runtime::current_epoch().number() <= self.end_epoch,
+                "Auction has ended"
+            );
+            assert!(
+                payment.resource_address() == self.proceeds.resource_address(),
+                "Payment resource address mismatch"
+            );
+
+            let price = self.get_current_price();
+            assert!(payment.amount() >= price, "Insufficient payment");
+
+            self.proceeds.put(
+                payment.take_advanced(price, WithdrawStrategy::Rounded(RoundingMode::AwayFromZero)),
+            );
+            self.sold = true;
+
+            Runtime::emit_event(LotSoldEvent {
+                price,
+                epoch: Runtime::current_epoch().number(),
+            });
+
+            (self.lot.take_all(), payment)
+        }
+
+        /* GETTERS */
+
+        /// Price of the lot, decreasing linearly from the start price to the end price over the
+        /// auction duration
+        pub fn get_current_price(&self) -> Decimal {
+            let current_epoch = Runtime::current_epoch().number().min(self.end_epoch);
+
+            let elapsed = Decimal::from(current_epoch - self.start_epoch);
+            let duration = Decimal::from(self.end_epoch - self.start_epoch);
+
+            self.start_price - (self.start_price - self.end_price) * elapsed / duration
+        }
+
+        pub fn is_sold(&self) -> bool {
+            self.sold
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Auction of 10 lot tokens started at epoch 10, from a price of 1000 to 100 over 10 epochs.
+/// The account is both the seller, holding the seller badge, and the bidder.
+struct TestAuction {
+    env: TestEnv,
+    account: ComponentAddress,
+    auction: ComponentAddress,
+    seller_badge: ResourceAddress,
+    lot: ResourceAddress,
+    payment: ResourceAddress,
+}
+
+impl TestAuction {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 1);
+        let account = env.accounts[0];
+        let lot = env
+            .test_runner
+            .create_fungible_resource(dec!(10), 18, account);
+        let payment = env
+            .test_runner
+            .create_fungible_resource(dec!(10000), 18, account);
+        env.set_epoch(10);
+
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account(account, lot, dec!(10))
+            .take_all_from_worktop(lot, "lot")
+            .call_function_with_name_lookup(
+                env.package_address,
+                "DutchAuction",
+                "instantiate",
+                |lookup| (lookup.bucket("lot"), payment, dec!(1000), dec!(100), 10u64),
+            )
+            .deposit_batch(account)
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[0].clone()]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            auction: commit.new_component_addresses()[0],
+            seller_badge: commit.new_resource_addresses()[0],
+            env,
+            account,
+            lot,
+            payment,
+        }
+    }
+
+    /// Execute the manifest with the account key, then deposit the worktop into the account
+    fn execute(&mut self, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute(0, builder)
+    }
+
+    fn bid(&mut self, amount: Decimal) -> TransactionReceipt {
+        let builder =
+            ManifestBuilder::new()
+                .withdraw_from_account(self.account, self.payment, amount)
+                .take_all_from_worktop(self.payment, "payment")
+                .call_method_with_name_lookup(self.auction, "bid", |lookup| {
+                    (lookup.bucket("payment"),)
+                });
+
+        self.execute(builder)
+    }
+
+    /// Call a seller method with the seller badge in the auth zone
+    fn call_as_seller(&mut self, method: &str) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(self.account, self.seller_badge, dec!(1))
+            .call_method(self.auction, method, manifest_args!());
+
+        self.execute(builder)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str) -> T {
+        let builder = ManifestBuilder::new().call_method(self.auction, method, manifest_args!());
+
+        self.execute(builder).expect_commit_success().output(0)
+    }
+
+    fn balance(&mut self, res_address: ResourceAddress) -> Decimal {
+        self.env.balance(0, res_address)
+    }
+}
+
+#[test]
+fn test_price_decreases_linearly() {
+    let mut auction = TestAuction::new();
+
+    assert_eq!(auction.get::<Decimal>("get_current_price"), dec!(1000));
+
+    auction.env.set_epoch(15);
+    assert_eq!(auction.get::<Decimal>("get_current_price"), dec!(550));
+
+    // The price stays at the end price once the auction has ended
+    auction.env.set_epoch(25);
+    assert_eq!(auction.get::<Decimal>("get_current_price"), dec!(100));
+}
+
+#[test]
+fn test_bid_buys_the_lot_at_the_current_price() {
+    let mut auction = TestAuction::new();
+    let (lot, payment) = (auction.lot, auction.payment);
+
+    auction.env.set_epoch(14);
+    auction.bid(dec!(700)).expect_commit_success();
+
+    assert_eq!(auction.balance(lot), dec!(10));
+    assert_eq!(auction.balance(payment), dec!(9360));
+    assert!(auction.get::<bool>("is_sold"));
+
+    auction
+        .call_as_seller("withdraw_proceeds")
+        .expect_commit_success();
+    assert_eq!(auction.balance(payment), dec!(10000));
+}
+
+#[test]
+fn test_bid_below_the_current_price_fails() {
+    let mut auction = TestAuction::new();
+
+    auction.env.set_epoch(14);
+    auction.bid(dec!(639)).expect_commit_failure();
+}
+
+#[test]
+fn test_lot_can_only_be_sold_once() {
+    let mut auction = TestAuction::new();
+
+    auction.bid(dec!(1000)).expect_commit_success();
+    auction.bid(dec!(1000)).expect_commit_failure();
+}
+
+#[test]
+fn test_bid_after_the_end_fails() {
+    let mut auction = TestAuction::new();
+
+    auction.env.set_epoch(21);
+    auction.bid(dec!(1000)).expect_commit_failure();
+}
+
+#[test]
+fn test_reclaim_unsold_lot() {
+    let mut auction = TestAuction::new();
+    let lot = auction.lot;
+
+    auction.env.set_epoch(20);
+    auction
+        .call_as_seller("reclaim_lot")
+        .expect_commit_failure();
+
+    auction.env.set_epoch(21);
+    auction
+        .call_as_seller("reclaim_lot")
+        .expect_commit_success();
+    assert_eq!(auction.balance(lot), dec!(10));
+}
+
+#[test]
+fn test_seller_methods_require_the_seller_badge() {
+    let mut auction = TestAuction::new();
+
+    auction.bid(dec!(1000)).expect_commit_success();
+
+    let builder =
+        ManifestBuilder::new().call_method(auction.auction, "withdraw_proceeds", manifest_args!());
+    auction.execute(builder).expect_commit_failure();
+}
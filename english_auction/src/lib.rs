@@ -0,0 +1,310 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct BidReceipt {
+    pub amount: Decimal,
+    pub epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BidPlacedEvent {
+    pub bid_id: NonFungibleLocalId,
+    pub amount: Decimal,
+    pub end_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct AuctionSettledEvent {
+    pub winning_bid_id: Option<NonFungibleLocalId>,
+    pub amount: Decimal,
+}
+
+#[blueprint]
+#[events(BidPlacedEvent, AuctionSettledEvent)]
+pub mod english_auction {
+
+    enable_method_auth! {
+        methods {
+
+            withdraw_proceeds => restrict_to :[OWNER];
+            reclaim_lot => restrict_to :[OWNER];
+
+            bid => PUBLIC;
+            claim_refund => PUBLIC;
+            settle => PUBLIC;
+            claim_lot => PUBLIC;
+
+            get_highest_bid => PUBLIC;
+            get_minimum_bid => PUBLIC;
+            get_end_epoch => PUBLIC;
+
+        }
+    }
+
+    pub struct EnglishAuction {
+        /// Auctioned fungible or non fungible resources, sold as a single lot
+        lot: Vault,
+
+        /// Escrow of the current highest bid
+        highest_bid: Vault,
+        highest_bid_id: Option<NonFungibleLocalId>,
+
+        /// Escrowed bids that have been outbid, claimable by their bidders
+        refunds: KeyValueStore<NonFungibleLocalId, Vault>,
+
+        /// Winning bid, withdrawable by the seller after settlement
+        proceeds: Vault,
+
+        /// Bid receipt non-fungible resource manager
+        bid_res_manager: ResourceManager,
+
+        /// Minimum amount of the first bid
+        reserve_price: Decimal,
+
+        /// Minimum increase of a bid over the current highest bid
+        min_increment: Decimal,
+
+        end_epoch: u64,
+
+        /// Bids placed less than this number of epochs before the end push the end back to
+        /// this number of epochs after the bid
+        extension_epochs: u64,
+
+        settled: bool,
+    }
+
+    impl EnglishAuction {
+        /// Instantiate an auction starting at the current epoch. Returns the auction component,
+        /// the seller badge, which is required to withdraw the proceeds or reclaim the unsold
+        /// lot, and the bid receipt resource address.
+        pub fn instantiate(
+            lot: Bucket,
+            payment_res_address: ResourceAddress,
+            reserve_price: Decimal,
+            min_increment: Decimal,
+            duration_epochs: u64,
+            extension_epochs: u64,
+        ) -> (Global<EnglishAuction>, Bucket, ResourceAddress) {
+            /* CHECK INPUT */
+            assert!(!lot.is_empty(), "Lot must not be empty");
+            assert_fungible_res_address(
+                payment_res_address,
+                Some("Payment resource must be fungible".to_string()),
+            );
+            assert!(
+                reserve_price > Decimal::ZERO,
+                "Reserve price must be greater than zero"
+            );
+            assert!(
+                min_increment > Decimal::ZERO,
+                "Minimum increment must be greater than zero"
+            );
+            assert!(duration_epochs > 0, "Duration must be greater than zero");
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(EnglishAuction::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let seller_badge = ResourceBuilder::new_fungible(OwnerRole::None)
+                .divisibility(DIVISIBILITY_NONE)
+                .mint_initial_supply(1);
+
+            let owner_role = OwnerRole::Fixed(rule!(require(seller_badge.resource_address())));
+
+            let bid_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<BidReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule;
+                        minter_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let auction_component = Self {
+                lot: Vault::with_bucket(lot),
+                highest_bid: Vault::new(payment_res_address),
+                highest_bid_id: None,
+                refunds: KeyValueStore::new(),
+                proceeds: Vault::new(payment_res_address),
+                bid_res_manager,
+                reserve_price,
+                min_increment,
+                end_epoch: Runtime::current_epoch().number() + duration_epochs,
+                extension_epochs,
+                settled: false,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (
+                auction_component,
+                seller_badge.into(),
+                bid_res_manager.address(),
+            )
+        }
+
+        /* SELLER METHODS */
+
+        pub fn withdraw_proceeds(&mut self) -> Bucket {
+            self.proceeds.take_all()
+        }
+
+        /// Reclaim the lot once the auction has ended without a bid
+        pub fn reclaim_lot(&mut self) -> Bucket {
+            assert!(self.highest_bid_id.is_none(), "Auction has a winning bid");
+            assert!(
+                Runtime::current_epoch().number() > self.end_epoch,
+                "Auction has not ended yet"
+            );
+
+            self.lot.take_all()
+        }
+
+        /* BIDDER METHODS */
+
+        /// Place a bid, escrowing the payment. The previous highest bid becomes claimable by
+        /// its bidder. Returns the bid receipt.
+        pub fn bid(&mut self, payment: Bucket) -> Bucket {
+            let current_epoch = Runtime::current_epoch().number();
+            let amount = payment.amount();
+
+            /* CHECK INPUT */
+            assert!(current_epoch <= self.end_epoch, "Auction has ended");
+            assert!(
+                payment.resource_address() == self.highest_bid.resource_address(),
+                "Payment resource address mismatch"
+            );
+            assert!(
+                amount >= self.get_minimum_bid(),
+                "Bid is lower than the minimum bid"
+            );
+
+            if let Some(previous_bid_id) = self.highest_bid_id.take() {
+                let previous_bid = self.highest_bid.take_all();
+                self.refunds
+                    .insert(previous_bid_id, Vault::with_bucket(previous_bid));
+            }
+
+            self.highest_bid.put(payment);
+
+            // Anti-sniping: late bids leave other bidders time to answer
+            if current_epoch + self.extension_epochs > self.end_epoch {
+                self.end_epoch = current_epoch + self.extension_epochs;
+            }
+
+            let receipt = self.bid_res_manager.mint_ruid_non_fungible(BidReceipt {
+                amount,
+                epoch: current_epoch,
+            });
+            let bid_id = receipt.as_non_fungible().non_fungible_local_id();
+
+            Runtime::emit_event(BidPlacedEvent {
+                bid_id: bid_id.clone(),
+                amount,
+                end_epoch: self.end_epoch,
+            });
+
+            self.highest_bid_id = Some(bid_id);
+
+            receipt
+        }
+
+        /// Claim the escrowed payment of a bid that has been outbid
+        pub fn claim_refund(&mut self, bid_proof: Proof) -> Bucket {
+            let bid_id = self._check_bid_proof(bid_proof);
+
+            self.refunds
+                .get_mut(&bid_id)
+                .expect("No refund for this bid")
+                .take_all()
+        }
+
+        /// Settle the auction once it has ended. The winning bid becomes withdrawable by the
+        /// seller and the lot claimable by the winner.
+        pub fn settle(&mut self) {
+            assert!(!self.settled, "Auction has already been settled");
+            assert!(
+                Runtime::current_epoch().number() > self.end_epoch,
+                "Auction has not ended yet"
+            );
+
+            let winning_bid = self.highest_bid.take_all();
+            let amount = winning_bid.amount();
+
+            self.proceeds.put(winning_bid);
+            self.settled = true;
+
+            Runtime::emit_event(AuctionSettledEvent {
+                winning_bid_id: self.highest_bid_id.clone(),
+                amount,
+            });
+        }
+
+        /// Claim the lot with the receipt of the winning bid, once the auction is settled
+        pub fn claim_lot(&mut self, bid_proof: Proof) -> Bucket {
+            let bid_id = self._check_bid_proof(bid_proof);
+
+            assert!(self.settled, "Auction has not been settled yet");
+            assert!(
+                self.highest_bid_id.as_ref() == Some(&bid_id),
+                "Only the winning bid can claim the lot"
+            );
+
+            self.lot.take_all()
+        }
+
+        /* GETTERS */
+
+        pub fn get_highest_bid(&self) -> Option<(NonFungibleLocalId, Decimal)> {
+            self.highest_bid_id
+                .clone()
+                .map(|bid_id| (bid_id, self.highest_bid.amount()))
+        }
+
+        pub fn get_minimum_bid(&self) -> Decimal {
+            match self.highest_bid_id {
+                Some(_) => self.highest_bid.amount() + self.min_increment,
+                None => self.reserve_price,
+            }
+        }
+
+        pub fn get_end_epoch(&self) -> u64 {
+            self.end_epoch
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _check_bid_proof(&self, bid_proof: Proof) -> NonFungibleLocalId {
+            bid_proof
+                .check(self.bid_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Seller of the lot, holding the seller badge
+const SELLER: usize = 0;
+/// Bidders, holding 1000 payment tokens each
+const ALICE: usize = 1;
+const BOB: usize = 2;
+
+/// Auction of a single lot token started at epoch 10 and ending at epoch 20, with a reserve
+/// price of 100, a minimum increment of 10 and an extension of 3 epochs
+struct TestAuction {
+    env: TestEnv,
+    auction: ComponentAddress,
+    seller_badge: ResourceAddress,
+    bid_receipt: ResourceAddress,
+    lot: ResourceAddress,
+    payment: ResourceAddress,
+}
+
+impl TestAuction {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let lot = env
+            .test_runner
+            .create_fungible_resource(dec!(1), 0, env.accounts[SELLER]);
+        let payment =
+            env.test_runner
+                .create_fungible_resource(dec!(2000), 18, env.accounts[SELLER]);
+
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account(env.accounts[SELLER], lot, dec!(1))
+            .take_all_from_worktop(lot, "lot")
+            .call_function_with_name_lookup(
+                env.package_address,
+                "EnglishAuction",
+                "instantiate",
+                |lookup| {
+                    (
+                        lookup.bucket("lot"),
+                        payment,
+                        dec!(100),
+                        dec!(10),
+                        10u64,
+                        3u64,
+                    )
+                },
+            )
+            .withdraw_from_account(env.accounts[SELLER], payment, dec!(2000))
+            .take_from_worktop(payment, dec!(1000), "alice")
+            .call_method_with_name_lookup(env.accounts[ALICE], "deposit", |lookup| {
+                (lookup.bucket("alice"),)
+            })
+            .take_from_worktop(payment, dec!(1000), "bob")
+            .call_method_with_name_lookup(env.accounts[BOB], "deposit", |lookup| {
+                (lookup.bucket("bob"),)
+            })
+            .deposit_batch(env.accounts[SELLER])
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            auction: commit.new_component_addresses()[0],
+            seller_badge: commit.new_resource_addresses()[0],
+            bid_receipt: commit.new_resource_addresses()[1],
+            env,
+            lot,
+            payment,
+        }
+    }
+
+    /// Execute the manifest with the keys of every account, then deposit the worktop into the
+    /// given account
+    fn execute(&mut self, account: usize, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute_with_all_keys(account, builder)
+    }
+
+    fn bid(&mut self, account: usize, amount: Decimal) -> TransactionReceipt {
+        let builder =
+            ManifestBuilder::new()
+                .withdraw_from_account(self.env.accounts[account], self.payment, amount)
+                .take_all_from_worktop(self.payment, "payment")
+                .call_method_with_name_lookup(self.auction, "bid", |lookup| {
+                    (lookup.bucket("payment"),)
+                });
+
+        self.execute(account, builder)
+    }
+
+    /// Call a method taking a proof of the single bid receipt held by the account
+    fn call_with_bid_proof(&mut self, account: usize, method: &str) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(
+                self.env.accounts[account],
+                self.bid_receipt,
+                dec!(1),
+            )
+            .pop_from_auth_zone("bid")
+            .call_method_with_name_lookup(self.auction, method, |lookup| (lookup.proof("bid"),));
+
+        self.execute(account, builder)
+    }
+
+    /// Call a seller method with the seller badge in the auth zone
+    fn call_as_seller(&mut self, method: &str) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(
+                self.env.accounts[SELLER],
+                self.seller_badge,
+                dec!(1),
+            )
+            .call_method(self.auction, method, manifest_args!());
+
+        self.execute(SELLER, builder)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str) -> T {
+        let builder = ManifestBuilder::new().call_method(self.auction, method, manifest_args!());
+
+        self.execute(SELLER, builder)
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_bids_must_increase_by_the_minimum_increment() {
+    let mut auction = TestAuction::new();
+
+    auction.bid(ALICE, dec!(99)).expect_commit_failure();
+    auction.bid(ALICE, dec!(100)).expect_commit_success();
+    assert_eq!(auction.get::<Decimal>("get_minimum_bid"), dec!(110));
+
+    auction.bid(BOB, dec!(105)).expect_commit_failure();
+    auction.bid(BOB, dec!(110)).expect_commit_success();
+
+    let (_, amount) = auction
+        .get::<Option<(NonFungibleLocalId, Decimal)>>("get_highest_bid")
+        .unwrap();
+    assert_eq!(amount, dec!(110));
+}
+
+#[test]
+fn test_outbid_bidder_claims_a_refund() {
+    let mut auction = TestAuction::new();
+    let payment = auction.payment;
+
+    auction.bid(ALICE, dec!(100)).expect_commit_success();
+
+    // The highest bid can not be refunded
+    auction
+        .call_with_bid_proof(ALICE, "claim_refund")
+        .expect_commit_failure();
+
+    auction.bid(BOB, dec!(150)).expect_commit_success();
+    auction
+        .call_with_bid_proof(ALICE, "claim_refund")
+        .expect_commit_success();
+
+    assert_eq!(auction.env.balance(ALICE, payment), dec!(1000));
+    assert_eq!(auction.env.balance(BOB, payment), dec!(850));
+}
+
+#[test]
+fn test_late_bid_extends_the_auction() {
+    let mut auction = TestAuction::new();
+
+    auction.bid(ALICE, dec!(100)).expect_commit_success();
+    assert_eq!(auction.get::<u64>("get_end_epoch"), 20);
+
+    auction.env.set_epoch(19);
+    auction.bid(BOB, dec!(110)).expect_commit_success();
+    assert_eq!(auction.get::<u64>("get_end_epoch"), 22);
+
+    auction.env.set_epoch(22);
+    auction.bid(ALICE, dec!(120)).expect_commit_success();
+
+    auction.env.set_epoch(26);
+    auction.bid(BOB, dec!(200)).expect_commit_failure();
+}
+
+#[test]
+fn test_settle_and_claim_the_lot() {
+    let mut auction = TestAuction::new();
+    let (lot, payment) = (auction.lot, auction.payment);
+
+    auction.bid(ALICE, dec!(100)).expect_commit_success();
+    auction.bid(BOB, dec!(200)).expect_commit_success();
+
+    auction.call_as_seller("settle").expect_commit_failure();
+
+    auction.env.set_epoch(21);
+    auction.call_as_seller("settle").expect_commit_success();
+    auction.call_as_seller("settle").expect_commit_failure();
+
+    // Only the winning bid can claim the lot
+    auction
+        .call_with_bid_proof(ALICE, "claim_lot")
+        .expect_commit_failure();
+    auction
+        .call_with_bid_proof(BOB, "claim_lot")
+        .expect_commit_success();
+    assert_eq!(auction.env.balance(BOB, lot), dec!(1));
+
+    auction
+        .call_as_seller("withdraw_proceeds")
+        .expect_commit_success();
+    assert_eq!(auction.env.balance(SELLER, payment), dec!(200));
+}
+
+#[test]
+fn test_reclaim_unsold_lot() {
+    let mut auction = TestAuction::new();
+    let lot = auction.lot;
+
+    auction
+        .call_as_seller("reclaim_lot")
+        .expect_commit_failure();
+
+    auction.env.set_epoch(21);
+    auction
+        .call_as_seller("reclaim_lot")
+        .expect_commit_success();
+    assert_eq!(auction.env.balance(SELLER, lot), dec!(1));
+}
+
+#[test]
+fn test_lot_with_a_bid_can_not_be_reclaimed() {
+    let mut auction = TestAuction::new();
+
+    auction.bid(ALICE, dec!(100)).expect_commit_success();
+
+    auction.env.set_epoch(21);
+    auction
+        .call_as_seller("reclaim_lot")
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_seller_methods_require_the_seller_badge() {
+    let mut auction = TestAuction::new();
+    let auction_address = auction.auction;
+
+    auction.bid(ALICE, dec!(100)).expect_commit_success();
+    auction.env.set_epoch(21);
+    auction.call_as_seller("settle").expect_commit_success();
+
+    let builder =
+        ManifestBuilder::new().call_method(auction_address, "withdraw_proceeds", manifest_args!());
+    auction.execute(ALICE, builder).expect_commit_failure();
+}
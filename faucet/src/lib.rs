@@ -0,0 +1,229 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct DripEvent {
+    pub account: ComponentAddress,
+    pub amount: Decimal,
+}
+
+#[blueprint]
+#[events(DripEvent)]
+pub mod faucet {
+
+    enable_method_auth! {
+        methods {
+
+            set_drip => restrict_to :[OWNER];
+            refill => PUBLIC;
+
+            drip => PUBLIC;
+
+            get_next_drip_epoch => PUBLIC;
+            get_token_res_address => PUBLIC;
+
+        }
+    }
+
+    pub struct Faucet {
+        /// Tokens dispensed by the faucet. Unused when the faucet mints the token.
+        tokens: Vault,
+
+        /// Resource manager of the token, when the faucet is allowed to mint it
+        minter: Option<ResourceManager>,
+
+        /// Amount sent on each drip
+        drip_amount: Decimal,
+
+        /// Number of epochs an account must wait between two drips
+        cooldown_epochs: u64,
+
+        /// Epoch of the last drip of each account
+        last_drips: KeyValueStore<ComponentAddress, u64>,
+    }
+
+    impl Faucet {
+        /// Instantiate a faucet minting a new test token on each drip. Returns the faucet
+        /// component and the token resource address.
+        pub fn instantiate_minting(
+            name: String,
+            symbol: String,
+            drip_amount: Decimal,
+            cooldown_epochs: u64,
+            owner_role: OwnerRole,
+        ) -> (Global<Faucet>, ResourceAddress) {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(Faucet::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let token_res_manager = ResourceBuilder::new_fungible(owner_role.clone())
+                .metadata(metadata! {
+                    init {
+                        "name" => name, updatable;
+                        "symbol" => symbol, updatable;
+                    }
+                })
+                .mint_roles(mint_roles! {
+                    minter => component_rule;
+                    minter_updater => rule!(deny_all);
+                })
+                .create_with_no_initial_supply();
+
+            let faucet_component = Faucet::_instantiate(
+                Vault::new(token_res_manager.address()),
+                Some(token_res_manager),
+                drip_amount,
+                cooldown_epochs,
+                owner_role,
+                address_reservation,
+            );
+
+            (faucet_component, token_res_manager.address())
+        }
+
+        /// Instantiate a faucet dispensing an existing token from a vault, refillable by anyone
+        pub fn instantiate_dispensing(
+            tokens: Bucket,
+            drip_amount: Decimal,
+            cooldown_epochs: u64,
+            owner_role: OwnerRole,
+        ) -> Global<Faucet> {
+            assert_fungible_res_address(
+                tokens.resource_address(),
+                Some("Token must be fungible".to_string()),
+            );
+
+            let (address_reservation, _) =
+                Runtime::allocate_component_address(Faucet::blueprint_id());
+
+            Faucet::_instantiate(
+                Vault::with_bucket(tokens),
+                None,
+                drip_amount,
+                cooldown_epochs,
+                owner_role,
+                address_reservation,
+            )
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn set_drip(&mut self, drip_amount: Decimal, cooldown_epochs: u64) {
+            assert!(
+                drip_amount > Decimal::ZERO,
+                "Drip amount must be greater than zero"
+            );
+
+            self.drip_amount = drip_amount;
+            self.cooldown_epochs = cooldown_epochs;
+        }
+
+        /// Add tokens to a dispensing faucet
+        pub fn refill(&mut self, tokens: Bucket) {
+            /* CHECK INPUT */
+            assert!(
+                tokens.resource_address() == self.tokens.resource_address(),
+                "Token resource address mismatch"
+            );
+
+            self.tokens.put(tokens);
+        }
+
+        /* USER METHODS */
+
+        /// Send the drip amount to the account, once per cooldown
+        pub fn drip(&mut self, mut account: Global<Account>) {
+            let current_epoch = Runtime::current_epoch().number();
+            let account_address = account.address();
+
+            assert!(
+                current_epoch >= self.get_next_drip_epoch(account_address),
+                "Account is in cooldown"
+            );
+
+            self.last_drips.insert(account_address, current_epoch);
+
+            let tokens = match &self.minter {
+                Some(minter) => minter.mint(self.drip_amount),
+                None => {
+                    assert!(self.tokens.amount() >= self.drip_amount, "Faucet is empty");
+
+                    self.tokens.take(self.drip_amount)
+                }
+            };
+
+            Runtime::emit_event(DripEvent {
+                account: account_address,
+                amount: self.drip_amount,
+            });
+
+            account.try_deposit_or_abort(tokens, None);
+        }
+
+        /* GETTERS */
+
+        pub fn get_next_drip_epoch(&self, account: ComponentAddress) -> u64 {
+            self.last_drips
+                .get(&account)
+                .map(|last_drip| *last_drip + self.cooldown_epochs)
+                .unwrap_or(0)
+        }
+
+        pub fn get_token_res_address(&self) -> ResourceAddress {
+            self.tokens.resource_address()
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _instantiate(
+            tokens: Vault,
+            minter: Option<ResourceManager>,
+            drip_amount: Decimal,
+            cooldown_epochs: u64,
+            owner_role: OwnerRole,
+            address_reservation: GlobalAddressReservation,
+        ) -> Global<Faucet> {
+            /* CHECK INPUT */
+            assert!(
+                drip_amount > Decimal::ZERO,
+                "Drip amount must be greater than zero"
+            );
+
+            Self {
+                tokens,
+                minter,
+                drip_amount,
+                cooldown_epochs,
+                last_drips: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize()
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Holds the dispensed tokens left after funding the dispensing faucet
+const OWNER: usize = 0;
+const ALICE: usize = 1;
+const BOB: usize = 2;
+
+/// Minting faucet and dispensing faucet holding 15 tokens, both with a drip of 10 tokens and a
+/// cooldown of 5 epochs, at epoch 10
+struct TestFaucet {
+    env: TestEnv,
+    minting: ComponentAddress,
+    minted: ResourceAddress,
+    dispensing: ComponentAddress,
+    dispensed: ResourceAddress,
+}
+
+impl TestFaucet {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let dispensed =
+            env.test_runner
+                .create_fungible_resource(dec!(1000), 18, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "Faucet",
+                "instantiate_minting",
+                manifest_args!(
+                    "Test token".to_string(),
+                    "TEST".to_string(),
+                    dec!(10),
+                    5u64,
+                    owner_role.clone()
+                ),
+            )
+            .withdraw_from_account(env.accounts[OWNER], dispensed, dec!(15))
+            .take_all_from_worktop(dispensed, "tokens")
+            .call_function_with_name_lookup(
+                env.package_address,
+                "Faucet",
+                "instantiate_dispensing",
+                |lookup| (lookup.bucket("tokens"), dec!(10), 5u64, owner_role),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            minting: commit.new_component_addresses()[0],
+            minted: commit.new_resource_addresses()[0],
+            dispensing: commit.new_component_addresses()[1],
+            env,
+            dispensed,
+        }
+    }
+
+    fn drip(&mut self, faucet: ComponentAddress, account: usize) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_method(
+            faucet,
+            "drip",
+            manifest_args!(self.env.accounts[account]),
+        );
+
+        self.env.execute(account, builder)
+    }
+
+    fn refill(&mut self, res_address: ResourceAddress, amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[BOB], res_address, amount)
+            .take_all_from_worktop(res_address, "tokens")
+            .call_method_with_name_lookup(self.dispensing, "refill", |lookup| {
+                (lookup.bucket("tokens"),)
+            });
+
+        self.env.execute(BOB, builder)
+    }
+}
+
+#[test]
+fn test_minting_faucet_drips_once_per_cooldown() {
+    let mut faucet = TestFaucet::new();
+    let (minting, minted, alice) = (faucet.minting, faucet.minted, faucet.env.accounts[ALICE]);
+
+    faucet.drip(minting, ALICE).expect_commit_success();
+    assert_eq!(faucet.env.balance(ALICE, minted), dec!(10));
+    faucet.drip(minting, ALICE).expect_commit_failure();
+
+    // Cooldowns are tracked per account
+    faucet.drip(minting, BOB).expect_commit_success();
+
+    let builder =
+        ManifestBuilder::new().call_method(minting, "get_next_drip_epoch", manifest_args!(alice));
+    let next_drip_epoch: u64 = faucet
+        .env
+        .execute(ALICE, builder)
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(next_drip_epoch, 15);
+
+    faucet.env.set_epoch(14);
+    faucet.drip(minting, ALICE).expect_commit_failure();
+
+    faucet.env.set_epoch(15);
+    faucet.drip(minting, ALICE).expect_commit_success();
+    assert_eq!(faucet.env.balance(ALICE, minted), dec!(20));
+}
+
+#[test]
+fn test_dispensing_faucet_is_refillable_by_anyone() {
+    let mut faucet = TestFaucet::new();
+    let (minting, dispensing) = (faucet.minting, faucet.dispensing);
+    let (minted, dispensed) = (faucet.minted, faucet.dispensed);
+
+    faucet.drip(dispensing, ALICE).expect_commit_success();
+    assert_eq!(faucet.env.balance(ALICE, dispensed), dec!(10));
+
+    faucet.drip(dispensing, BOB).expect_commit_failure();
+
+    let owner = faucet.env.accounts[OWNER];
+    let bob = faucet.env.accounts[BOB];
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(owner, dispensed, dec!(10))
+        .take_all_from_worktop(dispensed, "tokens")
+        .call_method_with_name_lookup(bob, "try_deposit_or_abort", |lookup| {
+            (lookup.bucket("tokens"), None::<ResourceOrNonFungible>)
+        });
+    faucet.env.execute(OWNER, builder).expect_commit_success();
+
+    faucet.refill(dispensed, dec!(10)).expect_commit_success();
+    faucet.drip(dispensing, BOB).expect_commit_success();
+    assert_eq!(faucet.env.balance(BOB, dispensed), dec!(10));
+
+    // Only the dispensed token can be refilled
+    faucet.drip(minting, BOB).expect_commit_success();
+    faucet.refill(minted, dec!(10)).expect_commit_failure();
+}
+
+#[test]
+fn test_set_drip_requires_the_owner() {
+    let mut faucet = TestFaucet::new();
+    let (minting, minted) = (faucet.minting, faucet.minted);
+
+    for (caller, drip_amount) in [(ALICE, dec!(50)), (OWNER, dec!(0))] {
+        let builder = ManifestBuilder::new().call_method(
+            minting,
+            "set_drip",
+            manifest_args!(drip_amount, 0u64),
+        );
+        faucet.env.execute(caller, builder).expect_commit_failure();
+    }
+
+    let builder =
+        ManifestBuilder::new().call_method(minting, "set_drip", manifest_args!(dec!(50), 0u64));
+    faucet.env.execute(OWNER, builder).expect_commit_success();
+
+    faucet.drip(minting, ALICE).expect_commit_success();
+    faucet.drip(minting, ALICE).expect_commit_success();
+    assert_eq!(faucet.env.balance(ALICE, minted), dec!(100));
+}
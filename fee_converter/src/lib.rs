@@ -0,0 +1,277 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use fee_distributor::fee_distributor::FeeDistributor;
+use price_oracle::price_oracle::PriceOracle;
+use scrypto::prelude::*;
+use swap_router::swap_router::SwapRouter;
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct FeesConvertedEvent {
+    pub res_address: ResourceAddress,
+    pub amount_in: Decimal,
+    pub amount_out: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct FeesDistributedEvent {
+    pub amount: Decimal,
+}
+
+#[blueprint]
+#[events(FeesConvertedEvent, FeesDistributedEvent)]
+pub mod fee_converter {
+
+    enable_method_auth! {
+        roles {
+            keeper => updatable_by: [OWNER];
+        },
+        methods {
+
+            set_path => restrict_to :[OWNER];
+            remove_path => restrict_to :[OWNER];
+            set_max_slippage => restrict_to :[OWNER];
+            set_router => restrict_to :[OWNER];
+            set_oracle => restrict_to :[OWNER];
+            set_distributor => restrict_to :[OWNER];
+            withdraw_converted => restrict_to :[OWNER];
+
+            convert => restrict_to :[keeper, OWNER];
+            distribute => restrict_to :[keeper, OWNER];
+
+            deposit_fees => PUBLIC;
+
+            get_collected => PUBLIC;
+            get_converted => PUBLIC;
+            get_path => PUBLIC;
+
+        }
+    }
+
+    pub struct FeeConverter {
+        /// Fees waiting to be converted, indexed by resource
+        collected: KeyValueStore<ResourceAddress, Vault>,
+
+        /// Converted fees, in the accounting asset
+        converted: Vault,
+
+        /// Swap router paths from each collected resource to the accounting asset
+        paths: KeyValueStore<ResourceAddress, Vec<ComponentAddress>>,
+
+        router: Global<SwapRouter>,
+
+        /// Maximum loss of a conversion compared to the oracle price
+        max_slippage: Decimal,
+
+        oracle: Global<PriceOracle>,
+        max_price_age_seconds: i64,
+
+        /// Distributor receiving the converted fees. Without distributor, the owner withdraws
+        /// them.
+        distributor: Option<Global<FeeDistributor>>,
+    }
+
+    impl FeeConverter {
+        #[allow(clippy::too_many_arguments)]
+        pub fn instantiate(
+            accounting_res_address: ResourceAddress,
+            router: Global<SwapRouter>,
+            max_slippage: Decimal,
+            oracle: Global<PriceOracle>,
+            max_price_age_seconds: i64,
+            distributor: Option<Global<FeeDistributor>>,
+            owner_role: OwnerRole,
+            keeper_rule: AccessRule,
+        ) -> Global<FeeConverter> {
+            /* CHECK INPUT */
+            FeeConverter::_assert_valid_slippage(max_slippage);
+            assert!(
+                max_price_age_seconds > 0,
+                "Max price age must be greater than zero"
+            );
+
+            Self {
+                collected: KeyValueStore::new(),
+                converted: Vault::new(accounting_res_address),
+                paths: KeyValueStore::new(),
+                router,
+                max_slippage,
+                oracle,
+                max_price_age_seconds,
+                distributor,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                keeper => keeper_rule;
+            ))
+            .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        /// Set the path of registered router pairs converting a collected resource to the
+        /// accounting asset
+        pub fn set_path(&mut self, res_address: ResourceAddress, path: Vec<ComponentAddress>) {
+            /* CHECK INPUT */
+            assert!(
+                res_address != self.converted.resource_address(),
+                "The accounting asset does not need conversion"
+            );
+            assert!(!path.is_empty(), "Path must not be empty");
+
+            self.paths.insert(res_address, path);
+        }
+
+        pub fn remove_path(&mut self, res_address: ResourceAddress) {
+            assert!(self.paths.get(&res_address).is_some(), "Path not found");
+
+            self.paths.remove(&res_address);
+        }
+
+        pub fn set_max_slippage(&mut self, max_slippage: Decimal) {
+            FeeConverter::_assert_valid_slippage(max_slippage);
+
+            self.max_slippage = max_slippage;
+        }
+
+        pub fn set_router(&mut self, router: Global<SwapRouter>) {
+            self.router = router;
+        }
+
+        pub fn set_oracle(&mut self, oracle: Global<PriceOracle>, max_price_age_seconds: i64) {
+            assert!(
+                max_price_age_seconds > 0,
+                "Max price age must be greater than zero"
+            );
+
+            self.oracle = oracle;
+            self.max_price_age_seconds = max_price_age_seconds;
+        }
+
+        pub fn set_distributor(&mut self, distributor: Option<Global<FeeDistributor>>) {
+            self.distributor = distributor;
+        }
+
+        /// Withdraw converted fees, e.g. when no distributor is set
+        pub fn withdraw_converted(&mut self, amount: Decimal) -> Bucket {
+            self.converted.take(amount)
+        }
+
+        /* KEEPER METHODS */
+
+        /// Convert collected fees of a resource to the accounting asset. The conversion fails
+        /// if its output is below the oracle price minus the max slippage.
+        pub fn convert(&mut self, res_address: ResourceAddress, amount: Decimal) {
+            let path = self
+                .paths
+                .get(&res_address)
+                .map(|path| path.clone())
+                .expect("Path not found");
+
+            let input = self
+                .collected
+                .get_mut(&res_address)
+                .expect("No fees collected for this resource")
+                .take(amount);
+
+            let amount_in = input.amount();
+            assert!(amount_in > Decimal::ZERO, "Nothing to convert");
+
+            let price = self.oracle.get_price(
+                res_address,
+                self.converted.resource_address(),
+                self.max_price_age_seconds,
+            );
+            let min_out = amount_in * price * (Decimal::ONE - self.max_slippage);
+
+            let output = self.router.swap_via(input, path, min_out);
+            let amount_out = output.amount();
+
+            // Vault deposit checks that the path ends with the accounting asset
+            self.converted.put(output);
+
+            Runtime::emit_event(FeesConvertedEvent {
+                res_address,
+                amount_in,
+                amount_out,
+            });
+        }
+
+        /// Send all converted fees to the distributor
+        pub fn distribute(&mut self) {
+            let fees = self.converted.take_all();
+            let amount = fees.amount();
+
+            assert!(amount > Decimal::ZERO, "Nothing to distribute");
+
+            self.distributor
+                .as_mut()
+                .expect("No distributor set")
+                .deposit_fees(fees);
+
+            Runtime::emit_event(FeesDistributedEvent { amount });
+        }
+
+        /* PUBLIC METHODS */
+
+        /// Collect fees of any resource, e.g. routed by the AssetPool treasury. Fees in the
+        /// accounting asset are ready to distribute.
+        pub fn deposit_fees(&mut self, fees: Bucket) {
+            let res_address = fees.resource_address();
+
+            if res_address == self.converted.resource_address() {
+                self.converted.put(fees);
+            } else if self.collected.get(&res_address).is_some() {
+                self.collected.get_mut(&res_address).unwrap().put(fees);
+            } else {
+                self.collected.insert(res_address, Vault::with_bucket(fees));
+            }
+        }
+
+        /* GETTERS */
+
+        pub fn get_collected(&self, res_address: ResourceAddress) -> Decimal {
+            self.collected
+                .get(&res_address)
+                .map_or(Decimal::ZERO, |vault| vault.amount())
+        }
+
+        pub fn get_converted(&self) -> Decimal {
+            self.converted.amount()
+        }
+
+        pub fn get_path(&self, res_address: ResourceAddress) -> Option<Vec<ComponentAddress>> {
+            self.paths.get(&res_address).map(|path| path.clone())
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_slippage(max_slippage: Decimal) {
+            assert!(
+                max_slippage >= Decimal::ZERO && max_slippage < Decimal::ONE,
+                "Max slippage must be between 0 and 1"
+            );
+        }
+    }
+}
@@ -0,0 +1,415 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the oracle, the router and the converter, and provides the pair liquidity
+const OWNER: usize = 0;
+const KEEPER: usize = 1;
+/// Holds 30 fee tokens and 5 USD of fees
+const ALICE: usize = 2;
+
+/// Ledger time of the tests, in seconds, rounded to the minute
+const NOW: i64 = 1_700_000_040;
+
+/// Converter of fee tokens to USD without distributor, with a max slippage of 10%. Fee tokens
+/// are priced 1 USD by the oracle, and swapped through a router over a Radiswap pair without
+/// fee holding 990 fee tokens and 1000 USD. A distributor of USD is ready to be set. The
+/// oracle, router, pair and distributor blueprints are linked into the converter package, so
+/// they are instantiated from the same package.
+struct TestConverter {
+    env: TestEnv,
+    converter: ComponentAddress,
+    oracle: ComponentAddress,
+    pair: ComponentAddress,
+    distributor: ComponentAddress,
+    fee_token: ResourceAddress,
+    usd: ResourceAddress,
+}
+
+impl TestConverter {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.test_runner
+            .advance_to_round_at_timestamp(Round::of(1), NOW * 1000);
+        let fee_token =
+            env.test_runner
+                .create_fungible_resource(dec!(1020), 18, env.accounts[OWNER]);
+        let usd = env
+            .test_runner
+            .create_fungible_resource(dec!(1005), 18, env.accounts[OWNER]);
+        let share = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "PriceOracle",
+                "instantiate",
+                manifest_args!(
+                    owner_role.clone(),
+                    rule!(require(env.badges[OWNER].clone())),
+                    2u32
+                ),
+            )
+            .call_function(
+                env.package_address,
+                "SwapRouter",
+                "instantiate",
+                manifest_args!(owner_role.clone()),
+            )
+            .call_function(
+                env.package_address,
+                "RadiswapPair",
+                "instantiate",
+                manifest_args!(fee_token, usd, dec!(0), owner_role.clone()),
+            )
+            .call_function(
+                env.package_address,
+                "FeeDistributor",
+                "instantiate",
+                manifest_args!(share, usd, 10u64, owner_role.clone()),
+            )
+            .withdraw_from_account(env.accounts[OWNER], fee_token, dec!(30))
+            .withdraw_from_account(env.accounts[OWNER], usd, dec!(5))
+            .deposit_batch(env.accounts[ALICE])
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+        let (oracle, router, pair, distributor) = (
+            commit.new_component_addresses()[0],
+            commit.new_component_addresses()[1],
+            commit.new_component_addresses()[2],
+            commit.new_component_addresses()[3],
+        );
+
+        // Manifest encoding of `AmmPool::Radiswap`
+        let pool = ManifestValue::Enum {
+            discriminator: 0,
+            fields: vec![to_manifest_value_and_unwrap!(&pair)],
+        };
+
+        let manifest = ManifestBuilder::new()
+            .call_method(
+                oracle,
+                "push_price",
+                manifest_args!(fee_token, usd, dec!(1), NOW - 60),
+            )
+            .withdraw_from_account(env.accounts[OWNER], fee_token, dec!(990))
+            .withdraw_from_account(env.accounts[OWNER], usd, dec!(1000))
+            .take_all_from_worktop(fee_token, "bucket_fee")
+            .take_all_from_worktop(usd, "bucket_usd")
+            .call_method_with_name_lookup(pair, "add_liquidity", |lookup| {
+                (lookup.bucket("bucket_fee"), lookup.bucket("bucket_usd"))
+            })
+            .call_method(
+                router,
+                "register_pair",
+                manifest_args!(pool, fee_token, usd),
+            )
+            .call_function(
+                env.package_address,
+                "FeeConverter",
+                "instantiate",
+                manifest_args!(
+                    usd,
+                    router,
+                    dec!("0.1"),
+                    oracle,
+                    3600i64,
+                    None::<ComponentAddress>,
+                    owner_role,
+                    rule!(require(env.badges[KEEPER].clone()))
+                ),
+            )
+            .deposit_batch(env.accounts[OWNER])
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()]);
+        let converter = receipt.expect_commit_success().new_component_addresses()[0];
+
+        Self {
+            env,
+            converter,
+            oracle,
+            pair,
+            distributor,
+            fee_token,
+            usd,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.converter, method, args)
+    }
+
+    fn set_path(
+        &mut self,
+        caller: usize,
+        res_address: ResourceAddress,
+        path: Vec<ComponentAddress>,
+    ) -> TransactionReceipt {
+        self.call(caller, "set_path", manifest_args!(res_address, path))
+    }
+
+    /// Deposit the given fees of Alice
+    fn deposit_fees(
+        &mut self,
+        res_address: ResourceAddress,
+        amount: Decimal,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[ALICE], res_address, amount)
+            .take_all_from_worktop(res_address, "fees")
+            .call_method_with_name_lookup(self.converter, "deposit_fees", |lookup| {
+                (lookup.bucket("fees"),)
+            });
+
+        self.env.execute(ALICE, builder)
+    }
+
+    fn convert(&mut self, caller: usize, amount: Decimal) -> TransactionReceipt {
+        let fee_token = self.fee_token;
+
+        self.call(caller, "convert", manifest_args!(fee_token, amount))
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str, args: impl ResolvableArguments) -> T {
+        self.call(OWNER, method, args)
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_fees_are_converted_and_distributed() {
+    let mut converter = TestConverter::new();
+    let (pair, distributor) = (converter.pair, converter.distributor);
+    let (fee_token, usd) = (converter.fee_token, converter.usd);
+
+    converter
+        .set_path(OWNER, fee_token, vec![pair])
+        .expect_commit_success();
+
+    // Fees in USD are ready to distribute
+    converter
+        .deposit_fees(fee_token, dec!(30))
+        .expect_commit_success();
+    converter.deposit_fees(usd, dec!(5)).expect_commit_success();
+    assert_eq!(
+        converter.get::<Decimal>("get_collected", manifest_args!(fee_token)),
+        dec!(30)
+    );
+    assert_eq!(
+        converter.get::<Decimal>("get_converted", manifest_args!()),
+        dec!(5)
+    );
+
+    converter.convert(ALICE, dec!(10)).expect_commit_failure();
+
+    // 10 fee tokens are swapped for 10 USD, above the oracle minimum of 9
+    converter.convert(KEEPER, dec!(10)).expect_commit_success();
+    assert_eq!(
+        converter.get::<Decimal>("get_collected", manifest_args!(fee_token)),
+        dec!(20)
+    );
+    assert_eq!(
+        converter.get::<Decimal>("get_converted", manifest_args!()),
+        dec!(15)
+    );
+
+    converter
+        .call(KEEPER, "distribute", manifest_args!())
+        .expect_commit_failure();
+    converter
+        .call(OWNER, "set_distributor", manifest_args!(Some(distributor)))
+        .expect_commit_success();
+    converter
+        .call(KEEPER, "distribute", manifest_args!())
+        .expect_commit_success();
+    assert_eq!(
+        converter.get::<Decimal>("get_converted", manifest_args!()),
+        dec!(0)
+    );
+
+    let builder =
+        ManifestBuilder::new().call_method(distributor, "get_period_fees", manifest_args!(0u64));
+    let period_fees: Decimal = converter
+        .env
+        .execute(OWNER, builder)
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(period_fees, dec!(15));
+
+    converter
+        .call(KEEPER, "distribute", manifest_args!())
+        .expect_commit_failure();
+
+    // At a price of 2 USD, the swap output is below the oracle minimum of 18
+    let builder = ManifestBuilder::new().call_method(
+        converter.oracle,
+        "push_price",
+        manifest_args!(fee_token, usd, dec!(2), NOW),
+    );
+    converter
+        .env
+        .execute(OWNER, builder)
+        .expect_commit_success();
+    converter.convert(KEEPER, dec!(10)).expect_commit_failure();
+}
+
+#[test]
+fn test_paths_and_withdrawals_are_checked() {
+    let mut converter = TestConverter::new();
+    let pair = converter.pair;
+    let (fee_token, usd) = (converter.fee_token, converter.usd);
+
+    converter
+        .set_path(ALICE, fee_token, vec![pair])
+        .expect_commit_failure();
+    converter
+        .set_path(OWNER, usd, vec![pair])
+        .expect_commit_failure();
+    converter
+        .set_path(OWNER, fee_token, vec![])
+        .expect_commit_failure();
+
+    converter
+        .deposit_fees(fee_token, dec!(10))
+        .expect_commit_success();
+    converter.convert(KEEPER, dec!(10)).expect_commit_failure();
+
+    converter
+        .set_path(OWNER, fee_token, vec![pair])
+        .expect_commit_success();
+    let path: Option<Vec<ComponentAddress>> = converter.get("get_path", manifest_args!(fee_token));
+    assert_eq!(path, Some(vec![pair]));
+
+    converter.convert(KEEPER, dec!(0)).expect_commit_failure();
+    converter
+        .call(OWNER, "set_max_slippage", manifest_args!(dec!(1)))
+        .expect_commit_failure();
+
+    converter.deposit_fees(usd, dec!(5)).expect_commit_success();
+    converter
+        .call(ALICE, "withdraw_converted", manifest_args!(dec!(5)))
+        .expect_commit_failure();
+    converter
+        .call(OWNER, "withdraw_converted", manifest_args!(dec!(5)))
+        .expect_commit_success();
+    assert_eq!(converter.env.balance(OWNER, usd), dec!(5));
+
+    converter
+        .call(OWNER, "remove_path", manifest_args!(fee_token))
+        .expect_commit_success();
+    converter
+        .call(OWNER, "remove_path", manifest_args!(fee_token))
+        .expect_commit_failure();
+    converter.convert(KEEPER, dec!(10)).expect_commit_failure();
+    assert_eq!(
+        converter.get::<Decimal>("get_collected", manifest_args!(fee_token)),
+        dec!(10)
+    );
+}
+
+#[test]
+fn test_stale_oracle_price_blocks_the_conversion() {
+    let mut converter = TestConverter::new();
+    let (oracle, pair, fee_token) = (converter.oracle, converter.pair, converter.fee_token);
+
+    converter
+        .set_path(OWNER, fee_token, vec![pair])
+        .expect_commit_success();
+    converter
+        .deposit_fees(fee_token, dec!(30))
+        .expect_commit_success();
+
+    // The price pushed a minute before the start is 3660 seconds old
+    converter
+        .env
+        .test_runner
+        .advance_to_round_at_timestamp(Round::of(2), (NOW + 3600) * 1000);
+    expect_failure_containing(converter.convert(KEEPER, dec!(10)), "Price is stale");
+
+    expect_failure_containing(
+        converter.call(OWNER, "set_oracle", manifest_args!(oracle, 0i64)),
+        "Max price age must be greater than zero",
+    );
+    converter
+        .call(OWNER, "set_oracle", manifest_args!(oracle, 7200i64))
+        .expect_commit_success();
+    converter.convert(KEEPER, dec!(10)).expect_commit_success();
+    assert_eq!(
+        converter.get::<Decimal>("get_converted", manifest_args!()),
+        dec!(10)
+    );
+}
+
+#[test]
+fn test_failures_carry_converter_messages() {
+    let mut converter = TestConverter::new();
+    let pair = converter.pair;
+    let (fee_token, usd) = (converter.fee_token, converter.usd);
+
+    expect_failure_containing(converter.convert(KEEPER, dec!(10)), "Path not found");
+    expect_failure_containing(
+        converter.call(OWNER, "remove_path", manifest_args!(fee_token)),
+        "Path not found",
+    );
+    expect_failure_containing(
+        converter.set_path(OWNER, usd, vec![pair]),
+        "The accounting asset does not need conversion",
+    );
+    expect_failure_containing(
+        converter.set_path(OWNER, fee_token, vec![]),
+        "Path must not be empty",
+    );
+    expect_failure_containing(
+        converter.call(OWNER, "set_max_slippage", manifest_args!(dec!(-1))),
+        "Max slippage must be between 0 and 1",
+    );
+
+    converter
+        .set_path(OWNER, fee_token, vec![pair])
+        .expect_commit_success();
+    expect_failure_containing(
+        converter.convert(KEEPER, dec!(10)),
+        "No fees collected for this resource",
+    );
+
+    // More than the collected fees can not be converted
+    converter
+        .deposit_fees(fee_token, dec!(30))
+        .expect_commit_success();
+    converter.convert(KEEPER, dec!(40)).expect_commit_failure();
+    expect_failure_containing(converter.convert(KEEPER, dec!(0)), "Nothing to convert");
+    assert_eq!(
+        converter.get::<Decimal>("get_collected", manifest_args!(fee_token)),
+        dec!(30)
+    );
+
+    expect_failure_containing(
+        converter.call(KEEPER, "distribute", manifest_args!()),
+        "Nothing to distribute",
+    );
+    converter.deposit_fees(usd, dec!(5)).expect_commit_success();
+    expect_failure_containing(
+        converter.call(KEEPER, "distribute", manifest_args!()),
+        "No distributor set",
+    );
+    assert_eq!(
+        converter.get::<Decimal>("get_converted", manifest_args!()),
+        dec!(5)
+    );
+}
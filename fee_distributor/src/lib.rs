@@ -0,0 +1,328 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::{assert_fungible_res_address, Checkpoints};
+use scrypto::prelude::*;
+
+/// Maximum number of periods processed by a single claim
+pub const MAX_CLAIM_PERIODS: u64 = 52;
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct HolderBadge {
+    pub created_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor)]
+pub struct HolderPosition {
+    /// Checkpoints of the locked shares
+    pub checkpoints: Checkpoints,
+
+    /// First period not claimed yet
+    pub next_claim_period: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct FeesDepositedEvent {
+    pub period: u64,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct FeesClaimedEvent {
+    pub holder_id: NonFungibleLocalId,
+    pub amount: Decimal,
+    pub next_claim_period: u64,
+}
+
+#[blueprint]
+#[events(FeesDepositedEvent, FeesClaimedEvent)]
+pub mod fee_distributor {
+
+    enable_method_auth! {
+        methods {
+
+            deposit_fees => PUBLIC;
+
+            create_holder => PUBLIC;
+            lock => PUBLIC;
+            unlock => PUBLIC;
+            claim => PUBLIC;
+
+            get_current_period => PUBLIC;
+            get_period_fees => PUBLIC;
+            get_claimable => PUBLIC;
+            get_locked_amount => PUBLIC;
+
+        }
+    }
+
+    pub struct FeeDistributor {
+        /// Locked shares, e.g. pool units
+        locked: Vault,
+        total_checkpoints: Checkpoints,
+
+        /// Holder badge non-fungible resource manager
+        holder_res_manager: ResourceManager,
+        positions: KeyValueStore<NonFungibleLocalId, HolderPosition>,
+
+        /// Deposited fees not claimed yet
+        fees: Vault,
+
+        /// Fees deposited during each period
+        period_fees: KeyValueStore<u64, Decimal>,
+
+        start_epoch: u64,
+        period_epochs: u64,
+    }
+
+    impl FeeDistributor {
+        /// Fees deposited during a period are distributed pro-rata to the shares locked before
+        /// the start of the period. Returns the distributor component and the holder badge
+        /// resource address.
+        pub fn instantiate(
+            share_res_address: ResourceAddress,
+            fee_res_address: ResourceAddress,
+            period_epochs: u64,
+            owner_role: OwnerRole,
+        ) -> (Global<FeeDistributor>, ResourceAddress) {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                share_res_address,
+                Some("Share resource must be fungible".to_string()),
+            );
+            assert_fungible_res_address(
+                fee_res_address,
+                Some("Fee resource must be fungible".to_string()),
+            );
+            assert!(period_epochs > 0, "Period must be greater than zero");
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(FeeDistributor::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let holder_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<HolderBadge>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule;
+                        minter_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let distributor_component = Self {
+                locked: Vault::new(share_res_address),
+                total_checkpoints: Checkpoints::new(),
+                holder_res_manager,
+                positions: KeyValueStore::new(),
+                fees: Vault::new(fee_res_address),
+                period_fees: KeyValueStore::new(),
+                start_epoch: Runtime::current_epoch().number(),
+                period_epochs,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (distributor_component, holder_res_manager.address())
+        }
+
+        /* FEE METHODS */
+
+        /// Add fees to the current period. Anyone can deposit fees.
+        pub fn deposit_fees(&mut self, fees: Bucket) {
+            /* CHECK INPUT */
+            assert!(
+                fees.resource_address() == self.fees.resource_address(),
+                "Fee resource address mismatch"
+            );
+
+            let period = self.get_current_period();
+
+            assert!(
+                self.total_checkpoints
+                    .value_before(self._period_start_epoch(period))
+                    > Decimal::ZERO,
+                "No share locked for the current period"
+            );
+
+            let amount = fees.amount();
+            let period_fees = self.get_period_fees(period);
+            self.period_fees.insert(period, period_fees + amount);
+            self.fees.put(fees);
+
+            Runtime::emit_event(FeesDepositedEvent { period, amount });
+        }
+
+        /* HOLDER METHODS */
+
+        pub fn create_holder(&mut self) -> Bucket {
+            let badge = self.holder_res_manager.mint_ruid_non_fungible(HolderBadge {
+                created_at_epoch: Runtime::current_epoch().number(),
+            });
+
+            self.positions.insert(
+                badge.as_non_fungible().non_fungible_local_id(),
+                HolderPosition {
+                    checkpoints: Checkpoints::new(),
+                    next_claim_period: self.get_current_period(),
+                },
+            );
+
+            badge
+        }
+
+        /// Lock shares. They earn the fees of the periods starting after this epoch.
+        pub fn lock(&mut self, holder_proof: Proof, shares: Bucket) {
+            let holder_id = self._check_holder_proof(holder_proof);
+
+            /* CHECK INPUT */
+            assert!(
+                shares.resource_address() == self.locked.resource_address(),
+                "Share resource address mismatch"
+            );
+
+            self._update_locked_amount(&holder_id, shares.amount());
+            self.locked.put(shares);
+        }
+
+        /// Unlock shares. Fees of past periods remain claimable.
+        pub fn unlock(&mut self, holder_proof: Proof, amount: Decimal) -> Bucket {
+            let holder_id = self._check_holder_proof(holder_proof);
+
+            /* CHECK INPUT */
+            assert!(amount > Decimal::ZERO, "Amount must be greater than zero!");
+
+            self._update_locked_amount(&holder_id, -amount);
+
+            self.locked.take(amount)
+        }
+
+        /// Claim the fees of the completed periods, up to `MAX_CLAIM_PERIODS` periods per call
+        pub fn claim(&mut self, holder_proof: Proof) -> Bucket {
+            let holder_id = self._check_holder_proof(holder_proof);
+
+            let (amount, next_claim_period) = self._compute_claimable(&holder_id);
+
+            self.positions
+                .get_mut(&holder_id)
+                .unwrap()
+                .next_claim_period = next_claim_period;
+
+            Runtime::emit_event(FeesClaimedEvent {
+                holder_id,
+                amount,
+                next_claim_period,
+            });
+
+            self.fees
+                .take_advanced(amount, WithdrawStrategy::Rounded(RoundingMode::ToZero))
+        }
+
+        /* GETTERS */
+
+        pub fn get_current_period(&self) -> u64 {
+            (Runtime::current_epoch().number() - self.start_epoch) / self.period_epochs
+        }
+
+        pub fn get_period_fees(&self, period: u64) -> Decimal {
+            self.period_fees
+                .get(&period)
+                .map(|fees| *fees)
+                .unwrap_or(Decimal::ZERO)
+        }
+
+        /// Fees claimable by the next claim of a holder
+        pub fn get_claimable(&self, holder_id: NonFungibleLocalId) -> Decimal {
+            self._compute_claimable(&holder_id).0
+        }
+
+        pub fn get_locked_amount(&self, holder_id: NonFungibleLocalId) -> Decimal {
+            let position = self.positions.get(&holder_id).expect("Holder not found");
+
+            position.checkpoints.latest()
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _period_start_epoch(&self, period: u64) -> u64 {
+            self.start_epoch + period * self.period_epochs
+        }
+
+        /// Fees of the completed periods not claimed yet, and the period following the last
+        /// one processed
+        fn _compute_claimable(&self, holder_id: &NonFungibleLocalId) -> (Decimal, u64) {
+            let position = self.positions.get(holder_id).expect("Holder not found");
+
+            let first_period = position.next_claim_period;
+            let end_period = self
+                .get_current_period()
+                .min(first_period + MAX_CLAIM_PERIODS);
+
+            let mut amount = PreciseDecimal::ZERO;
+
+            for period in first_period..end_period {
+                let period_fees = self.get_period_fees(period);
+
+                if period_fees == Decimal::ZERO {
+                    continue;
+                }
+
+                let start_epoch = self._period_start_epoch(period);
+                let shares = position.checkpoints.value_before(start_epoch);
+                let total_shares = self.total_checkpoints.value_before(start_epoch);
+
+                if shares > Decimal::ZERO {
+                    amount += PreciseDecimal::from(period_fees) * shares / total_shares;
+                }
+            }
+
+            (
+                amount.checked_truncate(RoundingMode::ToZero).unwrap(),
+                end_period.max(first_period),
+            )
+        }
+
+        fn _update_locked_amount(&mut self, holder_id: &NonFungibleLocalId, change: Decimal) {
+            let current_epoch = Runtime::current_epoch().number();
+
+            let locked_amount = self
+                .positions
+                .get_mut(holder_id)
+                .unwrap()
+                .checkpoints
+                .push_change(current_epoch, change);
+
+            assert!(locked_amount >= Decimal::ZERO, "Not enough locked shares");
+
+            self.total_checkpoints.push_change(current_epoch, change);
+        }
+
+        fn _check_holder_proof(&self, holder_proof: Proof) -> NonFungibleLocalId {
+            holder_proof
+                .check(self.holder_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+    }
+}
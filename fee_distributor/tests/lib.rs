@@ -0,0 +1,253 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Holds 1000 fee tokens
+const OWNER: usize = 0;
+/// Holders with a holder badge and 1000 shares each
+const ALICE: usize = 1;
+const BOB: usize = 2;
+
+/// Distributor with periods of 10 epochs, instantiated at epoch 10
+struct TestDistributor {
+    env: TestEnv,
+    distributor: ComponentAddress,
+    holder_badge: ResourceAddress,
+    share: ResourceAddress,
+    fee: ResourceAddress,
+}
+
+impl TestDistributor {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let share = env
+            .test_runner
+            .create_fungible_resource(dec!(2000), 18, env.accounts[ALICE]);
+        let fee = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, env.accounts[OWNER]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "FeeDistributor",
+                "instantiate",
+                manifest_args!(share, fee, 10u64, OwnerRole::None),
+            )
+            .withdraw_from_account(env.accounts[ALICE], share, dec!(1000))
+            .take_all_from_worktop(share, "bob")
+            .call_method_with_name_lookup(env.accounts[BOB], "deposit", |lookup| {
+                (lookup.bucket("bob"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+
+        let mut distributor = Self {
+            distributor: commit.new_component_addresses()[0],
+            holder_badge: commit.new_resource_addresses()[0],
+            env,
+            share,
+            fee,
+        };
+
+        for holder in [ALICE, BOB] {
+            let builder = ManifestBuilder::new().call_method(
+                distributor.distributor,
+                "create_holder",
+                manifest_args!(),
+            );
+            distributor
+                .env
+                .execute(holder, builder)
+                .expect_commit_success();
+        }
+
+        distributor
+    }
+
+    /// Proof of the holder badge of the holder, popped as "holder"
+    fn with_holder_proof(&self, holder: usize) -> ManifestBuilder {
+        ManifestBuilder::new()
+            .create_proof_from_account_of_amount(
+                self.env.accounts[holder],
+                self.holder_badge,
+                dec!(1),
+            )
+            .pop_from_auth_zone("holder")
+    }
+
+    fn lock(&mut self, holder: usize, amount: Decimal) -> TransactionReceipt {
+        let builder = self
+            .with_holder_proof(holder)
+            .withdraw_from_account(self.env.accounts[holder], self.share, amount)
+            .take_all_from_worktop(self.share, "shares")
+            .call_method_with_name_lookup(self.distributor, "lock", |lookup| {
+                (lookup.proof("holder"), lookup.bucket("shares"))
+            });
+
+        self.env.execute(holder, builder)
+    }
+
+    fn unlock(&mut self, holder: usize, amount: Decimal) -> TransactionReceipt {
+        let builder = self.with_holder_proof(holder).call_method_with_name_lookup(
+            self.distributor,
+            "unlock",
+            |lookup| (lookup.proof("holder"), amount),
+        );
+
+        self.env.execute(holder, builder)
+    }
+
+    fn claim(&mut self, holder: usize) -> TransactionReceipt {
+        let builder = self.with_holder_proof(holder).call_method_with_name_lookup(
+            self.distributor,
+            "claim",
+            |lookup| (lookup.proof("holder"),),
+        );
+
+        self.env.execute(holder, builder)
+    }
+
+    fn deposit_fees(&mut self, amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[OWNER], self.fee, amount)
+            .take_all_from_worktop(self.fee, "fees")
+            .call_method_with_name_lookup(self.distributor, "deposit_fees", |lookup| {
+                (lookup.bucket("fees"),)
+            });
+
+        self.env.execute(OWNER, builder)
+    }
+
+    fn get_claimable(&mut self, holder: usize) -> Decimal {
+        let holder_id = self.holder_id(holder);
+        let builder = ManifestBuilder::new().call_method(
+            self.distributor,
+            "get_claimable",
+            manifest_args!(holder_id),
+        );
+
+        self.env
+            .execute(holder, builder)
+            .expect_commit_success()
+            .output(0)
+    }
+
+    /// Id of the holder badge of the holder
+    fn holder_id(&mut self, holder: usize) -> NonFungibleLocalId {
+        let vault_id = self
+            .env
+            .test_runner
+            .get_component_vaults(self.env.accounts[holder], self.holder_badge)[0];
+        let (_, mut ids) = self
+            .env
+            .test_runner
+            .inspect_non_fungible_vault(vault_id)
+            .unwrap();
+
+        ids.next().unwrap()
+    }
+}
+
+#[test]
+fn test_fees_are_distributed_to_the_shares_locked_before_the_period() {
+    let mut distributor = TestDistributor::new();
+    let fee = distributor.fee;
+
+    distributor.lock(ALICE, dec!(300)).expect_commit_success();
+    distributor.lock(BOB, dec!(100)).expect_commit_success();
+
+    // Shares locked during the first period do not earn its fees
+    distributor.deposit_fees(dec!(10)).expect_commit_failure();
+
+    distributor.env.set_epoch(20);
+    distributor.deposit_fees(dec!(40)).expect_commit_success();
+    distributor.lock(BOB, dec!(200)).expect_commit_success();
+
+    distributor.env.set_epoch(30);
+    distributor.deposit_fees(dec!(60)).expect_commit_success();
+
+    // Fees of the current period are not claimable yet
+    assert_eq!(distributor.get_claimable(ALICE), dec!(30));
+
+    distributor.env.set_epoch(40);
+    assert_eq!(distributor.get_claimable(ALICE), dec!(60));
+    assert_eq!(distributor.get_claimable(BOB), dec!(40));
+
+    distributor.claim(ALICE).expect_commit_success();
+    distributor.claim(BOB).expect_commit_success();
+    assert_eq!(distributor.env.balance(ALICE, fee), dec!(60));
+    assert_eq!(distributor.env.balance(BOB, fee), dec!(40));
+
+    distributor.claim(ALICE).expect_commit_success();
+    assert_eq!(distributor.env.balance(ALICE, fee), dec!(60));
+}
+
+#[test]
+fn test_unlocked_shares_stop_earning_fees() {
+    let mut distributor = TestDistributor::new();
+    let (share, fee) = (distributor.share, distributor.fee);
+
+    distributor.lock(ALICE, dec!(300)).expect_commit_success();
+    distributor.lock(BOB, dec!(100)).expect_commit_success();
+
+    distributor.unlock(ALICE, dec!(301)).expect_commit_failure();
+    distributor.unlock(ALICE, dec!(0)).expect_commit_failure();
+    distributor.unlock(BOB, dec!(100)).expect_commit_success();
+    assert_eq!(distributor.env.balance(BOB, share), dec!(1000));
+
+    distributor.env.set_epoch(20);
+    distributor.deposit_fees(dec!(40)).expect_commit_success();
+
+    distributor.env.set_epoch(30);
+    distributor.claim(ALICE).expect_commit_success();
+    distributor.claim(BOB).expect_commit_success();
+    assert_eq!(distributor.env.balance(ALICE, fee), dec!(40));
+    assert_eq!(distributor.env.balance(BOB, fee), dec!(0));
+}
+
+#[test]
+fn test_holder_methods_require_a_holder_badge() {
+    let mut distributor = TestDistributor::new();
+    let (share, distributor_address, alice) = (
+        distributor.share,
+        distributor.distributor,
+        distributor.env.accounts[ALICE],
+    );
+
+    // A proof of another resource is rejected
+    let builder = ManifestBuilder::new()
+        .create_proof_from_account_of_amount(alice, share, dec!(1))
+        .pop_from_auth_zone("holder")
+        .withdraw_from_account(alice, share, dec!(100))
+        .take_all_from_worktop(share, "shares")
+        .call_method_with_name_lookup(distributor_address, "lock", |lookup| {
+            (lookup.proof("holder"), lookup.bucket("shares"))
+        });
+    distributor
+        .env
+        .execute(ALICE, builder)
+        .expect_commit_failure();
+
+    // Only shares can be locked
+    let (fee, owner) = (distributor.fee, distributor.env.accounts[OWNER]);
+    let builder = distributor
+        .with_holder_proof(ALICE)
+        .withdraw_from_account(owner, fee, dec!(10))
+        .take_all_from_worktop(fee, "shares")
+        .call_method_with_name_lookup(distributor_address, "lock", |lookup| {
+            (lookup.proof("holder"), lookup.bucket("shares"))
+        });
+    let manifest = builder.deposit_batch(alice).build();
+    distributor
+        .env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, distributor.env.badges.clone())
+        .expect_commit_failure();
+}
@@ -0,0 +1,113 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+
+/// Natural logarithm of 2
+pub const LN_2: PreciseDecimal = pdec!("0.693147180559945309417232121458176568");
+
+/// Number of terms used in the series expansions
+const SERIES_TERMS: u32 = 40;
+
+/// Natural logarithm of a strictly positive number. The input is first scaled into [1, 2)
+/// by powers of 2, then `ln(m) = 2 * atanh((m - 1) / (m + 1))` is computed with its series.
+/// The absolute error is below 1e-33.
+pub fn ln(value: PreciseDecimal) -> PreciseDecimal {
+    assert!(
+        value > PreciseDecimal::ZERO,
+        "Logarithm is only defined for positive numbers"
+    );
+
+    let two = PreciseDecimal::from(2);
+    let mut mantissa = value;
+    let mut exponent: i32 = 0;
+
+    while mantissa >= two {
+        mantissa = mantissa / two;
+        exponent += 1;
+    }
+    while mantissa < PreciseDecimal::ONE {
+        mantissa = mantissa * two;
+        exponent -= 1;
+    }
+
+    let z = (mantissa - PreciseDecimal::ONE) / (mantissa + PreciseDecimal::ONE);
+    let z_squared = z * z;
+
+    let mut term = z;
+    let mut sum = PreciseDecimal::ZERO;
+
+    for i in 0..SERIES_TERMS {
+        sum += term / PreciseDecimal::from(2 * i + 1);
+        term = term * z_squared;
+    }
+
+    two * sum + LN_2 * PreciseDecimal::from(exponent)
+}
+
+/// Exponential of a number. The input is reduced to `k * ln(2) + r` with `r` in [0, ln(2)),
+/// `exp(r)` is computed with its Taylor series and scaled back by `2^k`. The relative error
+/// is below 1e-33 for non negative inputs, and the absolute error is below 1e-33 for
+/// negative inputs. Panics on overflow, for inputs above about 93.
+pub fn exp(value: PreciseDecimal) -> PreciseDecimal {
+    let k = (value / LN_2)
+        .checked_round(0, RoundingMode::ToNegativeInfinity)
+        .expect("Exponential overflow");
+    let r = value - k * LN_2;
+
+    let mut term = PreciseDecimal::ONE;
+    let mut sum = PreciseDecimal::ONE;
+
+    for i in 1..SERIES_TERMS {
+        term = term * r / PreciseDecimal::from(i);
+        sum += term;
+    }
+
+    let two = PreciseDecimal::from(2);
+    let mut k = k;
+
+    while k > PreciseDecimal::ZERO {
+        sum = sum * two;
+        k -= PreciseDecimal::ONE;
+    }
+    while k < PreciseDecimal::ZERO && sum > PreciseDecimal::ZERO {
+        sum = sum / two;
+        k += PreciseDecimal::ONE;
+    }
+
+    sum
+}
+
+/// `base^exponent` for a strictly positive base and any exponent, computed as
+/// `exp(exponent * ln(base))`. The error of `ln` is amplified by the exponent, so the relative
+/// error is below `(1 + |exponent|) * 1e-33` for results above 1.
+pub fn pow(base: PreciseDecimal, exponent: PreciseDecimal) -> PreciseDecimal {
+    if exponent == PreciseDecimal::ZERO {
+        return PreciseDecimal::ONE;
+    }
+    if exponent == PreciseDecimal::ONE {
+        return base;
+    }
+
+    exp(exponent * ln(base))
+}
@@ -0,0 +1,102 @@
+use fixed_point_math::*;
+use scrypto::prelude::*;
+
+fn assert_close(value: PreciseDecimal, expected: PreciseDecimal, tolerance: PreciseDecimal) {
+    let error = if value > expected {
+        value - expected
+    } else {
+        expected - value
+    };
+
+    assert!(
+        error <= tolerance,
+        "Expected {} but got {} (error: {})",
+        expected,
+        value,
+        error
+    );
+}
+
+#[test]
+fn test_ln() {
+    let tolerance = pdec!("0.000000000000000000000000000000001");
+
+    assert_eq!(ln(PreciseDecimal::ONE), PreciseDecimal::ZERO);
+    assert_close(ln(pdec!(2)), LN_2, tolerance);
+    assert_close(
+        ln(pdec!(10)),
+        pdec!("2.302585092994045684017991454684364207"),
+        tolerance,
+    );
+    assert_close(
+        ln(pdec!("0.1")),
+        pdec!("-2.302585092994045684017991454684364207"),
+        tolerance,
+    );
+    assert_close(
+        ln(pdec!("1000000000")),
+        pdec!("20.723265836946411156161923092159277868"),
+        tolerance,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Logarithm is only defined for positive numbers")]
+fn test_ln_of_zero_panics() {
+    ln(PreciseDecimal::ZERO);
+}
+
+#[test]
+fn test_exp() {
+    let tolerance = pdec!("0.000000000000000000000000000000001");
+
+    assert_eq!(exp(PreciseDecimal::ZERO), PreciseDecimal::ONE);
+    assert_close(
+        exp(PreciseDecimal::ONE),
+        pdec!("2.718281828459045235360287471352662497"),
+        tolerance,
+    );
+    assert_close(
+        exp(pdec!("-1")),
+        pdec!("0.367879441171442321595523770161460867"),
+        tolerance,
+    );
+
+    // Relative error for results above 1
+    let expected = pdec!("22026.465794806716516957900645284244366353");
+    assert_close(exp(pdec!(10)), expected, expected * tolerance);
+}
+
+#[test]
+fn test_exp_of_ln() {
+    let value = pdec!("123.456");
+
+    assert_close(
+        exp(ln(value)),
+        value,
+        value * pdec!("0.00000000000000000000000000000001"),
+    );
+}
+
+#[test]
+fn test_pow() {
+    let tolerance = pdec!("0.00000000000000000000000000000001");
+
+    assert_eq!(pow(pdec!(5), PreciseDecimal::ZERO), PreciseDecimal::ONE);
+    assert_eq!(pow(pdec!(5), PreciseDecimal::ONE), pdec!(5));
+    assert_close(
+        pow(pdec!(2), pdec!("0.5")),
+        pdec!("1.414213562373095048801688724209698078"),
+        tolerance,
+    );
+    assert_close(
+        pow(pdec!("1.05"), pdec!(10)),
+        pdec!("1.62889462677744140625"),
+        tolerance,
+    );
+    assert_close(
+        pow(pdec!(9), pdec!("-0.5")),
+        pdec!("0.333333333333333333333333333333333333"),
+        tolerance,
+    );
+}
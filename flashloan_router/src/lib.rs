@@ -0,0 +1,360 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use asset_pool_interface::{AssetPoolStub, SingleResourcePoolInterface};
+use scrypto::prelude::*;
+
+/// Maximum fee rate of a registered pool
+pub const MAX_FEE_RATE: Decimal = dec!("0.05");
+
+#[derive(ScryptoSbor, Clone)]
+pub struct RegisteredPool {
+    /// AssetPool component, including flash-mint ones, or NativePoolAdapter component. The
+    /// router only calls the standard pool interface methods, and the pool admin rule must
+    /// allow the router component.
+    pub pool: AssetPoolStub,
+    pub res_address: ResourceAddress,
+
+    /// Fee charged on the amount borrowed from the pool
+    pub fee_rate: Decimal,
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub struct LoanLeg {
+    pub pool_id: u64,
+    pub loan_amount: Decimal,
+    pub fee_amount: Decimal,
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct RouterLoanTerm {
+    pub res_address: ResourceAddress,
+
+    /// Loans taken from each pool, in the order of the pool loan terms returned to the
+    /// borrower
+    pub legs: Vec<LoanLeg>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RoutedFlashloanEvent {
+    pub res_address: ResourceAddress,
+    pub loan_amount: Decimal,
+    pub fee_amount: Decimal,
+    pub pool_count: u32,
+}
+
+#[blueprint]
+#[events(RoutedFlashloanEvent)]
+pub mod flashloan_router {
+
+    enable_method_auth! {
+        methods {
+
+            register_pool => restrict_to :[OWNER];
+            set_pool_fee_rate => restrict_to :[OWNER];
+            unregister_pool => restrict_to :[OWNER];
+
+            take_flashloan => PUBLIC;
+            repay_flashloan => PUBLIC;
+
+            get_quote => PUBLIC;
+            get_pools => PUBLIC;
+
+        }
+    }
+
+    pub struct FlashloanRouter {
+        pools: KeyValueStore<u64, RegisteredPool>,
+        next_pool_id: u64,
+
+        /// Ids of the registered pools, indexed by pooled resource
+        pools_by_resource: KeyValueStore<ResourceAddress, Vec<u64>>,
+
+        /// Transient router loan term non-fungible resource manager
+        loan_term_res_manager: ResourceManager,
+    }
+
+    impl FlashloanRouter {
+        pub fn instantiate(owner_role: OwnerRole) -> Global<FlashloanRouter> {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(FlashloanRouter::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let loan_term_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<RouterLoanTerm>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule;
+                        burner_updater => rule!(deny_all);
+                    })
+                    // The loan term can not be deposited, forcing the repayment in the same
+                    // transaction
+                    .deposit_roles(deposit_roles! {
+                        depositor => rule!(deny_all);
+                        depositor_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            Self {
+                pools: KeyValueStore::new(),
+                next_pool_id: 1,
+                pools_by_resource: KeyValueStore::new(),
+                loan_term_res_manager,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        /// Register a pool to borrow from. Returns the pool id.
+        pub fn register_pool(
+            &mut self,
+            pool: ComponentAddress,
+            res_address: ResourceAddress,
+            fee_rate: Decimal,
+        ) -> u64 {
+            FlashloanRouter::_assert_valid_fee_rate(fee_rate);
+
+            let pool_id = self.next_pool_id;
+            self.next_pool_id += 1;
+
+            self.pools.insert(
+                pool_id,
+                RegisteredPool {
+                    pool: AssetPoolStub::from(pool),
+                    res_address,
+                    fee_rate,
+                },
+            );
+
+            let resource_registered = self.pools_by_resource.get(&res_address).is_some();
+
+            if resource_registered {
+                self.pools_by_resource
+                    .get_mut(&res_address)
+                    .unwrap()
+                    .push(pool_id);
+            } else {
+                self.pools_by_resource.insert(res_address, vec![pool_id]);
+            }
+
+            pool_id
+        }
+
+        pub fn set_pool_fee_rate(&mut self, pool_id: u64, fee_rate: Decimal) {
+            FlashloanRouter::_assert_valid_fee_rate(fee_rate);
+
+            self.pools
+                .get_mut(&pool_id)
+                .expect("Pool not found")
+                .fee_rate = fee_rate;
+        }
+
+        /// Stop routing loans to a pool
+        pub fn unregister_pool(&mut self, pool_id: u64) {
+            let res_address = self
+                .pools
+                .get(&pool_id)
+                .expect("Pool not found")
+                .res_address;
+
+            self.pools_by_resource
+                .get_mut(&res_address)
+                .unwrap()
+                .retain(|id| *id != pool_id);
+        }
+
+        /* BORROWER METHODS */
+
+        /// Borrow from the registered pools, cheapest first. Returns the combined loan, the
+        /// router loan term and the loan terms of each pool, which must all be handed back to
+        /// `repay_flashloan` in the same transaction.
+        pub fn take_flashloan(
+            &mut self,
+            res_address: ResourceAddress,
+            amount: Decimal,
+        ) -> (Bucket, Bucket, Vec<Bucket>) {
+            /* CHECK INPUT */
+            assert!(amount > Decimal::ZERO, "Amount must be greater than zero!");
+
+            let legs = self._route(res_address, amount);
+
+            let mut loan = Bucket::new(res_address);
+            let mut pool_terms = Vec::with_capacity(legs.len());
+            let mut fee_amount = Decimal::ZERO;
+
+            for leg in &legs {
+                let pool = self.pools.get(&leg.pool_id).unwrap().pool;
+
+                let (pool_loan, terms) = pool.take_flashloan(leg.loan_amount, leg.fee_amount);
+
+                loan.put(pool_loan);
+                pool_terms.push(terms);
+                fee_amount += leg.fee_amount;
+            }
+
+            Runtime::emit_event(RoutedFlashloanEvent {
+                res_address,
+                loan_amount: loan.amount(),
+                fee_amount,
+                pool_count: legs.len() as u32,
+            });
+
+            let router_term = self
+                .loan_term_res_manager
+                .mint_ruid_non_fungible(RouterLoanTerm { res_address, legs });
+
+            (loan, router_term, pool_terms)
+        }
+
+        /// Repay every pool its loan plus fee. Returns the change.
+        pub fn repay_flashloan(
+            &mut self,
+            mut repayment: Bucket,
+            router_term: Bucket,
+            pool_terms: Vec<Bucket>,
+        ) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                router_term.resource_address() == self.loan_term_res_manager.address(),
+                "Loan term resource address mismatch"
+            );
+
+            let term: RouterLoanTerm = router_term.as_non_fungible().non_fungible().data();
+
+            assert!(
+                repayment.resource_address() == term.res_address,
+                "Repayment resource address mismatch"
+            );
+            assert!(
+                pool_terms.len() == term.legs.len(),
+                "Pool loan terms count mismatch"
+            );
+
+            for (leg, terms) in term.legs.iter().zip(pool_terms) {
+                let pool = self.pools.get(&leg.pool_id).unwrap().pool;
+
+                let change =
+                    pool.repay_flashloan(repayment.take(leg.loan_amount + leg.fee_amount), terms);
+                repayment.put(change);
+            }
+
+            router_term.burn();
+
+            repayment
+        }
+
+        /* GETTERS */
+
+        /// Total fee for borrowing the given amount
+        pub fn get_quote(&self, res_address: ResourceAddress, amount: Decimal) -> Decimal {
+            self._route(res_address, amount)
+                .iter()
+                .fold(Decimal::ZERO, |total, leg| total + leg.fee_amount)
+        }
+
+        pub fn get_pools(&self, res_address: ResourceAddress) -> Vec<(u64, RegisteredPool)> {
+            self.pools_by_resource
+                .get(&res_address)
+                .map(|pool_ids| {
+                    pool_ids
+                        .iter()
+                        .map(|pool_id| (*pool_id, self.pools.get(pool_id).unwrap().clone()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        /// Split a loan over the registered pools of a resource, cheapest pools first. Each pool
+        /// lends up to its flashloan capacity, and pools whose minimum fee rate is above the
+        /// registered one are skipped, as they would refuse the fee.
+        fn _route(&self, res_address: ResourceAddress, amount: Decimal) -> Vec<LoanLeg> {
+            let divisibility = ResourceManager::from_address(res_address)
+                .resource_type()
+                .divisibility()
+                .expect("Resource must be fungible");
+
+            let mut pools = self.get_pools(res_address);
+            pools.sort_by(|(_, a), (_, b)| a.fee_rate.cmp(&b.fee_rate));
+
+            let mut legs = Vec::new();
+            let mut remaining = amount;
+
+            for (pool_id, registered_pool) in pools {
+                if remaining == Decimal::ZERO {
+                    break;
+                }
+
+                let pool = registered_pool.pool;
+
+                if pool.get_flashloan_fee_rate() > registered_pool.fee_rate {
+                    continue;
+                }
+
+                // Flash-mint pools can lend the whole remaining amount
+                let loan_amount = match pool.get_flashloan_capacity() {
+                    Some(capacity) => remaining.min(capacity),
+                    None => remaining,
+                };
+
+                if loan_amount == Decimal::ZERO {
+                    continue;
+                }
+
+                legs.push(LoanLeg {
+                    pool_id,
+                    loan_amount,
+                    fee_amount: (loan_amount * registered_pool.fee_rate)
+                        .checked_round(divisibility, RoundingMode::AwayFromZero)
+                        .unwrap(),
+                });
+
+                remaining -= loan_amount;
+            }
+
+            assert!(
+                remaining == Decimal::ZERO,
+                "Not enough liquidity in the registered pools"
+            );
+
+            legs
+        }
+
+        fn _assert_valid_fee_rate(fee_rate: Decimal) {
+            assert!(
+                fee_rate >= Decimal::ZERO && fee_rate <= MAX_FEE_RATE,
+                "Fee rate must be between 0 and {}",
+                MAX_FEE_RATE
+            );
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Router owned by the account, with two AssetPool components of the asset: the first one
+/// holding 100 tokens with a fee rate of 1%, registered with id 1, and the second one holding
+/// 200 tokens with a fee rate of 0.5%, registered with id 2. The pools are instantiated from the
+/// published SingleResourcePool and NativePoolAdapter packages.
+struct TestRouter {
+    env: TestEnv,
+    account: ComponentAddress,
+    asset_pool_package: PackageAddress,
+    adapter_package: PackageAddress,
+    router: ComponentAddress,
+    router_term: ResourceAddress,
+    pools: Vec<(ComponentAddress, ResourceAddress)>,
+    asset: ResourceAddress,
+}
+
+impl TestRouter {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 1);
+        let account = env.accounts[0];
+        let owner_badge = env.badges[0].clone();
+        let asset_pool_package = env.test_runner.compile_and_publish(format!(
+            "{}/../single_resource_pool",
+            env!("CARGO_MANIFEST_DIR")
+        ));
+        let adapter_package = env.test_runner.compile_and_publish(format!(
+            "{}/../native_pool_adapter",
+            env!("CARGO_MANIFEST_DIR")
+        ));
+
+        let asset = env
+            .test_runner
+            .create_fungible_resource(dec!(10000), 18, account);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "FlashloanRouter",
+                "instantiate",
+                manifest_args!(OwnerRole::Fixed(rule!(require(owner_badge.clone())))),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        let mut router = Self {
+            router: commit.new_component_addresses()[0],
+            router_term: commit.new_resource_addresses()[0],
+            env,
+            account,
+            asset_pool_package,
+            adapter_package,
+            pools: vec![],
+            asset,
+        };
+
+        router.add_pool("AssetPool", dec!(100), dec!("0.01"));
+        router.add_pool("AssetPool", dec!(200), dec!("0.005"));
+
+        router
+    }
+
+    /// Instantiate an AssetPool or NativePoolAdapter component of the asset, owned by the
+    /// account, usable by the router and funded by the account, then register it with the next
+    /// pool id
+    fn add_pool(&mut self, blueprint: &str, amount: Decimal, fee_rate: Decimal) {
+        let (router, asset) = (self.router, self.asset);
+        let owner_badge = self.env.badges[0].clone();
+        let owner_role = OwnerRole::Fixed(rule!(require(owner_badge.clone())));
+        let admin_rule = rule!(require(global_caller(router)) || require(owner_badge));
+        let package_address = if blueprint == "AssetPool" {
+            self.asset_pool_package
+        } else {
+            self.adapter_package
+        };
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                package_address,
+                blueprint,
+                "instantiate",
+                manifest_args!(asset, owner_role, admin_rule),
+            )
+            .build();
+        let (pool, _, pool_term): (ComponentAddress, ResourceAddress, ResourceAddress) = self
+            .env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![])
+            .expect_commit_success()
+            .output(0);
+        self.pools.push((pool, pool_term));
+
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, asset, amount)
+            .take_all_from_worktop(asset, "assets")
+            .call_method_with_name_lookup(pool, "contribute", |lookup| (lookup.bucket("assets"),))
+            .call_method(
+                router,
+                "register_pool",
+                manifest_args!(pool, asset, fee_rate),
+            );
+        self.execute(builder).expect_commit_success();
+    }
+
+    /// Execute the manifest with the account key, then deposit the worktop into the account
+    fn execute(&mut self, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute(0, builder)
+    }
+
+    fn call(&mut self, method: &str, args: impl ResolvableArguments) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_method(self.router, method, args);
+
+        self.execute(builder)
+    }
+
+    /// Borrow from the router and repay the loan with the given fee, returning the terms of the
+    /// given pools, in order
+    fn flashloan(&mut self, amount: Decimal, fee: Decimal, pool_ids: &[u64]) -> TransactionReceipt {
+        let (router, router_term, asset) = (self.router, self.router_term, self.asset);
+        let term_names: Vec<String> = pool_ids.iter().map(|id| format!("term_{}", id)).collect();
+
+        let mut builder = ManifestBuilder::new()
+            .call_method(router, "take_flashloan", manifest_args!(asset, amount))
+            .withdraw_from_account(self.account, asset, fee)
+            .take_all_from_worktop(asset, "repayment")
+            .take_all_from_worktop(router_term, "router_term");
+        for (pool_id, name) in pool_ids.iter().zip(&term_names) {
+            let (_, pool_term) = self.pools[*pool_id as usize - 1];
+            builder = builder.take_all_from_worktop(pool_term, name.as_str());
+        }
+        let builder = builder.call_method_with_name_lookup(router, "repay_flashloan", |lookup| {
+            let pool_terms: Vec<ManifestBucket> = term_names
+                .iter()
+                .map(|name| lookup.bucket(name.as_str()))
+                .collect();
+
+            (
+                lookup.bucket("repayment"),
+                lookup.bucket("router_term"),
+                pool_terms,
+            )
+        });
+
+        self.execute(builder)
+    }
+
+    fn get_quote(&mut self, amount: Decimal) -> TransactionReceipt {
+        let asset = self.asset;
+
+        self.call("get_quote", manifest_args!(asset, amount))
+    }
+
+    /// Liquidity held by the pool with the given id
+    fn pooled_amount(&mut self, pool_id: u64) -> Decimal {
+        let (pool, _) = self.pools[pool_id as usize - 1];
+        let builder =
+            ManifestBuilder::new().call_method(pool, "get_pooled_amount", manifest_args!());
+
+        let (pooled, _): (Decimal, Decimal) =
+            self.execute(builder).expect_commit_success().output(0);
+
+        pooled
+    }
+}
+
+#[test]
+fn test_flashloan_is_routed_to_the_cheapest_pools_first() {
+    let mut router = TestRouter::new();
+
+    let quote: Decimal = router
+        .get_quote(dec!(150))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(quote, dec!(1));
+
+    // 200 from the second pool, then 50 from the first one
+    let quote: Decimal = router
+        .get_quote(dec!(250))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(quote, dec!("1.5"));
+
+    router.get_quote(dec!(301)).expect_commit_failure();
+
+    router
+        .flashloan(dec!(250), dec!("1.5"), &[2, 1])
+        .expect_commit_success();
+
+    assert_eq!(router.pooled_amount(1), dec!("100.5"));
+    assert_eq!(router.pooled_amount(2), dec!(201));
+}
+
+#[test]
+fn test_flashloan_is_routed_to_native_pool_adapters() {
+    let mut router = TestRouter::new();
+    router.add_pool("NativePoolAdapter", dec!(50), dec!("0.001"));
+
+    // 50 from the adapter, then 200 from the second pool
+    let quote: Decimal = router
+        .get_quote(dec!(250))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(quote, dec!("1.05"));
+
+    router
+        .flashloan(dec!(250), dec!("1.05"), &[3, 2])
+        .expect_commit_success();
+
+    assert_eq!(router.pooled_amount(1), dec!(100));
+    assert_eq!(router.pooled_amount(2), dec!(201));
+    assert_eq!(router.pooled_amount(3), dec!("50.05"));
+
+    // The adapter loan term must be handed back as well
+    router
+        .flashloan(dec!(250), dec!("1.05"), &[2])
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_pools_requiring_a_higher_fee_are_skipped() {
+    let mut router = TestRouter::new();
+    let (pool, _) = router.pools[1];
+
+    // The second pool now requires 1%, above the 0.5% registered for it in the router
+    let fee_rate_change = ManifestValue::Enum {
+        discriminator: 6,
+        fields: vec![to_manifest_value_and_unwrap!(&dec!("0.01"))],
+    };
+    let builder = ManifestBuilder::new()
+        .call_method(pool, "queue_config_change", manifest_args!(fee_rate_change))
+        .call_method(pool, "apply_config_change", manifest_args!(0u64));
+    router.execute(builder).expect_commit_success();
+
+    // Only the first pool lends
+    let quote: Decimal = router
+        .get_quote(dec!(100))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(quote, dec!(1));
+
+    expect_failure_containing(
+        router.get_quote(dec!(101)),
+        "Not enough liquidity in the registered pools",
+    );
+
+    router
+        .flashloan(dec!(100), dec!(1), &[1])
+        .expect_commit_success();
+
+    assert_eq!(router.pooled_amount(1), dec!(101));
+    assert_eq!(router.pooled_amount(2), dec!(200));
+}
+
+#[test]
+fn test_flashloan_must_be_repaid_with_the_fee() {
+    let mut router = TestRouter::new();
+
+    router
+        .flashloan(dec!(250), dec!(1), &[2, 1])
+        .expect_commit_failure();
+
+    // Every pool loan term must be handed back
+    router
+        .flashloan(dec!(250), dec!("1.5"), &[2])
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_unregistered_pool_is_not_used() {
+    let mut router = TestRouter::new();
+
+    router
+        .call("unregister_pool", manifest_args!(2u64))
+        .expect_commit_success();
+
+    router.get_quote(dec!(101)).expect_commit_failure();
+    let quote: Decimal = router
+        .get_quote(dec!(100))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(quote, dec!(1));
+
+    router
+        .flashloan(dec!(100), dec!(1), &[1])
+        .expect_commit_success();
+    assert_eq!(router.pooled_amount(2), dec!(200));
+}
+
+#[test]
+fn test_admin_methods_require_the_owner() {
+    let mut router = TestRouter::new();
+    let router_address = router.router;
+
+    router
+        .call("set_pool_fee_rate", manifest_args!(1u64, dec!("0.051")))
+        .expect_commit_failure();
+    router
+        .call("set_pool_fee_rate", manifest_args!(1u64, dec!("0.001")))
+        .expect_commit_success();
+
+    // The first pool is now the cheapest one
+    let quote: Decimal = router
+        .get_quote(dec!(150))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(quote, dec!("0.35"));
+
+    let manifest = ManifestBuilder::new()
+        .call_method(router_address, "unregister_pool", manifest_args!(1u64))
+        .build();
+    router
+        .env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![])
+        .expect_commit_failure();
+}
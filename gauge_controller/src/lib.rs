@@ -0,0 +1,598 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+use ve_token::ve_token::VeToken;
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct GaugeStakeReceipt {
+    pub gauge: ResourceAddress,
+}
+
+#[derive(ScryptoSbor)]
+pub struct Gauge {
+    /// Staked pool units
+    pub staked: Vault,
+
+    /// Decrease per epoch of the voting power allocated to the gauge, by epoch at which the
+    /// allocated voting power reaches zero
+    pub vote_slopes: BTreeMap<u64, Decimal>,
+
+    /// Emissions distributed to the gauge stakers every epoch of the current period
+    pub emission_rate_per_epoch: Decimal,
+
+    /// Emissions distributed per staked pool unit since the gauge registration
+    pub reward_per_token: PreciseDecimal,
+    pub last_update_epoch: u64,
+}
+
+/// Voting power allocated by a veToken position to a gauge, decreasing by `slope` every
+/// epoch until it reaches zero at `end_epoch`
+#[derive(ScryptoSbor, Clone)]
+pub struct VoteSlope {
+    pub slope: Decimal,
+    pub end_epoch: u64,
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub struct GaugePosition {
+    pub gauge: ResourceAddress,
+    pub staked_amount: Decimal,
+    pub reward_per_token_paid: PreciseDecimal,
+    pub pending_emissions: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CheckpointEvent {
+    pub period_start_epoch: u64,
+    pub emission_rates: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct GaugeVoteEvent {
+    pub ve_position_id: NonFungibleLocalId,
+    pub votes: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[blueprint]
+#[events(CheckpointEvent, GaugeVoteEvent)]
+pub mod gauge_controller {
+
+    enable_method_auth! {
+        roles {
+            admin => updatable_by: [];
+        },
+        methods {
+
+            register_gauge => restrict_to :[admin, OWNER];
+            set_period_emission => restrict_to :[OWNER];
+            fund_emissions => PUBLIC;
+
+            vote_for_gauge => PUBLIC;
+            checkpoint => PUBLIC;
+
+            stake => PUBLIC;
+            unstake => PUBLIC;
+            claim_emissions => PUBLIC;
+
+            get_gauge_weights => PUBLIC;
+            get_pending_emissions => PUBLIC;
+
+        }
+    }
+
+    pub struct GaugeController {
+        /// Vote escrowed token component providing the voting power
+        ve_token: Global<VeToken>,
+        ve_position_res_address: ResourceAddress,
+
+        /// Funded emissions, allocated or not
+        emissions: Vault,
+
+        /// Part of the emissions vault not allocated to a period yet
+        unallocated_emissions: Decimal,
+
+        /// Emissions split across gauges every period
+        period_emission: Decimal,
+        period_epochs: u64,
+        period_start_epoch: u64,
+
+        /// Gauges indexed by the staked pool unit resource
+        gauges: KeyValueStore<ResourceAddress, Gauge>,
+        gauge_addresses: Vec<ResourceAddress>,
+
+        /// Voting power allocated by each veToken position, indexed by gauge
+        ve_votes: KeyValueStore<NonFungibleLocalId, IndexMap<ResourceAddress, VoteSlope>>,
+
+        /// Gauge stake receipt non-fungible resource manager
+        receipt_res_manager: ResourceManager,
+        positions: KeyValueStore<NonFungibleLocalId, GaugePosition>,
+    }
+
+    impl GaugeController {
+        pub fn instantiate(
+            ve_token: Global<VeToken>,
+            ve_position_res_address: ResourceAddress,
+            emission_res_address: ResourceAddress,
+            period_emission: Decimal,
+            period_epochs: u64,
+            owner_role: OwnerRole,
+            admin_rule: AccessRule,
+        ) -> (Global<GaugeController>, ResourceAddress) {
+            /* CHECK INPUT */
+            assert!(
+                period_emission >= Decimal::ZERO,
+                "Period emission must not be negative"
+            );
+            assert!(period_epochs > 0, "Period must be greater than zero");
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(GaugeController::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let receipt_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<GaugeStakeReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule;
+                        burner_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let controller_component = Self {
+                ve_token,
+                ve_position_res_address,
+                emissions: Vault::new(emission_res_address),
+                unallocated_emissions: Decimal::ZERO,
+                period_emission,
+                period_epochs,
+                period_start_epoch: Runtime::current_epoch().number(),
+                gauges: KeyValueStore::new(),
+                gauge_addresses: Vec::new(),
+                ve_votes: KeyValueStore::new(),
+                receipt_res_manager,
+                positions: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                admin => admin_rule;
+            ))
+            .with_address(address_reservation)
+            .globalize();
+
+            (controller_component, receipt_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        /// Register a gauge for the given pool unit resource. The gauge receives emissions
+        /// from the next checkpointed period.
+        pub fn register_gauge(&mut self, pool_unit_res_address: ResourceAddress) {
+            assert!(
+                self.gauges.get(&pool_unit_res_address).is_none(),
+                "Gauge already registered"
+            );
+
+            self.gauges.insert(
+                pool_unit_res_address,
+                Gauge {
+                    staked: Vault::new(pool_unit_res_address),
+                    vote_slopes: BTreeMap::new(),
+                    emission_rate_per_epoch: Decimal::ZERO,
+                    reward_per_token: PreciseDecimal::ZERO,
+                    last_update_epoch: Runtime::current_epoch().number(),
+                },
+            );
+            self.gauge_addresses.push(pool_unit_res_address);
+        }
+
+        /// Set the emissions split across gauges from the next period
+        pub fn set_period_emission(&mut self, period_emission: Decimal) {
+            assert!(
+                period_emission >= Decimal::ZERO,
+                "Period emission must not be negative"
+            );
+
+            self.period_emission = period_emission;
+        }
+
+        /// Add emissions to be distributed. Anyone can fund the emissions.
+        pub fn fund_emissions(&mut self, emissions: Bucket) {
+            assert!(
+                emissions.resource_address() == self.emissions.resource_address(),
+                "Emission resource address mismatch"
+            );
+
+            self.unallocated_emissions += emissions.amount();
+            self.emissions.put(emissions);
+        }
+
+        /* VOTER METHODS */
+
+        /// Allocate the current voting power of a veToken position across gauges. `votes`
+        /// gives the share of the voting power allocated to each gauge, and replaces the
+        /// previous allocation of the position. Votes are used from the next checkpoint.
+        ///
+        /// The allocated voting power decays with the lock, reaching zero at the last multiple
+        /// of the period length before the unlock epoch. Increasing or extending the lock does
+        /// not update the allocation: the position has to vote again to allocate its new voting
+        /// power.
+        pub fn vote_for_gauge(
+            &mut self,
+            ve_position_proof: Proof,
+            votes: IndexMap<ResourceAddress, Decimal>,
+        ) {
+            let ve_position_id = ve_position_proof
+                .check(self.ve_position_res_address)
+                .as_non_fungible()
+                .non_fungible_local_id();
+
+            /* CHECK INPUT */
+            let mut total_share = Decimal::ZERO;
+            for (gauge, share) in votes.iter() {
+                assert!(self.gauges.get(gauge).is_some(), "Gauge not registered");
+                assert!(*share >= Decimal::ZERO, "Vote share must not be negative");
+                total_share += *share;
+            }
+            assert!(
+                total_share <= Decimal::ONE,
+                "Vote shares must not sum to more than one"
+            );
+
+            // Remove the previous allocation of the position
+            let previous_allocation = self
+                .ve_votes
+                .get(&ve_position_id)
+                .map(|allocation| allocation.clone());
+            if let Some(previous_allocation) = previous_allocation {
+                for (gauge, vote_slope) in previous_allocation {
+                    GaugeController::_remove_vote_slope(
+                        &mut self.gauges.get_mut(&gauge).unwrap(),
+                        &vote_slope,
+                    );
+                }
+            }
+
+            let lock = self.ve_token.get_lock(ve_position_id.clone());
+            let max_lock_epochs = self.ve_token.get_max_lock_epochs();
+
+            // Ends are rounded down to a multiple of the period length, so each gauge keeps at
+            // most one slope per period of the maximum lock duration
+            let end_epoch = lock.unlock_epoch / self.period_epochs * self.period_epochs;
+
+            let allocation: IndexMap<ResourceAddress, VoteSlope> =
+                if end_epoch > Runtime::current_epoch().number() {
+                    votes
+                        .iter()
+                        .filter(|(_, share)| **share > Decimal::ZERO)
+                        .map(|(gauge, share)| {
+                            let vote_slope = VoteSlope {
+                                slope: lock.locked_amount * *share / max_lock_epochs,
+                                end_epoch,
+                            };
+
+                            (*gauge, vote_slope)
+                        })
+                        .collect()
+                } else {
+                    IndexMap::default()
+                };
+
+            for (gauge, vote_slope) in allocation.iter() {
+                *self
+                    .gauges
+                    .get_mut(gauge)
+                    .unwrap()
+                    .vote_slopes
+                    .entry(vote_slope.end_epoch)
+                    .or_insert(Decimal::ZERO) += vote_slope.slope;
+            }
+
+            Runtime::emit_event(GaugeVoteEvent {
+                ve_position_id: ve_position_id.clone(),
+                votes,
+            });
+
+            self.ve_votes.insert(ve_position_id, allocation);
+        }
+
+        /// Start a new emission period if the current one is over, splitting the period
+        /// emission across gauges in proportion to their votes
+        pub fn checkpoint(&mut self) {
+            let current_epoch = Runtime::current_epoch().number();
+
+            if current_epoch < self.period_start_epoch + self.period_epochs {
+                return;
+            }
+
+            let total_votes = self
+                .gauge_addresses
+                .iter()
+                .fold(Decimal::ZERO, |total, gauge| {
+                    total
+                        + GaugeController::_get_gauge_votes(
+                            &self.gauges.get(gauge).unwrap(),
+                            current_epoch,
+                        )
+                });
+
+            let period_end_epoch = self._get_period_end_epoch();
+            let period_emission = self.period_emission.min(self.unallocated_emissions);
+            let emission_divisibility = self._get_emission_divisibility();
+            let mut emission_rates = IndexMap::default();
+
+            for gauge_address in self.gauge_addresses.clone() {
+                let mut gauge = self.gauges.get_mut(&gauge_address).unwrap();
+
+                // Emissions of the previous period are distributed up to its end
+                GaugeController::_update_gauge(&mut gauge, period_end_epoch);
+
+                let gauge_votes = GaugeController::_get_gauge_votes(&gauge, current_epoch);
+
+                // Votes that reached zero are dropped
+                gauge
+                    .vote_slopes
+                    .retain(|end_epoch, _| *end_epoch > current_epoch);
+
+                let emission_rate_per_epoch = if total_votes == Decimal::ZERO {
+                    Decimal::ZERO
+                } else {
+                    (period_emission * gauge_votes / total_votes / self.period_epochs)
+                        .checked_round(emission_divisibility, RoundingMode::ToZero)
+                        .unwrap()
+                };
+
+                self.unallocated_emissions -= emission_rate_per_epoch * self.period_epochs;
+                gauge.emission_rate_per_epoch = emission_rate_per_epoch;
+                gauge.last_update_epoch = current_epoch;
+                emission_rates.insert(gauge_address, emission_rate_per_epoch);
+            }
+
+            self.period_start_epoch = current_epoch;
+
+            Runtime::emit_event(CheckpointEvent {
+                period_start_epoch: current_epoch,
+                emission_rates,
+            });
+        }
+
+        /* STAKER METHODS */
+
+        /// Stake pool units in their gauge. Returns the gauge stake receipt.
+        pub fn stake(&mut self, pool_units: Bucket) -> Bucket {
+            let gauge_address = pool_units.resource_address();
+
+            /* CHECK INPUT */
+            assert!(
+                pool_units.amount() > Decimal::ZERO,
+                "Amount must be greater than zero!"
+            );
+
+            let period_end_epoch = self._get_period_end_epoch();
+            let mut gauge = self
+                .gauges
+                .get_mut(&gauge_address)
+                .expect("Gauge not registered");
+
+            GaugeController::_update_gauge(&mut gauge, period_end_epoch);
+
+            let position = GaugePosition {
+                gauge: gauge_address,
+                staked_amount: pool_units.amount(),
+                reward_per_token_paid: gauge.reward_per_token,
+                pending_emissions: Decimal::ZERO,
+            };
+
+            gauge.staked.put(pool_units);
+            drop(gauge);
+
+            let receipt = self
+                .receipt_res_manager
+                .mint_ruid_non_fungible(GaugeStakeReceipt {
+                    gauge: gauge_address,
+                });
+
+            self.positions
+                .insert(receipt.as_non_fungible().non_fungible_local_id(), position);
+
+            receipt
+        }
+
+        /// Unstake the pool units of a position, burning its receipt. Returns the pool units
+        /// and the pending emissions.
+        pub fn unstake(&mut self, receipt: Bucket) -> (Bucket, Bucket) {
+            /* CHECK INPUT */
+            assert!(
+                receipt.resource_address() == self.receipt_res_manager.address(),
+                "Gauge stake receipt resource address mismatch"
+            );
+
+            let position_id = receipt.as_non_fungible().non_fungible_local_id();
+            receipt.burn();
+
+            let emissions = self._claim_emissions(&position_id);
+            let position = self.positions.remove(&position_id).unwrap();
+
+            let pool_units = self
+                .gauges
+                .get_mut(&position.gauge)
+                .unwrap()
+                .staked
+                .take(position.staked_amount);
+
+            (pool_units, emissions)
+        }
+
+        pub fn claim_emissions(&mut self, receipt_proof: Proof) -> Bucket {
+            let position_id = receipt_proof
+                .check(self.receipt_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id();
+
+            self._claim_emissions(&position_id)
+        }
+
+        /* GETTERS */
+
+        /// Share of the votes of each gauge at the current epoch
+        pub fn get_gauge_weights(&self) -> IndexMap<ResourceAddress, Decimal> {
+            let current_epoch = Runtime::current_epoch().number();
+
+            let votes: IndexMap<ResourceAddress, Decimal> = self
+                .gauge_addresses
+                .iter()
+                .map(|gauge| {
+                    let gauge_votes = GaugeController::_get_gauge_votes(
+                        &self.gauges.get(gauge).unwrap(),
+                        current_epoch,
+                    );
+
+                    (*gauge, gauge_votes)
+                })
+                .collect();
+
+            let total_votes = votes
+                .values()
+                .fold(Decimal::ZERO, |total, votes| total + *votes);
+
+            votes
+                .into_iter()
+                .map(|(gauge, votes)| {
+                    let weight = if total_votes == Decimal::ZERO {
+                        Decimal::ZERO
+                    } else {
+                        votes / total_votes
+                    };
+
+                    (gauge, weight)
+                })
+                .collect()
+        }
+
+        pub fn get_pending_emissions(&self, position_id: NonFungibleLocalId) -> Decimal {
+            let position = self
+                .positions
+                .get(&position_id)
+                .expect("Position not found");
+            let gauge = self.gauges.get(&position.gauge).unwrap();
+
+            position.pending_emissions
+                + GaugeController::_compute_emissions(
+                    position.staked_amount,
+                    GaugeController::_get_current_reward_per_token(
+                        &gauge,
+                        self._get_period_end_epoch(),
+                    ) - position.reward_per_token_paid,
+                )
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _claim_emissions(&mut self, position_id: &NonFungibleLocalId) -> Bucket {
+            let period_end_epoch = self._get_period_end_epoch();
+            let mut position = self.positions.get_mut(position_id).unwrap();
+            let mut gauge = self.gauges.get_mut(&position.gauge).unwrap();
+
+            GaugeController::_update_gauge(&mut gauge, period_end_epoch);
+
+            let amount = position.pending_emissions
+                + GaugeController::_compute_emissions(
+                    position.staked_amount,
+                    gauge.reward_per_token - position.reward_per_token_paid,
+                );
+
+            position.reward_per_token_paid = gauge.reward_per_token;
+            position.pending_emissions = Decimal::ZERO;
+
+            self.emissions.take(amount)
+        }
+
+        /// Reward per token accumulator of a gauge at the current epoch. Emissions stop at the
+        /// end of the period until the next checkpoint.
+        fn _get_current_reward_per_token(gauge: &Gauge, period_end_epoch: u64) -> PreciseDecimal {
+            let current_epoch = Runtime::current_epoch().number().min(period_end_epoch);
+            let total_staked = gauge.staked.amount();
+
+            if total_staked == Decimal::ZERO || current_epoch <= gauge.last_update_epoch {
+                return gauge.reward_per_token;
+            }
+
+            let distributed =
+                gauge.emission_rate_per_epoch * (current_epoch - gauge.last_update_epoch);
+
+            gauge.reward_per_token
+                + PreciseDecimal::from(distributed) / PreciseDecimal::from(total_staked)
+        }
+
+        fn _update_gauge(gauge: &mut Gauge, period_end_epoch: u64) {
+            gauge.reward_per_token =
+                GaugeController::_get_current_reward_per_token(gauge, period_end_epoch);
+            gauge.last_update_epoch = Runtime::current_epoch().number().min(period_end_epoch);
+        }
+
+        /// Voting power allocated to a gauge at the given epoch
+        fn _get_gauge_votes(gauge: &Gauge, epoch: u64) -> Decimal {
+            gauge
+                .vote_slopes
+                .range(epoch + 1..)
+                .fold(Decimal::ZERO, |votes, (end_epoch, slope)| {
+                    votes + *slope * (end_epoch - epoch)
+                })
+        }
+
+        fn _remove_vote_slope(gauge: &mut Gauge, vote_slope: &VoteSlope) {
+            if let Some(slope) = gauge.vote_slopes.get_mut(&vote_slope.end_epoch) {
+                *slope -= vote_slope.slope;
+
+                if *slope <= Decimal::ZERO {
+                    gauge.vote_slopes.remove(&vote_slope.end_epoch);
+                }
+            }
+        }
+
+        fn _get_period_end_epoch(&self) -> u64 {
+            self.period_start_epoch + self.period_epochs
+        }
+
+        fn _compute_emissions(
+            staked_amount: Decimal,
+            reward_per_token_delta: PreciseDecimal,
+        ) -> Decimal {
+            (PreciseDecimal::from(staked_amount) * reward_per_token_delta)
+                .checked_truncate(RoundingMode::ToZero)
+                .unwrap()
+        }
+
+        fn _get_emission_divisibility(&self) -> u8 {
+            match ResourceManager::from_address(self.emissions.resource_address()).resource_type() {
+                ResourceType::Fungible { divisibility } => divisibility,
+                _ => panic!("Emission resource must be fungible"),
+            }
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owner of the controller and staker of the gauges
+const OWNER: usize = 0;
+/// veToken holders, holding 1000 locked resource tokens each
+const ALICE: usize = 1;
+const BOB: usize = 2;
+
+/// Controller instantiated at epoch 100, splitting 1000 emissions every period of 10 epochs
+/// across two gauges. The veToken has a maximum lock duration of 100 epochs.
+struct TestController {
+    env: TestEnv,
+    controller: ComponentAddress,
+    receipt: ResourceAddress,
+    ve_token: ComponentAddress,
+    ve_position: ResourceAddress,
+    locked: ResourceAddress,
+    emission: ResourceAddress,
+    gauge_x: ResourceAddress,
+    gauge_y: ResourceAddress,
+}
+
+impl TestController {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(100);
+        let ve_package_address = env
+            .test_runner
+            .compile_and_publish(format!("{}/../ve_token", env!("CARGO_MANIFEST_DIR")));
+
+        let locked = env
+            .test_runner
+            .create_fungible_resource(dec!(2000), 18, env.accounts[OWNER]);
+        let emission =
+            env.test_runner
+                .create_fungible_resource(dec!(100000), 18, env.accounts[OWNER]);
+        let gauge_x = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, env.accounts[OWNER]);
+        let gauge_y = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, env.accounts[OWNER]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                ve_package_address,
+                "VeToken",
+                "instantiate",
+                manifest_args!(locked, 100u64, OwnerRole::None),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+        let ve_token = commit.new_component_addresses()[0];
+        let ve_position = commit.new_resource_addresses()[0];
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "GaugeController",
+                "instantiate",
+                manifest_args!(
+                    ve_token,
+                    ve_position,
+                    emission,
+                    dec!(1000),
+                    10u64,
+                    OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone()))),
+                    rule!(deny_all)
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        let mut test_controller = Self {
+            controller: commit.new_component_addresses()[0],
+            receipt: commit.new_resource_addresses()[0],
+            env,
+            ve_token,
+            ve_position,
+            locked,
+            emission,
+            gauge_x,
+            gauge_y,
+        };
+
+        for gauge in [gauge_x, gauge_y] {
+            test_controller
+                .call(OWNER, "register_gauge", manifest_args!(gauge))
+                .expect_commit_success();
+        }
+
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(test_controller.env.accounts[OWNER], emission, dec!(10000))
+            .take_all_from_worktop(emission, "emissions")
+            .call_method_with_name_lookup(test_controller.controller, "fund_emissions", |lookup| {
+                (lookup.bucket("emissions"),)
+            })
+            .withdraw_from_account(test_controller.env.accounts[OWNER], locked, dec!(2000))
+            .take_from_worktop(locked, dec!(1000), "alice")
+            .call_method_with_name_lookup(
+                test_controller.env.accounts[ALICE],
+                "deposit",
+                |lookup| (lookup.bucket("alice"),),
+            );
+        test_controller
+            .execute(BOB, builder)
+            .expect_commit_success();
+
+        test_controller
+    }
+
+    /// Execute the manifest with the keys of every account, then deposit the worktop into the
+    /// given account
+    fn execute(&mut self, account: usize, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute_with_all_keys(account, builder)
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_method(self.controller, method, args);
+
+        self.execute(account, builder)
+    }
+
+    fn with_ve_position_proof(&self, voter: usize) -> ManifestBuilder {
+        ManifestBuilder::new()
+            .create_proof_from_account_of_amount(
+                self.env.accounts[voter],
+                self.ve_position,
+                dec!(1),
+            )
+            .pop_from_auth_zone("position")
+    }
+
+    fn lock(&mut self, voter: usize, amount: Decimal, duration_epochs: u64) {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[voter], self.locked, amount)
+            .take_all_from_worktop(self.locked, "tokens")
+            .call_method_with_name_lookup(self.ve_token, "create_lock", |lookup| {
+                (lookup.bucket("tokens"), duration_epochs)
+            });
+
+        self.execute(voter, builder).expect_commit_success();
+    }
+
+    fn increase_amount(&mut self, voter: usize, amount: Decimal) {
+        let builder = self
+            .with_ve_position_proof(voter)
+            .withdraw_from_account(self.env.accounts[voter], self.locked, amount)
+            .take_all_from_worktop(self.locked, "tokens")
+            .call_method_with_name_lookup(self.ve_token, "increase_amount", |lookup| {
+                (lookup.proof("position"), lookup.bucket("tokens"))
+            });
+
+        self.execute(voter, builder).expect_commit_success();
+    }
+
+    fn vote(
+        &mut self,
+        voter: usize,
+        votes: IndexMap<ResourceAddress, Decimal>,
+    ) -> TransactionReceipt {
+        let builder = self
+            .with_ve_position_proof(voter)
+            .call_method_with_name_lookup(self.controller, "vote_for_gauge", |lookup| {
+                (lookup.proof("position"), votes)
+            });
+
+        self.execute(voter, builder)
+    }
+
+    fn get_gauge_weights(&mut self) -> IndexMap<ResourceAddress, Decimal> {
+        self.call(OWNER, "get_gauge_weights", manifest_args!())
+            .expect_commit_success()
+            .output(0)
+    }
+
+    fn set_epoch(&mut self, epoch: u64) {
+        self.env.set_epoch(epoch);
+    }
+}
+
+/// Alice locks 100 tokens until epoch 200 and votes for the first gauge, Bob locks 100 tokens
+/// until epoch 150 and votes for the second one
+fn voted_controller() -> TestController {
+    let mut controller = TestController::new();
+    let (gauge_x, gauge_y) = (controller.gauge_x, controller.gauge_y);
+
+    controller.lock(ALICE, dec!(100), 100);
+    controller.lock(BOB, dec!(100), 50);
+    controller
+        .vote(ALICE, indexmap!(gauge_x => dec!(1)))
+        .expect_commit_success();
+    controller
+        .vote(BOB, indexmap!(gauge_y => dec!(1)))
+        .expect_commit_success();
+
+    controller
+}
+
+#[test]
+fn test_votes_decay_with_the_locks() {
+    let mut controller = voted_controller();
+    let (gauge_x, gauge_y) = (controller.gauge_x, controller.gauge_y);
+
+    // 100 and 50 voting power
+    assert_eq!(
+        controller.get_gauge_weights(),
+        indexmap!(gauge_x => dec!(100) / dec!(150), gauge_y => dec!(50) / dec!(150))
+    );
+
+    // 60 and 10 voting power
+    controller.set_epoch(140);
+    assert_eq!(
+        controller.get_gauge_weights(),
+        indexmap!(gauge_x => dec!(60) / dec!(70), gauge_y => dec!(10) / dec!(70))
+    );
+
+    // Bob's lock expired
+    controller.set_epoch(150);
+    assert_eq!(
+        controller.get_gauge_weights(),
+        indexmap!(gauge_x => dec!(1), gauge_y => dec!(0))
+    );
+}
+
+#[test]
+fn test_checkpoint_splits_emissions_by_decayed_votes() {
+    let mut controller = voted_controller();
+    let (gauge_x, emission, owner) = (
+        controller.gauge_x,
+        controller.emission,
+        controller.env.accounts[OWNER],
+    );
+
+    // 90 and 40 voting power at the checkpoint
+    controller.set_epoch(110);
+    controller
+        .call(OWNER, "checkpoint", manifest_args!())
+        .expect_commit_success();
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(owner, gauge_x, dec!(100))
+        .take_all_from_worktop(gauge_x, "pool_units")
+        .call_method_with_name_lookup(controller.controller, "stake", |lookup| {
+            (lookup.bucket("pool_units"),)
+        });
+    controller.execute(OWNER, builder).expect_commit_success();
+
+    controller.set_epoch(115);
+    let receipt = controller.receipt;
+    let builder = ManifestBuilder::new()
+        .create_proof_from_account_of_amount(owner, receipt, dec!(1))
+        .pop_from_auth_zone("receipt")
+        .call_method_with_name_lookup(controller.controller, "claim_emissions", |lookup| {
+            (lookup.proof("receipt"),)
+        });
+    controller.execute(OWNER, builder).expect_commit_success();
+
+    let emission_rate_per_epoch = dec!(1000) * dec!(90) / dec!(130) / dec!(10);
+    let claimed = controller
+        .env
+        .test_runner
+        .get_component_balance(owner, emission)
+        - dec!(90000);
+    let expected = emission_rate_per_epoch * dec!(5);
+
+    assert!(claimed <= expected && expected - claimed <= dec!("0.000000000001"));
+}
+
+#[test]
+fn test_expired_lock_does_not_vote() {
+    let mut controller = voted_controller();
+    let (gauge_x, gauge_y) = (controller.gauge_x, controller.gauge_y);
+
+    // Bob's lock expired and he moves his vote to the first gauge
+    controller.set_epoch(150);
+    controller
+        .vote(BOB, indexmap!(gauge_x => dec!(1)))
+        .expect_commit_success();
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(
+            controller.env.accounts[BOB],
+            controller.ve_position,
+            dec!(1),
+        )
+        .take_all_from_worktop(controller.ve_position, "position")
+        .call_method_with_name_lookup(controller.ve_token, "withdraw", |lookup| {
+            (lookup.bucket("position"),)
+        });
+    controller.execute(BOB, builder).expect_commit_success();
+
+    controller.set_epoch(160);
+    assert_eq!(
+        controller.get_gauge_weights(),
+        indexmap!(gauge_x => dec!(1), gauge_y => dec!(0))
+    );
+
+    // Only Alice's 40 voting power is left
+    controller.lock(BOB, dec!(40), 100);
+    controller
+        .vote(BOB, indexmap!(gauge_y => dec!(1)))
+        .expect_commit_success();
+    assert_eq!(
+        controller.get_gauge_weights(),
+        indexmap!(gauge_x => dec!("0.5"), gauge_y => dec!("0.5"))
+    );
+}
+
+#[test]
+fn test_new_vote_replaces_the_allocation() {
+    let mut controller = voted_controller();
+    let (gauge_x, gauge_y) = (controller.gauge_x, controller.gauge_y);
+
+    controller
+        .vote(
+            ALICE,
+            indexmap!(gauge_x => dec!("0.5"), gauge_y => dec!("0.5")),
+        )
+        .expect_commit_success();
+
+    // 50 and 100 voting power
+    assert_eq!(
+        controller.get_gauge_weights(),
+        indexmap!(gauge_x => dec!(50) / dec!(150), gauge_y => dec!(100) / dec!(150))
+    );
+}
+
+#[test]
+fn test_increased_lock_votes_after_a_new_vote() {
+    let mut controller = voted_controller();
+    let (gauge_x, gauge_y) = (controller.gauge_x, controller.gauge_y);
+
+    controller.increase_amount(ALICE, dec!(100));
+    assert_eq!(
+        controller.get_gauge_weights(),
+        indexmap!(gauge_x => dec!(100) / dec!(150), gauge_y => dec!(50) / dec!(150))
+    );
+
+    controller
+        .vote(ALICE, indexmap!(gauge_x => dec!(1)))
+        .expect_commit_success();
+    assert_eq!(
+        controller.get_gauge_weights(),
+        indexmap!(gauge_x => dec!(200) / dec!(250), gauge_y => dec!(50) / dec!(250))
+    );
+}
+
+#[test]
+fn test_invalid_votes_fail() {
+    let mut controller = voted_controller();
+    let (gauge_x, gauge_y) = (controller.gauge_x, controller.gauge_y);
+
+    controller
+        .vote(
+            ALICE,
+            indexmap!(gauge_x => dec!("0.6"), gauge_y => dec!("0.6")),
+        )
+        .expect_commit_failure();
+
+    let emission = controller.emission;
+    controller
+        .vote(ALICE, indexmap!(emission => dec!(1)))
+        .expect_commit_failure();
+}
@@ -0,0 +1,313 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+
+/// Hash of a claim code, to be passed when issuing a voucher
+pub fn claim_code_hash(code: &[u8]) -> Hash {
+    hash(code)
+}
+
+/// Voucher NFT, redeemable for the locked funds
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct Voucher {
+    pub res_address: ResourceAddress,
+    pub amount: Decimal,
+    pub expires_at_epoch: Option<u64>,
+    pub requires_code: bool,
+}
+
+/// Receipt kept by the issuer to reclaim the funds of an expired voucher
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct IssuerReceipt {
+    pub voucher_id: NonFungibleLocalId,
+}
+
+#[derive(ScryptoSbor)]
+pub struct VoucherState {
+    pub funds: Vault,
+    pub code_hash: Option<Hash>,
+    pub expires_at_epoch: Option<u64>,
+    pub redeemed: bool,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct VoucherIssuedEvent {
+    pub voucher_id: NonFungibleLocalId,
+    pub res_address: ResourceAddress,
+    pub amount: Decimal,
+    pub expires_at_epoch: Option<u64>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct VoucherRedeemedEvent {
+    pub voucher_id: NonFungibleLocalId,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct VoucherReclaimedEvent {
+    pub voucher_id: NonFungibleLocalId,
+    pub amount: Decimal,
+}
+
+#[blueprint]
+#[events(VoucherIssuedEvent, VoucherRedeemedEvent, VoucherReclaimedEvent)]
+pub mod gift_vouchers {
+
+    enable_method_auth! {
+        methods {
+
+            issue => PUBLIC;
+            reclaim => PUBLIC;
+
+            redeem => PUBLIC;
+
+            get_voucher_value => PUBLIC;
+            is_redeemable => PUBLIC;
+
+        }
+    }
+
+    pub struct GiftVouchers {
+        /// Voucher non-fungible resource manager
+        voucher_res_manager: ResourceManager,
+
+        /// Issuer receipt non-fungible resource manager
+        issuer_receipt_res_manager: ResourceManager,
+
+        vouchers: KeyValueStore<NonFungibleLocalId, VoucherState>,
+    }
+
+    impl GiftVouchers {
+        pub fn instantiate(
+            owner_role: OwnerRole,
+        ) -> (Global<GiftVouchers>, ResourceAddress, ResourceAddress) {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(GiftVouchers::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let voucher_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<Voucher>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule.clone();
+                        burner_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let issuer_receipt_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<IssuerReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule;
+                        burner_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let vouchers_component = Self {
+                voucher_res_manager,
+                issuer_receipt_res_manager,
+                vouchers: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (
+                vouchers_component,
+                voucher_res_manager.address(),
+                issuer_receipt_res_manager.address(),
+            )
+        }
+
+        /* ISSUER METHODS */
+
+        /// Lock funds behind a voucher. If a claim code hash is given, the preimage must be
+        /// provided to redeem the voucher. Returns the voucher and the issuer receipt.
+        pub fn issue(
+            &mut self,
+            funds: Bucket,
+            code_hash: Option<Hash>,
+            expiry_epochs: Option<u64>,
+        ) -> (Bucket, Bucket) {
+            /* CHECK INPUT */
+            assert!(
+                funds.amount() > Decimal::ZERO,
+                "Voucher value must be greater than zero!"
+            );
+            assert!(
+                expiry_epochs.map_or(true, |epochs| epochs > 0),
+                "Expiry must be greater than zero"
+            );
+
+            let expires_at_epoch =
+                expiry_epochs.map(|epochs| Runtime::current_epoch().number() + epochs);
+
+            let res_address = funds.resource_address();
+            let amount = funds.amount();
+
+            let voucher = self.voucher_res_manager.mint_ruid_non_fungible(Voucher {
+                res_address,
+                amount,
+                expires_at_epoch,
+                requires_code: code_hash.is_some(),
+            });
+
+            let voucher_id = voucher.as_non_fungible().non_fungible_local_id();
+
+            let issuer_receipt =
+                self.issuer_receipt_res_manager
+                    .mint_ruid_non_fungible(IssuerReceipt {
+                        voucher_id: voucher_id.clone(),
+                    });
+
+            self.vouchers.insert(
+                voucher_id.clone(),
+                VoucherState {
+                    funds: Vault::with_bucket(funds),
+                    code_hash,
+                    expires_at_epoch,
+                    redeemed: false,
+                },
+            );
+
+            Runtime::emit_event(VoucherIssuedEvent {
+                voucher_id,
+                res_address,
+                amount,
+                expires_at_epoch,
+            });
+
+            (voucher, issuer_receipt)
+        }
+
+        /// Reclaim the funds of an expired and unredeemed voucher. The issuer receipt is burned,
+        /// and the returned bucket is empty if the voucher was already redeemed.
+        pub fn reclaim(&mut self, issuer_receipt: Bucket) -> Bucket {
+            assert!(
+                issuer_receipt.resource_address() == self.issuer_receipt_res_manager.address(),
+                "Invalid issuer receipt"
+            );
+
+            let receipt_data: IssuerReceipt =
+                issuer_receipt.as_non_fungible().non_fungible().data();
+
+            let mut voucher = self.vouchers.get_mut(&receipt_data.voucher_id).unwrap();
+
+            if !voucher.redeemed {
+                let expires_at_epoch = voucher
+                    .expires_at_epoch
+                    .expect("Voucher without expiry can not be reclaimed");
+
+                assert!(
+                    Runtime::current_epoch().number() >= expires_at_epoch,
+                    "Voucher not expired yet"
+                );
+            }
+
+            let funds = voucher.funds.take_all();
+
+            drop(voucher);
+
+            issuer_receipt.burn();
+
+            if !funds.is_empty() {
+                Runtime::emit_event(VoucherReclaimedEvent {
+                    voucher_id: receipt_data.voucher_id,
+                    amount: funds.amount(),
+                });
+            }
+
+            funds
+        }
+
+        /* HOLDER METHODS */
+
+        /// Redeem a voucher for its locked funds. The voucher is burned.
+        pub fn redeem(&mut self, voucher: Bucket, code: Option<Vec<u8>>) -> Bucket {
+            assert!(
+                voucher.resource_address() == self.voucher_res_manager.address(),
+                "Invalid voucher"
+            );
+
+            let voucher_id = voucher.as_non_fungible().non_fungible_local_id();
+
+            let mut voucher_state = self.vouchers.get_mut(&voucher_id).unwrap();
+
+            assert!(
+                voucher_state
+                    .expires_at_epoch
+                    .map_or(true, |epoch| Runtime::current_epoch().number() < epoch),
+                "Voucher expired"
+            );
+
+            if let Some(code_hash) = voucher_state.code_hash {
+                let code = code.expect("Claim code required");
+                assert!(claim_code_hash(&code) == code_hash, "Invalid claim code");
+            }
+
+            voucher_state.redeemed = true;
+            let funds = voucher_state.funds.take_all();
+
+            drop(voucher_state);
+
+            voucher.burn();
+
+            Runtime::emit_event(VoucherRedeemedEvent {
+                voucher_id,
+                amount: funds.amount(),
+            });
+
+            funds
+        }
+
+        /* GETTERS */
+
+        pub fn get_voucher_value(&self, voucher_id: NonFungibleLocalId) -> Decimal {
+            self.vouchers
+                .get(&voucher_id)
+                .expect("Voucher not found")
+                .funds
+                .amount()
+        }
+
+        pub fn is_redeemable(&self, voucher_id: NonFungibleLocalId) -> bool {
+            let voucher = self.vouchers.get(&voucher_id).expect("Voucher not found");
+
+            !voucher.redeemed
+                && !voucher.funds.is_empty()
+                && voucher
+                    .expires_at_epoch
+                    .map_or(true, |epoch| Runtime::current_epoch().number() < epoch)
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+const OWNER: usize = 0;
+/// Holds 100 tokens, and issues the vouchers
+const ISSUER: usize = 1;
+/// Receives the vouchers
+const ALICE: usize = 2;
+
+/// Gift vouchers component at epoch 10
+struct TestVouchers {
+    env: TestEnv,
+    vouchers: ComponentAddress,
+    voucher: ResourceAddress,
+    issuer_receipt: ResourceAddress,
+    token: ResourceAddress,
+}
+
+impl TestVouchers {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let token = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[ISSUER]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "GiftVouchers",
+                "instantiate",
+                manifest_args!(OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())))),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let (vouchers, voucher, issuer_receipt): (
+            ComponentAddress,
+            ResourceAddress,
+            ResourceAddress,
+        ) = receipt.expect_commit_success().output(0);
+
+        Self {
+            env,
+            vouchers,
+            voucher,
+            issuer_receipt,
+            token,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.vouchers, method, args)
+    }
+
+    /// Issue a voucher of the given amount of tokens of the issuer, then send it to Alice. The
+    /// issuer keeps the issuer receipt.
+    fn issue(
+        &mut self,
+        amount: Decimal,
+        code: Option<&[u8]>,
+        expiry_epochs: Option<u64>,
+    ) -> TransactionReceipt {
+        let (token, voucher) = (self.token, self.voucher);
+        let code_hash = code.map(hash);
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[ISSUER], token, amount)
+            .take_all_from_worktop(token, "funds")
+            .call_method_with_name_lookup(self.vouchers, "issue", |lookup| {
+                (lookup.bucket("funds"), code_hash, expiry_epochs)
+            })
+            .take_all_from_worktop(voucher, "voucher")
+            .call_method_with_name_lookup(self.env.accounts[ALICE], "deposit", |lookup| {
+                (lookup.bucket("voucher"),)
+            })
+            .deposit_batch(self.env.accounts[ISSUER])
+            .build();
+
+        self.env.test_runner.execute_manifest_ignoring_fee(
+            manifest,
+            vec![
+                self.env.badges[ISSUER].clone(),
+                self.env.badges[ALICE].clone(),
+            ],
+        )
+    }
+
+    /// Id of the only voucher of Alice
+    fn voucher_id(&mut self) -> NonFungibleLocalId {
+        let vault_id = self
+            .env
+            .test_runner
+            .get_component_vaults(self.env.accounts[ALICE], self.voucher)[0];
+        let (_, mut ids) = self
+            .env
+            .test_runner
+            .inspect_non_fungible_vault(vault_id)
+            .unwrap();
+
+        ids.next().unwrap()
+    }
+
+    /// Redeem the given resource of the account as a voucher
+    fn redeem_resource(
+        &mut self,
+        account: usize,
+        res_address: ResourceAddress,
+        code: Option<&[u8]>,
+    ) -> TransactionReceipt {
+        let code = code.map(|code| code.to_vec());
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[account], res_address, dec!(1))
+            .take_all_from_worktop(res_address, "voucher")
+            .call_method_with_name_lookup(self.vouchers, "redeem", |lookup| {
+                (lookup.bucket("voucher"), code)
+            });
+
+        self.env.execute(account, builder)
+    }
+
+    fn redeem(&mut self, code: Option<&[u8]>) -> TransactionReceipt {
+        self.redeem_resource(ALICE, self.voucher, code)
+    }
+
+    /// Reclaim the funds of a voucher with the given resource of the account
+    fn reclaim(&mut self, account: usize, res_address: ResourceAddress) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[account], res_address, dec!(1))
+            .take_all_from_worktop(res_address, "receipt")
+            .call_method_with_name_lookup(self.vouchers, "reclaim", |lookup| {
+                (lookup.bucket("receipt"),)
+            });
+
+        self.env.execute(account, builder)
+    }
+
+    fn is_redeemable(&mut self, voucher_id: NonFungibleLocalId) -> bool {
+        self.call(OWNER, "is_redeemable", manifest_args!(voucher_id))
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_voucher_is_redeemed_with_its_code() {
+    let mut vouchers = TestVouchers::new();
+    let (token, issuer_receipt) = (vouchers.token, vouchers.issuer_receipt);
+
+    vouchers
+        .issue(dec!(30), Some(b"secret"), Some(10))
+        .expect_commit_success();
+    assert_eq!(vouchers.env.balance(ISSUER, token), dec!(70));
+    assert_eq!(vouchers.env.balance(ISSUER, issuer_receipt), dec!(1));
+
+    let voucher_id = vouchers.voucher_id();
+    assert!(vouchers.is_redeemable(voucher_id.clone()));
+    let value: Decimal = vouchers
+        .call(
+            OWNER,
+            "get_voucher_value",
+            manifest_args!(voucher_id.clone()),
+        )
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(value, dec!(30));
+
+    vouchers.redeem(None).expect_commit_failure();
+    vouchers.redeem(Some(b"wrong")).expect_commit_failure();
+    vouchers.redeem(Some(b"secret")).expect_commit_success();
+    assert_eq!(vouchers.env.balance(ALICE, token), dec!(30));
+    assert!(!vouchers.is_redeemable(voucher_id));
+
+    // Reclaiming a redeemed voucher only burns the issuer receipt
+    vouchers
+        .reclaim(ISSUER, issuer_receipt)
+        .expect_commit_success();
+    assert_eq!(vouchers.env.balance(ISSUER, token), dec!(70));
+    assert_eq!(vouchers.env.balance(ISSUER, issuer_receipt), dec!(0));
+}
+
+#[test]
+fn test_expired_voucher_is_reclaimed() {
+    let mut vouchers = TestVouchers::new();
+    let (token, issuer_receipt) = (vouchers.token, vouchers.issuer_receipt);
+
+    vouchers
+        .issue(dec!(30), None, Some(10))
+        .expect_commit_success();
+    let voucher_id = vouchers.voucher_id();
+
+    vouchers.env.set_epoch(15);
+    vouchers
+        .reclaim(ISSUER, issuer_receipt)
+        .expect_commit_failure();
+
+    // The voucher expires at epoch 20
+    vouchers.env.set_epoch(20);
+    assert!(!vouchers.is_redeemable(voucher_id));
+    vouchers.redeem(None).expect_commit_failure();
+
+    vouchers
+        .reclaim(ISSUER, issuer_receipt)
+        .expect_commit_success();
+    assert_eq!(vouchers.env.balance(ISSUER, token), dec!(100));
+    assert_eq!(vouchers.env.balance(ALICE, token), dec!(0));
+}
+
+#[test]
+fn test_invalid_vouchers_are_rejected() {
+    let mut vouchers = TestVouchers::new();
+    let (token, voucher, issuer_receipt) =
+        (vouchers.token, vouchers.voucher, vouchers.issuer_receipt);
+
+    vouchers.issue(dec!(0), None, None).expect_commit_failure();
+    vouchers
+        .issue(dec!(30), None, Some(0))
+        .expect_commit_failure();
+    vouchers.issue(dec!(30), None, None).expect_commit_success();
+
+    // A voucher without expiry can not be reclaimed
+    vouchers.env.set_epoch(1000);
+    vouchers
+        .reclaim(ISSUER, issuer_receipt)
+        .expect_commit_failure();
+
+    // Vouchers and issuer receipts are not interchangeable
+    vouchers.reclaim(ALICE, voucher).expect_commit_failure();
+    vouchers
+        .redeem_resource(ISSUER, issuer_receipt, None)
+        .expect_commit_failure();
+
+    vouchers.redeem(None).expect_commit_success();
+    assert_eq!(vouchers.env.balance(ALICE, token), dec!(30));
+}
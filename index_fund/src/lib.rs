@@ -0,0 +1,382 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use multi_resource_pool::multi_pool::MultiAssetPool;
+use multi_resource_pool::{DepositType, WithdrawType};
+use price_oracle::price_oracle::PriceOracle;
+use scrypto::prelude::*;
+use swap_router::swap_router::SwapRouter;
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct IndexAsset {
+    pub res_address: ResourceAddress,
+    /// Share of the fund value held in this resource
+    pub target_weight: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RebalancedEvent {
+    pub sold_res_address: ResourceAddress,
+    pub sold_amount: Decimal,
+    pub bought_res_address: ResourceAddress,
+    pub bought_amount: Decimal,
+}
+
+#[blueprint]
+#[events(RebalancedEvent)]
+pub mod index_fund {
+
+    enable_method_auth! {
+        roles {
+            keeper => updatable_by: [OWNER];
+        },
+        methods {
+
+            set_target_weights => restrict_to :[OWNER];
+            set_deviation_band => restrict_to :[OWNER];
+            set_max_slippage => restrict_to :[OWNER];
+            set_router => restrict_to :[OWNER];
+            set_oracle => restrict_to :[OWNER];
+
+            rebalance => restrict_to :[keeper, OWNER];
+
+            deposit => PUBLIC;
+            redeem => PUBLIC;
+
+            get_target_weights => PUBLIC;
+            get_weights => PUBLIC;
+            get_value => PUBLIC;
+
+        }
+    }
+
+    pub struct IndexFund {
+        /// Pool holding the fund assets and minting index shares
+        pool: Owned<MultiAssetPool>,
+
+        target_weights: Vec<IndexAsset>,
+
+        /// Maximum difference between the weight of an asset and its target weight before the
+        /// asset can be rebalanced
+        deviation_band: Decimal,
+
+        /// Maximum loss of a rebalancing trade compared to the oracle prices
+        max_slippage: Decimal,
+
+        /// Router through which rebalancing trades are made
+        router: Global<SwapRouter>,
+
+        /// Oracle pricing every asset in the quote resource
+        oracle: Global<PriceOracle>,
+        quote_res_address: ResourceAddress,
+        max_price_age_seconds: i64,
+    }
+
+    impl IndexFund {
+        #[allow(clippy::too_many_arguments)]
+        pub fn instantiate(
+            target_weights: Vec<IndexAsset>,
+            deviation_band: Decimal,
+            max_slippage: Decimal,
+            router: Global<SwapRouter>,
+            oracle: Global<PriceOracle>,
+            quote_res_address: ResourceAddress,
+            max_price_age_seconds: i64,
+            owner_role: OwnerRole,
+            keeper_rule: AccessRule,
+        ) -> (Global<IndexFund>, ResourceAddress) {
+            /* CHECK INPUT */
+            IndexFund::_assert_valid_target_weights(&target_weights);
+            IndexFund::_assert_valid_ratio(deviation_band, "Deviation band");
+            IndexFund::_assert_valid_ratio(max_slippage, "Max slippage");
+            assert!(
+                max_price_age_seconds > 0,
+                "Max price age must be greater than zero"
+            );
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(IndexFund::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let (pool, share_res_address) = MultiAssetPool::instantiate_locally(
+                target_weights
+                    .iter()
+                    .map(|asset| asset.res_address)
+                    .collect(),
+                owner_role.clone(),
+                component_rule,
+            );
+
+            let fund_component = Self {
+                pool,
+                target_weights,
+                deviation_band,
+                max_slippage,
+                router,
+                oracle,
+                quote_res_address,
+                max_price_age_seconds,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                keeper => keeper_rule;
+            ))
+            .with_address(address_reservation)
+            .globalize();
+
+            (fund_component, share_res_address)
+        }
+
+        /* ADMIN METHODS */
+
+        /// Update the target weights. The fund resources can not be changed.
+        pub fn set_target_weights(&mut self, target_weights: Vec<IndexAsset>) {
+            /* CHECK INPUT */
+            IndexFund::_assert_valid_target_weights(&target_weights);
+            assert!(
+                target_weights.len() == self.target_weights.len()
+                    && self.target_weights.iter().all(|current| {
+                        target_weights
+                            .iter()
+                            .any(|asset| asset.res_address == current.res_address)
+                    }),
+                "Target weights must cover the fund resources"
+            );
+
+            self.target_weights = target_weights;
+        }
+
+        pub fn set_deviation_band(&mut self, deviation_band: Decimal) {
+            IndexFund::_assert_valid_ratio(deviation_band, "Deviation band");
+
+            self.deviation_band = deviation_band;
+        }
+
+        pub fn set_max_slippage(&mut self, max_slippage: Decimal) {
+            IndexFund::_assert_valid_ratio(max_slippage, "Max slippage");
+
+            self.max_slippage = max_slippage;
+        }
+
+        pub fn set_router(&mut self, router: Global<SwapRouter>) {
+            self.router = router;
+        }
+
+        pub fn set_oracle(
+            &mut self,
+            oracle: Global<PriceOracle>,
+            quote_res_address: ResourceAddress,
+            max_price_age_seconds: i64,
+        ) {
+            assert!(
+                max_price_age_seconds > 0,
+                "Max price age must be greater than zero"
+            );
+
+            self.oracle = oracle;
+            self.quote_res_address = quote_res_address;
+            self.max_price_age_seconds = max_price_age_seconds;
+        }
+
+        /* KEEPER METHODS */
+
+        /// Sell an overweight asset for an underweight one through the given swap router path.
+        /// Both assets must be outside of the deviation band, and the sold value can not bring
+        /// the sold asset below its target weight.
+        pub fn rebalance(
+            &mut self,
+            sell_res_address: ResourceAddress,
+            sell_amount: Decimal,
+            buy_res_address: ResourceAddress,
+            path: Vec<ComponentAddress>,
+        ) {
+            /* CHECK INPUT */
+            assert!(
+                sell_amount > Decimal::ZERO,
+                "Sell amount must be greater than zero!"
+            );
+
+            let prices = self._get_prices();
+            let values = self._get_values(&prices);
+            let total_value = IndexFund::_sum(values.values());
+
+            assert!(total_value > Decimal::ZERO, "Fund has no assets");
+
+            let sell_target_weight = self._get_target_weight(sell_res_address);
+            let buy_target_weight = self._get_target_weight(buy_res_address);
+            let sell_weight = values[&sell_res_address] / total_value;
+            let buy_weight = values[&buy_res_address] / total_value;
+
+            assert!(
+                sell_weight > sell_target_weight + self.deviation_band,
+                "Sold asset is within its deviation band"
+            );
+            assert!(
+                buy_weight < buy_target_weight - self.deviation_band,
+                "Bought asset is within its deviation band"
+            );
+
+            let sell_value = sell_amount * prices[&sell_res_address];
+
+            assert!(
+                sell_value <= (sell_weight - sell_target_weight) * total_value,
+                "Sell amount exceeds the asset excess value"
+            );
+
+            let min_out =
+                sell_value / prices[&buy_res_address] * (Decimal::ONE - self.max_slippage);
+
+            let input = self.pool.protected_withdraw(
+                sell_res_address,
+                sell_amount,
+                WithdrawType::LiquidityWithdrawal,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+            let sold_amount = input.amount();
+
+            let output = self.router.swap_via(input, path, min_out);
+
+            assert!(
+                output.resource_address() == buy_res_address,
+                "Path must end with the bought resource"
+            );
+
+            let bought_amount = output.amount();
+
+            self.pool
+                .protected_deposit(output, DepositType::LiquidityAddition);
+
+            Runtime::emit_event(RebalancedEvent {
+                sold_res_address: sell_res_address,
+                sold_amount,
+                bought_res_address: buy_res_address,
+                bought_amount,
+            });
+        }
+
+        /* USER METHODS */
+
+        /// Deposit every fund asset in proportion to the fund composition. Returns the index
+        /// shares and the assets that could not be deposited.
+        pub fn deposit(&mut self, assets: Vec<Bucket>) -> (Bucket, Vec<Bucket>) {
+            self.pool.contribute(assets)
+        }
+
+        /// Redeem index shares for their share of every fund asset
+        pub fn redeem(&mut self, shares: Bucket) -> Vec<Bucket> {
+            self.pool.redeem(shares)
+        }
+
+        /* GETTERS */
+
+        pub fn get_target_weights(&self) -> Vec<IndexAsset> {
+            self.target_weights.clone()
+        }
+
+        /// Current share of the fund value held in each asset
+        pub fn get_weights(&self) -> IndexMap<ResourceAddress, Decimal> {
+            let values = self._get_values(&self._get_prices());
+            let total_value = IndexFund::_sum(values.values());
+
+            values
+                .into_iter()
+                .map(|(res_address, value)| (res_address, value / total_value))
+                .collect()
+        }
+
+        /// Value of the fund assets in the quote resource
+        pub fn get_value(&self) -> Decimal {
+            IndexFund::_sum(self._get_values(&self._get_prices()).values())
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _get_prices(&self) -> IndexMap<ResourceAddress, Decimal> {
+            self.target_weights
+                .iter()
+                .map(|asset| {
+                    let price = if asset.res_address == self.quote_res_address {
+                        Decimal::ONE
+                    } else {
+                        self.oracle.get_price(
+                            asset.res_address,
+                            self.quote_res_address,
+                            self.max_price_age_seconds,
+                        )
+                    };
+
+                    (asset.res_address, price)
+                })
+                .collect()
+        }
+
+        fn _get_values(
+            &self,
+            prices: &IndexMap<ResourceAddress, Decimal>,
+        ) -> IndexMap<ResourceAddress, Decimal> {
+            self.pool
+                .get_pooled_amounts()
+                .into_iter()
+                .map(|(res_address, (liquidity, external))| {
+                    (res_address, (liquidity + external) * prices[&res_address])
+                })
+                .collect()
+        }
+
+        fn _get_target_weight(&self, res_address: ResourceAddress) -> Decimal {
+            self.target_weights
+                .iter()
+                .find(|asset| asset.res_address == res_address)
+                .expect("Resource not held by the fund")
+                .target_weight
+        }
+
+        fn _sum<'a>(amounts: impl Iterator<Item = &'a Decimal>) -> Decimal {
+            amounts.fold(Decimal::ZERO, |sum, amount| sum + *amount)
+        }
+
+        fn _assert_valid_target_weights(target_weights: &[IndexAsset]) {
+            assert!(
+                target_weights
+                    .iter()
+                    .all(|asset| asset.target_weight > Decimal::ZERO),
+                "Target weights must be greater than zero"
+            );
+            assert!(
+                IndexFund::_sum(target_weights.iter().map(|asset| &asset.target_weight))
+                    == Decimal::ONE,
+                "Target weights must sum to one"
+            );
+        }
+
+        fn _assert_valid_ratio(ratio: Decimal, name: &str) {
+            assert!(
+                ratio >= Decimal::ZERO && ratio < Decimal::ONE,
+                "{} must be between 0 and 1",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the oracle and the router, and provides the pair liquidity
+const OWNER: usize = 0;
+const KEEPER: usize = 1;
+/// Holds 300 X and 100 Y tokens
+const ALICE: usize = 2;
+
+/// Ledger time of the tests, in seconds, rounded to the minute
+const NOW: i64 = 1_700_000_040;
+
+/// Manifest encoding of an `IndexAsset`
+fn index_asset(res_address: ResourceAddress, target_weight: Decimal) -> ManifestValue {
+    ManifestValue::Tuple {
+        fields: vec![
+            to_manifest_value_and_unwrap!(&res_address),
+            to_manifest_value_and_unwrap!(&target_weight),
+        ],
+    }
+}
+
+/// Fund holding X and Y tokens with target weights of 50%, a deviation band of 5% and a max
+/// slippage of 10%. Both tokens are priced 1 X by the oracle, and rebalanced through a
+/// router over a Radiswap pair without fee holding 10000 X and 10000 Y. The oracle, router
+/// and pair blueprints are linked into the fund package, so they are instantiated from the
+/// same package.
+struct TestFund {
+    env: TestEnv,
+    fund: ComponentAddress,
+    share: ResourceAddress,
+    pair: ComponentAddress,
+    res_x: ResourceAddress,
+    res_y: ResourceAddress,
+}
+
+impl TestFund {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.test_runner
+            .advance_to_round_at_timestamp(Round::of(1), NOW * 1000);
+        let res_x = env
+            .test_runner
+            .create_fungible_resource(dec!(10300), 18, env.accounts[OWNER]);
+        let res_y = env
+            .test_runner
+            .create_fungible_resource(dec!(10100), 18, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+        let owner_rule = rule!(require(env.badges[OWNER].clone()));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "PriceOracle",
+                "instantiate",
+                manifest_args!(owner_role.clone(), owner_rule, 2u32),
+            )
+            .call_function(
+                env.package_address,
+                "SwapRouter",
+                "instantiate",
+                manifest_args!(owner_role.clone()),
+            )
+            .call_function(
+                env.package_address,
+                "RadiswapPair",
+                "instantiate",
+                manifest_args!(res_x, res_y, dec!(0), owner_role.clone()),
+            )
+            .withdraw_from_account(env.accounts[OWNER], res_x, dec!(300))
+            .withdraw_from_account(env.accounts[OWNER], res_y, dec!(100))
+            .deposit_batch(env.accounts[ALICE])
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+        let (oracle, router, pair) = (
+            commit.new_component_addresses()[0],
+            commit.new_component_addresses()[1],
+            commit.new_component_addresses()[2],
+        );
+
+        // Manifest encoding of `AmmPool::Radiswap`
+        let pool = ManifestValue::Enum {
+            discriminator: 0,
+            fields: vec![to_manifest_value_and_unwrap!(&pair)],
+        };
+
+        let manifest = ManifestBuilder::new()
+            .call_method(
+                oracle,
+                "push_price",
+                manifest_args!(res_y, res_x, dec!(1), NOW),
+            )
+            .withdraw_from_account(env.accounts[OWNER], res_x, dec!(10000))
+            .withdraw_from_account(env.accounts[OWNER], res_y, dec!(10000))
+            .take_all_from_worktop(res_x, "bucket_x")
+            .take_all_from_worktop(res_y, "bucket_y")
+            .call_method_with_name_lookup(pair, "add_liquidity", |lookup| {
+                (lookup.bucket("bucket_x"), lookup.bucket("bucket_y"))
+            })
+            .call_method(router, "register_pair", manifest_args!(pool, res_x, res_y))
+            .call_function(
+                env.package_address,
+                "IndexFund",
+                "instantiate",
+                manifest_args!(
+                    vec![
+                        index_asset(res_x, dec!("0.5")),
+                        index_asset(res_y, dec!("0.5"))
+                    ],
+                    dec!("0.05"),
+                    dec!("0.1"),
+                    router,
+                    oracle,
+                    res_x,
+                    3600i64,
+                    owner_role,
+                    rule!(require(env.badges[KEEPER].clone()))
+                ),
+            )
+            .deposit_batch(env.accounts[OWNER])
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            fund: commit.new_component_addresses()[0],
+            share: commit.new_resource_addresses()[0],
+            env,
+            pair,
+            res_x,
+            res_y,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.fund, method, args)
+    }
+
+    /// Deposit the given amounts of X and Y tokens of Alice into the fund
+    fn deposit(&mut self, amount_x: Decimal, amount_y: Decimal) -> TransactionReceipt {
+        let (alice, res_x, res_y) = (self.env.accounts[ALICE], self.res_x, self.res_y);
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(alice, res_x, amount_x)
+            .withdraw_from_account(alice, res_y, amount_y)
+            .take_all_from_worktop(res_x, "bucket_x")
+            .take_all_from_worktop(res_y, "bucket_y")
+            .call_method_with_name_lookup(self.fund, "deposit", |lookup| {
+                (vec![lookup.bucket("bucket_x"), lookup.bucket("bucket_y")],)
+            });
+
+        self.env.execute(ALICE, builder)
+    }
+
+    fn rebalance(
+        &mut self,
+        caller: usize,
+        sell_res_address: ResourceAddress,
+        sell_amount: Decimal,
+        buy_res_address: ResourceAddress,
+    ) -> TransactionReceipt {
+        let pair = self.pair;
+
+        self.call(
+            caller,
+            "rebalance",
+            manifest_args!(sell_res_address, sell_amount, buy_res_address, vec![pair]),
+        )
+    }
+
+    fn get_weights(&mut self) -> IndexMap<ResourceAddress, Decimal> {
+        self.call(ALICE, "get_weights", manifest_args!())
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_deposit_and_redeem_in_proportion() {
+    let mut fund = TestFund::new();
+    let (share, res_x, res_y) = (fund.share, fund.res_x, fund.res_y);
+
+    // The first deposit sets the fund composition
+    fund.deposit(dec!(200), dec!(50)).expect_commit_success();
+    assert_eq!(fund.env.balance(ALICE, share), dec!(200));
+
+    let value: Decimal = fund
+        .call(ALICE, "get_value", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(value, dec!(250));
+    assert_eq!(fund.get_weights()[&res_x], dec!("0.8"));
+
+    // Later deposits are capped by the scarcest asset
+    fund.deposit(dec!(100), dec!(50)).expect_commit_success();
+    assert_eq!(fund.env.balance(ALICE, share), dec!(300));
+    assert_eq!(fund.env.balance(ALICE, res_y), dec!(25));
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(fund.env.accounts[ALICE], share, dec!(150))
+        .take_all_from_worktop(share, "shares")
+        .call_method_with_name_lookup(fund.fund, "redeem", |lookup| (lookup.bucket("shares"),));
+    fund.env.execute(ALICE, builder).expect_commit_success();
+    assert_eq!(fund.env.balance(ALICE, res_x), dec!(150));
+    assert_eq!(fund.env.balance(ALICE, res_y), dec!("62.5"));
+}
+
+#[test]
+fn test_rebalance_sells_the_excess_of_an_overweight_asset() {
+    let mut fund = TestFund::new();
+    let (res_x, res_y) = (fund.res_x, fund.res_y);
+
+    // X is worth 75% of the fund, with an excess value of 100
+    fund.deposit(dec!(300), dec!(100)).expect_commit_success();
+
+    fund.rebalance(KEEPER, res_x, dec!(101), res_y)
+        .expect_commit_failure();
+    fund.rebalance(KEEPER, res_y, dec!(10), res_x)
+        .expect_commit_failure();
+
+    // 100 X give about 99 Y through the pair
+    fund.call(OWNER, "set_max_slippage", manifest_args!(dec!(0)))
+        .expect_commit_success();
+    fund.rebalance(KEEPER, res_x, dec!(100), res_y)
+        .expect_commit_failure();
+
+    fund.call(OWNER, "set_max_slippage", manifest_args!(dec!("0.1")))
+        .expect_commit_success();
+    fund.rebalance(KEEPER, res_x, dec!(100), res_y)
+        .expect_commit_success();
+
+    let weights = fund.get_weights();
+    assert!(weights[&res_x] > dec!("0.5") && weights[&res_x] < dec!("0.51"));
+
+    // Both assets are now within the deviation band
+    fund.rebalance(KEEPER, res_x, dec!(1), res_y)
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_admin_methods_require_the_owner() {
+    let mut fund = TestFund::new();
+    let (res_x, res_y, share) = (fund.res_x, fund.res_y, fund.share);
+
+    fund.deposit(dec!(300), dec!(100)).expect_commit_success();
+
+    fund.rebalance(ALICE, res_x, dec!(100), res_y)
+        .expect_commit_failure();
+
+    let weights = vec![
+        index_asset(res_x, dec!("0.75")),
+        index_asset(res_y, dec!("0.25")),
+    ];
+    fund.call(ALICE, "set_target_weights", manifest_args!(weights.clone()))
+        .expect_commit_failure();
+    fund.call(
+        OWNER,
+        "set_target_weights",
+        manifest_args!(vec![
+            index_asset(res_x, dec!("0.75")),
+            index_asset(res_y, dec!("0.2"))
+        ]),
+    )
+    .expect_commit_failure();
+    fund.call(
+        OWNER,
+        "set_target_weights",
+        manifest_args!(vec![
+            index_asset(res_x, dec!("0.75")),
+            index_asset(share, dec!("0.25"))
+        ]),
+    )
+    .expect_commit_failure();
+    fund.call(OWNER, "set_target_weights", manifest_args!(weights))
+        .expect_commit_success();
+
+    // The fund composition matches the new targets
+    fund.rebalance(OWNER, res_x, dec!(10), res_y)
+        .expect_commit_failure();
+
+    fund.call(OWNER, "set_deviation_band", manifest_args!(dec!(1)))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_stale_prices_are_rejected() {
+    let mut fund = TestFund::new();
+
+    fund.deposit(dec!(300), dec!(100)).expect_commit_success();
+
+    fund.env
+        .test_runner
+        .advance_to_round_at_timestamp(Round::of(2), (NOW + 3660) * 1000);
+    fund.call(ALICE, "get_value", manifest_args!())
+        .expect_commit_failure();
+}
@@ -0,0 +1,170 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+
+/// Kinked utilization curve. The borrow rate grows by `slope1` from `base_rate` up to the
+/// kink utilization, then by the steeper `slope2` up to full utilization. Rates are per epoch.
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KinkedRateParameters {
+    pub base_rate: Decimal,
+
+    /// Rate increase between zero and kink utilization
+    pub slope1: Decimal,
+
+    /// Rate increase between kink and full utilization
+    pub slope2: Decimal,
+
+    /// Utilization at which the slope changes, between 0 and 1
+    pub kink: Decimal,
+}
+
+impl KinkedRateParameters {
+    pub fn assert_valid(&self) {
+        assert!(
+            self.base_rate >= Decimal::ZERO
+                && self.slope1 >= Decimal::ZERO
+                && self.slope2 >= Decimal::ZERO,
+            "Rates must not be negative"
+        );
+        assert!(
+            self.kink > Decimal::ZERO && self.kink < Decimal::ONE,
+            "Kink must be between 0 and 1"
+        );
+    }
+
+    pub fn borrow_rate(&self, utilization: Decimal) -> Decimal {
+        let utilization = utilization.max(Decimal::ZERO).min(Decimal::ONE);
+
+        if utilization <= self.kink {
+            self.base_rate + self.slope1 * utilization / self.kink
+        } else {
+            self.base_rate
+                + self.slope1
+                + self.slope2 * (utilization - self.kink) / (Decimal::ONE - self.kink)
+        }
+    }
+
+    /// Rate earned by suppliers: the borrow interest spread over all the supplied liquidity,
+    /// minus the share kept by the protocol
+    pub fn supply_rate(&self, utilization: Decimal, reserve_factor: Decimal) -> Decimal {
+        let utilization = utilization.max(Decimal::ZERO).min(Decimal::ONE);
+
+        self.borrow_rate(utilization) * utilization * (Decimal::ONE - reserve_factor)
+    }
+}
+
+/// Share of the supplied liquidity that is borrowed
+pub fn utilization(available: Decimal, borrowed: Decimal) -> Decimal {
+    let total = available + borrowed;
+
+    if total == Decimal::ZERO {
+        Decimal::ZERO
+    } else {
+        borrowed / total
+    }
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RateParametersUpdatedEvent {
+    pub parameters: KinkedRateParameters,
+}
+
+#[blueprint]
+#[events(RateParametersUpdatedEvent)]
+pub mod interest_rate_model {
+
+    enable_method_auth! {
+        methods {
+
+            set_parameters => restrict_to :[OWNER];
+
+            get_borrow_rate => PUBLIC;
+            get_supply_rate => PUBLIC;
+            get_borrow_rate_for_amounts => PUBLIC;
+            get_parameters => PUBLIC;
+
+        }
+    }
+
+    pub struct InterestRateModel {
+        parameters: KinkedRateParameters,
+    }
+
+    impl InterestRateModel {
+        pub fn instantiate(
+            parameters: KinkedRateParameters,
+            owner_role: OwnerRole,
+        ) -> Global<InterestRateModel> {
+            /* CHECK INPUT */
+            parameters.assert_valid();
+
+            Self { parameters }
+                .instantiate()
+                .prepare_to_globalize(owner_role)
+                .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn set_parameters(&mut self, parameters: KinkedRateParameters) {
+            parameters.assert_valid();
+
+            self.parameters = parameters;
+
+            Runtime::emit_event(RateParametersUpdatedEvent { parameters });
+        }
+
+        /* GETTERS */
+
+        /// Borrow rate per epoch at the given utilization
+        pub fn get_borrow_rate(&self, utilization: Decimal) -> Decimal {
+            self.parameters.borrow_rate(utilization)
+        }
+
+        /// Supply rate per epoch at the given utilization
+        pub fn get_supply_rate(&self, utilization: Decimal, reserve_factor: Decimal) -> Decimal {
+            assert!(
+                reserve_factor >= Decimal::ZERO && reserve_factor <= Decimal::ONE,
+                "Reserve factor must be between 0 and 1"
+            );
+
+            self.parameters.supply_rate(utilization, reserve_factor)
+        }
+
+        /// Borrow rate per epoch for a pool with the given available and borrowed amounts,
+        /// e.g. the pooled amounts of an AssetPool
+        pub fn get_borrow_rate_for_amounts(
+            &self,
+            available: Decimal,
+            borrowed: Decimal,
+        ) -> Decimal {
+            self.parameters
+                .borrow_rate(utilization(available, borrowed))
+        }
+
+        pub fn get_parameters(&self) -> KinkedRateParameters {
+            self.parameters
+        }
+    }
+}
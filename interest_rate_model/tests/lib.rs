@@ -0,0 +1,208 @@
+use interest_rate_model::*;
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// 1% base rate, growing to 5% at the 80% kink, then to 55% at full utilization
+fn parameters() -> KinkedRateParameters {
+    KinkedRateParameters {
+        base_rate: dec!("0.01"),
+        slope1: dec!("0.04"),
+        slope2: dec!("0.5"),
+        kink: dec!("0.8"),
+    }
+}
+
+/// Manifest encoding of `KinkedRateParameters`
+fn manifest_parameters(parameters: &KinkedRateParameters) -> ManifestValue {
+    ManifestValue::Tuple {
+        fields: vec![
+            to_manifest_value_and_unwrap!(&parameters.base_rate),
+            to_manifest_value_and_unwrap!(&parameters.slope1),
+            to_manifest_value_and_unwrap!(&parameters.slope2),
+            to_manifest_value_and_unwrap!(&parameters.kink),
+        ],
+    }
+}
+
+#[test]
+fn test_borrow_rate_follows_the_kinked_curve() {
+    let parameters = parameters();
+
+    assert_eq!(parameters.borrow_rate(dec!(0)), dec!("0.01"));
+    assert_eq!(parameters.borrow_rate(dec!("0.4")), dec!("0.03"));
+    assert_eq!(parameters.borrow_rate(dec!("0.8")), dec!("0.05"));
+    assert_eq!(parameters.borrow_rate(dec!("0.9")), dec!("0.3"));
+    assert_eq!(parameters.borrow_rate(dec!(1)), dec!("0.55"));
+
+    // Utilization is clamped between 0 and 1
+    assert_eq!(parameters.borrow_rate(dec!(-1)), dec!("0.01"));
+    assert_eq!(parameters.borrow_rate(dec!("1.5")), dec!("0.55"));
+}
+
+#[test]
+fn test_supply_rate() {
+    let parameters = parameters();
+
+    assert_eq!(parameters.supply_rate(dec!(0), dec!("0.1")), dec!(0));
+    assert_eq!(parameters.supply_rate(dec!("0.5"), dec!(0)), dec!("0.0175"));
+    assert_eq!(
+        parameters.supply_rate(dec!("0.5"), dec!("0.1")),
+        dec!("0.01575")
+    );
+    assert_eq!(parameters.supply_rate(dec!(1), dec!(1)), dec!(0));
+}
+
+#[test]
+fn test_utilization() {
+    assert_eq!(utilization(dec!(0), dec!(0)), dec!(0));
+    assert_eq!(utilization(dec!(75), dec!(25)), dec!("0.25"));
+    assert_eq!(utilization(dec!(0), dec!(25)), dec!(1));
+}
+
+#[test]
+#[should_panic(expected = "Kink must be between 0 and 1")]
+fn test_kink_at_full_utilization_panics() {
+    KinkedRateParameters {
+        kink: dec!(1),
+        ..parameters()
+    }
+    .assert_valid();
+}
+
+#[test]
+#[should_panic(expected = "Rates must not be negative")]
+fn test_negative_slope_panics() {
+    KinkedRateParameters {
+        slope2: dec!("-0.5"),
+        ..parameters()
+    }
+    .assert_valid();
+}
+
+/// Model instantiated with the default parameters, owned by the account
+struct TestModel {
+    env: TestEnv,
+    account: ComponentAddress,
+    model: ComponentAddress,
+}
+
+impl TestModel {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 1);
+        let account = env.accounts[0];
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "InterestRateModel",
+                "instantiate",
+                manifest_args!(
+                    manifest_parameters(&parameters()),
+                    OwnerRole::Fixed(rule!(require(env.badges[0].clone())))
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            model: commit.new_component_addresses()[0],
+            env,
+            account,
+        }
+    }
+
+    /// Execute the manifest with the account key, then deposit the worktop into the account
+    fn execute(&mut self, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute(0, builder)
+    }
+
+    fn call(&mut self, method: &str, args: impl ResolvableArguments) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_method(self.model, method, args);
+
+        self.execute(builder)
+    }
+}
+
+#[test]
+fn test_model_rates() {
+    let mut model = TestModel::new();
+
+    let rate: Decimal = model
+        .call(
+            "get_borrow_rate_for_amounts",
+            manifest_args!(dec!(10), dec!(90)),
+        )
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(rate, dec!("0.3"));
+
+    let rate: Decimal = model
+        .call("get_supply_rate", manifest_args!(dec!("0.5"), dec!("0.1")))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(rate, dec!("0.01575"));
+
+    model
+        .call("get_supply_rate", manifest_args!(dec!("0.5"), dec!("1.1")))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_set_parameters_requires_the_owner() {
+    let mut model = TestModel::new();
+    let model_address = model.model;
+
+    let updated = KinkedRateParameters {
+        base_rate: dec!("0.02"),
+        ..parameters()
+    };
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            model_address,
+            "set_parameters",
+            manifest_args!(manifest_parameters(&updated)),
+        )
+        .build();
+    model
+        .env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![])
+        .expect_commit_failure();
+
+    let invalid = KinkedRateParameters {
+        kink: dec!(0),
+        ..parameters()
+    };
+    model
+        .call(
+            "set_parameters",
+            manifest_args!(manifest_parameters(&invalid)),
+        )
+        .expect_commit_failure();
+
+    model
+        .call(
+            "set_parameters",
+            manifest_args!(manifest_parameters(&updated)),
+        )
+        .expect_commit_success();
+
+    let parameters: KinkedRateParameters = model
+        .call("get_parameters", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(parameters, updated);
+
+    let rate: Decimal = model
+        .call("get_borrow_rate", manifest_args!(dec!(0)))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(rate, dec!("0.02"));
+}
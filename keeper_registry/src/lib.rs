@@ -0,0 +1,360 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+/// Receipt of the job owner, used to update or cancel the job
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct JobReceipt {
+    pub component_address: ComponentAddress,
+    pub method_name: String,
+}
+
+/// Badge presented by the registry when executing a job. It never leaves the registry.
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct JobBadge {}
+
+#[derive(ScryptoSbor)]
+pub struct Job {
+    pub component_address: ComponentAddress,
+    pub method_name: String,
+
+    /// SBOR encoded arguments of the method
+    pub args: Vec<u8>,
+
+    pub interval_epochs: u64,
+    pub next_execution_epoch: u64,
+
+    /// Bounties paid to keepers, funded by anyone
+    pub bounty: Vault,
+    pub bounty_per_execution: Decimal,
+
+    /// Job badge with the same local id as the job
+    pub job_badge: Vault,
+
+    pub active: bool,
+    pub cancelled: bool,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct JobRegisteredEvent {
+    pub job_id: u64,
+    pub component_address: ComponentAddress,
+    pub method_name: String,
+    pub interval_epochs: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct JobExecutedEvent {
+    pub job_id: u64,
+    pub bounty_amount: Decimal,
+    pub next_execution_epoch: u64,
+}
+
+#[blueprint]
+#[events(JobRegisteredEvent, JobExecutedEvent)]
+pub mod keeper_registry {
+
+    enable_method_auth! {
+        methods {
+
+            register_job => PUBLIC;
+            update_job => PUBLIC;
+            set_job_active => PUBLIC;
+            cancel_job => PUBLIC;
+            fund_job => PUBLIC;
+
+            execute => PUBLIC;
+
+            is_due => PUBLIC;
+            get_bounty => PUBLIC;
+            get_job_badge_address => PUBLIC;
+
+        }
+    }
+
+    pub struct KeeperRegistry {
+        /// Job receipt non-fungible resource manager
+        job_receipt_res_manager: ResourceManager,
+
+        /// Job badge non-fungible resource manager
+        job_badge_res_manager: ResourceManager,
+
+        jobs: KeyValueStore<u64, Job>,
+        job_counter: u64,
+    }
+
+    impl KeeperRegistry {
+        pub fn instantiate(owner_role: OwnerRole) -> (Global<KeeperRegistry>, ResourceAddress) {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(KeeperRegistry::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let job_receipt_res_manager =
+                ResourceBuilder::new_integer_non_fungible::<JobReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule.clone();
+                        burner_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let job_badge_res_manager =
+                ResourceBuilder::new_integer_non_fungible::<JobBadge>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .withdraw_roles(withdraw_roles! {
+                        withdrawer => component_rule;
+                        withdrawer_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let registry_component = Self {
+                job_receipt_res_manager,
+                job_badge_res_manager,
+                jobs: KeyValueStore::new(),
+                job_counter: 0,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (registry_component, job_receipt_res_manager.address())
+        }
+
+        /* JOB OWNER METHODS */
+
+        /// Register a job calling the given method every `interval_epochs`. The method must
+        /// not return buckets. The target component should restrict the method to the job
+        /// badge with the same local id as the job, since anyone can register jobs. Returns
+        /// the job receipt.
+        pub fn register_job(
+            &mut self,
+            component_address: ComponentAddress,
+            method_name: String,
+            args: Vec<u8>,
+            interval_epochs: u64,
+            bounty: Bucket,
+            bounty_per_execution: Decimal,
+        ) -> Bucket {
+            /* CHECK INPUT */
+            assert!(interval_epochs > 0, "Interval must be greater than zero");
+            assert_fungible_res_address(
+                bounty.resource_address(),
+                Some("Bounty resource must be fungible".to_string()),
+            );
+            assert!(
+                bounty_per_execution > Decimal::ZERO,
+                "Bounty per execution must be greater than zero"
+            );
+
+            self.job_counter += 1;
+            let job_id = self.job_counter;
+            let local_id = NonFungibleLocalId::integer(job_id);
+
+            let job_receipt = self.job_receipt_res_manager.mint_non_fungible(
+                &local_id,
+                JobReceipt {
+                    component_address,
+                    method_name: method_name.clone(),
+                },
+            );
+
+            let job_badge = self
+                .job_badge_res_manager
+                .mint_non_fungible(&local_id, JobBadge {});
+
+            self.jobs.insert(
+                job_id,
+                Job {
+                    component_address,
+                    method_name: method_name.clone(),
+                    args,
+                    interval_epochs,
+                    next_execution_epoch: Runtime::current_epoch().number(),
+                    bounty: Vault::with_bucket(bounty),
+                    bounty_per_execution,
+                    job_badge: Vault::with_bucket(job_badge),
+                    active: true,
+                    cancelled: false,
+                },
+            );
+
+            Runtime::emit_event(JobRegisteredEvent {
+                job_id,
+                component_address,
+                method_name,
+                interval_epochs,
+            });
+
+            job_receipt
+        }
+
+        pub fn update_job(
+            &mut self,
+            job_receipt_proof: Proof,
+            args: Vec<u8>,
+            interval_epochs: u64,
+            bounty_per_execution: Decimal,
+        ) {
+            let job_id = self._check_job_receipt(job_receipt_proof);
+
+            /* CHECK INPUT */
+            assert!(interval_epochs > 0, "Interval must be greater than zero");
+            assert!(
+                bounty_per_execution > Decimal::ZERO,
+                "Bounty per execution must be greater than zero"
+            );
+
+            let mut job = self.jobs.get_mut(&job_id).unwrap();
+            job.args = args;
+            job.interval_epochs = interval_epochs;
+            job.bounty_per_execution = bounty_per_execution;
+        }
+
+        pub fn set_job_active(&mut self, job_receipt_proof: Proof, active: bool) {
+            let job_id = self._check_job_receipt(job_receipt_proof);
+
+            self.jobs.get_mut(&job_id).unwrap().active = active;
+        }
+
+        /// Cancel a job, burning the job receipt. Returns the remaining bounty.
+        pub fn cancel_job(&mut self, job_receipt: Bucket) -> Bucket {
+            assert!(
+                job_receipt.resource_address() == self.job_receipt_res_manager.address(),
+                "Invalid job receipt"
+            );
+
+            let job_id = match job_receipt.as_non_fungible().non_fungible_local_id() {
+                NonFungibleLocalId::Integer(job_id) => job_id.value(),
+                _ => panic!("Invalid job receipt"),
+            };
+
+            let mut job = self.jobs.get_mut(&job_id).unwrap();
+            job.active = false;
+            job.cancelled = true;
+            let bounty = job.bounty.take_all();
+
+            drop(job);
+
+            job_receipt.burn();
+
+            bounty
+        }
+
+        /// Add bounties to a job. Anyone can fund a job.
+        pub fn fund_job(&mut self, job_id: u64, bounty: Bucket) {
+            let mut job = self.jobs.get_mut(&job_id).expect("Job not found");
+
+            assert!(!job.cancelled, "Job cancelled");
+
+            job.bounty.put(bounty);
+        }
+
+        /* KEEPER METHODS */
+
+        /// Execute a due job, with its job badge in the auth zone. Returns the bounty.
+        pub fn execute(&mut self, job_id: u64) -> Bucket {
+            let mut job = self.jobs.get_mut(&job_id).expect("Job not found");
+
+            let current_epoch = Runtime::current_epoch().number();
+
+            assert!(job.active, "Job not active");
+            assert!(current_epoch >= job.next_execution_epoch, "Job not due yet");
+            assert!(
+                job.bounty.amount() >= job.bounty_per_execution,
+                "Not enough bounty"
+            );
+
+            let component: Global<AnyComponent> = Global::from(job.component_address);
+
+            job.job_badge
+                .as_non_fungible()
+                .authorize_with_non_fungibles(
+                    &indexset!(NonFungibleLocalId::integer(job_id)),
+                    || {
+                        component.call_raw::<ScryptoValue>(&job.method_name, job.args.clone());
+                    },
+                );
+
+            job.next_execution_epoch = current_epoch + job.interval_epochs;
+
+            let bounty = job.bounty.take(job.bounty_per_execution);
+
+            Runtime::emit_event(JobExecutedEvent {
+                job_id,
+                bounty_amount: bounty.amount(),
+                next_execution_epoch: job.next_execution_epoch,
+            });
+
+            bounty
+        }
+
+        /* GETTERS */
+
+        pub fn is_due(&self, job_id: u64) -> bool {
+            let job = self.jobs.get(&job_id).expect("Job not found");
+
+            job.active
+                && Runtime::current_epoch().number() >= job.next_execution_epoch
+                && job.bounty.amount() >= job.bounty_per_execution
+        }
+
+        pub fn get_bounty(&self, job_id: u64) -> (ResourceAddress, Decimal, Decimal) {
+            let job = self.jobs.get(&job_id).expect("Job not found");
+
+            (
+                job.bounty.resource_address(),
+                job.bounty.amount(),
+                job.bounty_per_execution,
+            )
+        }
+
+        /// Resource address of the job badges, to build access rules of job targets
+        pub fn get_job_badge_address(&self) -> ResourceAddress {
+            self.job_badge_res_manager.address()
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _check_job_receipt(&self, job_receipt_proof: Proof) -> u64 {
+            let local_id = job_receipt_proof
+                .check(self.job_receipt_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id();
+
+            match local_id {
+                NonFungibleLocalId::Integer(job_id) => job_id.value(),
+                _ => panic!("Invalid job receipt"),
+            }
+        }
+    }
+}
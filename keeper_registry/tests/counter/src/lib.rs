@@ -0,0 +1,33 @@
+use scrypto::prelude::*;
+
+#[blueprint]
+mod counter {
+    struct Counter {
+        /// Job badge allowed to increment the counter
+        job_badge: NonFungibleGlobalId,
+
+        count: u64,
+    }
+
+    impl Counter {
+        pub fn instantiate(job_badge: NonFungibleGlobalId) -> Global<Counter> {
+            Self {
+                job_badge,
+                count: 0,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .globalize()
+        }
+
+        pub fn increment(&mut self, amount: u64) {
+            Runtime::assert_access_rule(rule!(require(self.job_badge.clone())));
+
+            self.count += amount;
+        }
+
+        pub fn get_count(&self) -> u64 {
+            self.count
+        }
+    }
+}
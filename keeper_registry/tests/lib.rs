@@ -0,0 +1,278 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+const OWNER: usize = 0;
+/// Holds 100 bounty tokens, and owns the job
+const ALICE: usize = 1;
+/// Executes the jobs
+const KEEPER: usize = 2;
+
+/// Registry at epoch 10 with a job of Alice incrementing a counter by 2 every 5 epochs, with a
+/// bounty of 10 paying 2 per execution. The counter only accepts the job badge of the job.
+struct TestRegistry {
+    env: TestEnv,
+    registry: ComponentAddress,
+    job_receipt: ResourceAddress,
+    counter: ComponentAddress,
+    bounty: ResourceAddress,
+}
+
+impl TestRegistry {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let counter_package_address = env
+            .test_runner
+            .compile_and_publish(format!("{}/tests/counter", env!("CARGO_MANIFEST_DIR")));
+        let bounty = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[ALICE]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "KeeperRegistry",
+                "instantiate",
+                manifest_args!(OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())))),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let (registry, job_receipt): (ComponentAddress, ResourceAddress) =
+            receipt.expect_commit_success().output(0);
+
+        let manifest = ManifestBuilder::new()
+            .call_method(registry, "get_job_badge_address", manifest_args!())
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let job_badge: ResourceAddress = receipt.expect_commit_success().output(0);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                counter_package_address,
+                "Counter",
+                "instantiate",
+                manifest_args!(NonFungibleGlobalId::new(
+                    job_badge,
+                    NonFungibleLocalId::integer(1)
+                )),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let counter = receipt.expect_commit_success().new_component_addresses()[0];
+
+        let mut registry = Self {
+            env,
+            registry,
+            job_receipt,
+            counter,
+            bounty,
+        };
+
+        registry
+            .register_job(ALICE, dec!(10), dec!(2))
+            .expect_commit_success();
+
+        registry
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.registry, method, args)
+    }
+
+    /// Register a job of the account incrementing the counter by 2 every 5 epochs
+    fn register_job(
+        &mut self,
+        account: usize,
+        bounty_amount: Decimal,
+        bounty_per_execution: Decimal,
+    ) -> TransactionReceipt {
+        let (bounty, counter) = (self.bounty, self.counter);
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[account], bounty, bounty_amount)
+            .take_all_from_worktop(bounty, "bounty")
+            .call_method_with_name_lookup(self.registry, "register_job", |lookup| {
+                (
+                    counter,
+                    "increment",
+                    scrypto_encode(&(2u64,)).unwrap(),
+                    5u64,
+                    lookup.bucket("bounty"),
+                    bounty_per_execution,
+                )
+            });
+
+        self.env.execute(account, builder)
+    }
+
+    /// Call a method with a proof of the job receipt of Alice
+    fn call_with_receipt<A: ResolvableArguments>(
+        &mut self,
+        method: &str,
+        args: impl FnOnce(ManifestProof) -> A,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(
+                self.env.accounts[ALICE],
+                self.job_receipt,
+                dec!(1),
+            )
+            .pop_from_auth_zone("receipt")
+            .call_method_with_name_lookup(self.registry, method, |lookup| {
+                args(lookup.proof("receipt"))
+            });
+
+        self.env.execute(ALICE, builder)
+    }
+
+    fn execute_job(&mut self, job_id: u64) -> TransactionReceipt {
+        self.call(KEEPER, "execute", manifest_args!(job_id))
+    }
+
+    fn is_due(&mut self, job_id: u64) -> bool {
+        self.call(KEEPER, "is_due", manifest_args!(job_id))
+            .expect_commit_success()
+            .output(0)
+    }
+
+    fn get_count(&mut self) -> u64 {
+        let builder =
+            ManifestBuilder::new().call_method(self.counter, "get_count", manifest_args!());
+
+        self.env
+            .execute(OWNER, builder)
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_due_job_is_executed_by_keepers() {
+    let mut registry = TestRegistry::new();
+    let bounty = registry.bounty;
+
+    // Only the job badge increments the counter
+    let builder =
+        ManifestBuilder::new().call_method(registry.counter, "increment", manifest_args!(2u64));
+    registry.env.execute(ALICE, builder).expect_commit_failure();
+
+    assert!(registry.is_due(1));
+    registry.execute_job(1).expect_commit_success();
+    assert_eq!(registry.get_count(), 2);
+    assert_eq!(registry.env.balance(KEEPER, bounty), dec!(2));
+
+    assert!(!registry.is_due(1));
+    registry.execute_job(1).expect_commit_failure();
+
+    registry.env.set_epoch(15);
+    registry.execute_job(1).expect_commit_success();
+    assert_eq!(registry.get_count(), 4);
+
+    registry
+        .call_with_receipt("update_job", |proof| {
+            (proof, scrypto_encode(&(5u64,)).unwrap(), 1u64, dec!(4))
+        })
+        .expect_commit_success();
+
+    // The new interval applies from the next execution, at epoch 20. 2 bounty tokens are left
+    // after it.
+    registry.env.set_epoch(20);
+    registry.execute_job(1).expect_commit_success();
+    assert_eq!(registry.get_count(), 9);
+
+    registry.env.set_epoch(21);
+    assert!(!registry.is_due(1));
+    registry.execute_job(1).expect_commit_failure();
+
+    // Anyone funds the job
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(registry.env.accounts[KEEPER], bounty, dec!(2))
+        .take_all_from_worktop(bounty, "bounty")
+        .call_method_with_name_lookup(registry.registry, "fund_job", |lookup| {
+            (1u64, lookup.bucket("bounty"))
+        });
+    registry
+        .env
+        .execute(KEEPER, builder)
+        .expect_commit_success();
+
+    registry.execute_job(1).expect_commit_success();
+    assert_eq!(registry.get_count(), 14);
+    assert_eq!(registry.env.balance(KEEPER, bounty), dec!(10));
+}
+
+#[test]
+fn test_job_owner_pauses_and_cancels_the_job() {
+    let mut registry = TestRegistry::new();
+    let (bounty, job_receipt) = (registry.bounty, registry.job_receipt);
+
+    registry
+        .call_with_receipt("update_job", |proof| {
+            (proof, scrypto_encode(&(2u64,)).unwrap(), 0u64, dec!(2))
+        })
+        .expect_commit_failure();
+
+    registry
+        .call_with_receipt("set_job_active", |proof| (proof, false))
+        .expect_commit_success();
+    assert!(!registry.is_due(1));
+    registry.execute_job(1).expect_commit_failure();
+
+    registry
+        .call_with_receipt("set_job_active", |proof| (proof, true))
+        .expect_commit_success();
+    registry.execute_job(1).expect_commit_success();
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(registry.env.accounts[ALICE], job_receipt, dec!(1))
+        .take_all_from_worktop(job_receipt, "receipt")
+        .call_method_with_name_lookup(registry.registry, "cancel_job", |lookup| {
+            (lookup.bucket("receipt"),)
+        });
+    registry.env.execute(ALICE, builder).expect_commit_success();
+    assert_eq!(registry.env.balance(ALICE, bounty), dec!(98));
+    assert_eq!(registry.env.balance(ALICE, job_receipt), dec!(0));
+
+    registry.env.set_epoch(15);
+    registry.execute_job(1).expect_commit_failure();
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(registry.env.accounts[ALICE], bounty, dec!(2))
+        .take_all_from_worktop(bounty, "bounty")
+        .call_method_with_name_lookup(registry.registry, "fund_job", |lookup| {
+            (1u64, lookup.bucket("bounty"))
+        });
+    registry.env.execute(ALICE, builder).expect_commit_failure();
+}
+
+#[test]
+fn test_jobs_only_use_their_own_badge() {
+    let mut registry = TestRegistry::new();
+
+    registry
+        .register_job(ALICE, dec!(10), dec!(0))
+        .expect_commit_failure();
+
+    // The second job calls the counter with the job badge 2, which the counter rejects
+    registry
+        .register_job(ALICE, dec!(10), dec!(2))
+        .expect_commit_success();
+    registry.execute_job(2).expect_commit_failure();
+    registry.execute_job(1).expect_commit_success();
+    assert_eq!(registry.get_count(), 2);
+
+    registry.execute_job(3).expect_commit_failure();
+}
@@ -0,0 +1,787 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::{checked_div_to_decimal, ReentrancyGuard};
+use scrypto::prelude::*;
+use single_asset_pool::pool::AssetPool;
+use single_asset_pool::{DepositType, InterestAccrualMode, WithdrawType};
+
+/// Share of a position debt that can be repaid in a single liquidation
+pub const CLOSE_FACTOR: Decimal = dec!("0.5");
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct BorrowerPosition {
+    /// Pool units deposited as collateral, indexed by the pooled resource
+    #[mutable]
+    pub collateral: IndexMap<ResourceAddress, Decimal>,
+
+    /// Debt of the position divided by the borrow index of the market, indexed by the
+    /// borrowed resource
+    #[mutable]
+    pub normalized_debts: IndexMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub struct MarketState {
+    pub pool_unit_res_address: ResourceAddress,
+
+    /// Price of the pooled resource in the market reference currency
+    pub price: Decimal,
+
+    /// Share of the collateral value that can be borrowed
+    pub loan_to_value: Decimal,
+
+    /// Share of the collateral value above which a position can be liquidated
+    pub liquidation_threshold: Decimal,
+
+    /// Extra collateral value given to liquidators
+    pub liquidation_bonus: Decimal,
+
+    /// Interest rate applied to borrowed amounts every epoch
+    pub interest_rate_per_epoch: Decimal,
+
+    /// Whether the interest rate compounds linearly between accruals or continuously
+    pub accrual_mode: InterestAccrualMode,
+
+    /// Growth of one unit of debt since the market creation
+    pub borrow_index: Decimal,
+
+    /// Sum of the normalized debts of all positions
+    pub total_normalized_debt: Decimal,
+
+    pub last_accrual_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct LiquidationEvent {
+    pub position_id: NonFungibleLocalId,
+    pub repaid_res_address: ResourceAddress,
+    pub repaid_amount: Decimal,
+    pub seized_res_address: ResourceAddress,
+    pub seized_pool_units: Decimal,
+}
+
+#[blueprint]
+#[events(LiquidationEvent)]
+pub mod lending_market {
+
+    enable_method_auth! {
+        roles {
+            admin => updatable_by: [];
+        },
+        methods {
+
+            create_market => restrict_to :[OWNER];
+            update_market_parameters => restrict_to :[OWNER];
+            set_accrual_mode => restrict_to :[OWNER];
+            set_price => restrict_to :[admin, OWNER];
+
+            supply => PUBLIC;
+            withdraw => PUBLIC;
+
+            open_position => PUBLIC;
+            deposit_collateral => PUBLIC;
+            remove_collateral => PUBLIC;
+            borrow => PUBLIC;
+            repay => PUBLIC;
+            liquidate => PUBLIC;
+
+            accrue_interest => PUBLIC;
+
+            get_market_state => PUBLIC;
+            get_pooled_amount => PUBLIC;
+            get_pool_unit_ratio => PUBLIC;
+            get_position_health => PUBLIC;
+
+        }
+    }
+
+    pub struct LendingMarket {
+        /// Rule satisfied by calls made from this component
+        component_rule: AccessRule,
+
+        /// Pool backing each market, indexed by the pooled resource
+        pools: KeyValueStore<ResourceAddress, Owned<AssetPool>>,
+
+        /// Market parameters and debt accounting, indexed by the pooled resource
+        markets: IndexMap<ResourceAddress, MarketState>,
+
+        /// Pool units deposited as collateral, indexed by the pooled resource
+        collateral_vaults: KeyValueStore<ResourceAddress, Vault>,
+
+        /// Borrower position non-fungible resource manager
+        position_res_manager: ResourceManager,
+
+        /// Rejects nested calls into the state changing user methods
+        reentrancy_guard: ReentrancyGuard,
+    }
+
+    impl LendingMarket {
+        pub fn instantiate(
+            owner_role: OwnerRole,
+            admin_rule: AccessRule,
+        ) -> (Global<LendingMarket>, ResourceAddress) {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(LendingMarket::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let position_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<BorrowerPosition>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule.clone();
+                        burner_updater => rule!(deny_all);
+                    })
+                    .non_fungible_data_update_roles(non_fungible_data_update_roles! {
+                        non_fungible_data_updater => component_rule.clone();
+                        non_fungible_data_updater_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let market_component = Self {
+                component_rule,
+                pools: KeyValueStore::new(),
+                markets: IndexMap::default(),
+                collateral_vaults: KeyValueStore::new(),
+                position_res_manager,
+                reentrancy_guard: ReentrancyGuard::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                admin => admin_rule;
+            ))
+            .with_address(address_reservation)
+            .globalize();
+
+            (market_component, position_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn create_market(
+            &mut self,
+            pool_res_address: ResourceAddress,
+            price: Decimal,
+            loan_to_value: Decimal,
+            liquidation_threshold: Decimal,
+            liquidation_bonus: Decimal,
+            interest_rate_per_epoch: Decimal,
+        ) -> ResourceAddress {
+            /* INPUT CHECK */
+            assert!(
+                !self.markets.contains_key(&pool_res_address),
+                "Market already exists for this resource"
+            );
+            LendingMarket::_assert_valid_parameters(
+                loan_to_value,
+                liquidation_threshold,
+                liquidation_bonus,
+                interest_rate_per_epoch,
+            );
+            assert!(price > 0.into(), "Price must be greater than zero!");
+
+            let (pool, pool_unit_res_address, _) = AssetPool::instantiate_locally(
+                pool_res_address,
+                OwnerRole::None,
+                self.component_rule.clone(),
+            );
+
+            self.pools.insert(pool_res_address, pool);
+            self.collateral_vaults
+                .insert(pool_res_address, Vault::new(pool_unit_res_address));
+            self.markets.insert(
+                pool_res_address,
+                MarketState {
+                    pool_unit_res_address,
+                    price,
+                    loan_to_value,
+                    liquidation_threshold,
+                    liquidation_bonus,
+                    interest_rate_per_epoch,
+                    accrual_mode: InterestAccrualMode::Linear,
+                    borrow_index: 1.into(),
+                    total_normalized_debt: 0.into(),
+                    last_accrual_epoch: Runtime::current_epoch().number(),
+                },
+            );
+
+            pool_unit_res_address
+        }
+
+        pub fn update_market_parameters(
+            &mut self,
+            pool_res_address: ResourceAddress,
+            loan_to_value: Decimal,
+            liquidation_threshold: Decimal,
+            liquidation_bonus: Decimal,
+            interest_rate_per_epoch: Decimal,
+        ) {
+            /* INPUT CHECK */
+            LendingMarket::_assert_valid_parameters(
+                loan_to_value,
+                liquidation_threshold,
+                liquidation_bonus,
+                interest_rate_per_epoch,
+            );
+
+            // Interest accrued so far uses the previous rate
+            self.accrue_interest(pool_res_address);
+
+            let market = self._get_market_mut(&pool_res_address);
+            market.loan_to_value = loan_to_value;
+            market.liquidation_threshold = liquidation_threshold;
+            market.liquidation_bonus = liquidation_bonus;
+            market.interest_rate_per_epoch = interest_rate_per_epoch;
+        }
+
+        pub fn set_accrual_mode(
+            &mut self,
+            pool_res_address: ResourceAddress,
+            accrual_mode: InterestAccrualMode,
+        ) {
+            // Interest accrued so far uses the previous mode
+            self.accrue_interest(pool_res_address);
+
+            self._get_market_mut(&pool_res_address).accrual_mode = accrual_mode;
+        }
+
+        pub fn set_price(&mut self, pool_res_address: ResourceAddress, price: Decimal) {
+            assert!(price > 0.into(), "Price must be greater than zero!");
+
+            self._get_market_mut(&pool_res_address).price = price;
+        }
+
+        /* LENDER METHODS */
+
+        pub fn supply(&mut self, assets: Bucket) -> (Bucket, Bucket) {
+            self.reentrancy_guard.enter();
+
+            let pool_res_address = assets.resource_address();
+
+            self.accrue_interest(pool_res_address);
+
+            let (pool_units, change) = self._get_pool_mut(&pool_res_address).contribute(assets);
+
+            self.reentrancy_guard.exit();
+
+            (pool_units, change)
+        }
+
+        pub fn withdraw(&mut self, pool_units: Bucket) -> (Bucket, Bucket) {
+            self.reentrancy_guard.enter();
+
+            let pool_res_address = self._get_pool_res_address(pool_units.resource_address());
+
+            self.accrue_interest(pool_res_address);
+
+            let (assets, remaining_units) =
+                self._get_pool_mut(&pool_res_address).redeem(pool_units);
+
+            self.reentrancy_guard.exit();
+
+            (assets, remaining_units)
+        }
+
+        /* BORROWER METHODS */
+
+        pub fn open_position(&mut self) -> Bucket {
+            self.position_res_manager
+                .mint_ruid_non_fungible(BorrowerPosition {
+                    collateral: IndexMap::default(),
+                    normalized_debts: IndexMap::default(),
+                })
+        }
+
+        pub fn deposit_collateral(&mut self, position_proof: Proof, pool_units: Bucket) {
+            self.reentrancy_guard.enter();
+
+            let position_id = self._check_position_proof(position_proof);
+            let pool_res_address = self._get_pool_res_address(pool_units.resource_address());
+
+            let mut position: BorrowerPosition = self
+                .position_res_manager
+                .get_non_fungible_data(&position_id);
+
+            *position
+                .collateral
+                .entry(pool_res_address)
+                .or_insert(0.into()) += pool_units.amount();
+
+            self.collateral_vaults
+                .get_mut(&pool_res_address)
+                .unwrap()
+                .put(pool_units);
+
+            self.position_res_manager.update_non_fungible_data(
+                &position_id,
+                "collateral",
+                position.collateral,
+            );
+
+            self.reentrancy_guard.exit();
+        }
+
+        pub fn remove_collateral(
+            &mut self,
+            position_proof: Proof,
+            pool_res_address: ResourceAddress,
+            unit_amount: Decimal,
+        ) -> Bucket {
+            self.reentrancy_guard.enter();
+
+            let position_id = self._check_position_proof(position_proof);
+
+            /* INPUT CHECK */
+            assert!(unit_amount > 0.into(), "Amount must be greater than zero!");
+
+            // The health check must use the debt accrued so far
+            self._accrue_all_interest();
+
+            let mut position: BorrowerPosition = self
+                .position_res_manager
+                .get_non_fungible_data(&position_id);
+
+            let collateral = position
+                .collateral
+                .get_mut(&pool_res_address)
+                .expect("No collateral for this resource");
+
+            assert!(unit_amount <= *collateral, "Not enough collateral");
+
+            *collateral -= unit_amount;
+
+            self.position_res_manager.update_non_fungible_data(
+                &position_id,
+                "collateral",
+                position.collateral,
+            );
+
+            self._assert_position_can_borrow(&position_id);
+
+            let pool_units = self
+                .collateral_vaults
+                .get_mut(&pool_res_address)
+                .unwrap()
+                .take(unit_amount);
+
+            self.reentrancy_guard.exit();
+
+            pool_units
+        }
+
+        pub fn borrow(
+            &mut self,
+            position_proof: Proof,
+            pool_res_address: ResourceAddress,
+            amount: Decimal,
+        ) -> Bucket {
+            self.reentrancy_guard.enter();
+
+            let position_id = self._check_position_proof(position_proof);
+
+            /* INPUT CHECK */
+            assert!(amount > 0.into(), "Amount must be greater than zero!");
+
+            self._accrue_all_interest();
+
+            let mut position: BorrowerPosition = self
+                .position_res_manager
+                .get_non_fungible_data(&position_id);
+
+            // Rounded up, so the debt recorded is never below the borrowed amount
+            let market = self._get_market_mut(&pool_res_address);
+            let normalized_amount = checked_div_to_decimal(
+                amount,
+                market.borrow_index.into(),
+                DIVISIBILITY_MAXIMUM,
+                RoundingMode::AwayFromZero,
+            );
+            market.total_normalized_debt += normalized_amount;
+
+            *position
+                .normalized_debts
+                .entry(pool_res_address)
+                .or_insert(0.into()) += normalized_amount;
+
+            self.position_res_manager.update_non_fungible_data(
+                &position_id,
+                "normalized_debts",
+                position.normalized_debts,
+            );
+
+            self._assert_position_can_borrow(&position_id);
+
+            let loan = self._get_pool_mut(&pool_res_address).protected_withdraw(
+                amount,
+                WithdrawType::ForTemporaryUse,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+
+            self.reentrancy_guard.exit();
+
+            loan
+        }
+
+        pub fn repay(&mut self, position_proof: Proof, payment: Bucket) -> Bucket {
+            self.reentrancy_guard.enter();
+
+            let position_id = self._check_position_proof(position_proof);
+
+            let (_, change) = self._repay(&position_id, payment, None);
+
+            self.reentrancy_guard.exit();
+
+            change
+        }
+
+        pub fn liquidate(
+            &mut self,
+            position_id: NonFungibleLocalId,
+            payment: Bucket,
+            seized_res_address: ResourceAddress,
+        ) -> (Bucket, Bucket) {
+            self.reentrancy_guard.enter();
+
+            self._accrue_all_interest();
+
+            let ((_, liquidation_limit), debt_value) = self._get_position_values(&position_id);
+
+            assert!(
+                debt_value > liquidation_limit,
+                "Position is not eligible for liquidation"
+            );
+
+            let repaid_res_address = payment.resource_address();
+            let debt_amount = self._get_debt_amount(&position_id, &repaid_res_address);
+            let max_repay_amount = debt_amount * CLOSE_FACTOR;
+
+            let (repaid_amount, change) =
+                self._repay(&position_id, payment, Some(max_repay_amount));
+
+            // Seize collateral worth the repaid value plus the liquidation bonus
+            let repaid_market = self._get_market(&repaid_res_address);
+            let seized_market = self._get_market(&seized_res_address);
+
+            let seized_amount = repaid_amount
+                * repaid_market.price
+                * (Decimal::ONE + seized_market.liquidation_bonus)
+                / seized_market.price;
+
+            let mut position: BorrowerPosition = self
+                .position_res_manager
+                .get_non_fungible_data(&position_id);
+
+            let collateral = position
+                .collateral
+                .get_mut(&seized_res_address)
+                .expect("No collateral for this resource");
+
+            let seized_pool_units = self
+                ._get_pool(&seized_res_address)
+                .get_units_for_amount(seized_amount)
+                .min(*collateral);
+
+            *collateral -= seized_pool_units;
+
+            self.position_res_manager.update_non_fungible_data(
+                &position_id,
+                "collateral",
+                position.collateral,
+            );
+
+            Runtime::emit_event(LiquidationEvent {
+                position_id,
+                repaid_res_address,
+                repaid_amount,
+                seized_res_address,
+                seized_pool_units,
+            });
+
+            let seized = self
+                .collateral_vaults
+                .get_mut(&seized_res_address)
+                .unwrap()
+                .take(seized_pool_units);
+
+            self.reentrancy_guard.exit();
+
+            (seized, change)
+        }
+
+        /// Accrue the interest of a market since the last accrual. Accrued interest is added to
+        /// the pool external liquidity so it is reflected in the pool unit ratio.
+        pub fn accrue_interest(&mut self, pool_res_address: ResourceAddress) {
+            let current_epoch = Runtime::current_epoch().number();
+
+            let market = self._get_market_mut(&pool_res_address);
+
+            let elapsed_epochs = current_epoch - market.last_accrual_epoch;
+            if elapsed_epochs == 0 {
+                return;
+            }
+
+            let growth_factor = market
+                .accrual_mode
+                .growth_factor(market.interest_rate_per_epoch, elapsed_epochs);
+            let total_debt = market.total_normalized_debt * market.borrow_index;
+            let interest = total_debt * (growth_factor - Decimal::ONE);
+
+            market.borrow_index *= growth_factor;
+            market.last_accrual_epoch = current_epoch;
+
+            if interest > 0.into() {
+                self._get_pool_mut(&pool_res_address)
+                    .increase_external_liquidity(interest);
+            }
+        }
+
+        /* GETTERS */
+
+        pub fn get_market_state(&self, pool_res_address: ResourceAddress) -> MarketState {
+            self._get_market(&pool_res_address).clone()
+        }
+
+        /// Liquidity and borrowed amount of the pool of a market
+        pub fn get_pooled_amount(&self, pool_res_address: ResourceAddress) -> (Decimal, Decimal) {
+            self._get_pool(&pool_res_address).get_pooled_amount()
+        }
+
+        pub fn get_pool_unit_ratio(&self, pool_res_address: ResourceAddress) -> PreciseDecimal {
+            self._get_pool(&pool_res_address).get_pool_unit_ratio()
+        }
+
+        /// Collateral value, liquidation value and debt value of a position, using the current
+        /// borrow indexes
+        pub fn get_position_health(
+            &self,
+            position_id: NonFungibleLocalId,
+        ) -> (Decimal, Decimal, Decimal) {
+            let ((borrow_limit, liquidation_limit), debt_value) =
+                self._get_position_values(&position_id);
+
+            (borrow_limit, liquidation_limit, debt_value)
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_parameters(
+            loan_to_value: Decimal,
+            liquidation_threshold: Decimal,
+            liquidation_bonus: Decimal,
+            interest_rate_per_epoch: Decimal,
+        ) {
+            assert!(
+                loan_to_value >= 0.into() && loan_to_value <= liquidation_threshold,
+                "Loan to value must be between 0 and the liquidation threshold"
+            );
+            assert!(
+                liquidation_threshold <= 1.into(),
+                "Liquidation threshold must not be greater than 1"
+            );
+            assert!(
+                liquidation_bonus >= 0.into(),
+                "Liquidation bonus must not be negative"
+            );
+            assert!(
+                interest_rate_per_epoch >= 0.into(),
+                "Interest rate must not be negative"
+            );
+        }
+
+        fn _repay(
+            &mut self,
+            position_id: &NonFungibleLocalId,
+            mut payment: Bucket,
+            max_repay_amount: Option<Decimal>,
+        ) -> (Decimal, Bucket) {
+            let pool_res_address = payment.resource_address();
+
+            self.accrue_interest(pool_res_address);
+
+            let mut position: BorrowerPosition =
+                self.position_res_manager.get_non_fungible_data(position_id);
+
+            let normalized_debt = position
+                .normalized_debts
+                .get_mut(&pool_res_address)
+                .expect("No debt for this resource");
+
+            let market = self._get_market_mut(&pool_res_address);
+
+            let debt_amount = *normalized_debt * market.borrow_index;
+            let repay_amount = payment
+                .amount()
+                .min(debt_amount)
+                .min(max_repay_amount.unwrap_or(debt_amount));
+
+            // Partial repayments are credited rounded down, in favor of the lenders
+            let normalized_repay_amount = if repay_amount == debt_amount {
+                *normalized_debt
+            } else {
+                checked_div_to_decimal(
+                    repay_amount,
+                    market.borrow_index.into(),
+                    DIVISIBILITY_MAXIMUM,
+                    RoundingMode::ToZero,
+                )
+            };
+
+            *normalized_debt -= normalized_repay_amount;
+            market.total_normalized_debt -= normalized_repay_amount;
+
+            self.position_res_manager.update_non_fungible_data(
+                position_id,
+                "normalized_debts",
+                position.normalized_debts,
+            );
+
+            self._get_pool_mut(&pool_res_address).protected_deposit(
+                payment.take_advanced(
+                    repay_amount,
+                    WithdrawStrategy::Rounded(RoundingMode::AwayFromZero),
+                ),
+                DepositType::FromTemporaryUse,
+            );
+
+            (repay_amount, payment)
+        }
+
+        fn _accrue_all_interest(&mut self) {
+            let pool_res_addresses: Vec<ResourceAddress> = self.markets.keys().cloned().collect();
+
+            for pool_res_address in pool_res_addresses {
+                self.accrue_interest(pool_res_address);
+            }
+        }
+
+        fn _get_debt_amount(
+            &self,
+            position_id: &NonFungibleLocalId,
+            pool_res_address: &ResourceAddress,
+        ) -> Decimal {
+            let position: BorrowerPosition =
+                self.position_res_manager.get_non_fungible_data(position_id);
+
+            position
+                .normalized_debts
+                .get(pool_res_address)
+                .map(|normalized_debt| {
+                    *normalized_debt * self._get_market(pool_res_address).borrow_index
+                })
+                .unwrap_or(0.into())
+        }
+
+        /// Returns ((borrow limit, liquidation limit), debt value) of a position
+        fn _get_position_values(
+            &self,
+            position_id: &NonFungibleLocalId,
+        ) -> ((Decimal, Decimal), Decimal) {
+            let position: BorrowerPosition =
+                self.position_res_manager.get_non_fungible_data(position_id);
+
+            let mut borrow_limit = Decimal::ZERO;
+            let mut liquidation_limit = Decimal::ZERO;
+
+            for (pool_res_address, units) in position.collateral.iter() {
+                let market = self._get_market(pool_res_address);
+                let value = self
+                    ._get_pool(pool_res_address)
+                    .get_amount_for_units(*units)
+                    * market.price;
+
+                borrow_limit += value * market.loan_to_value;
+                liquidation_limit += value * market.liquidation_threshold;
+            }
+
+            let mut debt_value = Decimal::ZERO;
+
+            for (pool_res_address, normalized_debt) in position.normalized_debts.iter() {
+                let market = self._get_market(pool_res_address);
+
+                debt_value += *normalized_debt * market.borrow_index * market.price;
+            }
+
+            ((borrow_limit, liquidation_limit), debt_value)
+        }
+
+        fn _assert_position_can_borrow(&self, position_id: &NonFungibleLocalId) {
+            let ((borrow_limit, _), debt_value) = self._get_position_values(position_id);
+
+            assert!(
+                debt_value <= borrow_limit,
+                "Position health check failed: debt exceeds the borrow limit"
+            );
+        }
+
+        fn _check_position_proof(&self, position_proof: Proof) -> NonFungibleLocalId {
+            position_proof
+                .check(self.position_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+
+        fn _get_pool(
+            &self,
+            pool_res_address: &ResourceAddress,
+        ) -> KeyValueEntryRef<Owned<AssetPool>> {
+            self.pools
+                .get(pool_res_address)
+                .expect("No market for this resource")
+        }
+
+        fn _get_pool_mut(
+            &mut self,
+            pool_res_address: &ResourceAddress,
+        ) -> KeyValueEntryRefMut<Owned<AssetPool>> {
+            self.pools
+                .get_mut(pool_res_address)
+                .expect("No market for this resource")
+        }
+
+        fn _get_pool_res_address(&self, pool_unit_res_address: ResourceAddress) -> ResourceAddress {
+            *self
+                .markets
+                .iter()
+                .find(|(_, market)| market.pool_unit_res_address == pool_unit_res_address)
+                .expect("No market for this pool unit")
+                .0
+        }
+
+        fn _get_market(&self, pool_res_address: &ResourceAddress) -> &MarketState {
+            self.markets
+                .get(pool_res_address)
+                .expect("No market for this resource")
+        }
+
+        fn _get_market_mut(&mut self, pool_res_address: &ResourceAddress) -> &mut MarketState {
+            self.markets
+                .get_mut(pool_res_address)
+                .expect("No market for this resource")
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use lending_market::MarketState;
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Market of two resources priced at 1, with a loan to value of 0.5 and an interest rate of
+/// 1% per epoch. The account owns the market, supplied 1000 of the borrowed resource and
+/// holds a position with 100 pool units of the collateral resource.
+struct TestMarket {
+    env: TestEnv,
+    account: ComponentAddress,
+    market: ComponentAddress,
+    position: ResourceAddress,
+    borrowed: ResourceAddress,
+    borrowed_pool_unit: ResourceAddress,
+    collateral: ResourceAddress,
+    collateral_pool_unit: ResourceAddress,
+}
+
+impl TestMarket {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 1);
+        let account = env.accounts[0];
+        let borrowed = env
+            .test_runner
+            .create_fungible_resource(dec!(10000), 18, account);
+        let collateral = env
+            .test_runner
+            .create_fungible_resource(dec!(10000), 18, account);
+        let owner_badge = env.badges[0].clone();
+
+        env.set_epoch(10);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "LendingMarket",
+                "instantiate",
+                manifest_args!(
+                    OwnerRole::Fixed(rule!(require(owner_badge))),
+                    rule!(deny_all)
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+        let market = commit.new_component_addresses()[0];
+        let position = commit.new_resource_addresses()[0];
+
+        let mut test_market = Self {
+            env,
+            account,
+            market,
+            position,
+            borrowed,
+            borrowed_pool_unit: borrowed,
+            collateral,
+            collateral_pool_unit: collateral,
+        };
+
+        test_market.borrowed_pool_unit = test_market.create_market(borrowed);
+        test_market.collateral_pool_unit = test_market.create_market(collateral);
+
+        test_market
+            .supply(borrowed, dec!(1000))
+            .expect_commit_success();
+        test_market
+            .supply(collateral, dec!(100))
+            .expect_commit_success();
+        test_market
+            .call("open_position", manifest_args!())
+            .expect_commit_success();
+
+        let pool_unit = test_market.collateral_pool_unit;
+        let builder = test_market
+            .with_position_proof()
+            .withdraw_from_account(account, pool_unit, dec!(100))
+            .take_all_from_worktop(pool_unit, "pool_units")
+            .call_method_with_name_lookup(market, "deposit_collateral", |lookup| {
+                (lookup.proof("position"), lookup.bucket("pool_units"))
+            });
+        test_market.execute(builder).expect_commit_success();
+
+        test_market
+    }
+
+    fn create_market(&mut self, pool_res_address: ResourceAddress) -> ResourceAddress {
+        self.call(
+            "create_market",
+            manifest_args!(
+                pool_res_address,
+                dec!(1),
+                dec!("0.5"),
+                dec!("0.8"),
+                dec!("0.05"),
+                dec!("0.01")
+            ),
+        )
+        .expect_commit_success()
+        .new_resource_addresses()[0]
+    }
+
+    /// Execute the manifest with the account key, then deposit the worktop into the account
+    fn execute(&mut self, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute(0, builder)
+    }
+
+    fn call(&mut self, method: &str, args: impl ResolvableArguments) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_method(self.market, method, args);
+
+        self.execute(builder)
+    }
+
+    fn supply(&mut self, res_address: ResourceAddress, amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, res_address, amount)
+            .take_all_from_worktop(res_address, "assets")
+            .call_method_with_name_lookup(self.market, "supply", |lookup| {
+                (lookup.bucket("assets"),)
+            });
+
+        self.execute(builder)
+    }
+
+    /// Manifest starting with a proof of the position of the account, named `position`
+    fn with_position_proof(&self) -> ManifestBuilder {
+        ManifestBuilder::new()
+            .create_proof_from_account_of_amount(self.account, self.position, dec!(1))
+            .pop_from_auth_zone("position")
+    }
+
+    fn borrow(&mut self, amount: Decimal) -> TransactionReceipt {
+        let borrowed = self.borrowed;
+        let builder = self.with_position_proof().call_method_with_name_lookup(
+            self.market,
+            "borrow",
+            |lookup| (lookup.proof("position"), borrowed, amount),
+        );
+
+        self.execute(builder)
+    }
+
+    fn remove_collateral(&mut self, units: Decimal) -> TransactionReceipt {
+        let collateral = self.collateral;
+        let builder = self.with_position_proof().call_method_with_name_lookup(
+            self.market,
+            "remove_collateral",
+            |lookup| (lookup.proof("position"), collateral, units),
+        );
+
+        self.execute(builder)
+    }
+
+    fn repay(&mut self, amount: Decimal) -> TransactionReceipt {
+        let borrowed = self.borrowed;
+        let builder = self
+            .with_position_proof()
+            .withdraw_from_account(self.account, borrowed, amount)
+            .take_all_from_worktop(borrowed, "payment")
+            .call_method_with_name_lookup(self.market, "repay", |lookup| {
+                (lookup.proof("position"), lookup.bucket("payment"))
+            });
+
+        self.execute(builder)
+    }
+
+    fn pooled_amount(&mut self, res_address: ResourceAddress) -> (Decimal, Decimal) {
+        let manifest = ManifestBuilder::new()
+            .call_method(
+                self.market,
+                "get_pooled_amount",
+                manifest_args!(res_address),
+            )
+            .build();
+
+        self.env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![])
+            .expect_commit_success()
+            .output(0)
+    }
+
+    fn balance(&mut self, res_address: ResourceAddress) -> Decimal {
+        self.env.balance(0, res_address)
+    }
+
+    /// State of the market of the borrowed resource
+    fn market_state(&mut self) -> MarketState {
+        let (market, borrowed) = (self.market, self.borrowed);
+
+        self.env
+            .get(market, "get_market_state", manifest_args!(borrowed))
+    }
+}
+
+#[test]
+fn test_supply_and_withdraw() {
+    let mut market = TestMarket::new();
+    assert_eq!(market.balance(market.borrowed_pool_unit), dec!(1000));
+
+    let pool_unit = market.borrowed_pool_unit;
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(market.account, pool_unit, dec!(400))
+        .take_all_from_worktop(pool_unit, "pool_units")
+        .call_method_with_name_lookup(market.market, "withdraw", |lookup| {
+            (lookup.bucket("pool_units"),)
+        });
+    market.execute(builder).expect_commit_success();
+
+    assert_eq!(market.balance(market.borrowed), dec!(9400));
+    assert_eq!(market.pooled_amount(market.borrowed), (dec!(600), dec!(0)));
+}
+
+#[test]
+fn test_borrow_is_limited_by_the_loan_to_value() {
+    let mut market = TestMarket::new();
+
+    market.borrow(dec!(51)).expect_commit_failure();
+    market.borrow(dec!(50)).expect_commit_success();
+
+    assert_eq!(market.balance(market.borrowed), dec!(9050));
+    assert_eq!(market.pooled_amount(market.borrowed), (dec!(950), dec!(50)));
+}
+
+#[test]
+fn test_interest_accrues_to_lenders() {
+    let mut market = TestMarket::new();
+    market.borrow(dec!(40)).expect_commit_success();
+
+    // 10 epochs at 1% per epoch
+    market.env.set_epoch(20);
+    market.repay(dec!(50)).expect_commit_success();
+
+    assert_eq!(market.pooled_amount(market.borrowed), (dec!(1004), dec!(0)));
+    assert_eq!(market.balance(market.borrowed), dec!(8996));
+}
+
+#[test]
+fn test_borrowed_debt_is_rounded_up() {
+    let mut market = TestMarket::new();
+    market.borrow(dec!(40)).expect_commit_success();
+
+    // After accrual, 10 is not a whole number of normalized debt units
+    market.env.set_epoch(13);
+    let borrowed = market.borrowed;
+    market
+        .call("accrue_interest", manifest_args!(borrowed))
+        .expect_commit_success();
+    let before = market.market_state();
+
+    market.borrow(dec!(10)).expect_commit_success();
+
+    let after = market.market_state();
+    assert_eq!(after.borrow_index, before.borrow_index);
+    assert!(
+        after.total_normalized_debt * after.borrow_index
+            >= before.total_normalized_debt * before.borrow_index + dec!(10)
+    );
+}
+
+#[test]
+fn test_remove_collateral_uses_accrued_debt() {
+    let mut market = TestMarket::new();
+    market.borrow(dec!(40)).expect_commit_success();
+
+    // Without accrual, a debt of 40 is below the 45 borrow limit left by 90 units
+    market.env.set_epoch(40);
+    market.remove_collateral(dec!(10)).expect_commit_failure();
+
+    // Once repaid, the collateral is free
+    market.repay(dec!(60)).expect_commit_success();
+    market.remove_collateral(dec!(10)).expect_commit_success();
+
+    assert_eq!(market.balance(market.collateral_pool_unit), dec!(10));
+}
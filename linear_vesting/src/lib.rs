@@ -0,0 +1,221 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+mod vesting_positions;
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct VestingSchedule {
+    pub total_amount: Decimal,
+    pub start_epoch: u64,
+    /// Number of epochs after the start before anything vests
+    pub cliff_epochs: u64,
+    /// Number of epochs after the start when everything has vested
+    pub duration_epochs: u64,
+}
+
+impl VestingSchedule {
+    pub fn new(
+        total_amount: Decimal,
+        start_epoch: u64,
+        cliff_epochs: u64,
+        duration_epochs: u64,
+    ) -> Self {
+        assert!(
+            total_amount > Decimal::ZERO,
+            "Amount must be greater than zero!"
+        );
+        assert!(duration_epochs > 0, "Duration must be greater than zero");
+        assert!(
+            cliff_epochs <= duration_epochs,
+            "Cliff must not be longer than the duration"
+        );
+
+        Self {
+            total_amount,
+            start_epoch,
+            cliff_epochs,
+            duration_epochs,
+        }
+    }
+
+    /// Amount vested at the given epoch, growing linearly from the start epoch once the cliff
+    /// has passed
+    pub fn vested_amount(&self, epoch: u64) -> Decimal {
+        if epoch < self.start_epoch + self.cliff_epochs {
+            return Decimal::ZERO;
+        }
+
+        let elapsed_epochs = (epoch - self.start_epoch).min(self.duration_epochs);
+
+        self.total_amount * elapsed_epochs / self.duration_epochs
+    }
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct VestingClaimedEvent {
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct VestingRevokedEvent {
+    pub vested_amount: Decimal,
+    pub unvested_amount: Decimal,
+}
+
+#[blueprint]
+#[events(VestingClaimedEvent, VestingRevokedEvent)]
+pub mod linear_vesting {
+
+    enable_method_auth! {
+        roles {
+            beneficiary => updatable_by: [];
+        },
+        methods {
+
+            revoke => restrict_to :[OWNER];
+
+            claim => restrict_to :[beneficiary];
+
+            get_schedule => PUBLIC;
+            get_vested_amount => PUBLIC;
+            get_claimable_amount => PUBLIC;
+
+        }
+    }
+
+    pub struct LinearVesting {
+        funds: Vault,
+
+        schedule: VestingSchedule,
+
+        /// Amount already claimed by the beneficiary
+        claimed_amount: Decimal,
+
+        /// Whether the owner can revoke the unvested funds
+        revocable: bool,
+
+        /// Vested amount frozen at revocation
+        revoked_vested_amount: Option<Decimal>,
+    }
+
+    impl LinearVesting {
+        /// Fund a vesting schedule. Set `revocable` to let the owner take back the funds that
+        /// have not vested yet.
+        pub fn instantiate(
+            funds: Bucket,
+            start_epoch: u64,
+            cliff_epochs: u64,
+            duration_epochs: u64,
+            revocable: bool,
+            owner_role: OwnerRole,
+            beneficiary_rule: AccessRule,
+        ) -> Global<LinearVesting> {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                funds.resource_address(),
+                Some("Vested resource must be fungible".to_string()),
+            );
+
+            let schedule =
+                VestingSchedule::new(funds.amount(), start_epoch, cliff_epochs, duration_epochs);
+
+            Self {
+                funds: Vault::with_bucket(funds),
+                schedule,
+                claimed_amount: Decimal::ZERO,
+                revocable,
+                revoked_vested_amount: None,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                beneficiary => beneficiary_rule;
+            ))
+            .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        /// Stop the vesting and return the funds that have not vested yet. The vested funds
+        /// remain claimable by the beneficiary.
+        pub fn revoke(&mut self) -> Bucket {
+            assert!(self.revocable, "Vesting is not revocable");
+            assert!(
+                self.revoked_vested_amount.is_none(),
+                "Vesting already revoked"
+            );
+
+            let unvested = self.funds.take_advanced(
+                self.schedule.total_amount - self.get_vested_amount(),
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+            let vested_amount = self.schedule.total_amount - unvested.amount();
+
+            self.revoked_vested_amount = Some(vested_amount);
+
+            Runtime::emit_event(VestingRevokedEvent {
+                vested_amount,
+                unvested_amount: unvested.amount(),
+            });
+
+            unvested
+        }
+
+        /* BENEFICIARY METHODS */
+
+        pub fn claim(&mut self) -> Bucket {
+            let claimed = self.funds.take_advanced(
+                self.get_claimable_amount(),
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+
+            self.claimed_amount += claimed.amount();
+
+            Runtime::emit_event(VestingClaimedEvent {
+                amount: claimed.amount(),
+            });
+
+            claimed
+        }
+
+        /* GETTERS */
+
+        pub fn get_schedule(&self) -> VestingSchedule {
+            self.schedule.clone()
+        }
+
+        pub fn get_vested_amount(&self) -> Decimal {
+            self.revoked_vested_amount.unwrap_or_else(|| {
+                self.schedule
+                    .vested_amount(Runtime::current_epoch().number())
+            })
+        }
+
+        pub fn get_claimable_amount(&self) -> Decimal {
+            self.get_vested_amount() - self.claimed_amount
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use crate::VestingSchedule;
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct VestingPosition {
+    pub schedule: VestingSchedule,
+
+    /// Whether the owner of the component can revoke the unvested funds
+    pub revocable: bool,
+
+    /// Amount already claimed by the holders of the position
+    #[mutable]
+    pub claimed_amount: Decimal,
+
+    /// Vested amount frozen at revocation
+    #[mutable]
+    pub revoked_vested_amount: Option<Decimal>,
+}
+
+impl VestingPosition {
+    pub fn vested_amount(&self, epoch: u64) -> Decimal {
+        self.revoked_vested_amount
+            .unwrap_or_else(|| self.schedule.vested_amount(epoch))
+    }
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PositionClaimedEvent {
+    pub position_id: NonFungibleGlobalId,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PositionRevokedEvent {
+    pub position_id: NonFungibleGlobalId,
+    pub vested_amount: Decimal,
+    pub unvested_amount: Decimal,
+}
+
+#[blueprint]
+#[events(PositionClaimedEvent, PositionRevokedEvent)]
+mod vesting_positions {
+
+    enable_method_auth! {
+        methods {
+
+            create_schedule => restrict_to :[OWNER];
+            revoke => restrict_to :[OWNER];
+
+            claim => PUBLIC;
+
+            get_position => PUBLIC;
+            get_claimable_amount => PUBLIC;
+
+        }
+    }
+
+    /// Variant of the linear vesting where each schedule is an NFT holding the claim rights,
+    /// so that positions can be transferred or sold
+    pub struct VestingPositions {
+        funds: Vault,
+
+        /// Positions that can be withdrawn from their account and traded
+        transferable_res_manager: ResourceManager,
+
+        /// Positions that can not be withdrawn once deposited to the beneficiary account
+        non_transferable_res_manager: ResourceManager,
+    }
+
+    impl VestingPositions {
+        pub fn instantiate(
+            vested_res_address: ResourceAddress,
+            owner_role: OwnerRole,
+        ) -> (Global<VestingPositions>, ResourceAddress, ResourceAddress) {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                vested_res_address,
+                Some("Vested resource must be fungible".to_string()),
+            );
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(VestingPositions::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let transferable_res_manager = VestingPositions::_create_position_resource(
+                owner_role.clone(),
+                component_rule.clone(),
+                rule!(allow_all),
+            );
+
+            let non_transferable_res_manager = VestingPositions::_create_position_resource(
+                owner_role.clone(),
+                component_rule,
+                rule!(deny_all),
+            );
+
+            let vesting_component = Self {
+                funds: Vault::new(vested_res_address),
+                transferable_res_manager,
+                non_transferable_res_manager,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (
+                vesting_component,
+                transferable_res_manager.address(),
+                non_transferable_res_manager.address(),
+            )
+        }
+
+        /* ADMIN METHODS */
+
+        /// Fund a vesting schedule and return its position NFT. Non transferable positions
+        /// must be deposited to the beneficiary account in the same transaction, they can not
+        /// be withdrawn afterwards.
+        pub fn create_schedule(
+            &mut self,
+            funds: Bucket,
+            start_epoch: u64,
+            cliff_epochs: u64,
+            duration_epochs: u64,
+            revocable: bool,
+            transferable: bool,
+        ) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                funds.resource_address() == self.funds.resource_address(),
+                "Vested resource address mismatch"
+            );
+
+            let schedule =
+                VestingSchedule::new(funds.amount(), start_epoch, cliff_epochs, duration_epochs);
+
+            self.funds.put(funds);
+
+            let position_res_manager = if transferable {
+                self.transferable_res_manager
+            } else {
+                self.non_transferable_res_manager
+            };
+
+            position_res_manager.mint_ruid_non_fungible(VestingPosition {
+                schedule,
+                revocable,
+                claimed_amount: Decimal::ZERO,
+                revoked_vested_amount: None,
+            })
+        }
+
+        /// Stop the vesting of a revocable position and return the funds that have not vested
+        /// yet. The vested funds remain claimable by the position holder.
+        pub fn revoke(&mut self, position_id: NonFungibleGlobalId) -> Bucket {
+            let position_res_manager = self._get_position_res_manager(&position_id);
+            let position: VestingPosition =
+                position_res_manager.get_non_fungible_data(position_id.local_id());
+
+            assert!(position.revocable, "Position is not revocable");
+            assert!(
+                position.revoked_vested_amount.is_none(),
+                "Position already revoked"
+            );
+
+            let unvested = self.funds.take_advanced(
+                position.schedule.total_amount
+                    - position.vested_amount(Runtime::current_epoch().number()),
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+            let vested_amount = position.schedule.total_amount - unvested.amount();
+
+            position_res_manager.update_non_fungible_data(
+                position_id.local_id(),
+                "revoked_vested_amount",
+                Some(vested_amount),
+            );
+
+            Runtime::emit_event(PositionRevokedEvent {
+                position_id,
+                vested_amount,
+                unvested_amount: unvested.amount(),
+            });
+
+            unvested
+        }
+
+        /* HOLDER METHODS */
+
+        pub fn claim(&mut self, position_proof: Proof) -> Bucket {
+            let position_id = self._check_position_proof(position_proof);
+            let position_res_manager = self._get_position_res_manager(&position_id);
+            let position: VestingPosition =
+                position_res_manager.get_non_fungible_data(position_id.local_id());
+
+            let claimed = self.funds.take_advanced(
+                position.vested_amount(Runtime::current_epoch().number()) - position.claimed_amount,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+
+            position_res_manager.update_non_fungible_data(
+                position_id.local_id(),
+                "claimed_amount",
+                position.claimed_amount + claimed.amount(),
+            );
+
+            Runtime::emit_event(PositionClaimedEvent {
+                position_id,
+                amount: claimed.amount(),
+            });
+
+            claimed
+        }
+
+        /* GETTERS */
+
+        pub fn get_position(&self, position_id: NonFungibleGlobalId) -> VestingPosition {
+            self._get_position_res_manager(&position_id)
+                .get_non_fungible_data(position_id.local_id())
+        }
+
+        pub fn get_claimable_amount(&self, position_id: NonFungibleGlobalId) -> Decimal {
+            let position = self.get_position(position_id);
+
+            position.vested_amount(Runtime::current_epoch().number()) - position.claimed_amount
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _create_position_resource(
+            owner_role: OwnerRole,
+            component_rule: AccessRule,
+            withdraw_rule: AccessRule,
+        ) -> ResourceManager {
+            ResourceBuilder::new_ruid_non_fungible::<VestingPosition>(owner_role)
+                .mint_roles(mint_roles! {
+                    minter => component_rule.clone();
+                    minter_updater => rule!(deny_all);
+                })
+                .withdraw_roles(withdraw_roles! {
+                    withdrawer => withdraw_rule;
+                    withdrawer_updater => rule!(deny_all);
+                })
+                .non_fungible_data_update_roles(non_fungible_data_update_roles! {
+                    non_fungible_data_updater => component_rule;
+                    non_fungible_data_updater_updater => rule!(deny_all);
+                })
+                .create_with_no_initial_supply()
+        }
+
+        fn _check_position_proof(&self, position_proof: Proof) -> NonFungibleGlobalId {
+            let position_res_address = position_proof.resource_address();
+
+            assert!(
+                position_res_address == self.transferable_res_manager.address()
+                    || position_res_address == self.non_transferable_res_manager.address(),
+                "Vesting position resource address mismatch"
+            );
+
+            let position_id = position_proof
+                .skip_checking()
+                .as_non_fungible()
+                .non_fungible_local_id();
+
+            NonFungibleGlobalId::new(position_res_address, position_id)
+        }
+
+        fn _get_position_res_manager(&self, position_id: &NonFungibleGlobalId) -> ResourceManager {
+            if position_id.resource_address() == self.transferable_res_manager.address() {
+                self.transferable_res_manager
+            } else if position_id.resource_address() == self.non_transferable_res_manager.address()
+            {
+                self.non_transferable_res_manager
+            } else {
+                panic!("Vesting position resource address mismatch")
+            }
+        }
+    }
+}
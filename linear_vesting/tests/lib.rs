@@ -0,0 +1,396 @@
+use linear_vesting::VestingSchedule;
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+#[test]
+fn test_vested_amount_grows_linearly_after_the_cliff() {
+    let schedule = VestingSchedule::new(dec!(1000), 20, 10, 100);
+
+    assert_eq!(schedule.vested_amount(0), dec!(0));
+    assert_eq!(schedule.vested_amount(29), dec!(0));
+    assert_eq!(schedule.vested_amount(30), dec!(100));
+    assert_eq!(schedule.vested_amount(70), dec!(500));
+    assert_eq!(schedule.vested_amount(120), dec!(1000));
+    assert_eq!(schedule.vested_amount(500), dec!(1000));
+}
+
+#[test]
+#[should_panic(expected = "Cliff must not be longer than the duration")]
+fn test_cliff_longer_than_the_duration_panics() {
+    VestingSchedule::new(dec!(1000), 20, 101, 100);
+}
+
+#[test]
+#[should_panic(expected = "Duration must be greater than zero")]
+fn test_zero_duration_panics() {
+    VestingSchedule::new(dec!(1000), 20, 0, 0);
+}
+
+/// Vesting of 1000 tokens starting at epoch 20, with a cliff of 10 epochs and a duration of 100
+/// epochs. The account key is both the owner and the beneficiary.
+struct TestVesting {
+    env: TestEnv,
+    account: ComponentAddress,
+    vesting: ComponentAddress,
+    token: ResourceAddress,
+}
+
+impl TestVesting {
+    fn new(revocable: bool) -> Self {
+        let mut env = TestEnv::new(this_package!(), 1);
+        let account = env.accounts[0];
+        let token = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, account);
+        let badge = env.badges[0].clone();
+        env.set_epoch(10);
+
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account(account, token, dec!(1000))
+            .take_all_from_worktop(token, "funds")
+            .call_function_with_name_lookup(
+                env.package_address,
+                "LinearVesting",
+                "instantiate",
+                |lookup| {
+                    (
+                        lookup.bucket("funds"),
+                        20u64,
+                        10u64,
+                        100u64,
+                        revocable,
+                        OwnerRole::Fixed(rule!(require(badge.clone()))),
+                        rule!(require(badge.clone())),
+                    )
+                },
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[0].clone()]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            vesting: commit.new_component_addresses()[0],
+            env,
+            account,
+            token,
+        }
+    }
+
+    /// Execute the manifest with the account key, then deposit the worktop into the account
+    fn execute(&mut self, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute(0, builder)
+    }
+
+    fn call(&mut self, method: &str) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_method(self.vesting, method, manifest_args!());
+
+        self.execute(builder)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str) -> T {
+        self.call(method).expect_commit_success().output(0)
+    }
+
+    fn balance(&mut self) -> Decimal {
+        self.env.balance(0, self.token)
+    }
+}
+
+#[test]
+fn test_claim_the_vested_amount() {
+    let mut vesting = TestVesting::new(false);
+
+    vesting.env.set_epoch(25);
+    vesting.call("claim").expect_commit_success();
+    assert_eq!(vesting.balance(), dec!(0));
+
+    vesting.env.set_epoch(30);
+    vesting.call("claim").expect_commit_success();
+    assert_eq!(vesting.balance(), dec!(100));
+
+    vesting.env.set_epoch(70);
+    assert_eq!(vesting.get::<Decimal>("get_claimable_amount"), dec!(400));
+    vesting.call("claim").expect_commit_success();
+    assert_eq!(vesting.balance(), dec!(500));
+
+    vesting.env.set_epoch(200);
+    vesting.call("claim").expect_commit_success();
+    assert_eq!(vesting.balance(), dec!(1000));
+}
+
+#[test]
+fn test_claim_requires_the_beneficiary() {
+    let mut vesting = TestVesting::new(false);
+    vesting.env.set_epoch(70);
+
+    let manifest = ManifestBuilder::new()
+        .call_method(vesting.vesting, "claim", manifest_args!())
+        .build();
+    vesting
+        .env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![])
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_revoke_returns_the_unvested_funds() {
+    let mut vesting = TestVesting::new(true);
+
+    vesting.env.set_epoch(70);
+    vesting.call("revoke").expect_commit_success();
+    assert_eq!(vesting.balance(), dec!(500));
+
+    vesting.call("revoke").expect_commit_failure();
+
+    // The vested amount stays claimable but stops growing
+    vesting.env.set_epoch(200);
+    assert_eq!(vesting.get::<Decimal>("get_vested_amount"), dec!(500));
+    vesting.call("claim").expect_commit_success();
+    assert_eq!(vesting.balance(), dec!(1000));
+}
+
+#[test]
+fn test_non_revocable_vesting_can_not_be_revoked() {
+    let mut vesting = TestVesting::new(false);
+
+    vesting.env.set_epoch(70);
+    vesting.call("revoke").expect_commit_failure();
+}
+
+/// Owner of the vesting positions component, holding 10000 tokens
+const OWNER: usize = 0;
+/// Beneficiaries of the positions
+const ALICE: usize = 1;
+const BOB: usize = 2;
+
+/// Vesting positions component instantiated at epoch 10
+struct TestPositions {
+    env: TestEnv,
+    positions: ComponentAddress,
+    transferable: ResourceAddress,
+    non_transferable: ResourceAddress,
+    token: ResourceAddress,
+}
+
+impl TestPositions {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let token = env
+            .test_runner
+            .create_fungible_resource(dec!(10000), 18, env.accounts[OWNER]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "VestingPositions",
+                "instantiate",
+                manifest_args!(
+                    token,
+                    OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())))
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            positions: commit.new_component_addresses()[0],
+            transferable: commit.new_resource_addresses()[0],
+            non_transferable: commit.new_resource_addresses()[1],
+            env,
+            token,
+        }
+    }
+
+    /// Execute the manifest with the keys of every account, then deposit the worktop into the
+    /// given account
+    fn execute(&mut self, account: usize, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute_with_all_keys(account, builder)
+    }
+
+    /// Create a schedule of 1000 tokens starting at epoch 20, with a cliff of 10 epochs and a
+    /// duration of 100 epochs, and deposit its position to the beneficiary
+    fn create_schedule(
+        &mut self,
+        beneficiary: usize,
+        revocable: bool,
+        transferable: bool,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[OWNER], self.token, dec!(1000))
+            .take_all_from_worktop(self.token, "funds")
+            .call_method_with_name_lookup(self.positions, "create_schedule", |lookup| {
+                (
+                    lookup.bucket("funds"),
+                    20u64,
+                    10u64,
+                    100u64,
+                    revocable,
+                    transferable,
+                )
+            });
+
+        self.execute(beneficiary, builder)
+    }
+
+    fn claim(
+        &mut self,
+        account: usize,
+        position_res_address: ResourceAddress,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(
+                self.env.accounts[account],
+                position_res_address,
+                dec!(1),
+            )
+            .pop_from_auth_zone("position")
+            .call_method_with_name_lookup(self.positions, "claim", |lookup| {
+                (lookup.proof("position"),)
+            });
+
+        self.execute(account, builder)
+    }
+
+    /// Global id of the single position of the given resource held by the account
+    fn position_id(
+        &mut self,
+        account: usize,
+        position_res_address: ResourceAddress,
+    ) -> NonFungibleGlobalId {
+        let vault_id = self
+            .env
+            .test_runner
+            .get_component_vaults(self.env.accounts[account], position_res_address)[0];
+        let (_, mut ids) = self
+            .env
+            .test_runner
+            .inspect_non_fungible_vault(vault_id)
+            .unwrap();
+
+        NonFungibleGlobalId::new(position_res_address, ids.next().unwrap())
+    }
+}
+
+#[test]
+fn test_position_holder_claims_the_vested_amount() {
+    let mut positions = TestPositions::new();
+    let (token, transferable) = (positions.token, positions.transferable);
+
+    positions
+        .create_schedule(ALICE, false, true)
+        .expect_commit_success();
+
+    positions.env.set_epoch(70);
+    positions.claim(ALICE, transferable).expect_commit_success();
+    assert_eq!(positions.env.balance(ALICE, token), dec!(500));
+
+    // The position is transferred with the remaining claim rights
+    let builder = ManifestBuilder::new().withdraw_from_account(
+        positions.env.accounts[ALICE],
+        transferable,
+        dec!(1),
+    );
+    positions.execute(BOB, builder).expect_commit_success();
+
+    positions.env.set_epoch(200);
+    positions.claim(BOB, transferable).expect_commit_success();
+    assert_eq!(positions.env.balance(BOB, token), dec!(500));
+}
+
+#[test]
+fn test_non_transferable_position_can_not_be_withdrawn() {
+    let mut positions = TestPositions::new();
+    let (token, non_transferable) = (positions.token, positions.non_transferable);
+
+    positions
+        .create_schedule(ALICE, false, false)
+        .expect_commit_success();
+
+    let builder = ManifestBuilder::new().withdraw_from_account(
+        positions.env.accounts[ALICE],
+        non_transferable,
+        dec!(1),
+    );
+    positions.execute(BOB, builder).expect_commit_failure();
+
+    positions.env.set_epoch(30);
+    positions
+        .claim(ALICE, non_transferable)
+        .expect_commit_success();
+    assert_eq!(positions.env.balance(ALICE, token), dec!(100));
+}
+
+#[test]
+fn test_create_schedule_requires_the_owner() {
+    let mut positions = TestPositions::new();
+    let (account, token) = (positions.env.accounts[ALICE], positions.token);
+
+    let builder = ManifestBuilder::new().withdraw_from_account(
+        positions.env.accounts[OWNER],
+        token,
+        dec!(1000),
+    );
+    positions.execute(ALICE, builder).expect_commit_success();
+
+    let manifest = ManifestBuilder::new()
+        .withdraw_from_account(account, token, dec!(1000))
+        .take_all_from_worktop(token, "funds")
+        .call_method_with_name_lookup(positions.positions, "create_schedule", |lookup| {
+            (lookup.bucket("funds"), 20u64, 10u64, 100u64, false, true)
+        })
+        .deposit_batch(account)
+        .build();
+
+    // Only the key of Alice is present, not the owner badge
+    let alice_badge = positions.env.badges[ALICE].clone();
+    positions
+        .env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![alice_badge])
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_revoke_position() {
+    let mut positions = TestPositions::new();
+    let (token, transferable) = (positions.token, positions.transferable);
+
+    positions
+        .create_schedule(ALICE, true, true)
+        .expect_commit_success();
+    positions
+        .create_schedule(BOB, false, true)
+        .expect_commit_success();
+    let revocable_id = positions.position_id(ALICE, transferable);
+    let non_revocable_id = positions.position_id(BOB, transferable);
+
+    positions.env.set_epoch(70);
+    let builder = ManifestBuilder::new().call_method(
+        positions.positions,
+        "revoke",
+        manifest_args!(non_revocable_id),
+    );
+    positions.execute(OWNER, builder).expect_commit_failure();
+
+    let builder = ManifestBuilder::new().call_method(
+        positions.positions,
+        "revoke",
+        manifest_args!(revocable_id),
+    );
+    positions.execute(OWNER, builder).expect_commit_success();
+    assert_eq!(positions.env.balance(OWNER, token), dec!(8500));
+
+    positions.env.set_epoch(200);
+    positions.claim(ALICE, transferable).expect_commit_success();
+    assert_eq!(positions.env.balance(ALICE, token), dec!(500));
+}
@@ -0,0 +1,382 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor)]
+pub struct Auction {
+    /// Seized collateral for sale. Once the auction is closed, holds the surplus owed to the
+    /// borrower.
+    pub collateral: Vault,
+
+    /// Debt resource, paid by the bidders
+    pub debt_res_address: ResourceAddress,
+
+    /// Debt left to cover by the auction
+    pub debt_remaining: Decimal,
+
+    /// Price of one collateral unit, in debt resource, at the start epoch
+    pub start_price: Decimal,
+    pub start_epoch: u64,
+
+    /// Position of the liquidated borrower, entitled to the surplus collateral
+    pub borrower: NonFungibleGlobalId,
+
+    pub closed: bool,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct AuctionStartedEvent {
+    pub auction_id: u64,
+    pub collateral_amount: Decimal,
+    pub debt_amount: Decimal,
+    pub start_price: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CollateralBoughtEvent {
+    pub auction_id: u64,
+    pub price: Decimal,
+    pub collateral_amount: Decimal,
+    pub cost: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct AuctionClosedEvent {
+    pub auction_id: u64,
+
+    /// Collateral left for the borrower
+    pub surplus: Decimal,
+
+    /// Debt the auction failed to cover
+    pub shortfall: Decimal,
+}
+
+#[blueprint]
+#[events(AuctionStartedEvent, CollateralBoughtEvent, AuctionClosedEvent)]
+pub mod liquidation_auction {
+
+    enable_method_auth! {
+        roles {
+            market => updatable_by: [OWNER];
+        },
+        methods {
+
+            set_keeper_reward => restrict_to :[OWNER];
+            fund_incentives => restrict_to :[OWNER];
+
+            start_auction => restrict_to :[market];
+            withdraw_proceeds => restrict_to :[market];
+
+            bid => PUBLIC;
+            reset_auction => PUBLIC;
+            claim_surplus => PUBLIC;
+
+            get_auction_price => PUBLIC;
+            get_debt_remaining => PUBLIC;
+
+        }
+    }
+
+    pub struct LiquidationAuction {
+        auctions: KeyValueStore<u64, Auction>,
+        next_auction_id: u64,
+
+        /// Auction proceeds, indexed by debt resource, withdrawable by the market
+        proceeds: KeyValueStore<ResourceAddress, Vault>,
+
+        /// Number of epochs for the price to decay from the start price to the floor price
+        duration_epochs: u64,
+
+        /// Floor price, as a share of the start price
+        floor_price_ratio: Decimal,
+
+        /// Rewards paid to keepers resetting auctions that reached their floor price
+        incentives: Vault,
+        keeper_reward: Decimal,
+    }
+
+    impl LiquidationAuction {
+        pub fn instantiate(
+            owner_role: OwnerRole,
+            market_rule: AccessRule,
+            duration_epochs: u64,
+            floor_price_ratio: Decimal,
+            incentive_res_address: ResourceAddress,
+            keeper_reward: Decimal,
+        ) -> Global<LiquidationAuction> {
+            /* CHECK INPUT */
+            assert!(duration_epochs > 0, "Duration must be greater than zero");
+            assert!(
+                floor_price_ratio >= Decimal::ZERO && floor_price_ratio < Decimal::ONE,
+                "Floor price ratio must be between 0 and 1"
+            );
+            assert!(
+                keeper_reward >= Decimal::ZERO,
+                "Keeper reward must not be negative"
+            );
+
+            Self {
+                auctions: KeyValueStore::new(),
+                next_auction_id: 1,
+                proceeds: KeyValueStore::new(),
+                duration_epochs,
+                floor_price_ratio,
+                incentives: Vault::new(incentive_res_address),
+                keeper_reward,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                market => market_rule;
+            ))
+            .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn set_keeper_reward(&mut self, keeper_reward: Decimal) {
+            assert!(
+                keeper_reward >= Decimal::ZERO,
+                "Keeper reward must not be negative"
+            );
+
+            self.keeper_reward = keeper_reward;
+        }
+
+        pub fn fund_incentives(&mut self, incentives: Bucket) {
+            self.incentives.put(incentives);
+        }
+
+        /* MARKET METHODS */
+
+        /// Start a descending price auction selling the seized collateral until the debt is
+        /// covered. Returns the auction id.
+        pub fn start_auction(
+            &mut self,
+            collateral: Bucket,
+            debt_res_address: ResourceAddress,
+            debt_amount: Decimal,
+            start_price: Decimal,
+            borrower: NonFungibleGlobalId,
+        ) -> u64 {
+            /* CHECK INPUT */
+            assert!(!collateral.is_empty(), "Collateral must not be empty");
+            assert!(
+                debt_amount > Decimal::ZERO,
+                "Debt amount must be greater than zero"
+            );
+            assert!(
+                start_price > Decimal::ZERO,
+                "Start price must be greater than zero"
+            );
+
+            let auction_id = self.next_auction_id;
+            self.next_auction_id += 1;
+
+            Runtime::emit_event(AuctionStartedEvent {
+                auction_id,
+                collateral_amount: collateral.amount(),
+                debt_amount,
+                start_price,
+            });
+
+            self.auctions.insert(
+                auction_id,
+                Auction {
+                    collateral: Vault::with_bucket(collateral),
+                    debt_res_address,
+                    debt_remaining: debt_amount,
+                    start_price,
+                    start_epoch: Runtime::current_epoch().number(),
+                    borrower,
+                    closed: false,
+                },
+            );
+
+            auction_id
+        }
+
+        pub fn withdraw_proceeds(&mut self, debt_res_address: ResourceAddress) -> Bucket {
+            self.proceeds
+                .get_mut(&debt_res_address)
+                .expect("No proceeds for this resource")
+                .take_all()
+        }
+
+        /* KEEPER METHODS */
+
+        /// Buy collateral at the current auction price, up to the remaining debt. Fails if the
+        /// price is above `max_price`. Returns the bought collateral and the change.
+        pub fn bid(
+            &mut self,
+            auction_id: u64,
+            mut payment: Bucket,
+            max_price: Decimal,
+        ) -> (Bucket, Bucket) {
+            let price = self.get_auction_price(auction_id);
+
+            let mut auction = self
+                .auctions
+                .get_mut(&auction_id)
+                .expect("Auction not found");
+
+            /* CHECK INPUT */
+            assert!(!auction.closed, "Auction is closed");
+            assert!(
+                payment.resource_address() == auction.debt_res_address,
+                "Payment resource address mismatch"
+            );
+            assert!(price <= max_price, "Auction price is above the max price");
+
+            let collateral_value = auction.collateral.amount() * price;
+            let cost = payment
+                .amount()
+                .min(auction.debt_remaining)
+                .min(collateral_value);
+
+            assert!(cost > Decimal::ZERO, "Payment must be greater than zero");
+
+            let collateral = if cost == collateral_value {
+                auction.collateral.take_all()
+            } else {
+                auction.collateral.take_advanced(
+                    cost / price,
+                    WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                )
+            };
+            let payment_taken =
+                payment.take_advanced(cost, WithdrawStrategy::Rounded(RoundingMode::AwayFromZero));
+
+            auction.debt_remaining =
+                (auction.debt_remaining - payment_taken.amount()).max(Decimal::ZERO);
+
+            Runtime::emit_event(CollateralBoughtEvent {
+                auction_id,
+                price,
+                collateral_amount: collateral.amount(),
+                cost: payment_taken.amount(),
+            });
+
+            if auction.debt_remaining == Decimal::ZERO || auction.collateral.is_empty() {
+                auction.closed = true;
+
+                Runtime::emit_event(AuctionClosedEvent {
+                    auction_id,
+                    surplus: auction.collateral.amount(),
+                    shortfall: auction.debt_remaining,
+                });
+            }
+
+            drop(auction);
+
+            self._deposit_proceeds(payment_taken);
+
+            (collateral, payment)
+        }
+
+        /// Restart an auction that reached its floor price without covering the debt. The
+        /// caller receives the keeper reward, if the incentives vault can pay it.
+        pub fn reset_auction(&mut self, auction_id: u64) -> Bucket {
+            let mut auction = self
+                .auctions
+                .get_mut(&auction_id)
+                .expect("Auction not found");
+
+            let current_epoch = Runtime::current_epoch().number();
+
+            assert!(!auction.closed, "Auction is closed");
+            assert!(
+                current_epoch >= auction.start_epoch + self.duration_epochs,
+                "Auction has not reached its floor price"
+            );
+
+            auction.start_epoch = current_epoch;
+
+            self.incentives.take_advanced(
+                self.keeper_reward.min(self.incentives.amount()),
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            )
+        }
+
+        /* BORROWER METHODS */
+
+        /// Claim the collateral left once the auction has covered the debt
+        pub fn claim_surplus(&mut self, auction_id: u64, borrower_proof: Proof) -> Bucket {
+            let mut auction = self
+                .auctions
+                .get_mut(&auction_id)
+                .expect("Auction not found");
+
+            let borrower_id = borrower_proof
+                .check(auction.borrower.resource_address())
+                .as_non_fungible()
+                .non_fungible_local_id();
+
+            assert!(
+                &borrower_id == auction.borrower.local_id(),
+                "Proof does not match the borrower"
+            );
+            assert!(auction.closed, "Auction is not closed");
+
+            auction.collateral.take_all()
+        }
+
+        /* GETTERS */
+
+        /// Price of one collateral unit, decaying linearly from the start price to the floor
+        /// price over the auction duration
+        pub fn get_auction_price(&self, auction_id: u64) -> Decimal {
+            let auction = self.auctions.get(&auction_id).expect("Auction not found");
+
+            let elapsed =
+                (Runtime::current_epoch().number() - auction.start_epoch).min(self.duration_epochs);
+
+            let decay = (Decimal::ONE - self.floor_price_ratio) * Decimal::from(elapsed)
+                / Decimal::from(self.duration_epochs);
+
+            auction.start_price * (Decimal::ONE - decay)
+        }
+
+        pub fn get_debt_remaining(&self, auction_id: u64) -> Decimal {
+            self.auctions
+                .get(&auction_id)
+                .expect("Auction not found")
+                .debt_remaining
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _deposit_proceeds(&mut self, proceeds: Bucket) {
+            let res_address = proceeds.resource_address();
+            let vault_exists = self.proceeds.get(&res_address).is_some();
+
+            if vault_exists {
+                self.proceeds.get_mut(&res_address).unwrap().put(proceeds);
+            } else {
+                self.proceeds
+                    .insert(res_address, Vault::with_bucket(proceeds));
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Holds the incentive tokens
+const OWNER: usize = 0;
+/// Holds the collateral tokens
+const MARKET: usize = 1;
+/// Holds 1000 debt tokens
+const KEEPER: usize = 2;
+/// Holds the borrower positions 1 to 3
+const BORROWER: usize = 3;
+
+/// Auctions decaying to a floor price of 50% over 10 epochs, with a keeper reward of 5, at
+/// epoch 10
+struct TestAuction {
+    env: TestEnv,
+    auction: ComponentAddress,
+    collateral: ResourceAddress,
+    debt: ResourceAddress,
+    incentive: ResourceAddress,
+    position: ResourceAddress,
+}
+
+impl TestAuction {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 4);
+        env.set_epoch(10);
+        let incentive = env
+            .test_runner
+            .create_fungible_resource(dec!(10), 18, env.accounts[OWNER]);
+        let collateral =
+            env.test_runner
+                .create_fungible_resource(dec!(100), 18, env.accounts[MARKET]);
+        let debt = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, env.accounts[KEEPER]);
+        let position = env
+            .test_runner
+            .create_non_fungible_resource(env.accounts[BORROWER]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "LiquidationAuction",
+                "instantiate",
+                manifest_args!(
+                    OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone()))),
+                    rule!(require(env.badges[MARKET].clone())),
+                    10u64,
+                    dec!("0.5"),
+                    incentive,
+                    dec!(5)
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            auction: commit.new_component_addresses()[0],
+            env,
+            collateral,
+            debt,
+            incentive,
+            position,
+        }
+    }
+
+    /// Auction 10 collateral tokens of the caller at a start price of 10 for the borrower
+    /// position 1
+    fn start_auction(&mut self, caller: usize, debt_amount: Decimal) -> TransactionReceipt {
+        let (collateral, debt) = (self.collateral, self.debt);
+        let borrower = NonFungibleGlobalId::new(self.position, NonFungibleLocalId::integer(1));
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[caller], collateral, dec!(10))
+            .take_all_from_worktop(collateral, "collateral")
+            .call_method_with_name_lookup(self.auction, "start_auction", |lookup| {
+                (
+                    lookup.bucket("collateral"),
+                    debt,
+                    debt_amount,
+                    dec!(10),
+                    borrower,
+                )
+            });
+
+        self.env.execute(caller, builder)
+    }
+
+    fn bid(&mut self, auction_id: u64, amount: Decimal, max_price: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[KEEPER], self.debt, amount)
+            .take_all_from_worktop(self.debt, "payment")
+            .call_method_with_name_lookup(self.auction, "bid", |lookup| {
+                (auction_id, lookup.bucket("payment"), max_price)
+            });
+
+        self.env.execute(KEEPER, builder)
+    }
+
+    fn claim_surplus(&mut self, auction_id: u64, position_id: u64) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_non_fungibles(
+                self.env.accounts[BORROWER],
+                self.position,
+                &BTreeSet::from([NonFungibleLocalId::integer(position_id)]),
+            )
+            .pop_from_auth_zone("borrower")
+            .call_method_with_name_lookup(self.auction, "claim_surplus", |lookup| {
+                (auction_id, lookup.proof("borrower"))
+            });
+
+        self.env.execute(BORROWER, builder)
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.auction, method, args)
+    }
+
+    fn get(&mut self, method: &str, auction_id: u64) -> Decimal {
+        self.call(KEEPER, method, manifest_args!(auction_id))
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_auction_price_decays_to_the_floor() {
+    let mut auction = TestAuction::new();
+
+    auction
+        .start_auction(MARKET, dec!(50))
+        .expect_commit_success();
+    assert_eq!(auction.get("get_auction_price", 1), dec!(10));
+
+    auction.env.set_epoch(15);
+    assert_eq!(auction.get("get_auction_price", 1), dec!("7.5"));
+
+    auction.env.set_epoch(30);
+    assert_eq!(auction.get("get_auction_price", 1), dec!(5));
+}
+
+#[test]
+fn test_bid_covers_the_debt_and_leaves_the_surplus() {
+    let mut auction = TestAuction::new();
+    let (collateral, debt) = (auction.collateral, auction.debt);
+
+    auction
+        .start_auction(MARKET, dec!(50))
+        .expect_commit_success();
+    auction.env.set_epoch(15);
+
+    auction.bid(1, dec!(100), dec!(7)).expect_commit_failure();
+    auction.claim_surplus(1, 1).expect_commit_failure();
+
+    auction.bid(1, dec!(100), dec!(8)).expect_commit_success();
+    assert_eq!(auction.env.balance(KEEPER, debt), dec!(950));
+    assert_eq!(auction.get("get_debt_remaining", 1), dec!(0));
+    auction.bid(1, dec!(10), dec!(8)).expect_commit_failure();
+
+    auction.claim_surplus(1, 2).expect_commit_failure();
+    auction.claim_surplus(1, 1).expect_commit_success();
+    assert_eq!(
+        auction.env.balance(KEEPER, collateral) + auction.env.balance(BORROWER, collateral),
+        dec!(10)
+    );
+    assert!(auction.env.balance(KEEPER, collateral) > dec!("6.66"));
+
+    auction
+        .call(KEEPER, "withdraw_proceeds", manifest_args!(debt))
+        .expect_commit_failure();
+    auction
+        .call(MARKET, "withdraw_proceeds", manifest_args!(debt))
+        .expect_commit_success();
+    assert_eq!(auction.env.balance(MARKET, debt), dec!(50));
+}
+
+#[test]
+fn test_auction_closes_with_a_shortfall_once_the_collateral_is_sold() {
+    let mut auction = TestAuction::new();
+    let (collateral, debt) = (auction.collateral, auction.debt);
+
+    auction
+        .start_auction(MARKET, dec!(80))
+        .expect_commit_success();
+    auction.env.set_epoch(20);
+
+    auction.bid(1, dec!(100), dec!(5)).expect_commit_success();
+
+    assert_eq!(auction.env.balance(KEEPER, collateral), dec!(10));
+    assert_eq!(auction.env.balance(KEEPER, debt), dec!(950));
+    assert_eq!(auction.get("get_debt_remaining", 1), dec!(30));
+    auction.bid(1, dec!(10), dec!(5)).expect_commit_failure();
+}
+
+#[test]
+fn test_keeper_resets_an_auction_at_its_floor_price() {
+    let mut auction = TestAuction::new();
+    let (incentive, auction_address, owner) = (
+        auction.incentive,
+        auction.auction,
+        auction.env.accounts[OWNER],
+    );
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(owner, incentive, dec!(8))
+        .take_all_from_worktop(incentive, "incentives")
+        .call_method_with_name_lookup(auction_address, "fund_incentives", |lookup| {
+            (lookup.bucket("incentives"),)
+        });
+    auction.env.execute(OWNER, builder).expect_commit_success();
+
+    auction
+        .start_auction(MARKET, dec!(50))
+        .expect_commit_success();
+
+    auction.env.set_epoch(19);
+    auction
+        .call(KEEPER, "reset_auction", manifest_args!(1u64))
+        .expect_commit_failure();
+
+    auction.env.set_epoch(20);
+    auction
+        .call(KEEPER, "reset_auction", manifest_args!(1u64))
+        .expect_commit_success();
+    assert_eq!(auction.env.balance(KEEPER, incentive), dec!(5));
+    assert_eq!(auction.get("get_auction_price", 1), dec!(10));
+
+    // The reward is capped by the incentives left
+    auction.env.set_epoch(30);
+    auction
+        .call(KEEPER, "reset_auction", manifest_args!(1u64))
+        .expect_commit_success();
+    assert_eq!(auction.env.balance(KEEPER, incentive), dec!(8));
+}
+
+#[test]
+fn test_admin_methods_require_their_role() {
+    let mut auction = TestAuction::new();
+    let (collateral, keeper) = (auction.collateral, auction.env.accounts[KEEPER]);
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(auction.env.accounts[MARKET], collateral, dec!(10))
+        .take_all_from_worktop(collateral, "collateral")
+        .call_method_with_name_lookup(keeper, "try_deposit_or_abort", |lookup| {
+            (lookup.bucket("collateral"), None::<ResourceOrNonFungible>)
+        });
+    auction.env.execute(MARKET, builder).expect_commit_success();
+
+    auction
+        .start_auction(KEEPER, dec!(50))
+        .expect_commit_failure();
+
+    auction
+        .call(MARKET, "set_keeper_reward", manifest_args!(dec!(1)))
+        .expect_commit_failure();
+    auction
+        .call(OWNER, "set_keeper_reward", manifest_args!(dec!(-1)))
+        .expect_commit_failure();
+    auction
+        .call(OWNER, "set_keeper_reward", manifest_args!(dec!(1)))
+        .expect_commit_success();
+}
@@ -0,0 +1,438 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+
+/// Maximum number of point batches held by a customer for a merchant. Points issued during
+/// the same epoch are merged in a single batch.
+pub const MAX_POINT_BATCHES: usize = 50;
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct MerchantBadge {
+    pub name: String,
+}
+
+/// Non-transferable badge identifying a customer across merchants
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct CustomerBadge {
+    pub registered_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor)]
+pub struct Merchant {
+    /// Checkout component allowed to issue points on purchases
+    pub checkout: Option<ComponentAddress>,
+
+    /// Points issued per unit of purchase amount
+    pub points_per_unit: Decimal,
+
+    /// Number of epochs after which issued points expire
+    pub expiry_epochs: u64,
+
+    /// Rewards funded by the merchant, paid out on redemption
+    pub rewards: Vault,
+
+    /// Rewards paid per redeemed point
+    pub reward_per_point: Decimal,
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct PointBatch {
+    pub amount: Decimal,
+    pub expires_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PointsIssuedEvent {
+    pub merchant_id: NonFungibleLocalId,
+    pub customer_id: NonFungibleLocalId,
+    pub points: Decimal,
+    pub expires_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PointsRedeemedEvent {
+    pub merchant_id: NonFungibleLocalId,
+    pub customer_id: NonFungibleLocalId,
+    pub points: Decimal,
+    pub reward_amount: Decimal,
+}
+
+#[blueprint]
+#[events(PointsIssuedEvent, PointsRedeemedEvent)]
+pub mod loyalty_points {
+
+    enable_method_auth! {
+        methods {
+
+            register_merchant => PUBLIC;
+            set_checkout => PUBLIC;
+            set_terms => PUBLIC;
+            fund_rewards => PUBLIC;
+            withdraw_rewards => PUBLIC;
+
+            issue_points => PUBLIC;
+
+            register_customer => PUBLIC;
+            redeem => PUBLIC;
+
+            get_balance => PUBLIC;
+
+        }
+    }
+
+    pub struct LoyaltyPoints {
+        /// Merchant badge non-fungible resource manager
+        merchant_res_manager: ResourceManager,
+        merchants: KeyValueStore<NonFungibleLocalId, Merchant>,
+
+        /// Customer badge non-fungible resource manager
+        customer_res_manager: ResourceManager,
+
+        /// Unexpired point batches of each customer, indexed by merchant and customer
+        balances: KeyValueStore<(NonFungibleLocalId, NonFungibleLocalId), Vec<PointBatch>>,
+    }
+
+    impl LoyaltyPoints {
+        pub fn instantiate(
+            owner_role: OwnerRole,
+        ) -> (Global<LoyaltyPoints>, ResourceAddress, ResourceAddress) {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(LoyaltyPoints::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let merchant_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<MerchantBadge>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let customer_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<CustomerBadge>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule;
+                        minter_updater => rule!(deny_all);
+                    })
+                    .withdraw_roles(withdraw_roles! {
+                        withdrawer => rule!(deny_all);
+                        withdrawer_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let loyalty_component = Self {
+                merchant_res_manager,
+                merchants: KeyValueStore::new(),
+                customer_res_manager,
+                balances: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (
+                loyalty_component,
+                merchant_res_manager.address(),
+                customer_res_manager.address(),
+            )
+        }
+
+        /* MERCHANT METHODS */
+
+        /// Register a merchant program paying rewards in the given resource. Returns the
+        /// merchant badge.
+        pub fn register_merchant(
+            &mut self,
+            name: String,
+            reward_res_address: ResourceAddress,
+            points_per_unit: Decimal,
+            reward_per_point: Decimal,
+            expiry_epochs: u64,
+        ) -> Bucket {
+            /* CHECK INPUT */
+            LoyaltyPoints::_assert_valid_terms(points_per_unit, reward_per_point, expiry_epochs);
+
+            let merchant_badge = self
+                .merchant_res_manager
+                .mint_ruid_non_fungible(MerchantBadge { name });
+
+            self.merchants.insert(
+                merchant_badge.as_non_fungible().non_fungible_local_id(),
+                Merchant {
+                    checkout: None,
+                    points_per_unit,
+                    expiry_epochs,
+                    rewards: Vault::new(reward_res_address),
+                    reward_per_point,
+                },
+            );
+
+            merchant_badge
+        }
+
+        /// Set the checkout component allowed to issue points for the merchant
+        pub fn set_checkout(&mut self, merchant_proof: Proof, checkout: Option<ComponentAddress>) {
+            let merchant_id = self._check_merchant_proof(merchant_proof);
+
+            self.merchants.get_mut(&merchant_id).unwrap().checkout = checkout;
+        }
+
+        /// Update the merchant terms. Points already issued keep their expiry.
+        pub fn set_terms(
+            &mut self,
+            merchant_proof: Proof,
+            points_per_unit: Decimal,
+            reward_per_point: Decimal,
+            expiry_epochs: u64,
+        ) {
+            let merchant_id = self._check_merchant_proof(merchant_proof);
+
+            /* CHECK INPUT */
+            LoyaltyPoints::_assert_valid_terms(points_per_unit, reward_per_point, expiry_epochs);
+
+            let mut merchant = self.merchants.get_mut(&merchant_id).unwrap();
+            merchant.points_per_unit = points_per_unit;
+            merchant.reward_per_point = reward_per_point;
+            merchant.expiry_epochs = expiry_epochs;
+        }
+
+        pub fn fund_rewards(&mut self, merchant_proof: Proof, rewards: Bucket) {
+            let merchant_id = self._check_merchant_proof(merchant_proof);
+
+            self.merchants
+                .get_mut(&merchant_id)
+                .unwrap()
+                .rewards
+                .put(rewards);
+        }
+
+        pub fn withdraw_rewards(&mut self, merchant_proof: Proof, amount: Decimal) -> Bucket {
+            let merchant_id = self._check_merchant_proof(merchant_proof);
+
+            self.merchants
+                .get_mut(&merchant_id)
+                .unwrap()
+                .rewards
+                .take(amount)
+        }
+
+        /* CHECKOUT METHODS */
+
+        /// Issue points to a customer for a purchase. Must be called by the checkout component
+        /// of the merchant.
+        pub fn issue_points(
+            &mut self,
+            merchant_id: NonFungibleLocalId,
+            customer_id: NonFungibleLocalId,
+            purchase_amount: Decimal,
+        ) -> Decimal {
+            let (points, expires_at_epoch) = {
+                let merchant = self
+                    .merchants
+                    .get(&merchant_id)
+                    .expect("Merchant not found");
+
+                let checkout = merchant
+                    .checkout
+                    .expect("Merchant has no checkout component");
+                Runtime::assert_access_rule(rule!(require(global_caller(checkout))));
+
+                (
+                    purchase_amount * merchant.points_per_unit,
+                    Runtime::current_epoch().number() + merchant.expiry_epochs,
+                )
+            };
+
+            /* CHECK INPUT */
+            assert!(
+                purchase_amount > Decimal::ZERO,
+                "Purchase amount must be greater than zero!"
+            );
+            assert!(
+                self.customer_res_manager.non_fungible_exists(&customer_id),
+                "Customer not found"
+            );
+
+            let mut batches = self._get_unexpired_batches(&merchant_id, &customer_id);
+
+            match batches.last_mut() {
+                Some(batch) if batch.expires_at_epoch == expires_at_epoch => {
+                    batch.amount += points;
+                }
+                _ => {
+                    assert!(batches.len() < MAX_POINT_BATCHES, "Too many point batches");
+
+                    batches.push(PointBatch {
+                        amount: points,
+                        expires_at_epoch,
+                    });
+                }
+            }
+
+            self.balances
+                .insert((merchant_id.clone(), customer_id.clone()), batches);
+
+            Runtime::emit_event(PointsIssuedEvent {
+                merchant_id,
+                customer_id,
+                points,
+                expires_at_epoch,
+            });
+
+            points
+        }
+
+        /* CUSTOMER METHODS */
+
+        /// Register a customer. The customer badge is deposited into the account and can not
+        /// be transferred.
+        pub fn register_customer(&mut self, mut account: Global<Account>) {
+            let customer_badge = self
+                .customer_res_manager
+                .mint_ruid_non_fungible(CustomerBadge {
+                    registered_at_epoch: Runtime::current_epoch().number(),
+                });
+
+            account.try_deposit_or_abort(customer_badge, None);
+        }
+
+        /// Redeem unexpired points of a merchant for its rewards. Points expiring first are
+        /// redeemed first.
+        pub fn redeem(
+            &mut self,
+            customer_proof: Proof,
+            merchant_id: NonFungibleLocalId,
+            points: Decimal,
+        ) -> Bucket {
+            let customer_id = customer_proof
+                .check(self.customer_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id();
+
+            /* CHECK INPUT */
+            assert!(points > Decimal::ZERO, "Points must be greater than zero!");
+
+            let mut batches = self._get_unexpired_batches(&merchant_id, &customer_id);
+
+            let mut remaining = points;
+            for batch in batches.iter_mut() {
+                let used = batch.amount.min(remaining);
+                batch.amount -= used;
+                remaining -= used;
+            }
+
+            assert!(remaining == Decimal::ZERO, "Not enough points");
+
+            batches.retain(|batch| batch.amount > Decimal::ZERO);
+            self.balances
+                .insert((merchant_id.clone(), customer_id.clone()), batches);
+
+            let mut merchant = self
+                .merchants
+                .get_mut(&merchant_id)
+                .expect("Merchant not found");
+
+            let reward_amount = points * merchant.reward_per_point;
+            assert!(
+                merchant.rewards.amount() >= reward_amount,
+                "Not enough rewards funded"
+            );
+
+            let rewards = merchant.rewards.take_advanced(
+                reward_amount,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+
+            drop(merchant);
+
+            Runtime::emit_event(PointsRedeemedEvent {
+                merchant_id,
+                customer_id,
+                points,
+                reward_amount: rewards.amount(),
+            });
+
+            rewards
+        }
+
+        /* GETTERS */
+
+        /// Unexpired points of a customer for a merchant
+        pub fn get_balance(
+            &self,
+            merchant_id: NonFungibleLocalId,
+            customer_id: NonFungibleLocalId,
+        ) -> Decimal {
+            self._get_unexpired_batches(&merchant_id, &customer_id)
+                .iter()
+                .fold(Decimal::ZERO, |total, batch| total + batch.amount)
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _get_unexpired_batches(
+            &self,
+            merchant_id: &NonFungibleLocalId,
+            customer_id: &NonFungibleLocalId,
+        ) -> Vec<PointBatch> {
+            let current_epoch = Runtime::current_epoch().number();
+
+            self.balances
+                .get(&(merchant_id.clone(), customer_id.clone()))
+                .map(|batches| {
+                    batches
+                        .iter()
+                        .filter(|batch| batch.expires_at_epoch > current_epoch)
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        fn _assert_valid_terms(
+            points_per_unit: Decimal,
+            reward_per_point: Decimal,
+            expiry_epochs: u64,
+        ) {
+            assert!(
+                points_per_unit >= Decimal::ZERO,
+                "Points per unit must not be negative"
+            );
+            assert!(
+                reward_per_point >= Decimal::ZERO,
+                "Reward per point must not be negative"
+            );
+            assert!(expiry_epochs > 0, "Expiry must be greater than zero");
+        }
+
+        fn _check_merchant_proof(&self, merchant_proof: Proof) -> NonFungibleLocalId {
+            merchant_proof
+                .check(self.merchant_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use scrypto::prelude::*;
+
+#[blueprint]
+mod checkout {
+    struct Checkout {
+        /// Loyalty component issuing the points of the merchant
+        loyalty: ComponentAddress,
+
+        merchant_id: NonFungibleLocalId,
+    }
+
+    impl Checkout {
+        pub fn instantiate(
+            loyalty: ComponentAddress,
+            merchant_id: NonFungibleLocalId,
+        ) -> Global<Checkout> {
+            Self {
+                loyalty,
+                merchant_id,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .globalize()
+        }
+
+        /// Record a purchase of the customer, and return the issued points
+        pub fn purchase(
+            &mut self,
+            customer_id: NonFungibleLocalId,
+            purchase_amount: Decimal,
+        ) -> Decimal {
+            Global::<AnyComponent>::from(self.loyalty).call_raw::<Decimal>(
+                "issue_points",
+                scrypto_args!(self.merchant_id.clone(), customer_id, purchase_amount),
+            )
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+const OWNER: usize = 0;
+/// Holds the merchant badge and 100 reward tokens
+const MERCHANT: usize = 1;
+/// Registered customer
+const ALICE: usize = 2;
+/// Registered customer
+const BOB: usize = 3;
+
+/// Loyalty program of a merchant at epoch 10, issuing 1 point per unit of purchase through
+/// its checkout component, with points expiring after 10 epochs and redeemed for 0.5 reward
+/// tokens each. The merchant funded 50 reward tokens.
+struct TestLoyalty {
+    env: TestEnv,
+    loyalty: ComponentAddress,
+    checkout: ComponentAddress,
+    merchant_badge: ResourceAddress,
+    customer_badge: ResourceAddress,
+    reward: ResourceAddress,
+}
+
+impl TestLoyalty {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 4);
+        env.set_epoch(10);
+        let checkout_package_address = env
+            .test_runner
+            .compile_and_publish(format!("{}/tests/checkout", env!("CARGO_MANIFEST_DIR")));
+        let reward =
+            env.test_runner
+                .create_fungible_resource(dec!(100), 18, env.accounts[MERCHANT]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "LoyaltyPoints",
+                "instantiate",
+                manifest_args!(OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())))),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let (loyalty, merchant_badge, customer_badge): (
+            ComponentAddress,
+            ResourceAddress,
+            ResourceAddress,
+        ) = receipt.expect_commit_success().output(0);
+
+        let manifest = ManifestBuilder::new()
+            .call_method(
+                loyalty,
+                "register_merchant",
+                manifest_args!("Coffee Shop", reward, dec!(1), dec!("0.5"), 10u64),
+            )
+            .deposit_batch(env.accounts[MERCHANT])
+            .build();
+        env.test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[MERCHANT].clone()])
+            .expect_commit_success();
+
+        let vault_id = env
+            .test_runner
+            .get_component_vaults(env.accounts[MERCHANT], merchant_badge)[0];
+        let (_, mut ids) = env
+            .test_runner
+            .inspect_non_fungible_vault(vault_id)
+            .unwrap();
+        let merchant_id = ids.next().unwrap();
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                checkout_package_address,
+                "Checkout",
+                "instantiate",
+                manifest_args!(loyalty, merchant_id),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let checkout = receipt.expect_commit_success().new_component_addresses()[0];
+
+        let mut loyalty = Self {
+            env,
+            loyalty,
+            checkout,
+            merchant_badge,
+            customer_badge,
+            reward,
+        };
+
+        loyalty
+            .call_with_merchant_badge("set_checkout", |proof| (proof, Some(checkout)))
+            .expect_commit_success();
+
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(loyalty.env.accounts[MERCHANT], reward, dec!(50))
+            .take_all_from_worktop(reward, "rewards");
+        loyalty
+            .call_with_badge(
+                MERCHANT,
+                merchant_badge,
+                builder,
+                "fund_rewards",
+                |lookup| (lookup.proof("badge"), lookup.bucket("rewards")),
+            )
+            .expect_commit_success();
+
+        for account in [ALICE, BOB] {
+            let account_address = loyalty.env.accounts[account];
+            loyalty
+                .call(
+                    account,
+                    "register_customer",
+                    manifest_args!(account_address),
+                )
+                .expect_commit_success();
+        }
+
+        loyalty
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.loyalty, method, args)
+    }
+
+    /// Id of the only badge of the given resource held by the account
+    fn badge_id(&mut self, account: usize, res_address: ResourceAddress) -> NonFungibleLocalId {
+        let vault_id = self
+            .env
+            .test_runner
+            .get_component_vaults(self.env.accounts[account], res_address)[0];
+        let (_, mut ids) = self
+            .env
+            .test_runner
+            .inspect_non_fungible_vault(vault_id)
+            .unwrap();
+
+        ids.next().unwrap()
+    }
+
+    /// Call a method with a proof of the badge of the account, prepending the manifest of the
+    /// builder
+    fn call_with_badge<A: ResolvableArguments>(
+        &mut self,
+        account: usize,
+        res_address: ResourceAddress,
+        builder: ManifestBuilder,
+        method: &str,
+        args: impl FnOnce(&ManifestNameLookup) -> A,
+    ) -> TransactionReceipt {
+        let builder = builder
+            .create_proof_from_account_of_amount(self.env.accounts[account], res_address, dec!(1))
+            .pop_from_auth_zone("badge")
+            .call_method_with_name_lookup(self.loyalty, method, |lookup| args(lookup));
+
+        self.env.execute(account, builder)
+    }
+
+    fn call_with_merchant_badge<A: ResolvableArguments>(
+        &mut self,
+        method: &str,
+        args: impl FnOnce(ManifestProof) -> A,
+    ) -> TransactionReceipt {
+        let merchant_badge = self.merchant_badge;
+
+        self.call_with_badge(
+            MERCHANT,
+            merchant_badge,
+            ManifestBuilder::new(),
+            method,
+            |lookup| args(lookup.proof("badge")),
+        )
+    }
+
+    /// Record a purchase of the customer through the checkout component of the merchant
+    fn purchase(&mut self, customer: usize, amount: Decimal) -> TransactionReceipt {
+        let customer_id = self.badge_id(customer, self.customer_badge);
+        let builder = ManifestBuilder::new().call_method(
+            self.checkout,
+            "purchase",
+            manifest_args!(customer_id, amount),
+        );
+
+        self.env.execute(customer, builder)
+    }
+
+    fn redeem(&mut self, customer: usize, points: Decimal) -> TransactionReceipt {
+        let (customer_badge, merchant_badge) = (self.customer_badge, self.merchant_badge);
+        let merchant_id = self.badge_id(MERCHANT, merchant_badge);
+
+        self.call_with_badge(
+            customer,
+            customer_badge,
+            ManifestBuilder::new(),
+            "redeem",
+            |lookup| (lookup.proof("badge"), merchant_id, points),
+        )
+    }
+
+    fn get_balance(&mut self, customer: usize) -> Decimal {
+        let merchant_id = self.badge_id(MERCHANT, self.merchant_badge);
+        let customer_id = self.badge_id(customer, self.customer_badge);
+
+        self.call(
+            OWNER,
+            "get_balance",
+            manifest_args!(merchant_id, customer_id),
+        )
+        .expect_commit_success()
+        .output(0)
+    }
+}
+
+#[test]
+fn test_points_are_issued_redeemed_and_expire() {
+    let mut loyalty = TestLoyalty::new();
+    let reward = loyalty.reward;
+
+    let points: Decimal = loyalty
+        .purchase(ALICE, dec!(20))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(points, dec!(20));
+
+    loyalty.env.set_epoch(15);
+    loyalty.purchase(ALICE, dec!(10)).expect_commit_success();
+    assert_eq!(loyalty.get_balance(ALICE), dec!(30));
+    assert_eq!(loyalty.get_balance(BOB), dec!(0));
+
+    loyalty.redeem(ALICE, dec!(31)).expect_commit_failure();
+    loyalty.redeem(BOB, dec!(1)).expect_commit_failure();
+
+    // The points issued at epoch 10 are redeemed first
+    loyalty.redeem(ALICE, dec!(16)).expect_commit_success();
+    assert_eq!(loyalty.env.balance(ALICE, reward), dec!(8));
+    assert_eq!(loyalty.get_balance(ALICE), dec!(14));
+
+    // The 4 points left from epoch 10 expire at epoch 20
+    loyalty.env.set_epoch(20);
+    assert_eq!(loyalty.get_balance(ALICE), dec!(10));
+    loyalty.redeem(ALICE, dec!(11)).expect_commit_failure();
+    loyalty.redeem(ALICE, dec!(10)).expect_commit_success();
+    assert_eq!(loyalty.env.balance(ALICE, reward), dec!(13));
+    assert_eq!(loyalty.get_balance(ALICE), dec!(0));
+
+    // The customer badge can not be transferred
+    let customer_badge = loyalty.customer_badge;
+    let builder = ManifestBuilder::new().withdraw_from_account(
+        loyalty.env.accounts[ALICE],
+        customer_badge,
+        dec!(1),
+    );
+    loyalty.env.execute(ALICE, builder).expect_commit_failure();
+}
+
+#[test]
+fn test_points_are_only_issued_by_the_checkout() {
+    let mut loyalty = TestLoyalty::new();
+    let merchant_badge = loyalty.merchant_badge;
+
+    let merchant_id = loyalty.badge_id(MERCHANT, merchant_badge);
+    let customer_id = loyalty.badge_id(ALICE, loyalty.customer_badge);
+    loyalty
+        .call(
+            MERCHANT,
+            "issue_points",
+            manifest_args!(merchant_id, customer_id, dec!(20)),
+        )
+        .expect_commit_failure();
+
+    loyalty.purchase(ALICE, dec!(0)).expect_commit_failure();
+
+    // Only the merchant badge updates the checkout
+    let customer_badge = loyalty.customer_badge;
+    loyalty
+        .call_with_badge(
+            ALICE,
+            customer_badge,
+            ManifestBuilder::new(),
+            "set_checkout",
+            |lookup| (lookup.proof("badge"), None::<ComponentAddress>),
+        )
+        .expect_commit_failure();
+    loyalty.purchase(ALICE, dec!(20)).expect_commit_success();
+
+    loyalty
+        .call_with_merchant_badge("set_checkout", |proof| (proof, None::<ComponentAddress>))
+        .expect_commit_success();
+    loyalty.purchase(ALICE, dec!(20)).expect_commit_failure();
+    assert_eq!(loyalty.get_balance(ALICE), dec!(20));
+}
+
+#[test]
+fn test_merchant_terms_and_rewards() {
+    let mut loyalty = TestLoyalty::new();
+    let reward = loyalty.reward;
+
+    loyalty
+        .call_with_merchant_badge("set_terms", |proof| (proof, dec!(-1), dec!(1), 10u64))
+        .expect_commit_failure();
+    loyalty
+        .call_with_merchant_badge("set_terms", |proof| (proof, dec!(2), dec!(1), 0u64))
+        .expect_commit_failure();
+    loyalty
+        .call_with_merchant_badge("set_terms", |proof| (proof, dec!(2), dec!(1), 10u64))
+        .expect_commit_success();
+
+    loyalty.purchase(ALICE, dec!(20)).expect_commit_success();
+    assert_eq!(loyalty.get_balance(ALICE), dec!(40));
+
+    // 40 points are worth 40 rewards, more than the 20 left after the withdrawal
+    loyalty
+        .call_with_merchant_badge("withdraw_rewards", |proof| (proof, dec!(30)))
+        .expect_commit_success();
+    assert_eq!(loyalty.env.balance(MERCHANT, reward), dec!(80));
+
+    loyalty.redeem(ALICE, dec!(40)).expect_commit_failure();
+    loyalty.redeem(ALICE, dec!(20)).expect_commit_success();
+    assert_eq!(loyalty.env.balance(ALICE, reward), dec!(20));
+    assert_eq!(loyalty.get_balance(ALICE), dec!(20));
+}
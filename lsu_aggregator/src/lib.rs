@@ -0,0 +1,320 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::{checked_div_to_decimal, checked_mul_to_decimal};
+use scrypto::prelude::*;
+
+/// Maximum number of validators whose LSUs are accepted
+pub const MAX_VALIDATORS: usize = 20;
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct LsuDepositedEvent {
+    pub lsu_res_address: ResourceAddress,
+    pub lsu_amount: Decimal,
+    pub xrd_value: Decimal,
+    pub liquid_amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct UnstakedEvent {
+    pub lsu_res_address: ResourceAddress,
+    pub lsu_amount: Decimal,
+    pub liquid_amount: Decimal,
+}
+
+#[blueprint]
+#[events(LsuDepositedEvent, UnstakedEvent)]
+pub mod lsu_aggregator {
+
+    enable_method_auth! {
+        methods {
+
+            add_validator => restrict_to :[OWNER];
+            set_deposits_enabled => restrict_to :[OWNER];
+
+            deposit => PUBLIC;
+            redeem => PUBLIC;
+            unstake => PUBLIC;
+
+            get_total_xrd_value => PUBLIC;
+            get_unit_to_xrd_ratio => PUBLIC;
+            get_liquid_amount_for_xrd => PUBLIC;
+            get_xrd_for_liquid_amount => PUBLIC;
+            get_lsu_amounts => PUBLIC;
+
+        }
+    }
+
+    pub struct LsuAggregator {
+        /// Validator of each accepted LSU resource
+        validators: IndexMap<ResourceAddress, Global<Validator>>,
+
+        /// LSU resources whose deposits are currently accepted
+        deposits_enabled: IndexMap<ResourceAddress, bool>,
+
+        /// Deposited LSUs, indexed by resource
+        lsu_vaults: KeyValueStore<ResourceAddress, Vault>,
+
+        /// Liquid token fungible resource manager
+        liquid_res_manager: ResourceManager,
+    }
+
+    impl LsuAggregator {
+        pub fn instantiate(owner_role: OwnerRole) -> (Global<LsuAggregator>, ResourceAddress) {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(LsuAggregator::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let liquid_res_manager = ResourceBuilder::new_fungible(owner_role.clone())
+                .mint_roles(mint_roles! {
+                    minter => component_rule.clone();
+                    minter_updater => rule!(deny_all);
+                })
+                .burn_roles(burn_roles! {
+                    burner => component_rule;
+                    burner_updater => rule!(deny_all);
+                })
+                .create_with_no_initial_supply();
+
+            let aggregator_component = Self {
+                validators: IndexMap::default(),
+                deposits_enabled: IndexMap::default(),
+                lsu_vaults: KeyValueStore::new(),
+                liquid_res_manager,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (aggregator_component, liquid_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        /// Accept the LSUs of a validator. The LSU resource address must be the stake unit
+        /// resource of the validator.
+        pub fn add_validator(
+            &mut self,
+            validator: Global<Validator>,
+            lsu_res_address: ResourceAddress,
+        ) {
+            /* CHECK INPUT */
+            assert!(
+                !self.validators.contains_key(&lsu_res_address),
+                "Validator already added"
+            );
+            assert!(
+                self.validators.len() < MAX_VALIDATORS,
+                "Maximum number of validators reached"
+            );
+
+            self.validators.insert(lsu_res_address, validator);
+            self.deposits_enabled.insert(lsu_res_address, true);
+            self.lsu_vaults
+                .insert(lsu_res_address, Vault::new(lsu_res_address));
+        }
+
+        /// Stop or resume accepting the LSUs of a validator. Deposited LSUs keep backing the
+        /// liquid token and can still be redeemed.
+        pub fn set_deposits_enabled(&mut self, lsu_res_address: ResourceAddress, enabled: bool) {
+            *self
+                .deposits_enabled
+                .get_mut(&lsu_res_address)
+                .expect("Validator not found") = enabled;
+        }
+
+        /* USER METHODS */
+
+        /// Deposit LSUs of an accepted validator. Returns liquid tokens worth the XRD
+        /// redemption value of the LSUs.
+        pub fn deposit(&mut self, lsus: Bucket) -> Bucket {
+            let lsu_res_address = lsus.resource_address();
+
+            /* CHECK INPUT */
+            assert!(
+                *self
+                    .deposits_enabled
+                    .get(&lsu_res_address)
+                    .expect("LSU resource not accepted"),
+                "Deposits are disabled for this validator"
+            );
+
+            let lsu_amount = lsus.amount();
+            let xrd_value = self.validators[&lsu_res_address].get_redemption_value(lsu_amount);
+
+            // Liquid amount is computed before the deposit changes the total value
+            let liquid_amount = self.get_liquid_amount_for_xrd(xrd_value);
+
+            self.lsu_vaults.get_mut(&lsu_res_address).unwrap().put(lsus);
+
+            Runtime::emit_event(LsuDepositedEvent {
+                lsu_res_address,
+                lsu_amount,
+                xrd_value,
+                liquid_amount,
+            });
+
+            self.liquid_res_manager.mint(liquid_amount)
+        }
+
+        /// Redeem liquid tokens for the LSUs of the given validator, at their XRD redemption
+        /// value
+        pub fn redeem(
+            &mut self,
+            liquid_tokens: Bucket,
+            lsu_res_address: ResourceAddress,
+        ) -> Bucket {
+            self._withdraw_lsus(liquid_tokens, lsu_res_address)
+        }
+
+        /// Redeem liquid tokens for LSUs of the given validator and unstake them. Returns the
+        /// validator claim NFT, redeemable for XRD once the unstaking delay has passed.
+        pub fn unstake(
+            &mut self,
+            liquid_tokens: Bucket,
+            lsu_res_address: ResourceAddress,
+        ) -> Bucket {
+            let lsus = self._withdraw_lsus(liquid_tokens, lsu_res_address);
+
+            self.validators
+                .get_mut(&lsu_res_address)
+                .unwrap()
+                .unstake(lsus)
+        }
+
+        /* GETTERS */
+
+        /// XRD redemption value of every deposited LSU
+        pub fn get_total_xrd_value(&self) -> Decimal {
+            self.validators
+                .iter()
+                .fold(Decimal::ZERO, |total, (lsu_res_address, validator)| {
+                    let lsu_amount = self.lsu_vaults.get(lsu_res_address).unwrap().amount();
+
+                    if lsu_amount == Decimal::ZERO {
+                        total
+                    } else {
+                        total + validator.get_redemption_value(lsu_amount)
+                    }
+                })
+        }
+
+        /// Liquid tokens per XRD of redemption value
+        pub fn get_unit_to_xrd_ratio(&self) -> PreciseDecimal {
+            let total_xrd_value = self.get_total_xrd_value();
+
+            let total_supply = self.liquid_res_manager.total_supply().unwrap_or(dec!(0));
+
+            if total_xrd_value != 0.into() {
+                PreciseDecimal::from(total_supply) / PreciseDecimal::from(total_xrd_value)
+            } else {
+                1.into()
+            }
+        }
+
+        pub fn get_liquid_amount_for_xrd(&self, xrd_amount: Decimal) -> Decimal {
+            checked_mul_to_decimal(
+                xrd_amount,
+                self.get_unit_to_xrd_ratio(),
+                DIVISIBILITY_MAXIMUM,
+                RoundingMode::ToZero,
+            )
+        }
+
+        pub fn get_xrd_for_liquid_amount(&self, liquid_amount: Decimal) -> Decimal {
+            checked_div_to_decimal(
+                liquid_amount,
+                self.get_unit_to_xrd_ratio(),
+                DIVISIBILITY_MAXIMUM,
+                RoundingMode::ToZero,
+            )
+        }
+
+        /// Deposited amount of each accepted LSU resource
+        pub fn get_lsu_amounts(&self) -> IndexMap<ResourceAddress, Decimal> {
+            self.validators
+                .keys()
+                .map(|lsu_res_address| {
+                    (
+                        *lsu_res_address,
+                        self.lsu_vaults.get(lsu_res_address).unwrap().amount(),
+                    )
+                })
+                .collect()
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        /// Burn liquid tokens and take the LSUs of the given validator worth their XRD value
+        fn _withdraw_lsus(
+            &mut self,
+            liquid_tokens: Bucket,
+            lsu_res_address: ResourceAddress,
+        ) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                liquid_tokens.resource_address() == self.liquid_res_manager.address(),
+                "Liquid token resource address mismatch"
+            );
+
+            let validator = self
+                .validators
+                .get(&lsu_res_address)
+                .expect("LSU resource not accepted");
+
+            let liquid_amount = liquid_tokens.amount();
+            let xrd_value = self.get_xrd_for_liquid_amount(liquid_amount);
+
+            let mut lsu_vault = self.lsu_vaults.get_mut(&lsu_res_address).unwrap();
+            let vault_amount = lsu_vault.amount();
+            let vault_xrd_value = validator.get_redemption_value(vault_amount);
+
+            assert!(
+                xrd_value <= vault_xrd_value,
+                "Not enough LSUs of this validator"
+            );
+
+            let lsu_amount = (PreciseDecimal::from(xrd_value) * PreciseDecimal::from(vault_amount)
+                / PreciseDecimal::from(vault_xrd_value))
+            .checked_truncate(RoundingMode::ToZero)
+            .unwrap();
+
+            let lsus = lsu_vault
+                .take_advanced(lsu_amount, WithdrawStrategy::Rounded(RoundingMode::ToZero));
+
+            drop(lsu_vault);
+
+            self.liquid_res_manager.burn(liquid_tokens);
+
+            Runtime::emit_event(UnstakedEvent {
+                lsu_res_address,
+                lsu_amount: lsus.amount(),
+                liquid_amount,
+            });
+
+            lsus
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the aggregator and both validators, and holds 1000 LSUs of each
+const OWNER: usize = 0;
+const ALICE: usize = 1;
+
+/// Aggregator and two validators, each staked with 1000 XRD by their owner
+struct TestAggregator {
+    env: TestEnv,
+    aggregator: ComponentAddress,
+    liquid: ResourceAddress,
+    validators: Vec<ComponentAddress>,
+    lsus: Vec<ResourceAddress>,
+}
+
+impl TestAggregator {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 2);
+
+        let mut validators = vec![];
+        let mut lsus = vec![];
+        for key in 1..=2 {
+            let public_key = Secp256k1PrivateKey::from_u64(key).unwrap().public_key();
+            let validator = env
+                .test_runner
+                .new_validator_with_pub_key(public_key, env.accounts[OWNER]);
+
+            let manifest = ManifestBuilder::new()
+                .create_proof_from_account_of_amount(
+                    env.accounts[OWNER],
+                    VALIDATOR_OWNER_BADGE,
+                    dec!(1),
+                )
+                .withdraw_from_account(env.accounts[OWNER], XRD, dec!(1000))
+                .take_all_from_worktop(XRD, "xrd")
+                .call_method_with_name_lookup(validator, "stake_as_owner", |lookup| {
+                    (lookup.bucket("xrd"),)
+                })
+                .deposit_batch(env.accounts[OWNER])
+                .build();
+            env.test_runner
+                .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()])
+                .expect_commit_success();
+
+            lsus.push(
+                env.test_runner
+                    .get_validator_info(validator)
+                    .stake_unit_resource,
+            );
+            validators.push(validator);
+        }
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "LsuAggregator",
+                "instantiate",
+                manifest_args!(OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())))),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            aggregator: commit.new_component_addresses()[0],
+            liquid: commit.new_resource_addresses()[0],
+            env,
+            validators,
+            lsus,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.aggregator, method, args)
+    }
+
+    fn add_validator(&mut self, caller: usize, index: usize) -> TransactionReceipt {
+        let (validator, lsu) = (self.validators[index], self.lsus[index]);
+
+        self.call(caller, "add_validator", manifest_args!(validator, lsu))
+    }
+
+    /// Accept the LSUs of both validators
+    fn add_validators(&mut self) {
+        for index in 0..2 {
+            self.add_validator(OWNER, index).expect_commit_success();
+        }
+    }
+
+    /// Deposit the given amount of a resource of the owner
+    fn deposit(&mut self, res_address: ResourceAddress, amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[OWNER], res_address, amount)
+            .take_all_from_worktop(res_address, "lsus")
+            .call_method_with_name_lookup(self.aggregator, "deposit", |lookup| {
+                (lookup.bucket("lsus"),)
+            });
+
+        self.env.execute(OWNER, builder)
+    }
+
+    /// Call `redeem` or `unstake` with liquid tokens of the owner
+    fn withdraw(
+        &mut self,
+        method: &str,
+        liquid_amount: Decimal,
+        lsu_res_address: ResourceAddress,
+    ) -> TransactionReceipt {
+        let liquid = self.liquid;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[OWNER], liquid, liquid_amount)
+            .take_all_from_worktop(liquid, "liquid")
+            .call_method_with_name_lookup(self.aggregator, method, |lookup| {
+                (lookup.bucket("liquid"), lsu_res_address)
+            });
+
+        self.env.execute(OWNER, builder)
+    }
+
+    fn get_total_xrd_value(&mut self) -> Decimal {
+        self.call(ALICE, "get_total_xrd_value", manifest_args!())
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_deposit_and_redeem_lsus_of_several_validators() {
+    let mut aggregator = TestAggregator::new();
+    aggregator.add_validators();
+    let (liquid, lsu_1, lsu_2) = (aggregator.liquid, aggregator.lsus[0], aggregator.lsus[1]);
+
+    aggregator.deposit(lsu_1, dec!(500)).expect_commit_success();
+    aggregator.deposit(lsu_2, dec!(300)).expect_commit_success();
+    assert_eq!(aggregator.env.balance(OWNER, liquid), dec!(800));
+    assert_eq!(aggregator.get_total_xrd_value(), dec!(800));
+
+    let lsu_amounts: IndexMap<ResourceAddress, Decimal> = aggregator
+        .call(ALICE, "get_lsu_amounts", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(lsu_amounts[&lsu_2], dec!(300));
+
+    // Liquid tokens are redeemable for the LSUs of any validator
+    aggregator
+        .withdraw("redeem", dec!(200), lsu_2)
+        .expect_commit_success();
+    assert_eq!(aggregator.env.balance(OWNER, lsu_2), dec!(900));
+    assert_eq!(aggregator.env.balance(OWNER, liquid), dec!(600));
+
+    aggregator
+        .withdraw("redeem", dec!(101), lsu_2)
+        .expect_commit_failure();
+    aggregator
+        .withdraw("redeem", dec!(500), lsu_1)
+        .expect_commit_success();
+    assert_eq!(aggregator.get_total_xrd_value(), dec!(100));
+}
+
+#[test]
+fn test_unstake_returns_the_claim_nft() {
+    let mut aggregator = TestAggregator::new();
+    aggregator.add_validators();
+    let (lsu_1, validator) = (aggregator.lsus[0], aggregator.validators[0]);
+    let claim_nft = aggregator
+        .env
+        .test_runner
+        .get_validator_info(validator)
+        .claim_nft;
+
+    aggregator.deposit(lsu_1, dec!(500)).expect_commit_success();
+    aggregator
+        .withdraw("unstake", dec!(100), lsu_1)
+        .expect_commit_success();
+
+    assert_eq!(aggregator.env.balance(OWNER, claim_nft), dec!(1));
+    assert_eq!(aggregator.get_total_xrd_value(), dec!(400));
+}
+
+#[test]
+fn test_only_accepted_lsus_are_deposited() {
+    let mut aggregator = TestAggregator::new();
+    aggregator.add_validators();
+    let (lsu_1, lsu_2) = (aggregator.lsus[0], aggregator.lsus[1]);
+
+    aggregator.deposit(XRD, dec!(100)).expect_commit_failure();
+
+    aggregator.deposit(lsu_1, dec!(500)).expect_commit_success();
+    aggregator
+        .call(ALICE, "set_deposits_enabled", manifest_args!(lsu_1, false))
+        .expect_commit_failure();
+    aggregator
+        .call(OWNER, "set_deposits_enabled", manifest_args!(lsu_1, false))
+        .expect_commit_success();
+
+    aggregator.deposit(lsu_1, dec!(100)).expect_commit_failure();
+    aggregator.deposit(lsu_2, dec!(100)).expect_commit_success();
+
+    // Deposited LSUs of a disabled validator are still redeemable
+    aggregator
+        .withdraw("redeem", dec!(500), lsu_1)
+        .expect_commit_success();
+    assert_eq!(aggregator.env.balance(OWNER, lsu_1), dec!(1000));
+}
+
+#[test]
+fn test_add_validator_requires_the_owner() {
+    let mut aggregator = TestAggregator::new();
+    let lsu_1 = aggregator.lsus[0];
+
+    aggregator.deposit(lsu_1, dec!(100)).expect_commit_failure();
+
+    aggregator.add_validator(ALICE, 0).expect_commit_failure();
+    aggregator.add_validator(OWNER, 0).expect_commit_success();
+    aggregator.add_validator(OWNER, 0).expect_commit_failure();
+
+    aggregator.deposit(lsu_1, dec!(100)).expect_commit_success();
+}
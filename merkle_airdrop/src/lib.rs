@@ -0,0 +1,181 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+/// Hash of a leaf of the airdrop tree: the Blake2b-256 hash of the SBOR encoded
+/// `(account, amount)` pair
+pub fn leaf_hash(account: ComponentAddress, amount: Decimal) -> Hash {
+    hash(scrypto_encode(&(account, amount)).unwrap())
+}
+
+/// Hash of a pair of nodes. Nodes are sorted before hashing so proofs do not need to
+/// specify the side of each sibling.
+pub fn node_hash(left: Hash, right: Hash) -> Hash {
+    let (first, second) = if left.0 <= right.0 {
+        (left, right)
+    } else {
+        (right, left)
+    };
+
+    let mut data = first.0.to_vec();
+    data.extend_from_slice(&second.0);
+
+    hash(data)
+}
+
+/// Check that a leaf belongs to the tree with the given root
+pub fn verify_merkle_proof(root: Hash, leaf: Hash, proof: &[Hash]) -> bool {
+    proof
+        .iter()
+        .fold(leaf, |node, sibling| node_hash(node, *sibling))
+        == root
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct AirdropClaimedEvent {
+    pub account: ComponentAddress,
+    pub amount: Decimal,
+}
+
+#[blueprint]
+#[events(AirdropClaimedEvent)]
+pub mod merkle_airdrop {
+
+    enable_method_auth! {
+        methods {
+
+            withdraw_unclaimed => restrict_to :[OWNER];
+
+            claim => PUBLIC;
+
+            is_claimed => PUBLIC;
+            get_merkle_root => PUBLIC;
+            get_remaining_amount => PUBLIC;
+
+        }
+    }
+
+    pub struct MerkleAirdrop {
+        /// Root of the tree of the `(account, amount)` allocations
+        merkle_root: Hash,
+
+        funds: Vault,
+
+        /// Accounts that have claimed their allocation
+        claimed: KeyValueStore<ComponentAddress, ()>,
+
+        /// Epoch after which the owner can withdraw the unclaimed funds
+        claim_deadline_epoch: Option<u64>,
+    }
+
+    impl MerkleAirdrop {
+        pub fn instantiate(
+            merkle_root: Hash,
+            funds: Bucket,
+            claim_deadline_epoch: Option<u64>,
+            owner_role: OwnerRole,
+        ) -> Global<MerkleAirdrop> {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                funds.resource_address(),
+                Some("Airdropped resource must be fungible".to_string()),
+            );
+
+            Self {
+                merkle_root,
+                funds: Vault::with_bucket(funds),
+                claimed: KeyValueStore::new(),
+                claim_deadline_epoch,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        /// Withdraw the funds that have not been claimed once the claim deadline has passed
+        pub fn withdraw_unclaimed(&mut self) -> Bucket {
+            let claim_deadline_epoch = self
+                .claim_deadline_epoch
+                .expect("Airdrop has no claim deadline");
+
+            assert!(
+                Runtime::current_epoch().number() > claim_deadline_epoch,
+                "Claim deadline has not passed yet"
+            );
+
+            self.funds.take_all()
+        }
+
+        /* CLAIMANT METHODS */
+
+        /// Claim the allocation of an account, which is deposited to that account. Anyone can
+        /// submit the claim since the funds can only go to the account of the allocation.
+        pub fn claim(&mut self, account: Global<Account>, amount: Decimal, proof: Vec<Hash>) {
+            let account_address = account.address();
+
+            /* CHECK INPUT */
+            assert!(
+                self.claim_deadline_epoch
+                    .map_or(true, |deadline| Runtime::current_epoch().number()
+                        <= deadline),
+                "Claim deadline has passed"
+            );
+            assert!(
+                self.claimed.get(&account_address).is_none(),
+                "Allocation already claimed"
+            );
+            assert!(
+                verify_merkle_proof(self.merkle_root, leaf_hash(account_address, amount), &proof),
+                "Invalid Merkle proof"
+            );
+
+            self.claimed.insert(account_address, ());
+
+            Runtime::emit_event(AirdropClaimedEvent {
+                account: account_address,
+                amount,
+            });
+
+            let mut account = account;
+            account.try_deposit_or_abort(self.funds.take(amount), None);
+        }
+
+        /* GETTERS */
+
+        pub fn is_claimed(&self, account: ComponentAddress) -> bool {
+            self.claimed.get(&account).is_some()
+        }
+
+        pub fn get_merkle_root(&self) -> Hash {
+            self.merkle_root
+        }
+
+        pub fn get_remaining_amount(&self) -> Decimal {
+            self.funds.amount()
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use merkle_airdrop::*;
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Tree of four leaves and the proof of each leaf
+fn four_leaves_tree(leaves: [Hash; 4]) -> (Hash, Vec<Vec<Hash>>) {
+    let left = node_hash(leaves[0], leaves[1]);
+    let right = node_hash(leaves[2], leaves[3]);
+
+    (
+        node_hash(left, right),
+        vec![
+            vec![leaves[1], right],
+            vec![leaves[0], right],
+            vec![leaves[3], left],
+            vec![leaves[2], left],
+        ],
+    )
+}
+
+fn test_leaves() -> [Hash; 4] {
+    [hash("a"), hash("b"), hash("c"), hash("d")]
+}
+
+#[test]
+fn test_node_hash_does_not_depend_on_the_order() {
+    let (a, b) = (hash("a"), hash("b"));
+
+    assert_eq!(node_hash(a, b), node_hash(b, a));
+    assert_ne!(node_hash(a, b), node_hash(a, a));
+}
+
+#[test]
+fn test_node_hash_hashes_the_sorted_concatenation() {
+    let (a, b) = (hash("a"), hash("b"));
+    let (first, second) = if a.0 <= b.0 { (a, b) } else { (b, a) };
+
+    let mut data = first.0.to_vec();
+    data.extend_from_slice(&second.0);
+
+    assert_eq!(node_hash(a, b), hash(data));
+}
+
+#[test]
+fn test_verify_merkle_proof_of_every_leaf() {
+    let leaves = test_leaves();
+    let (root, proofs) = four_leaves_tree(leaves);
+
+    for (leaf, proof) in leaves.iter().zip(proofs.iter()) {
+        assert!(verify_merkle_proof(root, *leaf, proof));
+    }
+}
+
+#[test]
+fn test_single_leaf_is_its_own_root() {
+    let leaf = hash("a");
+
+    assert!(verify_merkle_proof(leaf, leaf, &[]));
+    assert!(!verify_merkle_proof(leaf, hash("b"), &[]));
+}
+
+#[test]
+fn test_verify_merkle_proof_rejects_invalid_proofs() {
+    let leaves = test_leaves();
+    let (root, proofs) = four_leaves_tree(leaves);
+
+    // Leaf not in the tree
+    assert!(!verify_merkle_proof(root, hash("e"), &proofs[0]));
+
+    // Proof of another leaf
+    assert!(!verify_merkle_proof(root, leaves[0], &proofs[2]));
+
+    // Truncated proof
+    assert!(!verify_merkle_proof(root, leaves[0], &proofs[0][..1]));
+
+    // Wrong sibling
+    assert!(!verify_merkle_proof(
+        root,
+        leaves[0],
+        &[leaves[2], proofs[0][1]]
+    ));
+}
+
+/// Airdrop of 600 tokens to three accounts, claimable until epoch 20 and owned by the first
+/// account
+struct TestAirdrop {
+    env: TestEnv,
+    airdrop: ComponentAddress,
+    token: ResourceAddress,
+    /// Proof of the allocation of each account
+    proofs: Vec<Vec<Hash>>,
+}
+
+/// Allocation of each test account
+const AMOUNTS: [u64; 3] = [100, 200, 300];
+
+impl TestAirdrop {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+
+        let owner = env.accounts[0];
+        let token = env
+            .test_runner
+            .create_fungible_resource(dec!(600), 18, owner);
+
+        let leaves: Vec<Hash> = env
+            .accounts
+            .iter()
+            .zip(AMOUNTS)
+            .map(|(account, amount)| leaf_hash(*account, Decimal::from(amount)))
+            .collect();
+        let node = node_hash(leaves[0], leaves[1]);
+        let root = node_hash(node, leaves[2]);
+        let proofs = vec![
+            vec![leaves[1], leaves[2]],
+            vec![leaves[0], leaves[2]],
+            vec![node],
+        ];
+
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account(owner, token, dec!(600))
+            .take_all_from_worktop(token, "funds")
+            .call_function_with_name_lookup(
+                env.package_address,
+                "MerkleAirdrop",
+                "instantiate",
+                |lookup| {
+                    (
+                        root,
+                        lookup.bucket("funds"),
+                        Some(20u64),
+                        OwnerRole::Fixed(rule!(require(env.badges[0].clone()))),
+                    )
+                },
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[0].clone()]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            airdrop: commit.new_component_addresses()[0],
+            env,
+            token,
+            proofs,
+        }
+    }
+
+    /// Execute the manifest with the owner key, then deposit the worktop into the owner account
+    fn execute(&mut self, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute(0, builder)
+    }
+
+    /// Claim an allocation without any signature, since anyone can submit a claim
+    fn claim(&mut self, index: usize, amount: Decimal, proof: Vec<Hash>) -> TransactionReceipt {
+        let manifest = ManifestBuilder::new()
+            .call_method(
+                self.airdrop,
+                "claim",
+                manifest_args!(self.env.accounts[index], amount, proof),
+            )
+            .build();
+
+        self.env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![])
+    }
+
+    fn claim_allocation(&mut self, index: usize) -> TransactionReceipt {
+        let proof = self.proofs[index].clone();
+
+        self.claim(index, Decimal::from(AMOUNTS[index]), proof)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str, args: impl ResolvableArguments) -> T {
+        let builder = ManifestBuilder::new().call_method(self.airdrop, method, args);
+
+        self.execute(builder).expect_commit_success().output(0)
+    }
+
+    fn balance(&mut self, index: usize) -> Decimal {
+        self.env.balance(index, self.token)
+    }
+}
+
+#[test]
+fn test_claim_deposits_the_allocation() {
+    let mut airdrop = TestAirdrop::new();
+    let account = airdrop.env.accounts[1];
+
+    airdrop.claim_allocation(1).expect_commit_success();
+
+    assert_eq!(airdrop.balance(1), dec!(200));
+    assert!(airdrop.get::<bool>("is_claimed", manifest_args!(account)));
+    assert_eq!(
+        airdrop.get::<Decimal>("get_remaining_amount", manifest_args!()),
+        dec!(400)
+    );
+}
+
+#[test]
+fn test_allocation_can_only_be_claimed_once() {
+    let mut airdrop = TestAirdrop::new();
+
+    airdrop.claim_allocation(2).expect_commit_success();
+    airdrop.claim_allocation(2).expect_commit_failure();
+}
+
+#[test]
+fn test_claim_with_an_invalid_proof_fails() {
+    let mut airdrop = TestAirdrop::new();
+    let proof = airdrop.proofs[1].clone();
+
+    // Wrong amount
+    airdrop
+        .claim(1, dec!(300), proof.clone())
+        .expect_commit_failure();
+
+    // Proof of another account
+    airdrop.claim(2, dec!(200), proof).expect_commit_failure();
+}
+
+#[test]
+fn test_claim_after_the_deadline_fails() {
+    let mut airdrop = TestAirdrop::new();
+
+    airdrop.env.set_epoch(20);
+    airdrop.claim_allocation(0).expect_commit_success();
+
+    airdrop.env.set_epoch(21);
+    airdrop.claim_allocation(1).expect_commit_failure();
+}
+
+#[test]
+fn test_withdraw_unclaimed_after_the_deadline() {
+    let mut airdrop = TestAirdrop::new();
+    let airdrop_address = airdrop.airdrop;
+
+    airdrop.claim_allocation(1).expect_commit_success();
+
+    let builder =
+        ManifestBuilder::new().call_method(airdrop_address, "withdraw_unclaimed", manifest_args!());
+    airdrop.execute(builder).expect_commit_failure();
+
+    airdrop.env.set_epoch(21);
+
+    // Only the owner can withdraw
+    let manifest = ManifestBuilder::new()
+        .call_method(airdrop_address, "withdraw_unclaimed", manifest_args!())
+        .build();
+    airdrop
+        .env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![])
+        .expect_commit_failure();
+
+    let builder =
+        ManifestBuilder::new().call_method(airdrop_address, "withdraw_unclaimed", manifest_args!());
+    airdrop.execute(builder).expect_commit_success();
+    assert_eq!(airdrop.balance(0), dec!(400));
+}
@@ -0,0 +1,405 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+
+pub use blueprint_commons::assert_fungible_res_address;
+
+// Same protected withdraw and deposit types as the single resource pool
+pub use asset_pool_interface::{DepositType, WithdrawType};
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RepaymentMismatch {
+    pub res_address: ResourceAddress,
+    pub external_liquidity_amount: Decimal,
+    pub repaid_amount: Decimal,
+}
+
+#[blueprint]
+#[events(RepaymentMismatch)]
+pub mod multi_pool {
+
+    enable_method_auth! {
+        roles {
+            admin => updatable_by: [];
+        },
+        methods {
+
+            protected_deposit => restrict_to :[admin];
+            protected_withdraw => restrict_to :[admin];
+
+            decrease_external_liquidity => restrict_to :[admin];
+            increase_external_liquidity => restrict_to :[admin];
+
+            contribute => restrict_to :[admin];
+            redeem  => restrict_to :[admin];
+
+            get_pool_unit_supply => PUBLIC;
+            get_pooled_amounts => PUBLIC;
+            get_redemption_value => PUBLIC;
+
+        }
+    }
+
+    pub struct MultiAssetPool {
+        /// Vaults containing the pooled tokens
+        liquidity: IndexMap<ResourceAddress, Vault>,
+
+        /// Amounts taken from the pool and not yet returned, per resource
+        external_liquidity_amounts: IndexMap<ResourceAddress, Decimal>,
+
+        /// Pool unit fungible resource manager
+        pool_unit_res_manager: ResourceManager,
+    }
+
+    impl MultiAssetPool {
+        pub fn instantiate_locally(
+            pool_res_addresses: Vec<ResourceAddress>,
+            owner_role: OwnerRole,
+            component_rule: AccessRule,
+        ) -> (Owned<MultiAssetPool>, ResourceAddress) {
+            /* CHECK INPUTS */
+            assert!(
+                pool_res_addresses.len() >= 2,
+                "Pool must contain at least two resources"
+            );
+
+            let mut liquidity: IndexMap<ResourceAddress, Vault> = IndexMap::default();
+            let mut external_liquidity_amounts: IndexMap<ResourceAddress, Decimal> =
+                IndexMap::default();
+
+            for res_address in pool_res_addresses {
+                assert_fungible_res_address(res_address, None);
+                assert!(
+                    !liquidity.contains_key(&res_address),
+                    "Duplicate pool resource"
+                );
+
+                liquidity.insert(res_address, Vault::new(res_address));
+                external_liquidity_amounts.insert(res_address, 0.into());
+            }
+
+            let pool_unit_res_manager = ResourceBuilder::new_fungible(owner_role)
+                .mint_roles(mint_roles! {
+                    minter => component_rule.clone();
+                    minter_updater => rule!(deny_all);
+                })
+                .burn_roles(burn_roles! {
+                    burner => component_rule;
+                    burner_updater => rule!(deny_all);
+                })
+                .create_with_no_initial_supply();
+
+            let pool_component = Self {
+                liquidity,
+                external_liquidity_amounts,
+                pool_unit_res_manager,
+            }
+            .instantiate();
+
+            (pool_component, pool_unit_res_manager.address())
+        }
+
+        pub fn instantiate(
+            pool_res_addresses: Vec<ResourceAddress>,
+            owner_role: OwnerRole,
+            admin_rule: AccessRule,
+        ) -> (Global<MultiAssetPool>, ResourceAddress) {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(MultiAssetPool::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let (owned_pool_component, pool_unit_res_address) = MultiAssetPool::instantiate_locally(
+                pool_res_addresses,
+                owner_role.clone(),
+                component_rule,
+            );
+
+            let pool_component = owned_pool_component
+                .prepare_to_globalize(owner_role)
+                .roles(roles!(
+                    admin => admin_rule;
+                ))
+                .with_address(address_reservation)
+                .globalize();
+
+            (pool_component, pool_unit_res_address)
+        }
+
+        pub fn get_pool_unit_supply(&self) -> Decimal {
+            self.pool_unit_res_manager.total_supply().unwrap_or(dec!(0))
+        }
+
+        /// Amount in the pool and amount used outside of the pool, per resource
+        pub fn get_pooled_amounts(&self) -> IndexMap<ResourceAddress, (Decimal, Decimal)> {
+            self.liquidity
+                .iter()
+                .map(|(res_address, vault)| {
+                    (
+                        *res_address,
+                        (vault.amount(), self.external_liquidity_amounts[res_address]),
+                    )
+                })
+                .collect()
+        }
+
+        /// Amount of each resource a given amount of pool units can be redeemed for
+        pub fn get_redemption_value(&self, units: Decimal) -> IndexMap<ResourceAddress, Decimal> {
+            let total_supply = self.get_pool_unit_supply();
+
+            assert!(
+                units >= 0.into() && units <= total_supply && total_supply > 0.into(),
+                "Invalid pool unit amount"
+            );
+
+            let share = PreciseDecimal::from(units) / PreciseDecimal::from(total_supply);
+
+            self.liquidity
+                .keys()
+                .map(|res_address| {
+                    let amount = (self._get_total_amount(res_address) * share) //
+                        .checked_truncate(RoundingMode::ToZero)
+                        .unwrap();
+
+                    (*res_address, amount)
+                })
+                .collect()
+        }
+
+        // Handle request to increase liquidity.
+        // Add liquidity in proportion to the pool composition and get pool units back along
+        // with the change that could not be added
+        pub fn contribute(&mut self, assets: Vec<Bucket>) -> (Bucket, Vec<Bucket>) {
+            /* CHECK INPUT */
+            assert!(
+                assets.len() == self.liquidity.len(),
+                "All pool resources must be provided"
+            );
+
+            let mut assets: IndexMap<ResourceAddress, Bucket> = assets
+                .into_iter()
+                .map(|bucket| (bucket.resource_address(), bucket))
+                .collect();
+
+            for res_address in self.liquidity.keys() {
+                assert!(
+                    assets.contains_key(res_address),
+                    "Pool resource address mismatch"
+                );
+            }
+
+            let total_supply = self.get_pool_unit_supply();
+
+            let unit_amount = if total_supply == 0.into() {
+                // The first contribution sets the pool composition. Pool units are minted 1:1
+                // with the first pool resource
+                let first_res_address = *self.liquidity.keys().next().unwrap();
+                let unit_amount = assets[&first_res_address].amount();
+
+                for (res_address, bucket) in assets.iter_mut() {
+                    assert!(
+                        bucket.amount() > 0.into(),
+                        "Initial contribution must include every pool resource"
+                    );
+
+                    let amount = bucket.amount();
+                    self.liquidity
+                        .get_mut(res_address)
+                        .unwrap()
+                        .put(bucket.take(amount));
+                }
+
+                unit_amount
+            } else {
+                // Contribution share is capped by the scarcest provided resource
+                let share = self
+                    .liquidity
+                    .keys()
+                    .filter(|res_address| self._get_total_amount(res_address) > 0.into())
+                    .map(|res_address| {
+                        PreciseDecimal::from(assets[res_address].amount())
+                            / PreciseDecimal::from(self._get_total_amount(res_address))
+                    })
+                    .min()
+                    .expect("Pool has no liquidity");
+
+                let res_addresses: Vec<ResourceAddress> = self.liquidity.keys().cloned().collect();
+
+                for res_address in res_addresses {
+                    let amount =
+                        (self._get_total_amount(&res_address) * share) //
+                            .checked_truncate(RoundingMode::AwayFromZero)
+                            .unwrap()
+                            .min(assets[&res_address].amount());
+
+                    let bucket = assets.get_mut(&res_address).unwrap().take_advanced(
+                        amount,
+                        WithdrawStrategy::Rounded(RoundingMode::AwayFromZero),
+                    );
+                    self.liquidity.get_mut(&res_address).unwrap().put(bucket);
+                }
+
+                (total_supply * share) //
+                    .checked_truncate(RoundingMode::ToZero)
+                    .unwrap()
+            };
+
+            let pool_units = self.pool_unit_res_manager.mint(unit_amount);
+
+            let change = assets.into_iter().map(|(_, bucket)| bucket).collect();
+
+            (pool_units, change)
+        }
+
+        // Handle request to decrease liquidity.
+        // Remove each resource in proportion to the redeemed pool units
+        pub fn redeem(&mut self, pool_units: Bucket) -> Vec<Bucket> {
+            /* INPUT CHECK */
+            assert!(
+                pool_units.resource_address() == self.pool_unit_res_manager.address(),
+                "Pool unit resource address mismatch"
+            );
+
+            let redemption_value = self.get_redemption_value(pool_units.amount());
+
+            self.pool_unit_res_manager.burn(pool_units);
+
+            redemption_value
+                .into_iter()
+                .map(|(res_address, amount)| {
+                    let vault = self.liquidity.get_mut(&res_address).unwrap();
+
+                    assert!(
+                        amount <= vault.amount(),
+                        "Not enough liquidity to withdraw this amount"
+                    );
+
+                    vault.take_advanced(amount, WithdrawStrategy::Rounded(RoundingMode::ToZero))
+                })
+                .collect()
+        }
+
+        pub fn protected_withdraw(
+            &mut self,
+            res_address: ResourceAddress,
+            amount: Decimal,
+            withdraw_type: WithdrawType,
+            withdraw_strategy: WithdrawStrategy,
+        ) -> Bucket {
+            /* INPUT CHECK */
+            assert!(amount >= 0.into(), "Withdraw amount must not be negative!");
+
+            let assets = self
+                .liquidity
+                .get_mut(&res_address)
+                .expect("Pool resource address mismatch")
+                .take_advanced(amount, withdraw_strategy);
+
+            if withdraw_type == WithdrawType::ForTemporaryUse {
+                *self
+                    .external_liquidity_amounts
+                    .get_mut(&res_address)
+                    .unwrap() += amount;
+            }
+
+            assets
+        }
+
+        pub fn protected_deposit(&mut self, assets: Bucket, deposit_type: DepositType) {
+            let res_address = assets.resource_address();
+            let amount = assets.amount();
+
+            self.liquidity
+                .get_mut(&res_address)
+                .expect("Pool resource address mismatch")
+                .put(assets);
+
+            if deposit_type == DepositType::FromTemporaryUse {
+                let external_liquidity_amount = self
+                    .external_liquidity_amounts
+                    .get_mut(&res_address)
+                    .unwrap();
+
+                if amount > *external_liquidity_amount {
+                    // More than what was taken is returned: the excess is recorded as yield
+                    Runtime::emit_event(RepaymentMismatch {
+                        res_address,
+                        external_liquidity_amount: *external_liquidity_amount,
+                        repaid_amount: amount,
+                    });
+
+                    *external_liquidity_amount = 0.into();
+                } else {
+                    *external_liquidity_amount -= amount;
+                }
+            }
+        }
+
+        pub fn increase_external_liquidity(
+            &mut self,
+            res_address: ResourceAddress,
+            amount: Decimal,
+        ) {
+            assert!(
+                amount >= 0.into(),
+                "External liquidity amount must not be negative!"
+            );
+
+            *self
+                .external_liquidity_amounts
+                .get_mut(&res_address)
+                .expect("Pool resource address mismatch") += amount;
+        }
+
+        pub fn decrease_external_liquidity(
+            &mut self,
+            res_address: ResourceAddress,
+            amount: Decimal,
+        ) {
+            /* INPUT CHECK */
+            assert!(
+                amount >= 0.into(),
+                "External liquidity amount must not be negative!"
+            );
+
+            let external_liquidity_amount = self
+                .external_liquidity_amounts
+                .get_mut(&res_address)
+                .expect("Pool resource address mismatch");
+
+            assert!(
+                amount <= *external_liquidity_amount,
+                "Provided amount is greater than the external liquidity amount!"
+            );
+
+            *external_liquidity_amount -= amount;
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _get_total_amount(&self, res_address: &ResourceAddress) -> Decimal {
+            self.liquidity[res_address].amount() + self.external_liquidity_amounts[res_address]
+        }
+    }
+}
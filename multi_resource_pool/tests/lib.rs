@@ -0,0 +1,303 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Pool of two resources whose admin is the key of the account holding 10000 of each resource
+struct TestPool {
+    env: TestEnv,
+    account: ComponentAddress,
+    pool: ComponentAddress,
+    pool_unit: ResourceAddress,
+    res_a: ResourceAddress,
+    res_b: ResourceAddress,
+}
+
+impl TestPool {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 1);
+        let account = env.accounts[0];
+        let res_a = env
+            .test_runner
+            .create_fungible_resource(dec!(10000), 18, account);
+        let res_b = env
+            .test_runner
+            .create_fungible_resource(dec!(10000), 18, account);
+        let admin_badge = env.badges[0].clone();
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "MultiAssetPool",
+                "instantiate",
+                manifest_args!(
+                    vec![res_a, res_b],
+                    OwnerRole::None,
+                    rule!(require(admin_badge))
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            pool: commit.new_component_addresses()[0],
+            pool_unit: commit.new_resource_addresses()[0],
+            env,
+            account,
+            res_a,
+            res_b,
+        }
+    }
+
+    /// Pool holding 100 of the first resource and 200 of the second one
+    fn initialized() -> Self {
+        let mut pool = Self::new();
+
+        pool.contribute(dec!(100), dec!(200))
+            .expect_commit_success();
+
+        pool
+    }
+
+    /// Execute the manifest with the account key, then deposit the worktop into the account
+    fn execute(&mut self, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute(0, builder)
+    }
+
+    fn contribute(&mut self, amount_a: Decimal, amount_b: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, self.res_a, amount_a)
+            .withdraw_from_account(self.account, self.res_b, amount_b)
+            .take_all_from_worktop(self.res_a, "bucket_a")
+            .take_all_from_worktop(self.res_b, "bucket_b")
+            .call_method_with_name_lookup(self.pool, "contribute", |lookup| {
+                (vec![lookup.bucket("bucket_a"), lookup.bucket("bucket_b")],)
+            });
+
+        self.execute(builder)
+    }
+
+    fn redeem(&mut self, units: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, self.pool_unit, units)
+            .take_all_from_worktop(self.pool_unit, "pool_units")
+            .call_method_with_name_lookup(self.pool, "redeem", |lookup| {
+                (lookup.bucket("pool_units"),)
+            });
+
+        self.execute(builder)
+    }
+
+    fn protected_withdraw(
+        &mut self,
+        res_address: ResourceAddress,
+        amount: Decimal,
+        withdraw_type: ManifestValue,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_method(
+            self.pool,
+            "protected_withdraw",
+            manifest_args!(res_address, amount, withdraw_type, exact()),
+        );
+
+        self.execute(builder)
+    }
+
+    fn protected_deposit(
+        &mut self,
+        res_address: ResourceAddress,
+        amount: Decimal,
+        deposit_type: ManifestValue,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, res_address, amount)
+            .take_all_from_worktop(res_address, "assets")
+            .call_method_with_name_lookup(self.pool, "protected_deposit", |lookup| {
+                (lookup.bucket("assets"), deposit_type)
+            });
+
+        self.execute(builder)
+    }
+
+    fn get_pooled_amounts(&mut self) -> IndexMap<ResourceAddress, (Decimal, Decimal)> {
+        let manifest = ManifestBuilder::new()
+            .call_method(self.pool, "get_pooled_amounts", manifest_args!())
+            .build();
+
+        self.env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![])
+            .expect_commit_success()
+            .output(0)
+    }
+
+    fn balance(&mut self, res_address: ResourceAddress) -> Decimal {
+        self.env.balance(0, res_address)
+    }
+}
+
+/// Manifest encoding of `WithdrawType::ForTemporaryUse`
+fn for_temporary_use() -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 0,
+        fields: vec![],
+    }
+}
+
+/// Manifest encoding of `DepositType::FromTemporaryUse`
+fn from_temporary_use() -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 0,
+        fields: vec![],
+    }
+}
+
+/// Manifest encoding of `WithdrawStrategy::Exact`
+fn exact() -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 0,
+        fields: vec![],
+    }
+}
+
+#[test]
+fn test_first_contribution_mints_units_of_the_first_resource() {
+    let mut pool = TestPool::initialized();
+    let (res_a, res_b, pool_unit) = (pool.res_a, pool.res_b, pool.pool_unit);
+
+    assert_eq!(pool.balance(pool_unit), dec!(100));
+    assert_eq!(
+        pool.get_pooled_amounts(),
+        indexmap!(res_a => (dec!(100), dec!(0)), res_b => (dec!(200), dec!(0)))
+    );
+}
+
+#[test]
+fn test_first_contribution_without_every_resource_fails() {
+    let mut pool = TestPool::new();
+
+    pool.contribute(dec!(100), dec!(0)).expect_commit_failure();
+}
+
+#[test]
+fn test_contribute_returns_change() {
+    let mut pool = TestPool::initialized();
+    let (res_a, res_b, pool_unit) = (pool.res_a, pool.res_b, pool.pool_unit);
+
+    // The first resource is the scarcest: a tenth of the pool is added
+    pool.contribute(dec!(10), dec!(40)).expect_commit_success();
+
+    assert_eq!(pool.balance(pool_unit), dec!(110));
+    assert_eq!(pool.balance(res_a), dec!(9890));
+    assert_eq!(pool.balance(res_b), dec!(9780));
+}
+
+#[test]
+fn test_contribute_without_every_resource_fails() {
+    let mut pool = TestPool::initialized();
+    let (account, res_a) = (pool.account, pool.res_a);
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(account, res_a, dec!(10))
+        .take_all_from_worktop(res_a, "bucket_a")
+        .call_method_with_name_lookup(pool.pool, "contribute", |lookup| {
+            (vec![lookup.bucket("bucket_a")],)
+        });
+
+    pool.execute(builder).expect_commit_failure();
+}
+
+#[test]
+fn test_redeem_is_pro_rata() {
+    let mut pool = TestPool::initialized();
+    let (res_a, res_b) = (pool.res_a, pool.res_b);
+
+    pool.redeem(dec!(25)).expect_commit_success();
+
+    assert_eq!(pool.balance(res_a), dec!(9925));
+    assert_eq!(pool.balance(res_b), dec!(9850));
+}
+
+#[test]
+fn test_external_liquidity_is_part_of_the_pool_value() {
+    let mut pool = TestPool::initialized();
+    let (res_a, res_b, pool_unit) = (pool.res_a, pool.res_b, pool.pool_unit);
+
+    pool.protected_withdraw(res_a, dec!(50), for_temporary_use())
+        .expect_commit_success();
+    assert_eq!(pool.get_pooled_amounts()[&res_a], (dec!(50), dec!(50)));
+
+    // The composition still counts the 50 used outside the pool
+    pool.contribute(dec!(10), dec!(20)).expect_commit_success();
+    assert_eq!(pool.balance(pool_unit), dec!(110));
+
+    // Redeeming more than the pool holds fails until the liquidity is returned
+    pool.redeem(dec!(110)).expect_commit_failure();
+
+    pool.protected_deposit(res_a, dec!(50), from_temporary_use())
+        .expect_commit_success();
+    pool.redeem(dec!(110)).expect_commit_success();
+
+    assert_eq!(pool.balance(res_a), dec!(10000));
+    assert_eq!(pool.balance(res_b), dec!(10000));
+}
+
+#[test]
+fn test_repayment_excess_is_recorded_as_yield() {
+    let mut pool = TestPool::initialized();
+    let res_a = pool.res_a;
+
+    pool.protected_withdraw(res_a, dec!(10), for_temporary_use())
+        .expect_commit_success();
+    pool.protected_deposit(res_a, dec!(20), from_temporary_use())
+        .expect_commit_success();
+    assert_eq!(pool.get_pooled_amounts()[&res_a], (dec!(110), dec!(0)));
+
+    // The excess goes to the pool unit holders
+    pool.redeem(dec!(100)).expect_commit_success();
+    assert_eq!(pool.balance(res_a), dec!(10010));
+}
+
+#[test]
+fn test_admin_methods_require_the_admin_role() {
+    let mut pool = TestPool::initialized();
+    let (pool_address, res_a) = (pool.pool, pool.res_a);
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            pool_address,
+            "increase_external_liquidity",
+            manifest_args!(res_a, dec!(10)),
+        )
+        .build();
+
+    pool.env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![])
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_instantiate_with_duplicate_resources_fails() {
+    let mut env = TestEnv::new(this_package!(), 1);
+    let res_a = env
+        .test_runner
+        .create_fungible_resource(dec!(100), 18, env.accounts[0]);
+
+    let manifest = ManifestBuilder::new()
+        .call_function(
+            env.package_address,
+            "MultiAssetPool",
+            "instantiate",
+            manifest_args!(vec![res_a, res_a], OwnerRole::None, rule!(allow_all)),
+        )
+        .build();
+
+    env.test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![])
+        .expect_commit_failure();
+}
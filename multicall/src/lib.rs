@@ -0,0 +1,59 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+
+/// Maximum number of calls in a single batch
+pub const MAX_CALLS: usize = 100;
+
+#[blueprint]
+pub mod multicall {
+
+    /// Stateless blueprint, only used through its functions
+    pub struct Multicall {}
+
+    impl Multicall {
+        /// Call each component method with its arguments and return the SBOR encoded outputs in
+        /// order, as outputs of different kinds cannot be held by the same array. Arguments are
+        /// the tuple of the method arguments. Intended for read-only calls in transaction
+        /// previews, e.g. by wallets displaying the state of many components at once: a method
+        /// returning buckets makes the transaction fail.
+        pub fn aggregate(calls: Vec<(ComponentAddress, String, ScryptoValue)>) -> Vec<Vec<u8>> {
+            /* CHECK INPUT */
+            assert!(!calls.is_empty(), "No call to aggregate");
+            assert!(calls.len() <= MAX_CALLS, "Too many calls");
+
+            calls
+                .into_iter()
+                .map(|(component_address, method_name, args)| {
+                    let component: Global<AnyComponent> = Global::from(component_address);
+
+                    let output = component
+                        .call_raw::<ScryptoValue>(&method_name, scrypto_encode(&args).unwrap());
+
+                    scrypto_encode(&output).unwrap()
+                })
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// A call of the batch, with the manifest encoding of the tuple of the method arguments
+type Call = (ComponentAddress, String, ManifestValue);
+
+fn call(component: ComponentAddress, method: &str, args: Vec<ManifestValue>) -> Call {
+    (
+        component,
+        method.to_string(),
+        ManifestValue::Tuple { fields: args },
+    )
+}
+
+/// Published Multicall package, and two readers of the values 1 and 2
+struct TestMulticall {
+    env: TestEnv,
+    readers: Vec<ComponentAddress>,
+}
+
+impl TestMulticall {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 0);
+        let reader_package_address = env
+            .test_runner
+            .compile_and_publish(format!("{}/tests/reader", env!("CARGO_MANIFEST_DIR")));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                reader_package_address,
+                "Reader",
+                "instantiate",
+                manifest_args!(1u64),
+            )
+            .call_function(
+                reader_package_address,
+                "Reader",
+                "instantiate",
+                manifest_args!(2u64),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let readers = receipt.expect_commit_success().new_component_addresses()[..2].to_vec();
+
+        Self { env, readers }
+    }
+
+    fn aggregate(&mut self, calls: Vec<Call>) -> TransactionReceipt {
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                self.env.package_address,
+                "Multicall",
+                "aggregate",
+                manifest_args!(calls),
+            )
+            .build();
+
+        self.env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![])
+    }
+}
+
+#[test]
+fn test_outputs_are_returned_in_order() {
+    let mut multicall = TestMulticall::new();
+    let (reader_1, reader_2) = (multicall.readers[0], multicall.readers[1]);
+
+    let outputs: Vec<Vec<u8>> = multicall
+        .aggregate(vec![
+            call(reader_2, "get_value", vec![]),
+            call(
+                reader_1,
+                "get_sum",
+                vec![to_manifest_value_and_unwrap!(&5u64)],
+            ),
+            call(reader_1, "get_name", vec![]),
+        ])
+        .expect_commit_success()
+        .output(0);
+
+    assert_eq!(outputs.len(), 3);
+    assert_eq!(scrypto_decode::<u64>(&outputs[0]).unwrap(), 2);
+    assert_eq!(scrypto_decode::<u64>(&outputs[1]).unwrap(), 6);
+    assert_eq!(
+        scrypto_decode::<String>(&outputs[2]).unwrap(),
+        "Reader 1".to_string()
+    );
+}
+
+#[test]
+fn test_invalid_batches_are_rejected() {
+    let mut multicall = TestMulticall::new();
+    let reader = multicall.readers[0];
+
+    multicall.aggregate(vec![]).expect_commit_failure();
+    multicall
+        .aggregate(vec![call(reader, "get_value", vec![]); 101])
+        .expect_commit_failure();
+    multicall
+        .aggregate(vec![call(reader, "get_value", vec![]); 100])
+        .expect_commit_success();
+
+    multicall
+        .aggregate(vec![call(reader, "get_balance", vec![])])
+        .expect_commit_failure();
+    multicall
+        .aggregate(vec![call(reader, "get_sum", vec![])])
+        .expect_commit_failure();
+
+    // The bucket returned by the method cannot be deposited
+    multicall
+        .aggregate(vec![
+            call(reader, "get_value", vec![]),
+            call(reader, "take_token", vec![]),
+        ])
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_full_batches_keep_the_call_order() {
+    let mut multicall = TestMulticall::new();
+    let readers = multicall.readers.clone();
+
+    let calls: Vec<Call> = (0..100)
+        .map(|index| call(readers[index % 2], "get_value", vec![]))
+        .collect();
+    let outputs: Vec<Vec<u8>> = multicall.aggregate(calls).expect_commit_success().output(0);
+
+    assert_eq!(outputs.len(), 100);
+    for (index, output) in outputs.iter().enumerate() {
+        assert_eq!(
+            scrypto_decode::<u64>(output).unwrap(),
+            (index % 2) as u64 + 1
+        );
+    }
+}
+
+#[test]
+fn test_failures_carry_multicall_messages() {
+    let mut multicall = TestMulticall::new();
+    let reader = multicall.readers[0];
+
+    expect_failure_containing(multicall.aggregate(vec![]), "No call to aggregate");
+    expect_failure_containing(
+        multicall.aggregate(vec![call(reader, "get_value", vec![]); 101]),
+        "Too many calls",
+    );
+
+    // Arguments must match the method signature, and a failed call fails the whole batch
+    multicall
+        .aggregate(vec![
+            call(reader, "get_value", vec![]),
+            call(
+                reader,
+                "get_sum",
+                vec![to_manifest_value_and_unwrap!(&"5".to_string())],
+            ),
+        ])
+        .expect_commit_failure();
+    multicall
+        .aggregate(vec![call(
+            reader,
+            "get_sum",
+            vec![
+                to_manifest_value_and_unwrap!(&5u64),
+                to_manifest_value_and_unwrap!(&5u64),
+            ],
+        )])
+        .expect_commit_failure();
+}
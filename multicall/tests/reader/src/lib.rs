@@ -0,0 +1,41 @@
+use scrypto::prelude::*;
+
+#[blueprint]
+mod reader {
+    struct Reader {
+        value: u64,
+
+        tokens: Vault,
+    }
+
+    impl Reader {
+        pub fn instantiate(value: u64) -> Global<Reader> {
+            let tokens = ResourceBuilder::new_fungible(OwnerRole::None).mint_initial_supply(100);
+
+            Self {
+                value,
+                tokens: Vault::with_bucket(tokens.into()),
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .globalize()
+        }
+
+        pub fn get_value(&self) -> u64 {
+            self.value
+        }
+
+        pub fn get_sum(&self, amount: u64) -> u64 {
+            self.value + amount
+        }
+
+        pub fn get_name(&self) -> String {
+            format!("Reader {}", self.value)
+        }
+
+        /// Not a getter, the returned bucket must be deposited by the caller
+        pub fn take_token(&mut self) -> Bucket {
+            self.tokens.take(1)
+        }
+    }
+}
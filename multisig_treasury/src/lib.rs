@@ -0,0 +1,379 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct MemberBadge {
+    pub joined_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub enum ProposalAction {
+    /// Send treasury funds to an account
+    Withdraw {
+        res_address: ResourceAddress,
+        amount: Decimal,
+        recipient: ComponentAddress,
+    },
+    /// Call a method of any component as the treasury. `args` are the SBOR encoded arguments
+    /// of the method, e.g. built with `scrypto_args!`. The method must not return buckets.
+    CallMethod {
+        component_address: ComponentAddress,
+        method_name: String,
+        args: Vec<u8>,
+    },
+    /// Mint a member badge to an account
+    AddMember {
+        account: ComponentAddress,
+    },
+    RemoveMember {
+        member_id: NonFungibleLocalId,
+    },
+    ChangeThreshold {
+        threshold: u64,
+    },
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub struct Proposal {
+    pub action: ProposalAction,
+    pub proposer: NonFungibleLocalId,
+    pub approvals: IndexSet<NonFungibleLocalId>,
+    /// Last epoch at which the proposal can be approved or executed
+    pub expiry_epoch: u64,
+    pub executed: bool,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ProposalCreatedEvent {
+    pub proposal_id: u64,
+    pub action: ProposalAction,
+    pub expiry_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ProposalExecutedEvent {
+    pub proposal_id: u64,
+}
+
+#[blueprint]
+#[events(ProposalCreatedEvent, ProposalExecutedEvent)]
+pub mod multisig_treasury {
+
+    enable_method_auth! {
+        methods {
+
+            deposit => PUBLIC;
+
+            propose => PUBLIC;
+            approve => PUBLIC;
+            execute => PUBLIC;
+
+            get_proposal => PUBLIC;
+            get_members => PUBLIC;
+            get_threshold => PUBLIC;
+            get_balance => PUBLIC;
+
+        }
+    }
+
+    pub struct MultisigTreasury {
+        vaults: KeyValueStore<ResourceAddress, Vault>,
+
+        /// Member badge non-fungible resource manager
+        member_res_manager: ResourceManager,
+
+        /// Ids of the badges of the current members
+        members: IndexSet<NonFungibleLocalId>,
+        next_member_id: u64,
+
+        /// Number of approvals of current members required to execute a proposal
+        threshold: u64,
+
+        proposals: KeyValueStore<u64, Proposal>,
+        next_proposal_id: u64,
+
+        /// Number of epochs a proposal stays open
+        proposal_lifetime_epochs: u64,
+    }
+
+    impl MultisigTreasury {
+        /// Instantiate a treasury with `member_count` members. Returns the member badges, to
+        /// be sent to the members.
+        pub fn instantiate(
+            member_count: u64,
+            threshold: u64,
+            proposal_lifetime_epochs: u64,
+        ) -> (Global<MultisigTreasury>, Bucket) {
+            /* CHECK INPUT */
+            assert!(
+                threshold > 0 && threshold <= member_count,
+                "Threshold must be between 1 and the number of members"
+            );
+            assert!(
+                proposal_lifetime_epochs > 0,
+                "Proposal lifetime must be greater than zero"
+            );
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(MultisigTreasury::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+            let joined_at_epoch = Runtime::current_epoch().number();
+
+            let member_badges = ResourceBuilder::new_integer_non_fungible::<MemberBadge>(
+                OwnerRole::Fixed(component_rule.clone()),
+            )
+            .mint_roles(mint_roles! {
+                minter => component_rule;
+                minter_updater => rule!(deny_all);
+            })
+            .mint_initial_supply((1..=member_count).map(|id| {
+                (
+                    IntegerNonFungibleLocalId::new(id),
+                    MemberBadge { joined_at_epoch },
+                )
+            }));
+
+            let members = (1..=member_count)
+                .map(NonFungibleLocalId::integer)
+                .collect();
+
+            let treasury_component = Self {
+                vaults: KeyValueStore::new(),
+                member_res_manager: member_badges.resource_manager(),
+                members,
+                next_member_id: member_count + 1,
+                threshold,
+                proposals: KeyValueStore::new(),
+                next_proposal_id: 1,
+                proposal_lifetime_epochs,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::Fixed(rule!(require(global_caller(
+                component_address
+            )))))
+            .with_address(address_reservation)
+            .globalize();
+
+            (treasury_component, member_badges.into())
+        }
+
+        /// Deposit funds in the treasury. Anyone can deposit.
+        pub fn deposit(&mut self, funds: Bucket) {
+            let res_address = funds.resource_address();
+            let vault_exists = self.vaults.get(&res_address).is_some();
+
+            if vault_exists {
+                self.vaults.get_mut(&res_address).unwrap().put(funds);
+            } else {
+                self.vaults.insert(res_address, Vault::with_bucket(funds));
+            }
+        }
+
+        /* MEMBER METHODS */
+
+        /// Propose an action, approved by the proposer. Returns the proposal id.
+        pub fn propose(&mut self, member_proof: Proof, action: ProposalAction) -> u64 {
+            let member_id = self._check_member_proof(member_proof);
+
+            if let ProposalAction::ChangeThreshold { threshold } = action {
+                assert!(threshold > 0, "Threshold must be greater than zero");
+            }
+
+            let proposal_id = self.next_proposal_id;
+            self.next_proposal_id += 1;
+
+            let expiry_epoch = Runtime::current_epoch().number() + self.proposal_lifetime_epochs;
+
+            let mut approvals = IndexSet::new();
+            approvals.insert(member_id.clone());
+
+            self.proposals.insert(
+                proposal_id,
+                Proposal {
+                    action: action.clone(),
+                    proposer: member_id,
+                    approvals,
+                    expiry_epoch,
+                    executed: false,
+                },
+            );
+
+            Runtime::emit_event(ProposalCreatedEvent {
+                proposal_id,
+                action,
+                expiry_epoch,
+            });
+
+            proposal_id
+        }
+
+        pub fn approve(&mut self, member_proof: Proof, proposal_id: u64) {
+            let member_id = self._check_member_proof(member_proof);
+
+            let mut proposal = self._get_open_proposal(proposal_id);
+
+            assert!(
+                proposal.approvals.insert(member_id),
+                "Proposal already approved by this member"
+            );
+        }
+
+        /// Execute a proposal once it has been approved by enough current members. Any member
+        /// can execute it.
+        pub fn execute(&mut self, member_proof: Proof, proposal_id: u64) {
+            self._check_member_proof(member_proof);
+
+            let (approvals, action) = {
+                let proposal = self._get_open_proposal(proposal_id);
+
+                (proposal.approvals.clone(), proposal.action.clone())
+            };
+
+            let approval_count = approvals
+                .iter()
+                .filter(|member_id| self.members.contains(*member_id))
+                .count() as u64;
+
+            assert!(
+                approval_count >= self.threshold,
+                "Proposal has {} approvals out of the {} required",
+                approval_count,
+                self.threshold
+            );
+
+            self.proposals.get_mut(&proposal_id).unwrap().executed = true;
+
+            match action {
+                ProposalAction::Withdraw {
+                    res_address,
+                    amount,
+                    recipient,
+                } => {
+                    let funds = self
+                        .vaults
+                        .get_mut(&res_address)
+                        .expect("No funds for this resource")
+                        .take(amount);
+
+                    let mut account: Global<Account> = Global::from(recipient);
+                    account.try_deposit_or_abort(funds, None);
+                }
+                ProposalAction::CallMethod {
+                    component_address,
+                    method_name,
+                    args,
+                } => {
+                    let component: Global<AnyComponent> = Global::from(component_address);
+                    component.call_raw::<ScryptoValue>(&method_name, args);
+                }
+                ProposalAction::AddMember { account } => {
+                    let member_id = NonFungibleLocalId::integer(self.next_member_id);
+                    self.next_member_id += 1;
+
+                    let badge = self.member_res_manager.mint_non_fungible(
+                        &member_id,
+                        MemberBadge {
+                            joined_at_epoch: Runtime::current_epoch().number(),
+                        },
+                    );
+                    self.members.insert(member_id);
+
+                    let mut account: Global<Account> = Global::from(account);
+                    account.try_deposit_or_abort(badge, None);
+                }
+                ProposalAction::RemoveMember { member_id } => {
+                    assert!(self.members.remove(&member_id), "Not a member");
+                    assert!(
+                        self.members.len() as u64 >= self.threshold,
+                        "Removing this member would make the threshold unreachable"
+                    );
+                }
+                ProposalAction::ChangeThreshold { threshold } => {
+                    assert!(
+                        threshold <= self.members.len() as u64,
+                        "Threshold must not exceed the number of members"
+                    );
+
+                    self.threshold = threshold;
+                }
+            }
+
+            Runtime::emit_event(ProposalExecutedEvent { proposal_id });
+        }
+
+        /* GETTERS */
+
+        pub fn get_proposal(&self, proposal_id: u64) -> Proposal {
+            self.proposals
+                .get(&proposal_id)
+                .expect("Proposal not found")
+                .clone()
+        }
+
+        pub fn get_members(&self) -> IndexSet<NonFungibleLocalId> {
+            self.members.clone()
+        }
+
+        pub fn get_threshold(&self) -> u64 {
+            self.threshold
+        }
+
+        pub fn get_balance(&self, res_address: ResourceAddress) -> Decimal {
+            self.vaults
+                .get(&res_address)
+                .map(|vault| vault.amount())
+                .unwrap_or(Decimal::ZERO)
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _check_member_proof(&self, member_proof: Proof) -> NonFungibleLocalId {
+            let member_id = member_proof
+                .check(self.member_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id();
+
+            assert!(self.members.contains(&member_id), "Not a member");
+
+            member_id
+        }
+
+        fn _get_open_proposal(&mut self, proposal_id: u64) -> KeyValueEntryRefMut<Proposal> {
+            let proposal = self
+                .proposals
+                .get_mut(&proposal_id)
+                .expect("Proposal not found");
+
+            assert!(!proposal.executed, "Proposal already executed");
+            assert!(
+                Runtime::current_epoch().number() <= proposal.expiry_epoch,
+                "Proposal has expired"
+            );
+
+            proposal
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Members holding the member badges 1, 2 and 3. The first one also holds 1000 tokens.
+const ALICE: usize = 0;
+const BOB: usize = 1;
+const CAROL: usize = 2;
+/// Account without a member badge
+const OUTSIDER: usize = 3;
+
+/// Treasury of three members with a threshold of 2 and a proposal lifetime of 10 epochs,
+/// instantiated at epoch 10 and holding 500 tokens
+struct TestTreasury {
+    env: TestEnv,
+    treasury: ComponentAddress,
+    member_badge: ResourceAddress,
+    token: ResourceAddress,
+}
+
+impl TestTreasury {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 4);
+        env.set_epoch(10);
+        let token = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, env.accounts[ALICE]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "MultisigTreasury",
+                "instantiate",
+                manifest_args!(3u64, 2u64, 10u64),
+            )
+            .deposit_batch(env.accounts[ALICE])
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+
+        let mut treasury = Self {
+            treasury: commit.new_component_addresses()[0],
+            member_badge: commit.new_resource_addresses()[0],
+            env,
+            token,
+        };
+
+        // Send the badges 2 and 3 to their members and fund the treasury
+        let mut builder = ManifestBuilder::new();
+        for member in [BOB, CAROL] {
+            let name = format!("badge_{}", member);
+            builder = builder
+                .withdraw_non_fungibles_from_account(
+                    treasury.env.accounts[ALICE],
+                    treasury.member_badge,
+                    &BTreeSet::from([NonFungibleLocalId::integer(member as u64 + 1)]),
+                )
+                .take_all_from_worktop(treasury.member_badge, name.as_str())
+                .call_method_with_name_lookup(treasury.env.accounts[member], "deposit", |lookup| {
+                    (lookup.bucket(name.as_str()),)
+                });
+        }
+        let builder = builder
+            .withdraw_from_account(treasury.env.accounts[ALICE], token, dec!(500))
+            .take_all_from_worktop(token, "funds")
+            .call_method_with_name_lookup(treasury.treasury, "deposit", |lookup| {
+                (lookup.bucket("funds"),)
+            });
+        treasury.execute(ALICE, builder).expect_commit_success();
+
+        treasury
+    }
+
+    /// Execute the manifest with the keys of every account, then deposit the worktop into the
+    /// given account
+    fn execute(&mut self, account: usize, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute_with_all_keys(account, builder)
+    }
+
+    /// Proof of the member badge of the account, popped as "member"
+    fn with_member_proof(&self, member: usize) -> ManifestBuilder {
+        ManifestBuilder::new()
+            .create_proof_from_account_of_non_fungibles(
+                self.env.accounts[member],
+                self.member_badge,
+                &BTreeSet::from([NonFungibleLocalId::integer(member as u64 + 1)]),
+            )
+            .pop_from_auth_zone("member")
+    }
+
+    fn propose(&mut self, member: usize, action: ManifestValue) -> TransactionReceipt {
+        let builder = self.with_member_proof(member).call_method_with_name_lookup(
+            self.treasury,
+            "propose",
+            |lookup| (lookup.proof("member"), action),
+        );
+
+        self.execute(member, builder)
+    }
+
+    /// Call `approve` or `execute` on a proposal
+    fn call(&mut self, member: usize, method: &str, proposal_id: u64) -> TransactionReceipt {
+        let builder = self.with_member_proof(member).call_method_with_name_lookup(
+            self.treasury,
+            method,
+            |lookup| (lookup.proof("member"), proposal_id),
+        );
+
+        self.execute(member, builder)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str, args: impl ResolvableArguments) -> T {
+        let builder = ManifestBuilder::new().call_method(self.treasury, method, args);
+
+        self.execute(ALICE, builder)
+            .expect_commit_success()
+            .output(0)
+    }
+
+    /// Manifest encoding of `ProposalAction::Withdraw` of tokens to the given account
+    fn withdraw_action(&self, amount: Decimal, recipient: usize) -> ManifestValue {
+        ManifestValue::Enum {
+            discriminator: 0,
+            fields: vec![
+                to_manifest_value_and_unwrap!(&self.token),
+                to_manifest_value_and_unwrap!(&amount),
+                to_manifest_value_and_unwrap!(&self.env.accounts[recipient]),
+            ],
+        }
+    }
+}
+
+/// Manifest encoding of `ProposalAction::AddMember`
+fn add_member_action(account: ComponentAddress) -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 2,
+        fields: vec![to_manifest_value_and_unwrap!(&account)],
+    }
+}
+
+/// Manifest encoding of `ProposalAction::RemoveMember`
+fn remove_member_action(member_id: u64) -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 3,
+        fields: vec![to_manifest_value_and_unwrap!(&NonFungibleLocalId::integer(
+            member_id
+        ))],
+    }
+}
+
+/// Manifest encoding of `ProposalAction::ChangeThreshold`
+fn change_threshold_action(threshold: u64) -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 4,
+        fields: vec![to_manifest_value_and_unwrap!(&threshold)],
+    }
+}
+
+#[test]
+fn test_withdraw_with_enough_approvals() {
+    let mut treasury = TestTreasury::new();
+    let token = treasury.token;
+
+    let action = treasury.withdraw_action(dec!(100), OUTSIDER);
+    treasury.propose(ALICE, action).expect_commit_success();
+
+    // The proposer approval alone is not enough
+    treasury.call(ALICE, "execute", 1).expect_commit_failure();
+    treasury.call(ALICE, "approve", 1).expect_commit_failure();
+
+    treasury.call(BOB, "approve", 1).expect_commit_success();
+    treasury.call(CAROL, "execute", 1).expect_commit_success();
+
+    assert_eq!(treasury.env.balance(OUTSIDER, token), dec!(100));
+    assert_eq!(
+        treasury.get::<Decimal>("get_balance", manifest_args!(token)),
+        dec!(400)
+    );
+
+    treasury.call(CAROL, "execute", 1).expect_commit_failure();
+}
+
+#[test]
+fn test_non_member_can_not_propose() {
+    let mut treasury = TestTreasury::new();
+    let (outsider, token, treasury_address) = (
+        treasury.env.accounts[OUTSIDER],
+        treasury.token,
+        treasury.treasury,
+    );
+
+    // Give some tokens to the outsider to build a proof of another resource
+    let action = treasury.withdraw_action(dec!(100), OUTSIDER);
+    treasury.propose(ALICE, action).expect_commit_success();
+    treasury.call(BOB, "approve", 1).expect_commit_success();
+    treasury.call(BOB, "execute", 1).expect_commit_success();
+
+    let action = treasury.withdraw_action(dec!(100), OUTSIDER);
+    let builder = ManifestBuilder::new()
+        .create_proof_from_account_of_amount(outsider, token, dec!(1))
+        .pop_from_auth_zone("member")
+        .call_method_with_name_lookup(treasury_address, "propose", |lookup| {
+            (lookup.proof("member"), action)
+        });
+    treasury.execute(OUTSIDER, builder).expect_commit_failure();
+}
+
+#[test]
+fn test_expired_proposal_can_not_be_approved() {
+    let mut treasury = TestTreasury::new();
+
+    let action = treasury.withdraw_action(dec!(100), OUTSIDER);
+    treasury.propose(ALICE, action).expect_commit_success();
+
+    treasury.env.set_epoch(21);
+    treasury.call(BOB, "approve", 1).expect_commit_failure();
+}
+
+#[test]
+fn test_add_member() {
+    let mut treasury = TestTreasury::new();
+    let (outsider, member_badge) = (treasury.env.accounts[OUTSIDER], treasury.member_badge);
+
+    treasury
+        .propose(ALICE, add_member_action(outsider))
+        .expect_commit_success();
+    treasury.call(CAROL, "approve", 1).expect_commit_success();
+    treasury.call(CAROL, "execute", 1).expect_commit_success();
+
+    assert_eq!(treasury.env.balance(OUTSIDER, member_badge), dec!(1));
+    assert_eq!(
+        treasury
+            .get::<IndexSet<NonFungibleLocalId>>("get_members", manifest_args!())
+            .len(),
+        4
+    );
+
+    // The new member can approve proposals
+    treasury
+        .propose(ALICE, change_threshold_action(3))
+        .expect_commit_success();
+    treasury
+        .call(OUTSIDER, "approve", 2)
+        .expect_commit_success();
+    treasury
+        .call(OUTSIDER, "execute", 2)
+        .expect_commit_success();
+    assert_eq!(treasury.get::<u64>("get_threshold", manifest_args!()), 3);
+}
+
+#[test]
+fn test_approvals_of_removed_members_do_not_count() {
+    let mut treasury = TestTreasury::new();
+
+    let action = treasury.withdraw_action(dec!(100), OUTSIDER);
+    treasury.propose(ALICE, action).expect_commit_success();
+    treasury.call(CAROL, "approve", 1).expect_commit_success();
+
+    treasury
+        .propose(ALICE, remove_member_action(3))
+        .expect_commit_success();
+    treasury.call(BOB, "approve", 2).expect_commit_success();
+    treasury.call(BOB, "execute", 2).expect_commit_success();
+
+    treasury.call(ALICE, "execute", 1).expect_commit_failure();
+    treasury.call(CAROL, "approve", 1).expect_commit_failure();
+}
+
+#[test]
+fn test_threshold_must_be_reachable() {
+    let mut treasury = TestTreasury::new();
+
+    treasury
+        .propose(ALICE, change_threshold_action(0))
+        .expect_commit_failure();
+
+    treasury
+        .propose(ALICE, change_threshold_action(4))
+        .expect_commit_success();
+    treasury.call(BOB, "approve", 1).expect_commit_success();
+    treasury.call(BOB, "execute", 1).expect_commit_failure();
+}
@@ -0,0 +1,491 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::{assert_fungible_res_address, draw, mix_entropy, reveal_seed};
+use scrypto::prelude::*;
+
+// RANDOMNESS
+//
+// Boxes are opened with the commit-reveal scheme of `blueprint_commons` (see `randomness.rs`):
+// the operator commits at instantiation, box purchases mix the entropy, and the operator
+// reveals the secret once the sale has ended. The revealed seed is stored, and each box then
+// draws its prize tier from `hash(seed || box number)`. If the secret is not revealed before
+// the reveal deadline, the sale is cancelled: boxes are refunded and the operator can reclaim
+// the prizes.
+
+/// Maximum number of prize tiers
+pub const MAX_TIERS: usize = 20;
+
+pub use blueprint_commons::commitment;
+
+/// Draw a number between 0 and `total_weight - 1` for the given box
+pub fn draw_weight(seed: Hash, box_number: u64, total_weight: u64) -> u64 {
+    let box_seed = hash([&seed.0[..], &box_number.to_le_bytes()[..]].concat());
+
+    draw(box_seed, total_weight)
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct MysteryBoxItem {
+    pub price: Decimal,
+}
+
+#[derive(ScryptoSbor)]
+pub struct PrizeTier {
+    /// Relative probability of the tier
+    pub weight: u64,
+
+    /// Prizes of the tier, fungible or non fungible
+    pub prizes: Vault,
+
+    /// Amount paid out per prize. Always one for non fungible prizes.
+    pub amount_per_prize: Decimal,
+}
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SaleState {
+    /// Boxes can be bought until the end epoch
+    Open,
+    /// The secret has been revealed, boxes can be opened
+    Revealed { seed: Hash },
+    /// The secret has not been revealed in time, boxes are refunded
+    Cancelled,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BoxesBoughtEvent {
+    pub first_box: u64,
+    pub count: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SecretRevealedEvent {
+    pub seed: Hash,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BoxOpenedEvent {
+    pub box_number: u64,
+    pub tier: usize,
+}
+
+#[blueprint]
+#[events(BoxesBoughtEvent, SecretRevealedEvent, BoxOpenedEvent)]
+pub mod mystery_box {
+
+    enable_method_auth! {
+        methods {
+
+            add_tier => restrict_to :[OWNER];
+            fund_tier => restrict_to :[OWNER];
+            reveal => restrict_to :[OWNER];
+            withdraw_proceeds => restrict_to :[OWNER];
+            reclaim_prizes => restrict_to :[OWNER];
+
+            buy_boxes => PUBLIC;
+            open_boxes => PUBLIC;
+            cancel => PUBLIC;
+            refund => PUBLIC;
+
+            get_state => PUBLIC;
+            get_boxes_sold => PUBLIC;
+            get_available_prizes => PUBLIC;
+            get_entropy => PUBLIC;
+
+        }
+    }
+
+    pub struct MysteryBox {
+        tiers: Vec<PrizeTier>,
+
+        /// Box sales
+        proceeds: Vault,
+        box_price: Decimal,
+        boxes_sold: u64,
+
+        /// Number of sold boxes not opened yet
+        unopened_boxes: u64,
+
+        /// Box non-fungible resource manager
+        box_res_manager: ResourceManager,
+
+        /// Operator commitment to the secret
+        commitment: Hash,
+
+        /// Public entropy accumulated from box purchases
+        entropy: Hash,
+
+        end_epoch: u64,
+
+        /// Last epoch at which the operator can reveal the secret
+        reveal_deadline_epoch: u64,
+
+        state: SaleState,
+    }
+
+    impl MysteryBox {
+        /// Instantiate a mystery box sale running until the end epoch. Returns the component,
+        /// the operator badge and the box resource address.
+        pub fn instantiate(
+            payment_res_address: ResourceAddress,
+            box_price: Decimal,
+            commitment: Hash,
+            end_epoch: u64,
+            reveal_period_epochs: u64,
+        ) -> (Global<MysteryBox>, Bucket, ResourceAddress) {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                payment_res_address,
+                Some("Payment resource must be fungible".to_string()),
+            );
+            assert!(
+                box_price > Decimal::ZERO,
+                "Box price must be greater than zero"
+            );
+            assert!(
+                end_epoch > Runtime::current_epoch().number(),
+                "End epoch must be in the future"
+            );
+            assert!(
+                reveal_period_epochs > 0,
+                "Reveal period must be greater than zero"
+            );
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(MysteryBox::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let operator_badge = ResourceBuilder::new_fungible(OwnerRole::None)
+                .divisibility(DIVISIBILITY_NONE)
+                .mint_initial_supply(1);
+
+            let owner_role = OwnerRole::Fixed(rule!(require(operator_badge.resource_address())));
+
+            let box_res_manager =
+                ResourceBuilder::new_integer_non_fungible::<MysteryBoxItem>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule;
+                        burner_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let mystery_box_component = Self {
+                tiers: Vec::new(),
+                proceeds: Vault::new(payment_res_address),
+                box_price,
+                boxes_sold: 0,
+                unopened_boxes: 0,
+                box_res_manager,
+                commitment,
+                entropy: commitment,
+                end_epoch,
+                reveal_deadline_epoch: end_epoch + reveal_period_epochs,
+                state: SaleState::Open,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (
+                mystery_box_component,
+                operator_badge.into(),
+                box_res_manager.address(),
+            )
+        }
+
+        /* OPERATOR METHODS */
+
+        /// Add a prize tier. Fungible prizes are paid out by `amount_per_prize`, non fungible
+        /// prizes one by one.
+        pub fn add_tier(&mut self, weight: u64, prizes: Bucket, amount_per_prize: Decimal) {
+            /* CHECK INPUT */
+            assert!(self.state == SaleState::Open, "Sale is not open");
+            assert!(self.tiers.len() < MAX_TIERS, "Too many prize tiers");
+            assert!(weight > 0, "Weight must be greater than zero");
+
+            if prizes.resource_address().is_fungible() {
+                assert!(
+                    amount_per_prize > Decimal::ZERO,
+                    "Amount per prize must be greater than zero"
+                );
+            } else {
+                assert!(
+                    amount_per_prize == Decimal::ONE,
+                    "Non fungible prizes must be paid out one by one"
+                );
+            }
+
+            self.tiers.push(PrizeTier {
+                weight,
+                prizes: Vault::with_bucket(prizes),
+                amount_per_prize,
+            });
+        }
+
+        pub fn fund_tier(&mut self, tier: usize, prizes: Bucket) {
+            assert!(self.state == SaleState::Open, "Sale is not open");
+
+            self.tiers
+                .get_mut(tier)
+                .expect("Prize tier not found")
+                .prizes
+                .put(prizes);
+        }
+
+        /// Reveal the committed secret once the sale has ended, allowing boxes to be opened
+        pub fn reveal(&mut self, secret: Vec<u8>) {
+            let current_epoch = Runtime::current_epoch().number();
+
+            assert!(self.state == SaleState::Open, "Sale is not open");
+            assert!(current_epoch > self.end_epoch, "Sale has not ended yet");
+            assert!(
+                current_epoch <= self.reveal_deadline_epoch,
+                "Reveal deadline has passed"
+            );
+            assert!(
+                commitment(&secret) == self.commitment,
+                "Secret does not match the commitment"
+            );
+
+            let seed = reveal_seed(&secret, self.entropy);
+
+            self.state = SaleState::Revealed { seed };
+
+            Runtime::emit_event(SecretRevealedEvent { seed });
+        }
+
+        /// Withdraw the box sales once the secret has been revealed
+        pub fn withdraw_proceeds(&mut self) -> Bucket {
+            assert!(
+                matches!(self.state, SaleState::Revealed { .. }),
+                "Secret has not been revealed"
+            );
+
+            self.proceeds.take_all()
+        }
+
+        /// Reclaim the prizes of a tier once the sale has been cancelled or all sold boxes
+        /// have been opened
+        pub fn reclaim_prizes(&mut self, tier: usize) -> Bucket {
+            assert!(
+                self.state == SaleState::Cancelled
+                    || (matches!(self.state, SaleState::Revealed { .. })
+                        && self.unopened_boxes == 0),
+                "Prizes cannot be reclaimed"
+            );
+
+            self.tiers
+                .get_mut(tier)
+                .expect("Prize tier not found")
+                .prizes
+                .take_all()
+        }
+
+        /* USER METHODS */
+
+        /// Buy the given number of boxes. Returns the boxes and the change.
+        pub fn buy_boxes(&mut self, mut payment: Bucket, count: u64) -> (Bucket, Bucket) {
+            /* CHECK INPUT */
+            assert!(self.state == SaleState::Open, "Sale is not open");
+            assert!(
+                Runtime::current_epoch().number() <= self.end_epoch,
+                "Sale has ended"
+            );
+            assert!(
+                payment.resource_address() == self.proceeds.resource_address(),
+                "Payment resource address mismatch"
+            );
+
+            assert!(count > 0, "Box count must be greater than zero");
+            assert!(
+                Decimal::from(self.unopened_boxes + count) <= self.get_available_prizes(),
+                "Not enough prizes left"
+            );
+
+            self.proceeds
+                .put(payment.take(self.box_price * Decimal::from(count)));
+
+            let first_box = self.boxes_sold + 1;
+            let mut boxes = Bucket::new(self.box_res_manager.address());
+
+            for box_number in first_box..first_box + count {
+                boxes.put(self.box_res_manager.mint_non_fungible(
+                    &NonFungibleLocalId::integer(box_number),
+                    MysteryBoxItem {
+                        price: self.box_price,
+                    },
+                ));
+            }
+
+            self.boxes_sold += count;
+            self.unopened_boxes += count;
+            self.entropy = mix_entropy(self.entropy);
+
+            Runtime::emit_event(BoxesBoughtEvent { first_box, count });
+
+            (boxes, payment)
+        }
+
+        /// Open boxes once the secret has been revealed. Boxes are burned and the prizes are
+        /// returned, one bucket per prize resource.
+        pub fn open_boxes(&mut self, boxes: Bucket) -> Vec<Bucket> {
+            /* CHECK INPUT */
+            assert!(
+                boxes.resource_address() == self.box_res_manager.address(),
+                "Box resource address mismatch"
+            );
+
+            let seed = match self.state {
+                SaleState::Revealed { seed } => seed,
+                _ => panic!("Secret has not been revealed"),
+            };
+
+            let box_ids = boxes.as_non_fungible().non_fungible_local_ids();
+            let mut prizes: IndexMap<ResourceAddress, Bucket> = IndexMap::new();
+
+            for box_id in box_ids.iter() {
+                let box_number = match box_id {
+                    NonFungibleLocalId::Integer(number) => number.value(),
+                    _ => panic!("Invalid box id"),
+                };
+
+                let tier = self._draw_tier(seed, box_number);
+                let prize_tier = &mut self.tiers[tier];
+                let prize = prize_tier.prizes.take(prize_tier.amount_per_prize);
+
+                match prizes.get_mut(&prize.resource_address()) {
+                    Some(bucket) => bucket.put(prize),
+                    None => {
+                        prizes.insert(prize.resource_address(), prize);
+                    }
+                }
+
+                Runtime::emit_event(BoxOpenedEvent { box_number, tier });
+            }
+
+            self.unopened_boxes -= box_ids.len() as u64;
+
+            boxes.burn();
+
+            prizes.into_values().collect()
+        }
+
+        /// Cancel the sale if the operator has not revealed the secret before the deadline
+        pub fn cancel(&mut self) {
+            assert!(self.state == SaleState::Open, "Sale is not open");
+            assert!(
+                Runtime::current_epoch().number() > self.reveal_deadline_epoch,
+                "Reveal deadline has not passed yet"
+            );
+
+            self.state = SaleState::Cancelled;
+        }
+
+        /// Burn boxes of a cancelled sale in exchange for their price
+        pub fn refund(&mut self, boxes: Bucket) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                boxes.resource_address() == self.box_res_manager.address(),
+                "Box resource address mismatch"
+            );
+            assert!(
+                self.state == SaleState::Cancelled,
+                "Sale has not been cancelled"
+            );
+
+            let refund_amount = boxes
+                .as_non_fungible()
+                .non_fungibles::<MysteryBoxItem>()
+                .iter()
+                .fold(Decimal::ZERO, |total, item| total + item.data().price);
+
+            boxes.burn();
+
+            self.proceeds.take(refund_amount)
+        }
+
+        /* GETTERS */
+
+        pub fn get_state(&self) -> SaleState {
+            self.state
+        }
+
+        pub fn get_boxes_sold(&self) -> u64 {
+            self.boxes_sold
+        }
+
+        /// Total number of prizes held by all tiers, including prizes of unopened boxes
+        pub fn get_available_prizes(&self) -> u64 {
+            self.tiers
+                .iter()
+                .fold(0, |total, tier| total + MysteryBox::_prize_count(tier))
+        }
+
+        pub fn get_entropy(&self) -> Hash {
+            self.entropy
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        /// Draw the prize tier of a box. Tiers without prizes left are excluded from the draw.
+        fn _draw_tier(&self, seed: Hash, box_number: u64) -> usize {
+            let weights: Vec<u64> = self
+                .tiers
+                .iter()
+                .map(|tier| {
+                    if MysteryBox::_prize_count(tier) > Decimal::ZERO {
+                        tier.weight
+                    } else {
+                        0
+                    }
+                })
+                .collect();
+
+            let total_weight = weights.iter().sum::<u64>();
+            assert!(total_weight > 0, "No prize left");
+
+            let mut drawn_weight = draw_weight(seed, box_number, total_weight);
+
+            for (tier, weight) in weights.iter().enumerate() {
+                if drawn_weight < *weight {
+                    return tier;
+                }
+                drawn_weight -= weight;
+            }
+
+            unreachable!()
+        }
+
+        fn _prize_count(tier: &PrizeTier) -> Decimal {
+            (tier.prizes.amount() / tier.amount_per_prize)
+                .checked_round(0, RoundingMode::ToZero)
+                .unwrap()
+        }
+    }
+}
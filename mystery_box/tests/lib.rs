@@ -0,0 +1,351 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Holds the operator badge, 100 prize tokens and the prize NFTs 1 to 3
+const OPERATOR: usize = 0;
+/// Holds 100 payment tokens
+const ALICE: usize = 1;
+/// Holds 100 payment tokens
+const BOB: usize = 2;
+
+/// Mystery box sale at epoch 10, selling boxes at a price of 10 until epoch 20, with a reveal
+/// period of 5 epochs. The first tier pays 10 prize tokens with a weight of 3 and holds 3
+/// prizes, the second tier pays the prize NFT 1 with a weight of 1.
+struct TestMysteryBox {
+    env: TestEnv,
+    mystery_box: ComponentAddress,
+    operator_badge: ResourceAddress,
+    mystery_box_item: ResourceAddress,
+    payment: ResourceAddress,
+    prize_token: ResourceAddress,
+    prize_nft: ResourceAddress,
+}
+
+impl TestMysteryBox {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let payment = env
+            .test_runner
+            .create_fungible_resource(dec!(200), 18, env.accounts[ALICE]);
+        let prize_token =
+            env.test_runner
+                .create_fungible_resource(dec!(100), 18, env.accounts[OPERATOR]);
+        let prize_nft = env
+            .test_runner
+            .create_non_fungible_resource(env.accounts[OPERATOR]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "MysteryBox",
+                "instantiate",
+                manifest_args!(payment, dec!(10), hash(b"secret"), 20u64, 5u64),
+            )
+            .withdraw_from_account(env.accounts[ALICE], payment, dec!(100))
+            .take_all_from_worktop(payment, "bob")
+            .call_method_with_name_lookup(env.accounts[BOB], "deposit", |lookup| {
+                (lookup.bucket("bob"),)
+            })
+            .deposit_batch(env.accounts[OPERATOR])
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+
+        let mut mystery_box = Self {
+            mystery_box: commit.new_component_addresses()[0],
+            operator_badge: commit.new_resource_addresses()[0],
+            mystery_box_item: commit.new_resource_addresses()[1],
+            env,
+            payment,
+            prize_token,
+            prize_nft,
+        };
+
+        mystery_box
+            .add_prize_tokens(3, dec!(30), dec!(10))
+            .expect_commit_success();
+        mystery_box
+            .add_prize_nft(1, 1, dec!(1))
+            .expect_commit_success();
+
+        mystery_box
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.mystery_box, method, args)
+    }
+
+    /// Call a method with a proof of the operator badge, prepending the manifest of the
+    /// builder
+    fn call_as_operator<A: ResolvableArguments>(
+        &mut self,
+        builder: ManifestBuilder,
+        method: &str,
+        args: impl FnOnce(&ManifestNameLookup) -> A,
+    ) -> TransactionReceipt {
+        let builder = builder
+            .create_proof_from_account_of_amount(
+                self.env.accounts[OPERATOR],
+                self.operator_badge,
+                dec!(1),
+            )
+            .call_method_with_name_lookup(self.mystery_box, method, |lookup| args(lookup));
+
+        self.env.execute(OPERATOR, builder)
+    }
+
+    fn add_prize_tokens(
+        &mut self,
+        weight: u64,
+        amount: Decimal,
+        amount_per_prize: Decimal,
+    ) -> TransactionReceipt {
+        let prize_token = self.prize_token;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[OPERATOR], prize_token, amount)
+            .take_all_from_worktop(prize_token, "prizes");
+
+        self.call_as_operator(builder, "add_tier", |lookup| {
+            (weight, lookup.bucket("prizes"), amount_per_prize)
+        })
+    }
+
+    fn add_prize_nft(
+        &mut self,
+        weight: u64,
+        nft_id: u64,
+        amount_per_prize: Decimal,
+    ) -> TransactionReceipt {
+        let prize_nft = self.prize_nft;
+        let builder = ManifestBuilder::new()
+            .withdraw_non_fungibles_from_account(
+                self.env.accounts[OPERATOR],
+                prize_nft,
+                &BTreeSet::from([NonFungibleLocalId::integer(nft_id)]),
+            )
+            .take_all_from_worktop(prize_nft, "prizes");
+
+        self.call_as_operator(builder, "add_tier", |lookup| {
+            (weight, lookup.bucket("prizes"), amount_per_prize)
+        })
+    }
+
+    fn buy_boxes(&mut self, account: usize, amount: Decimal, count: u64) -> TransactionReceipt {
+        let payment = self.payment;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[account], payment, amount)
+            .take_all_from_worktop(payment, "payment")
+            .call_method_with_name_lookup(self.mystery_box, "buy_boxes", |lookup| {
+                (lookup.bucket("payment"), count)
+            });
+
+        self.env.execute(account, builder)
+    }
+
+    /// Call a method with all the boxes of the account
+    fn call_with_boxes(&mut self, account: usize, method: &str) -> TransactionReceipt {
+        let mystery_box_item = self.mystery_box_item;
+        let amount = self.env.balance(account, mystery_box_item);
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[account], mystery_box_item, amount)
+            .take_all_from_worktop(mystery_box_item, "boxes")
+            .call_method_with_name_lookup(self.mystery_box, method, |lookup| {
+                (lookup.bucket("boxes"),)
+            });
+
+        self.env.execute(account, builder)
+    }
+
+    fn reveal(&mut self, secret: &[u8]) -> TransactionReceipt {
+        let secret = secret.to_vec();
+
+        self.call_as_operator(ManifestBuilder::new(), "reveal", |_| (secret,))
+    }
+
+    fn reclaim_prizes(&mut self, tier: u64) -> TransactionReceipt {
+        self.call_as_operator(ManifestBuilder::new(), "reclaim_prizes", |_| (tier,))
+    }
+}
+
+#[test]
+fn test_boxes_are_opened_after_reveal() {
+    let mut mystery_box = TestMysteryBox::new();
+    let (payment, prize_token, prize_nft) = (
+        mystery_box.payment,
+        mystery_box.prize_token,
+        mystery_box.prize_nft,
+    );
+
+    mystery_box
+        .buy_boxes(ALICE, dec!(35), 3)
+        .expect_commit_success();
+    assert_eq!(mystery_box.env.balance(ALICE, payment), dec!(70));
+    assert_eq!(
+        mystery_box.env.balance(ALICE, mystery_box.mystery_box_item),
+        dec!(3)
+    );
+
+    // Only one prize is left for the unopened boxes
+    mystery_box
+        .buy_boxes(BOB, dec!(20), 2)
+        .expect_commit_failure();
+    mystery_box
+        .buy_boxes(BOB, dec!(10), 1)
+        .expect_commit_success();
+
+    mystery_box.reveal(b"secret").expect_commit_failure();
+
+    mystery_box.env.set_epoch(21);
+    mystery_box
+        .call_with_boxes(ALICE, "open_boxes")
+        .expect_commit_failure();
+    mystery_box.reveal(b"wrong").expect_commit_failure();
+    mystery_box.reveal(b"secret").expect_commit_success();
+
+    mystery_box
+        .call_with_boxes(ALICE, "open_boxes")
+        .expect_commit_success();
+    mystery_box.reclaim_prizes(0).expect_commit_failure();
+    mystery_box
+        .call_with_boxes(BOB, "open_boxes")
+        .expect_commit_success();
+
+    // Every prize has been drawn, whatever the seed
+    assert_eq!(
+        mystery_box.env.balance(ALICE, prize_token) + mystery_box.env.balance(BOB, prize_token),
+        dec!(30)
+    );
+    assert_eq!(
+        mystery_box.env.balance(ALICE, prize_nft) + mystery_box.env.balance(BOB, prize_nft),
+        dec!(1)
+    );
+
+    mystery_box
+        .call_as_operator(
+            ManifestBuilder::new(),
+            "withdraw_proceeds",
+            |_| manifest_args!(),
+        )
+        .expect_commit_success();
+    assert_eq!(mystery_box.env.balance(OPERATOR, payment), dec!(40));
+    mystery_box.reclaim_prizes(0).expect_commit_success();
+}
+
+#[test]
+fn test_sale_is_cancelled_without_reveal() {
+    let mut mystery_box = TestMysteryBox::new();
+    let (payment, prize_token, prize_nft) = (
+        mystery_box.payment,
+        mystery_box.prize_token,
+        mystery_box.prize_nft,
+    );
+
+    mystery_box
+        .buy_boxes(ALICE, dec!(20), 2)
+        .expect_commit_success();
+    mystery_box.reclaim_prizes(0).expect_commit_failure();
+
+    // The reveal deadline is epoch 25
+    mystery_box.env.set_epoch(25);
+    mystery_box
+        .call(BOB, "cancel", manifest_args!())
+        .expect_commit_failure();
+
+    mystery_box.env.set_epoch(26);
+    mystery_box.reveal(b"secret").expect_commit_failure();
+    mystery_box
+        .call(BOB, "cancel", manifest_args!())
+        .expect_commit_success();
+
+    mystery_box
+        .call_with_boxes(ALICE, "refund")
+        .expect_commit_success();
+    assert_eq!(mystery_box.env.balance(ALICE, payment), dec!(100));
+    mystery_box
+        .call_as_operator(
+            ManifestBuilder::new(),
+            "withdraw_proceeds",
+            |_| manifest_args!(),
+        )
+        .expect_commit_failure();
+
+    mystery_box.reclaim_prizes(0).expect_commit_success();
+    mystery_box.reclaim_prizes(1).expect_commit_success();
+    assert_eq!(mystery_box.env.balance(OPERATOR, prize_token), dec!(100));
+    assert_eq!(mystery_box.env.balance(OPERATOR, prize_nft), dec!(3));
+}
+
+#[test]
+fn test_prize_tiers_and_payments_are_checked() {
+    let mut mystery_box = TestMysteryBox::new();
+    let (payment, prize_token) = (mystery_box.payment, mystery_box.prize_token);
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(mystery_box.env.accounts[ALICE], payment, dec!(10))
+        .take_all_from_worktop(payment, "prizes")
+        .call_method_with_name_lookup(mystery_box.mystery_box, "add_tier", |lookup| {
+            (1u64, lookup.bucket("prizes"), dec!(10))
+        });
+    mystery_box
+        .env
+        .execute(ALICE, builder)
+        .expect_commit_failure();
+
+    mystery_box
+        .add_prize_tokens(0, dec!(10), dec!(10))
+        .expect_commit_failure();
+    mystery_box
+        .add_prize_tokens(1, dec!(10), dec!(0))
+        .expect_commit_failure();
+    mystery_box
+        .add_prize_nft(1, 2, dec!(2))
+        .expect_commit_failure();
+
+    let operator = mystery_box.env.accounts[OPERATOR];
+    let fund_tier = move |mystery_box: &mut TestMysteryBox, tier: u64| {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(operator, prize_token, dec!(10))
+            .take_all_from_worktop(prize_token, "prizes");
+
+        mystery_box.call_as_operator(builder, "fund_tier", |lookup| {
+            (tier, lookup.bucket("prizes"))
+        })
+    };
+    fund_tier(&mut mystery_box, 2).expect_commit_failure();
+    fund_tier(&mut mystery_box, 0).expect_commit_success();
+
+    let available_prizes: u64 = mystery_box
+        .call(ALICE, "get_available_prizes", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(available_prizes, 5);
+
+    // Boxes are only paid with the payment resource
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(mystery_box.env.accounts[ALICE], XRD, dec!(10))
+        .take_all_from_worktop(XRD, "payment")
+        .call_method_with_name_lookup(mystery_box.mystery_box, "buy_boxes", |lookup| {
+            (lookup.bucket("payment"), 1u64)
+        });
+    mystery_box
+        .env
+        .execute(ALICE, builder)
+        .expect_commit_failure();
+    mystery_box
+        .buy_boxes(ALICE, dec!(10), 0)
+        .expect_commit_failure();
+    mystery_box
+        .buy_boxes(ALICE, dec!(50), 5)
+        .expect_commit_success();
+}
@@ -0,0 +1,332 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+pub const MIN_NAME_LENGTH: usize = 3;
+pub const MAX_NAME_LENGTH: usize = 32;
+
+/// Names are made of lowercase letters, digits, `-` and `_`
+pub fn assert_valid_name(name: &str) {
+    assert!(
+        name.len() >= MIN_NAME_LENGTH && name.len() <= MAX_NAME_LENGTH,
+        "Name must be between {} and {} characters",
+        MIN_NAME_LENGTH,
+        MAX_NAME_LENGTH
+    );
+    assert!(
+        name.chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_'),
+        "Name contains invalid characters"
+    );
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct NameOwnership {
+    pub name: String,
+    pub registered_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct NameEntry {
+    /// Ownership NFT of the current registration
+    pub ownership_id: NonFungibleLocalId,
+    pub target: Option<GlobalAddress>,
+    pub expires_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct NameRegisteredEvent {
+    pub name: String,
+    pub ownership_id: NonFungibleLocalId,
+    pub expires_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct NameRenewedEvent {
+    pub name: String,
+    pub expires_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct TargetUpdatedEvent {
+    pub name: String,
+    pub target: Option<GlobalAddress>,
+}
+
+#[blueprint]
+#[events(NameRegisteredEvent, NameRenewedEvent, TargetUpdatedEvent)]
+pub mod name_registry {
+
+    enable_method_auth! {
+        methods {
+
+            set_fee_per_epoch => restrict_to :[OWNER];
+            withdraw_fees => restrict_to :[OWNER];
+
+            register => PUBLIC;
+            renew => PUBLIC;
+            set_target => PUBLIC;
+
+            resolve => PUBLIC;
+            get_entry => PUBLIC;
+            get_registration_cost => PUBLIC;
+
+        }
+    }
+
+    pub struct NameRegistry {
+        names: KeyValueStore<String, NameEntry>,
+
+        /// Ownership NFT resource manager. Transferring the NFT transfers the name.
+        ownership_res_manager: ResourceManager,
+
+        /// Registration fees
+        fees: Vault,
+        fee_per_epoch: Decimal,
+
+        /// Number of epochs after expiry during which only the owner can renew the name
+        grace_period_epochs: u64,
+    }
+
+    impl NameRegistry {
+        pub fn instantiate(
+            fee_res_address: ResourceAddress,
+            fee_per_epoch: Decimal,
+            grace_period_epochs: u64,
+            owner_role: OwnerRole,
+        ) -> (Global<NameRegistry>, ResourceAddress) {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                fee_res_address,
+                Some("Fee resource must be fungible".to_string()),
+            );
+            assert!(fee_per_epoch >= Decimal::ZERO, "Fee must not be negative");
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(NameRegistry::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let ownership_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<NameOwnership>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule;
+                        minter_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let registry_component = Self {
+                names: KeyValueStore::new(),
+                ownership_res_manager,
+                fees: Vault::new(fee_res_address),
+                fee_per_epoch,
+                grace_period_epochs,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (registry_component, ownership_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn set_fee_per_epoch(&mut self, fee_per_epoch: Decimal) {
+            assert!(fee_per_epoch >= Decimal::ZERO, "Fee must not be negative");
+
+            self.fee_per_epoch = fee_per_epoch;
+        }
+
+        pub fn withdraw_fees(&mut self) -> Bucket {
+            self.fees.take_all()
+        }
+
+        /* USER METHODS */
+
+        /// Register a free name, or a name whose grace period has ended, for the given number
+        /// of epochs. Returns the ownership NFT and the change.
+        pub fn register(
+            &mut self,
+            name: String,
+            target: Option<GlobalAddress>,
+            duration_epochs: u64,
+            payment: Bucket,
+        ) -> (Bucket, Bucket) {
+            let current_epoch = Runtime::current_epoch().number();
+
+            /* CHECK INPUT */
+            assert_valid_name(&name);
+            assert!(duration_epochs > 0, "Duration must be greater than zero");
+            if let Some(entry) = self.names.get(&name) {
+                assert!(
+                    current_epoch > entry.expires_at_epoch + self.grace_period_epochs,
+                    "Name is already registered"
+                );
+            }
+
+            let change = self._collect_fee(payment, duration_epochs);
+
+            let ownership = self
+                .ownership_res_manager
+                .mint_ruid_non_fungible(NameOwnership {
+                    name: name.clone(),
+                    registered_at_epoch: current_epoch,
+                });
+            let ownership_id = ownership.as_non_fungible().non_fungible_local_id();
+            let expires_at_epoch = current_epoch + duration_epochs;
+
+            self.names.insert(
+                name.clone(),
+                NameEntry {
+                    ownership_id: ownership_id.clone(),
+                    target,
+                    expires_at_epoch,
+                },
+            );
+
+            Runtime::emit_event(NameRegisteredEvent {
+                name,
+                ownership_id,
+                expires_at_epoch,
+            });
+
+            (ownership, change)
+        }
+
+        /// Extend the registration of a name. Anyone can renew a name before it expires, and
+        /// only its owner during the grace period. Returns the change.
+        pub fn renew(
+            &mut self,
+            name: String,
+            duration_epochs: u64,
+            payment: Bucket,
+            ownership_proof: Option<Proof>,
+        ) -> Bucket {
+            let current_epoch = Runtime::current_epoch().number();
+
+            /* CHECK INPUT */
+            assert!(duration_epochs > 0, "Duration must be greater than zero");
+
+            let expires_at_epoch = self.get_entry(name.clone()).expires_at_epoch;
+
+            assert!(
+                current_epoch <= expires_at_epoch + self.grace_period_epochs,
+                "Grace period has ended"
+            );
+            if current_epoch > expires_at_epoch {
+                self._check_ownership(
+                    &name,
+                    ownership_proof.expect("Only the owner can renew during the grace period"),
+                );
+            }
+
+            let change = self._collect_fee(payment, duration_epochs);
+
+            // Renewals during the grace period extend from the expiry, not from the renewal
+            let expires_at_epoch = expires_at_epoch + duration_epochs;
+            self.names.get_mut(&name).unwrap().expires_at_epoch = expires_at_epoch;
+
+            Runtime::emit_event(NameRenewedEvent {
+                name,
+                expires_at_epoch,
+            });
+
+            change
+        }
+
+        pub fn set_target(
+            &mut self,
+            ownership_proof: Proof,
+            name: String,
+            target: Option<GlobalAddress>,
+        ) {
+            self._check_ownership(&name, ownership_proof);
+
+            let mut entry = self.names.get_mut(&name).unwrap();
+            assert!(
+                Runtime::current_epoch().number() <= entry.expires_at_epoch,
+                "Name has expired"
+            );
+            entry.target = target;
+
+            Runtime::emit_event(TargetUpdatedEvent { name, target });
+        }
+
+        /* GETTERS */
+
+        /// Address a name points to, if the name is registered and not expired
+        pub fn resolve(&self, name: String) -> Option<GlobalAddress> {
+            self.names.get(&name).and_then(|entry| {
+                if Runtime::current_epoch().number() <= entry.expires_at_epoch {
+                    entry.target
+                } else {
+                    None
+                }
+            })
+        }
+
+        pub fn get_entry(&self, name: String) -> NameEntry {
+            self.names.get(&name).expect("Name not registered").clone()
+        }
+
+        pub fn get_registration_cost(&self, duration_epochs: u64) -> Decimal {
+            self.fee_per_epoch * duration_epochs
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _collect_fee(&mut self, mut payment: Bucket, duration_epochs: u64) -> Bucket {
+            assert!(
+                payment.resource_address() == self.fees.resource_address(),
+                "Fee resource address mismatch"
+            );
+
+            let cost = self.get_registration_cost(duration_epochs);
+            assert!(
+                payment.amount() >= cost,
+                "Payment is below the registration cost"
+            );
+
+            self.fees.put(
+                payment.take_advanced(cost, WithdrawStrategy::Rounded(RoundingMode::AwayFromZero)),
+            );
+
+            payment
+        }
+
+        fn _check_ownership(&self, name: &str, ownership_proof: Proof) {
+            let ownership_id = ownership_proof
+                .check(self.ownership_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id();
+
+            assert!(
+                self.get_entry(name.to_string()).ownership_id == ownership_id,
+                "Proof does not match the name owner"
+            );
+        }
+    }
+}
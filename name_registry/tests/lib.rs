@@ -0,0 +1,262 @@
+use name_registry::{assert_valid_name, NameEntry};
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+const OWNER: usize = 0;
+/// Users holding 1000 fee tokens each
+const ALICE: usize = 1;
+const BOB: usize = 2;
+
+#[test]
+fn test_valid_names() {
+    assert_valid_name("abc");
+    assert_valid_name("my-name_42");
+    assert_valid_name(&"a".repeat(32));
+}
+
+#[test]
+#[should_panic(expected = "Name must be between 3 and 32 characters")]
+fn test_short_name_panics() {
+    assert_valid_name("ab");
+}
+
+#[test]
+#[should_panic(expected = "Name contains invalid characters")]
+fn test_uppercase_name_panics() {
+    assert_valid_name("Alice");
+}
+
+/// Registry charging 1 fee token per epoch, with a grace period of 5 epochs, at epoch 10
+struct TestRegistry {
+    env: TestEnv,
+    registry: ComponentAddress,
+    ownership: ResourceAddress,
+    fee: ResourceAddress,
+}
+
+impl TestRegistry {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let fee = env
+            .test_runner
+            .create_fungible_resource(dec!(2000), 18, env.accounts[ALICE]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "NameRegistry",
+                "instantiate",
+                manifest_args!(
+                    fee,
+                    dec!(1),
+                    5u64,
+                    OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())))
+                ),
+            )
+            .withdraw_from_account(env.accounts[ALICE], fee, dec!(1000))
+            .take_all_from_worktop(fee, "bob")
+            .call_method_with_name_lookup(env.accounts[BOB], "deposit", |lookup| {
+                (lookup.bucket("bob"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            registry: commit.new_component_addresses()[0],
+            ownership: commit.new_resource_addresses()[0],
+            env,
+            fee,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.registry, method, args)
+    }
+
+    /// Register the name for 10 epochs with a payment of 15, pointing to the account of the
+    /// user
+    fn register(&mut self, user: usize, name: &str) -> TransactionReceipt {
+        let (name, target) = (
+            name.to_string(),
+            Some(GlobalAddress::from(self.env.accounts[user])),
+        );
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[user], self.fee, dec!(15))
+            .take_all_from_worktop(self.fee, "payment")
+            .call_method_with_name_lookup(self.registry, "register", |lookup| {
+                (name, target, 10u64, lookup.bucket("payment"))
+            });
+
+        self.env.execute(user, builder)
+    }
+
+    /// Renew the name for 5 epochs, with a proof of the ownership of the user if requested
+    fn renew(&mut self, user: usize, name: &str, with_proof: bool) -> TransactionReceipt {
+        let name = name.to_string();
+        let mut builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[user], self.fee, dec!(5))
+            .take_all_from_worktop(self.fee, "payment");
+        if with_proof {
+            builder = builder
+                .create_proof_from_account_of_amount(
+                    self.env.accounts[user],
+                    self.ownership,
+                    dec!(1),
+                )
+                .pop_from_auth_zone("ownership");
+        }
+        let builder = builder.call_method_with_name_lookup(self.registry, "renew", |lookup| {
+            let ownership_proof = if with_proof {
+                Some(lookup.proof("ownership"))
+            } else {
+                None
+            };
+
+            (name, 5u64, lookup.bucket("payment"), ownership_proof)
+        });
+
+        self.env.execute(user, builder)
+    }
+
+    fn set_target(&mut self, user: usize, name: &str) -> TransactionReceipt {
+        let name = name.to_string();
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(self.env.accounts[user], self.ownership, dec!(1))
+            .pop_from_auth_zone("ownership")
+            .call_method_with_name_lookup(self.registry, "set_target", |lookup| {
+                (lookup.proof("ownership"), name, None::<GlobalAddress>)
+            });
+
+        self.env.execute(user, builder)
+    }
+
+    fn resolve(&mut self, name: &str) -> Option<GlobalAddress> {
+        self.call(OWNER, "resolve", manifest_args!(name.to_string()))
+            .expect_commit_success()
+            .output(0)
+    }
+
+    fn get_entry(&mut self, name: &str) -> NameEntry {
+        self.call(OWNER, "get_entry", manifest_args!(name.to_string()))
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_register_and_resolve_until_expiry() {
+    let mut registry = TestRegistry::new();
+    let (fee, ownership, alice) = (
+        registry.fee,
+        registry.ownership,
+        registry.env.accounts[ALICE],
+    );
+
+    registry.register(ALICE, "alice").expect_commit_success();
+    assert_eq!(registry.env.balance(ALICE, fee), dec!(990));
+    assert_eq!(registry.env.balance(ALICE, ownership), dec!(1));
+    assert_eq!(registry.get_entry("alice").expires_at_epoch, 20);
+    assert_eq!(registry.resolve("alice"), Some(GlobalAddress::from(alice)));
+
+    registry.register(BOB, "alice").expect_commit_failure();
+    registry.register(BOB, "Bob").expect_commit_failure();
+
+    registry.env.set_epoch(21);
+    assert_eq!(registry.resolve("alice"), None);
+    assert_eq!(registry.resolve("unknown"), None);
+
+    // The name is only released once the grace period has ended
+    registry.env.set_epoch(25);
+    registry.register(BOB, "alice").expect_commit_failure();
+
+    registry.env.set_epoch(26);
+    registry.register(BOB, "alice").expect_commit_success();
+    assert_eq!(
+        registry.resolve("alice"),
+        Some(GlobalAddress::from(registry.env.accounts[BOB]))
+    );
+
+    // The previous ownership is no longer valid
+    registry.set_target(ALICE, "alice").expect_commit_failure();
+}
+
+#[test]
+fn test_only_the_owner_renews_during_the_grace_period() {
+    let mut registry = TestRegistry::new();
+
+    registry.register(ALICE, "alice").expect_commit_success();
+
+    // Anyone can renew before the expiry
+    registry.env.set_epoch(15);
+    registry.renew(BOB, "alice", false).expect_commit_success();
+    assert_eq!(registry.get_entry("alice").expires_at_epoch, 25);
+
+    registry.env.set_epoch(27);
+    registry.renew(BOB, "alice", false).expect_commit_failure();
+    registry
+        .renew(ALICE, "alice", false)
+        .expect_commit_failure();
+    registry.renew(ALICE, "alice", true).expect_commit_success();
+
+    // Renewals extend from the expiry
+    assert_eq!(registry.get_entry("alice").expires_at_epoch, 30);
+
+    registry.env.set_epoch(36);
+    registry.renew(ALICE, "alice", true).expect_commit_failure();
+}
+
+#[test]
+fn test_set_target_requires_the_ownership() {
+    let mut registry = TestRegistry::new();
+
+    registry.register(ALICE, "alice").expect_commit_success();
+    registry.register(BOB, "bob").expect_commit_success();
+
+    registry.set_target(BOB, "alice").expect_commit_failure();
+    registry.set_target(ALICE, "alice").expect_commit_success();
+    assert_eq!(registry.resolve("alice"), None);
+
+    registry.env.set_epoch(21);
+    registry.set_target(BOB, "bob").expect_commit_failure();
+}
+
+#[test]
+fn test_fee_methods_require_the_owner() {
+    let mut registry = TestRegistry::new();
+    let fee = registry.fee;
+
+    registry.register(ALICE, "alice").expect_commit_success();
+
+    registry
+        .call(ALICE, "withdraw_fees", manifest_args!())
+        .expect_commit_failure();
+    registry
+        .call(OWNER, "withdraw_fees", manifest_args!())
+        .expect_commit_success();
+    assert_eq!(registry.env.balance(OWNER, fee), dec!(10));
+
+    registry
+        .call(ALICE, "set_fee_per_epoch", manifest_args!(dec!(2)))
+        .expect_commit_failure();
+    registry
+        .call(OWNER, "set_fee_per_epoch", manifest_args!(dec!(-1)))
+        .expect_commit_failure();
+    registry
+        .call(OWNER, "set_fee_per_epoch", manifest_args!(dec!(2)))
+        .expect_commit_success();
+
+    // 10 epochs now cost 20
+    registry.register(BOB, "bob").expect_commit_failure();
+}
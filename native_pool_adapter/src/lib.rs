@@ -0,0 +1,499 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use asset_pool_interface::{DepositType, FlashloanTerm, WithdrawType, FLASHLOAN_CALLBACK_METHOD};
+use blueprint_commons::{
+    assert_fungible_res_address, assert_non_fungible_res_address, checked_div_to_decimal,
+    checked_mul_to_decimal, ReentrancyGuard,
+};
+use scrypto::prelude::*;
+
+#[blueprint]
+pub mod adapter {
+
+    enable_method_auth! {
+        roles {
+            admin => updatable_by: [];
+        },
+        methods {
+            protected_deposit => restrict_to :[admin];
+            protected_withdraw => restrict_to :[admin];
+
+            decrease_external_liquidity => restrict_to :[admin];
+            increase_external_liquidity => restrict_to :[admin];
+
+            contribute => restrict_to :[admin];
+            redeem  => restrict_to :[admin];
+
+            take_flashloan => restrict_to :[admin];
+            repay_flashloan => restrict_to :[admin];
+            flashloan_and_call => restrict_to :[admin];
+
+            get_pool_unit_ratio => PUBLIC;
+            get_pool_unit_supply => PUBLIC;
+            get_pooled_amount => PUBLIC;
+            get_units_for_amount => PUBLIC;
+            get_amount_for_units => PUBLIC;
+            get_flashloan_fee_rate => PUBLIC;
+            get_flashloan_capacity => PUBLIC;
+            get_native_pool => PUBLIC;
+        }
+    }
+
+    /// Exposes the AssetPool method surface on top of a native `OneResourcePool`.
+    ///
+    /// The native pool only knows about the liquidity held in its vault, so the adapter keeps
+    /// track of the liquidity used outside the pool and of the flashloans not yet repaid, and
+    /// prices contributions and redemptions on the total amount. The native pool is managed by
+    /// the adapter: contributions must go through it, and redemptions must go through it too
+    /// while liquidity is outside the pool, as the native pool would otherwise undervalue the
+    /// redeemed pool units.
+    struct NativePoolAdapter {
+        native_pool: Global<OneResourcePool>,
+
+        pool_res_address: ResourceAddress,
+
+        divisibility: u8,
+
+        pool_unit_res_manager: ResourceManager,
+
+        flashloan_term_res_manager: ResourceManager,
+
+        external_liquidity_amount: Decimal,
+
+        flashloan_outstanding_amount: Decimal,
+
+        reentrancy_guard: ReentrancyGuard,
+    }
+
+    impl NativePoolAdapter {
+        pub fn instantiate(
+            pool_res_address: ResourceAddress,
+            owner_role: OwnerRole,
+            admin_rule: AccessRule,
+        ) -> (Global<NativePoolAdapter>, ResourceAddress, ResourceAddress) {
+            /* CHECK INPUTS */
+            assert_fungible_res_address(pool_res_address, None);
+
+            let divisibility = match ResourceManager::from_address(pool_res_address).resource_type()
+            {
+                ResourceType::Fungible { divisibility } => divisibility,
+                _ => panic!("Resource must be fungible"),
+            };
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(NativePoolAdapter::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            // The adapter is the only pool manager of the native pool
+            let native_pool = Blueprint::<OneResourcePool>::instantiate(
+                owner_role.clone(),
+                component_rule.clone(),
+                pool_res_address,
+                None,
+            );
+
+            let pool_unit_address: GlobalAddress = native_pool
+                .get_metadata("pool_unit")
+                .unwrap()
+                .expect("Native pool unit address not found");
+
+            let pool_unit_res_manager = ResourceManager::from_address(
+                ResourceAddress::new_or_panic(pool_unit_address.as_node_id().0),
+            );
+
+            let flashloan_term_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<FlashloanTerm>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule;
+                        burner_updater => rule!(deny_all);
+                    })
+                    // ! critical
+                    .deposit_roles(deposit_roles! {
+                        depositor => rule!(deny_all);
+                        depositor_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let adapter_component = Self {
+                native_pool,
+                pool_res_address,
+                divisibility,
+                pool_unit_res_manager,
+                flashloan_term_res_manager,
+                external_liquidity_amount: 0.into(),
+                flashloan_outstanding_amount: 0.into(),
+                reentrancy_guard: ReentrancyGuard::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                admin => admin_rule;
+            ))
+            .with_address(address_reservation)
+            .globalize();
+
+            (
+                adapter_component,
+                pool_unit_res_manager.address(),
+                flashloan_term_res_manager.address(),
+            )
+        }
+
+        /* GETTERS */
+
+        pub fn get_pool_unit_ratio(&self) -> PreciseDecimal {
+            self._get_unit_to_asset_ratio()
+        }
+
+        pub fn get_pool_unit_supply(&self) -> Decimal {
+            self.pool_unit_res_manager.total_supply().unwrap_or(dec!(0))
+        }
+
+        pub fn get_pooled_amount(&self) -> (Decimal, Decimal) {
+            (
+                self.native_pool.get_vault_amount(),
+                self.external_liquidity_amount,
+            )
+        }
+
+        /// Pool units minted by `contribute` for the given amount of pooled token
+        pub fn get_units_for_amount(&self, amount: Decimal) -> Decimal {
+            checked_mul_to_decimal(
+                amount,
+                self._get_unit_to_asset_ratio(),
+                self.divisibility,
+                RoundingMode::ToZero,
+            )
+        }
+
+        /// Amount of pooled token returned by `redeem` for the given amount of pool units
+        pub fn get_amount_for_units(&self, units: Decimal) -> Decimal {
+            checked_div_to_decimal(
+                units,
+                self._get_unit_to_asset_ratio(),
+                self.divisibility,
+                RoundingMode::ToZero,
+            )
+        }
+
+        /// The adapter does not charge a minimum flashloan fee
+        pub fn get_flashloan_fee_rate(&self) -> Decimal {
+            Decimal::ZERO
+        }
+
+        /// Loans are taken from the native pool vault
+        pub fn get_flashloan_capacity(&self) -> Option<Decimal> {
+            Some(self.native_pool.get_vault_amount())
+        }
+
+        pub fn get_native_pool(&self) -> Global<OneResourcePool> {
+            self.native_pool
+        }
+
+        // Handle request to increase liquidity.
+        // The native pool prices the contribution on its vault only: only the share of the
+        // assets matching the liquidity held in the vault is contributed, the rest is deposited
+        // without minting pool units, so that the contributor gets units priced on the total
+        // amount. The second bucket is always empty and kept for AssetPool compatibility.
+        pub fn contribute(&mut self, mut assets: Bucket) -> (Bucket, Bucket) {
+            self.reentrancy_guard.assert_not_entered();
+
+            /* CHECK INPUT */
+            assert!(
+                assets.resource_address() == self.pool_res_address,
+                "Pool resource address mismatch"
+            );
+
+            let outside_amount = self._get_outside_amount();
+
+            if outside_amount == 0.into() || self.get_pool_unit_supply() == 0.into() {
+                let pool_units = self.native_pool.contribute(assets);
+
+                return (pool_units, Bucket::new(self.pool_res_address));
+            }
+
+            let vault_amount = self.native_pool.get_vault_amount();
+
+            assert!(
+                vault_amount > 0.into(),
+                "No liquidity left in the native pool to price the contribution"
+            );
+
+            let vault_share = PreciseDecimal::from(vault_amount)
+                / PreciseDecimal::from(vault_amount + outside_amount);
+
+            let contributed_amount = checked_mul_to_decimal(
+                assets.amount(),
+                vault_share,
+                self.divisibility,
+                RoundingMode::ToZero,
+            );
+
+            let pool_units = self.native_pool.contribute(assets.take(contributed_amount));
+
+            self.native_pool.protected_deposit(assets);
+
+            (pool_units, Bucket::new(self.pool_res_address))
+        }
+
+        // Handle request to decrease liquidity.
+        // The native pool redeems the units on its vault only: the share of the liquidity
+        // outside the pool owed to the redeemer is withdrawn from the vault on top of it.
+        // The second bucket is always empty and kept for AssetPool compatibility.
+        pub fn redeem(&mut self, pool_units: Bucket) -> (Bucket, Bucket) {
+            self.reentrancy_guard.assert_not_entered();
+
+            /* CHECK INPUT */
+            assert!(
+                pool_units.resource_address() == self.pool_unit_res_manager.address(),
+                "Pool unit resource address mismatch"
+            );
+
+            let amount = self.get_amount_for_units(pool_units.amount());
+
+            let mut assets = self.native_pool.redeem(pool_units);
+
+            let missing_amount = amount - assets.amount();
+
+            if missing_amount > 0.into() {
+                assert!(
+                    missing_amount <= self.native_pool.get_vault_amount(),
+                    "Insufficient liquidity"
+                );
+
+                assets.put(self.native_pool.protected_withdraw(
+                    missing_amount,
+                    WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                ));
+            }
+
+            (assets, Bucket::new(self.pool_unit_res_manager.address()))
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn protected_withdraw(
+            &mut self,
+            amount: Decimal,
+            withdraw_type: WithdrawType,
+            withdraw_strategy: WithdrawStrategy,
+        ) -> Bucket {
+            self.reentrancy_guard.assert_not_entered();
+
+            /* INPUT CHECK */
+            assert!(amount >= 0.into(), "Amount must not be negative");
+
+            let assets = self
+                .native_pool
+                .protected_withdraw(amount, withdraw_strategy);
+
+            if withdraw_type == WithdrawType::ForTemporaryUse {
+                self.external_liquidity_amount += assets.amount();
+            }
+
+            assets
+        }
+
+        pub fn protected_deposit(&mut self, assets: Bucket, deposit_type: DepositType) {
+            self.reentrancy_guard.assert_not_entered();
+
+            /* INPUT CHECK */
+            assert!(
+                assets.resource_address() == self.pool_res_address,
+                "Pool resource address mismatch"
+            );
+
+            if deposit_type == DepositType::FromTemporaryUse {
+                // More than what was taken can be returned: the excess is recorded as yield
+                self.external_liquidity_amount -=
+                    assets.amount().min(self.external_liquidity_amount);
+            }
+
+            self.native_pool.protected_deposit(assets);
+        }
+
+        pub fn increase_external_liquidity(&mut self, amount: Decimal) {
+            self.reentrancy_guard.assert_not_entered();
+
+            assert!(amount >= 0.into(), "Amount must not be negative");
+
+            self.external_liquidity_amount += amount;
+        }
+
+        pub fn decrease_external_liquidity(&mut self, amount: Decimal) {
+            self.reentrancy_guard.assert_not_entered();
+
+            /* INPUT CHECK */
+            assert!(amount >= 0.into(), "Amount must not be negative");
+            assert!(
+                amount <= self.external_liquidity_amount,
+                "Amount exceeds external liquidity"
+            );
+
+            self.external_liquidity_amount -= amount;
+        }
+
+        pub fn take_flashloan(
+            &mut self,
+            loan_amount: Decimal,
+            fee_amount: Decimal,
+        ) -> (Bucket, Bucket) {
+            // Same as AssetPool: the guard stays entered until `repay_flashloan`
+            self.reentrancy_guard.enter();
+
+            self._assert_valid_loan(loan_amount, fee_amount);
+
+            // The loan term can not be deposited: it has to be returned with the repayment
+            let loan_terms =
+                self.flashloan_term_res_manager
+                    .mint_ruid_non_fungible(FlashloanTerm {
+                        fee_amount,
+                        loan_amount,
+                    });
+            let loan = self._take_loan(loan_amount);
+
+            (loan, loan_terms)
+        }
+
+        pub fn repay_flashloan(&mut self, loan_repayment: Bucket, loan_terms: Bucket) -> Bucket {
+            /* INPUT CHECK */
+            assert_non_fungible_res_address(loan_terms.resource_address(), None);
+            assert!(
+                loan_terms.resource_address() == self.flashloan_term_res_manager.address(),
+                "Loan term resource address mismatch"
+            );
+
+            let terms: FlashloanTerm = loan_terms.as_non_fungible().non_fungible().data();
+
+            let change =
+                self._settle_repayment(loan_repayment, terms.loan_amount, terms.fee_amount);
+
+            loan_terms.burn();
+
+            self.reentrancy_guard.exit();
+
+            change
+        }
+
+        /// Same as the AssetPool method: the loan is passed to the `on_flashloan` method of the
+        /// borrower component and the repayment it returns is verified before returning.
+        pub fn flashloan_and_call(
+            &mut self,
+            borrower: ComponentAddress,
+            loan_amount: Decimal,
+            fee_amount: Decimal,
+            callback_args: ScryptoValue,
+        ) -> Bucket {
+            self.reentrancy_guard.assert_not_entered();
+
+            self._assert_valid_loan(loan_amount, fee_amount);
+
+            let loan = self._take_loan(loan_amount);
+
+            // The borrower can not call back into the state changing methods of the adapter
+            let loan_repayment = self.reentrancy_guard.scope(|| {
+                Global::<AnyComponent>::from(borrower).call_raw::<Bucket>(
+                    FLASHLOAN_CALLBACK_METHOD,
+                    scrypto_args!(loan, fee_amount, callback_args),
+                )
+            });
+
+            self._settle_repayment(loan_repayment, loan_amount, fee_amount)
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_loan(&self, loan_amount: Decimal, fee_amount: Decimal) {
+            assert!(
+                loan_amount > 0.into(),
+                "Loan amount must be greater than zero"
+            );
+            assert!(fee_amount >= 0.into(), "Amount must not be negative");
+            assert!(
+                loan_amount <= self.native_pool.get_vault_amount(),
+                "Insufficient liquidity"
+            );
+        }
+
+        fn _take_loan(&mut self, loan_amount: Decimal) -> Bucket {
+            self.flashloan_outstanding_amount += loan_amount;
+
+            self.native_pool
+                .protected_withdraw(loan_amount, WithdrawStrategy::Rounded(RoundingMode::ToZero))
+        }
+
+        // Put the amount due back into the native pool and return the change of the repayment
+        fn _settle_repayment(
+            &mut self,
+            mut loan_repayment: Bucket,
+            loan_amount: Decimal,
+            fee_amount: Decimal,
+        ) -> Bucket {
+            assert!(
+                loan_repayment.resource_address() == self.pool_res_address,
+                "Pool resource address mismatch"
+            );
+
+            let amount_due = fee_amount + loan_amount;
+            assert!(
+                loan_repayment.amount() >= amount_due,
+                "Insufficient flashloan repayment"
+            );
+
+            let repayment = loan_repayment
+                .take_advanced(amount_due, WithdrawStrategy::Rounded(RoundingMode::ToZero));
+
+            self.flashloan_outstanding_amount -= loan_amount;
+
+            self.native_pool.protected_deposit(repayment);
+
+            loan_repayment
+        }
+
+        // Liquidity used outside the pool and flashloans not yet repaid
+        fn _get_outside_amount(&self) -> Decimal {
+            self.external_liquidity_amount + self.flashloan_outstanding_amount
+        }
+
+        fn _get_unit_to_asset_ratio(&self) -> PreciseDecimal {
+            let total_liquidity_amount =
+                self.native_pool.get_vault_amount() + self._get_outside_amount();
+
+            if total_liquidity_amount != 0.into() {
+                PreciseDecimal::from(self.get_pool_unit_supply())
+                    / PreciseDecimal::from(total_liquidity_amount)
+            } else {
+                1.into()
+            }
+        }
+    }
+}
+
+// Global adapter components implement the standard pool interface, like AssetPool components
+asset_pool_interface::impl_single_resource_pool!(adapter::NativePoolAdapter);
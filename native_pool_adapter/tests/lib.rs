@@ -0,0 +1,307 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns and administrates the adapter. Holds 900 assets.
+const OWNER: usize = 0;
+/// Holds 100 assets
+const ALICE: usize = 1;
+
+/// Manifest encoding of `WithdrawType::ForTemporaryUse`
+fn for_temporary_use() -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 0,
+        fields: vec![],
+    }
+}
+
+/// Manifest encoding of `DepositType::FromTemporaryUse`
+fn from_temporary_use() -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 0,
+        fields: vec![],
+    }
+}
+
+/// Manifest encoding of `WithdrawStrategy::Exact`
+fn exact() -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 0,
+        fields: vec![],
+    }
+}
+
+/// Adapter of an empty native pool of the asset
+struct TestAdapter {
+    env: TestEnv,
+    adapter: ComponentAddress,
+    asset: ResourceAddress,
+    pool_unit: ResourceAddress,
+    flashloan_term: ResourceAddress,
+}
+
+impl TestAdapter {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 2);
+        let asset = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, env.accounts[OWNER]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "NativePoolAdapter",
+                "instantiate",
+                manifest_args!(
+                    asset,
+                    OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone()))),
+                    rule!(require(env.badges[OWNER].clone()))
+                ),
+            )
+            .withdraw_from_account(env.accounts[OWNER], asset, dec!(100))
+            .take_all_from_worktop(asset, "alice")
+            .call_method_with_name_lookup(env.accounts[ALICE], "deposit", |lookup| {
+                (lookup.bucket("alice"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let (adapter, pool_unit, flashloan_term): (
+            ComponentAddress,
+            ResourceAddress,
+            ResourceAddress,
+        ) = receipt.expect_commit_success().output(0);
+
+        Self {
+            env,
+            adapter,
+            asset,
+            pool_unit,
+            flashloan_term,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.adapter, method, args)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str, args: impl ResolvableArguments) -> T {
+        self.call(ALICE, method, args)
+            .expect_commit_success()
+            .output(0)
+    }
+
+    /// Call a method of the adapter with a bucket of the resource withdrawn from the account
+    fn call_with_bucket(
+        &mut self,
+        account: usize,
+        method: &str,
+        res_address: ResourceAddress,
+        amount: Decimal,
+    ) -> TransactionReceipt {
+        let builder =
+            ManifestBuilder::new()
+                .withdraw_from_account(self.env.accounts[account], res_address, amount)
+                .take_all_from_worktop(res_address, "bucket")
+                .call_method_with_name_lookup(self.adapter, method, |lookup| {
+                    (lookup.bucket("bucket"),)
+                });
+
+        self.env.execute(account, builder)
+    }
+
+    fn contribute(&mut self, account: usize, amount: Decimal) -> TransactionReceipt {
+        let asset = self.asset;
+
+        self.call_with_bucket(account, "contribute", asset, amount)
+    }
+
+    fn redeem(&mut self, units: Decimal) -> TransactionReceipt {
+        let pool_unit = self.pool_unit;
+
+        self.call_with_bucket(OWNER, "redeem", pool_unit, units)
+    }
+
+    fn balance(&mut self, res_address: ResourceAddress) -> Decimal {
+        self.env.balance(OWNER, res_address)
+    }
+}
+
+#[test]
+fn test_pool_units_are_priced_on_the_total_liquidity() {
+    let mut adapter = TestAdapter::new();
+    let (asset, pool_unit) = (adapter.asset, adapter.pool_unit);
+
+    adapter.contribute(OWNER, dec!(100)).expect_commit_success();
+    assert_eq!(adapter.balance(pool_unit), dec!(100));
+
+    adapter
+        .call(
+            OWNER,
+            "protected_withdraw",
+            manifest_args!(dec!(50), for_temporary_use(), exact()),
+        )
+        .expect_commit_success();
+    assert_eq!(
+        adapter.get::<(Decimal, Decimal)>("get_pooled_amount", manifest_args!()),
+        (dec!(50), dec!(50))
+    );
+
+    // Only half of the contribution goes through the native pool, which would otherwise mint
+    // twice as many units
+    adapter.contribute(OWNER, dec!(100)).expect_commit_success();
+    assert_eq!(adapter.balance(pool_unit), dec!(200));
+    assert_eq!(
+        adapter.get::<(Decimal, Decimal)>("get_pooled_amount", manifest_args!()),
+        (dec!(150), dec!(50))
+    );
+    assert_eq!(
+        adapter.get::<Decimal>("get_amount_for_units", manifest_args!(dec!(100))),
+        dec!(100)
+    );
+
+    // The native pool redeems 75 assets, the share of the external liquidity is withdrawn on
+    // top of it
+    adapter.redeem(dec!(100)).expect_commit_success();
+    assert_eq!(adapter.balance(asset), dec!(850));
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(adapter.env.accounts[OWNER], asset, dec!(60))
+        .take_all_from_worktop(asset, "assets")
+        .call_method_with_name_lookup(adapter.adapter, "protected_deposit", |lookup| {
+            (lookup.bucket("assets"), from_temporary_use())
+        });
+    adapter.env.execute(OWNER, builder).expect_commit_success();
+    assert_eq!(
+        adapter.get::<(Decimal, Decimal)>("get_pooled_amount", manifest_args!()),
+        (dec!(110), dec!(0))
+    );
+
+    adapter.redeem(dec!(100)).expect_commit_success();
+    assert_eq!(adapter.balance(asset), dec!(900));
+    assert_eq!(
+        adapter.get::<Decimal>("get_pool_unit_supply", manifest_args!()),
+        dec!(0)
+    );
+}
+
+#[test]
+fn test_liquidity_is_only_managed_by_the_admin() {
+    let mut adapter = TestAdapter::new();
+    let (asset, pool_unit) = (adapter.asset, adapter.pool_unit);
+
+    adapter.contribute(ALICE, dec!(10)).expect_commit_failure();
+    adapter.contribute(OWNER, dec!(100)).expect_commit_success();
+    adapter
+        .call_with_bucket(OWNER, "contribute", pool_unit, dec!(10))
+        .expect_commit_failure();
+
+    // The adapter is the only pool manager of the native pool
+    let native_pool: ComponentAddress = adapter.get("get_native_pool", manifest_args!());
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(adapter.env.accounts[OWNER], asset, dec!(10))
+        .take_all_from_worktop(asset, "assets")
+        .call_method_with_name_lookup(native_pool, "contribute", |lookup| {
+            (lookup.bucket("assets"),)
+        });
+    adapter.env.execute(OWNER, builder).expect_commit_failure();
+
+    adapter
+        .call(
+            ALICE,
+            "protected_withdraw",
+            manifest_args!(dec!(10), for_temporary_use(), exact()),
+        )
+        .expect_commit_failure();
+    adapter
+        .call(
+            OWNER,
+            "protected_withdraw",
+            manifest_args!(dec!("-1"), for_temporary_use(), exact()),
+        )
+        .expect_commit_failure();
+
+    adapter
+        .call(
+            OWNER,
+            "increase_external_liquidity",
+            manifest_args!(dec!(10)),
+        )
+        .expect_commit_success();
+    adapter
+        .call(
+            OWNER,
+            "decrease_external_liquidity",
+            manifest_args!(dec!(11)),
+        )
+        .expect_commit_failure();
+    adapter
+        .call(
+            OWNER,
+            "decrease_external_liquidity",
+            manifest_args!(dec!(10)),
+        )
+        .expect_commit_success();
+
+    adapter.redeem(dec!(100)).expect_commit_success();
+    assert_eq!(adapter.balance(asset), dec!(900));
+}
+
+#[test]
+fn test_flashloans_are_repaid_with_their_fee() {
+    let mut adapter = TestAdapter::new();
+    let (asset, flashloan_term) = (adapter.asset, adapter.flashloan_term);
+
+    adapter.contribute(OWNER, dec!(100)).expect_commit_success();
+
+    // Loans are taken from the native pool vault, without a minimum fee
+    assert_eq!(
+        adapter.get::<Option<Decimal>>("get_flashloan_capacity", manifest_args!()),
+        Some(dec!(100))
+    );
+    assert_eq!(
+        adapter.get::<Decimal>("get_flashloan_fee_rate", manifest_args!()),
+        dec!(0)
+    );
+
+    let flashloan = |adapter: &TestAdapter, loan_amount: Decimal, repaid_fee: Decimal| {
+        ManifestBuilder::new()
+            .call_method(
+                adapter.adapter,
+                "take_flashloan",
+                manifest_args!(loan_amount, dec!(1)),
+            )
+            .withdraw_from_account(adapter.env.accounts[OWNER], asset, repaid_fee)
+            .take_all_from_worktop(asset, "repayment")
+            .take_all_from_worktop(flashloan_term, "terms")
+            .call_method_with_name_lookup(adapter.adapter, "repay_flashloan", |lookup| {
+                (lookup.bucket("repayment"), lookup.bucket("terms"))
+            })
+    };
+
+    let builder = flashloan(&adapter, dec!(101), dec!(1));
+    adapter.env.execute(OWNER, builder).expect_commit_failure();
+    let builder = flashloan(&adapter, dec!(10), dec!("0.5"));
+    adapter.env.execute(OWNER, builder).expect_commit_failure();
+
+    // The loan term can not be deposited
+    adapter
+        .call(OWNER, "take_flashloan", manifest_args!(dec!(10), dec!(1)))
+        .expect_commit_failure();
+
+    let builder = flashloan(&adapter, dec!(10), dec!(1));
+    adapter.env.execute(OWNER, builder).expect_commit_success();
+    assert_eq!(adapter.balance(asset), dec!(799));
+    assert_eq!(
+        adapter.get::<(Decimal, Decimal)>("get_pooled_amount", manifest_args!()),
+        (dec!(101), dec!(0))
+    );
+}
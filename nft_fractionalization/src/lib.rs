@@ -0,0 +1,241 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::{assert_fungible_res_address, assert_non_fungible_res_address};
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BuyoutEvent {
+    pub price: Decimal,
+    pub paid_amount: Decimal,
+    pub fractions_used: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ReservePriceUpdatedEvent {
+    pub reserve_price: Decimal,
+}
+
+#[blueprint]
+#[events(BuyoutEvent, ReservePriceUpdatedEvent)]
+pub mod nft_fractionalization {
+
+    enable_method_auth! {
+        methods {
+
+            set_reserve_price => restrict_to :[OWNER];
+
+            buyout => PUBLIC;
+            redeem => PUBLIC;
+
+            get_reserve_price => PUBLIC;
+            get_buyout_cost => PUBLIC;
+            is_bought_out => PUBLIC;
+
+        }
+    }
+
+    pub struct NftFractionalization {
+        /// Locked non fungibles, released as a whole on buyout
+        nfts: Vault,
+
+        /// Fraction token resource manager
+        fraction_res_manager: ResourceManager,
+        fraction_supply: Decimal,
+
+        /// Price of the whole set of non fungibles
+        reserve_price: Decimal,
+
+        /// Buyout payment, redeemable by fraction holders
+        proceeds: Vault,
+
+        bought_out: bool,
+    }
+
+    impl NftFractionalization {
+        /// Lock the non fungibles and mint their fractions. Returns the vault component, the
+        /// curator badge, which is required to raise the reserve price, and the fractions.
+        pub fn instantiate(
+            nfts: Bucket,
+            fraction_supply: Decimal,
+            payment_res_address: ResourceAddress,
+            reserve_price: Decimal,
+        ) -> (Global<NftFractionalization>, Bucket, Bucket) {
+            /* CHECK INPUT */
+            assert_non_fungible_res_address(
+                nfts.resource_address(),
+                Some("Locked resource must be non fungible".to_string()),
+            );
+            assert!(!nfts.is_empty(), "No non fungible to lock");
+            assert!(
+                fraction_supply > Decimal::ZERO,
+                "Fraction supply must be greater than zero"
+            );
+            assert_fungible_res_address(
+                payment_res_address,
+                Some("Payment resource must be fungible".to_string()),
+            );
+            assert!(
+                reserve_price > Decimal::ZERO,
+                "Reserve price must be greater than zero"
+            );
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(NftFractionalization::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let curator_badge = ResourceBuilder::new_fungible(OwnerRole::None)
+                .divisibility(DIVISIBILITY_NONE)
+                .mint_initial_supply(1);
+
+            let owner_role = OwnerRole::Fixed(rule!(require(curator_badge.resource_address())));
+
+            let fractions = ResourceBuilder::new_fungible(owner_role.clone())
+                .burn_roles(burn_roles! {
+                    burner => component_rule;
+                    burner_updater => rule!(deny_all);
+                })
+                .mint_initial_supply(fraction_supply);
+
+            let fractionalization_component = Self {
+                nfts: Vault::with_bucket(nfts),
+                fraction_res_manager: fractions.resource_manager(),
+                fraction_supply: fractions.amount(),
+                reserve_price,
+                proceeds: Vault::new(payment_res_address),
+                bought_out: false,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (
+                fractionalization_component,
+                curator_badge.into(),
+                fractions.into(),
+            )
+        }
+
+        /* CURATOR METHODS */
+
+        /// Raise the reserve price. The fractions are minted at instantiation, so lowering the
+        /// price would let the curator buy the non fungibles out from the fraction holders for
+        /// less than they agreed to.
+        pub fn set_reserve_price(&mut self, reserve_price: Decimal) {
+            assert!(!self.bought_out, "Non fungibles have been bought out");
+            assert!(
+                reserve_price > self.reserve_price,
+                "Reserve price can only be raised"
+            );
+
+            self.reserve_price = reserve_price;
+
+            Runtime::emit_event(ReservePriceUpdatedEvent { reserve_price });
+        }
+
+        /* BUYER METHODS */
+
+        /// Buy the locked non fungibles at the reserve price. Fractions held by the buyer
+        /// are burned and reduce the price pro-rata. Returns the non fungibles and the change.
+        pub fn buyout(&mut self, mut payment: Bucket, fractions: Bucket) -> (Bucket, Bucket) {
+            /* CHECK INPUT */
+            assert!(!self.bought_out, "Non fungibles have been bought out");
+            assert!(
+                payment.resource_address() == self.proceeds.resource_address(),
+                "Payment resource address mismatch"
+            );
+            assert!(
+                fractions.resource_address() == self.fraction_res_manager.address(),
+                "Fraction resource address mismatch"
+            );
+
+            let fractions_used = fractions.amount();
+            let price = self.get_buyout_cost(fractions_used);
+
+            assert!(
+                payment.amount() >= price,
+                "Payment is below the buyout cost"
+            );
+
+            // Burned fractions are no longer redeemable, the remaining ones share the payment
+            self.fraction_supply -= fractions_used;
+            fractions.burn();
+
+            self.proceeds.put(
+                payment.take_advanced(price, WithdrawStrategy::Rounded(RoundingMode::AwayFromZero)),
+            );
+            self.bought_out = true;
+
+            Runtime::emit_event(BuyoutEvent {
+                price: self.reserve_price,
+                paid_amount: self.proceeds.amount(),
+                fractions_used,
+            });
+
+            (self.nfts.take_all(), payment)
+        }
+
+        /* FRACTION HOLDER METHODS */
+
+        /// Burn fractions for their pro-rata share of the buyout payment
+        pub fn redeem(&mut self, fractions: Bucket) -> Bucket {
+            /* CHECK INPUT */
+            assert!(self.bought_out, "Non fungibles have not been bought out");
+            assert!(
+                fractions.resource_address() == self.fraction_res_manager.address(),
+                "Fraction resource address mismatch"
+            );
+
+            let amount = self.proceeds.amount() * fractions.amount() / self.fraction_supply;
+
+            self.fraction_supply -= fractions.amount();
+            fractions.burn();
+
+            self.proceeds
+                .take_advanced(amount, WithdrawStrategy::Rounded(RoundingMode::ToZero))
+        }
+
+        /* GETTERS */
+
+        pub fn get_reserve_price(&self) -> Decimal {
+            self.reserve_price
+        }
+
+        /// Payment required to buy out the non fungibles while burning the given amount of
+        /// fractions
+        pub fn get_buyout_cost(&self, fractions_used: Decimal) -> Decimal {
+            assert!(
+                fractions_used <= self.fraction_supply,
+                "Fraction amount exceeds the supply"
+            );
+
+            self.reserve_price * (self.fraction_supply - fractions_used) / self.fraction_supply
+        }
+
+        pub fn is_bought_out(&self) -> bool {
+            self.bought_out
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Holds the curator badge and 40 fractions
+const CURATOR: usize = 0;
+/// Holds 40 fractions
+const ALICE: usize = 1;
+/// Holds 20 fractions, 1000 payment tokens and 1000 other tokens
+const BUYER: usize = 2;
+
+/// Non fungibles 1 to 3 locked into 100 fractions, with a reserve price of 500 payment tokens
+struct TestFractionalization {
+    env: TestEnv,
+    vault: ComponentAddress,
+    curator_badge: ResourceAddress,
+    fractions: ResourceAddress,
+    nft: ResourceAddress,
+    payment: ResourceAddress,
+    other: ResourceAddress,
+}
+
+impl TestFractionalization {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        let nft = env
+            .test_runner
+            .create_non_fungible_resource(env.accounts[CURATOR]);
+        let payment = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, env.accounts[BUYER]);
+        let other = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, env.accounts[BUYER]);
+
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account(env.accounts[CURATOR], nft, dec!(3))
+            .take_all_from_worktop(nft, "nfts")
+            .call_function_with_name_lookup(
+                env.package_address,
+                "NftFractionalization",
+                "instantiate",
+                |lookup| (lookup.bucket("nfts"), dec!(100), payment, dec!(500)),
+            )
+            .deposit_batch(env.accounts[CURATOR])
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[CURATOR].clone()]);
+        let commit = receipt.expect_commit_success();
+
+        let mut fractionalization = Self {
+            vault: commit.new_component_addresses()[0],
+            curator_badge: commit.new_resource_addresses()[0],
+            fractions: commit.new_resource_addresses()[1],
+            env,
+            nft,
+            payment,
+            other,
+        };
+
+        let fractions = fractionalization.fractions;
+        let (alice, buyer) = (
+            fractionalization.env.accounts[ALICE],
+            fractionalization.env.accounts[BUYER],
+        );
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(fractionalization.env.accounts[CURATOR], fractions, dec!(60))
+            .take_from_worktop(fractions, dec!(40), "alice")
+            .call_method_with_name_lookup(alice, "try_deposit_or_abort", |lookup| {
+                (lookup.bucket("alice"), None::<ResourceOrNonFungible>)
+            })
+            .take_all_from_worktop(fractions, "buyer")
+            .call_method_with_name_lookup(buyer, "try_deposit_or_abort", |lookup| {
+                (lookup.bucket("buyer"), None::<ResourceOrNonFungible>)
+            });
+        fractionalization
+            .env
+            .execute(CURATOR, builder)
+            .expect_commit_success();
+
+        fractionalization
+    }
+
+    /// Buy out the non fungibles with the given payment, burning the given amount of fractions
+    /// of the buyer
+    fn buyout(
+        &mut self,
+        payment_res_address: ResourceAddress,
+        amount: Decimal,
+        fractions_used: Decimal,
+    ) -> TransactionReceipt {
+        let (buyer, fractions) = (self.env.accounts[BUYER], self.fractions);
+
+        let mut builder = ManifestBuilder::new()
+            .withdraw_from_account(buyer, payment_res_address, amount)
+            .take_all_from_worktop(payment_res_address, "payment");
+        if fractions_used > Decimal::ZERO {
+            builder = builder.withdraw_from_account(buyer, fractions, fractions_used);
+        }
+        let builder = builder
+            .take_all_from_worktop(fractions, "fractions")
+            .call_method_with_name_lookup(self.vault, "buyout", |lookup| {
+                (lookup.bucket("payment"), lookup.bucket("fractions"))
+            });
+
+        self.env.execute(BUYER, builder)
+    }
+
+    fn redeem(&mut self, holder: usize, amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[holder], self.fractions, amount)
+            .take_all_from_worktop(self.fractions, "fractions")
+            .call_method_with_name_lookup(self.vault, "redeem", |lookup| {
+                (lookup.bucket("fractions"),)
+            });
+
+        self.env.execute(holder, builder)
+    }
+
+    /// Set the reserve price, with a proof of the curator badge if the caller is the curator
+    fn set_reserve_price(&mut self, caller: usize, reserve_price: Decimal) -> TransactionReceipt {
+        let mut builder = ManifestBuilder::new();
+        if caller == CURATOR {
+            builder = builder.create_proof_from_account_of_amount(
+                self.env.accounts[CURATOR],
+                self.curator_badge,
+                dec!(1),
+            );
+        }
+        let builder = builder.call_method(
+            self.vault,
+            "set_reserve_price",
+            manifest_args!(reserve_price),
+        );
+
+        self.env.execute(caller, builder)
+    }
+
+    fn get_buyout_cost(&mut self, fractions_used: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_method(
+            self.vault,
+            "get_buyout_cost",
+            manifest_args!(fractions_used),
+        );
+
+        self.env.execute(ALICE, builder)
+    }
+}
+
+#[test]
+fn test_buyout_with_fractions_reduces_the_price() {
+    let mut fractionalization = TestFractionalization::new();
+    let (nft, payment) = (fractionalization.nft, fractionalization.payment);
+
+    let cost: Decimal = fractionalization
+        .get_buyout_cost(dec!(20))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(cost, dec!(400));
+    fractionalization
+        .get_buyout_cost(dec!(101))
+        .expect_commit_failure();
+
+    fractionalization
+        .buyout(payment, dec!(399), dec!(20))
+        .expect_commit_failure();
+    fractionalization
+        .buyout(payment, dec!(450), dec!(20))
+        .expect_commit_success();
+
+    assert_eq!(fractionalization.env.balance(BUYER, nft), dec!(3));
+    assert_eq!(fractionalization.env.balance(BUYER, payment), dec!(600));
+
+    fractionalization
+        .buyout(payment, dec!(500), dec!(0))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_fraction_holders_redeem_the_buyout_payment() {
+    let mut fractionalization = TestFractionalization::new();
+    let payment = fractionalization.payment;
+
+    fractionalization
+        .redeem(ALICE, dec!(40))
+        .expect_commit_failure();
+
+    fractionalization
+        .buyout(payment, dec!(500), dec!(0))
+        .expect_commit_success();
+    assert_eq!(fractionalization.env.balance(BUYER, payment), dec!(500));
+
+    fractionalization
+        .redeem(ALICE, dec!(40))
+        .expect_commit_success();
+    fractionalization
+        .redeem(BUYER, dec!(20))
+        .expect_commit_success();
+    fractionalization
+        .redeem(CURATOR, dec!(40))
+        .expect_commit_success();
+
+    assert_eq!(fractionalization.env.balance(ALICE, payment), dec!(200));
+    assert_eq!(fractionalization.env.balance(BUYER, payment), dec!(600));
+    assert_eq!(fractionalization.env.balance(CURATOR, payment), dec!(200));
+}
+
+#[test]
+fn test_buyout_rejects_another_payment_resource() {
+    let mut fractionalization = TestFractionalization::new();
+    let other = fractionalization.other;
+
+    fractionalization
+        .buyout(other, dec!(500), dec!(0))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_set_reserve_price_requires_the_curator_badge() {
+    let mut fractionalization = TestFractionalization::new();
+    let payment = fractionalization.payment;
+
+    fractionalization
+        .set_reserve_price(ALICE, dec!(600))
+        .expect_commit_failure();
+    fractionalization
+        .set_reserve_price(CURATOR, dec!(600))
+        .expect_commit_success();
+
+    let cost: Decimal = fractionalization
+        .get_buyout_cost(dec!(0))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(cost, dec!(600));
+
+    fractionalization
+        .buyout(payment, dec!(600), dec!(0))
+        .expect_commit_success();
+    fractionalization
+        .set_reserve_price(CURATOR, dec!(700))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_reserve_price_can_not_be_lowered() {
+    let mut fractionalization = TestFractionalization::new();
+
+    fractionalization
+        .set_reserve_price(CURATOR, dec!(0))
+        .expect_commit_failure();
+    fractionalization
+        .set_reserve_price(CURATOR, dec!(300))
+        .expect_commit_failure();
+    fractionalization
+        .set_reserve_price(CURATOR, dec!(500))
+        .expect_commit_failure();
+
+    let cost: Decimal = fractionalization
+        .get_buyout_cost(dec!(0))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(cost, dec!(500));
+}
+
+#[test]
+fn test_curator_can_not_lower_the_reserve_and_buy_out_for_dust() {
+    let mut fractionalization = TestFractionalization::new();
+    let (curator, buyer) = (
+        fractionalization.env.accounts[CURATOR],
+        fractionalization.env.accounts[BUYER],
+    );
+    let (curator_badge, fractions, nft, payment) = (
+        fractionalization.curator_badge,
+        fractionalization.fractions,
+        fractionalization.nft,
+        fractionalization.payment,
+    );
+
+    // The curator lowers the reserve to dust and buys out in the same transaction, with
+    // payment tokens of an accomplice
+    let manifest = ManifestBuilder::new()
+        .create_proof_from_account_of_amount(curator, curator_badge, dec!(1))
+        .call_method(
+            fractionalization.vault,
+            "set_reserve_price",
+            manifest_args!(dec!("0.001")),
+        )
+        .withdraw_from_account(buyer, payment, dec!(1))
+        .take_all_from_worktop(payment, "payment")
+        .take_all_from_worktop(fractions, "fractions")
+        .call_method_with_name_lookup(fractionalization.vault, "buyout", |lookup| {
+            (lookup.bucket("payment"), lookup.bucket("fractions"))
+        })
+        .deposit_batch(curator)
+        .build();
+    let signers = vec![
+        fractionalization.env.badges[CURATOR].clone(),
+        fractionalization.env.badges[BUYER].clone(),
+    ];
+    fractionalization
+        .env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, signers)
+        .expect_commit_failure();
+
+    assert_eq!(fractionalization.env.balance(CURATOR, nft), dec!(0));
+    assert_eq!(fractionalization.env.balance(BUYER, payment), dec!(1000));
+}
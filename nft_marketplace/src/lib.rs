@@ -0,0 +1,545 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_non_fungible_res_address;
+use scrypto::prelude::*;
+
+/// Maximum royalty rate a creator can configure
+pub const MAX_ROYALTY_RATE: Decimal = dec!("0.25");
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct ListingReceipt {
+    pub nft_res_address: ResourceAddress,
+    pub payment_res_address: ResourceAddress,
+    pub price: Decimal,
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct OfferReceipt {
+    pub nft_res_address: ResourceAddress,
+    pub nft_id: NonFungibleLocalId,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor)]
+pub struct Listing {
+    /// Listed NFTs, sold as a single lot. Empty once sold.
+    pub nfts: Vault,
+    pub price: Decimal,
+    /// Payment received for the lot, minus royalties
+    pub proceeds: Vault,
+}
+
+#[derive(ScryptoSbor)]
+pub struct Offer {
+    pub nft_res_address: ResourceAddress,
+    pub nft_id: NonFungibleLocalId,
+    /// Escrowed payment. Empty once accepted.
+    pub payment: Vault,
+    /// NFT received when the offer is accepted
+    pub nft: Vault,
+}
+
+#[derive(ScryptoSbor)]
+pub struct RoyaltyConfig {
+    /// Badge proving the authority of the collection creator
+    pub creator_badge: ResourceAddress,
+    pub rate: Decimal,
+    pub recipient: Option<Global<Account>>,
+    /// Royalties refused by the recipient account, claimable with the creator badge
+    pub unclaimed_royalties: KeyValueStore<ResourceAddress, Vault>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ListingCreatedEvent {
+    pub listing_id: NonFungibleLocalId,
+    pub nft_res_address: ResourceAddress,
+    pub nft_ids: Vec<NonFungibleLocalId>,
+    pub payment_res_address: ResourceAddress,
+    pub price: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SaleEvent {
+    pub nft_res_address: ResourceAddress,
+    pub nft_ids: Vec<NonFungibleLocalId>,
+    pub payment_res_address: ResourceAddress,
+    pub price: Decimal,
+    pub royalty_amount: Decimal,
+}
+
+#[blueprint]
+#[events(ListingCreatedEvent, SaleEvent)]
+pub mod nft_marketplace {
+
+    enable_method_auth! {
+        methods {
+
+            register_collection => restrict_to :[OWNER];
+
+            set_royalty_config => PUBLIC;
+            claim_royalties => PUBLIC;
+
+            list => PUBLIC;
+            close_listing => PUBLIC;
+            buy => PUBLIC;
+
+            make_offer => PUBLIC;
+            accept_offer => PUBLIC;
+            close_offer => PUBLIC;
+
+            get_listing_price => PUBLIC;
+            get_royalty_rate => PUBLIC;
+
+        }
+    }
+
+    pub struct NftMarketplace {
+        /// Listing receipt non-fungible resource manager
+        listing_res_manager: ResourceManager,
+        listings: KeyValueStore<NonFungibleLocalId, Listing>,
+
+        /// Offer receipt non-fungible resource manager
+        offer_res_manager: ResourceManager,
+        offers: KeyValueStore<NonFungibleLocalId, Offer>,
+
+        /// Royalty configuration of registered collections
+        collections: KeyValueStore<ResourceAddress, RoyaltyConfig>,
+    }
+
+    impl NftMarketplace {
+        pub fn instantiate(
+            owner_role: OwnerRole,
+        ) -> (Global<NftMarketplace>, ResourceAddress, ResourceAddress) {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(NftMarketplace::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let listing_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<ListingReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule.clone();
+                        burner_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let offer_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<OfferReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule;
+                        burner_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let marketplace_component = Self {
+                listing_res_manager,
+                listings: KeyValueStore::new(),
+                offer_res_manager,
+                offers: KeyValueStore::new(),
+                collections: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (
+                marketplace_component,
+                listing_res_manager.address(),
+                offer_res_manager.address(),
+            )
+        }
+
+        /* ADMIN METHODS */
+
+        /// Register a collection and the badge of its creator, who can then configure the
+        /// royalties of the collection
+        pub fn register_collection(
+            &mut self,
+            nft_res_address: ResourceAddress,
+            creator_badge: ResourceAddress,
+        ) {
+            assert_non_fungible_res_address(nft_res_address, None);
+            assert!(
+                self.collections.get(&nft_res_address).is_none(),
+                "Collection already registered"
+            );
+
+            self.collections.insert(
+                nft_res_address,
+                RoyaltyConfig {
+                    creator_badge,
+                    rate: Decimal::ZERO,
+                    recipient: None,
+                    unclaimed_royalties: KeyValueStore::new(),
+                },
+            );
+        }
+
+        /* CREATOR METHODS */
+
+        pub fn set_royalty_config(
+            &mut self,
+            creator_proof: Proof,
+            nft_res_address: ResourceAddress,
+            rate: Decimal,
+            recipient: Global<Account>,
+        ) {
+            /* CHECK INPUT */
+            assert!(
+                rate >= Decimal::ZERO && rate <= MAX_ROYALTY_RATE,
+                "Royalty rate must be between 0 and {}",
+                MAX_ROYALTY_RATE
+            );
+
+            let mut config = self
+                .collections
+                .get_mut(&nft_res_address)
+                .expect("Collection not registered");
+
+            creator_proof.check(config.creator_badge);
+
+            config.rate = rate;
+            config.recipient = Some(recipient);
+        }
+
+        /// Claim the royalties that could not be deposited to the recipient account
+        pub fn claim_royalties(
+            &mut self,
+            creator_proof: Proof,
+            nft_res_address: ResourceAddress,
+            payment_res_address: ResourceAddress,
+        ) -> Bucket {
+            let mut config = self
+                .collections
+                .get_mut(&nft_res_address)
+                .expect("Collection not registered");
+
+            creator_proof.check(config.creator_badge);
+
+            let mut vault = config
+                .unclaimed_royalties
+                .get_mut(&payment_res_address)
+                .expect("No royalties for this resource");
+
+            vault.take_all()
+        }
+
+        /* SELLER METHODS */
+
+        /// List NFTs of a single collection as one lot at a fixed price. Returns the listing
+        /// receipt.
+        pub fn list(
+            &mut self,
+            nfts: Bucket,
+            payment_res_address: ResourceAddress,
+            price: Decimal,
+        ) -> Bucket {
+            let nft_res_address = nfts.resource_address();
+
+            /* CHECK INPUT */
+            assert_non_fungible_res_address(nft_res_address, None);
+            assert!(!nfts.is_empty(), "No NFT to list");
+            assert!(price > Decimal::ZERO, "Price must be greater than zero!");
+
+            let nft_ids: Vec<NonFungibleLocalId> = nfts
+                .as_non_fungible()
+                .non_fungible_local_ids()
+                .into_iter()
+                .collect();
+
+            let receipt = self
+                .listing_res_manager
+                .mint_ruid_non_fungible(ListingReceipt {
+                    nft_res_address,
+                    payment_res_address,
+                    price,
+                });
+            let listing_id = receipt.as_non_fungible().non_fungible_local_id();
+
+            self.listings.insert(
+                listing_id.clone(),
+                Listing {
+                    nfts: Vault::with_bucket(nfts),
+                    price,
+                    proceeds: Vault::new(payment_res_address),
+                },
+            );
+
+            Runtime::emit_event(ListingCreatedEvent {
+                listing_id,
+                nft_res_address,
+                nft_ids,
+                payment_res_address,
+                price,
+            });
+
+            receipt
+        }
+
+        /// Close a listing, burning its receipt. Returns the NFTs if the lot has not been sold
+        /// and the proceeds of the sale otherwise.
+        pub fn close_listing(&mut self, listing_receipt: Bucket) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                listing_receipt.resource_address() == self.listing_res_manager.address(),
+                "Listing receipt resource address mismatch"
+            );
+
+            let listing_id = listing_receipt.as_non_fungible().non_fungible_local_id();
+            listing_receipt.burn();
+
+            let mut listing = self.listings.get_mut(&listing_id).unwrap();
+
+            if listing.nfts.is_empty() {
+                listing.proceeds.take_all()
+            } else {
+                listing.nfts.take_all()
+            }
+        }
+
+        /* BUYER METHODS */
+
+        /// Buy one or several listings in one call. All listings must be priced in the payment
+        /// resource. Returns the bought lots and the change of the payment.
+        pub fn buy(
+            &mut self,
+            listing_ids: Vec<NonFungibleLocalId>,
+            mut payment: Bucket,
+        ) -> (Vec<Bucket>, Bucket) {
+            let mut lots = Vec::new();
+
+            for listing_id in listing_ids {
+                let (nfts, price, mut sale_payment) = {
+                    let mut listing = self
+                        .listings
+                        .get_mut(&listing_id)
+                        .expect("Listing not found");
+
+                    assert!(!listing.nfts.is_empty(), "Listing already sold");
+                    assert!(
+                        payment.resource_address() == listing.proceeds.resource_address(),
+                        "Payment resource address mismatch"
+                    );
+                    assert!(payment.amount() >= listing.price, "Insufficient payment");
+
+                    let sale_payment = payment.take_advanced(
+                        listing.price,
+                        WithdrawStrategy::Rounded(RoundingMode::AwayFromZero),
+                    );
+
+                    (listing.nfts.take_all(), listing.price, sale_payment)
+                };
+
+                let nft_ids = nfts
+                    .as_non_fungible()
+                    .non_fungible_local_ids()
+                    .into_iter()
+                    .collect();
+                let royalty_amount =
+                    self._pay_royalties(nfts.resource_address(), &mut sale_payment);
+
+                self.listings
+                    .get_mut(&listing_id)
+                    .unwrap()
+                    .proceeds
+                    .put(sale_payment);
+
+                Runtime::emit_event(SaleEvent {
+                    nft_res_address: nfts.resource_address(),
+                    nft_ids,
+                    payment_res_address: payment.resource_address(),
+                    price,
+                    royalty_amount,
+                });
+
+                lots.push(nfts);
+            }
+
+            (lots, payment)
+        }
+
+        /// Make an offer on a single NFT, escrowing the payment. Returns the offer receipt.
+        pub fn make_offer(
+            &mut self,
+            nft_res_address: ResourceAddress,
+            nft_id: NonFungibleLocalId,
+            payment: Bucket,
+        ) -> Bucket {
+            /* CHECK INPUT */
+            assert_non_fungible_res_address(nft_res_address, None);
+            assert!(
+                payment.amount() > Decimal::ZERO,
+                "Amount must be greater than zero!"
+            );
+
+            let receipt = self.offer_res_manager.mint_ruid_non_fungible(OfferReceipt {
+                nft_res_address,
+                nft_id: nft_id.clone(),
+                amount: payment.amount(),
+            });
+
+            self.offers.insert(
+                receipt.as_non_fungible().non_fungible_local_id(),
+                Offer {
+                    nft_res_address,
+                    nft_id,
+                    payment: Vault::with_bucket(payment),
+                    nft: Vault::new(nft_res_address),
+                },
+            );
+
+            receipt
+        }
+
+        /// Accept an offer by providing the requested NFT. Returns the offered payment minus
+        /// royalties.
+        pub fn accept_offer(&mut self, offer_id: NonFungibleLocalId, nft: Bucket) -> Bucket {
+            let mut offer = self.offers.get_mut(&offer_id).expect("Offer not found");
+
+            /* CHECK INPUT */
+            assert!(!offer.payment.is_empty(), "Offer already accepted");
+            assert!(
+                nft.resource_address() == offer.nft_res_address
+                    && nft.amount() == Decimal::ONE
+                    && nft.as_non_fungible().non_fungible_local_id() == offer.nft_id,
+                "NFT does not match the offer"
+            );
+
+            let mut payment = offer.payment.take_all();
+            let price = payment.amount();
+            let nft_res_address = offer.nft_res_address;
+            let nft_ids = vec![offer.nft_id.clone()];
+
+            offer.nft.put(nft);
+            drop(offer);
+
+            let royalty_amount = self._pay_royalties(nft_res_address, &mut payment);
+
+            Runtime::emit_event(SaleEvent {
+                nft_res_address,
+                nft_ids,
+                payment_res_address: payment.resource_address(),
+                price,
+                royalty_amount,
+            });
+
+            payment
+        }
+
+        /// Close an offer, burning its receipt. Returns the NFT if the offer has been accepted
+        /// and the escrowed payment otherwise.
+        pub fn close_offer(&mut self, offer_receipt: Bucket) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                offer_receipt.resource_address() == self.offer_res_manager.address(),
+                "Offer receipt resource address mismatch"
+            );
+
+            let offer_id = offer_receipt.as_non_fungible().non_fungible_local_id();
+            offer_receipt.burn();
+
+            let mut offer = self.offers.get_mut(&offer_id).unwrap();
+
+            if offer.payment.is_empty() {
+                offer.nft.take_all()
+            } else {
+                offer.payment.take_all()
+            }
+        }
+
+        /* GETTERS */
+
+        pub fn get_listing_price(&self, listing_id: NonFungibleLocalId) -> Option<Decimal> {
+            self.listings
+                .get(&listing_id)
+                .filter(|listing| !listing.nfts.is_empty())
+                .map(|listing| listing.price)
+        }
+
+        pub fn get_royalty_rate(&self, nft_res_address: ResourceAddress) -> Decimal {
+            self.collections
+                .get(&nft_res_address)
+                .map(|config| config.rate)
+                .unwrap_or(Decimal::ZERO)
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        /// Take the royalties of a sale from the payment and deposit them to the recipient
+        /// account. Royalties refused by the account are kept claimable by the creator.
+        fn _pay_royalties(
+            &mut self,
+            nft_res_address: ResourceAddress,
+            payment: &mut Bucket,
+        ) -> Decimal {
+            let mut config = match self.collections.get_mut(&nft_res_address) {
+                Some(config) => config,
+                None => return Decimal::ZERO,
+            };
+
+            let mut recipient = match config.recipient.clone() {
+                Some(recipient) if config.rate > Decimal::ZERO => recipient,
+                _ => return Decimal::ZERO,
+            };
+
+            let royalties = payment.take_advanced(
+                payment.amount() * config.rate,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+            let royalty_amount = royalties.amount();
+
+            if let Some(refused_royalties) = recipient.try_deposit_or_refund(royalties, None) {
+                let payment_res_address = refused_royalties.resource_address();
+
+                let vault_exists = config
+                    .unclaimed_royalties
+                    .get(&payment_res_address)
+                    .is_some();
+
+                if vault_exists {
+                    config
+                        .unclaimed_royalties
+                        .get_mut(&payment_res_address)
+                        .unwrap()
+                        .put(refused_royalties);
+                } else {
+                    config
+                        .unclaimed_royalties
+                        .insert(payment_res_address, Vault::with_bucket(refused_royalties));
+                }
+            }
+
+            royalty_amount
+        }
+    }
+}
@@ -0,0 +1,407 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owner of the marketplace and seller of the NFTs
+const SELLER: usize = 0;
+/// Holds 1000 payment tokens
+const BUYER: usize = 1;
+/// Holds the creator badge of the NFT collection
+const CREATOR: usize = 2;
+
+/// Marketplace owned by the seller, who holds the NFTs 1, 2 and 3 of a collection
+struct TestMarketplace {
+    env: TestEnv,
+    marketplace: ComponentAddress,
+    listing_receipt: ResourceAddress,
+    offer_receipt: ResourceAddress,
+    nft: ResourceAddress,
+    payment: ResourceAddress,
+    creator_badge: ResourceAddress,
+}
+
+impl TestMarketplace {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        let nft = env
+            .test_runner
+            .create_non_fungible_resource(env.accounts[SELLER]);
+        let payment = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, env.accounts[BUYER]);
+        let creator_badge =
+            env.test_runner
+                .create_fungible_resource(dec!(1), 0, env.accounts[CREATOR]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "NftMarketplace",
+                "instantiate",
+                manifest_args!(OwnerRole::Fixed(rule!(require(env.badges[SELLER].clone())))),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            marketplace: commit.new_component_addresses()[0],
+            listing_receipt: commit.new_resource_addresses()[0],
+            offer_receipt: commit.new_resource_addresses()[1],
+            env,
+            nft,
+            payment,
+            creator_badge,
+        }
+    }
+
+    /// Marketplace where the collection is registered, with a royalty rate of 10% paid to the
+    /// creator account
+    fn with_royalties() -> Self {
+        let mut marketplace = Self::new();
+
+        marketplace.register_collection().expect_commit_success();
+        marketplace
+            .set_royalty_config(CREATOR, dec!("0.1"))
+            .expect_commit_success();
+
+        marketplace
+    }
+
+    /// Execute the manifest with the keys of every account, then deposit the worktop into the
+    /// given account
+    fn execute(&mut self, account: usize, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute_with_all_keys(account, builder)
+    }
+
+    fn register_collection(&mut self) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_method(
+            self.marketplace,
+            "register_collection",
+            manifest_args!(self.nft, self.creator_badge),
+        );
+
+        self.execute(SELLER, builder)
+    }
+
+    /// Set the royalty config with a proof of the creator badge taken from the given account
+    fn set_royalty_config(&mut self, account: usize, rate: Decimal) -> TransactionReceipt {
+        let (nft, recipient) = (self.nft, self.env.accounts[CREATOR]);
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(
+                self.env.accounts[account],
+                self.creator_badge,
+                dec!(1),
+            )
+            .pop_from_auth_zone("creator")
+            .call_method_with_name_lookup(self.marketplace, "set_royalty_config", |lookup| {
+                (lookup.proof("creator"), nft, rate, recipient)
+            });
+
+        self.execute(account, builder)
+    }
+
+    /// List the given NFTs of the seller as one lot
+    fn list(&mut self, nft_ids: Vec<u64>, price: Decimal) -> TransactionReceipt {
+        let ids: BTreeSet<NonFungibleLocalId> = nft_ids
+            .into_iter()
+            .map(NonFungibleLocalId::integer)
+            .collect();
+        let builder = ManifestBuilder::new()
+            .withdraw_non_fungibles_from_account(self.env.accounts[SELLER], self.nft, &ids)
+            .take_all_from_worktop(self.nft, "nfts")
+            .call_method_with_name_lookup(self.marketplace, "list", |lookup| {
+                (lookup.bucket("nfts"), self.payment, price)
+            });
+
+        self.execute(SELLER, builder)
+    }
+
+    fn buy(&mut self, listing_ids: Vec<NonFungibleLocalId>, amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[BUYER], self.payment, amount)
+            .take_all_from_worktop(self.payment, "payment")
+            .call_method_with_name_lookup(self.marketplace, "buy", |lookup| {
+                (listing_ids, lookup.bucket("payment"))
+            });
+
+        self.execute(BUYER, builder)
+    }
+
+    /// Send a receipt held by the account to a method taking it as a bucket
+    fn close(
+        &mut self,
+        account: usize,
+        receipt_res_address: ResourceAddress,
+        receipt_id: NonFungibleLocalId,
+        method: &str,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_non_fungibles_from_account(
+                self.env.accounts[account],
+                receipt_res_address,
+                &BTreeSet::from([receipt_id]),
+            )
+            .take_all_from_worktop(receipt_res_address, "receipt")
+            .call_method_with_name_lookup(self.marketplace, method, |lookup| {
+                (lookup.bucket("receipt"),)
+            });
+
+        self.execute(account, builder)
+    }
+
+    fn make_offer(&mut self, nft_id: u64, amount: Decimal) -> TransactionReceipt {
+        let nft = self.nft;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[BUYER], self.payment, amount)
+            .take_all_from_worktop(self.payment, "payment")
+            .call_method_with_name_lookup(self.marketplace, "make_offer", |lookup| {
+                (
+                    nft,
+                    NonFungibleLocalId::integer(nft_id),
+                    lookup.bucket("payment"),
+                )
+            });
+
+        self.execute(BUYER, builder)
+    }
+
+    fn accept_offer(&mut self, offer_id: NonFungibleLocalId, nft_id: u64) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_non_fungibles_from_account(
+                self.env.accounts[SELLER],
+                self.nft,
+                &BTreeSet::from([NonFungibleLocalId::integer(nft_id)]),
+            )
+            .take_all_from_worktop(self.nft, "nft")
+            .call_method_with_name_lookup(self.marketplace, "accept_offer", |lookup| {
+                (offer_id, lookup.bucket("nft"))
+            });
+
+        self.execute(SELLER, builder)
+    }
+
+    fn get_royalty_rate(&mut self) -> Decimal {
+        let builder = ManifestBuilder::new().call_method(
+            self.marketplace,
+            "get_royalty_rate",
+            manifest_args!(self.nft),
+        );
+
+        self.execute(SELLER, builder)
+            .expect_commit_success()
+            .output(0)
+    }
+
+    /// Ids of the non fungibles of the given resource held by the account
+    fn ids(&mut self, account: usize, res_address: ResourceAddress) -> Vec<NonFungibleLocalId> {
+        let vault_id = self
+            .env
+            .test_runner
+            .get_component_vaults(self.env.accounts[account], res_address)[0];
+        let (_, ids) = self
+            .env
+            .test_runner
+            .inspect_non_fungible_vault(vault_id)
+            .unwrap();
+
+        ids.collect()
+    }
+}
+
+#[test]
+fn test_buy_listing_and_close_it() {
+    let mut marketplace = TestMarketplace::new();
+    let (nft, payment, listing_receipt) = (
+        marketplace.nft,
+        marketplace.payment,
+        marketplace.listing_receipt,
+    );
+
+    marketplace
+        .list(vec![1, 2], dec!(100))
+        .expect_commit_success();
+    let listing_id = marketplace.ids(SELLER, listing_receipt)[0].clone();
+
+    marketplace
+        .buy(vec![listing_id.clone()], dec!(150))
+        .expect_commit_success();
+    assert_eq!(marketplace.env.balance(BUYER, nft), dec!(2));
+    assert_eq!(marketplace.env.balance(BUYER, payment), dec!(900));
+
+    marketplace
+        .buy(vec![listing_id.clone()], dec!(150))
+        .expect_commit_failure();
+
+    // Closing the sold listing returns the proceeds
+    marketplace
+        .close(SELLER, listing_receipt, listing_id, "close_listing")
+        .expect_commit_success();
+    assert_eq!(marketplace.env.balance(SELLER, payment), dec!(100));
+    assert_eq!(marketplace.env.balance(SELLER, listing_receipt), dec!(0));
+}
+
+#[test]
+fn test_buy_several_listings_at_once() {
+    let mut marketplace = TestMarketplace::new();
+    let (nft, payment, listing_receipt) = (
+        marketplace.nft,
+        marketplace.payment,
+        marketplace.listing_receipt,
+    );
+
+    marketplace.list(vec![1], dec!(100)).expect_commit_success();
+    marketplace.list(vec![2], dec!(50)).expect_commit_success();
+    let listing_ids = marketplace.ids(SELLER, listing_receipt);
+
+    marketplace
+        .buy(listing_ids.clone(), dec!(149))
+        .expect_commit_failure();
+    marketplace
+        .buy(listing_ids, dec!(150))
+        .expect_commit_success();
+
+    assert_eq!(marketplace.env.balance(BUYER, nft), dec!(2));
+    assert_eq!(marketplace.env.balance(BUYER, payment), dec!(850));
+}
+
+#[test]
+fn test_close_unsold_listing_returns_the_nfts() {
+    let mut marketplace = TestMarketplace::new();
+    let (nft, listing_receipt) = (marketplace.nft, marketplace.listing_receipt);
+
+    marketplace.list(vec![1], dec!(100)).expect_commit_success();
+    let listing_id = marketplace.ids(SELLER, listing_receipt)[0].clone();
+
+    marketplace
+        .close(SELLER, listing_receipt, listing_id.clone(), "close_listing")
+        .expect_commit_success();
+    assert_eq!(marketplace.env.balance(SELLER, nft), dec!(3));
+
+    marketplace
+        .buy(vec![listing_id], dec!(100))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_royalties_are_paid_to_the_creator() {
+    let mut marketplace = TestMarketplace::with_royalties();
+    let (payment, listing_receipt) = (marketplace.payment, marketplace.listing_receipt);
+
+    assert_eq!(marketplace.get_royalty_rate(), dec!("0.1"));
+
+    marketplace.list(vec![1], dec!(100)).expect_commit_success();
+    let listing_id = marketplace.ids(SELLER, listing_receipt)[0].clone();
+    marketplace
+        .buy(vec![listing_id.clone()], dec!(100))
+        .expect_commit_success();
+    marketplace
+        .close(SELLER, listing_receipt, listing_id, "close_listing")
+        .expect_commit_success();
+
+    assert_eq!(marketplace.env.balance(CREATOR, payment), dec!(10));
+    assert_eq!(marketplace.env.balance(SELLER, payment), dec!(90));
+}
+
+#[test]
+fn test_royalty_config_requires_the_creator_badge() {
+    let mut marketplace = TestMarketplace::new();
+
+    // The collection must be registered first
+    marketplace
+        .set_royalty_config(CREATOR, dec!("0.1"))
+        .expect_commit_failure();
+
+    marketplace.register_collection().expect_commit_success();
+    marketplace.register_collection().expect_commit_failure();
+
+    // A proof of another resource is rejected
+    let (nft, recipient) = (marketplace.nft, marketplace.env.accounts[CREATOR]);
+    let builder = ManifestBuilder::new()
+        .create_proof_from_account_of_amount(
+            marketplace.env.accounts[BUYER],
+            marketplace.payment,
+            dec!(1),
+        )
+        .pop_from_auth_zone("creator")
+        .call_method_with_name_lookup(marketplace.marketplace, "set_royalty_config", |lookup| {
+            (lookup.proof("creator"), nft, dec!("0.1"), recipient)
+        });
+    marketplace.execute(BUYER, builder).expect_commit_failure();
+
+    marketplace
+        .set_royalty_config(CREATOR, dec!("0.26"))
+        .expect_commit_failure();
+    marketplace
+        .set_royalty_config(CREATOR, dec!("0.25"))
+        .expect_commit_success();
+}
+
+#[test]
+fn test_register_collection_requires_the_owner() {
+    let mut marketplace = TestMarketplace::new();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            marketplace.marketplace,
+            "register_collection",
+            manifest_args!(marketplace.nft, marketplace.creator_badge),
+        )
+        .build();
+    marketplace
+        .env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![])
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_accepted_offer_pays_the_seller_and_royalties() {
+    let mut marketplace = TestMarketplace::with_royalties();
+    let (nft, payment, offer_receipt) = (
+        marketplace.nft,
+        marketplace.payment,
+        marketplace.offer_receipt,
+    );
+
+    marketplace.make_offer(2, dec!(80)).expect_commit_success();
+    let offer_id = marketplace.ids(BUYER, offer_receipt)[0].clone();
+
+    marketplace
+        .accept_offer(offer_id.clone(), 3)
+        .expect_commit_failure();
+    marketplace
+        .accept_offer(offer_id.clone(), 2)
+        .expect_commit_success();
+    assert_eq!(marketplace.env.balance(SELLER, payment), dec!(72));
+    assert_eq!(marketplace.env.balance(CREATOR, payment), dec!(8));
+
+    // The buyer gets the NFT by closing the offer
+    marketplace
+        .close(BUYER, offer_receipt, offer_id, "close_offer")
+        .expect_commit_success();
+    assert_eq!(marketplace.env.balance(BUYER, nft), dec!(1));
+    assert_eq!(marketplace.env.balance(BUYER, payment), dec!(920));
+}
+
+#[test]
+fn test_close_open_offer_refunds_the_payment() {
+    let mut marketplace = TestMarketplace::new();
+    let (payment, offer_receipt) = (marketplace.payment, marketplace.offer_receipt);
+
+    marketplace.make_offer(2, dec!(80)).expect_commit_success();
+    let offer_id = marketplace.ids(BUYER, offer_receipt)[0].clone();
+
+    marketplace
+        .close(BUYER, offer_receipt, offer_id.clone(), "close_offer")
+        .expect_commit_success();
+    assert_eq!(marketplace.env.balance(BUYER, payment), dec!(1000));
+
+    marketplace
+        .accept_offer(offer_id, 2)
+        .expect_commit_failure();
+}
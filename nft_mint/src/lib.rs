@@ -0,0 +1,381 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+/// Maximum number of NFTs minted in a single call
+pub const MAX_MINT_PER_CALL: u64 = 50;
+
+/// Maximum number of creator accounts sharing the proceeds
+pub const MAX_PAYEES: usize = 10;
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct CollectionItem {
+    pub name: String,
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct MintPhase {
+    pub start_epoch: u64,
+    pub price: Decimal,
+    /// Whether only allowlisted accounts can mint during the phase
+    pub allowlist_only: bool,
+    /// Maximum number of NFTs minted per account during the phase
+    pub max_per_account: u64,
+}
+
+#[derive(ScryptoSbor)]
+pub struct Payee {
+    pub account: Global<Account>,
+    /// Share of the proceeds sent to the account
+    pub share: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct MintedEvent {
+    pub account: ComponentAddress,
+    pub phase: u32,
+    pub first_id: u64,
+    pub count: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ProceedsDistributedEvent {
+    pub amount: Decimal,
+}
+
+#[blueprint]
+#[events(MintedEvent, ProceedsDistributedEvent)]
+pub mod nft_mint {
+
+    enable_method_auth! {
+        methods {
+
+            set_phases => restrict_to :[OWNER];
+            add_to_allowlist => restrict_to :[OWNER];
+            set_payees => restrict_to :[OWNER];
+
+            mint => PUBLIC;
+            distribute => PUBLIC;
+
+            get_current_phase => PUBLIC;
+            get_minted_count => PUBLIC;
+            get_mintable_count => PUBLIC;
+
+        }
+    }
+
+    pub struct NftMint {
+        /// Collection non-fungible resource manager
+        collection_res_manager: ResourceManager,
+        collection_name: String,
+
+        max_supply: u64,
+        minted_count: u64,
+
+        /// Mint phases, ordered by start epoch
+        phases: Vec<MintPhase>,
+
+        /// Remaining allowlist allowance of each account, indexed by phase
+        allowlists: KeyValueStore<(u32, ComponentAddress), u64>,
+
+        /// Number of NFTs minted by each account, indexed by phase
+        minted_per_account: KeyValueStore<(u32, ComponentAddress), u64>,
+
+        /// Primary sale proceeds not distributed yet
+        proceeds: Vault,
+        payees: Vec<Payee>,
+    }
+
+    impl NftMint {
+        pub fn instantiate(
+            collection_name: String,
+            max_supply: u64,
+            payment_res_address: ResourceAddress,
+            phases: Vec<MintPhase>,
+            payees: Vec<Payee>,
+            owner_role: OwnerRole,
+        ) -> (Global<NftMint>, ResourceAddress) {
+            /* CHECK INPUT */
+            assert!(max_supply > 0, "Max supply must be greater than zero");
+            assert_fungible_res_address(
+                payment_res_address,
+                Some("Payment resource must be fungible".to_string()),
+            );
+            NftMint::_assert_valid_phases(&phases);
+            NftMint::_assert_valid_payees(&payees);
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(NftMint::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let collection_res_manager =
+                ResourceBuilder::new_integer_non_fungible::<CollectionItem>(owner_role.clone())
+                    .metadata(metadata! {
+                        init {
+                            "name" => collection_name.clone(), locked;
+                        }
+                    })
+                    .mint_roles(mint_roles! {
+                        minter => component_rule;
+                        minter_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let mint_component = Self {
+                collection_res_manager,
+                collection_name,
+                max_supply,
+                minted_count: 0,
+                phases,
+                allowlists: KeyValueStore::new(),
+                minted_per_account: KeyValueStore::new(),
+                proceeds: Vault::new(payment_res_address),
+                payees,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (mint_component, collection_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn set_phases(&mut self, phases: Vec<MintPhase>) {
+            NftMint::_assert_valid_phases(&phases);
+
+            self.phases = phases;
+        }
+
+        /// Allow the accounts to mint up to `allowance` NFTs during an allowlist phase
+        pub fn add_to_allowlist(
+            &mut self,
+            phase: u32,
+            accounts: Vec<ComponentAddress>,
+            allowance: u64,
+        ) {
+            /* CHECK INPUT */
+            assert!((phase as usize) < self.phases.len(), "Phase not found");
+
+            for account in accounts {
+                self.allowlists.insert((phase, account), allowance);
+            }
+        }
+
+        /// Replace the payees. Proceeds collected so far are distributed first.
+        pub fn set_payees(&mut self, payees: Vec<Payee>) {
+            NftMint::_assert_valid_payees(&payees);
+
+            self.distribute();
+
+            self.payees = payees;
+        }
+
+        /* USER METHODS */
+
+        /// Mint `count` NFTs at the price of the current phase and deposit them into the
+        /// account. The proceeds are split between the payees. Returns the payment change.
+        pub fn mint(
+            &mut self,
+            mut account: Global<Account>,
+            mut payment: Bucket,
+            count: u64,
+        ) -> Bucket {
+            let account_address = account.address();
+            let phase_index = self.get_current_phase().expect("Mint has not started");
+            let phase = self.phases[phase_index as usize].clone();
+
+            /* CHECK INPUT */
+            assert!(
+                count > 0 && count <= MAX_MINT_PER_CALL,
+                "Count must be between 1 and {}",
+                MAX_MINT_PER_CALL
+            );
+            assert!(
+                count <= self.get_mintable_count(account_address),
+                "Mint limit exceeded"
+            );
+
+            let cost = phase.price * count;
+            assert!(payment.amount() >= cost, "Insufficient payment");
+
+            self.proceeds.put(
+                payment.take_advanced(cost, WithdrawStrategy::Rounded(RoundingMode::AwayFromZero)),
+            );
+
+            if phase.allowlist_only {
+                *self
+                    .allowlists
+                    .get_mut(&(phase_index, account_address))
+                    .unwrap() -= count;
+            }
+
+            let minted = self
+                .minted_per_account
+                .get(&(phase_index, account_address))
+                .map(|minted| *minted)
+                .unwrap_or(0);
+            self.minted_per_account
+                .insert((phase_index, account_address), minted + count);
+
+            let first_id = self.minted_count + 1;
+            self.minted_count += count;
+
+            let mut nfts = Bucket::new(self.collection_res_manager.address());
+            for id in first_id..first_id + count {
+                nfts.put(self.collection_res_manager.mint_non_fungible(
+                    &NonFungibleLocalId::integer(id),
+                    CollectionItem {
+                        name: format!("{} #{}", self.collection_name, id),
+                    },
+                ));
+            }
+
+            Runtime::emit_event(MintedEvent {
+                account: account_address,
+                phase: phase_index,
+                first_id,
+                count,
+            });
+
+            account.try_deposit_or_abort(nfts, None);
+
+            self.distribute();
+
+            payment
+        }
+
+        /// Send the proceeds not distributed yet to the payees, according to their shares.
+        /// The last payee receives the rounding remainder.
+        pub fn distribute(&mut self) {
+            let amount = self.proceeds.amount();
+
+            if amount == Decimal::ZERO {
+                return;
+            }
+
+            let last_index = self.payees.len() - 1;
+
+            for (index, payee) in self.payees.iter_mut().enumerate() {
+                let share = if index == last_index {
+                    self.proceeds.take_all()
+                } else {
+                    self.proceeds.take_advanced(
+                        amount * payee.share,
+                        WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                    )
+                };
+
+                payee.account.try_deposit_or_abort(share, None);
+            }
+
+            Runtime::emit_event(ProceedsDistributedEvent { amount });
+        }
+
+        /* GETTERS */
+
+        /// Index of the phase started last, if any
+        pub fn get_current_phase(&self) -> Option<u32> {
+            let current_epoch = Runtime::current_epoch().number();
+
+            self.phases
+                .iter()
+                .rposition(|phase| phase.start_epoch <= current_epoch)
+                .map(|index| index as u32)
+        }
+
+        pub fn get_minted_count(&self) -> u64 {
+            self.minted_count
+        }
+
+        /// Number of NFTs the account can still mint during the current phase
+        pub fn get_mintable_count(&self, account: ComponentAddress) -> u64 {
+            let phase_index = match self.get_current_phase() {
+                Some(phase_index) => phase_index,
+                None => return 0,
+            };
+            let phase = &self.phases[phase_index as usize];
+
+            let minted = self
+                .minted_per_account
+                .get(&(phase_index, account))
+                .map(|minted| *minted)
+                .unwrap_or(0);
+
+            let mut mintable = phase
+                .max_per_account
+                .saturating_sub(minted)
+                .min(self.max_supply - self.minted_count);
+
+            if phase.allowlist_only {
+                mintable = mintable.min(
+                    self.allowlists
+                        .get(&(phase_index, account))
+                        .map(|allowance| *allowance)
+                        .unwrap_or(0),
+                );
+            }
+
+            mintable
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_phases(phases: &[MintPhase]) {
+            assert!(!phases.is_empty(), "At least one phase is required");
+            assert!(
+                phases
+                    .windows(2)
+                    .all(|pair| pair[0].start_epoch < pair[1].start_epoch),
+                "Phases must be ordered by start epoch"
+            );
+            assert!(
+                phases.iter().all(|phase| phase.price >= Decimal::ZERO),
+                "Price must not be negative"
+            );
+        }
+
+        fn _assert_valid_payees(payees: &[Payee]) {
+            assert!(
+                !payees.is_empty() && payees.len() <= MAX_PAYEES,
+                "Number of payees must be between 1 and {}",
+                MAX_PAYEES
+            );
+            assert!(
+                payees.iter().all(|payee| payee.share > Decimal::ZERO),
+                "Payee shares must be greater than zero"
+            );
+            assert!(
+                payees
+                    .iter()
+                    .fold(Decimal::ZERO, |total, payee| total + payee.share)
+                    == Decimal::ONE,
+                "Payee shares must sum to one"
+            );
+        }
+    }
+}
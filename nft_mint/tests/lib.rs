@@ -0,0 +1,398 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the mint, and receives 75% of the proceeds
+const OWNER: usize = 0;
+/// Receives 25% of the proceeds
+const CREATOR: usize = 1;
+/// Holds 100 payment tokens
+const ALICE: usize = 2;
+/// Holds 100 payment tokens
+const BOB: usize = 3;
+
+/// Manifest encoding of a `MintPhase`
+fn mint_phase(
+    start_epoch: u64,
+    price: Decimal,
+    allowlist_only: bool,
+    max_per_account: u64,
+) -> ManifestValue {
+    ManifestValue::Tuple {
+        fields: vec![
+            to_manifest_value_and_unwrap!(&start_epoch),
+            to_manifest_value_and_unwrap!(&price),
+            to_manifest_value_and_unwrap!(&allowlist_only),
+            to_manifest_value_and_unwrap!(&max_per_account),
+        ],
+    }
+}
+
+/// Manifest encoding of a `Payee`
+fn payee(account: ComponentAddress, share: Decimal) -> ManifestValue {
+    ManifestValue::Tuple {
+        fields: vec![
+            to_manifest_value_and_unwrap!(&account),
+            to_manifest_value_and_unwrap!(&share),
+        ],
+    }
+}
+
+/// Collection of 5 NFTs at epoch 10, with an allowlist phase from epoch 20 at a price of 10
+/// and 2 NFTs per account, then a public phase from epoch 30 at a price of 20 and 3 NFTs per
+/// account
+struct TestMint {
+    env: TestEnv,
+    mint: ComponentAddress,
+    collection: ResourceAddress,
+    payment: ResourceAddress,
+}
+
+impl TestMint {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 4);
+        env.set_epoch(10);
+        let payment = env
+            .test_runner
+            .create_fungible_resource(dec!(200), 18, env.accounts[ALICE]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "NftMint",
+                "instantiate",
+                manifest_args!(
+                    "Test Collection",
+                    5u64,
+                    payment,
+                    vec![
+                        mint_phase(20, dec!(10), true, 2),
+                        mint_phase(30, dec!(20), false, 3)
+                    ],
+                    vec![
+                        payee(env.accounts[OWNER], dec!("0.75")),
+                        payee(env.accounts[CREATOR], dec!("0.25"))
+                    ],
+                    OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())))
+                ),
+            )
+            .withdraw_from_account(env.accounts[ALICE], payment, dec!(100))
+            .take_all_from_worktop(payment, "bob")
+            .call_method_with_name_lookup(env.accounts[BOB], "deposit", |lookup| {
+                (lookup.bucket("bob"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let (mint, collection): (ComponentAddress, ResourceAddress) =
+            receipt.expect_commit_success().output(0);
+
+        Self {
+            env,
+            mint,
+            collection,
+            payment,
+        }
+    }
+
+    /// Instantiate another collection with a single public phase at a price of 10, whose
+    /// proceeds go to the owner
+    fn instantiate(
+        &mut self,
+        max_supply: u64,
+        payment_res_address: ResourceAddress,
+    ) -> TransactionReceipt {
+        let owner = self.env.accounts[OWNER];
+        let owner_role = OwnerRole::Fixed(rule!(require(self.env.badges[OWNER].clone())));
+
+        self.env.call_function(
+            OWNER,
+            "NftMint",
+            "instantiate",
+            manifest_args!(
+                "Other Collection",
+                max_supply,
+                payment_res_address,
+                vec![mint_phase(10, dec!(10), false, 1)],
+                vec![payee(owner, dec!(1))],
+                owner_role
+            ),
+        )
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.mint, method, args)
+    }
+
+    /// Mint NFTs into the account, paying with the given amount of payment tokens
+    fn mint(&mut self, account: usize, amount: Decimal, count: u64) -> TransactionReceipt {
+        let (account_address, payment) = (self.env.accounts[account], self.payment);
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(account_address, payment, amount)
+            .take_all_from_worktop(payment, "payment")
+            .call_method_with_name_lookup(self.mint, "mint", |lookup| {
+                (account_address, lookup.bucket("payment"), count)
+            });
+
+        self.env.execute(account, builder)
+    }
+}
+
+#[test]
+fn test_mint_phases_and_proceeds_split() {
+    let mut mint = TestMint::new();
+    let (collection, payment) = (mint.collection, mint.payment);
+    let alice = mint.env.accounts[ALICE];
+
+    mint.mint(ALICE, dec!(10), 1).expect_commit_failure();
+
+    // Only allowlisted accounts mint during the first phase
+    mint.env.set_epoch(20);
+    mint.mint(BOB, dec!(10), 1).expect_commit_failure();
+    mint.call(
+        OWNER,
+        "add_to_allowlist",
+        manifest_args!(0u32, vec![alice], 1u64),
+    )
+    .expect_commit_success();
+    mint.mint(ALICE, dec!(20), 2).expect_commit_failure();
+    mint.mint(ALICE, dec!(15), 1).expect_commit_success();
+
+    assert_eq!(mint.env.balance(ALICE, collection), dec!(1));
+    assert_eq!(mint.env.balance(ALICE, payment), dec!(90));
+    assert_eq!(mint.env.balance(OWNER, payment), dec!("7.5"));
+    assert_eq!(mint.env.balance(CREATOR, payment), dec!("2.5"));
+
+    mint.env.set_epoch(30);
+    mint.mint(BOB, dec!(60), 3).expect_commit_success();
+    mint.mint(BOB, dec!(20), 1).expect_commit_failure();
+    assert_eq!(mint.env.balance(BOB, collection), dec!(3));
+
+    // A single NFT is left in the supply
+    mint.mint(ALICE, dec!(40), 2).expect_commit_failure();
+    mint.mint(ALICE, dec!(19), 1).expect_commit_failure();
+    mint.mint(ALICE, dec!(20), 1).expect_commit_success();
+
+    let minted_count: u64 = mint
+        .call(ALICE, "get_minted_count", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(minted_count, 5);
+
+    assert_eq!(mint.env.balance(OWNER, payment), dec!("67.5"));
+    assert_eq!(mint.env.balance(CREATOR, payment), dec!("22.5"));
+}
+
+#[test]
+fn test_admin_methods_require_the_owner() {
+    let mut mint = TestMint::new();
+    let (owner, creator) = (mint.env.accounts[OWNER], mint.env.accounts[CREATOR]);
+    let payment = mint.payment;
+
+    mint.call(
+        ALICE,
+        "set_phases",
+        manifest_args!(vec![mint_phase(10, dec!(5), false, 1)]),
+    )
+    .expect_commit_failure();
+    mint.call(
+        OWNER,
+        "set_phases",
+        manifest_args!(vec![
+            mint_phase(20, dec!(5), false, 1),
+            mint_phase(10, dec!(5), false, 1)
+        ]),
+    )
+    .expect_commit_failure();
+    mint.call(
+        OWNER,
+        "set_phases",
+        manifest_args!(vec![mint_phase(10, dec!(5), false, 1)]),
+    )
+    .expect_commit_success();
+
+    mint.call(
+        OWNER,
+        "add_to_allowlist",
+        manifest_args!(1u32, vec![owner], 1u64),
+    )
+    .expect_commit_failure();
+
+    mint.call(
+        ALICE,
+        "set_payees",
+        manifest_args!(vec![payee(creator, dec!(1))]),
+    )
+    .expect_commit_failure();
+    mint.call(
+        OWNER,
+        "set_payees",
+        manifest_args!(vec![payee(owner, dec!("0.5")), payee(creator, dec!("0.4"))]),
+    )
+    .expect_commit_failure();
+    mint.call(
+        OWNER,
+        "set_payees",
+        manifest_args!(vec![payee(creator, dec!(1))]),
+    )
+    .expect_commit_success();
+
+    mint.mint(ALICE, dec!(5), 1).expect_commit_success();
+    assert_eq!(mint.env.balance(CREATOR, payment), dec!(5));
+    assert_eq!(mint.env.balance(OWNER, payment), dec!(0));
+}
+
+#[test]
+fn test_mintable_counts_follow_the_phases() {
+    let mut mint = TestMint::new();
+    let (alice, payment) = (mint.env.accounts[ALICE], mint.payment);
+
+    let get_mintable_count = |mint: &mut TestMint| -> u64 {
+        mint.call(ALICE, "get_mintable_count", manifest_args!(alice))
+            .expect_commit_success()
+            .output(0)
+    };
+    let get_current_phase = |mint: &mut TestMint| -> Option<u32> {
+        mint.call(ALICE, "get_current_phase", manifest_args!())
+            .expect_commit_success()
+            .output(0)
+    };
+
+    assert_eq!(get_current_phase(&mut mint), None);
+    assert_eq!(get_mintable_count(&mut mint), 0);
+
+    // The allowance is capped by the limit per account of the phase
+    mint.env.set_epoch(20);
+    assert_eq!(get_current_phase(&mut mint), Some(0));
+    assert_eq!(get_mintable_count(&mut mint), 0);
+    mint.call(
+        OWNER,
+        "add_to_allowlist",
+        manifest_args!(0u32, vec![alice], 5u64),
+    )
+    .expect_commit_success();
+    assert_eq!(get_mintable_count(&mut mint), 2);
+    mint.mint(ALICE, dec!(20), 2).expect_commit_success();
+    assert_eq!(get_mintable_count(&mut mint), 0);
+
+    // Mints of the previous phase do not count, but the remaining supply does
+    mint.env.set_epoch(30);
+    assert_eq!(get_current_phase(&mut mint), Some(1));
+    assert_eq!(get_mintable_count(&mut mint), 3);
+    mint.mint(BOB, dec!(40), 2).expect_commit_success();
+    assert_eq!(get_mintable_count(&mut mint), 1);
+
+    // A free phase only returns the payment
+    mint.call(
+        OWNER,
+        "set_phases",
+        manifest_args!(vec![mint_phase(30, dec!(0), false, 1)]),
+    )
+    .expect_commit_success();
+    mint.mint(ALICE, dec!(5), 1).expect_commit_success();
+    assert_eq!(mint.env.balance(ALICE, payment), dec!(80));
+    assert_eq!(get_mintable_count(&mut mint), 0);
+    mint.call(BOB, "distribute", manifest_args!())
+        .expect_commit_success();
+}
+
+#[test]
+fn test_failures_carry_mint_messages() {
+    let mut mint = TestMint::new();
+    let (owner, creator) = (mint.env.accounts[OWNER], mint.env.accounts[CREATOR]);
+    let payment = mint.payment;
+
+    expect_failure_containing(mint.mint(ALICE, dec!(10), 1), "Mint has not started");
+
+    mint.env.set_epoch(30);
+    expect_failure_containing(
+        mint.mint(ALICE, dec!(10), 0),
+        "Count must be between 1 and 50",
+    );
+    expect_failure_containing(
+        mint.mint(ALICE, dec!(100), 51),
+        "Count must be between 1 and 50",
+    );
+    expect_failure_containing(mint.mint(ALICE, dec!(80), 4), "Mint limit exceeded");
+    expect_failure_containing(mint.mint(ALICE, dec!(19), 1), "Insufficient payment");
+    expect_failure_containing(
+        mint.call(
+            OWNER,
+            "add_to_allowlist",
+            manifest_args!(2u32, vec![owner], 1u64),
+        ),
+        "Phase not found",
+    );
+
+    expect_failure_containing(
+        mint.call(
+            OWNER,
+            "set_phases",
+            manifest_args!(Vec::<ManifestValue>::new()),
+        ),
+        "At least one phase is required",
+    );
+    expect_failure_containing(
+        mint.call(
+            OWNER,
+            "set_phases",
+            manifest_args!(vec![
+                mint_phase(20, dec!(5), false, 1),
+                mint_phase(20, dec!(5), false, 1)
+            ]),
+        ),
+        "Phases must be ordered by start epoch",
+    );
+    expect_failure_containing(
+        mint.call(
+            OWNER,
+            "set_phases",
+            manifest_args!(vec![mint_phase(20, dec!(-1), false, 1)]),
+        ),
+        "Price must not be negative",
+    );
+
+    expect_failure_containing(
+        mint.call(
+            OWNER,
+            "set_payees",
+            manifest_args!(Vec::<ManifestValue>::new()),
+        ),
+        "Number of payees must be between 1 and 10",
+    );
+    expect_failure_containing(
+        mint.call(
+            OWNER,
+            "set_payees",
+            manifest_args!(vec![payee(owner, dec!(1)), payee(creator, dec!(0))]),
+        ),
+        "Payee shares must be greater than zero",
+    );
+    expect_failure_containing(
+        mint.call(
+            OWNER,
+            "set_payees",
+            manifest_args!(vec![payee(owner, dec!("0.5")), payee(creator, dec!("0.6"))]),
+        ),
+        "Payee shares must sum to one",
+    );
+
+    expect_failure_containing(
+        mint.instantiate(0, payment),
+        "Max supply must be greater than zero",
+    );
+    let nft = mint.collection;
+    expect_failure_containing(
+        mint.instantiate(5, nft),
+        "Payment resource must be fungible",
+    );
+    mint.instantiate(5, payment).expect_commit_success();
+}
@@ -0,0 +1,352 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct ListingReceipt {
+    pub nft: NonFungibleGlobalId,
+}
+
+/// Non-transferable badge granting the use of a rented NFT until its expiry
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct UsageBadge {
+    pub nft: NonFungibleGlobalId,
+    pub listing_id: NonFungibleLocalId,
+    pub expires_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor)]
+pub struct Listing {
+    /// Escrowed NFT
+    pub nft: Vault,
+    pub nft_id: NonFungibleGlobalId,
+
+    /// Rental payments not withdrawn by the owner yet
+    pub proceeds: Vault,
+    pub price_per_epoch: Decimal,
+    pub max_rental_epochs: u64,
+
+    /// Epoch at which the current rental ends
+    pub rented_until_epoch: u64,
+    pub delisted: bool,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RentedEvent {
+    pub listing_id: NonFungibleLocalId,
+    pub renter: ComponentAddress,
+    pub epochs: u64,
+    pub expires_at_epoch: u64,
+}
+
+#[blueprint]
+#[events(RentedEvent)]
+pub mod nft_rental {
+
+    enable_method_auth! {
+        methods {
+
+            list => PUBLIC;
+            update_listing => PUBLIC;
+            delist => PUBLIC;
+            withdraw_proceeds => PUBLIC;
+
+            rent => PUBLIC;
+
+            check_usage => PUBLIC;
+            is_rented => PUBLIC;
+
+        }
+    }
+
+    pub struct NftRental {
+        /// Listing receipt non-fungible resource manager
+        listing_res_manager: ResourceManager,
+        listings: KeyValueStore<NonFungibleLocalId, Listing>,
+
+        /// Usage badge non-fungible resource manager
+        usage_res_manager: ResourceManager,
+
+        payment_res_address: ResourceAddress,
+    }
+
+    impl NftRental {
+        pub fn instantiate(
+            payment_res_address: ResourceAddress,
+            owner_role: OwnerRole,
+        ) -> (Global<NftRental>, ResourceAddress, ResourceAddress) {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                payment_res_address,
+                Some("Payment resource must be fungible".to_string()),
+            );
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(NftRental::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let listing_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<ListingReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule.clone();
+                        burner_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let usage_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<UsageBadge>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule;
+                        minter_updater => rule!(deny_all);
+                    })
+                    .withdraw_roles(withdraw_roles! {
+                        withdrawer => rule!(deny_all);
+                        withdrawer_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let rental_component = Self {
+                listing_res_manager,
+                listings: KeyValueStore::new(),
+                usage_res_manager,
+                payment_res_address,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (
+                rental_component,
+                listing_res_manager.address(),
+                usage_res_manager.address(),
+            )
+        }
+
+        /* OWNER METHODS */
+
+        /// Escrow an NFT and list it for rental. Returns the listing receipt.
+        pub fn list(
+            &mut self,
+            nft: Bucket,
+            price_per_epoch: Decimal,
+            max_rental_epochs: u64,
+        ) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                nft.amount() == Decimal::ONE,
+                "Exactly one NFT must be listed"
+            );
+            NftRental::_assert_valid_terms(price_per_epoch, max_rental_epochs);
+
+            let nft_id = NonFungibleGlobalId::new(
+                nft.resource_address(),
+                nft.as_non_fungible().non_fungible_local_id(),
+            );
+
+            let receipt = self
+                .listing_res_manager
+                .mint_ruid_non_fungible(ListingReceipt {
+                    nft: nft_id.clone(),
+                });
+
+            self.listings.insert(
+                receipt.as_non_fungible().non_fungible_local_id(),
+                Listing {
+                    nft: Vault::with_bucket(nft),
+                    nft_id,
+                    proceeds: Vault::new(self.payment_res_address),
+                    price_per_epoch,
+                    max_rental_epochs,
+                    rented_until_epoch: 0,
+                    delisted: false,
+                },
+            );
+
+            receipt
+        }
+
+        /// Update the rental terms. The current rental is not affected.
+        pub fn update_listing(
+            &mut self,
+            listing_proof: Proof,
+            price_per_epoch: Decimal,
+            max_rental_epochs: u64,
+        ) {
+            let listing_id = self._check_listing_proof(listing_proof);
+
+            /* CHECK INPUT */
+            NftRental::_assert_valid_terms(price_per_epoch, max_rental_epochs);
+
+            let mut listing = self.listings.get_mut(&listing_id).unwrap();
+            listing.price_per_epoch = price_per_epoch;
+            listing.max_rental_epochs = max_rental_epochs;
+        }
+
+        /// Return the NFT to its owner once the current rental has ended, burning the listing
+        /// receipt. Returns the NFT and the remaining proceeds.
+        pub fn delist(&mut self, listing_receipt: Bucket) -> (Bucket, Bucket) {
+            /* CHECK INPUT */
+            assert!(
+                listing_receipt.resource_address() == self.listing_res_manager.address(),
+                "Listing receipt resource address mismatch"
+            );
+
+            let listing_id = listing_receipt.as_non_fungible().non_fungible_local_id();
+            listing_receipt.burn();
+
+            let mut listing = self.listings.get_mut(&listing_id).unwrap();
+
+            assert!(
+                Runtime::current_epoch().number() >= listing.rented_until_epoch,
+                "NFT is rented"
+            );
+
+            listing.delisted = true;
+
+            (listing.nft.take_all(), listing.proceeds.take_all())
+        }
+
+        pub fn withdraw_proceeds(&mut self, listing_proof: Proof) -> Bucket {
+            let listing_id = self._check_listing_proof(listing_proof);
+
+            self.listings
+                .get_mut(&listing_id)
+                .unwrap()
+                .proceeds
+                .take_all()
+        }
+
+        /* RENTER METHODS */
+
+        /// Rent a listed NFT for the given number of epochs, paying upfront. The usage badge
+        /// is deposited into the renter account and can not be transferred. Returns the
+        /// payment change.
+        pub fn rent(
+            &mut self,
+            listing_id: NonFungibleLocalId,
+            mut renter: Global<Account>,
+            mut payment: Bucket,
+            epochs: u64,
+        ) -> Bucket {
+            let current_epoch = Runtime::current_epoch().number();
+
+            let mut listing = self
+                .listings
+                .get_mut(&listing_id)
+                .expect("Listing not found");
+
+            /* CHECK INPUT */
+            assert!(!listing.delisted, "Listing is closed");
+            assert!(current_epoch >= listing.rented_until_epoch, "NFT is rented");
+            assert!(
+                epochs > 0 && epochs <= listing.max_rental_epochs,
+                "Rental duration must be between 1 and {} epochs",
+                listing.max_rental_epochs
+            );
+
+            let cost = listing.price_per_epoch * epochs;
+            assert!(payment.amount() >= cost, "Insufficient payment");
+
+            listing.proceeds.put(
+                payment.take_advanced(cost, WithdrawStrategy::Rounded(RoundingMode::AwayFromZero)),
+            );
+
+            let expires_at_epoch = current_epoch + epochs;
+            listing.rented_until_epoch = expires_at_epoch;
+
+            let nft = listing.nft_id.clone();
+
+            drop(listing);
+
+            let usage_badge = self.usage_res_manager.mint_ruid_non_fungible(UsageBadge {
+                nft,
+                listing_id: listing_id.clone(),
+                expires_at_epoch,
+            });
+
+            Runtime::emit_event(RentedEvent {
+                listing_id,
+                renter: renter.address(),
+                epochs,
+                expires_at_epoch,
+            });
+
+            renter.try_deposit_or_abort(usage_badge, None);
+
+            payment
+        }
+
+        /* GETTERS */
+
+        /// Check a usage badge proof, failing if the rental has expired. Returns the rented
+        /// NFT.
+        pub fn check_usage(&self, usage_proof: Proof) -> NonFungibleGlobalId {
+            let usage_badge = usage_proof
+                .check(self.usage_res_manager.address())
+                .as_non_fungible()
+                .non_fungible::<UsageBadge>()
+                .data();
+
+            assert!(
+                Runtime::current_epoch().number() < usage_badge.expires_at_epoch,
+                "Rental has expired"
+            );
+
+            usage_badge.nft
+        }
+
+        pub fn is_rented(&self, listing_id: NonFungibleLocalId) -> bool {
+            let listing = self.listings.get(&listing_id).expect("Listing not found");
+
+            Runtime::current_epoch().number() < listing.rented_until_epoch
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_terms(price_per_epoch: Decimal, max_rental_epochs: u64) {
+            assert!(
+                price_per_epoch >= Decimal::ZERO,
+                "Price must not be negative"
+            );
+            assert!(
+                max_rental_epochs > 0,
+                "Max rental duration must be greater than zero"
+            );
+        }
+
+        fn _check_listing_proof(&self, listing_proof: Proof) -> NonFungibleLocalId {
+            listing_proof
+                .check(self.listing_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+    }
+}
@@ -0,0 +1,336 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+const OWNER: usize = 0;
+/// Holds the NFTs 1 to 3 of the collection
+const LANDLORD: usize = 1;
+/// Holds 100 payment tokens
+const RENTER: usize = 2;
+
+/// Rental listing the NFT 1 of the landlord at a price of 2 per epoch for up to 10 epochs, at
+/// epoch 10
+struct TestRental {
+    env: TestEnv,
+    rental: ComponentAddress,
+    listing: ResourceAddress,
+    usage: ResourceAddress,
+    collection: ResourceAddress,
+    payment: ResourceAddress,
+}
+
+impl TestRental {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let collection = env
+            .test_runner
+            .create_non_fungible_resource(env.accounts[LANDLORD]);
+        let payment = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[RENTER]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "NftRental",
+                "instantiate",
+                manifest_args!(
+                    payment,
+                    OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())))
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let (rental, listing, usage): (ComponentAddress, ResourceAddress, ResourceAddress) =
+            receipt.expect_commit_success().output(0);
+
+        let mut rental = Self {
+            env,
+            rental,
+            listing,
+            usage,
+            collection,
+            payment,
+        };
+
+        rental.list(&[1], dec!(2)).expect_commit_success();
+
+        rental
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.rental, method, args)
+    }
+
+    /// List the given NFTs of the landlord for up to 10 epochs
+    fn list(&mut self, nft_ids: &[u64], price_per_epoch: Decimal) -> TransactionReceipt {
+        let collection = self.collection;
+        let nft_ids: BTreeSet<NonFungibleLocalId> = nft_ids
+            .iter()
+            .map(|id| NonFungibleLocalId::integer(*id))
+            .collect();
+        let builder = ManifestBuilder::new()
+            .withdraw_non_fungibles_from_account(self.env.accounts[LANDLORD], collection, &nft_ids)
+            .take_all_from_worktop(collection, "nft")
+            .call_method_with_name_lookup(self.rental, "list", |lookup| {
+                (lookup.bucket("nft"), price_per_epoch, 10u64)
+            });
+
+        self.env.execute(LANDLORD, builder)
+    }
+
+    /// Id of the only listing of the landlord
+    fn listing_id(&mut self) -> NonFungibleLocalId {
+        let vault_id = self
+            .env
+            .test_runner
+            .get_component_vaults(self.env.accounts[LANDLORD], self.listing)[0];
+        let (_, mut ids) = self
+            .env
+            .test_runner
+            .inspect_non_fungible_vault(vault_id)
+            .unwrap();
+
+        ids.next().unwrap()
+    }
+
+    fn rent(&mut self, amount: Decimal, epochs: u64) -> TransactionReceipt {
+        let listing_id = self.listing_id();
+
+        self.rent_listing(listing_id, amount, epochs)
+    }
+
+    /// Rent the given listing, which may no longer be held by the landlord
+    fn rent_listing(
+        &mut self,
+        listing_id: NonFungibleLocalId,
+        amount: Decimal,
+        epochs: u64,
+    ) -> TransactionReceipt {
+        let (renter, payment) = (self.env.accounts[RENTER], self.payment);
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(renter, payment, amount)
+            .take_all_from_worktop(payment, "payment")
+            .call_method_with_name_lookup(self.rental, "rent", |lookup| {
+                (listing_id, renter, lookup.bucket("payment"), epochs)
+            });
+
+        self.env.execute(RENTER, builder)
+    }
+
+    /// Call a method with a proof of the listing receipt of the landlord
+    fn call_with_listing<A: ResolvableArguments>(
+        &mut self,
+        method: &str,
+        args: impl FnOnce(ManifestProof) -> A,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(self.env.accounts[LANDLORD], self.listing, dec!(1))
+            .pop_from_auth_zone("listing")
+            .call_method_with_name_lookup(self.rental, method, |lookup| {
+                args(lookup.proof("listing"))
+            });
+
+        self.env.execute(LANDLORD, builder)
+    }
+
+    fn check_usage(&mut self) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(self.env.accounts[RENTER], self.usage, dec!(1))
+            .pop_from_auth_zone("usage")
+            .call_method_with_name_lookup(self.rental, "check_usage", |lookup| {
+                (lookup.proof("usage"),)
+            });
+
+        self.env.execute(RENTER, builder)
+    }
+
+    fn delist(&mut self) -> TransactionReceipt {
+        let listing = self.listing;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[LANDLORD], listing, dec!(1))
+            .take_all_from_worktop(listing, "receipt")
+            .call_method_with_name_lookup(self.rental, "delist", |lookup| {
+                (lookup.bucket("receipt"),)
+            });
+
+        self.env.execute(LANDLORD, builder)
+    }
+}
+
+#[test]
+fn test_rented_nft_is_returned_after_expiry() {
+    let mut rental = TestRental::new();
+    let (collection, payment, usage) = (rental.collection, rental.payment, rental.usage);
+
+    rental.rent(dec!(30), 10).expect_commit_success();
+    assert_eq!(rental.env.balance(RENTER, payment), dec!(80));
+    assert_eq!(rental.env.balance(RENTER, usage), dec!(1));
+
+    let nft: NonFungibleGlobalId = rental.check_usage().expect_commit_success().output(2);
+    assert_eq!(
+        nft,
+        NonFungibleGlobalId::new(collection, NonFungibleLocalId::integer(1))
+    );
+
+    // The NFT is rented until epoch 20
+    rental.rent(dec!(2), 1).expect_commit_failure();
+    rental.delist().expect_commit_failure();
+
+    rental
+        .call_with_listing("withdraw_proceeds", |proof| (proof,))
+        .expect_commit_success();
+    assert_eq!(rental.env.balance(LANDLORD, payment), dec!(20));
+
+    // The usage badge can not be transferred
+    let builder =
+        ManifestBuilder::new().withdraw_from_account(rental.env.accounts[RENTER], usage, dec!(1));
+    rental.env.execute(RENTER, builder).expect_commit_failure();
+
+    rental.env.set_epoch(20);
+    rental.check_usage().expect_commit_failure();
+
+    let listing_id = rental.listing_id();
+    let is_rented: bool = rental
+        .call(RENTER, "is_rented", manifest_args!(listing_id))
+        .expect_commit_success()
+        .output(0);
+    assert!(!is_rented);
+
+    rental.delist().expect_commit_success();
+    assert_eq!(rental.env.balance(LANDLORD, collection), dec!(3));
+}
+
+#[test]
+fn test_rental_terms_are_enforced() {
+    let mut rental = TestRental::new();
+
+    rental.list(&[2, 3], dec!(2)).expect_commit_failure();
+    rental.list(&[2], dec!(-1)).expect_commit_failure();
+
+    rental.rent(dec!(30), 11).expect_commit_failure();
+    rental.rent(dec!(19), 10).expect_commit_failure();
+    rental.rent(dec!(10), 0).expect_commit_failure();
+
+    rental
+        .call_with_listing("update_listing", |proof| (proof, dec!(-1), 2u64))
+        .expect_commit_failure();
+    rental
+        .call_with_listing("update_listing", |proof| (proof, dec!(5), 2u64))
+        .expect_commit_success();
+
+    rental.rent(dec!(15), 3).expect_commit_failure();
+    rental.rent(dec!(10), 2).expect_commit_success();
+}
+
+#[test]
+fn test_nft_is_rented_again_until_delisted() {
+    let mut rental = TestRental::new();
+    let (collection, payment) = (rental.collection, rental.payment);
+    let listing_id = rental.listing_id();
+
+    // The rental ends at epoch 12, from which the NFT can be rented again
+    rental.rent(dec!(4), 2).expect_commit_success();
+    rental.env.set_epoch(12);
+    let is_rented: bool = rental
+        .call(RENTER, "is_rented", manifest_args!(listing_id.clone()))
+        .expect_commit_success()
+        .output(0);
+    assert!(!is_rented);
+
+    // Updated terms only apply to the next rentals
+    rental.rent(dec!(2), 1).expect_commit_success();
+    rental
+        .call_with_listing("update_listing", |proof| (proof, dec!(5), 1u64))
+        .expect_commit_success();
+    rental.env.set_epoch(13);
+    rental.rent(dec!(4), 1).expect_commit_failure();
+    rental.rent(dec!(5), 1).expect_commit_success();
+    assert_eq!(rental.env.balance(RENTER, payment), dec!(89));
+
+    // Delisting returns the NFT with the proceeds not withdrawn yet
+    rental.env.set_epoch(14);
+    rental.delist().expect_commit_success();
+    assert_eq!(rental.env.balance(LANDLORD, collection), dec!(3));
+    assert_eq!(rental.env.balance(LANDLORD, payment), dec!(11));
+
+    expect_failure_containing(
+        rental.rent_listing(listing_id, dec!(5), 1),
+        "Listing is closed",
+    );
+}
+
+#[test]
+fn test_failures_carry_rental_messages() {
+    let mut rental = TestRental::new();
+    let (collection, payment) = (rental.collection, rental.payment);
+
+    expect_failure_containing(
+        rental.list(&[2, 3], dec!(2)),
+        "Exactly one NFT must be listed",
+    );
+    expect_failure_containing(rental.list(&[2], dec!(-1)), "Price must not be negative");
+    expect_failure_containing(
+        rental.call_with_listing("update_listing", |proof| (proof, dec!(2), 0u64)),
+        "Max rental duration must be greater than zero",
+    );
+
+    expect_failure_containing(
+        rental.rent(dec!(30), 11),
+        "Rental duration must be between 1 and 10 epochs",
+    );
+    expect_failure_containing(rental.rent(dec!(19), 10), "Insufficient payment");
+    expect_failure_containing(
+        rental.rent_listing(NonFungibleLocalId::ruid([0; 32]), dec!(2), 1),
+        "Listing not found",
+    );
+
+    rental.rent(dec!(2), 1).expect_commit_success();
+    expect_failure_containing(rental.rent(dec!(2), 1), "NFT is rented");
+    expect_failure_containing(rental.delist(), "NFT is rented");
+
+    rental.env.set_epoch(11);
+    expect_failure_containing(rental.check_usage(), "Rental has expired");
+
+    let builder =
+        ManifestBuilder::new()
+            .withdraw_from_account(rental.env.accounts[LANDLORD], collection, dec!(1))
+            .take_all_from_worktop(collection, "receipt")
+            .call_method_with_name_lookup(rental.rental, "delist", |lookup| {
+                (lookup.bucket("receipt"),)
+            });
+    expect_failure_containing(
+        rental.env.execute(LANDLORD, builder),
+        "Listing receipt resource address mismatch",
+    );
+
+    let owner_role = OwnerRole::Fixed(rule!(require(rental.env.badges[OWNER].clone())));
+    expect_failure_containing(
+        rental.env.call_function(
+            OWNER,
+            "NftRental",
+            "instantiate",
+            manifest_args!(collection, owner_role.clone()),
+        ),
+        "Payment resource must be fungible",
+    );
+    rental
+        .env
+        .call_function(
+            OWNER,
+            "NftRental",
+            "instantiate",
+            manifest_args!(payment, owner_role),
+        )
+        .expect_commit_success();
+}
@@ -0,0 +1,459 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::{assert_fungible_res_address, assert_non_fungible_res_address};
+use scrypto::prelude::*;
+
+/// Maximum number of NFTs staked in a single position
+pub const MAX_NFTS_PER_POSITION: usize = 100;
+
+/// Data of any non fungible resource, decoded without knowing its schema
+#[derive(ScryptoSbor)]
+#[sbor(transparent)]
+pub struct RawNftData(pub ScryptoValue);
+
+impl NonFungibleData for RawNftData {
+    const MUTABLE_FIELDS: &'static [&'static str] = &[];
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct StakeReceipt {
+    pub created_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor)]
+pub struct StakePosition {
+    pub nfts: Vault,
+
+    /// Reward weight of each staked NFT, set when it was staked
+    pub weights: IndexMap<NonFungibleLocalId, Decimal>,
+    pub total_weight: Decimal,
+
+    /// Value of the reward per weight accumulator when the rewards were last computed
+    pub reward_per_weight_paid: PreciseDecimal,
+
+    /// Rewards computed but not claimed yet
+    pub pending_rewards: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RewardsClaimedEvent {
+    pub position_id: NonFungibleLocalId,
+    pub amount: Decimal,
+}
+
+#[blueprint]
+#[events(RewardsClaimedEvent)]
+pub mod nft_staking {
+
+    enable_method_auth! {
+        methods {
+
+            set_reward_rate => restrict_to :[OWNER];
+            set_rarity_multipliers => restrict_to :[OWNER];
+            top_up_rewards => PUBLIC;
+
+            stake => PUBLIC;
+            add_stake => PUBLIC;
+            unstake => PUBLIC;
+            claim => PUBLIC;
+
+            get_pending_rewards => PUBLIC;
+            get_nft_weight => PUBLIC;
+            get_total_weight => PUBLIC;
+
+        }
+    }
+
+    pub struct NftStaking {
+        collection_res_address: ResourceAddress,
+
+        /// Rewards funded but not distributed yet, and distributed rewards not claimed yet
+        rewards: Vault,
+
+        /// Part of the rewards vault not distributed yet
+        reward_budget: Decimal,
+
+        /// Rewards distributed every epoch per staked NFT of weight 1, while the budget lasts
+        reward_rate_per_epoch: Decimal,
+
+        /// Rewards distributed per unit of weight since instantiation
+        reward_per_weight: PreciseDecimal,
+        last_update_epoch: u64,
+
+        /// Sum of the weights of all staked NFTs
+        total_weight: Decimal,
+
+        /// Index of the NFT data field holding the rarity, if multipliers are enabled
+        rarity_field_index: Option<usize>,
+
+        /// Weight of the NFTs, indexed by rarity. NFTs with an unknown rarity have weight 1.
+        rarity_multipliers: IndexMap<String, Decimal>,
+
+        /// Stake receipt non-fungible resource manager
+        receipt_res_manager: ResourceManager,
+        positions: KeyValueStore<NonFungibleLocalId, StakePosition>,
+    }
+
+    impl NftStaking {
+        pub fn instantiate(
+            collection_res_address: ResourceAddress,
+            reward_res_address: ResourceAddress,
+            reward_rate_per_epoch: Decimal,
+            rarity_field_index: Option<usize>,
+            rarity_multipliers: IndexMap<String, Decimal>,
+            owner_role: OwnerRole,
+        ) -> (Global<NftStaking>, ResourceAddress) {
+            /* CHECK INPUT */
+            assert_non_fungible_res_address(
+                collection_res_address,
+                Some("Collection must be non fungible".to_string()),
+            );
+            assert_fungible_res_address(
+                reward_res_address,
+                Some("Reward resource must be fungible".to_string()),
+            );
+            assert!(
+                reward_rate_per_epoch >= Decimal::ZERO,
+                "Reward rate must not be negative"
+            );
+            NftStaking::_assert_valid_multipliers(&rarity_multipliers);
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(NftStaking::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let receipt_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<StakeReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule;
+                        minter_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let staking_component = Self {
+                collection_res_address,
+                rewards: Vault::new(reward_res_address),
+                reward_budget: Decimal::ZERO,
+                reward_rate_per_epoch,
+                reward_per_weight: PreciseDecimal::ZERO,
+                last_update_epoch: Runtime::current_epoch().number(),
+                total_weight: Decimal::ZERO,
+                rarity_field_index,
+                rarity_multipliers,
+                receipt_res_manager,
+                positions: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (staking_component, receipt_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn set_reward_rate(&mut self, reward_rate_per_epoch: Decimal) {
+            assert!(
+                reward_rate_per_epoch >= Decimal::ZERO,
+                "Reward rate must not be negative"
+            );
+
+            // Rewards distributed so far use the previous rate
+            self._update_reward_per_weight();
+
+            self.reward_rate_per_epoch = reward_rate_per_epoch;
+        }
+
+        /// Update the rarity multipliers. Only NFTs staked afterwards use the new multipliers.
+        pub fn set_rarity_multipliers(
+            &mut self,
+            rarity_field_index: Option<usize>,
+            rarity_multipliers: IndexMap<String, Decimal>,
+        ) {
+            NftStaking::_assert_valid_multipliers(&rarity_multipliers);
+
+            self.rarity_field_index = rarity_field_index;
+            self.rarity_multipliers = rarity_multipliers;
+        }
+
+        /// Add rewards to be distributed. Anyone can fund the rewards.
+        pub fn top_up_rewards(&mut self, rewards: Bucket) {
+            /* CHECK INPUT */
+            assert!(
+                rewards.resource_address() == self.rewards.resource_address(),
+                "Reward resource address mismatch"
+            );
+
+            self._update_reward_per_weight();
+
+            self.reward_budget += rewards.amount();
+            self.rewards.put(rewards);
+        }
+
+        /* STAKER METHODS */
+
+        /// Stake NFTs of the collection in a new position. Returns the stake receipt.
+        pub fn stake(&mut self, nfts: Bucket) -> Bucket {
+            self._update_reward_per_weight();
+
+            let receipt = self
+                .receipt_res_manager
+                .mint_ruid_non_fungible(StakeReceipt {
+                    created_at_epoch: Runtime::current_epoch().number(),
+                });
+
+            self.positions.insert(
+                receipt.as_non_fungible().non_fungible_local_id(),
+                StakePosition {
+                    nfts: Vault::new(self.collection_res_address),
+                    weights: IndexMap::default(),
+                    total_weight: Decimal::ZERO,
+                    reward_per_weight_paid: self.reward_per_weight,
+                    pending_rewards: Decimal::ZERO,
+                },
+            );
+
+            self._add_stake(receipt.as_non_fungible().non_fungible_local_id(), nfts);
+
+            receipt
+        }
+
+        pub fn add_stake(&mut self, receipt_proof: Proof, nfts: Bucket) {
+            let position_id = self._check_receipt_proof(receipt_proof);
+
+            self._update_reward_per_weight();
+
+            self._add_stake(position_id, nfts);
+        }
+
+        pub fn unstake(
+            &mut self,
+            receipt_proof: Proof,
+            nft_ids: IndexSet<NonFungibleLocalId>,
+        ) -> Bucket {
+            let position_id = self._check_receipt_proof(receipt_proof);
+
+            self._update_reward_per_weight();
+            self._update_position_rewards(&position_id);
+
+            let mut position = self.positions.get_mut(&position_id).unwrap();
+
+            let mut removed_weight = Decimal::ZERO;
+            for nft_id in nft_ids.iter() {
+                removed_weight += position
+                    .weights
+                    .shift_remove(nft_id)
+                    .expect("NFT not staked in this position");
+            }
+
+            position.total_weight -= removed_weight;
+
+            let nfts = position.nfts.as_non_fungible().take_non_fungibles(&nft_ids);
+
+            drop(position);
+
+            self.total_weight -= removed_weight;
+
+            nfts.into()
+        }
+
+        pub fn claim(&mut self, receipt_proof: Proof) -> Bucket {
+            let position_id = self._check_receipt_proof(receipt_proof);
+
+            self._update_reward_per_weight();
+            self._update_position_rewards(&position_id);
+
+            let amount = {
+                let mut position = self.positions.get_mut(&position_id).unwrap();
+                let amount = position.pending_rewards;
+                position.pending_rewards = Decimal::ZERO;
+                amount
+            };
+
+            Runtime::emit_event(RewardsClaimedEvent {
+                position_id,
+                amount,
+            });
+
+            self.rewards.take(amount)
+        }
+
+        /* GETTERS */
+
+        pub fn get_pending_rewards(&self, position_id: NonFungibleLocalId) -> Decimal {
+            let position = self
+                .positions
+                .get(&position_id)
+                .expect("Position not found");
+
+            position.pending_rewards
+                + NftStaking::_compute_rewards(
+                    position.total_weight,
+                    self._get_current_reward_per_weight().0 - position.reward_per_weight_paid,
+                )
+        }
+
+        /// Weight an NFT of the collection would be staked with
+        pub fn get_nft_weight(&self, nft_id: NonFungibleLocalId) -> Decimal {
+            let rarity_field_index = match self.rarity_field_index {
+                Some(rarity_field_index) => rarity_field_index,
+                None => return Decimal::ONE,
+            };
+
+            let data: RawNftData = ResourceManager::from_address(self.collection_res_address)
+                .get_non_fungible_data(&nft_id);
+
+            let rarity = match data.0 {
+                ScryptoValue::Tuple { fields } => {
+                    fields
+                        .get(rarity_field_index)
+                        .and_then(|field| match field {
+                            ScryptoValue::String { value } => Some(value.clone()),
+                            ScryptoValue::U8 { value } => Some(value.to_string()),
+                            ScryptoValue::U16 { value } => Some(value.to_string()),
+                            ScryptoValue::U32 { value } => Some(value.to_string()),
+                            ScryptoValue::U64 { value } => Some(value.to_string()),
+                            _ => None,
+                        })
+                }
+                _ => None,
+            };
+
+            rarity
+                .and_then(|rarity| self.rarity_multipliers.get(&rarity).cloned())
+                .unwrap_or(Decimal::ONE)
+        }
+
+        pub fn get_total_weight(&self) -> Decimal {
+            self.total_weight
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _add_stake(&mut self, position_id: NonFungibleLocalId, nfts: Bucket) {
+            /* CHECK INPUT */
+            assert!(
+                nfts.resource_address() == self.collection_res_address,
+                "Collection resource address mismatch"
+            );
+            assert!(!nfts.is_empty(), "No NFT provided");
+
+            self._update_position_rewards(&position_id);
+
+            let nft_weights: Vec<(NonFungibleLocalId, Decimal)> = nfts
+                .as_non_fungible()
+                .non_fungible_local_ids()
+                .into_iter()
+                .map(|nft_id| {
+                    let weight = self.get_nft_weight(nft_id.clone());
+                    (nft_id, weight)
+                })
+                .collect();
+
+            let mut position = self.positions.get_mut(&position_id).unwrap();
+
+            assert!(
+                position.weights.len() + nft_weights.len() <= MAX_NFTS_PER_POSITION,
+                "A position can not hold more than {} NFTs",
+                MAX_NFTS_PER_POSITION
+            );
+
+            let mut added_weight = Decimal::ZERO;
+            for (nft_id, weight) in nft_weights {
+                added_weight += weight;
+                position.weights.insert(nft_id, weight);
+            }
+
+            position.total_weight += added_weight;
+            position.nfts.put(nfts);
+
+            drop(position);
+
+            self.total_weight += added_weight;
+        }
+
+        /// Reward per weight accumulator and remaining budget at the current epoch
+        fn _get_current_reward_per_weight(&self) -> (PreciseDecimal, Decimal) {
+            let elapsed_epochs = Runtime::current_epoch().number() - self.last_update_epoch;
+
+            if self.total_weight == Decimal::ZERO || elapsed_epochs == 0 {
+                return (self.reward_per_weight, self.reward_budget);
+            }
+
+            let distributed = (self.reward_rate_per_epoch * self.total_weight * elapsed_epochs)
+                .min(self.reward_budget);
+
+            (
+                self.reward_per_weight
+                    + PreciseDecimal::from(distributed) / PreciseDecimal::from(self.total_weight),
+                self.reward_budget - distributed,
+            )
+        }
+
+        fn _update_reward_per_weight(&mut self) {
+            let (reward_per_weight, reward_budget) = self._get_current_reward_per_weight();
+
+            self.reward_per_weight = reward_per_weight;
+            self.reward_budget = reward_budget;
+            self.last_update_epoch = Runtime::current_epoch().number();
+        }
+
+        /// Move the rewards earned since the last update of a position to its pending rewards.
+        /// The reward per weight accumulator must be up to date.
+        fn _update_position_rewards(&mut self, position_id: &NonFungibleLocalId) {
+            let reward_per_weight = self.reward_per_weight;
+            let mut position = self.positions.get_mut(position_id).unwrap();
+
+            position.pending_rewards += NftStaking::_compute_rewards(
+                position.total_weight,
+                reward_per_weight - position.reward_per_weight_paid,
+            );
+            position.reward_per_weight_paid = reward_per_weight;
+        }
+
+        fn _compute_rewards(weight: Decimal, reward_per_weight_delta: PreciseDecimal) -> Decimal {
+            (PreciseDecimal::from(weight) * reward_per_weight_delta)
+                .checked_truncate(RoundingMode::ToZero)
+                .unwrap()
+        }
+
+        fn _assert_valid_multipliers(rarity_multipliers: &IndexMap<String, Decimal>) {
+            assert!(
+                rarity_multipliers
+                    .values()
+                    .all(|multiplier| *multiplier > Decimal::ZERO),
+                "Multipliers must be greater than zero"
+            );
+        }
+
+        fn _check_receipt_proof(&self, receipt_proof: Proof) -> NonFungibleLocalId {
+            receipt_proof
+                .check(self.receipt_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the staking component and funded the rewards
+const OWNER: usize = 0;
+/// Holds the NFTs 1 and 2 of the collection
+const ALICE: usize = 1;
+/// Holds the NFT 3 of the collection
+const BOB: usize = 2;
+
+/// Staking distributing 1 reward token per NFT and per epoch from a budget of 100, at epoch
+/// 10. All NFTs of the collection have a weight of 1.
+struct TestStaking {
+    env: TestEnv,
+    staking: ComponentAddress,
+    receipt: ResourceAddress,
+    collection: ResourceAddress,
+    reward: ResourceAddress,
+}
+
+impl TestStaking {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let collection = env
+            .test_runner
+            .create_non_fungible_resource(env.accounts[ALICE]);
+        let reward = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[OWNER]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "NftStaking",
+                "instantiate",
+                manifest_args!(
+                    collection,
+                    reward,
+                    dec!(1),
+                    None::<u64>,
+                    IndexMap::<String, Decimal>::new(),
+                    OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())))
+                ),
+            )
+            .withdraw_non_fungibles_from_account(
+                env.accounts[ALICE],
+                collection,
+                &BTreeSet::from([NonFungibleLocalId::integer(3)]),
+            )
+            .take_all_from_worktop(collection, "bob")
+            .call_method_with_name_lookup(env.accounts[BOB], "deposit", |lookup| {
+                (lookup.bucket("bob"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let (staking, receipt): (ComponentAddress, ResourceAddress) =
+            receipt.expect_commit_success().output(0);
+
+        let mut staking = Self {
+            env,
+            staking,
+            receipt,
+            collection,
+            reward,
+        };
+
+        staking
+            .top_up_rewards(reward, dec!(100))
+            .expect_commit_success();
+
+        staking
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.staking, method, args)
+    }
+
+    /// Top up the rewards with the given resource of the owner
+    fn top_up_rewards(
+        &mut self,
+        res_address: ResourceAddress,
+        amount: Decimal,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[OWNER], res_address, amount)
+            .take_all_from_worktop(res_address, "rewards")
+            .call_method_with_name_lookup(self.staking, "top_up_rewards", |lookup| {
+                (lookup.bucket("rewards"),)
+            });
+
+        self.env.execute(OWNER, builder)
+    }
+
+    /// Stake all the NFTs of the account in a new position
+    fn stake(&mut self, account: usize) -> TransactionReceipt {
+        let collection = self.collection;
+        let amount = self.env.balance(account, collection);
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[account], collection, amount)
+            .take_all_from_worktop(collection, "nfts")
+            .call_method_with_name_lookup(self.staking, "stake", |lookup| (lookup.bucket("nfts"),));
+
+        self.env.execute(account, builder)
+    }
+
+    /// Id of the only position of the account
+    fn position_id(&mut self, account: usize) -> NonFungibleLocalId {
+        let vault_id = self
+            .env
+            .test_runner
+            .get_component_vaults(self.env.accounts[account], self.receipt)[0];
+        let (_, mut ids) = self
+            .env
+            .test_runner
+            .inspect_non_fungible_vault(vault_id)
+            .unwrap();
+
+        ids.next().unwrap()
+    }
+
+    /// Call a method with a proof of the position of the account
+    fn call_with_receipt<A: ResolvableArguments>(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl FnOnce(ManifestProof) -> A,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(self.env.accounts[account], self.receipt, dec!(1))
+            .pop_from_auth_zone("receipt")
+            .call_method_with_name_lookup(self.staking, method, |lookup| {
+                args(lookup.proof("receipt"))
+            });
+
+        self.env.execute(account, builder)
+    }
+
+    fn unstake(&mut self, account: usize, nft_id: u64) -> TransactionReceipt {
+        self.call_with_receipt(account, "unstake", |proof| {
+            (proof, vec![NonFungibleLocalId::integer(nft_id)])
+        })
+    }
+
+    fn get_pending_rewards(&mut self, account: usize) -> Decimal {
+        let position_id = self.position_id(account);
+
+        self.call(OWNER, "get_pending_rewards", manifest_args!(position_id))
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_rewards_are_shared_by_weight() {
+    let mut staking = TestStaking::new();
+    let (collection, reward) = (staking.collection, staking.reward);
+
+    staking.stake(ALICE).expect_commit_success();
+    staking.stake(BOB).expect_commit_success();
+
+    let total_weight: Decimal = staking
+        .call(BOB, "get_total_weight", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(total_weight, dec!(3));
+
+    staking.env.set_epoch(15);
+    assert_eq!(staking.get_pending_rewards(ALICE), dec!(10));
+    assert_eq!(staking.get_pending_rewards(BOB), dec!(5));
+
+    staking
+        .call_with_receipt(ALICE, "claim", |proof| (proof,))
+        .expect_commit_success();
+    assert_eq!(staking.env.balance(ALICE, reward), dec!(10));
+
+    staking.unstake(ALICE, 1).expect_commit_success();
+    assert_eq!(staking.env.balance(ALICE, collection), dec!(1));
+
+    staking.env.set_epoch(20);
+    assert_eq!(staking.get_pending_rewards(ALICE), dec!(5));
+    assert_eq!(staking.get_pending_rewards(BOB), dec!(10));
+
+    // The 75 rewards left in the budget are distributed, then the distribution stops
+    staking.env.set_epoch(100);
+    assert_eq!(staking.get_pending_rewards(ALICE), dec!("42.5"));
+    assert_eq!(staking.get_pending_rewards(BOB), dec!("47.5"));
+
+    staking
+        .call_with_receipt(BOB, "claim", |proof| (proof,))
+        .expect_commit_success();
+    assert_eq!(staking.env.balance(BOB, reward), dec!("47.5"));
+}
+
+#[test]
+fn test_stake_rejects_other_resources() {
+    let mut staking = TestStaking::new();
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(staking.env.accounts[ALICE], XRD, dec!(1))
+        .take_all_from_worktop(XRD, "nfts")
+        .call_method_with_name_lookup(staking.staking, "stake", |lookup| (lookup.bucket("nfts"),));
+    staking.env.execute(ALICE, builder).expect_commit_failure();
+
+    staking.top_up_rewards(XRD, dec!(1)).expect_commit_failure();
+
+    // NFTs can only be unstaked from the position holding them
+    staking.stake(ALICE).expect_commit_success();
+    staking.unstake(ALICE, 3).expect_commit_failure();
+    staking.unstake(ALICE, 2).expect_commit_success();
+}
+
+#[test]
+fn test_admin_methods_require_the_owner() {
+    let mut staking = TestStaking::new();
+
+    staking
+        .call(ALICE, "set_reward_rate", manifest_args!(dec!(2)))
+        .expect_commit_failure();
+    staking
+        .call(OWNER, "set_reward_rate", manifest_args!(dec!(-1)))
+        .expect_commit_failure();
+    staking
+        .call(OWNER, "set_reward_rate", manifest_args!(dec!(2)))
+        .expect_commit_success();
+
+    let mut multipliers = IndexMap::<String, Decimal>::new();
+    multipliers.insert("legendary".to_string(), dec!(3));
+
+    staking
+        .call(
+            ALICE,
+            "set_rarity_multipliers",
+            manifest_args!(Some(0u64), multipliers.clone()),
+        )
+        .expect_commit_failure();
+
+    multipliers.insert("common".to_string(), dec!(0));
+    staking
+        .call(
+            OWNER,
+            "set_rarity_multipliers",
+            manifest_args!(Some(0u64), multipliers),
+        )
+        .expect_commit_failure();
+
+    // Both NFTs have a weight of 1, and earn 2 per epoch
+    staking.stake(ALICE).expect_commit_success();
+    staking.env.set_epoch(11);
+    assert_eq!(staking.get_pending_rewards(ALICE), dec!(4));
+}
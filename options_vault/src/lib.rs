@@ -0,0 +1,484 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use dutch_auction::dutch_auction::DutchAuction;
+use price_oracle::price_oracle::PriceOracle;
+use scrypto::prelude::*;
+use single_asset_pool::pool::AssetPool;
+use single_asset_pool::{DepositType, WithdrawType};
+
+#[derive(ScryptoSbor, PartialEq, Clone, Copy, Debug)]
+pub enum OptionKind {
+    /// Covered call: the vault holds the underlying resource
+    Call,
+    /// Cash-secured put: the vault holds the quote resource
+    Put,
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct OptionToken {
+    pub round_id: u64,
+    pub kind: OptionKind,
+    /// Price of the underlying resource in the quote resource
+    pub strike: Decimal,
+    pub expiry_epoch: u64,
+    /// Underlying amount covered by the option
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor)]
+pub struct Round {
+    pub strike: Decimal,
+    pub expiry_epoch: u64,
+
+    /// Auction selling the options of the round
+    pub auction: Global<DutchAuction>,
+    pub seller_badge: Vault,
+
+    /// Pool liquidity backing the options until the round is settled
+    pub collateral: Vault,
+
+    /// Underlying amount covered by the options sold
+    pub sold_amount: Decimal,
+    pub premium_collected: bool,
+
+    /// Payout per covered underlying unit, set at settlement
+    pub payout_per_unit: Option<Decimal>,
+    pub payouts: Vault,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RoundStartedEvent {
+    pub round_id: u64,
+    pub strike: Decimal,
+    pub expiry_epoch: u64,
+    pub collateral_amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RoundSettledEvent {
+    pub round_id: u64,
+    pub settlement_price: Decimal,
+    pub total_payout: Decimal,
+}
+
+#[blueprint]
+#[events(RoundStartedEvent, RoundSettledEvent)]
+pub mod options_vault {
+
+    enable_method_auth! {
+        roles {
+            keeper => updatable_by: [OWNER];
+        },
+        methods {
+
+            set_oracle => restrict_to :[OWNER];
+
+            start_round => restrict_to :[keeper, OWNER];
+
+            collect_premium => PUBLIC;
+            settle_round => PUBLIC;
+            exercise => PUBLIC;
+
+            deposit => PUBLIC;
+            withdraw => PUBLIC;
+
+            get_current_round_id => PUBLIC;
+            get_round => PUBLIC;
+
+        }
+    }
+
+    pub struct OptionsVault {
+        kind: OptionKind,
+
+        /// Pool holding the vault liquidity, the underlying resource for calls and the quote
+        /// resource for puts
+        pool: Owned<AssetPool>,
+        pool_res_address: ResourceAddress,
+
+        underlying_res_address: ResourceAddress,
+        quote_res_address: ResourceAddress,
+
+        /// Option non-fungible resource manager
+        option_res_manager: ResourceManager,
+
+        rounds: KeyValueStore<u64, Round>,
+        current_round_id: u64,
+
+        /// Oracle pricing the underlying resource in the quote resource
+        oracle: Global<PriceOracle>,
+        max_price_age_seconds: i64,
+    }
+
+    impl OptionsVault {
+        pub fn instantiate(
+            kind: OptionKind,
+            underlying_res_address: ResourceAddress,
+            quote_res_address: ResourceAddress,
+            oracle: Global<PriceOracle>,
+            max_price_age_seconds: i64,
+            owner_role: OwnerRole,
+            keeper_rule: AccessRule,
+        ) -> (Global<OptionsVault>, ResourceAddress, ResourceAddress) {
+            /* CHECK INPUT */
+            assert!(
+                underlying_res_address != quote_res_address,
+                "Underlying and quote resources must be different"
+            );
+            assert!(
+                max_price_age_seconds > 0,
+                "Max price age must be greater than zero"
+            );
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(OptionsVault::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let pool_res_address = match kind {
+                OptionKind::Call => underlying_res_address,
+                OptionKind::Put => quote_res_address,
+            };
+
+            let (pool, share_res_address, _) = AssetPool::instantiate_locally(
+                pool_res_address,
+                owner_role.clone(),
+                component_rule.clone(),
+            );
+
+            let option_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<OptionToken>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule;
+                        burner_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let vault_component = Self {
+                kind,
+                pool,
+                pool_res_address,
+                underlying_res_address,
+                quote_res_address,
+                option_res_manager,
+                rounds: KeyValueStore::new(),
+                current_round_id: 0,
+                oracle,
+                max_price_age_seconds,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                keeper => keeper_rule;
+            ))
+            .with_address(address_reservation)
+            .globalize();
+
+            (
+                vault_component,
+                share_res_address,
+                option_res_manager.address(),
+            )
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn set_oracle(&mut self, oracle: Global<PriceOracle>, max_price_age_seconds: i64) {
+            assert!(
+                max_price_age_seconds > 0,
+                "Max price age must be greater than zero"
+            );
+
+            self.oracle = oracle;
+            self.max_price_age_seconds = max_price_age_seconds;
+        }
+
+        /* KEEPER METHODS */
+
+        /// Write options backed by all the available liquidity, split in `option_count` NFTs,
+        /// and sell them in a descending price auction. The previous round must be settled.
+        /// Returns the round id.
+        #[allow(clippy::too_many_arguments)]
+        pub fn start_round(
+            &mut self,
+            strike: Decimal,
+            expiry_epoch: u64,
+            option_count: u32,
+            start_premium: Decimal,
+            end_premium: Decimal,
+            auction_duration_epochs: u64,
+        ) -> u64 {
+            /* CHECK INPUT */
+            assert!(strike > Decimal::ZERO, "Strike must be greater than zero");
+            assert!(option_count > 0, "Option count must be greater than zero");
+            assert!(
+                expiry_epoch > Runtime::current_epoch().number() + auction_duration_epochs,
+                "Expiry must be after the end of the auction"
+            );
+
+            if self.current_round_id > 0 {
+                assert!(
+                    self.rounds
+                        .get(&self.current_round_id)
+                        .unwrap()
+                        .payout_per_unit
+                        .is_some(),
+                    "Current round is not settled"
+                );
+            }
+
+            let (liquidity, _) = self.pool.get_pooled_amount();
+            assert!(liquidity > Decimal::ZERO, "Vault has no liquidity");
+
+            let collateral = self.pool.protected_withdraw(
+                liquidity,
+                WithdrawType::ForTemporaryUse,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+
+            let total_amount = match self.kind {
+                OptionKind::Call => collateral.amount(),
+                OptionKind::Put => collateral.amount() / strike,
+            };
+
+            let round_id = self.current_round_id + 1;
+            let amount = total_amount / option_count;
+
+            let mut options = Bucket::new(self.option_res_manager.address());
+            for _ in 0..option_count {
+                options.put(self.option_res_manager.mint_ruid_non_fungible(OptionToken {
+                    round_id,
+                    kind: self.kind,
+                    strike,
+                    expiry_epoch,
+                    amount,
+                }));
+            }
+
+            let (auction, seller_badge) = DutchAuction::instantiate(
+                options,
+                self.pool_res_address,
+                start_premium,
+                end_premium,
+                auction_duration_epochs,
+            );
+
+            Runtime::emit_event(RoundStartedEvent {
+                round_id,
+                strike,
+                expiry_epoch,
+                collateral_amount: collateral.amount(),
+            });
+
+            self.rounds.insert(
+                round_id,
+                Round {
+                    strike,
+                    expiry_epoch,
+                    auction,
+                    seller_badge: Vault::with_bucket(seller_badge),
+                    collateral: Vault::with_bucket(collateral),
+                    sold_amount: Decimal::ZERO,
+                    premium_collected: false,
+                    payout_per_unit: None,
+                    payouts: Vault::new(self.pool_res_address),
+                },
+            );
+            self.current_round_id = round_id;
+
+            round_id
+        }
+
+        /* ROUND METHODS */
+
+        /// Add the premium of a sold auction to the vault liquidity, increasing the share
+        /// ratio. Options of an auction that ended unsold are burned.
+        pub fn collect_premium(&mut self, round_id: u64) {
+            let mut round_ref = self.rounds.get_mut(&round_id).expect("Round not found");
+            let round = &mut *round_ref;
+
+            assert!(!round.premium_collected, "Premium already collected");
+
+            let sold = round.auction.is_sold();
+            let auction = &mut round.auction;
+
+            let (premium, unsold_options) =
+                round
+                    .seller_badge
+                    .as_fungible()
+                    .authorize_with_amount(1, || {
+                        if sold {
+                            (Some(auction.withdraw_proceeds()), None)
+                        } else {
+                            (None, Some(auction.reclaim_lot()))
+                        }
+                    });
+
+            if sold {
+                round.sold_amount = match self.kind {
+                    OptionKind::Call => round.collateral.amount(),
+                    OptionKind::Put => round.collateral.amount() / round.strike,
+                };
+            }
+            round.premium_collected = true;
+
+            drop(round_ref);
+
+            if let Some(premium) = premium {
+                self.pool
+                    .protected_deposit(premium, DepositType::LiquidityAddition);
+            }
+            if let Some(unsold_options) = unsold_options {
+                unsold_options.burn();
+            }
+        }
+
+        /// Settle a round at the oracle price once its options have expired. In the money
+        /// payouts are set aside for the option holders and the rest of the collateral returns
+        /// to the vault.
+        pub fn settle_round(&mut self, round_id: u64) {
+            let settlement_price = self.oracle.get_price(
+                self.underlying_res_address,
+                self.quote_res_address,
+                self.max_price_age_seconds,
+            );
+
+            let mut round = self.rounds.get_mut(&round_id).expect("Round not found");
+
+            assert!(
+                round.premium_collected,
+                "Premium must be collected before settlement"
+            );
+            assert!(round.payout_per_unit.is_none(), "Round already settled");
+            assert!(
+                Runtime::current_epoch().number() >= round.expiry_epoch,
+                "Options have not expired yet"
+            );
+
+            let payout_per_unit = match self.kind {
+                OptionKind::Call if settlement_price > round.strike => {
+                    (settlement_price - round.strike) / settlement_price
+                }
+                OptionKind::Put if settlement_price < round.strike => {
+                    round.strike - settlement_price
+                }
+                _ => Decimal::ZERO,
+            };
+
+            let total_payout = (round.sold_amount * payout_per_unit).min(round.collateral.amount());
+
+            let payouts = round.collateral.take_advanced(
+                total_payout,
+                WithdrawStrategy::Rounded(RoundingMode::AwayFromZero),
+            );
+            let total_payout = payouts.amount();
+            round.payouts.put(payouts);
+            round.payout_per_unit = Some(payout_per_unit);
+
+            let collateral = round.collateral.take_all();
+
+            drop(round);
+
+            // Returned collateral reduces the external liquidity, the payouts are a loss
+            self.pool
+                .protected_deposit(collateral, DepositType::FromTemporaryUse);
+            if total_payout > Decimal::ZERO {
+                let (_, external_liquidity) = self.pool.get_pooled_amount();
+                self.pool
+                    .decrease_external_liquidity(total_payout.min(external_liquidity));
+            }
+
+            Runtime::emit_event(RoundSettledEvent {
+                round_id,
+                settlement_price,
+                total_payout,
+            });
+        }
+
+        /// Exercise settled options, burning them. Returns their payout.
+        pub fn exercise(&mut self, options: Bucket) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                options.resource_address() == self.option_res_manager.address(),
+                "Option resource address mismatch"
+            );
+
+            let mut payout = Bucket::new(self.pool_res_address);
+
+            for option in options.as_non_fungible().non_fungibles::<OptionToken>() {
+                let data = option.data();
+
+                let mut round = self.rounds.get_mut(&data.round_id).unwrap();
+                let payout_per_unit = round.payout_per_unit.expect("Round is not settled");
+
+                let amount = (data.amount * payout_per_unit).min(round.payouts.amount());
+                payout.put(
+                    round
+                        .payouts
+                        .take_advanced(amount, WithdrawStrategy::Rounded(RoundingMode::ToZero)),
+                );
+            }
+
+            options.burn();
+
+            payout
+        }
+
+        /* LP METHODS */
+
+        /// Deposit liquidity. It backs the options from the next round on. Returns the vault
+        /// shares and the change.
+        pub fn deposit(&mut self, assets: Bucket) -> (Bucket, Bucket) {
+            self.pool.contribute(assets)
+        }
+
+        /// Redeem vault shares. Only the liquidity not backing the options of the current round
+        /// can be withdrawn.
+        pub fn withdraw(&mut self, shares: Bucket) -> (Bucket, Bucket) {
+            self.pool.redeem(shares)
+        }
+
+        /* GETTERS */
+
+        pub fn get_current_round_id(&self) -> u64 {
+            self.current_round_id
+        }
+
+        /// Strike, expiry epoch, collateral amount and payout per unit of a round
+        pub fn get_round(&self, round_id: u64) -> (Decimal, u64, Decimal, Option<Decimal>) {
+            let round = self.rounds.get(&round_id).expect("Round not found");
+
+            (
+                round.strike,
+                round.expiry_epoch,
+                round.collateral.amount(),
+                round.payout_per_unit,
+            )
+        }
+    }
+}
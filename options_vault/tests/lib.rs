@@ -0,0 +1,289 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the vault and feeds the oracle
+const OWNER: usize = 0;
+const KEEPER: usize = 1;
+/// Provides the 100 underlying tokens of the vault liquidity
+const ALICE: usize = 2;
+/// Holds 10 underlying tokens, and buys the options
+const BOB: usize = 3;
+
+/// Ledger time of the tests, in seconds, rounded to the minute
+const NOW: i64 = 1_700_000_040;
+
+/// Covered call vault holding 100 underlying tokens deposited by Alice, with the underlying
+/// priced 60 by the oracle, at epoch 10. The oracle and auction blueprints are linked into the
+/// vault package, so they are instantiated from the same package.
+struct TestVault {
+    env: TestEnv,
+    vault: ComponentAddress,
+    share: ResourceAddress,
+    option: ResourceAddress,
+    oracle: ComponentAddress,
+    underlying: ResourceAddress,
+    quote: ResourceAddress,
+}
+
+impl TestVault {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 4);
+        env.set_epoch(10);
+        env.test_runner
+            .advance_to_round_at_timestamp(Round::of(1), NOW * 1000);
+        let underlying =
+            env.test_runner
+                .create_fungible_resource(dec!(110), 18, env.accounts[ALICE]);
+        let quote = env
+            .test_runner
+            .create_fungible_resource(dec!(1), 18, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "PriceOracle",
+                "instantiate",
+                manifest_args!(
+                    owner_role.clone(),
+                    rule!(require(env.badges[OWNER].clone())),
+                    2u32
+                ),
+            )
+            .withdraw_from_account(env.accounts[ALICE], underlying, dec!(10))
+            .take_all_from_worktop(underlying, "bob")
+            .call_method_with_name_lookup(env.accounts[BOB], "deposit", |lookup| {
+                (lookup.bucket("bob"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let oracle = receipt.expect_commit_success().new_component_addresses()[0];
+
+        // Manifest encoding of `OptionKind::Call`
+        let call_kind = ManifestValue::Enum {
+            discriminator: 0,
+            fields: vec![],
+        };
+
+        let manifest = ManifestBuilder::new()
+            .call_method(
+                oracle,
+                "push_price",
+                manifest_args!(underlying, quote, dec!(60), NOW - 60),
+            )
+            .call_function(
+                env.package_address,
+                "OptionsVault",
+                "instantiate",
+                manifest_args!(
+                    call_kind,
+                    underlying,
+                    quote,
+                    oracle,
+                    3600i64,
+                    owner_role,
+                    rule!(require(env.badges[KEEPER].clone()))
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()]);
+        let (vault, share, option): (ComponentAddress, ResourceAddress, ResourceAddress) =
+            receipt.expect_commit_success().output(1);
+
+        let mut vault = Self {
+            env,
+            vault,
+            share,
+            option,
+            oracle,
+            underlying,
+            quote,
+        };
+
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(vault.env.accounts[ALICE], underlying, dec!(100))
+            .take_all_from_worktop(underlying, "assets")
+            .call_method_with_name_lookup(vault.vault, "deposit", |lookup| {
+                (lookup.bucket("assets"),)
+            });
+        vault.env.execute(ALICE, builder).expect_commit_success();
+
+        vault
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.vault, method, args)
+    }
+
+    /// Start a round of 2 options with a strike of 75 expiring at epoch 20, auctioned from
+    /// a premium of 6 down to 1 over 5 epochs
+    fn start_round(&mut self, caller: usize) -> TransactionReceipt {
+        self.call(
+            caller,
+            "start_round",
+            manifest_args!(dec!(75), 20u64, 2u32, dec!(6), dec!(1), 5u64),
+        )
+    }
+
+    /// Start a round, and return the address of its auction
+    fn start_auction(&mut self) -> ComponentAddress {
+        let receipt = self.start_round(KEEPER);
+
+        receipt.expect_commit_success().new_component_addresses()[0]
+    }
+
+    fn bid(&mut self, auction: ComponentAddress, amount: Decimal) -> TransactionReceipt {
+        let underlying = self.underlying;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[BOB], underlying, amount)
+            .take_all_from_worktop(underlying, "payment")
+            .call_method_with_name_lookup(auction, "bid", |lookup| (lookup.bucket("payment"),));
+
+        self.env.execute(BOB, builder)
+    }
+
+    fn push_price(&mut self, price: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_method(
+            self.oracle,
+            "push_price",
+            manifest_args!(self.underlying, self.quote, price, NOW),
+        );
+
+        self.env.execute(OWNER, builder)
+    }
+
+    fn withdraw(&mut self, shares: Decimal) -> TransactionReceipt {
+        let share = self.share;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[ALICE], share, shares)
+            .take_all_from_worktop(share, "shares")
+            .call_method_with_name_lookup(self.vault, "withdraw", |lookup| {
+                (lookup.bucket("shares"),)
+            });
+
+        self.env.execute(ALICE, builder)
+    }
+}
+
+#[test]
+fn test_sold_options_are_exercised_in_the_money() {
+    let mut vault = TestVault::new();
+    let (option, underlying) = (vault.option, vault.underlying);
+
+    let auction = vault.start_auction();
+    vault.start_round(KEEPER).expect_commit_failure();
+
+    vault.env.set_epoch(11);
+    vault.bid(auction, dec!(5)).expect_commit_success();
+    assert_eq!(vault.env.balance(BOB, option), dec!(2));
+    assert_eq!(vault.env.balance(BOB, underlying), dec!(5));
+
+    vault
+        .call(BOB, "settle_round", manifest_args!(1u64))
+        .expect_commit_failure();
+    vault
+        .call(BOB, "collect_premium", manifest_args!(1u64))
+        .expect_commit_success();
+
+    // The collateral of the round can not be withdrawn
+    vault.withdraw(dec!(100)).expect_commit_failure();
+    vault
+        .call(BOB, "settle_round", manifest_args!(1u64))
+        .expect_commit_failure();
+
+    // Options pay 25 / 100 of the covered amount at a settlement price of 100
+    vault.env.set_epoch(20);
+    vault.push_price(dec!(100)).expect_commit_success();
+    vault
+        .call(BOB, "settle_round", manifest_args!(1u64))
+        .expect_commit_success();
+
+    let round: (Decimal, u64, Decimal, Option<Decimal>) = vault
+        .call(BOB, "get_round", manifest_args!(1u64))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(round, (dec!(75), 20, dec!(0), Some(dec!("0.25"))));
+
+    let builder =
+        ManifestBuilder::new()
+            .withdraw_from_account(vault.env.accounts[BOB], option, dec!(2))
+            .take_all_from_worktop(option, "options")
+            .call_method_with_name_lookup(vault.vault, "exercise", |lookup| {
+                (lookup.bucket("options"),)
+            });
+    vault.env.execute(BOB, builder).expect_commit_success();
+    assert_eq!(vault.env.balance(BOB, underlying), dec!(30));
+
+    // The vault earned the premium of 5 and paid 25
+    vault.withdraw(dec!(100)).expect_commit_success();
+    assert_eq!(vault.env.balance(ALICE, underlying), dec!(80));
+}
+
+#[test]
+fn test_unsold_options_are_burned() {
+    let mut vault = TestVault::new();
+    let underlying = vault.underlying;
+
+    let auction = vault.start_auction();
+
+    vault.env.set_epoch(15);
+    vault
+        .call(BOB, "collect_premium", manifest_args!(1u64))
+        .expect_commit_failure();
+
+    vault.env.set_epoch(16);
+    vault.bid(auction, dec!(1)).expect_commit_failure();
+    vault
+        .call(BOB, "collect_premium", manifest_args!(1u64))
+        .expect_commit_success();
+    vault
+        .call(BOB, "collect_premium", manifest_args!(1u64))
+        .expect_commit_failure();
+
+    vault.env.set_epoch(20);
+    vault.push_price(dec!(100)).expect_commit_success();
+    vault
+        .call(BOB, "settle_round", manifest_args!(1u64))
+        .expect_commit_success();
+
+    vault.withdraw(dec!(100)).expect_commit_success();
+    assert_eq!(vault.env.balance(ALICE, underlying), dec!(100));
+}
+
+#[test]
+fn test_admin_methods_require_the_keeper_or_the_owner() {
+    let mut vault = TestVault::new();
+    let oracle = vault.oracle;
+
+    vault.start_round(ALICE).expect_commit_failure();
+    vault
+        .call(
+            KEEPER,
+            "start_round",
+            manifest_args!(dec!(75), 15u64, 2u32, dec!(6), dec!(1), 5u64),
+        )
+        .expect_commit_failure();
+    vault.start_round(OWNER).expect_commit_success();
+
+    vault
+        .call(KEEPER, "set_oracle", manifest_args!(oracle, 60i64))
+        .expect_commit_failure();
+    vault
+        .call(OWNER, "set_oracle", manifest_args!(oracle, 0i64))
+        .expect_commit_failure();
+    vault
+        .call(OWNER, "set_oracle", manifest_args!(oracle, 60i64))
+        .expect_commit_success();
+}
@@ -0,0 +1,220 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use price_oracle::price_oracle::PriceOracle;
+use scrypto::prelude::*;
+
+/// Maximum number of registered oracles
+pub const MAX_ORACLES: usize = 10;
+
+/// Median of a non empty list of prices. With an even number of prices, the median is the
+/// average of the two middle prices.
+pub fn median(prices: &[Decimal]) -> Decimal {
+    assert!(!prices.is_empty(), "No price to aggregate");
+
+    let mut sorted = prices.to_vec();
+    sorted.sort();
+
+    let middle = sorted.len() / 2;
+
+    if sorted.len() % 2 == 0 {
+        (sorted[middle - 1] + sorted[middle]) / 2
+    } else {
+        sorted[middle]
+    }
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct AggregatedPrice {
+    pub price: Decimal,
+
+    /// Number of fresh prices within the max deviation of the median
+    pub source_count: u32,
+
+    /// Whether enough sources agree on the price
+    pub confident: bool,
+}
+
+#[blueprint]
+pub mod oracle_aggregator {
+
+    enable_method_auth! {
+        methods {
+
+            add_oracle => restrict_to :[OWNER];
+            remove_oracle => restrict_to :[OWNER];
+            set_parameters => restrict_to :[OWNER];
+
+            get_price => PUBLIC;
+            get_confident_price => PUBLIC;
+            get_oracles => PUBLIC;
+
+        }
+    }
+
+    pub struct OracleAggregator {
+        oracles: IndexMap<ComponentAddress, Global<PriceOracle>>,
+
+        /// Prices older than this are discarded
+        max_age_seconds: i64,
+
+        /// Maximum relative deviation from the median of the fresh prices
+        max_deviation: Decimal,
+
+        /// Minimum number of agreeing sources for a price to be confident
+        min_sources: u32,
+    }
+
+    impl OracleAggregator {
+        pub fn instantiate(
+            max_age_seconds: i64,
+            max_deviation: Decimal,
+            min_sources: u32,
+            owner_role: OwnerRole,
+        ) -> Global<OracleAggregator> {
+            /* CHECK INPUT */
+            OracleAggregator::_assert_valid_parameters(max_age_seconds, max_deviation, min_sources);
+
+            Self {
+                oracles: IndexMap::new(),
+                max_age_seconds,
+                max_deviation,
+                min_sources,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn add_oracle(&mut self, oracle: Global<PriceOracle>) {
+            let oracle_address = oracle.address();
+
+            /* CHECK INPUT */
+            assert!(
+                !self.oracles.contains_key(&oracle_address),
+                "Oracle already registered"
+            );
+            assert!(self.oracles.len() < MAX_ORACLES, "Too many oracles");
+
+            self.oracles.insert(oracle_address, oracle);
+        }
+
+        pub fn remove_oracle(&mut self, oracle_address: ComponentAddress) {
+            assert!(
+                self.oracles.shift_remove(&oracle_address).is_some(),
+                "Oracle not registered"
+            );
+        }
+
+        pub fn set_parameters(
+            &mut self,
+            max_age_seconds: i64,
+            max_deviation: Decimal,
+            min_sources: u32,
+        ) {
+            OracleAggregator::_assert_valid_parameters(max_age_seconds, max_deviation, min_sources);
+
+            self.max_age_seconds = max_age_seconds;
+            self.max_deviation = max_deviation;
+            self.min_sources = min_sources;
+        }
+
+        /* GETTERS */
+
+        /// Median price of the pair over the registered oracles. Missing and stale prices are
+        /// discarded, then prices deviating from their median by more than the max deviation.
+        /// Fails if no fresh price is available.
+        pub fn get_price(&self, base: ResourceAddress, quote: ResourceAddress) -> AggregatedPrice {
+            let now = Clock::current_time_rounded_to_minutes().seconds_since_unix_epoch;
+
+            let fresh_prices: Vec<Decimal> = self
+                .oracles
+                .values()
+                .filter_map(|oracle| oracle.get_price_update(base, quote))
+                .filter(|update| now - update.timestamp <= self.max_age_seconds)
+                .map(|update| update.price)
+                .collect();
+
+            assert!(!fresh_prices.is_empty(), "No fresh price for this pair");
+
+            let fresh_median = median(&fresh_prices);
+            let max_distance = fresh_median * self.max_deviation;
+
+            let agreeing_prices: Vec<Decimal> = fresh_prices
+                .into_iter()
+                .filter(|price| (*price - fresh_median).checked_abs().unwrap() <= max_distance)
+                .collect();
+
+            let source_count = agreeing_prices.len() as u32;
+
+            AggregatedPrice {
+                price: median(&agreeing_prices),
+                source_count,
+                confident: source_count >= self.min_sources,
+            }
+        }
+
+        /// Median price of the pair, failing if it is not confident
+        pub fn get_confident_price(
+            &self,
+            base: ResourceAddress,
+            quote: ResourceAddress,
+        ) -> Decimal {
+            let aggregated_price = self.get_price(base, quote);
+
+            assert!(
+                aggregated_price.confident,
+                "Not enough sources agree on the price"
+            );
+
+            aggregated_price.price
+        }
+
+        pub fn get_oracles(&self) -> Vec<ComponentAddress> {
+            self.oracles.keys().cloned().collect()
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_parameters(
+            max_age_seconds: i64,
+            max_deviation: Decimal,
+            min_sources: u32,
+        ) {
+            assert!(
+                max_age_seconds > 0,
+                "Max price age must be greater than zero"
+            );
+            assert!(
+                max_deviation >= Decimal::ZERO && max_deviation < Decimal::ONE,
+                "Max deviation must be between 0 and 1"
+            );
+            assert!(
+                min_sources > 0 && min_sources as usize <= MAX_ORACLES,
+                "Min sources must be between 1 and the max number of oracles"
+            );
+        }
+    }
+}
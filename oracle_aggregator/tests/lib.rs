@@ -0,0 +1,227 @@
+use oracle_aggregator::AggregatedPrice;
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the aggregator, and feeds the oracles
+const OWNER: usize = 0;
+const ALICE: usize = 1;
+
+/// Ledger time of the tests, in seconds, rounded to the minute
+const NOW: i64 = 1_700_000_040;
+
+/// Aggregator of prices at most an hour old, deviating from their median by at most 10%, and
+/// confident with 2 agreeing sources. Three oracles are instantiated but not registered yet.
+/// The oracle blueprint is linked into the aggregator package, so they are instantiated from
+/// the same package.
+struct TestAggregator {
+    env: TestEnv,
+    aggregator: ComponentAddress,
+    oracles: Vec<ComponentAddress>,
+    base: ResourceAddress,
+    quote: ResourceAddress,
+}
+
+impl TestAggregator {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 2);
+        env.test_runner
+            .advance_to_round_at_timestamp(Round::of(1), NOW * 1000);
+        let base = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[OWNER]);
+        let quote = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+        let feeder_rule = rule!(require(env.badges[OWNER].clone()));
+
+        let mut builder = ManifestBuilder::new().call_function(
+            env.package_address,
+            "OracleAggregator",
+            "instantiate",
+            manifest_args!(3600i64, dec!("0.1"), 2u32, owner_role.clone()),
+        );
+        for _ in 0..3 {
+            builder = builder.call_function(
+                env.package_address,
+                "PriceOracle",
+                "instantiate",
+                manifest_args!(owner_role.clone(), feeder_rule.clone(), 2u32),
+            );
+        }
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(builder.build(), vec![]);
+        let component_addresses = receipt.expect_commit_success().new_component_addresses();
+
+        Self {
+            aggregator: component_addresses[0],
+            oracles: component_addresses[1..].to_vec(),
+            env,
+            base,
+            quote,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.aggregator, method, args)
+    }
+
+    fn add_oracle(&mut self, caller: usize, index: usize) -> TransactionReceipt {
+        let oracle = self.oracles[index];
+
+        self.call(caller, "add_oracle", manifest_args!(oracle))
+    }
+
+    /// Register all the oracles
+    fn add_oracles(&mut self) {
+        for index in 0..self.oracles.len() {
+            self.add_oracle(OWNER, index).expect_commit_success();
+        }
+    }
+
+    fn push_price(&mut self, index: usize, price: Decimal, timestamp: i64) {
+        let (base, quote) = (self.base, self.quote);
+        let builder = ManifestBuilder::new().call_method(
+            self.oracles[index],
+            "push_price",
+            manifest_args!(base, quote, price, timestamp),
+        );
+
+        self.env.execute(OWNER, builder).expect_commit_success();
+    }
+
+    fn get_price(&mut self, method: &str) -> TransactionReceipt {
+        let (base, quote) = (self.base, self.quote);
+
+        self.call(ALICE, method, manifest_args!(base, quote))
+    }
+}
+
+#[test]
+fn test_outliers_are_discarded() {
+    let mut aggregator = TestAggregator::new();
+    aggregator.add_oracles();
+
+    // The price of 150 deviates from the median of 104 by more than 10%
+    aggregator.push_price(0, dec!(100), NOW);
+    aggregator.push_price(1, dec!(104), NOW);
+    aggregator.push_price(2, dec!(150), NOW);
+
+    let aggregated_price: AggregatedPrice = aggregator
+        .get_price("get_price")
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(aggregated_price.price, dec!(102));
+    assert_eq!(aggregated_price.source_count, 2);
+    assert!(aggregated_price.confident);
+
+    let price: Decimal = aggregator
+        .get_price("get_confident_price")
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(price, dec!(102));
+}
+
+#[test]
+fn test_stale_and_missing_prices_are_discarded() {
+    let mut aggregator = TestAggregator::new();
+    aggregator.get_price("get_price").expect_commit_failure();
+
+    aggregator.add_oracles();
+    aggregator.get_price("get_price").expect_commit_failure();
+
+    aggregator.push_price(0, dec!(100), NOW);
+    aggregator.push_price(2, dec!(150), NOW - 7200);
+
+    let aggregated_price: AggregatedPrice = aggregator
+        .get_price("get_price")
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(aggregated_price.price, dec!(100));
+    assert_eq!(aggregated_price.source_count, 1);
+    assert!(!aggregated_price.confident);
+    aggregator
+        .get_price("get_confident_price")
+        .expect_commit_failure();
+
+    aggregator
+        .call(
+            OWNER,
+            "set_parameters",
+            manifest_args!(3600i64, dec!("0.1"), 1u32),
+        )
+        .expect_commit_success();
+    let price: Decimal = aggregator
+        .get_price("get_confident_price")
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(price, dec!(100));
+}
+
+#[test]
+fn test_oracles_and_parameters_are_checked() {
+    let mut aggregator = TestAggregator::new();
+    let oracle = aggregator.oracles[0];
+
+    aggregator.add_oracle(ALICE, 0).expect_commit_failure();
+    aggregator.add_oracle(OWNER, 0).expect_commit_success();
+    aggregator.add_oracle(OWNER, 0).expect_commit_failure();
+
+    aggregator
+        .call(OWNER, "remove_oracle", manifest_args!(oracle))
+        .expect_commit_success();
+    aggregator
+        .call(OWNER, "remove_oracle", manifest_args!(oracle))
+        .expect_commit_failure();
+
+    let oracles: Vec<ComponentAddress> = aggregator
+        .call(ALICE, "get_oracles", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert!(oracles.is_empty());
+
+    aggregator
+        .call(
+            ALICE,
+            "set_parameters",
+            manifest_args!(3600i64, dec!("0.1"), 1u32),
+        )
+        .expect_commit_failure();
+    aggregator
+        .call(
+            OWNER,
+            "set_parameters",
+            manifest_args!(0i64, dec!("0.1"), 1u32),
+        )
+        .expect_commit_failure();
+    aggregator
+        .call(
+            OWNER,
+            "set_parameters",
+            manifest_args!(3600i64, dec!(1), 1u32),
+        )
+        .expect_commit_failure();
+    aggregator
+        .call(
+            OWNER,
+            "set_parameters",
+            manifest_args!(3600i64, dec!("0.1"), 0u32),
+        )
+        .expect_commit_failure();
+    aggregator
+        .call(
+            OWNER,
+            "set_parameters",
+            manifest_args!(3600i64, dec!("0.1"), 11u32),
+        )
+        .expect_commit_failure();
+}
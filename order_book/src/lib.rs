@@ -0,0 +1,556 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+/// Maximum number of price levels on each side of the book
+pub const MAX_PRICE_LEVELS: usize = 100;
+
+/// Maximum number of open orders in a price level
+pub const MAX_LEVEL_ORDERS: usize = 50;
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OrderSide {
+    /// Buy the base resource, escrowing the quote resource
+    Bid,
+    /// Sell the base resource, escrowing the base resource
+    Ask,
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct OrderReceipt {
+    pub side: OrderSide,
+    /// Price in quote resource per base resource
+    pub price: Decimal,
+    /// Escrowed amount when the order was placed
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub struct OrderState {
+    pub side: OrderSide,
+    pub price: Decimal,
+    /// Escrowed amount not filled yet, in base resource for asks and quote resource for bids
+    pub remaining_amount: Decimal,
+    /// Filled amount not claimed yet, in the resource bought by the order
+    pub unclaimed_proceeds: Decimal,
+    /// Whether the order is still in the book
+    pub is_open: bool,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct OrderPlacedEvent {
+    pub order_id: u64,
+    pub side: OrderSide,
+    pub price: Decimal,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct OrderFilledEvent {
+    pub order_id: u64,
+    pub side: OrderSide,
+    pub price: Decimal,
+    pub base_amount: Decimal,
+    pub quote_amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct OrderCancelledEvent {
+    pub order_id: u64,
+    pub remaining_amount: Decimal,
+}
+
+pub fn get_divisibility(res_address: ResourceAddress) -> u8 {
+    match ResourceManager::from_address(res_address).resource_type() {
+        ResourceType::Fungible { divisibility } => divisibility,
+        _ => panic!("Resource must be fungible"),
+    }
+}
+
+#[blueprint]
+#[events(OrderPlacedEvent, OrderFilledEvent, OrderCancelledEvent)]
+pub mod order_book {
+
+    enable_method_auth! {
+        methods {
+
+            place_order => PUBLIC;
+            cancel_order => PUBLIC;
+            claim_proceeds => PUBLIC;
+            take => PUBLIC;
+
+            get_order => PUBLIC;
+            get_best_bid => PUBLIC;
+            get_best_ask => PUBLIC;
+            get_depth => PUBLIC;
+            get_min_order_size => PUBLIC;
+
+        }
+    }
+
+    pub struct OrderBook {
+        /// Escrowed asks and unclaimed proceeds of bids
+        base_vault: Vault,
+        base_divisibility: u8,
+
+        /// Escrowed bids and unclaimed proceeds of asks
+        quote_vault: Vault,
+        quote_divisibility: u8,
+
+        /// Order receipt non-fungible resource manager
+        order_res_manager: ResourceManager,
+        next_order_id: u64,
+
+        orders: KeyValueStore<u64, OrderState>,
+
+        /// Minimum base resource amount of a new order
+        min_order_size: Decimal,
+
+        /// Open bid order ids by price level, in time priority
+        bids: BTreeMap<Decimal, Vec<u64>>,
+
+        /// Open ask order ids by price level, in time priority
+        asks: BTreeMap<Decimal, Vec<u64>>,
+    }
+
+    impl OrderBook {
+        pub fn instantiate(
+            base_res_address: ResourceAddress,
+            quote_res_address: ResourceAddress,
+            min_order_size: Decimal,
+            owner_role: OwnerRole,
+        ) -> (Global<OrderBook>, ResourceAddress) {
+            /* CHECK INPUT */
+            assert_fungible_res_address(base_res_address, None);
+            assert_fungible_res_address(quote_res_address, None);
+            assert!(
+                base_res_address != quote_res_address,
+                "Base and quote resources must be different"
+            );
+            assert!(
+                min_order_size > Decimal::ZERO,
+                "Minimum order size must be greater than zero!"
+            );
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(OrderBook::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let order_res_manager =
+                ResourceBuilder::new_integer_non_fungible::<OrderReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule;
+                        burner_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let order_book_component = Self {
+                base_vault: Vault::new(base_res_address),
+                base_divisibility: get_divisibility(base_res_address),
+                quote_vault: Vault::new(quote_res_address),
+                quote_divisibility: get_divisibility(quote_res_address),
+                order_res_manager,
+                next_order_id: 1,
+                orders: KeyValueStore::new(),
+                min_order_size,
+                bids: BTreeMap::new(),
+                asks: BTreeMap::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (order_book_component, order_res_manager.address())
+        }
+
+        /* MAKER METHODS */
+
+        /// Place a limit order. A base resource bucket places an ask and a quote resource
+        /// bucket places a bid. Orders are post-only: an order crossing the best opposite
+        /// price is rejected and should be filled with `take` instead. Orders must be worth at
+        /// least the minimum order size in base resource, and the number of price levels and
+        /// of orders per level is capped to keep the component state bounded.
+        pub fn place_order(&mut self, assets: Bucket, price: Decimal) -> Bucket {
+            let amount = assets.amount();
+
+            /* CHECK INPUT */
+            assert!(price > Decimal::ZERO, "Price must be greater than zero!");
+            assert!(amount > Decimal::ZERO, "Amount must be greater than zero!");
+
+            let base_amount = if assets.resource_address() == self.base_vault.resource_address() {
+                amount
+            } else {
+                amount / price
+            };
+            assert!(
+                base_amount >= self.min_order_size,
+                "Order is smaller than the minimum order size"
+            );
+
+            let side = if assets.resource_address() == self.base_vault.resource_address() {
+                if let Some(best_bid) = self.get_best_bid() {
+                    assert!(price > best_bid, "Ask price crosses the best bid");
+                }
+
+                self.base_vault.put(assets);
+                OrderSide::Ask
+            } else if assets.resource_address() == self.quote_vault.resource_address() {
+                if let Some(best_ask) = self.get_best_ask() {
+                    assert!(price < best_ask, "Bid price crosses the best ask");
+                }
+
+                self.quote_vault.put(assets);
+                OrderSide::Bid
+            } else {
+                panic!("Resource does not belong to the order book")
+            };
+
+            let order_id = self.next_order_id;
+            self.next_order_id += 1;
+
+            self.orders.insert(
+                order_id,
+                OrderState {
+                    side,
+                    price,
+                    remaining_amount: amount,
+                    unclaimed_proceeds: Decimal::ZERO,
+                    is_open: true,
+                },
+            );
+
+            let levels = match side {
+                OrderSide::Bid => &mut self.bids,
+                OrderSide::Ask => &mut self.asks,
+            };
+            match levels.get(&price) {
+                Some(level) => assert!(level.len() < MAX_LEVEL_ORDERS, "Price level is full"),
+                None => assert!(
+                    levels.len() < MAX_PRICE_LEVELS,
+                    "Too many price levels on this side of the book"
+                ),
+            }
+            levels.entry(price).or_insert(Vec::new()).push(order_id);
+
+            Runtime::emit_event(OrderPlacedEvent {
+                order_id,
+                side,
+                price,
+                amount,
+            });
+
+            self.order_res_manager.mint_non_fungible(
+                &NonFungibleLocalId::integer(order_id),
+                OrderReceipt {
+                    side,
+                    price,
+                    amount,
+                },
+            )
+        }
+
+        /// Cancel an order, returning the unfilled escrowed amount and the unclaimed proceeds.
+        /// The order receipt is burnt.
+        pub fn cancel_order(&mut self, order_receipt: Bucket) -> (Bucket, Bucket) {
+            /* CHECK INPUT */
+            assert!(
+                order_receipt.resource_address() == self.order_res_manager.address(),
+                "Order receipt resource address mismatch"
+            );
+
+            let order_id =
+                OrderBook::_get_order_id(&order_receipt.as_non_fungible().non_fungible_local_id());
+            order_receipt.burn();
+
+            let order = self.orders.remove(&order_id).expect("Order not found");
+
+            if order.is_open {
+                self._remove_from_level(order.side, order.price, order_id);
+            }
+
+            Runtime::emit_event(OrderCancelledEvent {
+                order_id,
+                remaining_amount: order.remaining_amount,
+            });
+
+            let (escrow_vault, proceeds_vault) = match order.side {
+                OrderSide::Bid => (&mut self.quote_vault, &mut self.base_vault),
+                OrderSide::Ask => (&mut self.base_vault, &mut self.quote_vault),
+            };
+
+            (
+                escrow_vault.take(order.remaining_amount),
+                proceeds_vault.take(order.unclaimed_proceeds),
+            )
+        }
+
+        /// Claim the proceeds of the filled part of an order
+        pub fn claim_proceeds(&mut self, order_proof: Proof) -> Bucket {
+            let order_id = OrderBook::_get_order_id(
+                &order_proof
+                    .check(self.order_res_manager.address())
+                    .as_non_fungible()
+                    .non_fungible_local_id(),
+            );
+
+            let mut order = self.orders.get_mut(&order_id).expect("Order not found");
+            let proceeds = order.unclaimed_proceeds;
+            order.unclaimed_proceeds = Decimal::ZERO;
+
+            match order.side {
+                OrderSide::Bid => self.base_vault.take(proceeds),
+                OrderSide::Ask => self.quote_vault.take(proceeds),
+            }
+        }
+
+        /* TAKER METHODS */
+
+        /// Fill resting orders in price-time priority. A quote resource bucket buys from the
+        /// asks and a base resource bucket sells into the bids. Orders priced beyond
+        /// `limit_price` are not filled. Returns the bought resource and the unused input.
+        pub fn take(
+            &mut self,
+            mut input: Bucket,
+            limit_price: Option<Decimal>,
+        ) -> (Bucket, Bucket) {
+            let buying_base = if input.resource_address() == self.quote_vault.resource_address() {
+                true
+            } else if input.resource_address() == self.base_vault.resource_address() {
+                false
+            } else {
+                panic!("Resource does not belong to the order book")
+            };
+
+            let mut input_left = input.amount();
+            let mut output_amount = Decimal::ZERO;
+
+            loop {
+                let best_price = if buying_base {
+                    self.asks.keys().next().cloned()
+                } else {
+                    self.bids.keys().next_back().cloned()
+                };
+
+                let price = match best_price {
+                    Some(price) => price,
+                    None => break,
+                };
+
+                if let Some(limit_price) = limit_price {
+                    if (buying_base && price > limit_price) || (!buying_base && price < limit_price)
+                    {
+                        break;
+                    }
+                }
+
+                let level = if buying_base {
+                    self.asks.get_mut(&price).unwrap()
+                } else {
+                    self.bids.get_mut(&price).unwrap()
+                };
+
+                let order_id = level[0];
+                let mut order = self.orders.get_mut(&order_id).unwrap();
+
+                let (base_amount, quote_amount) = if buying_base {
+                    let affordable = (input_left / price)
+                        .checked_round(self.base_divisibility, RoundingMode::ToZero)
+                        .unwrap();
+
+                    if affordable == Decimal::ZERO {
+                        break;
+                    }
+
+                    let base_amount = order.remaining_amount.min(affordable);
+                    let quote_amount = (base_amount * price)
+                        .checked_round(self.quote_divisibility, RoundingMode::AwayFromZero)
+                        .unwrap()
+                        .min(input_left);
+
+                    order.remaining_amount -= base_amount;
+                    order.unclaimed_proceeds += quote_amount;
+                    input_left -= quote_amount;
+                    output_amount += base_amount;
+
+                    (base_amount, quote_amount)
+                } else {
+                    let fillable = (order.remaining_amount / price)
+                        .checked_round(self.base_divisibility, RoundingMode::ToZero)
+                        .unwrap();
+
+                    let base_amount = input_left.min(fillable);
+                    let quote_amount = (base_amount * price)
+                        .checked_round(self.quote_divisibility, RoundingMode::ToZero)
+                        .unwrap();
+
+                    if fillable > Decimal::ZERO && quote_amount == Decimal::ZERO {
+                        break;
+                    }
+
+                    order.remaining_amount -= quote_amount;
+                    order.unclaimed_proceeds += base_amount;
+                    input_left -= base_amount;
+                    output_amount += quote_amount;
+
+                    (base_amount, quote_amount)
+                };
+
+                // Orders that can not be filled anymore because of the divisibility leave the
+                // book, their remaining amount can be recovered by cancelling them
+                let is_exhausted = match order.side {
+                    OrderSide::Ask => order.remaining_amount == Decimal::ZERO,
+                    OrderSide::Bid => {
+                        (order.remaining_amount / price)
+                            .checked_round(self.base_divisibility, RoundingMode::ToZero)
+                            .unwrap()
+                            == Decimal::ZERO
+                    }
+                };
+
+                if is_exhausted {
+                    order.is_open = false;
+
+                    level.remove(0);
+                    if level.is_empty() {
+                        if buying_base {
+                            self.asks.remove(&price);
+                        } else {
+                            self.bids.remove(&price);
+                        }
+                    }
+                }
+
+                if base_amount > Decimal::ZERO {
+                    Runtime::emit_event(OrderFilledEvent {
+                        order_id,
+                        side: order.side,
+                        price,
+                        base_amount,
+                        quote_amount,
+                    });
+                }
+
+                if input_left == Decimal::ZERO {
+                    break;
+                }
+            }
+
+            let used_input = input.take(input.amount() - input_left);
+
+            let output = if buying_base {
+                self.quote_vault.put(used_input);
+                self.base_vault.take(output_amount)
+            } else {
+                self.base_vault.put(used_input);
+                self.quote_vault.take(output_amount)
+            };
+
+            (output, input)
+        }
+
+        /* GETTERS */
+
+        pub fn get_order(&self, order_id: u64) -> OrderState {
+            self.orders.get(&order_id).expect("Order not found").clone()
+        }
+
+        pub fn get_best_bid(&self) -> Option<Decimal> {
+            self.bids.keys().next_back().cloned()
+        }
+
+        pub fn get_best_ask(&self) -> Option<Decimal> {
+            self.asks.keys().next().cloned()
+        }
+
+        /// Base resource amount available at the best `levels` bid and ask prices, from the best
+        /// price outwards
+        pub fn get_depth(&self, levels: u32) -> (Vec<(Decimal, Decimal)>, Vec<(Decimal, Decimal)>) {
+            let bids = self
+                .bids
+                .iter()
+                .rev()
+                .take(levels as usize)
+                .map(|(price, order_ids)| (*price, self._get_level_base_amount(*price, order_ids)))
+                .collect();
+
+            let asks = self
+                .asks
+                .iter()
+                .take(levels as usize)
+                .map(|(price, order_ids)| (*price, self._get_level_base_amount(*price, order_ids)))
+                .collect();
+
+            (bids, asks)
+        }
+
+        pub fn get_min_order_size(&self) -> Decimal {
+            self.min_order_size
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _get_order_id(local_id: &NonFungibleLocalId) -> u64 {
+            match local_id {
+                NonFungibleLocalId::Integer(id) => id.value(),
+                _ => panic!("Invalid order id"),
+            }
+        }
+
+        fn _get_level_base_amount(&self, price: Decimal, order_ids: &Vec<u64>) -> Decimal {
+            order_ids
+                .iter()
+                .map(|order_id| {
+                    let order = self.orders.get(order_id).unwrap();
+
+                    match order.side {
+                        OrderSide::Ask => order.remaining_amount,
+                        OrderSide::Bid => order.remaining_amount / price,
+                    }
+                })
+                .fold(Decimal::ZERO, |total, amount| total + amount)
+        }
+
+        fn _remove_from_level(&mut self, side: OrderSide, price: Decimal, order_id: u64) {
+            let levels = match side {
+                OrderSide::Bid => &mut self.bids,
+                OrderSide::Ask => &mut self.asks,
+            };
+
+            if let Some(level) = levels.get_mut(&price) {
+                level.retain(|id| *id != order_id);
+
+                if level.is_empty() {
+                    levels.remove(&price);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use order_book::{OrderState, MAX_LEVEL_ORDERS, MAX_PRICE_LEVELS};
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Order book with a minimum order size of 1 base resource. The account holds 100000 of the
+/// base and quote resources.
+struct TestBook {
+    env: TestEnv,
+    account: ComponentAddress,
+    book: ComponentAddress,
+    order_receipt: ResourceAddress,
+    base: ResourceAddress,
+    quote: ResourceAddress,
+}
+
+impl TestBook {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 1);
+        let account = env.accounts[0];
+        let base = env
+            .test_runner
+            .create_fungible_resource(dec!(100000), 18, account);
+        let quote = env
+            .test_runner
+            .create_fungible_resource(dec!(100000), 18, account);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "OrderBook",
+                "instantiate",
+                manifest_args!(base, quote, dec!(1), OwnerRole::None),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            book: commit.new_component_addresses()[0],
+            order_receipt: commit.new_resource_addresses()[0],
+            env,
+            account,
+            base,
+            quote,
+        }
+    }
+
+    /// Execute the manifest with the account key, then deposit the worktop into the account
+    fn execute(&mut self, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute(0, builder)
+    }
+
+    /// Place one order per given price, each escrowing `amount` of the given resource
+    fn place_orders(
+        &mut self,
+        res_address: ResourceAddress,
+        amount: Decimal,
+        prices: Vec<Decimal>,
+    ) -> TransactionReceipt {
+        let mut builder = ManifestBuilder::new().withdraw_from_account(
+            self.account,
+            res_address,
+            amount * Decimal::from(prices.len() as u64),
+        );
+
+        for (index, price) in prices.into_iter().enumerate() {
+            let name = format!("assets_{}", index);
+            builder = builder
+                .take_from_worktop(res_address, amount, name.as_str())
+                .call_method_with_name_lookup(self.book, "place_order", |lookup| {
+                    (lookup.bucket(name.as_str()), price)
+                });
+        }
+
+        self.execute(builder)
+    }
+
+    fn place_order(
+        &mut self,
+        res_address: ResourceAddress,
+        amount: Decimal,
+        price: Decimal,
+    ) -> TransactionReceipt {
+        self.place_orders(res_address, amount, vec![price])
+    }
+
+    fn take(
+        &mut self,
+        res_address: ResourceAddress,
+        amount: Decimal,
+        limit_price: Option<Decimal>,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, res_address, amount)
+            .take_all_from_worktop(res_address, "input")
+            .call_method_with_name_lookup(self.book, "take", |lookup| {
+                (lookup.bucket("input"), limit_price)
+            });
+
+        self.execute(builder)
+    }
+
+    fn claim_proceeds(&mut self, order_id: u64) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_non_fungibles(
+                self.account,
+                self.order_receipt,
+                &BTreeSet::from([NonFungibleLocalId::integer(order_id)]),
+            )
+            .pop_from_auth_zone("order")
+            .call_method_with_name_lookup(self.book, "claim_proceeds", |lookup| {
+                (lookup.proof("order"),)
+            });
+
+        self.execute(builder)
+    }
+
+    fn cancel_order(&mut self, order_id: u64) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_non_fungibles_from_account(
+                self.account,
+                self.order_receipt,
+                &BTreeSet::from([NonFungibleLocalId::integer(order_id)]),
+            )
+            .take_all_from_worktop(self.order_receipt, "order")
+            .call_method_with_name_lookup(self.book, "cancel_order", |lookup| {
+                (lookup.bucket("order"),)
+            });
+
+        self.execute(builder)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str, args: impl ResolvableArguments) -> T {
+        let builder = ManifestBuilder::new().call_method(self.book, method, args);
+
+        self.execute(builder).expect_commit_success().output(0)
+    }
+
+    fn balance(&mut self, res_address: ResourceAddress) -> Decimal {
+        self.env.balance(0, res_address)
+    }
+}
+
+#[test]
+fn test_take_fills_asks_in_price_time_priority() {
+    let mut book = TestBook::new();
+    let (base, quote) = (book.base, book.quote);
+
+    book.place_orders(base, dec!(10), vec![dec!(3), dec!(2), dec!(2)])
+        .expect_commit_success();
+    assert_eq!(
+        book.get::<Option<Decimal>>("get_best_ask", manifest_args!()),
+        Some(dec!(2))
+    );
+
+    // 30 quote buy the 10 base of the first order at 2, then 5 base of the second one
+    book.take(quote, dec!(30), None).expect_commit_success();
+
+    assert_eq!(book.balance(base), dec!(99985));
+    assert_eq!(book.balance(quote), dec!(99970));
+    assert!(
+        !book
+            .get::<OrderState>("get_order", manifest_args!(2u64))
+            .is_open
+    );
+    assert_eq!(
+        book.get::<OrderState>("get_order", manifest_args!(3u64))
+            .remaining_amount,
+        dec!(5)
+    );
+    assert_eq!(
+        book.get::<OrderState>("get_order", manifest_args!(1u64))
+            .remaining_amount,
+        dec!(10)
+    );
+}
+
+#[test]
+fn test_take_stops_at_the_limit_price() {
+    let mut book = TestBook::new();
+    let (base, quote) = (book.base, book.quote);
+
+    book.place_orders(base, dec!(10), vec![dec!(2), dec!(3)])
+        .expect_commit_success();
+    book.take(quote, dec!(50), Some(dec!(2)))
+        .expect_commit_success();
+
+    // Only the order at 2 is filled, the unused input is returned
+    assert_eq!(book.balance(base), dec!(99990));
+    assert_eq!(book.balance(quote), dec!(99980));
+}
+
+#[test]
+fn test_sell_into_bids_and_claim_proceeds() {
+    let mut book = TestBook::new();
+    let (base, quote) = (book.base, book.quote);
+
+    book.place_order(quote, dec!(20), dec!(2))
+        .expect_commit_success();
+    book.take(base, dec!(4), None).expect_commit_success();
+
+    assert_eq!(book.balance(base), dec!(99996));
+    assert_eq!(book.balance(quote), dec!(99988));
+
+    book.claim_proceeds(1).expect_commit_success();
+    assert_eq!(book.balance(base), dec!(100000));
+
+    let order = book.get::<OrderState>("get_order", manifest_args!(1u64));
+    assert_eq!(order.remaining_amount, dec!(12));
+    assert_eq!(order.unclaimed_proceeds, Decimal::ZERO);
+}
+
+#[test]
+fn test_cancel_order_returns_escrow_and_proceeds() {
+    let mut book = TestBook::new();
+    let (base, quote) = (book.base, book.quote);
+
+    book.place_order(base, dec!(10), dec!(2))
+        .expect_commit_success();
+    book.take(quote, dec!(8), None).expect_commit_success();
+    book.cancel_order(1).expect_commit_success();
+
+    assert_eq!(book.balance(base), dec!(100000));
+    assert_eq!(book.balance(quote), dec!(100000));
+    assert_eq!(
+        book.get::<Option<Decimal>>("get_best_ask", manifest_args!()),
+        None
+    );
+}
+
+#[test]
+fn test_crossing_order_fails() {
+    let mut book = TestBook::new();
+    let (base, quote) = (book.base, book.quote);
+
+    book.place_order(base, dec!(10), dec!(2))
+        .expect_commit_success();
+    book.place_order(quote, dec!(10), dec!(2))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_order_below_minimum_size_fails() {
+    let mut book = TestBook::new();
+    let (base, quote) = (book.base, book.quote);
+
+    book.place_order(base, dec!("0.5"), dec!(2))
+        .expect_commit_failure();
+
+    // 1 quote at a price of 2 only buys 0.5 base
+    book.place_order(quote, dec!(1), dec!(2))
+        .expect_commit_failure();
+    book.place_order(quote, dec!(2), dec!(2))
+        .expect_commit_success();
+}
+
+#[test]
+fn test_price_levels_are_capped() {
+    let mut book = TestBook::new();
+    let base = book.base;
+
+    for batch in 0..(MAX_PRICE_LEVELS / 10) {
+        let prices = (1..=10)
+            .map(|index| Decimal::from((batch * 10 + index) as u64))
+            .collect();
+
+        book.place_orders(base, dec!(1), prices)
+            .expect_commit_success();
+    }
+
+    book.place_order(base, dec!(1), Decimal::from((MAX_PRICE_LEVELS + 1) as u64))
+        .expect_commit_failure();
+
+    // Existing levels can still receive orders
+    book.place_order(base, dec!(1), dec!(1))
+        .expect_commit_success();
+}
+
+#[test]
+fn test_orders_per_level_are_capped() {
+    let mut book = TestBook::new();
+    let base = book.base;
+
+    for _ in 0..(MAX_LEVEL_ORDERS / 10) {
+        book.place_orders(base, dec!(1), vec![dec!(2); 10])
+            .expect_commit_success();
+    }
+
+    book.place_order(base, dec!(1), dec!(2))
+        .expect_commit_failure();
+    book.place_order(base, dec!(1), dec!(3))
+        .expect_commit_success();
+}
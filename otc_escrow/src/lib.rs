@@ -0,0 +1,250 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub enum AgreedAssets {
+    Fungible {
+        res_address: ResourceAddress,
+        amount: Decimal,
+    },
+    NonFungible {
+        res_address: ResourceAddress,
+        ids: IndexSet<NonFungibleLocalId>,
+    },
+}
+
+impl AgreedAssets {
+    pub fn res_address(&self) -> ResourceAddress {
+        match self {
+            AgreedAssets::Fungible { res_address, .. } => *res_address,
+            AgreedAssets::NonFungible { res_address, .. } => *res_address,
+        }
+    }
+
+    /// Take the agreed assets out of the deposit, returning them with the change
+    pub fn take_from(&self, mut deposit: Bucket) -> (Bucket, Bucket) {
+        assert!(
+            deposit.resource_address() == self.res_address(),
+            "Deposit resource address mismatch"
+        );
+
+        let assets = match self {
+            AgreedAssets::Fungible { amount, .. } => {
+                assert!(
+                    deposit.amount() >= *amount,
+                    "Deposit is below the agreed amount"
+                );
+
+                deposit.take(*amount)
+            }
+            AgreedAssets::NonFungible { ids, .. } => {
+                deposit.as_non_fungible().take_non_fungibles(ids).into()
+            }
+        };
+
+        (assets, deposit)
+    }
+}
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Party {
+    A,
+    B,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SideFundedEvent {
+    pub party: Party,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SwapExecutedEvent {}
+
+#[blueprint]
+#[events(SideFundedEvent, SwapExecutedEvent)]
+pub mod otc_escrow {
+
+    enable_method_auth! {
+        roles {
+            party_a => updatable_by: [];
+            party_b => updatable_by: [];
+        },
+        methods {
+
+            fund_a => restrict_to :[party_a];
+            withdraw_a => restrict_to :[party_a];
+
+            fund_b => restrict_to :[party_b];
+            withdraw_b => restrict_to :[party_b];
+
+            is_funded => PUBLIC;
+            is_swapped => PUBLIC;
+            get_deadline_epoch => PUBLIC;
+
+        }
+    }
+
+    pub struct OtcEscrow {
+        /// Assets each party agreed to deliver
+        assets_a: AgreedAssets,
+        assets_b: AgreedAssets,
+
+        /// Assets deposited by each party
+        vault_a: Vault,
+        vault_b: Vault,
+
+        funded_a: bool,
+        funded_b: bool,
+
+        /// Last epoch at which the parties can fund the escrow
+        deadline_epoch: u64,
+    }
+
+    impl OtcEscrow {
+        pub fn instantiate(
+            assets_a: AgreedAssets,
+            assets_b: AgreedAssets,
+            deadline_epoch: u64,
+            party_a_rule: AccessRule,
+            party_b_rule: AccessRule,
+        ) -> Global<OtcEscrow> {
+            /* CHECK INPUT */
+            assert!(
+                assets_a.res_address() != assets_b.res_address(),
+                "Swapped resources must differ"
+            );
+            assert!(
+                deadline_epoch >= Runtime::current_epoch().number(),
+                "Deadline must not be in the past"
+            );
+
+            Self {
+                vault_a: Vault::new(assets_a.res_address()),
+                vault_b: Vault::new(assets_b.res_address()),
+                assets_a,
+                assets_b,
+                funded_a: false,
+                funded_b: false,
+                deadline_epoch,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .roles(roles!(
+                party_a => party_a_rule;
+                party_b => party_b_rule;
+            ))
+            .globalize()
+        }
+
+        /* PARTY METHODS */
+
+        /// Deposit the agreed assets of party A. Returns the change.
+        pub fn fund_a(&mut self, deposit: Bucket) -> Bucket {
+            self._fund(Party::A, deposit)
+        }
+
+        /// Withdraw the assets of party B once the swap is executed, or reclaim the deposit
+        /// of party A once the deadline has passed without the swap
+        pub fn withdraw_a(&mut self) -> Bucket {
+            self._withdraw(Party::A)
+        }
+
+        /// Deposit the agreed assets of party B. Returns the change.
+        pub fn fund_b(&mut self, deposit: Bucket) -> Bucket {
+            self._fund(Party::B, deposit)
+        }
+
+        /// Withdraw the assets of party A once the swap is executed, or reclaim the deposit
+        /// of party B once the deadline has passed without the swap
+        pub fn withdraw_b(&mut self) -> Bucket {
+            self._withdraw(Party::B)
+        }
+
+        /* GETTERS */
+
+        pub fn is_funded(&self, party: Party) -> bool {
+            match party {
+                Party::A => self.funded_a,
+                Party::B => self.funded_b,
+            }
+        }
+
+        pub fn is_swapped(&self) -> bool {
+            self.funded_a && self.funded_b
+        }
+
+        pub fn get_deadline_epoch(&self) -> u64 {
+            self.deadline_epoch
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _fund(&mut self, party: Party, deposit: Bucket) -> Bucket {
+            assert!(
+                Runtime::current_epoch().number() <= self.deadline_epoch,
+                "Funding deadline has passed"
+            );
+            assert!(!self.is_funded(party), "Side already funded");
+
+            let (assets, vault, funded) = match party {
+                Party::A => (&self.assets_a, &mut self.vault_a, &mut self.funded_a),
+                Party::B => (&self.assets_b, &mut self.vault_b, &mut self.funded_b),
+            };
+
+            let (agreed, change) = assets.take_from(deposit);
+            vault.put(agreed);
+            *funded = true;
+
+            Runtime::emit_event(SideFundedEvent { party });
+
+            // The swap executes atomically with the second funding: from then on, each party
+            // can only withdraw the assets of the other
+            if self.is_swapped() {
+                Runtime::emit_event(SwapExecutedEvent {});
+            }
+
+            change
+        }
+
+        fn _withdraw(&mut self, party: Party) -> Bucket {
+            if self.is_swapped() {
+                return match party {
+                    Party::A => self.vault_b.take_all(),
+                    Party::B => self.vault_a.take_all(),
+                };
+            }
+
+            assert!(
+                Runtime::current_epoch().number() > self.deadline_epoch,
+                "Swap is pending until the deadline"
+            );
+
+            match party {
+                Party::A => self.vault_a.take_all(),
+                Party::B => self.vault_b.take_all(),
+            }
+        }
+    }
+}
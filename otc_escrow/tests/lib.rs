@@ -0,0 +1,228 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Party A, holding 1000 tokens
+const ALICE: usize = 0;
+/// Party B, holding the non fungibles 1 to 3
+const BOB: usize = 1;
+
+/// Manifest encoding of `AgreedAssets::Fungible`
+fn fungible_assets(res_address: ResourceAddress, amount: Decimal) -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 0,
+        fields: vec![
+            to_manifest_value_and_unwrap!(&res_address),
+            to_manifest_value_and_unwrap!(&amount),
+        ],
+    }
+}
+
+/// Manifest encoding of `AgreedAssets::NonFungible`
+fn non_fungible_assets(res_address: ResourceAddress, ids: Vec<u64>) -> ManifestValue {
+    let ids: Vec<NonFungibleLocalId> = ids.into_iter().map(NonFungibleLocalId::integer).collect();
+
+    ManifestValue::Enum {
+        discriminator: 1,
+        fields: vec![
+            to_manifest_value_and_unwrap!(&res_address),
+            to_manifest_value_and_unwrap!(&ids),
+        ],
+    }
+}
+
+/// Instantiate an escrow between Alice and Bob
+fn instantiate(
+    env: &mut TestEnv,
+    assets_a: ManifestValue,
+    assets_b: ManifestValue,
+    deadline_epoch: u64,
+) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .call_function(
+            env.package_address,
+            "OtcEscrow",
+            "instantiate",
+            manifest_args!(
+                assets_a,
+                assets_b,
+                deadline_epoch,
+                rule!(require(env.badges[ALICE].clone())),
+                rule!(require(env.badges[BOB].clone()))
+            ),
+        )
+        .build();
+
+    env.test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![])
+}
+
+/// Escrow swapping 100 tokens of Alice for the non fungibles 1 and 2 of Bob, with a funding
+/// deadline at epoch 20, instantiated at epoch 10
+struct TestEscrow {
+    env: TestEnv,
+    escrow: ComponentAddress,
+    token: ResourceAddress,
+    nft: ResourceAddress,
+}
+
+impl TestEscrow {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 2);
+        env.set_epoch(10);
+        let token = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, env.accounts[ALICE]);
+        let nft = env
+            .test_runner
+            .create_non_fungible_resource(env.accounts[BOB]);
+
+        let escrow = instantiate(
+            &mut env,
+            fungible_assets(token, dec!(100)),
+            non_fungible_assets(nft, vec![1, 2]),
+            20,
+        )
+        .expect_commit_success()
+        .new_component_addresses()[0];
+
+        Self {
+            env,
+            escrow,
+            token,
+            nft,
+        }
+    }
+
+    fn instantiate(
+        &mut self,
+        assets_a: ManifestValue,
+        assets_b: ManifestValue,
+        deadline_epoch: u64,
+    ) -> TransactionReceipt {
+        instantiate(&mut self.env, assets_a, assets_b, deadline_epoch)
+    }
+
+    /// Call `fund_a` or `fund_b` with the given amount of the resource of the caller
+    fn fund(
+        &mut self,
+        caller: usize,
+        method: &str,
+        res_address: ResourceAddress,
+        amount: Decimal,
+    ) -> TransactionReceipt {
+        let builder =
+            ManifestBuilder::new()
+                .withdraw_from_account(self.env.accounts[caller], res_address, amount)
+                .take_all_from_worktop(res_address, "deposit")
+                .call_method_with_name_lookup(self.escrow, method, |lookup| {
+                    (lookup.bucket("deposit"),)
+                });
+
+        self.env.execute(caller, builder)
+    }
+
+    fn fund_a(&mut self, amount: Decimal) -> TransactionReceipt {
+        let token = self.token;
+
+        self.fund(ALICE, "fund_a", token, amount)
+    }
+
+    fn fund_b(&mut self, amount: Decimal) -> TransactionReceipt {
+        let nft = self.nft;
+
+        self.fund(BOB, "fund_b", nft, amount)
+    }
+
+    fn call(&mut self, caller: usize, method: &str) -> TransactionReceipt {
+        self.env.call(caller, self.escrow, method, manifest_args!())
+    }
+}
+
+#[test]
+fn test_swap_executes_once_both_sides_are_funded() {
+    let mut escrow = TestEscrow::new();
+    let (token, nft) = (escrow.token, escrow.nft);
+
+    escrow.fund_a(dec!(150)).expect_commit_success();
+    assert_eq!(escrow.env.balance(ALICE, token), dec!(900));
+
+    let is_swapped: bool = escrow
+        .call(ALICE, "is_swapped")
+        .expect_commit_success()
+        .output(0);
+    assert!(!is_swapped);
+
+    escrow.fund_b(dec!(3)).expect_commit_success();
+    assert_eq!(escrow.env.balance(BOB, nft), dec!(1));
+
+    escrow.call(ALICE, "withdraw_a").expect_commit_success();
+    escrow.call(BOB, "withdraw_b").expect_commit_success();
+
+    assert_eq!(escrow.env.balance(ALICE, nft), dec!(2));
+    assert_eq!(escrow.env.balance(BOB, token), dec!(100));
+}
+
+#[test]
+fn test_deposit_is_reclaimed_after_the_deadline() {
+    let mut escrow = TestEscrow::new();
+    let token = escrow.token;
+
+    escrow.fund_a(dec!(99)).expect_commit_failure();
+    escrow.fund_a(dec!(100)).expect_commit_success();
+    escrow.fund_a(dec!(100)).expect_commit_failure();
+
+    escrow.env.set_epoch(20);
+    escrow.call(ALICE, "withdraw_a").expect_commit_failure();
+
+    escrow.env.set_epoch(21);
+    escrow.fund_b(dec!(2)).expect_commit_failure();
+
+    escrow.call(ALICE, "withdraw_a").expect_commit_success();
+    assert_eq!(escrow.env.balance(ALICE, token), dec!(1000));
+}
+
+#[test]
+fn test_each_side_is_restricted_to_its_party() {
+    let mut escrow = TestEscrow::new();
+    let token = escrow.token;
+
+    // Bob can not fund the side of Alice, even with the agreed assets
+    let (alice, bob) = (escrow.env.accounts[ALICE], escrow.env.accounts[BOB]);
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(alice, token, dec!(100))
+        .take_all_from_worktop(token, "tokens")
+        .call_method_with_name_lookup(bob, "try_deposit_or_abort", |lookup| {
+            (lookup.bucket("tokens"), None::<ResourceOrNonFungible>)
+        });
+    escrow.env.execute(ALICE, builder).expect_commit_success();
+
+    escrow
+        .fund(BOB, "fund_a", token, dec!(100))
+        .expect_commit_failure();
+    escrow.call(BOB, "withdraw_a").expect_commit_failure();
+    escrow.call(ALICE, "withdraw_b").expect_commit_failure();
+}
+
+#[test]
+fn test_instantiate_rejects_invalid_agreements() {
+    let mut escrow = TestEscrow::new();
+    let (token, nft) = (escrow.token, escrow.nft);
+
+    escrow
+        .instantiate(
+            fungible_assets(token, dec!(100)),
+            fungible_assets(token, dec!(50)),
+            20,
+        )
+        .expect_commit_failure();
+    escrow
+        .instantiate(
+            fungible_assets(token, dec!(100)),
+            non_fungible_assets(nft, vec![1]),
+            9,
+        )
+        .expect_commit_failure();
+}
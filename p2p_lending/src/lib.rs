@@ -0,0 +1,580 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use price_oracle::price_oracle::PriceOracle;
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, PartialEq, Clone, Copy, Debug)]
+pub enum OrderSide {
+    Lend,
+    Borrow,
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct OrderReceipt {
+    pub side: OrderSide,
+}
+
+#[derive(ScryptoSbor)]
+pub struct LendOffer {
+    /// Assets lent, moved to the loan once matched
+    pub assets: Vault,
+    pub collateral_res_address: ResourceAddress,
+    /// Minimum collateral amount per lent unit
+    pub collateral_ratio: Decimal,
+    /// Interest due at maturity, as a fraction of the principal
+    pub interest_rate: Decimal,
+    pub duration_epochs: u64,
+    pub loan_id: Option<u64>,
+    pub cancelled: bool,
+}
+
+#[derive(ScryptoSbor)]
+pub struct BorrowRequest {
+    /// Collateral, moved to the loan once matched
+    pub collateral: Vault,
+    pub loan_res_address: ResourceAddress,
+    pub amount: Decimal,
+    /// Maximum interest accepted, as a fraction of the principal
+    pub max_interest_rate: Decimal,
+    pub duration_epochs: u64,
+    pub loan_id: Option<u64>,
+    pub cancelled: bool,
+}
+
+#[derive(ScryptoSbor, PartialEq, Clone, Copy, Debug)]
+pub enum LoanStatus {
+    Active,
+    Repaid,
+    Defaulted,
+    Liquidated,
+}
+
+#[derive(ScryptoSbor)]
+pub struct Loan {
+    pub lender_id: NonFungibleLocalId,
+    pub borrower_id: NonFungibleLocalId,
+    /// Principal not withdrawn by the borrower yet
+    pub principal: Vault,
+    pub principal_amount: Decimal,
+    pub interest_amount: Decimal,
+    pub collateral: Vault,
+    /// Repayment not claimed by the lender yet
+    pub repayment: Vault,
+    pub due_epoch: u64,
+    pub status: LoanStatus,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct LoanMatchedEvent {
+    pub loan_id: u64,
+    pub lender_id: NonFungibleLocalId,
+    pub borrower_id: NonFungibleLocalId,
+    pub principal_amount: Decimal,
+    pub interest_amount: Decimal,
+    pub due_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct LoanClosedEvent {
+    pub loan_id: u64,
+    pub status: LoanStatus,
+}
+
+#[blueprint]
+#[events(LoanMatchedEvent, LoanClosedEvent)]
+pub mod p2p_lending {
+
+    enable_method_auth! {
+        methods {
+
+            set_oracle => restrict_to :[OWNER];
+
+            post_offer => PUBLIC;
+            cancel_offer => PUBLIC;
+            post_request => PUBLIC;
+            cancel_request => PUBLIC;
+            match_orders => PUBLIC;
+
+            withdraw_principal => PUBLIC;
+            repay => PUBLIC;
+            claim_default => PUBLIC;
+            liquidate => PUBLIC;
+            claim_repayment => PUBLIC;
+
+            get_loan => PUBLIC;
+
+        }
+    }
+
+    pub struct P2pLending {
+        /// Order receipt non-fungible resource manager
+        receipt_res_manager: ResourceManager,
+
+        offers: KeyValueStore<NonFungibleLocalId, LendOffer>,
+        requests: KeyValueStore<NonFungibleLocalId, BorrowRequest>,
+
+        loans: KeyValueStore<u64, Loan>,
+        next_loan_id: u64,
+
+        /// Oracle pricing collateral in the loan resource, enabling liquidations before
+        /// maturity
+        oracle: Option<Global<PriceOracle>>,
+        max_price_age_seconds: i64,
+
+        /// Collateral value to debt ratio below which a loan can be liquidated
+        liquidation_threshold: Decimal,
+    }
+
+    impl P2pLending {
+        pub fn instantiate(
+            owner_role: OwnerRole,
+            oracle: Option<Global<PriceOracle>>,
+            max_price_age_seconds: i64,
+            liquidation_threshold: Decimal,
+        ) -> (Global<P2pLending>, ResourceAddress) {
+            /* CHECK INPUT */
+            P2pLending::_assert_valid_oracle_config(max_price_age_seconds, liquidation_threshold);
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(P2pLending::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let receipt_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<OrderReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule;
+                        burner_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let lending_component = Self {
+                receipt_res_manager,
+                offers: KeyValueStore::new(),
+                requests: KeyValueStore::new(),
+                loans: KeyValueStore::new(),
+                next_loan_id: 1,
+                oracle,
+                max_price_age_seconds,
+                liquidation_threshold,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (lending_component, receipt_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn set_oracle(
+            &mut self,
+            oracle: Option<Global<PriceOracle>>,
+            max_price_age_seconds: i64,
+            liquidation_threshold: Decimal,
+        ) {
+            P2pLending::_assert_valid_oracle_config(max_price_age_seconds, liquidation_threshold);
+
+            self.oracle = oracle;
+            self.max_price_age_seconds = max_price_age_seconds;
+            self.liquidation_threshold = liquidation_threshold;
+        }
+
+        /* ORDER METHODS */
+
+        /// Offer to lend the given assets. Returns the lender receipt, which also tracks the
+        /// loan once the offer is matched.
+        pub fn post_offer(
+            &mut self,
+            assets: Bucket,
+            collateral_res_address: ResourceAddress,
+            collateral_ratio: Decimal,
+            interest_rate: Decimal,
+            duration_epochs: u64,
+        ) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                assets.amount() > Decimal::ZERO,
+                "Lent amount must be greater than zero!"
+            );
+            assert!(
+                assets.resource_address() != collateral_res_address,
+                "Loan and collateral resources must be different"
+            );
+            assert!(
+                collateral_ratio > Decimal::ZERO,
+                "Collateral ratio must be greater than zero"
+            );
+            assert!(
+                interest_rate >= Decimal::ZERO,
+                "Interest rate must not be negative"
+            );
+            assert!(duration_epochs > 0, "Duration must be greater than zero");
+
+            let receipt = self
+                .receipt_res_manager
+                .mint_ruid_non_fungible(OrderReceipt {
+                    side: OrderSide::Lend,
+                });
+
+            self.offers.insert(
+                receipt.as_non_fungible().non_fungible_local_id(),
+                LendOffer {
+                    assets: Vault::with_bucket(assets),
+                    collateral_res_address,
+                    collateral_ratio,
+                    interest_rate,
+                    duration_epochs,
+                    loan_id: None,
+                    cancelled: false,
+                },
+            );
+
+            receipt
+        }
+
+        pub fn cancel_offer(&mut self, lender_proof: Proof) -> Bucket {
+            let offer_id = self._check_receipt(lender_proof, OrderSide::Lend);
+
+            let mut offer = self.offers.get_mut(&offer_id).unwrap();
+
+            assert!(offer.loan_id.is_none(), "Offer already matched");
+
+            offer.cancelled = true;
+            offer.assets.take_all()
+        }
+
+        /// Request to borrow an amount against the given collateral. Returns the borrower
+        /// receipt, which also tracks the loan once the request is matched.
+        pub fn post_request(
+            &mut self,
+            collateral: Bucket,
+            loan_res_address: ResourceAddress,
+            amount: Decimal,
+            max_interest_rate: Decimal,
+            duration_epochs: u64,
+        ) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                collateral.amount() > Decimal::ZERO,
+                "Collateral amount must be greater than zero!"
+            );
+            assert!(
+                collateral.resource_address() != loan_res_address,
+                "Loan and collateral resources must be different"
+            );
+            assert!(
+                amount > Decimal::ZERO,
+                "Borrowed amount must be greater than zero!"
+            );
+            assert!(
+                max_interest_rate >= Decimal::ZERO,
+                "Interest rate must not be negative"
+            );
+            assert!(duration_epochs > 0, "Duration must be greater than zero");
+
+            let receipt = self
+                .receipt_res_manager
+                .mint_ruid_non_fungible(OrderReceipt {
+                    side: OrderSide::Borrow,
+                });
+
+            self.requests.insert(
+                receipt.as_non_fungible().non_fungible_local_id(),
+                BorrowRequest {
+                    collateral: Vault::with_bucket(collateral),
+                    loan_res_address,
+                    amount,
+                    max_interest_rate,
+                    duration_epochs,
+                    loan_id: None,
+                    cancelled: false,
+                },
+            );
+
+            receipt
+        }
+
+        pub fn cancel_request(&mut self, borrower_proof: Proof) -> Bucket {
+            let request_id = self._check_receipt(borrower_proof, OrderSide::Borrow);
+
+            let mut request = self.requests.get_mut(&request_id).unwrap();
+
+            assert!(request.loan_id.is_none(), "Request already matched");
+
+            request.cancelled = true;
+            request.collateral.take_all()
+        }
+
+        /// Match a lend offer with a borrow request of the same amount and duration. The
+        /// offer interest rate applies. Anyone can match compatible orders.
+        pub fn match_orders(
+            &mut self,
+            offer_id: NonFungibleLocalId,
+            request_id: NonFungibleLocalId,
+        ) -> u64 {
+            let loan_id = self.next_loan_id;
+
+            let mut offer = self.offers.get_mut(&offer_id).expect("Offer not found");
+            let mut request = self
+                .requests
+                .get_mut(&request_id)
+                .expect("Request not found");
+
+            assert!(
+                offer.loan_id.is_none() && !offer.cancelled,
+                "Offer is not open"
+            );
+            assert!(
+                request.loan_id.is_none() && !request.cancelled,
+                "Request is not open"
+            );
+            assert!(
+                offer.assets.resource_address() == request.loan_res_address
+                    && offer.collateral_res_address == request.collateral.resource_address(),
+                "Order resources mismatch"
+            );
+            assert!(
+                offer.assets.amount() == request.amount
+                    && offer.duration_epochs == request.duration_epochs,
+                "Order amount or duration mismatch"
+            );
+            assert!(
+                offer.interest_rate <= request.max_interest_rate,
+                "Offer interest rate exceeds the request maximum"
+            );
+            assert!(
+                request.collateral.amount() >= request.amount * offer.collateral_ratio,
+                "Insufficient collateral"
+            );
+
+            let principal_amount = request.amount;
+            let interest_amount = principal_amount * offer.interest_rate;
+            let due_epoch = Runtime::current_epoch().number() + offer.duration_epochs;
+
+            offer.loan_id = Some(loan_id);
+            request.loan_id = Some(loan_id);
+
+            let loan = Loan {
+                lender_id: offer_id.clone(),
+                borrower_id: request_id.clone(),
+                principal: Vault::with_bucket(offer.assets.take_all()),
+                principal_amount,
+                interest_amount,
+                collateral: Vault::with_bucket(request.collateral.take_all()),
+                repayment: Vault::new(request.loan_res_address),
+                due_epoch,
+                status: LoanStatus::Active,
+            };
+
+            drop(offer);
+            drop(request);
+
+            self.loans.insert(loan_id, loan);
+            self.next_loan_id += 1;
+
+            Runtime::emit_event(LoanMatchedEvent {
+                loan_id,
+                lender_id: offer_id,
+                borrower_id: request_id,
+                principal_amount,
+                interest_amount,
+                due_epoch,
+            });
+
+            loan_id
+        }
+
+        /* BORROWER METHODS */
+
+        pub fn withdraw_principal(&mut self, borrower_proof: Proof) -> Bucket {
+            let loan_id = self._get_borrower_loan_id(borrower_proof);
+
+            self.loans.get_mut(&loan_id).unwrap().principal.take_all()
+        }
+
+        /// Repay principal and interest of an active loan. Returns the collateral and the
+        /// payment change.
+        pub fn repay(&mut self, borrower_proof: Proof, mut payment: Bucket) -> (Bucket, Bucket) {
+            let loan_id = self._get_borrower_loan_id(borrower_proof);
+
+            let mut loan = self.loans.get_mut(&loan_id).unwrap();
+
+            assert!(loan.status == LoanStatus::Active, "Loan is not active");
+
+            let amount_due = loan.principal_amount + loan.interest_amount;
+
+            assert!(payment.amount() >= amount_due, "Insufficient repayment");
+
+            loan.repayment.put(payment.take(amount_due));
+            loan.status = LoanStatus::Repaid;
+
+            let collateral = loan.collateral.take_all();
+
+            drop(loan);
+
+            Runtime::emit_event(LoanClosedEvent {
+                loan_id,
+                status: LoanStatus::Repaid,
+            });
+
+            (collateral, payment)
+        }
+
+        /* LENDER METHODS */
+
+        /// Claim the collateral of a loan not repaid by its due epoch
+        pub fn claim_default(&mut self, lender_proof: Proof) -> Bucket {
+            let loan_id = self._get_lender_loan_id(lender_proof);
+
+            let mut loan = self.loans.get_mut(&loan_id).unwrap();
+
+            assert!(loan.status == LoanStatus::Active, "Loan is not active");
+            assert!(
+                Runtime::current_epoch().number() > loan.due_epoch,
+                "Loan is not due yet"
+            );
+
+            loan.status = LoanStatus::Defaulted;
+
+            let collateral = loan.collateral.take_all();
+
+            drop(loan);
+
+            Runtime::emit_event(LoanClosedEvent {
+                loan_id,
+                status: LoanStatus::Defaulted,
+            });
+
+            collateral
+        }
+
+        /// Claim the collateral of an active loan before its due epoch, when the oracle value
+        /// of the collateral falls below the liquidation threshold of the amount due
+        pub fn liquidate(&mut self, lender_proof: Proof) -> Bucket {
+            let loan_id = self._get_lender_loan_id(lender_proof);
+
+            let oracle = self.oracle.as_ref().expect("Liquidations are disabled");
+
+            let mut loan = self.loans.get_mut(&loan_id).unwrap();
+
+            assert!(loan.status == LoanStatus::Active, "Loan is not active");
+
+            let price = oracle.get_price(
+                loan.collateral.resource_address(),
+                loan.repayment.resource_address(),
+                self.max_price_age_seconds,
+            );
+            let collateral_value = loan.collateral.amount() * price;
+            let amount_due = loan.principal_amount + loan.interest_amount;
+
+            assert!(
+                collateral_value < amount_due * self.liquidation_threshold,
+                "Loan is sufficiently collateralized"
+            );
+
+            loan.status = LoanStatus::Liquidated;
+
+            let collateral = loan.collateral.take_all();
+
+            drop(loan);
+
+            Runtime::emit_event(LoanClosedEvent {
+                loan_id,
+                status: LoanStatus::Liquidated,
+            });
+
+            collateral
+        }
+
+        pub fn claim_repayment(&mut self, lender_proof: Proof) -> Bucket {
+            let loan_id = self._get_lender_loan_id(lender_proof);
+
+            self.loans.get_mut(&loan_id).unwrap().repayment.take_all()
+        }
+
+        /* GETTERS */
+
+        /// Status, principal amount, interest amount and due epoch of a loan
+        pub fn get_loan(&self, loan_id: u64) -> (LoanStatus, Decimal, Decimal, u64) {
+            let loan = self.loans.get(&loan_id).expect("Loan not found");
+
+            (
+                loan.status,
+                loan.principal_amount,
+                loan.interest_amount,
+                loan.due_epoch,
+            )
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _check_receipt(&self, receipt_proof: Proof, side: OrderSide) -> NonFungibleLocalId {
+            let receipt = receipt_proof
+                .check(self.receipt_res_manager.address())
+                .as_non_fungible()
+                .non_fungible::<OrderReceipt>();
+
+            assert!(receipt.data().side == side, "Receipt side mismatch");
+
+            receipt.local_id().clone()
+        }
+
+        fn _get_lender_loan_id(&self, lender_proof: Proof) -> u64 {
+            let offer_id = self._check_receipt(lender_proof, OrderSide::Lend);
+
+            self.offers
+                .get(&offer_id)
+                .unwrap()
+                .loan_id
+                .expect("Offer not matched")
+        }
+
+        fn _get_borrower_loan_id(&self, borrower_proof: Proof) -> u64 {
+            let request_id = self._check_receipt(borrower_proof, OrderSide::Borrow);
+
+            self.requests
+                .get(&request_id)
+                .unwrap()
+                .loan_id
+                .expect("Request not matched")
+        }
+
+        fn _assert_valid_oracle_config(max_price_age_seconds: i64, liquidation_threshold: Decimal) {
+            assert!(
+                max_price_age_seconds > 0,
+                "Max price age must be greater than zero"
+            );
+            assert!(
+                liquidation_threshold > Decimal::ZERO,
+                "Liquidation threshold must be greater than zero"
+            );
+        }
+    }
+}
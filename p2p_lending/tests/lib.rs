@@ -0,0 +1,393 @@
+use p2p_lending::LoanStatus;
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the platform and feeds the oracle
+const OWNER: usize = 0;
+/// Holds 1000 loan tokens
+const LENDER: usize = 1;
+/// Holds 1000 collateral tokens and 100 loan tokens
+const BORROWER: usize = 2;
+
+/// Ledger time of the tests, in seconds, rounded to the minute
+const NOW: i64 = 1_700_000_040;
+
+/// Platform liquidating loans whose collateral value falls below 120% of the amount due,
+/// with the collateral priced 1 loan token by the oracle, at epoch 10. The oracle blueprint is
+/// linked into the lending package, so it is instantiated from the same package.
+struct TestLending {
+    env: TestEnv,
+    lending: ComponentAddress,
+    receipt: ResourceAddress,
+    oracle: ComponentAddress,
+    loan_token: ResourceAddress,
+    collateral: ResourceAddress,
+}
+
+impl TestLending {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        env.test_runner
+            .advance_to_round_at_timestamp(Round::of(1), NOW * 1000);
+        let loan_token =
+            env.test_runner
+                .create_fungible_resource(dec!(1100), 18, env.accounts[LENDER]);
+        let collateral =
+            env.test_runner
+                .create_fungible_resource(dec!(1000), 18, env.accounts[BORROWER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "PriceOracle",
+                "instantiate",
+                manifest_args!(
+                    owner_role.clone(),
+                    rule!(require(env.badges[OWNER].clone())),
+                    2u32
+                ),
+            )
+            .withdraw_from_account(env.accounts[LENDER], loan_token, dec!(100))
+            .take_all_from_worktop(loan_token, "borrower")
+            .call_method_with_name_lookup(env.accounts[BORROWER], "deposit", |lookup| {
+                (lookup.bucket("borrower"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let oracle = receipt.expect_commit_success().new_component_addresses()[0];
+
+        let manifest = ManifestBuilder::new()
+            .call_method(
+                oracle,
+                "push_price",
+                manifest_args!(collateral, loan_token, dec!(1), NOW - 60),
+            )
+            .call_function(
+                env.package_address,
+                "P2pLending",
+                "instantiate",
+                manifest_args!(owner_role, Some(oracle), 3600i64, dec!("1.2")),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            lending: commit.new_component_addresses()[0],
+            receipt: commit.new_resource_addresses()[0],
+            env,
+            oracle,
+            loan_token,
+            collateral,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.lending, method, args)
+    }
+
+    fn receipt_ids(&mut self, account: usize) -> Vec<NonFungibleLocalId> {
+        let vault_ids = self
+            .env
+            .test_runner
+            .get_component_vaults(self.env.accounts[account], self.receipt);
+
+        match vault_ids.first() {
+            Some(vault_id) => {
+                let (_, ids) = self
+                    .env
+                    .test_runner
+                    .inspect_non_fungible_vault(*vault_id)
+                    .unwrap();
+
+                ids.collect()
+            }
+            None => vec![],
+        }
+    }
+
+    /// Execute the manifest of an order, and return the id of the new receipt
+    fn post(&mut self, account: usize, builder: ManifestBuilder) -> NonFungibleLocalId {
+        let previous_ids = self.receipt_ids(account);
+
+        self.env.execute(account, builder).expect_commit_success();
+
+        self.receipt_ids(account)
+            .into_iter()
+            .find(|id| !previous_ids.contains(id))
+            .unwrap()
+    }
+
+    /// Offer to lend 100 loan tokens for 10 epochs, against 150% of collateral
+    fn post_offer(&mut self, interest_rate: Decimal) -> NonFungibleLocalId {
+        let (loan_token, collateral) = (self.loan_token, self.collateral);
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[LENDER], loan_token, dec!(100))
+            .take_all_from_worktop(loan_token, "assets")
+            .call_method_with_name_lookup(self.lending, "post_offer", |lookup| {
+                (
+                    lookup.bucket("assets"),
+                    collateral,
+                    dec!("1.5"),
+                    interest_rate,
+                    10u64,
+                )
+            });
+
+        self.post(LENDER, builder)
+    }
+
+    /// Request to borrow 100 loan tokens for 10 epochs, against the given collateral
+    fn post_request(
+        &mut self,
+        collateral_amount: Decimal,
+        max_interest_rate: Decimal,
+    ) -> NonFungibleLocalId {
+        let (loan_token, collateral) = (self.loan_token, self.collateral);
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[BORROWER], collateral, collateral_amount)
+            .take_all_from_worktop(collateral, "collateral")
+            .call_method_with_name_lookup(self.lending, "post_request", |lookup| {
+                (
+                    lookup.bucket("collateral"),
+                    loan_token,
+                    dec!(100),
+                    max_interest_rate,
+                    10u64,
+                )
+            });
+
+        self.post(BORROWER, builder)
+    }
+
+    fn match_orders(
+        &mut self,
+        offer_id: &NonFungibleLocalId,
+        request_id: &NonFungibleLocalId,
+    ) -> TransactionReceipt {
+        self.call(
+            OWNER,
+            "match_orders",
+            manifest_args!(offer_id.clone(), request_id.clone()),
+        )
+    }
+
+    /// Post and match a loan of 100 loan tokens at 10% of interest, against 150 collateral
+    fn new_loan(&mut self) -> (NonFungibleLocalId, NonFungibleLocalId) {
+        let offer_id = self.post_offer(dec!("0.1"));
+        let request_id = self.post_request(dec!(150), dec!("0.1"));
+
+        self.match_orders(&offer_id, &request_id)
+            .expect_commit_success();
+
+        (offer_id, request_id)
+    }
+
+    /// Call a method with a proof of the given receipt of the account
+    fn call_with_receipt<A: ResolvableArguments>(
+        &mut self,
+        account: usize,
+        receipt_id: &NonFungibleLocalId,
+        method: &str,
+        args: impl FnOnce(ManifestProof) -> A,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_non_fungibles(
+                self.env.accounts[account],
+                self.receipt,
+                &BTreeSet::from([receipt_id.clone()]),
+            )
+            .pop_from_auth_zone("receipt")
+            .call_method_with_name_lookup(self.lending, method, |lookup| {
+                args(lookup.proof("receipt"))
+            });
+
+        self.env.execute(account, builder)
+    }
+
+    /// Repay the loan of the request with the given amount of loan tokens
+    fn repay(&mut self, request_id: &NonFungibleLocalId, amount: Decimal) -> TransactionReceipt {
+        let loan_token = self.loan_token;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[BORROWER], loan_token, amount)
+            .take_all_from_worktop(loan_token, "payment")
+            .create_proof_from_account_of_non_fungibles(
+                self.env.accounts[BORROWER],
+                self.receipt,
+                &BTreeSet::from([request_id.clone()]),
+            )
+            .pop_from_auth_zone("receipt")
+            .call_method_with_name_lookup(self.lending, "repay", |lookup| {
+                (lookup.proof("receipt"), lookup.bucket("payment"))
+            });
+
+        self.env.execute(BORROWER, builder)
+    }
+
+    fn get_loan(&mut self, loan_id: u64) -> (LoanStatus, Decimal, Decimal, u64) {
+        self.call(OWNER, "get_loan", manifest_args!(loan_id))
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_matched_loan_is_repaid() {
+    let mut lending = TestLending::new();
+    let (loan_token, collateral) = (lending.loan_token, lending.collateral);
+
+    let (offer_id, request_id) = lending.new_loan();
+    assert_eq!(
+        lending.get_loan(1),
+        (LoanStatus::Active, dec!(100), dec!(10), 20)
+    );
+
+    lending
+        .call_with_receipt(
+            BORROWER,
+            &request_id,
+            "withdraw_principal",
+            |proof| (proof,),
+        )
+        .expect_commit_success();
+    assert_eq!(lending.env.balance(BORROWER, loan_token), dec!(200));
+    assert_eq!(lending.env.balance(BORROWER, collateral), dec!(850));
+
+    lending
+        .repay(&request_id, dec!(109))
+        .expect_commit_failure();
+    lending
+        .repay(&request_id, dec!(120))
+        .expect_commit_success();
+    assert_eq!(lending.env.balance(BORROWER, loan_token), dec!(90));
+    assert_eq!(lending.env.balance(BORROWER, collateral), dec!(1000));
+    assert_eq!(lending.get_loan(1).0, LoanStatus::Repaid);
+
+    lending.repay(&request_id, dec!(90)).expect_commit_failure();
+
+    lending
+        .call_with_receipt(LENDER, &offer_id, "claim_repayment", |proof| (proof,))
+        .expect_commit_success();
+    assert_eq!(lending.env.balance(LENDER, loan_token), dec!(1010));
+
+    lending.env.set_epoch(21);
+    lending
+        .call_with_receipt(LENDER, &offer_id, "claim_default", |proof| (proof,))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_match_rejects_incompatible_orders() {
+    let mut lending = TestLending::new();
+    let collateral = lending.collateral;
+
+    let offer_id = lending.post_offer(dec!("0.1"));
+
+    let request_id = lending.post_request(dec!(150), dec!("0.05"));
+    lending
+        .match_orders(&offer_id, &request_id)
+        .expect_commit_failure();
+    lending
+        .call_with_receipt(BORROWER, &request_id, "cancel_request", |proof| (proof,))
+        .expect_commit_success();
+    assert_eq!(lending.env.balance(BORROWER, collateral), dec!(1000));
+
+    let request_id = lending.post_request(dec!(149), dec!("0.1"));
+    lending
+        .match_orders(&offer_id, &request_id)
+        .expect_commit_failure();
+
+    let request_id = lending.post_request(dec!(150), dec!("0.1"));
+    lending
+        .match_orders(&offer_id, &request_id)
+        .expect_commit_success();
+    lending
+        .match_orders(&offer_id, &request_id)
+        .expect_commit_failure();
+
+    lending
+        .call_with_receipt(LENDER, &offer_id, "cancel_offer", |proof| (proof,))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_lender_claims_the_collateral_of_a_defaulted_loan() {
+    let mut lending = TestLending::new();
+    let collateral = lending.collateral;
+
+    let (offer_id, request_id) = lending.new_loan();
+
+    lending.env.set_epoch(20);
+    lending
+        .call_with_receipt(LENDER, &offer_id, "claim_default", |proof| (proof,))
+        .expect_commit_failure();
+
+    lending.env.set_epoch(21);
+    lending
+        .call_with_receipt(BORROWER, &request_id, "claim_default", |proof| (proof,))
+        .expect_commit_failure();
+    lending
+        .call_with_receipt(LENDER, &offer_id, "claim_default", |proof| (proof,))
+        .expect_commit_success();
+
+    assert_eq!(lending.env.balance(LENDER, collateral), dec!(150));
+    assert_eq!(lending.get_loan(1).0, LoanStatus::Defaulted);
+    lending
+        .repay(&request_id, dec!(110))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_undercollateralized_loan_is_liquidated() {
+    let mut lending = TestLending::new();
+    let (oracle, loan_token, collateral) = (lending.oracle, lending.loan_token, lending.collateral);
+
+    let (offer_id, _) = lending.new_loan();
+
+    // 150 collateral are worth more than 120% of the 110 due
+    lending
+        .call_with_receipt(LENDER, &offer_id, "liquidate", |proof| (proof,))
+        .expect_commit_failure();
+
+    let builder = ManifestBuilder::new().call_method(
+        oracle,
+        "push_price",
+        manifest_args!(collateral, loan_token, dec!("0.8"), NOW),
+    );
+    lending.env.execute(OWNER, builder).expect_commit_success();
+
+    lending
+        .call_with_receipt(LENDER, &offer_id, "liquidate", |proof| (proof,))
+        .expect_commit_success();
+    assert_eq!(lending.env.balance(LENDER, collateral), dec!(150));
+    assert_eq!(lending.get_loan(1).0, LoanStatus::Liquidated);
+
+    lending
+        .call(
+            LENDER,
+            "set_oracle",
+            manifest_args!(None::<ComponentAddress>, 3600i64, dec!("1.2")),
+        )
+        .expect_commit_failure();
+    lending
+        .call(
+            OWNER,
+            "set_oracle",
+            manifest_args!(None::<ComponentAddress>, 0i64, dec!("1.2")),
+        )
+        .expect_commit_failure();
+}
@@ -0,0 +1,200 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use asset_pool_interface::AssetPoolStub;
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+/// Maximum number of pools registered to a controller, bounding the cost of `pause_all`
+pub const MAX_POOLS: usize = 100;
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PoolsPausedEvent {
+    pub pools: Vec<ComponentAddress>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PoolsResumedEvent {
+    pub pools: Vec<ComponentAddress>,
+}
+
+#[blueprint]
+#[events(PoolsPausedEvent, PoolsResumedEvent)]
+pub mod pause_controller {
+
+    enable_method_auth! {
+        roles {
+            guardian => updatable_by: [OWNER];
+        },
+        methods {
+
+            register_pool => restrict_to :[OWNER];
+            unregister_pool => restrict_to :[OWNER];
+            deposit_badge => restrict_to :[OWNER];
+            withdraw_badge => restrict_to :[OWNER];
+            resume_pool => restrict_to :[OWNER];
+            resume_all => restrict_to :[OWNER];
+
+            pause_pool => restrict_to :[guardian, OWNER];
+            pause_all => restrict_to :[guardian, OWNER];
+
+            get_pools => PUBLIC;
+            get_paused_pools => PUBLIC;
+
+        }
+    }
+
+    pub struct PauseController {
+        /// Fungible badge satisfying the owner rule of every registered pool
+        badge: Vault,
+
+        pools: IndexMap<ComponentAddress, AssetPoolStub>,
+    }
+
+    impl PauseController {
+        /// Instantiate a controller holding the given badge. The owner rule of registered
+        /// pools must be satisfied by this badge. Resuming pools is restricted to the owner,
+        /// while the guardian role can only pause them.
+        pub fn instantiate(
+            badge: Bucket,
+            owner_role: OwnerRole,
+            guardian_rule: AccessRule,
+        ) -> Global<PauseController> {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                badge.resource_address(),
+                Some("Badge must be fungible".to_string()),
+            );
+
+            Self {
+                badge: Vault::with_bucket(badge),
+                pools: IndexMap::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                guardian => guardian_rule;
+            ))
+            .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn register_pool(&mut self, pool_address: ComponentAddress) {
+            /* CHECK INPUT */
+            assert!(
+                !self.pools.contains_key(&pool_address),
+                "Pool already registered"
+            );
+            assert!(self.pools.len() < MAX_POOLS, "Too many pools");
+
+            self.pools
+                .insert(pool_address, AssetPoolStub::from(pool_address));
+        }
+
+        pub fn unregister_pool(&mut self, pool_address: ComponentAddress) {
+            assert!(
+                self.pools.shift_remove(&pool_address).is_some(),
+                "Pool not registered"
+            );
+        }
+
+        pub fn deposit_badge(&mut self, badge: Bucket) {
+            self.badge.put(badge);
+        }
+
+        pub fn withdraw_badge(&mut self) -> Bucket {
+            self.badge.take_all()
+        }
+
+        pub fn resume_pool(&mut self, pool_address: ComponentAddress) {
+            self._set_paused(vec![pool_address], false);
+        }
+
+        pub fn resume_all(&mut self) {
+            self._set_paused(self.pools.keys().cloned().collect(), false);
+        }
+
+        /* GUARDIAN METHODS */
+
+        pub fn pause_pool(&mut self, pool_address: ComponentAddress) {
+            self._set_paused(vec![pool_address], true);
+        }
+
+        /// Pause every registered pool in a single transaction
+        pub fn pause_all(&mut self) {
+            self._set_paused(self.pools.keys().cloned().collect(), true);
+        }
+
+        /* GETTERS */
+
+        pub fn get_pools(&self) -> Vec<ComponentAddress> {
+            self.pools.keys().cloned().collect()
+        }
+
+        pub fn get_paused_pools(&self) -> Vec<ComponentAddress> {
+            self.pools
+                .iter()
+                .filter(|(_, pool)| pool.is_paused())
+                .map(|(pool_address, _)| *pool_address)
+                .collect()
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        /// Pause or resume pools, skipping those already in the requested state
+        fn _set_paused(&mut self, pool_addresses: Vec<ComponentAddress>, paused: bool) {
+            let badge_amount = self.badge.amount();
+            assert!(badge_amount > Decimal::ZERO, "No badge deposited");
+
+            let pools = &self.pools;
+            let mut changed = Vec::new();
+
+            self.badge
+                .as_fungible()
+                .authorize_with_amount(badge_amount, || {
+                    for pool_address in pool_addresses {
+                        let pool = pools.get(&pool_address).expect("Pool not registered");
+
+                        if pool.is_paused() == paused {
+                            continue;
+                        }
+
+                        if paused {
+                            pool.pause();
+                        } else {
+                            pool.resume();
+                        }
+
+                        changed.push(pool_address);
+                    }
+                });
+
+            if paused {
+                Runtime::emit_event(PoolsPausedEvent { pools: changed });
+            } else {
+                Runtime::emit_event(PoolsResumedEvent { pools: changed });
+            }
+        }
+    }
+}
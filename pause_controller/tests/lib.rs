@@ -0,0 +1,318 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the controller, and administrates the pools. Holds 100 assets.
+const OWNER: usize = 0;
+const GUARDIAN: usize = 1;
+const ALICE: usize = 2;
+
+/// Controller holding the badge of the owner rule of two pools, A and B, which are not
+/// registered yet. The pools are instantiated from the published SingleResourcePool package.
+struct TestController {
+    env: TestEnv,
+    pool_package_address: PackageAddress,
+    controller: ComponentAddress,
+    pool_a: ComponentAddress,
+    pool_b: ComponentAddress,
+    badge: ResourceAddress,
+    asset: ResourceAddress,
+}
+
+impl TestController {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        let asset = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[OWNER]);
+        let badge = env
+            .test_runner
+            .create_fungible_resource(dec!(1), 0, env.accounts[OWNER]);
+        let pool_owner_role = OwnerRole::Fixed(rule!(require(badge)));
+        let admin_rule = rule!(require(env.badges[OWNER].clone()));
+        let pool_package_address = env.test_runner.compile_and_publish(format!(
+            "{}/../single_resource_pool",
+            env!("CARGO_MANIFEST_DIR")
+        ));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                pool_package_address,
+                "AssetPool",
+                "instantiate",
+                manifest_args!(asset, pool_owner_role.clone(), admin_rule.clone()),
+            )
+            .call_function(
+                pool_package_address,
+                "AssetPool",
+                "instantiate",
+                manifest_args!(asset, pool_owner_role, admin_rule),
+            )
+            .withdraw_from_account(env.accounts[OWNER], badge, dec!(1))
+            .take_all_from_worktop(badge, "badge")
+            .call_function_with_name_lookup(
+                env.package_address,
+                "PauseController",
+                "instantiate",
+                |lookup| {
+                    (
+                        lookup.bucket("badge"),
+                        OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone()))),
+                        rule!(require(env.badges[GUARDIAN].clone())),
+                    )
+                },
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            pool_a: commit.new_component_addresses()[0],
+            pool_b: commit.new_component_addresses()[1],
+            controller: commit.new_component_addresses()[2],
+            env,
+            pool_package_address,
+            badge,
+            asset,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.controller, method, args)
+    }
+
+    /// Register both pools
+    fn register_pools(&mut self) {
+        let (pool_a, pool_b) = (self.pool_a, self.pool_b);
+
+        self.call(OWNER, "register_pool", manifest_args!(pool_a))
+            .expect_commit_success();
+        self.call(OWNER, "register_pool", manifest_args!(pool_b))
+            .expect_commit_success();
+    }
+
+    /// Contribute 10 assets of the owner to the pool
+    fn contribute(&mut self, pool: ComponentAddress) -> TransactionReceipt {
+        let asset = self.asset;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[OWNER], asset, dec!(10))
+            .take_all_from_worktop(asset, "assets")
+            .call_method_with_name_lookup(pool, "contribute", |lookup| (lookup.bucket("assets"),));
+
+        self.env.execute(OWNER, builder)
+    }
+
+    fn get_paused_pools(&mut self) -> Vec<ComponentAddress> {
+        self.call(ALICE, "get_paused_pools", manifest_args!())
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_guardian_pauses_and_owner_resumes() {
+    let mut controller = TestController::new();
+    let (pool_a, pool_b) = (controller.pool_a, controller.pool_b);
+
+    controller
+        .call(ALICE, "register_pool", manifest_args!(pool_a))
+        .expect_commit_failure();
+    controller.register_pools();
+    controller
+        .call(OWNER, "register_pool", manifest_args!(pool_a))
+        .expect_commit_failure();
+
+    controller
+        .call(ALICE, "pause_all", manifest_args!())
+        .expect_commit_failure();
+    controller
+        .call(GUARDIAN, "pause_all", manifest_args!())
+        .expect_commit_success();
+    assert_eq!(controller.get_paused_pools(), vec![pool_a, pool_b]);
+    controller.contribute(pool_a).expect_commit_failure();
+
+    controller
+        .call(GUARDIAN, "resume_all", manifest_args!())
+        .expect_commit_failure();
+    controller
+        .call(GUARDIAN, "resume_pool", manifest_args!(pool_a))
+        .expect_commit_failure();
+    controller
+        .call(OWNER, "resume_pool", manifest_args!(pool_a))
+        .expect_commit_success();
+    assert_eq!(controller.get_paused_pools(), vec![pool_b]);
+    controller.contribute(pool_a).expect_commit_success();
+
+    // Pools already in the requested state are skipped
+    controller
+        .call(OWNER, "resume_all", manifest_args!())
+        .expect_commit_success();
+    controller
+        .call(OWNER, "resume_all", manifest_args!())
+        .expect_commit_success();
+    assert_eq!(controller.get_paused_pools(), vec![]);
+    controller.contribute(pool_b).expect_commit_success();
+}
+
+#[test]
+fn test_owner_manages_the_badge_and_the_pools() {
+    let mut controller = TestController::new();
+    let (pool_a, pool_b, badge) = (controller.pool_a, controller.pool_b, controller.badge);
+
+    controller
+        .call(GUARDIAN, "pause_pool", manifest_args!(pool_a))
+        .expect_commit_failure();
+    controller.register_pools();
+
+    controller
+        .call(OWNER, "withdraw_badge", manifest_args!())
+        .expect_commit_success();
+    controller
+        .call(GUARDIAN, "pause_pool", manifest_args!(pool_a))
+        .expect_commit_failure();
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(controller.env.accounts[OWNER], badge, dec!(1))
+        .take_all_from_worktop(badge, "badge")
+        .call_method_with_name_lookup(controller.controller, "deposit_badge", |lookup| {
+            (lookup.bucket("badge"),)
+        });
+    controller
+        .env
+        .execute(OWNER, builder)
+        .expect_commit_success();
+    controller
+        .call(GUARDIAN, "pause_pool", manifest_args!(pool_a))
+        .expect_commit_success();
+    assert_eq!(controller.get_paused_pools(), vec![pool_a]);
+
+    controller
+        .call(OWNER, "unregister_pool", manifest_args!(pool_a))
+        .expect_commit_success();
+    controller
+        .call(OWNER, "unregister_pool", manifest_args!(pool_a))
+        .expect_commit_failure();
+    controller
+        .call(OWNER, "resume_pool", manifest_args!(pool_a))
+        .expect_commit_failure();
+
+    let pools: Vec<ComponentAddress> = controller
+        .call(ALICE, "get_pools", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(pools, vec![pool_b]);
+}
+
+#[test]
+fn test_pause_all_reverts_if_a_pool_rejects_the_badge() {
+    let mut controller = TestController::new();
+    let (pool_a, asset) = (controller.pool_a, controller.asset);
+
+    // Pausing without registered pools does nothing
+    controller
+        .call(GUARDIAN, "pause_all", manifest_args!())
+        .expect_commit_success();
+    controller.register_pools();
+
+    // Pool C is owned by the owner account, not by the controller badge
+    let owner_rule = rule!(require(controller.env.badges[OWNER].clone()));
+    let builder = ManifestBuilder::new().call_function(
+        controller.pool_package_address,
+        "AssetPool",
+        "instantiate",
+        manifest_args!(asset, OwnerRole::Fixed(owner_rule.clone()), owner_rule),
+    );
+    let pool_c = controller
+        .env
+        .execute(OWNER, builder)
+        .expect_commit_success()
+        .new_component_addresses()[0];
+    controller
+        .call(OWNER, "register_pool", manifest_args!(pool_c))
+        .expect_commit_success();
+
+    controller
+        .call(GUARDIAN, "pause_all", manifest_args!())
+        .expect_commit_failure();
+    assert_eq!(controller.get_paused_pools(), vec![]);
+
+    controller
+        .call(OWNER, "unregister_pool", manifest_args!(pool_c))
+        .expect_commit_success();
+    controller
+        .call(GUARDIAN, "pause_pool", manifest_args!(pool_a))
+        .expect_commit_success();
+
+    // Pausing a paused pool is skipped
+    controller
+        .call(GUARDIAN, "pause_pool", manifest_args!(pool_a))
+        .expect_commit_success();
+    controller
+        .call(GUARDIAN, "pause_all", manifest_args!())
+        .expect_commit_success();
+    assert_eq!(
+        controller.get_paused_pools(),
+        vec![pool_a, controller.pool_b]
+    );
+}
+
+#[test]
+fn test_failures_carry_controller_messages() {
+    let mut controller = TestController::new();
+    let pool_a = controller.pool_a;
+
+    expect_failure_containing(
+        controller.call(GUARDIAN, "pause_pool", manifest_args!(pool_a)),
+        "Pool not registered",
+    );
+    expect_failure_containing(
+        controller.call(OWNER, "unregister_pool", manifest_args!(pool_a)),
+        "Pool not registered",
+    );
+
+    controller.register_pools();
+    expect_failure_containing(
+        controller.call(OWNER, "register_pool", manifest_args!(pool_a)),
+        "Pool already registered",
+    );
+
+    controller
+        .call(OWNER, "withdraw_badge", manifest_args!())
+        .expect_commit_success();
+    expect_failure_containing(
+        controller.call(GUARDIAN, "pause_all", manifest_args!()),
+        "No badge deposited",
+    );
+    expect_failure_containing(
+        controller.call(OWNER, "resume_all", manifest_args!()),
+        "No badge deposited",
+    );
+
+    let nft = controller
+        .env
+        .test_runner
+        .create_non_fungible_resource(controller.env.accounts[OWNER]);
+    let owner_role = OwnerRole::Fixed(rule!(require(controller.env.badges[OWNER].clone())));
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(controller.env.accounts[OWNER], nft, dec!(1))
+        .take_all_from_worktop(nft, "badge")
+        .call_function_with_name_lookup(
+            controller.env.package_address,
+            "PauseController",
+            "instantiate",
+            |lookup| (lookup.bucket("badge"), owner_role, AccessRule::DenyAll),
+        );
+    expect_failure_containing(
+        controller.env.execute(OWNER, builder),
+        "Badge must be fungible",
+    );
+}
@@ -0,0 +1,327 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct StreamBadge {
+    pub stream_id: u64,
+}
+
+#[derive(ScryptoSbor)]
+pub struct Stream {
+    /// Funds not withdrawn by the recipient or refunded to the sender yet
+    pub funds: Vault,
+    pub deposited_amount: Decimal,
+    pub withdrawn_amount: Decimal,
+    pub start_epoch: u64,
+    pub end_epoch: u64,
+    /// Epoch at which the stream was cancelled, stopping the accrual
+    pub cancelled_at_epoch: Option<u64>,
+    /// Amount refunded to the sender at cancellation, not withdrawn yet
+    pub unclaimed_refund: Decimal,
+}
+
+impl Stream {
+    /// Amount streamed to the recipient at the given epoch, growing linearly from the start
+    /// epoch to the end epoch
+    pub fn streamed_amount(&self, epoch: u64) -> Decimal {
+        let epoch = self
+            .cancelled_at_epoch
+            .unwrap_or(epoch)
+            .min(epoch)
+            .clamp(self.start_epoch, self.end_epoch);
+
+        self.deposited_amount * (epoch - self.start_epoch) / (self.end_epoch - self.start_epoch)
+    }
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct StreamCreatedEvent {
+    pub stream_id: u64,
+    pub res_address: ResourceAddress,
+    pub amount: Decimal,
+    pub start_epoch: u64,
+    pub end_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct StreamCancelledEvent {
+    pub stream_id: u64,
+    pub recipient_amount: Decimal,
+    pub sender_amount: Decimal,
+}
+
+#[blueprint]
+#[events(StreamCreatedEvent, StreamCancelledEvent)]
+pub mod payment_streams {
+
+    enable_method_auth! {
+        methods {
+
+            create_stream => PUBLIC;
+            withdraw => PUBLIC;
+            cancel_as_sender => PUBLIC;
+            cancel_as_recipient => PUBLIC;
+            withdraw_refund => PUBLIC;
+
+            get_withdrawable_amount => PUBLIC;
+            get_stream_state => PUBLIC;
+
+        }
+    }
+
+    pub struct PaymentStreams {
+        streams: KeyValueStore<u64, Stream>,
+        next_stream_id: u64,
+
+        /// Badges held by the senders of the streams
+        sender_res_manager: ResourceManager,
+
+        /// Badges held by the recipients of the streams
+        recipient_res_manager: ResourceManager,
+    }
+
+    impl PaymentStreams {
+        pub fn instantiate(
+            owner_role: OwnerRole,
+        ) -> (Global<PaymentStreams>, ResourceAddress, ResourceAddress) {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(PaymentStreams::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let sender_res_manager =
+                ResourceBuilder::new_integer_non_fungible::<StreamBadge>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let recipient_res_manager =
+                ResourceBuilder::new_integer_non_fungible::<StreamBadge>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule;
+                        minter_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let streams_component = Self {
+                streams: KeyValueStore::new(),
+                next_stream_id: 1,
+                sender_res_manager,
+                recipient_res_manager,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (
+                streams_component,
+                sender_res_manager.address(),
+                recipient_res_manager.address(),
+            )
+        }
+
+        /// Open a stream of the deposited funds from the start epoch to the end epoch. Returns
+        /// the sender badge and the recipient badge, which should be sent to the recipient.
+        pub fn create_stream(
+            &mut self,
+            funds: Bucket,
+            start_epoch: u64,
+            end_epoch: u64,
+        ) -> (Bucket, Bucket) {
+            let res_address = funds.resource_address();
+            let amount = funds.amount();
+
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                res_address,
+                Some("Streamed resource must be fungible".to_string()),
+            );
+            assert!(amount > Decimal::ZERO, "Amount must be greater than zero!");
+            assert!(
+                end_epoch > start_epoch,
+                "End epoch must be after the start epoch"
+            );
+
+            let stream_id = self.next_stream_id;
+            self.next_stream_id += 1;
+
+            self.streams.insert(
+                stream_id,
+                Stream {
+                    funds: Vault::with_bucket(funds),
+                    deposited_amount: amount,
+                    withdrawn_amount: Decimal::ZERO,
+                    start_epoch,
+                    end_epoch,
+                    cancelled_at_epoch: None,
+                    unclaimed_refund: Decimal::ZERO,
+                },
+            );
+
+            Runtime::emit_event(StreamCreatedEvent {
+                stream_id,
+                res_address,
+                amount,
+                start_epoch,
+                end_epoch,
+            });
+
+            let badge_id = NonFungibleLocalId::integer(stream_id);
+
+            (
+                self.sender_res_manager
+                    .mint_non_fungible(&badge_id, StreamBadge { stream_id }),
+                self.recipient_res_manager
+                    .mint_non_fungible(&badge_id, StreamBadge { stream_id }),
+            )
+        }
+
+        /* RECIPIENT METHODS */
+
+        /// Withdraw the amount streamed so far and not withdrawn yet
+        pub fn withdraw(&mut self, recipient_proof: Proof) -> Bucket {
+            let stream_id =
+                PaymentStreams::_check_badge(recipient_proof, self.recipient_res_manager);
+
+            self._withdraw(stream_id)
+        }
+
+        /// Cancel the stream. Returns the amount streamed so far, the rest becomes withdrawable
+        /// by the sender.
+        pub fn cancel_as_recipient(&mut self, recipient_proof: Proof) -> Bucket {
+            let stream_id =
+                PaymentStreams::_check_badge(recipient_proof, self.recipient_res_manager);
+
+            self._cancel(stream_id);
+
+            self._withdraw(stream_id)
+        }
+
+        /* SENDER METHODS */
+
+        /// Cancel the stream. Returns the amount not streamed yet, the amount streamed so far
+        /// remains withdrawable by the recipient.
+        pub fn cancel_as_sender(&mut self, sender_proof: Proof) -> Bucket {
+            let stream_id = PaymentStreams::_check_badge(sender_proof, self.sender_res_manager);
+
+            self._cancel(stream_id);
+
+            self._withdraw_refund(stream_id)
+        }
+
+        /// Withdraw the amount refunded when the recipient cancelled the stream
+        pub fn withdraw_refund(&mut self, sender_proof: Proof) -> Bucket {
+            let stream_id = PaymentStreams::_check_badge(sender_proof, self.sender_res_manager);
+
+            self._withdraw_refund(stream_id)
+        }
+
+        /* GETTERS */
+
+        pub fn get_withdrawable_amount(&self, stream_id: u64) -> Decimal {
+            let stream = self.streams.get(&stream_id).expect("Stream not found");
+
+            stream.streamed_amount(Runtime::current_epoch().number()) - stream.withdrawn_amount
+        }
+
+        /// Deposited, withdrawn and streamed amounts of a stream
+        pub fn get_stream_state(&self, stream_id: u64) -> (Decimal, Decimal, Decimal) {
+            let stream = self.streams.get(&stream_id).expect("Stream not found");
+
+            (
+                stream.deposited_amount,
+                stream.withdrawn_amount,
+                stream.streamed_amount(Runtime::current_epoch().number()),
+            )
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _withdraw(&mut self, stream_id: u64) -> Bucket {
+            let mut stream = self.streams.get_mut(&stream_id).unwrap();
+
+            let amount =
+                stream.streamed_amount(Runtime::current_epoch().number()) - stream.withdrawn_amount;
+
+            let withdrawn = stream
+                .funds
+                .take_advanced(amount, WithdrawStrategy::Rounded(RoundingMode::ToZero));
+            stream.withdrawn_amount += withdrawn.amount();
+
+            withdrawn
+        }
+
+        fn _cancel(&mut self, stream_id: u64) {
+            let current_epoch = Runtime::current_epoch().number();
+            let mut stream = self.streams.get_mut(&stream_id).unwrap();
+
+            assert!(
+                stream.cancelled_at_epoch.is_none(),
+                "Stream already cancelled"
+            );
+            assert!(current_epoch < stream.end_epoch, "Stream has ended");
+
+            stream.cancelled_at_epoch = Some(current_epoch);
+
+            let recipient_amount = stream.streamed_amount(current_epoch);
+            let sender_amount = stream.deposited_amount - recipient_amount;
+
+            stream.unclaimed_refund = sender_amount;
+
+            Runtime::emit_event(StreamCancelledEvent {
+                stream_id,
+                recipient_amount,
+                sender_amount,
+            });
+        }
+
+        fn _withdraw_refund(&mut self, stream_id: u64) -> Bucket {
+            let mut stream = self.streams.get_mut(&stream_id).unwrap();
+
+            let refund = stream.funds.take_advanced(
+                stream.unclaimed_refund,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+            stream.unclaimed_refund = Decimal::ZERO;
+
+            refund
+        }
+
+        fn _check_badge(badge_proof: Proof, badge_res_manager: ResourceManager) -> u64 {
+            match badge_proof
+                .check(badge_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+            {
+                NonFungibleLocalId::Integer(stream_id) => stream_id.value(),
+                _ => panic!("Invalid stream badge"),
+            }
+        }
+    }
+}
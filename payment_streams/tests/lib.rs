@@ -0,0 +1,220 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Sender of the streams, holding 10000 tokens
+const SENDER: usize = 0;
+const RECIPIENT: usize = 1;
+
+/// Payment streams component at epoch 10
+struct TestStreams {
+    env: TestEnv,
+    streams: ComponentAddress,
+    sender_badge: ResourceAddress,
+    recipient_badge: ResourceAddress,
+    token: ResourceAddress,
+}
+
+impl TestStreams {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 2);
+        env.set_epoch(10);
+        let token = env
+            .test_runner
+            .create_fungible_resource(dec!(10000), 18, env.accounts[SENDER]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "PaymentStreams",
+                "instantiate",
+                manifest_args!(OwnerRole::None),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            streams: commit.new_component_addresses()[0],
+            sender_badge: commit.new_resource_addresses()[0],
+            recipient_badge: commit.new_resource_addresses()[1],
+            env,
+            token,
+        }
+    }
+
+    /// Component with a stream of 1000 tokens from epoch 20 to epoch 30
+    fn with_stream() -> Self {
+        let mut streams = Self::new();
+
+        streams
+            .create_stream(dec!(1000), 20, 30)
+            .expect_commit_success();
+
+        streams
+    }
+
+    /// Execute the manifest with the keys of every account, then deposit the worktop into the
+    /// given account
+    fn execute(&mut self, account: usize, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute_with_all_keys(account, builder)
+    }
+
+    /// Create a stream from the sender and send the recipient badge to the recipient
+    fn create_stream(
+        &mut self,
+        amount: Decimal,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[SENDER], self.token, amount)
+            .take_all_from_worktop(self.token, "funds")
+            .call_method_with_name_lookup(self.streams, "create_stream", |lookup| {
+                (lookup.bucket("funds"), start_epoch, end_epoch)
+            })
+            .take_all_from_worktop(self.recipient_badge, "recipient_badge")
+            .call_method_with_name_lookup(self.env.accounts[RECIPIENT], "deposit", |lookup| {
+                (lookup.bucket("recipient_badge"),)
+            });
+
+        self.execute(SENDER, builder)
+    }
+
+    /// Call a method of the first stream with a proof of a badge held by the account
+    fn call_with_badge(
+        &mut self,
+        account: usize,
+        badge_res_address: ResourceAddress,
+        method: &str,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_non_fungibles(
+                self.env.accounts[account],
+                badge_res_address,
+                &BTreeSet::from([NonFungibleLocalId::integer(1)]),
+            )
+            .pop_from_auth_zone("badge")
+            .call_method_with_name_lookup(self.streams, method, |lookup| (lookup.proof("badge"),));
+
+        self.execute(account, builder)
+    }
+
+    fn as_sender(&mut self, method: &str) -> TransactionReceipt {
+        self.call_with_badge(SENDER, self.sender_badge, method)
+    }
+
+    fn as_recipient(&mut self, method: &str) -> TransactionReceipt {
+        self.call_with_badge(RECIPIENT, self.recipient_badge, method)
+    }
+
+    fn get_withdrawable_amount(&mut self) -> Decimal {
+        let builder = ManifestBuilder::new().call_method(
+            self.streams,
+            "get_withdrawable_amount",
+            manifest_args!(1u64),
+        );
+
+        self.execute(SENDER, builder)
+            .expect_commit_success()
+            .output(0)
+    }
+
+    fn balance(&mut self, account: usize) -> Decimal {
+        self.env.balance(account, self.token)
+    }
+}
+
+#[test]
+fn test_recipient_withdraws_the_streamed_amount() {
+    let mut streams = TestStreams::with_stream();
+
+    streams.env.set_epoch(15);
+    streams.as_recipient("withdraw").expect_commit_success();
+    assert_eq!(streams.balance(RECIPIENT), dec!(0));
+
+    streams.env.set_epoch(25);
+    assert_eq!(streams.get_withdrawable_amount(), dec!(500));
+    streams.as_recipient("withdraw").expect_commit_success();
+    assert_eq!(streams.balance(RECIPIENT), dec!(500));
+
+    streams.env.set_epoch(40);
+    streams.as_recipient("withdraw").expect_commit_success();
+    assert_eq!(streams.balance(RECIPIENT), dec!(1000));
+    assert_eq!(streams.get_withdrawable_amount(), dec!(0));
+}
+
+#[test]
+fn test_withdraw_requires_the_recipient_badge() {
+    let mut streams = TestStreams::with_stream();
+    let sender_badge = streams.sender_badge;
+
+    streams.env.set_epoch(25);
+    streams
+        .call_with_badge(SENDER, sender_badge, "withdraw")
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_cancel_as_sender_refunds_the_unstreamed_amount() {
+    let mut streams = TestStreams::with_stream();
+
+    streams.env.set_epoch(24);
+    streams
+        .as_sender("cancel_as_sender")
+        .expect_commit_success();
+    assert_eq!(streams.balance(SENDER), dec!(9600));
+
+    streams
+        .as_sender("cancel_as_sender")
+        .expect_commit_failure();
+
+    // The streamed amount stays withdrawable but stops growing
+    streams.env.set_epoch(40);
+    streams.as_recipient("withdraw").expect_commit_success();
+    assert_eq!(streams.balance(RECIPIENT), dec!(400));
+}
+
+#[test]
+fn test_cancel_as_recipient_leaves_the_refund_to_the_sender() {
+    let mut streams = TestStreams::with_stream();
+
+    streams.env.set_epoch(27);
+    streams
+        .as_recipient("cancel_as_recipient")
+        .expect_commit_success();
+    assert_eq!(streams.balance(RECIPIENT), dec!(700));
+
+    streams.as_sender("withdraw_refund").expect_commit_success();
+    assert_eq!(streams.balance(SENDER), dec!(9300));
+
+    // The refund can only be withdrawn once
+    streams.as_sender("withdraw_refund").expect_commit_success();
+    assert_eq!(streams.balance(SENDER), dec!(9300));
+}
+
+#[test]
+fn test_ended_stream_can_not_be_cancelled() {
+    let mut streams = TestStreams::with_stream();
+
+    streams.env.set_epoch(30);
+    streams
+        .as_sender("cancel_as_sender")
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_create_stream_ending_before_its_start_fails() {
+    let mut streams = TestStreams::new();
+
+    streams
+        .create_stream(dec!(1000), 20, 20)
+        .expect_commit_failure();
+    streams
+        .create_stream(dec!(0), 20, 30)
+        .expect_commit_failure();
+}
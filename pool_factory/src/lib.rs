@@ -0,0 +1,163 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+use single_asset_pool::pool::AssetPool;
+
+/// Maximum number of pools returned by a single enumeration call
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct PoolRecord {
+    pub pool: Global<AssetPool>,
+    pub pool_res_address: ResourceAddress,
+    pub pool_unit_res_address: ResourceAddress,
+    pub created_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PoolCreatedEvent {
+    pub index: u64,
+    pub pool_address: ComponentAddress,
+    pub pool_res_address: ResourceAddress,
+    pub pool_unit_res_address: ResourceAddress,
+}
+
+#[blueprint]
+#[events(PoolCreatedEvent)]
+pub mod pool_factory {
+
+    enable_method_auth! {
+        methods {
+
+            create_pool => restrict_to :[OWNER];
+
+            get_pool => PUBLIC;
+            get_pool_at => PUBLIC;
+            get_pools => PUBLIC;
+            get_pool_count => PUBLIC;
+
+        }
+    }
+
+    pub struct PoolFactory {
+        /// Owner role of the created pools
+        pool_owner_role: OwnerRole,
+
+        /// Pool of each pooled resource
+        pools: KeyValueStore<ResourceAddress, PoolRecord>,
+
+        /// Pooled resources by creation order, for enumeration
+        pool_index: KeyValueStore<u64, ResourceAddress>,
+        pool_count: u64,
+    }
+
+    impl PoolFactory {
+        pub fn instantiate(
+            owner_role: OwnerRole,
+            pool_owner_role: OwnerRole,
+        ) -> Global<PoolFactory> {
+            Self {
+                pool_owner_role,
+                pools: KeyValueStore::new(),
+                pool_index: KeyValueStore::new(),
+                pool_count: 0,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        /// Instantiate an AssetPool for a resource with the given admin rule. Only one pool can
+        /// be created per resource.
+        pub fn create_pool(
+            &mut self,
+            pool_res_address: ResourceAddress,
+            admin_rule: AccessRule,
+        ) -> Global<AssetPool> {
+            /* CHECK INPUT */
+            assert!(
+                self.pools.get(&pool_res_address).is_none(),
+                "A pool already exists for this resource"
+            );
+
+            let (pool, pool_unit_res_address, _) =
+                AssetPool::instantiate(pool_res_address, self.pool_owner_role.clone(), admin_rule);
+
+            let pool_address = pool.address();
+            let index = self.pool_count;
+            self.pool_count += 1;
+
+            self.pools.insert(
+                pool_res_address,
+                PoolRecord {
+                    pool: pool.clone(),
+                    pool_res_address,
+                    pool_unit_res_address,
+                    created_at_epoch: Runtime::current_epoch().number(),
+                },
+            );
+            self.pool_index.insert(index, pool_res_address);
+
+            Runtime::emit_event(PoolCreatedEvent {
+                index,
+                pool_address,
+                pool_res_address,
+                pool_unit_res_address,
+            });
+
+            pool
+        }
+
+        /* GETTERS */
+
+        pub fn get_pool(&self, pool_res_address: ResourceAddress) -> Option<PoolRecord> {
+            self.pools
+                .get(&pool_res_address)
+                .map(|record| record.clone())
+        }
+
+        pub fn get_pool_at(&self, index: u64) -> Option<PoolRecord> {
+            self.pool_index
+                .get(&index)
+                .and_then(|pool_res_address| self.get_pool(*pool_res_address))
+        }
+
+        /// Enumerate pools by creation order, starting from `start_index`
+        pub fn get_pools(&self, start_index: u64, count: u64) -> Vec<PoolRecord> {
+            assert!(count <= MAX_PAGE_SIZE, "Page size too large");
+
+            let end_index = start_index.saturating_add(count).min(self.pool_count);
+
+            (start_index..end_index)
+                .map(|index| self.get_pool_at(index).unwrap())
+                .collect()
+        }
+
+        pub fn get_pool_count(&self) -> u64 {
+            self.pool_count
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use pool_factory::PoolRecord;
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the factory, and administrates the created pools. Holds 100 tokens of each resource.
+const OWNER: usize = 0;
+const ALICE: usize = 1;
+
+/// Factory at epoch 10, whose pools are owned by the owner
+struct TestFactory {
+    env: TestEnv,
+    factory: ComponentAddress,
+    res_x: ResourceAddress,
+    res_y: ResourceAddress,
+}
+
+impl TestFactory {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 2);
+        env.set_epoch(10);
+        let res_x = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[OWNER]);
+        let res_y = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "PoolFactory",
+                "instantiate",
+                manifest_args!(owner_role.clone(), owner_role),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let factory = receipt.expect_commit_success().new_component_addresses()[0];
+
+        Self {
+            env,
+            factory,
+            res_x,
+            res_y,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.factory, method, args)
+    }
+
+    /// Create a pool administrated by the owner
+    fn create_pool(&mut self, caller: usize, res_address: ResourceAddress) -> TransactionReceipt {
+        let admin_rule = rule!(require(self.env.badges[OWNER].clone()));
+
+        self.call(
+            caller,
+            "create_pool",
+            manifest_args!(res_address, admin_rule),
+        )
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str, args: impl ResolvableArguments) -> T {
+        self.call(ALICE, method, args)
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_owner_creates_one_pool_per_resource() {
+    let mut factory = TestFactory::new();
+    let res_x = factory.res_x;
+
+    factory.create_pool(ALICE, res_x).expect_commit_failure();
+    let pool = factory
+        .create_pool(OWNER, res_x)
+        .expect_commit_success()
+        .new_component_addresses()[0];
+    factory.create_pool(OWNER, res_x).expect_commit_failure();
+
+    let record: PoolRecord = factory
+        .get::<Option<PoolRecord>>("get_pool", manifest_args!(res_x))
+        .unwrap();
+    assert_eq!(record.pool_res_address, res_x);
+    assert_eq!(record.created_at_epoch, 10);
+
+    // The recorded pool unit is minted by the pool on contributions of its admin
+    let pool_unit = record.pool_unit_res_address;
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(factory.env.accounts[OWNER], res_x, dec!(10))
+        .take_all_from_worktop(res_x, "assets")
+        .call_method_with_name_lookup(pool, "contribute", |lookup| (lookup.bucket("assets"),));
+    factory.env.execute(OWNER, builder).expect_commit_success();
+    assert_eq!(factory.env.balance(OWNER, pool_unit), dec!(10));
+}
+
+#[test]
+fn test_pools_are_enumerated_by_creation_order() {
+    let mut factory = TestFactory::new();
+    let (res_x, res_y) = (factory.res_x, factory.res_y);
+
+    factory.create_pool(OWNER, res_y).expect_commit_success();
+    factory.create_pool(OWNER, res_x).expect_commit_success();
+    assert_eq!(factory.get::<u64>("get_pool_count", manifest_args!()), 2);
+
+    let pools: Vec<PoolRecord> = factory.get("get_pools", manifest_args!(0u64, 10u64));
+    assert_eq!(
+        pools
+            .iter()
+            .map(|record| record.pool_res_address)
+            .collect::<Vec<_>>(),
+        vec![res_y, res_x]
+    );
+
+    let pools: Vec<PoolRecord> = factory.get("get_pools", manifest_args!(1u64, 10u64));
+    assert_eq!(pools.len(), 1);
+    assert_eq!(pools[0].pool_res_address, res_x);
+
+    let record: Option<PoolRecord> = factory.get("get_pool_at", manifest_args!(2u64));
+    assert!(record.is_none());
+
+    factory
+        .call(ALICE, "get_pools", manifest_args!(0u64, 101u64))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_pool_enumeration_stops_at_the_last_pool() {
+    let mut factory = TestFactory::new();
+    let (res_x, res_y) = (factory.res_x, factory.res_y);
+
+    let get_pool_res_addresses =
+        |factory: &mut TestFactory, start_index: u64, count: u64| -> Vec<ResourceAddress> {
+            let pools: Vec<PoolRecord> =
+                factory.get("get_pools", manifest_args!(start_index, count));
+
+            pools
+                .into_iter()
+                .map(|record| record.pool_res_address)
+                .collect()
+        };
+
+    assert!(get_pool_res_addresses(&mut factory, 0, 10).is_empty());
+    assert!(factory
+        .get::<Option<PoolRecord>>("get_pool", manifest_args!(res_x))
+        .is_none());
+    assert!(factory
+        .get::<Option<PoolRecord>>("get_pool_at", manifest_args!(0u64))
+        .is_none());
+
+    factory.create_pool(OWNER, res_y).expect_commit_success();
+    factory.create_pool(OWNER, res_x).expect_commit_success();
+
+    assert_eq!(
+        get_pool_res_addresses(&mut factory, 0, 100),
+        vec![res_y, res_x]
+    );
+    assert_eq!(get_pool_res_addresses(&mut factory, 0, 1), vec![res_y]);
+    assert!(get_pool_res_addresses(&mut factory, 0, 0).is_empty());
+    assert!(get_pool_res_addresses(&mut factory, 2, 10).is_empty());
+    assert!(get_pool_res_addresses(&mut factory, u64::MAX, 100).is_empty());
+}
+
+#[test]
+fn test_failures_carry_factory_messages() {
+    let mut factory = TestFactory::new();
+    let res_x = factory.res_x;
+
+    // The pool resource is checked by the pool itself, and nothing is recorded on failure
+    let nft = factory
+        .env
+        .test_runner
+        .create_non_fungible_resource(factory.env.accounts[OWNER]);
+    expect_failure_containing(factory.create_pool(OWNER, nft), "POOL_ERR_008");
+    assert_eq!(factory.get::<u64>("get_pool_count", manifest_args!()), 0);
+
+    let pool = factory
+        .create_pool(OWNER, res_x)
+        .expect_commit_success()
+        .new_component_addresses()[0];
+    expect_failure_containing(
+        factory.create_pool(OWNER, res_x),
+        "A pool already exists for this resource",
+    );
+    assert_eq!(factory.get::<u64>("get_pool_count", manifest_args!()), 1);
+
+    expect_failure_containing(
+        factory.call(ALICE, "get_pools", manifest_args!(0u64, 101u64)),
+        "Page size too large",
+    );
+
+    // The created pool is administrated by the owner only
+    let builder = ManifestBuilder::new()
+        .take_all_from_worktop(res_x, "assets")
+        .call_method_with_name_lookup(pool, "contribute", |lookup| (lookup.bucket("assets"),));
+    factory.env.execute(ALICE, builder).expect_commit_failure();
+}
@@ -0,0 +1,206 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use asset_pool_interface::{AssetPoolStub, SingleResourcePoolInterface};
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+mod passthrough_logic;
+
+// IMPLEMENTATION INTERFACE
+//
+// Integrators only use the proxy address, while the logic lives in implementation components
+// registered by the owner. The proxy holds the admin badge of the pool and forwards each call
+// to the active implementation with the pool address and a proof of the admin badge as the
+// first arguments. Implementations push the proof to their auth zone before calling the pool,
+// and must expose:
+// - `contribute(pool: ComponentAddress, admin_proof: Proof, assets: Bucket) -> (Bucket, Bucket)`
+// - `redeem(pool: ComponentAddress, admin_proof: Proof, pool_units: Bucket) -> (Bucket, Bucket)`
+//
+// `PassthroughLogic` is the reference implementation, forwarding calls to the pool as is.
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ImplementationRegisteredEvent {
+    pub version: u32,
+    pub implementation: ComponentAddress,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ImplementationActivatedEvent {
+    pub version: u32,
+    pub implementation: ComponentAddress,
+}
+
+#[blueprint]
+#[events(ImplementationRegisteredEvent, ImplementationActivatedEvent)]
+pub mod pool_proxy {
+
+    enable_method_auth! {
+        methods {
+
+            register_implementation => restrict_to :[OWNER];
+            set_active_version => restrict_to :[OWNER];
+            withdraw_admin_badge => restrict_to :[OWNER];
+
+            contribute => PUBLIC;
+            redeem => PUBLIC;
+
+            get_pool => PUBLIC;
+            get_pool_unit_ratio => PUBLIC;
+            get_pooled_amount => PUBLIC;
+            get_implementations => PUBLIC;
+            get_active_version => PUBLIC;
+
+        }
+    }
+
+    pub struct PoolProxy {
+        pool: AssetPoolStub,
+
+        /// Fungible badge required by the admin rule of the pool
+        admin_badge: Vault,
+
+        /// Registered implementations, indexed by version
+        implementations: Vec<ComponentAddress>,
+        active_version: u32,
+    }
+
+    impl PoolProxy {
+        /// Instantiate a proxy for a pool whose admin rule requires the given badge, with a
+        /// first implementation registered as version 0
+        pub fn instantiate(
+            pool: ComponentAddress,
+            admin_badge: Bucket,
+            implementation: ComponentAddress,
+            owner_role: OwnerRole,
+        ) -> Global<PoolProxy> {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                admin_badge.resource_address(),
+                Some("Admin badge must be fungible".to_string()),
+            );
+            assert!(!admin_badge.is_empty(), "Admin badge must not be empty");
+
+            Self {
+                pool: AssetPoolStub::from(pool),
+                admin_badge: Vault::with_bucket(admin_badge),
+                implementations: vec![implementation],
+                active_version: 0,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        /// Register a new implementation. It is only used once activated.
+        pub fn register_implementation(&mut self, implementation: ComponentAddress) -> u32 {
+            let version = self.implementations.len() as u32;
+            self.implementations.push(implementation);
+
+            Runtime::emit_event(ImplementationRegisteredEvent {
+                version,
+                implementation,
+            });
+
+            version
+        }
+
+        /// Activate a registered implementation, which can also roll back to a previous
+        /// version
+        pub fn set_active_version(&mut self, version: u32) {
+            let implementation = *self
+                .implementations
+                .get(version as usize)
+                .expect("Version not registered");
+
+            self.active_version = version;
+
+            Runtime::emit_event(ImplementationActivatedEvent {
+                version,
+                implementation,
+            });
+        }
+
+        /// Take the admin badge back, e.g. to migrate the pool away from the proxy
+        pub fn withdraw_admin_badge(&mut self) -> Bucket {
+            self.admin_badge.take_all()
+        }
+
+        /* USER METHODS */
+
+        pub fn contribute(&mut self, assets: Bucket) -> (Bucket, Bucket) {
+            let admin_proof = self._admin_proof();
+
+            self._implementation().call_raw::<(Bucket, Bucket)>(
+                "contribute",
+                scrypto_args!(self.pool.address(), admin_proof, assets),
+            )
+        }
+
+        pub fn redeem(&mut self, pool_units: Bucket) -> (Bucket, Bucket) {
+            let admin_proof = self._admin_proof();
+
+            self._implementation().call_raw::<(Bucket, Bucket)>(
+                "redeem",
+                scrypto_args!(self.pool.address(), admin_proof, pool_units),
+            )
+        }
+
+        /* GETTERS */
+
+        pub fn get_pool(&self) -> ComponentAddress {
+            self.pool.address()
+        }
+
+        pub fn get_pool_unit_ratio(&self) -> PreciseDecimal {
+            self.pool.get_pool_unit_ratio()
+        }
+
+        pub fn get_pooled_amount(&self) -> (Decimal, Decimal) {
+            self.pool.get_pooled_amount()
+        }
+
+        pub fn get_implementations(&self) -> Vec<ComponentAddress> {
+            self.implementations.clone()
+        }
+
+        pub fn get_active_version(&self) -> (u32, ComponentAddress) {
+            (self.active_version, self._implementation().address())
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _implementation(&self) -> Global<AnyComponent> {
+            Global::from(self.implementations[self.active_version as usize])
+        }
+
+        fn _admin_proof(&self) -> Proof {
+            self.admin_badge
+                .as_fungible()
+                .create_proof_of_amount(self.admin_badge.amount())
+                .into()
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use asset_pool_interface::{AssetPoolStub, SingleResourcePoolInterface};
+use scrypto::prelude::*;
+
+#[blueprint]
+mod passthrough_logic {
+
+    enable_method_auth! {
+        methods {
+
+            contribute => PUBLIC;
+            redeem => PUBLIC;
+
+        }
+    }
+
+    /// Reference implementation of the proxy interface, forwarding calls to the pool as is.
+    /// Methods are public since they can only act on the pool with a proof of its admin badge.
+    pub struct PassthroughLogic {}
+
+    impl PassthroughLogic {
+        pub fn instantiate(owner_role: OwnerRole) -> Global<PassthroughLogic> {
+            Self {}
+                .instantiate()
+                .prepare_to_globalize(owner_role)
+                .globalize()
+        }
+
+        pub fn contribute(
+            &mut self,
+            pool: ComponentAddress,
+            admin_proof: Proof,
+            assets: Bucket,
+        ) -> (Bucket, Bucket) {
+            LocalAuthZone::push(admin_proof);
+
+            AssetPoolStub::from(pool).contribute(assets)
+        }
+
+        pub fn redeem(
+            &mut self,
+            pool: ComponentAddress,
+            admin_proof: Proof,
+            pool_units: Bucket,
+        ) -> (Bucket, Bucket) {
+            LocalAuthZone::push(admin_proof);
+
+            AssetPoolStub::from(pool).redeem(pool_units)
+        }
+    }
+}
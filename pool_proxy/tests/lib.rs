@@ -0,0 +1,299 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the proxy
+const OWNER: usize = 0;
+/// Holds 100 assets
+const ALICE: usize = 1;
+
+/// Proxy holding the admin badge of a pool, with two passthrough implementations: version 0
+/// is active, the other one is not registered yet. The pool is instantiated from the published
+/// SingleResourcePool package.
+struct TestProxy {
+    env: TestEnv,
+    proxy: ComponentAddress,
+    pool: ComponentAddress,
+    pool_unit: ResourceAddress,
+    logic_v0: ComponentAddress,
+    logic_v1: ComponentAddress,
+    asset: ResourceAddress,
+}
+
+impl TestProxy {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 2);
+        let asset = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[ALICE]);
+        let admin_badge = env
+            .test_runner
+            .create_fungible_resource(dec!(1), 0, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+        let pool_package_address = env.test_runner.compile_and_publish(format!(
+            "{}/../single_resource_pool",
+            env!("CARGO_MANIFEST_DIR")
+        ));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                pool_package_address,
+                "AssetPool",
+                "instantiate",
+                manifest_args!(asset, owner_role.clone(), rule!(require(admin_badge))),
+            )
+            .call_function(
+                env.package_address,
+                "PassthroughLogic",
+                "instantiate",
+                manifest_args!(owner_role.clone()),
+            )
+            .call_function(
+                env.package_address,
+                "PassthroughLogic",
+                "instantiate",
+                manifest_args!(owner_role.clone()),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+        let (pool, pool_unit, _): (ComponentAddress, ResourceAddress, ResourceAddress) =
+            commit.output(0);
+        let (logic_v0, logic_v1) = (
+            commit.new_component_addresses()[1],
+            commit.new_component_addresses()[2],
+        );
+
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account(env.accounts[OWNER], admin_badge, dec!(1))
+            .take_all_from_worktop(admin_badge, "admin_badge")
+            .call_function_with_name_lookup(
+                env.package_address,
+                "PoolProxy",
+                "instantiate",
+                |lookup| (pool, lookup.bucket("admin_badge"), logic_v0, owner_role),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()]);
+        let proxy = receipt.expect_commit_success().new_component_addresses()[0];
+
+        Self {
+            env,
+            proxy,
+            pool,
+            pool_unit,
+            logic_v0,
+            logic_v1,
+            asset,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.proxy, method, args)
+    }
+
+    /// Call a method of the given component with a bucket of the given resource of Alice
+    fn call_with_bucket(
+        &mut self,
+        component_address: ComponentAddress,
+        method: &str,
+        res_address: ResourceAddress,
+        amount: Decimal,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[ALICE], res_address, amount)
+            .take_all_from_worktop(res_address, "bucket")
+            .call_method_with_name_lookup(component_address, method, |lookup| {
+                (lookup.bucket("bucket"),)
+            });
+
+        self.env.execute(ALICE, builder)
+    }
+
+    fn contribute(&mut self, amount: Decimal) -> TransactionReceipt {
+        self.call_with_bucket(self.proxy, "contribute", self.asset, amount)
+    }
+
+    fn redeem(&mut self, amount: Decimal) -> TransactionReceipt {
+        self.call_with_bucket(self.proxy, "redeem", self.pool_unit, amount)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str) -> T {
+        self.call(ALICE, method, manifest_args!())
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_users_contribute_and_redeem_through_the_proxy() {
+    let mut proxy = TestProxy::new();
+    let (pool, asset, pool_unit) = (proxy.pool, proxy.asset, proxy.pool_unit);
+
+    // The pool only accepts calls with the admin badge held by the proxy
+    proxy
+        .call_with_bucket(pool, "contribute", asset, dec!(10))
+        .expect_commit_failure();
+
+    proxy.contribute(dec!(10)).expect_commit_success();
+    assert_eq!(proxy.env.balance(ALICE, pool_unit), dec!(10));
+    assert_eq!(proxy.env.balance(ALICE, asset), dec!(90));
+
+    proxy.redeem(dec!(4)).expect_commit_success();
+    assert_eq!(proxy.env.balance(ALICE, pool_unit), dec!(6));
+    assert_eq!(proxy.env.balance(ALICE, asset), dec!(94));
+
+    assert_eq!(proxy.get::<ComponentAddress>("get_pool"), pool);
+    assert_eq!(
+        proxy.get::<(Decimal, Decimal)>("get_pooled_amount").0,
+        dec!(6)
+    );
+}
+
+#[test]
+fn test_owner_upgrades_and_rolls_back_the_implementation() {
+    let mut proxy = TestProxy::new();
+    let (logic_v0, logic_v1) = (proxy.logic_v0, proxy.logic_v1);
+    let alice = proxy.env.accounts[ALICE];
+
+    proxy
+        .call(ALICE, "register_implementation", manifest_args!(logic_v1))
+        .expect_commit_failure();
+    let version: u32 = proxy
+        .call(OWNER, "register_implementation", manifest_args!(logic_v1))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(version, 1);
+
+    proxy
+        .call(OWNER, "set_active_version", manifest_args!(2u32))
+        .expect_commit_failure();
+    proxy
+        .call(ALICE, "set_active_version", manifest_args!(1u32))
+        .expect_commit_failure();
+    proxy
+        .call(OWNER, "set_active_version", manifest_args!(1u32))
+        .expect_commit_success();
+    assert_eq!(
+        proxy.get::<(u32, ComponentAddress)>("get_active_version"),
+        (1, logic_v1)
+    );
+    proxy.contribute(dec!(10)).expect_commit_success();
+
+    // A component without the implementation interface can not handle the calls
+    proxy
+        .call(OWNER, "register_implementation", manifest_args!(alice))
+        .expect_commit_success();
+    proxy
+        .call(OWNER, "set_active_version", manifest_args!(2u32))
+        .expect_commit_success();
+    proxy.contribute(dec!(10)).expect_commit_failure();
+
+    proxy
+        .call(OWNER, "set_active_version", manifest_args!(0u32))
+        .expect_commit_success();
+    proxy.contribute(dec!(10)).expect_commit_success();
+    assert_eq!(
+        proxy.get::<Vec<ComponentAddress>>("get_implementations"),
+        vec![logic_v0, logic_v1, alice]
+    );
+
+    // Without the admin badge, the proxy can no longer act on the pool
+    proxy
+        .call(ALICE, "withdraw_admin_badge", manifest_args!())
+        .expect_commit_failure();
+    proxy
+        .call(OWNER, "withdraw_admin_badge", manifest_args!())
+        .expect_commit_success();
+    proxy.redeem(dec!(10)).expect_commit_failure();
+}
+
+#[test]
+fn test_pool_units_are_fully_redeemed_through_the_proxy() {
+    let mut proxy = TestProxy::new();
+    let (asset, pool_unit) = (proxy.asset, proxy.pool_unit);
+
+    proxy.contribute(dec!(10)).expect_commit_success();
+    assert_eq!(
+        proxy.get::<PreciseDecimal>("get_pool_unit_ratio"),
+        PreciseDecimal::ONE
+    );
+
+    // More pool units than Alice holds can not be redeemed
+    proxy.redeem(dec!(11)).expect_commit_failure();
+
+    proxy.redeem(dec!(10)).expect_commit_success();
+    assert_eq!(proxy.env.balance(ALICE, pool_unit), dec!(0));
+    assert_eq!(proxy.env.balance(ALICE, asset), dec!(100));
+    assert_eq!(
+        proxy.get::<(Decimal, Decimal)>("get_pooled_amount"),
+        (dec!(0), dec!(0))
+    );
+}
+
+#[test]
+fn test_failures_carry_proxy_messages() {
+    let mut proxy = TestProxy::new();
+    let (pool, logic_v0) = (proxy.pool, proxy.logic_v0);
+    let owner_role = OwnerRole::Fixed(rule!(require(proxy.env.badges[OWNER].clone())));
+
+    expect_failure_containing(
+        proxy.call(OWNER, "set_active_version", manifest_args!(1u32)),
+        "Version not registered",
+    );
+
+    let nft = proxy
+        .env
+        .test_runner
+        .create_non_fungible_resource(proxy.env.accounts[OWNER]);
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(proxy.env.accounts[OWNER], nft, dec!(1))
+        .take_all_from_worktop(nft, "admin_badge")
+        .call_function_with_name_lookup(
+            proxy.env.package_address,
+            "PoolProxy",
+            "instantiate",
+            |lookup| {
+                (
+                    pool,
+                    lookup.bucket("admin_badge"),
+                    logic_v0,
+                    owner_role.clone(),
+                )
+            },
+        );
+    expect_failure_containing(
+        proxy.env.execute(OWNER, builder),
+        "Admin badge must be fungible",
+    );
+
+    // The worktop holds no badge, so the bucket is empty
+    let badge =
+        proxy
+            .env
+            .test_runner
+            .create_fungible_resource(dec!(1), 0, proxy.env.accounts[OWNER]);
+    let builder = ManifestBuilder::new()
+        .take_all_from_worktop(badge, "admin_badge")
+        .call_function_with_name_lookup(
+            proxy.env.package_address,
+            "PoolProxy",
+            "instantiate",
+            |lookup| (pool, lookup.bucket("admin_badge"), logic_v0, owner_role),
+        );
+    expect_failure_containing(
+        proxy.env.execute(OWNER, builder),
+        "Admin badge must not be empty",
+    );
+}
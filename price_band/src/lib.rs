@@ -0,0 +1,224 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use price_oracle::price_oracle::PriceOracle;
+use scrypto::prelude::*;
+
+/// Base and quote resources of a price
+pub type Pair = (ResourceAddress, ResourceAddress);
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct PriceBand {
+    /// Reference price, in quote resource per base resource
+    pub reference_price: Decimal,
+
+    /// Epoch at which the reference price was last updated
+    pub updated_at_epoch: u64,
+
+    /// Maximum relative deviation from the reference price
+    pub max_deviation: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ReferenceUpdatedEvent {
+    pub pair: Pair,
+    pub reference_price: Decimal,
+}
+
+#[blueprint]
+#[events(ReferenceUpdatedEvent)]
+pub mod price_band {
+
+    enable_method_auth! {
+        roles {
+            keeper => updatable_by: [OWNER];
+        },
+        methods {
+
+            set_band => restrict_to :[OWNER];
+            remove_band => restrict_to :[OWNER];
+            set_oracle => restrict_to :[OWNER];
+            set_max_reference_age => restrict_to :[OWNER];
+
+            update_reference => restrict_to :[keeper, OWNER];
+
+            assert_within_band => PUBLIC;
+            is_within_band => PUBLIC;
+            get_band => PUBLIC;
+
+        }
+    }
+
+    pub struct PriceBandBreaker {
+        bands: KeyValueStore<Pair, PriceBand>,
+
+        /// Oracle from which reference prices are read
+        oracle: Global<PriceOracle>,
+        max_price_age_seconds: i64,
+
+        /// Number of epochs after which a reference price is stale and every check fails
+        max_reference_age_epochs: u64,
+    }
+
+    impl PriceBandBreaker {
+        pub fn instantiate(
+            oracle: Global<PriceOracle>,
+            max_price_age_seconds: i64,
+            max_reference_age_epochs: u64,
+            owner_role: OwnerRole,
+            keeper_rule: AccessRule,
+        ) -> Global<PriceBandBreaker> {
+            /* CHECK INPUT */
+            assert!(
+                max_price_age_seconds > 0,
+                "Max price age must be greater than zero"
+            );
+            assert!(
+                max_reference_age_epochs > 0,
+                "Max reference age must be greater than zero"
+            );
+
+            Self {
+                bands: KeyValueStore::new(),
+                oracle,
+                max_price_age_seconds,
+                max_reference_age_epochs,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                keeper => keeper_rule;
+            ))
+            .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        /// Set the max deviation of a pair, reading its reference price from the oracle
+        pub fn set_band(&mut self, pair: Pair, max_deviation: Decimal) {
+            /* CHECK INPUT */
+            assert!(
+                max_deviation > Decimal::ZERO && max_deviation < Decimal::ONE,
+                "Max deviation must be between 0 and 1"
+            );
+
+            let reference_price = self._read_oracle(pair);
+
+            self.bands.insert(
+                pair,
+                PriceBand {
+                    reference_price,
+                    updated_at_epoch: Runtime::current_epoch().number(),
+                    max_deviation,
+                },
+            );
+
+            Runtime::emit_event(ReferenceUpdatedEvent {
+                pair,
+                reference_price,
+            });
+        }
+
+        /// Remove the band of a pair, after which every check of the pair fails
+        pub fn remove_band(&mut self, pair: Pair) {
+            assert!(self.bands.get(&pair).is_some(), "No band for this pair");
+
+            self.bands.remove(&pair);
+        }
+
+        pub fn set_oracle(&mut self, oracle: Global<PriceOracle>, max_price_age_seconds: i64) {
+            assert!(
+                max_price_age_seconds > 0,
+                "Max price age must be greater than zero"
+            );
+
+            self.oracle = oracle;
+            self.max_price_age_seconds = max_price_age_seconds;
+        }
+
+        pub fn set_max_reference_age(&mut self, max_reference_age_epochs: u64) {
+            assert!(
+                max_reference_age_epochs > 0,
+                "Max reference age must be greater than zero"
+            );
+
+            self.max_reference_age_epochs = max_reference_age_epochs;
+        }
+
+        /* KEEPER METHODS */
+
+        /// Refresh the reference price of a pair from the oracle
+        pub fn update_reference(&mut self, pair: Pair) {
+            let reference_price = self._read_oracle(pair);
+
+            let mut band = self.bands.get_mut(&pair).expect("No band for this pair");
+            band.reference_price = reference_price;
+            band.updated_at_epoch = Runtime::current_epoch().number();
+
+            Runtime::emit_event(ReferenceUpdatedEvent {
+                pair,
+                reference_price,
+            });
+        }
+
+        /* PUBLIC METHODS */
+
+        /// Fail if the price deviates from the reference price of the pair by more than the
+        /// max deviation, or if the reference price is stale. Called by AMMs and lending
+        /// markets before acting on a price, halting them during extreme deviations.
+        pub fn assert_within_band(&self, pair: Pair, price: Decimal) {
+            assert!(
+                self.is_within_band(pair, price),
+                "Price is outside of the band"
+            );
+        }
+
+        pub fn is_within_band(&self, pair: Pair, price: Decimal) -> bool {
+            let band = self.bands.get(&pair).expect("No band for this pair");
+
+            let reference_age = Runtime::current_epoch().number() - band.updated_at_epoch;
+            let max_distance = band.reference_price * band.max_deviation;
+
+            reference_age <= self.max_reference_age_epochs
+                && (price - band.reference_price).checked_abs().unwrap() <= max_distance
+        }
+
+        /* GETTERS */
+
+        pub fn get_band(&self, pair: Pair) -> Option<PriceBand> {
+            self.bands.get(&pair).map(|band| band.clone())
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _read_oracle(&self, pair: Pair) -> Decimal {
+            let price = self
+                .oracle
+                .get_price(pair.0, pair.1, self.max_price_age_seconds);
+
+            assert!(price > Decimal::ZERO, "Oracle price must be positive");
+
+            price
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use price_band::PriceBand;
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the breaker, and feeds the oracle
+const OWNER: usize = 0;
+const KEEPER: usize = 1;
+const ALICE: usize = 2;
+
+/// Ledger time of the tests, in seconds, rounded to the minute
+const NOW: i64 = 1_700_000_040;
+
+/// Breaker at epoch 10 reading prices at most an hour old from an oracle pricing the base
+/// resource 100 quote resources, with reference prices stale after 5 epochs. The oracle
+/// blueprint is linked into the breaker package, so it is instantiated from the same package.
+struct TestBreaker {
+    env: TestEnv,
+    breaker: ComponentAddress,
+    oracle: ComponentAddress,
+    base: ResourceAddress,
+    quote: ResourceAddress,
+}
+
+impl TestBreaker {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        env.test_runner
+            .advance_to_round_at_timestamp(Round::of(1), NOW * 1000);
+        let base = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[OWNER]);
+        let quote = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "PriceOracle",
+                "instantiate",
+                manifest_args!(
+                    owner_role.clone(),
+                    rule!(require(env.badges[OWNER].clone())),
+                    2u32
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let oracle = receipt.expect_commit_success().new_component_addresses()[0];
+
+        let manifest = ManifestBuilder::new()
+            .call_method(
+                oracle,
+                "push_price",
+                manifest_args!(base, quote, dec!(100), NOW - 60),
+            )
+            .call_function(
+                env.package_address,
+                "PriceBandBreaker",
+                "instantiate",
+                manifest_args!(
+                    oracle,
+                    3600i64,
+                    5u64,
+                    owner_role,
+                    rule!(require(env.badges[KEEPER].clone()))
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()]);
+        let breaker = receipt.expect_commit_success().new_component_addresses()[0];
+
+        Self {
+            env,
+            breaker,
+            oracle,
+            base,
+            quote,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.breaker, method, args)
+    }
+
+    /// Set the band of the base/quote pair
+    fn set_band(&mut self, caller: usize, max_deviation: Decimal) -> TransactionReceipt {
+        let pair = (self.base, self.quote);
+
+        self.call(caller, "set_band", manifest_args!(pair, max_deviation))
+    }
+
+    /// Check a price of the base/quote pair
+    fn check_price(&mut self, method: &str, price: Decimal) -> TransactionReceipt {
+        let pair = (self.base, self.quote);
+
+        self.call(ALICE, method, manifest_args!(pair, price))
+    }
+
+    fn is_within_band(&mut self, price: Decimal) -> bool {
+        self.check_price("is_within_band", price)
+            .expect_commit_success()
+            .output(0)
+    }
+
+    fn get_band(&mut self) -> Option<PriceBand> {
+        let pair = (self.base, self.quote);
+
+        self.call(ALICE, "get_band", manifest_args!(pair))
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_prices_are_checked_against_the_band() {
+    let mut breaker = TestBreaker::new();
+    let (base, quote) = (breaker.base, breaker.quote);
+
+    breaker.set_band(OWNER, dec!("0.1")).expect_commit_success();
+
+    assert!(breaker.is_within_band(dec!(110)));
+    assert!(!breaker.is_within_band(dec!(111)));
+    assert!(breaker.is_within_band(dec!(90)));
+    assert!(!breaker.is_within_band(dec!(89)));
+    breaker
+        .check_price("assert_within_band", dec!(105))
+        .expect_commit_success();
+    breaker
+        .check_price("assert_within_band", dec!(111))
+        .expect_commit_failure();
+
+    // The reference price set at epoch 10 is stale from epoch 16
+    breaker.env.set_epoch(16);
+    assert!(!breaker.is_within_band(dec!(100)));
+
+    let builder = ManifestBuilder::new().call_method(
+        breaker.oracle,
+        "push_price",
+        manifest_args!(base, quote, dec!(120), NOW),
+    );
+    breaker.env.execute(OWNER, builder).expect_commit_success();
+
+    breaker
+        .call(ALICE, "update_reference", manifest_args!((base, quote)))
+        .expect_commit_failure();
+    breaker
+        .call(KEEPER, "update_reference", manifest_args!((base, quote)))
+        .expect_commit_success();
+
+    let band = breaker.get_band().unwrap();
+    assert_eq!(band.reference_price, dec!(120));
+    assert_eq!(band.updated_at_epoch, 16);
+    assert!(breaker.is_within_band(dec!(130)));
+    assert!(!breaker.is_within_band(dec!(100)));
+}
+
+#[test]
+fn test_bands_are_managed_by_the_owner() {
+    let mut breaker = TestBreaker::new();
+    let (base, quote) = (breaker.base, breaker.quote);
+
+    breaker
+        .check_price("is_within_band", dec!(100))
+        .expect_commit_failure();
+    breaker
+        .call(KEEPER, "update_reference", manifest_args!((base, quote)))
+        .expect_commit_failure();
+
+    breaker.set_band(ALICE, dec!("0.1")).expect_commit_failure();
+    breaker.set_band(OWNER, dec!(0)).expect_commit_failure();
+    breaker.set_band(OWNER, dec!(1)).expect_commit_failure();
+
+    // The oracle has no price for the inverted pair
+    breaker
+        .call(
+            OWNER,
+            "set_band",
+            manifest_args!((quote, base), dec!("0.1")),
+        )
+        .expect_commit_failure();
+
+    breaker.set_band(OWNER, dec!("0.1")).expect_commit_success();
+    breaker
+        .call(OWNER, "set_max_reference_age", manifest_args!(0u64))
+        .expect_commit_failure();
+
+    breaker
+        .call(OWNER, "remove_band", manifest_args!((base, quote)))
+        .expect_commit_success();
+    breaker
+        .call(OWNER, "remove_band", manifest_args!((base, quote)))
+        .expect_commit_failure();
+    assert!(breaker.get_band().is_none());
+    breaker
+        .check_price("assert_within_band", dec!(100))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_reference_age_and_oracle_staleness() {
+    let mut breaker = TestBreaker::new();
+    let (oracle, base, quote) = (breaker.oracle, breaker.base, breaker.quote);
+
+    breaker.set_band(OWNER, dec!("0.1")).expect_commit_success();
+
+    // The reference price is still valid at its max age of 5 epochs
+    breaker.env.set_epoch(15);
+    assert!(breaker.is_within_band(dec!(100)));
+    breaker.env.set_epoch(16);
+    expect_failure_containing(
+        breaker.check_price("assert_within_band", dec!(100)),
+        "Price is outside of the band",
+    );
+
+    breaker
+        .call(OWNER, "set_max_reference_age", manifest_args!(10u64))
+        .expect_commit_success();
+    assert!(breaker.is_within_band(dec!(100)));
+
+    // The oracle price pushed a minute before the start is 3660 seconds old, so the
+    // reference price can not be refreshed
+    breaker
+        .env
+        .test_runner
+        .advance_to_round_at_timestamp(Round::of(2), (NOW + 3600) * 1000);
+    expect_failure_containing(
+        breaker.call(KEEPER, "update_reference", manifest_args!((base, quote))),
+        "Price is stale",
+    );
+    assert_eq!(breaker.get_band().unwrap().updated_at_epoch, 10);
+
+    breaker
+        .call(OWNER, "set_oracle", manifest_args!(oracle, 7200i64))
+        .expect_commit_success();
+    breaker
+        .call(KEEPER, "update_reference", manifest_args!((base, quote)))
+        .expect_commit_success();
+    assert_eq!(breaker.get_band().unwrap().updated_at_epoch, 16);
+}
+
+#[test]
+fn test_failures_carry_breaker_messages() {
+    let mut breaker = TestBreaker::new();
+    let (oracle, base, quote) = (breaker.oracle, breaker.base, breaker.quote);
+
+    expect_failure_containing(
+        breaker.check_price("is_within_band", dec!(100)),
+        "No band for this pair",
+    );
+    expect_failure_containing(
+        breaker.call(KEEPER, "update_reference", manifest_args!((base, quote))),
+        "No band for this pair",
+    );
+    expect_failure_containing(
+        breaker.call(OWNER, "remove_band", manifest_args!((base, quote))),
+        "No band for this pair",
+    );
+
+    expect_failure_containing(
+        breaker.set_band(OWNER, dec!(-1)),
+        "Max deviation must be between 0 and 1",
+    );
+    expect_failure_containing(
+        breaker.call(
+            OWNER,
+            "set_band",
+            manifest_args!((quote, base), dec!("0.1")),
+        ),
+        "No price for this pair",
+    );
+
+    expect_failure_containing(
+        breaker.call(OWNER, "set_max_reference_age", manifest_args!(0u64)),
+        "Max reference age must be greater than zero",
+    );
+    expect_failure_containing(
+        breaker.call(OWNER, "set_oracle", manifest_args!(oracle, 0i64)),
+        "Max price age must be greater than zero",
+    );
+    assert!(breaker.get_band().is_none());
+}
@@ -0,0 +1,242 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+
+/// Maximum number of past updates kept per pair
+pub const MAX_HISTORY_LENGTH: u32 = 100;
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct PriceUpdate {
+    /// Quote resource amount per base resource
+    pub price: Decimal,
+
+    /// Time of the observation, in seconds since the unix epoch, as reported by the feeder
+    pub timestamp: i64,
+
+    /// Epoch at which the update was pushed
+    pub epoch: u64,
+}
+
+#[derive(ScryptoSbor)]
+pub struct PairFeed {
+    pub latest: PriceUpdate,
+
+    /// Previous updates, oldest first, kept for dispute review
+    pub history: Vec<PriceUpdate>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PriceUpdatedEvent {
+    pub base: ResourceAddress,
+    pub quote: ResourceAddress,
+    pub price: Decimal,
+    pub timestamp: i64,
+}
+
+#[blueprint]
+#[events(PriceUpdatedEvent)]
+pub mod price_oracle {
+
+    enable_method_auth! {
+        roles {
+            feeder => updatable_by: [OWNER];
+        },
+        methods {
+
+            set_history_length => restrict_to :[OWNER];
+
+            push_price => restrict_to :[feeder, OWNER];
+            push_prices => restrict_to :[feeder, OWNER];
+
+            get_price => PUBLIC;
+            get_price_update => PUBLIC;
+            get_history => PUBLIC;
+
+        }
+    }
+
+    pub struct PriceOracle {
+        feeds: KeyValueStore<(ResourceAddress, ResourceAddress), PairFeed>,
+
+        /// Number of past updates kept per pair
+        history_length: u32,
+    }
+
+    impl PriceOracle {
+        pub fn instantiate(
+            owner_role: OwnerRole,
+            feeder_rule: AccessRule,
+            history_length: u32,
+        ) -> Global<PriceOracle> {
+            /* CHECK INPUT */
+            PriceOracle::_assert_valid_history_length(history_length);
+
+            Self {
+                feeds: KeyValueStore::new(),
+                history_length,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                feeder => feeder_rule;
+            ))
+            .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        /// Change the number of past updates kept per pair. Longer histories are trimmed on
+        /// the next update of each pair.
+        pub fn set_history_length(&mut self, history_length: u32) {
+            PriceOracle::_assert_valid_history_length(history_length);
+
+            self.history_length = history_length;
+        }
+
+        /* FEEDER METHODS */
+
+        pub fn push_price(
+            &mut self,
+            base: ResourceAddress,
+            quote: ResourceAddress,
+            price: Decimal,
+            timestamp: i64,
+        ) {
+            /* CHECK INPUT */
+            assert!(base != quote, "Base and quote resources must differ");
+            assert!(price > Decimal::ZERO, "Price must be greater than zero");
+            // The ledger clock is rounded down to the minute
+            assert!(
+                timestamp < PriceOracle::_now() + 60,
+                "Timestamp must not be in the future"
+            );
+
+            let update = PriceUpdate {
+                price,
+                timestamp,
+                epoch: Runtime::current_epoch().number(),
+            };
+
+            let feed_exists = self.feeds.get(&(base, quote)).is_some();
+
+            if feed_exists {
+                let mut feed = self.feeds.get_mut(&(base, quote)).unwrap();
+
+                assert!(
+                    timestamp > feed.latest.timestamp,
+                    "Timestamp must be after the latest update"
+                );
+
+                let previous = std::mem::replace(&mut feed.latest, update);
+                feed.history.push(previous);
+
+                let excess = feed
+                    .history
+                    .len()
+                    .saturating_sub(self.history_length as usize);
+                feed.history.drain(..excess);
+            } else {
+                self.feeds.insert(
+                    (base, quote),
+                    PairFeed {
+                        latest: update,
+                        history: Vec::new(),
+                    },
+                );
+            }
+
+            Runtime::emit_event(PriceUpdatedEvent {
+                base,
+                quote,
+                price,
+                timestamp,
+            });
+        }
+
+        pub fn push_prices(
+            &mut self,
+            updates: Vec<(ResourceAddress, ResourceAddress, Decimal, i64)>,
+        ) {
+            for (base, quote, price, timestamp) in updates {
+                self.push_price(base, quote, price, timestamp);
+            }
+        }
+
+        /* GETTERS */
+
+        /// Latest price of the pair, failing if it is older than `max_age_seconds`
+        pub fn get_price(
+            &self,
+            base: ResourceAddress,
+            quote: ResourceAddress,
+            max_age_seconds: i64,
+        ) -> Decimal {
+            let update = self
+                .get_price_update(base, quote)
+                .expect("No price for this pair");
+
+            assert!(
+                PriceOracle::_now() - update.timestamp <= max_age_seconds,
+                "Price is stale"
+            );
+
+            update.price
+        }
+
+        pub fn get_price_update(
+            &self,
+            base: ResourceAddress,
+            quote: ResourceAddress,
+        ) -> Option<PriceUpdate> {
+            self.feeds
+                .get(&(base, quote))
+                .map(|feed| feed.latest.clone())
+        }
+
+        pub fn get_history(
+            &self,
+            base: ResourceAddress,
+            quote: ResourceAddress,
+        ) -> Vec<PriceUpdate> {
+            self.feeds
+                .get(&(base, quote))
+                .map(|feed| feed.history.clone())
+                .unwrap_or_default()
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_history_length(history_length: u32) {
+            assert!(
+                history_length <= MAX_HISTORY_LENGTH,
+                "History length must not exceed {}",
+                MAX_HISTORY_LENGTH
+            );
+        }
+
+        fn _now() -> i64 {
+            Clock::current_time_rounded_to_minutes().seconds_since_unix_epoch
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use price_oracle::PriceUpdate;
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+const OWNER: usize = 0;
+const FEEDER: usize = 1;
+
+/// Ledger time of the tests, in seconds, rounded to the minute
+const NOW: i64 = 1_700_000_040;
+
+/// Oracle keeping 2 past updates per pair, with the ledger clock at `NOW`
+struct TestOracle {
+    env: TestEnv,
+    oracle: ComponentAddress,
+    base: ResourceAddress,
+    quote: ResourceAddress,
+}
+
+impl TestOracle {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 2);
+        env.test_runner
+            .advance_to_round_at_timestamp(Round::of(1), NOW * 1000);
+        let base = env
+            .test_runner
+            .create_fungible_resource(dec!(1), 18, env.accounts[OWNER]);
+        let quote = env
+            .test_runner
+            .create_fungible_resource(dec!(1), 18, env.accounts[OWNER]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "PriceOracle",
+                "instantiate",
+                manifest_args!(
+                    OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone()))),
+                    rule!(require(env.badges[FEEDER].clone())),
+                    2u32
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            oracle: commit.new_component_addresses()[0],
+            env,
+            base,
+            quote,
+        }
+    }
+
+    /// Call a method with the key of the account only
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        let manifest = ManifestBuilder::new()
+            .call_method(self.oracle, method, args)
+            .build();
+
+        self.env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![self.env.badges[account].clone()])
+    }
+
+    fn push_price(&mut self, price: Decimal, timestamp: i64) -> TransactionReceipt {
+        let (base, quote) = (self.base, self.quote);
+
+        self.call(
+            FEEDER,
+            "push_price",
+            manifest_args!(base, quote, price, timestamp),
+        )
+    }
+
+    fn get_price(&mut self, max_age_seconds: i64) -> TransactionReceipt {
+        let (base, quote) = (self.base, self.quote);
+
+        self.call(
+            FEEDER,
+            "get_price",
+            manifest_args!(base, quote, max_age_seconds),
+        )
+    }
+
+    fn get_history(&mut self) -> Vec<PriceUpdate> {
+        let (base, quote) = (self.base, self.quote);
+
+        self.call(FEEDER, "get_history", manifest_args!(base, quote))
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_get_price_fails_when_stale() {
+    let mut oracle = TestOracle::new();
+
+    oracle.get_price(60).expect_commit_failure();
+
+    oracle.push_price(dec!(2), NOW - 30).expect_commit_success();
+
+    let price: Decimal = oracle.get_price(60).expect_commit_success().output(0);
+    assert_eq!(price, dec!(2));
+    oracle.get_price(29).expect_commit_failure();
+}
+
+#[test]
+fn test_history_keeps_the_latest_updates() {
+    let mut oracle = TestOracle::new();
+    let (base, quote) = (oracle.base, oracle.quote);
+
+    for (i, timestamp) in [NOW - 30, NOW - 20, NOW - 10, NOW].into_iter().enumerate() {
+        oracle
+            .push_price(Decimal::from(i + 1), timestamp)
+            .expect_commit_success();
+    }
+
+    let latest: Option<PriceUpdate> = oracle
+        .call(FEEDER, "get_price_update", manifest_args!(base, quote))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(latest.unwrap().price, dec!(4));
+
+    let history = oracle.get_history();
+    assert_eq!(history.len(), 2);
+    assert_eq!(
+        (history[0].price, history[0].timestamp),
+        (dec!(2), NOW - 20)
+    );
+    assert_eq!(
+        (history[1].price, history[1].timestamp),
+        (dec!(3), NOW - 10)
+    );
+
+    oracle
+        .call(OWNER, "set_history_length", manifest_args!(0u32))
+        .expect_commit_success();
+    oracle.push_price(dec!(5), NOW + 1).expect_commit_success();
+    assert!(oracle.get_history().is_empty());
+}
+
+#[test]
+fn test_push_price_rejects_invalid_updates() {
+    let mut oracle = TestOracle::new();
+    let base = oracle.base;
+
+    oracle.push_price(dec!(0), NOW).expect_commit_failure();
+    oracle.push_price(dec!(1), NOW + 60).expect_commit_failure();
+    oracle.push_price(dec!(1), NOW + 59).expect_commit_success();
+
+    oracle.push_price(dec!(1), NOW + 59).expect_commit_failure();
+    oracle.push_price(dec!(1), NOW).expect_commit_failure();
+
+    oracle
+        .call(
+            FEEDER,
+            "push_price",
+            manifest_args!(base, base, dec!(1), NOW),
+        )
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_push_prices_requires_the_feeder_or_the_owner() {
+    let mut oracle = TestOracle::new();
+    let (base, quote) = (oracle.base, oracle.quote);
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            oracle.oracle,
+            "push_price",
+            manifest_args!(base, quote, dec!(1), NOW),
+        )
+        .build();
+    oracle
+        .env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![])
+        .expect_commit_failure();
+
+    oracle
+        .call(
+            OWNER,
+            "push_prices",
+            manifest_args!(vec![
+                (base, quote, dec!(1), NOW - 10),
+                (quote, base, dec!(1), NOW)
+            ]),
+        )
+        .expect_commit_success();
+    oracle
+        .call(
+            FEEDER,
+            "push_prices",
+            manifest_args!(vec![(base, quote, dec!(2), NOW)]),
+        )
+        .expect_commit_success();
+
+    let price: Decimal = oracle.get_price(60).expect_commit_success().output(0);
+    assert_eq!(price, dec!(2));
+}
+
+#[test]
+fn test_set_history_length_requires_the_owner() {
+    let mut oracle = TestOracle::new();
+
+    oracle
+        .call(FEEDER, "set_history_length", manifest_args!(10u32))
+        .expect_commit_failure();
+    oracle
+        .call(OWNER, "set_history_length", manifest_args!(101u32))
+        .expect_commit_failure();
+    oracle
+        .call(OWNER, "set_history_length", manifest_args!(100u32))
+        .expect_commit_success();
+}
@@ -0,0 +1,538 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::{draw, mix_entropy, reveal_seed};
+use scrypto::prelude::*;
+use single_asset_pool::pool::AssetPool;
+
+// RANDOMNESS
+//
+// Winners are drawn with the commit-reveal scheme of `blueprint_commons` (see `randomness.rs`):
+// the operator commits when starting a round, and deposits and withdrawals mix the entropy.
+// Once the round has ended, deposits and withdrawals are rejected until the draw, so the
+// entropy and the tickets are frozen, and the operator reveals the secret. A missed reveal is
+// penalized with the bond the operator locks when starting a round: it is returned by the draw,
+// and if the secret is not revealed before the reveal deadline, anyone can skip the round and
+// the bond is added to the prize, which rolls over to the next round.
+
+/// Maximum number of tickets minted by a single deposit
+pub const MAX_TICKETS_PER_DEPOSIT: u64 = 100;
+
+pub use blueprint_commons::commitment;
+
+/// Index of the winning ticket, between 0 and `ticket_count - 1`
+pub fn draw_index(secret: &[u8], entropy: Hash, ticket_count: u64) -> u64 {
+    draw(reveal_seed(secret, entropy), ticket_count)
+}
+
+/// Ticket worth `ticket_size` of principal
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct SavingsTicket {
+    pub deposited_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct Round {
+    pub commitment: Hash,
+
+    /// Last epoch of deposits and withdrawals
+    pub end_epoch: u64,
+
+    /// Last epoch at which the operator can reveal the secret
+    pub reveal_deadline_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RoundStartedEvent {
+    pub commitment: Hash,
+
+    /// Last epoch of deposits and withdrawals
+    pub end_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RoundSkippedEvent {
+    pub commitment: Hash,
+    pub forfeited_bond: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PrizeAwardedEvent {
+    pub ticket_id: u64,
+    pub prize: Decimal,
+    pub seed_entropy: Hash,
+}
+
+#[blueprint]
+#[events(RoundStartedEvent, RoundSkippedEvent, PrizeAwardedEvent)]
+pub mod prize_savings {
+
+    enable_method_auth! {
+        methods {
+
+            start_round => restrict_to :[OWNER];
+            draw => restrict_to :[OWNER];
+
+            skip_round => PUBLIC;
+            add_prize => PUBLIC;
+
+            deposit => PUBLIC;
+            withdraw => PUBLIC;
+            claim_prize => PUBLIC;
+
+            get_prize => PUBLIC;
+            get_total_principal => PUBLIC;
+            get_ticket_count => PUBLIC;
+            get_round => PUBLIC;
+            get_pending_prize => PUBLIC;
+            get_reveal_bond => PUBLIC;
+
+        }
+    }
+
+    pub struct PrizeSavings {
+        /// Pool in which principal is contributed to earn yield
+        pool: Global<AssetPool>,
+
+        /// Pool units of the contributed principal and yield
+        pool_units: Vault,
+
+        /// Principal deposited through tickets
+        total_principal: Decimal,
+        ticket_size: Decimal,
+
+        /// Ticket non-fungible resource manager
+        ticket_res_manager: ResourceManager,
+        next_ticket_id: u64,
+
+        /// Active tickets stored densely, so a ticket can be drawn from its index
+        ticket_slots: KeyValueStore<u64, u64>,
+        ticket_indexes: KeyValueStore<u64, u64>,
+        ticket_count: u64,
+
+        /// Awarded prizes and sponsored prizes not awarded yet
+        prizes: Vault,
+
+        /// Awarded prizes not claimed yet, indexed by ticket
+        pending_prizes: KeyValueStore<u64, Decimal>,
+        total_pending_prizes: Decimal,
+
+        round_epochs: u64,
+        reveal_period_epochs: u64,
+        round: Option<Round>,
+
+        /// Amount locked by the operator for each round, forfeited to the prize when the
+        /// secret is not revealed
+        reveal_bond: Decimal,
+        bond: Vault,
+
+        /// Public entropy accumulated from deposits and withdrawals
+        entropy: Hash,
+    }
+
+    impl PrizeSavings {
+        /// Instantiate prize savings contributing to the given AssetPool, with its pooled and
+        /// pool unit resources. Deposits are made by tickets of `ticket_size`, and the operator
+        /// locks `reveal_bond` assets for each round. Returns the component and the ticket
+        /// resource address.
+        pub fn instantiate(
+            pool: Global<AssetPool>,
+            asset_res_address: ResourceAddress,
+            pool_unit_res_address: ResourceAddress,
+            ticket_size: Decimal,
+            round_epochs: u64,
+            reveal_period_epochs: u64,
+            reveal_bond: Decimal,
+            owner_role: OwnerRole,
+        ) -> (Global<PrizeSavings>, ResourceAddress) {
+            /* CHECK INPUT */
+            assert!(
+                ticket_size > Decimal::ZERO,
+                "Ticket size must be greater than zero"
+            );
+            assert!(round_epochs > 0, "Round duration must be greater than zero");
+            assert!(
+                reveal_period_epochs > 0,
+                "Reveal period must be greater than zero"
+            );
+            assert!(
+                reveal_bond > Decimal::ZERO,
+                "Reveal bond must be greater than zero"
+            );
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(PrizeSavings::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let ticket_res_manager =
+                ResourceBuilder::new_integer_non_fungible::<SavingsTicket>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule;
+                        burner_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let savings_component = Self {
+                pool,
+                pool_units: Vault::new(pool_unit_res_address),
+                total_principal: Decimal::ZERO,
+                ticket_size,
+                ticket_res_manager,
+                next_ticket_id: 1,
+                ticket_slots: KeyValueStore::new(),
+                ticket_indexes: KeyValueStore::new(),
+                ticket_count: 0,
+                prizes: Vault::new(asset_res_address),
+                pending_prizes: KeyValueStore::new(),
+                total_pending_prizes: Decimal::ZERO,
+                round_epochs,
+                reveal_period_epochs,
+                round: None,
+                reveal_bond,
+                bond: Vault::new(asset_res_address),
+                entropy: Hash([0u8; 32]),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (savings_component, ticket_res_manager.address())
+        }
+
+        /* OPERATOR METHODS */
+
+        /// Start a round, committing to the secret revealed at the end of the round and
+        /// locking the reveal bond. Returns the change.
+        pub fn start_round(&mut self, commitment: Hash, mut bond: Bucket) -> Bucket {
+            /* CHECK INPUT */
+            assert!(self.round.is_none(), "A round is already running");
+            assert!(
+                bond.resource_address() == self.bond.resource_address(),
+                "Bond resource address mismatch"
+            );
+            assert!(
+                bond.amount() >= self.reveal_bond,
+                "Bond is below the reveal bond"
+            );
+
+            self.bond.put(bond.take(self.reveal_bond));
+
+            let end_epoch = Runtime::current_epoch().number() + self.round_epochs;
+
+            self.round = Some(Round {
+                commitment,
+                end_epoch,
+                reveal_deadline_epoch: end_epoch + self.reveal_period_epochs,
+            });
+            self.entropy = commitment;
+
+            Runtime::emit_event(RoundStartedEvent {
+                commitment,
+                end_epoch,
+            });
+
+            bond
+        }
+
+        /// Reveal the committed secret once the round has ended, awarding the accumulated
+        /// yield to a random ticket. Without tickets, the prize rolls over. Returns the bond.
+        pub fn draw(&mut self, secret: Vec<u8>) -> Bucket {
+            let round = self.round.clone().expect("No round running");
+            let current_epoch = Runtime::current_epoch().number();
+
+            assert!(current_epoch > round.end_epoch, "Round has not ended yet");
+            assert!(
+                current_epoch <= round.reveal_deadline_epoch,
+                "Reveal deadline has passed"
+            );
+            assert!(
+                commitment(&secret) == round.commitment,
+                "Secret does not match the commitment"
+            );
+
+            self.round = None;
+            self._harvest_yield();
+
+            let prize = self.get_prize();
+
+            if self.ticket_count == 0 || prize == Decimal::ZERO {
+                return self.bond.take_all();
+            }
+
+            let index = draw_index(&secret, self.entropy, self.ticket_count);
+            let ticket_id = *self.ticket_slots.get(&index).unwrap();
+
+            let pending_prize = self
+                .pending_prizes
+                .get(&ticket_id)
+                .map_or(Decimal::ZERO, |pending_prize| *pending_prize);
+
+            self.pending_prizes.insert(ticket_id, pending_prize + prize);
+            self.total_pending_prizes += prize;
+
+            Runtime::emit_event(PrizeAwardedEvent {
+                ticket_id,
+                prize,
+                seed_entropy: self.entropy,
+            });
+
+            self.bond.take_all()
+        }
+
+        /* PUBLIC METHODS */
+
+        /// Skip the current round if the operator has not revealed the secret before the
+        /// deadline. The bond of the operator is added to the prize, which rolls over to the
+        /// next round.
+        pub fn skip_round(&mut self) {
+            let round = self.round.clone().expect("No round running");
+
+            assert!(
+                Runtime::current_epoch().number() > round.reveal_deadline_epoch,
+                "Reveal deadline has not passed yet"
+            );
+
+            self.round = None;
+
+            let forfeited_bond = self.bond.take_all();
+
+            Runtime::emit_event(RoundSkippedEvent {
+                commitment: round.commitment,
+                forfeited_bond: forfeited_bond.amount(),
+            });
+
+            self.prizes.put(forfeited_bond);
+        }
+
+        /// Sponsor the next prize
+        pub fn add_prize(&mut self, prize: Bucket) {
+            self.prizes.put(prize);
+        }
+
+        /* DEPOSITOR METHODS */
+
+        /// Deposit principal and receive one ticket per `ticket_size`. Returns the tickets and
+        /// the change.
+        pub fn deposit(&mut self, mut assets: Bucket) -> (Bucket, Bucket) {
+            /* CHECK INPUT */
+            self._assert_round_not_ended();
+            assert!(
+                assets.resource_address() == self.prizes.resource_address(),
+                "Asset resource address mismatch"
+            );
+
+            let ticket_count = (assets.amount() / self.ticket_size)
+                .checked_round(0, RoundingMode::ToZero)
+                .unwrap();
+
+            assert!(
+                ticket_count >= Decimal::ONE,
+                "Deposit is below the ticket size"
+            );
+            assert!(
+                ticket_count <= Decimal::from(MAX_TICKETS_PER_DEPOSIT),
+                "Too many tickets for a single deposit"
+            );
+
+            let principal = ticket_count * self.ticket_size;
+
+            let (pool_units, change) = self.pool.contribute(assets.take(principal));
+            assets.put(change);
+
+            self.pool_units.put(pool_units);
+            self.total_principal += principal;
+
+            let mut tickets = Bucket::new(self.ticket_res_manager.address());
+            let deposited_at_epoch = Runtime::current_epoch().number();
+
+            while tickets.amount() < ticket_count {
+                let ticket_id = self.next_ticket_id;
+                self.next_ticket_id += 1;
+
+                tickets.put(self.ticket_res_manager.mint_non_fungible(
+                    &NonFungibleLocalId::integer(ticket_id),
+                    SavingsTicket { deposited_at_epoch },
+                ));
+
+                self.ticket_slots.insert(self.ticket_count, ticket_id);
+                self.ticket_indexes.insert(ticket_id, self.ticket_count);
+                self.ticket_count += 1;
+            }
+
+            self._mix_entropy();
+
+            (tickets, assets)
+        }
+
+        /// Burn tickets for their principal and their unclaimed prizes
+        pub fn withdraw(&mut self, tickets: Bucket) -> Bucket {
+            /* CHECK INPUT */
+            self._assert_round_not_ended();
+            assert!(
+                tickets.resource_address() == self.ticket_res_manager.address(),
+                "Ticket resource address mismatch"
+            );
+
+            let mut assets = Bucket::new(self.prizes.resource_address());
+
+            for local_id in tickets.as_non_fungible().non_fungible_local_ids() {
+                let ticket_id = PrizeSavings::_ticket_id(&local_id);
+
+                assets.put(self._take_pending_prize(ticket_id));
+                self._remove_ticket(ticket_id);
+            }
+
+            let principal = self.ticket_size * tickets.amount();
+            tickets.burn();
+
+            let units = self
+                .pool
+                .get_units_for_amount(principal)
+                .min(self.pool_units.amount());
+
+            let (redeemed, remaining_units) = self.pool.redeem(self.pool_units.take(units));
+            self.pool_units.put(remaining_units);
+            self.total_principal -= principal;
+
+            assets.put(redeemed);
+
+            self._mix_entropy();
+
+            assets
+        }
+
+        /// Claim the prizes awarded to a ticket
+        pub fn claim_prize(&mut self, ticket_proof: Proof) -> Bucket {
+            let local_id = ticket_proof
+                .check(self.ticket_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id();
+
+            self._take_pending_prize(PrizeSavings::_ticket_id(&local_id))
+        }
+
+        /* GETTERS */
+
+        /// Prize of the next draw, i.e. sponsored prizes and yield already harvested. Yield
+        /// still held in the pool is harvested at the draw.
+        pub fn get_prize(&self) -> Decimal {
+            self.prizes.amount() - self.total_pending_prizes
+        }
+
+        pub fn get_total_principal(&self) -> Decimal {
+            self.total_principal
+        }
+
+        pub fn get_ticket_count(&self) -> u64 {
+            self.ticket_count
+        }
+
+        pub fn get_round(&self) -> Option<Round> {
+            self.round.clone()
+        }
+
+        pub fn get_pending_prize(&self, ticket_id: u64) -> Decimal {
+            self.pending_prizes
+                .get(&ticket_id)
+                .map_or(Decimal::ZERO, |pending_prize| *pending_prize)
+        }
+
+        pub fn get_reveal_bond(&self) -> Decimal {
+            self.reveal_bond
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        /// Deposits and withdrawals change the entropy and the odds of the draw, so they are
+        /// rejected between the end of a round and its draw or skip
+        fn _assert_round_not_ended(&self) {
+            if let Some(round) = &self.round {
+                assert!(
+                    Runtime::current_epoch().number() <= round.end_epoch,
+                    "Round has ended, waiting for the draw"
+                );
+            }
+        }
+
+        /// Redeem the yield earned by the principal from the pool into the prizes
+        fn _harvest_yield(&mut self) {
+            let value = self.pool.get_amount_for_units(self.pool_units.amount());
+
+            if value <= self.total_principal {
+                return;
+            }
+
+            let units = self.pool.get_units_for_amount(value - self.total_principal);
+
+            let (yield_assets, remaining_units) = self.pool.redeem(self.pool_units.take(units));
+            self.pool_units.put(remaining_units);
+
+            self.prizes.put(yield_assets);
+        }
+
+        fn _take_pending_prize(&mut self, ticket_id: u64) -> Bucket {
+            let pending_prize = self.get_pending_prize(ticket_id);
+
+            if pending_prize > Decimal::ZERO {
+                self.pending_prizes.insert(ticket_id, Decimal::ZERO);
+                self.total_pending_prizes -= pending_prize;
+            }
+
+            self.prizes.take(pending_prize)
+        }
+
+        /// Remove a ticket from the dense slots by moving the last ticket into its slot
+        fn _remove_ticket(&mut self, ticket_id: u64) {
+            let index = *self.ticket_indexes.get(&ticket_id).unwrap();
+            let last_index = self.ticket_count - 1;
+
+            if index != last_index {
+                let last_ticket_id = *self.ticket_slots.get(&last_index).unwrap();
+
+                self.ticket_slots.insert(index, last_ticket_id);
+                self.ticket_indexes.insert(last_ticket_id, index);
+            }
+
+            self.ticket_slots.remove(&last_index);
+            self.ticket_indexes.remove(&ticket_id);
+            self.ticket_count -= 1;
+        }
+
+        fn _mix_entropy(&mut self) {
+            self.entropy = mix_entropy(self.entropy);
+        }
+
+        fn _ticket_id(local_id: &NonFungibleLocalId) -> u64 {
+            match local_id {
+                NonFungibleLocalId::Integer(ticket_id) => ticket_id.value(),
+                _ => panic!("Invalid ticket id"),
+            }
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Operates the rounds and administrates the pool, holds 100 assets to generate yield
+const OWNER: usize = 0;
+/// Holds 100 assets
+const ALICE: usize = 1;
+/// Holds 100 assets
+const BOB: usize = 2;
+
+/// Manifest encoding of `DepositType::LiquidityAddition`
+fn liquidity_addition() -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 1,
+        fields: vec![],
+    }
+}
+
+/// Prize savings at epoch 10 with tickets of 10 assets and rounds of 10 epochs, revealed within
+/// 5 epochs against a bond of 5 assets. The AssetPool blueprint is linked into the savings package, so the pool is
+/// instantiated from the same package, with an admin rule allowing the owner and the savings
+/// blueprint.
+struct TestSavings {
+    env: TestEnv,
+    savings: ComponentAddress,
+    ticket: ResourceAddress,
+    pool: ComponentAddress,
+    asset: ResourceAddress,
+}
+
+impl TestSavings {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let asset = env
+            .test_runner
+            .create_fungible_resource(dec!(300), 18, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+        let admin_rule = rule!(
+            require(env.badges[OWNER].clone())
+                || require(global_caller(BlueprintId::new(
+                    &env.package_address,
+                    "PrizeSavings"
+                )))
+        );
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "AssetPool",
+                "instantiate",
+                manifest_args!(asset, owner_role.clone(), admin_rule),
+            )
+            .withdraw_from_account(env.accounts[OWNER], asset, dec!(200))
+            .take_from_worktop(asset, dec!(100), "alice")
+            .take_from_worktop(asset, dec!(100), "bob")
+            .call_method_with_name_lookup(env.accounts[ALICE], "deposit", |lookup| {
+                (lookup.bucket("alice"),)
+            })
+            .call_method_with_name_lookup(env.accounts[BOB], "deposit", |lookup| {
+                (lookup.bucket("bob"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let (pool, pool_unit, _): (ComponentAddress, ResourceAddress, ResourceAddress) =
+            receipt.expect_commit_success().output(0);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "PrizeSavings",
+                "instantiate",
+                manifest_args!(
+                    pool,
+                    asset,
+                    pool_unit,
+                    dec!(10),
+                    10u64,
+                    5u64,
+                    dec!(5),
+                    owner_role
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let (savings, ticket): (ComponentAddress, ResourceAddress) =
+            receipt.expect_commit_success().output(0);
+
+        Self {
+            env,
+            savings,
+            ticket,
+            pool,
+            asset,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.savings, method, args)
+    }
+
+    /// Call a method with a bucket of the given resource of the account
+    fn call_with_bucket(
+        &mut self,
+        account: usize,
+        res_address: ResourceAddress,
+        amount: Decimal,
+        method: &str,
+    ) -> TransactionReceipt {
+        let builder =
+            ManifestBuilder::new()
+                .withdraw_from_account(self.env.accounts[account], res_address, amount)
+                .take_all_from_worktop(res_address, "bucket")
+                .call_method_with_name_lookup(self.savings, method, |lookup| {
+                    (lookup.bucket("bucket"),)
+                });
+
+        self.env.execute(account, builder)
+    }
+
+    /// Start a round committing to the secret, with a bond of 5 assets of the caller
+    fn start_round(&mut self, caller: usize, secret: &[u8]) -> TransactionReceipt {
+        let asset = self.asset;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[caller], asset, dec!(5))
+            .take_all_from_worktop(asset, "bond")
+            .call_method_with_name_lookup(self.savings, "start_round", |lookup| {
+                (hash(secret), lookup.bucket("bond"))
+            });
+
+        self.env.execute(caller, builder)
+    }
+
+    fn deposit(&mut self, account: usize, amount: Decimal) -> TransactionReceipt {
+        self.call_with_bucket(account, self.asset, amount, "deposit")
+    }
+
+    /// Withdraw all the tickets of the account
+    fn withdraw(&mut self, account: usize) -> TransactionReceipt {
+        let ticket = self.ticket;
+        let amount = self.env.balance(account, ticket);
+
+        self.call_with_bucket(account, ticket, amount, "withdraw")
+    }
+
+    /// Add yield to the pool, shared by the pool units of the savings
+    fn add_yield(&mut self, amount: Decimal) -> TransactionReceipt {
+        let asset = self.asset;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[OWNER], asset, amount)
+            .take_all_from_worktop(asset, "assets")
+            .call_method_with_name_lookup(self.pool, "protected_deposit", |lookup| {
+                (lookup.bucket("assets"), liquidity_addition())
+            });
+
+        self.env.execute(OWNER, builder)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str, args: impl ResolvableArguments) -> T {
+        self.call(OWNER, method, args)
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_yield_is_awarded_to_a_ticket() {
+    let mut savings = TestSavings::new();
+    let (asset, ticket) = (savings.asset, savings.ticket);
+
+    savings
+        .start_round(OWNER, b"secret")
+        .expect_commit_success();
+
+    savings.deposit(ALICE, dec!(25)).expect_commit_success();
+    assert_eq!(savings.env.balance(ALICE, ticket), dec!(2));
+    assert_eq!(savings.env.balance(ALICE, asset), dec!(80));
+    savings.deposit(BOB, dec!(10)).expect_commit_success();
+
+    assert_eq!(
+        savings.get::<Decimal>("get_total_principal", manifest_args!()),
+        dec!(30)
+    );
+    assert_eq!(savings.get::<u64>("get_ticket_count", manifest_args!()), 3);
+
+    // The principal of 30 doubles in the pool
+    savings.add_yield(dec!(30)).expect_commit_success();
+
+    savings.env.set_epoch(20);
+    savings
+        .call(OWNER, "draw", manifest_args!(b"secret".to_vec()))
+        .expect_commit_failure();
+
+    // Once the round has ended, the tickets are frozen until the draw
+    savings.env.set_epoch(21);
+    savings.deposit(BOB, dec!(10)).expect_commit_failure();
+    savings.withdraw(ALICE).expect_commit_failure();
+
+    savings
+        .call(OWNER, "draw", manifest_args!(b"wrong".to_vec()))
+        .expect_commit_failure();
+    assert_eq!(savings.env.balance(OWNER, asset), dec!(65));
+    savings
+        .call(OWNER, "draw", manifest_args!(b"secret".to_vec()))
+        .expect_commit_success();
+
+    // The bond is returned by the draw
+    assert_eq!(savings.env.balance(OWNER, asset), dec!(70));
+
+    let round: Option<(Hash, u64, u64)> = savings.get("get_round", manifest_args!());
+    assert_eq!(round, None);
+
+    // The whole yield is awarded to one of the 3 tickets
+    let pending_prizes: Vec<Decimal> = (1..=3u64)
+        .map(|ticket_id| savings.get("get_pending_prize", manifest_args!(ticket_id)))
+        .collect();
+    assert_eq!(
+        pending_prizes
+            .iter()
+            .fold(Decimal::ZERO, |total, prize| total + *prize),
+        dec!(30)
+    );
+    assert!(pending_prizes.contains(&dec!(30)));
+
+    savings.withdraw(ALICE).expect_commit_success();
+    savings.withdraw(BOB).expect_commit_success();
+    assert_eq!(
+        savings.env.balance(ALICE, asset) + savings.env.balance(BOB, asset),
+        dec!(230)
+    );
+    assert_eq!(
+        savings.get::<Decimal>("get_total_principal", manifest_args!()),
+        dec!(0)
+    );
+    assert_eq!(savings.get::<u64>("get_ticket_count", manifest_args!()), 0);
+}
+
+#[test]
+fn test_round_is_skipped_without_reveal() {
+    let mut savings = TestSavings::new();
+    let asset = savings.asset;
+
+    savings
+        .start_round(OWNER, b"secret")
+        .expect_commit_success();
+    savings
+        .start_round(OWNER, b"secret")
+        .expect_commit_failure();
+
+    // The reveal deadline is epoch 25
+    savings.env.set_epoch(25);
+    savings
+        .call(ALICE, "skip_round", manifest_args!())
+        .expect_commit_failure();
+
+    savings.env.set_epoch(26);
+    savings
+        .call(OWNER, "draw", manifest_args!(b"secret".to_vec()))
+        .expect_commit_failure();
+    savings
+        .call(ALICE, "skip_round", manifest_args!())
+        .expect_commit_success();
+
+    // The bond of the operator is forfeited to the prize
+    assert_eq!(savings.env.balance(OWNER, asset), dec!(95));
+    assert_eq!(
+        savings.get::<Decimal>("get_prize", manifest_args!()),
+        dec!(5)
+    );
+    savings
+        .call_with_bucket(BOB, asset, dec!(5), "add_prize")
+        .expect_commit_success();
+    assert_eq!(
+        savings.get::<Decimal>("get_prize", manifest_args!()),
+        dec!(10)
+    );
+
+    // Without tickets, the prize rolls over to the next round
+    savings.start_round(OWNER, b"other").expect_commit_success();
+    savings.env.set_epoch(37);
+    savings
+        .call(OWNER, "draw", manifest_args!(b"other".to_vec()))
+        .expect_commit_success();
+    assert_eq!(
+        savings.get::<Decimal>("get_prize", manifest_args!()),
+        dec!(10)
+    );
+    assert_eq!(savings.env.balance(OWNER, asset), dec!(95));
+}
+
+#[test]
+fn test_tickets_are_frozen_until_the_round_is_skipped() {
+    let mut savings = TestSavings::new();
+    let asset = savings.asset;
+
+    savings.deposit(ALICE, dec!(20)).expect_commit_success();
+    savings
+        .start_round(OWNER, b"secret")
+        .expect_commit_success();
+
+    // Deposits and withdrawals are accepted until the end epoch included
+    savings.env.set_epoch(20);
+    savings.deposit(BOB, dec!(10)).expect_commit_success();
+
+    savings.env.set_epoch(21);
+    savings.deposit(ALICE, dec!(10)).expect_commit_failure();
+    savings.withdraw(BOB).expect_commit_failure();
+
+    // Withholding the reveal does not keep the principal locked
+    savings.env.set_epoch(26);
+    savings
+        .call(BOB, "skip_round", manifest_args!())
+        .expect_commit_success();
+    savings.withdraw(ALICE).expect_commit_success();
+    savings.withdraw(BOB).expect_commit_success();
+    assert_eq!(savings.env.balance(ALICE, asset), dec!(100));
+    assert_eq!(savings.env.balance(BOB, asset), dec!(100));
+}
+
+#[test]
+fn test_deposits_and_operator_methods_are_checked() {
+    let mut savings = TestSavings::new();
+    let asset = savings.asset;
+
+    savings
+        .start_round(ALICE, b"secret")
+        .expect_commit_failure();
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(savings.env.accounts[OWNER], asset, dec!(4))
+        .take_all_from_worktop(asset, "bond")
+        .call_method_with_name_lookup(savings.savings, "start_round", |lookup| {
+            (hash(b"secret"), lookup.bucket("bond"))
+        });
+    savings.env.execute(OWNER, builder).expect_commit_failure();
+    savings
+        .call(OWNER, "draw", manifest_args!(b"secret".to_vec()))
+        .expect_commit_failure();
+
+    savings.deposit(ALICE, dec!(5)).expect_commit_failure();
+    savings
+        .call_with_bucket(ALICE, XRD, dec!(10), "deposit")
+        .expect_commit_failure();
+    savings
+        .call_with_bucket(ALICE, asset, dec!(10), "withdraw")
+        .expect_commit_failure();
+
+    savings.deposit(ALICE, dec!(10)).expect_commit_success();
+    savings.withdraw(ALICE).expect_commit_success();
+    assert_eq!(savings.env.balance(ALICE, asset), dec!(100));
+}
@@ -0,0 +1,394 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::{assert_fungible_res_address, ReentrancyGuard};
+use scrypto::prelude::*;
+
+/// LP amount locked in the pair on the first deposit
+pub const MINIMUM_LIQUIDITY: Decimal = dec!("0.000000000000001");
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SwapEvent {
+    pub input_res_address: ResourceAddress,
+    pub input_amount: Decimal,
+    pub output_res_address: ResourceAddress,
+    pub output_amount: Decimal,
+    pub fee_amount: Decimal,
+    pub reserves_after: (Decimal, Decimal),
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct AddLiquidityEvent {
+    pub amounts: (Decimal, Decimal),
+    pub lp_amount: Decimal,
+    pub reserves_after: (Decimal, Decimal),
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RemoveLiquidityEvent {
+    pub amounts: (Decimal, Decimal),
+    pub lp_amount: Decimal,
+    pub reserves_after: (Decimal, Decimal),
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SwapFeeUpdatedEvent {
+    pub previous_fee: Decimal,
+    pub new_fee: Decimal,
+}
+
+#[blueprint]
+#[events(
+    SwapEvent,
+    AddLiquidityEvent,
+    RemoveLiquidityEvent,
+    SwapFeeUpdatedEvent
+)]
+pub mod radiswap_pair {
+
+    enable_method_auth! {
+        methods {
+
+            set_swap_fee => restrict_to :[OWNER];
+
+            add_liquidity => PUBLIC;
+            remove_liquidity => PUBLIC;
+            swap => PUBLIC;
+
+            get_reserves => PUBLIC;
+            get_output_amount => PUBLIC;
+            get_lp_supply => PUBLIC;
+            get_swap_fee => PUBLIC;
+
+        }
+    }
+
+    pub struct RadiswapPair {
+        vault_a: Vault,
+        vault_b: Vault,
+
+        /// LP token resource manager
+        lp_res_manager: ResourceManager,
+
+        /// LP tokens minted on the first deposit that can never be redeemed
+        locked_liquidity: Vault,
+
+        /// Share of the input amount kept in the pool on each swap
+        swap_fee: Decimal,
+
+        /// Rejects nested calls into the state changing user methods
+        reentrancy_guard: ReentrancyGuard,
+    }
+
+    impl RadiswapPair {
+        pub fn instantiate(
+            res_address_a: ResourceAddress,
+            res_address_b: ResourceAddress,
+            swap_fee: Decimal,
+            owner_role: OwnerRole,
+        ) -> (Global<RadiswapPair>, ResourceAddress) {
+            /* CHECK INPUT */
+            assert_fungible_res_address(res_address_a, None);
+            assert_fungible_res_address(res_address_b, None);
+            assert!(
+                res_address_a != res_address_b,
+                "Pair resources must be different"
+            );
+            RadiswapPair::_assert_valid_swap_fee(swap_fee);
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(RadiswapPair::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let lp_res_manager = ResourceBuilder::new_fungible(owner_role.clone())
+                .mint_roles(mint_roles! {
+                    minter => component_rule.clone();
+                    minter_updater => rule!(deny_all);
+                })
+                .burn_roles(burn_roles! {
+                    burner => component_rule;
+                    burner_updater => rule!(deny_all);
+                })
+                .create_with_no_initial_supply();
+
+            let pair_component = Self {
+                vault_a: Vault::new(res_address_a),
+                vault_b: Vault::new(res_address_b),
+                lp_res_manager,
+                locked_liquidity: Vault::new(lp_res_manager.address()),
+                swap_fee,
+                reentrancy_guard: ReentrancyGuard::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (pair_component, lp_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn set_swap_fee(&mut self, swap_fee: Decimal) {
+            RadiswapPair::_assert_valid_swap_fee(swap_fee);
+
+            Runtime::emit_event(SwapFeeUpdatedEvent {
+                previous_fee: self.swap_fee,
+                new_fee: swap_fee,
+            });
+
+            self.swap_fee = swap_fee;
+        }
+
+        /* LIQUIDITY METHODS */
+
+        /// Add liquidity in proportion to the current reserves. The first provider sets the
+        /// pool price and `MINIMUM_LIQUIDITY` of its LP tokens are locked in the pair.
+        /// Returns the minted LP tokens and the unused part of each bucket.
+        pub fn add_liquidity(
+            &mut self,
+            mut bucket_a: Bucket,
+            mut bucket_b: Bucket,
+        ) -> (Bucket, Bucket, Bucket) {
+            self.reentrancy_guard.enter();
+
+            /* CHECK INPUT */
+            if bucket_a.resource_address() == self.vault_b.resource_address() {
+                std::mem::swap(&mut bucket_a, &mut bucket_b);
+            }
+            assert!(
+                bucket_a.resource_address() == self.vault_a.resource_address()
+                    && bucket_b.resource_address() == self.vault_b.resource_address(),
+                "Buckets do not match the pair resources"
+            );
+            assert!(
+                bucket_a.amount() > 0.into() && bucket_b.amount() > 0.into(),
+                "Amounts must be greater than zero!"
+            );
+
+            let (reserve_a, reserve_b) = self.get_reserves();
+            let lp_supply = self.get_lp_supply();
+
+            let (deposit_a, deposit_b, lp_amount) = if lp_supply == 0.into() {
+                let lp_amount = (PreciseDecimal::from(bucket_a.amount()) * bucket_b.amount())
+                    .sqrt()
+                    .and_then(|amount| amount.checked_truncate(RoundingMode::ToZero))
+                    .expect("Invalid initial liquidity");
+
+                assert!(
+                    lp_amount > MINIMUM_LIQUIDITY,
+                    "Initial liquidity is too small"
+                );
+
+                let deposit_a = bucket_a.take(bucket_a.amount());
+                let deposit_b = bucket_b.take(bucket_b.amount());
+
+                (deposit_a, deposit_b, lp_amount)
+            } else {
+                let ratio_a = bucket_a.amount() / reserve_a;
+                let ratio_b = bucket_b.amount() / reserve_b;
+                let mut ratio = ratio_a.min(ratio_b);
+
+                // Amounts are rounded down to the resource divisibility: the ratio is
+                // recomputed from what was actually taken so LP tokens are never over-minted
+                let deposit_a = bucket_a.take_advanced(
+                    reserve_a * ratio,
+                    WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                );
+                ratio = ratio.min(deposit_a.amount() / reserve_a);
+
+                let deposit_b = bucket_b.take_advanced(
+                    reserve_b * ratio,
+                    WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                );
+                ratio = ratio.min(deposit_b.amount() / reserve_b);
+
+                (deposit_a, deposit_b, lp_supply * ratio)
+            };
+
+            assert!(lp_amount > 0.into(), "Liquidity amount is too small");
+
+            let amounts = (deposit_a.amount(), deposit_b.amount());
+            self.vault_a.put(deposit_a);
+            self.vault_b.put(deposit_b);
+
+            let mut lp_tokens = self.lp_res_manager.mint(lp_amount);
+
+            // The first LP tokens are locked forever so the supply can never be
+            // brought back to a dust amount to inflate the value of a share
+            if lp_supply == 0.into() {
+                self.locked_liquidity.put(lp_tokens.take(MINIMUM_LIQUIDITY));
+            }
+
+            Runtime::emit_event(AddLiquidityEvent {
+                amounts,
+                lp_amount,
+                reserves_after: self.get_reserves(),
+            });
+
+            self.reentrancy_guard.exit();
+
+            (lp_tokens, bucket_a, bucket_b)
+        }
+
+        pub fn remove_liquidity(&mut self, lp_tokens: Bucket) -> (Bucket, Bucket) {
+            self.reentrancy_guard.enter();
+
+            /* CHECK INPUT */
+            assert!(
+                lp_tokens.resource_address() == self.lp_res_manager.address(),
+                "LP token resource address mismatch"
+            );
+
+            let lp_amount = lp_tokens.amount();
+            let share = lp_amount / self.get_lp_supply();
+
+            let bucket_a = self.vault_a.take_advanced(
+                self.vault_a.amount() * share,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+            let bucket_b = self.vault_b.take_advanced(
+                self.vault_b.amount() * share,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+
+            lp_tokens.burn();
+
+            Runtime::emit_event(RemoveLiquidityEvent {
+                amounts: (bucket_a.amount(), bucket_b.amount()),
+                lp_amount,
+                reserves_after: self.get_reserves(),
+            });
+
+            self.reentrancy_guard.exit();
+
+            (bucket_a, bucket_b)
+        }
+
+        /* SWAP METHODS */
+
+        pub fn swap(&mut self, input: Bucket) -> Bucket {
+            self.reentrancy_guard.enter();
+
+            let input_res_address = input.resource_address();
+            let input_amount = input.amount();
+
+            let (output_amount, fee_amount) =
+                self._get_output_amount(input_res_address, input_amount);
+
+            assert!(output_amount > 0.into(), "Output amount is too small");
+
+            let (input_vault, output_vault) =
+                if input_res_address == self.vault_a.resource_address() {
+                    (&mut self.vault_a, &mut self.vault_b)
+                } else {
+                    (&mut self.vault_b, &mut self.vault_a)
+                };
+
+            input_vault.put(input);
+            let output = output_vault.take_advanced(
+                output_amount,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+
+            Runtime::emit_event(SwapEvent {
+                input_res_address,
+                input_amount,
+                output_res_address: output.resource_address(),
+                output_amount: output.amount(),
+                fee_amount,
+                reserves_after: self.get_reserves(),
+            });
+
+            self.reentrancy_guard.exit();
+
+            output
+        }
+
+        /* GETTERS */
+
+        pub fn get_reserves(&self) -> (Decimal, Decimal) {
+            (self.vault_a.amount(), self.vault_b.amount())
+        }
+
+        pub fn get_output_amount(
+            &self,
+            input_res_address: ResourceAddress,
+            input_amount: Decimal,
+        ) -> Decimal {
+            self._get_output_amount(input_res_address, input_amount).0
+        }
+
+        pub fn get_lp_supply(&self) -> Decimal {
+            self.lp_res_manager.total_supply().unwrap_or(Decimal::ZERO)
+        }
+
+        pub fn get_swap_fee(&self) -> Decimal {
+            self.swap_fee
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_swap_fee(swap_fee: Decimal) {
+            assert!(
+                swap_fee >= 0.into() && swap_fee < 1.into(),
+                "Swap fee must be between 0 and 1"
+            );
+        }
+
+        /// Output amount and fee amount of a swap, keeping x * y = k on the input after fee
+        fn _get_output_amount(
+            &self,
+            input_res_address: ResourceAddress,
+            input_amount: Decimal,
+        ) -> (Decimal, Decimal) {
+            /* CHECK INPUT */
+            assert!(input_amount > 0.into(), "Amount must be greater than zero!");
+
+            let (input_reserve, output_reserve) =
+                if input_res_address == self.vault_a.resource_address() {
+                    (self.vault_a.amount(), self.vault_b.amount())
+                } else if input_res_address == self.vault_b.resource_address() {
+                    (self.vault_b.amount(), self.vault_a.amount())
+                } else {
+                    panic!("Input resource does not belong to the pair")
+                };
+
+            assert!(
+                input_reserve > 0.into() && output_reserve > 0.into(),
+                "Pair has no liquidity"
+            );
+
+            let fee_amount = input_amount * self.swap_fee;
+            let input_amount_after_fee = input_amount - fee_amount;
+
+            let output_amount = (PreciseDecimal::from(output_reserve) * input_amount_after_fee
+                / (PreciseDecimal::from(input_reserve) + input_amount_after_fee))
+                .checked_truncate(RoundingMode::ToZero)
+                .expect("Invalid output amount");
+
+            (output_amount, fee_amount)
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use radiswap_pair::MINIMUM_LIQUIDITY;
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Pair with a swap fee of 1%, owned by the account holding 10000 of each resource
+struct TestPair {
+    env: TestEnv,
+    account: ComponentAddress,
+    pair: ComponentAddress,
+    lp: ResourceAddress,
+    res_a: ResourceAddress,
+    res_b: ResourceAddress,
+}
+
+impl TestPair {
+    fn new(divisibility_a: u8) -> Self {
+        let mut env = TestEnv::new(this_package!(), 1);
+        let account = env.accounts[0];
+        let res_a = env
+            .test_runner
+            .create_fungible_resource(dec!(10000), divisibility_a, account);
+        let res_b = env
+            .test_runner
+            .create_fungible_resource(dec!(10000), 18, account);
+        let owner_badge = env.badges[0].clone();
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "RadiswapPair",
+                "instantiate",
+                manifest_args!(
+                    res_a,
+                    res_b,
+                    dec!("0.01"),
+                    OwnerRole::Fixed(rule!(require(owner_badge)))
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            pair: commit.new_component_addresses()[0],
+            lp: commit.new_resource_addresses()[0],
+            env,
+            account,
+            res_a,
+            res_b,
+        }
+    }
+
+    /// Execute the manifest with the account key, then deposit the worktop into the account
+    fn execute(&mut self, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute(0, builder)
+    }
+
+    fn add_liquidity(&mut self, amount_a: Decimal, amount_b: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, self.res_a, amount_a)
+            .withdraw_from_account(self.account, self.res_b, amount_b)
+            .take_all_from_worktop(self.res_a, "bucket_a")
+            .take_all_from_worktop(self.res_b, "bucket_b")
+            .call_method_with_name_lookup(self.pair, "add_liquidity", |lookup| {
+                (lookup.bucket("bucket_a"), lookup.bucket("bucket_b"))
+            });
+
+        self.execute(builder)
+    }
+
+    fn remove_liquidity(&mut self, lp_amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, self.lp, lp_amount)
+            .take_all_from_worktop(self.lp, "lp_tokens")
+            .call_method_with_name_lookup(self.pair, "remove_liquidity", |lookup| {
+                (lookup.bucket("lp_tokens"),)
+            });
+
+        self.execute(builder)
+    }
+
+    fn swap(&mut self, res_address: ResourceAddress, amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, res_address, amount)
+            .take_all_from_worktop(res_address, "input")
+            .call_method_with_name_lookup(self.pair, "swap", |lookup| (lookup.bucket("input"),));
+
+        self.execute(builder)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str) -> T {
+        let builder = ManifestBuilder::new().call_method(self.pair, method, manifest_args!());
+
+        self.execute(builder).expect_commit_success().output(0)
+    }
+
+    fn balance(&mut self, res_address: ResourceAddress) -> Decimal {
+        self.env.balance(0, res_address)
+    }
+}
+
+#[test]
+fn test_first_deposit_locks_minimum_liquidity() {
+    let mut pair = TestPair::new(18);
+
+    pair.add_liquidity(dec!(100), dec!(400))
+        .expect_commit_success();
+
+    let lp = pair.lp;
+    assert_eq!(pair.balance(lp), dec!(200) - MINIMUM_LIQUIDITY);
+    assert_eq!(pair.get::<Decimal>("get_lp_supply"), dec!(200));
+    assert_eq!(
+        pair.get::<(Decimal, Decimal)>("get_reserves"),
+        (dec!(100), dec!(400))
+    );
+}
+
+#[test]
+fn test_first_deposit_below_minimum_liquidity_fails() {
+    let mut pair = TestPair::new(18);
+
+    pair.add_liquidity(MINIMUM_LIQUIDITY, MINIMUM_LIQUIDITY)
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_add_liquidity_returns_unused_amounts() {
+    let mut pair = TestPair::new(18);
+
+    pair.add_liquidity(dec!(100), dec!(100))
+        .expect_commit_success();
+    pair.add_liquidity(dec!(10), dec!(20))
+        .expect_commit_success();
+
+    let (lp, res_a, res_b) = (pair.lp, pair.res_a, pair.res_b);
+    assert_eq!(pair.balance(res_a), dec!(9890));
+    assert_eq!(pair.balance(res_b), dec!(9890));
+    assert_eq!(pair.balance(lp), dec!(110) - MINIMUM_LIQUIDITY);
+}
+
+#[test]
+fn test_low_divisibility_deposit_is_not_over_minted() {
+    let mut pair = TestPair::new(0);
+
+    pair.add_liquidity(dec!(10), dec!(10))
+        .expect_commit_success();
+
+    // A quarter of the reserves would be 2.5 of the indivisible resource: only 2 can be
+    // deposited, so the provider gets a fifth of the supply and keeps the unused amounts
+    pair.add_liquidity(dec!(3), dec!("2.5"))
+        .expect_commit_success();
+
+    let (lp, res_a, res_b) = (pair.lp, pair.res_a, pair.res_b);
+    assert_eq!(pair.balance(res_a), dec!(9988));
+    assert_eq!(pair.balance(res_b), dec!(9988));
+    assert_eq!(pair.balance(lp), dec!(12) - MINIMUM_LIQUIDITY);
+    assert_eq!(
+        pair.get::<(Decimal, Decimal)>("get_reserves"),
+        (dec!(12), dec!(12))
+    );
+}
+
+#[test]
+fn test_add_liquidity_with_foreign_resource_fails() {
+    let mut pair = TestPair::new(18);
+    let account = pair.account;
+    let foreign = pair
+        .env
+        .test_runner
+        .create_fungible_resource(dec!(100), 18, account);
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(account, pair.res_a, dec!(10))
+        .withdraw_from_account(account, foreign, dec!(10))
+        .take_all_from_worktop(pair.res_a, "bucket_a")
+        .take_all_from_worktop(foreign, "bucket_b")
+        .call_method_with_name_lookup(pair.pair, "add_liquidity", |lookup| {
+            (lookup.bucket("bucket_a"), lookup.bucket("bucket_b"))
+        });
+
+    pair.execute(builder).expect_commit_failure();
+}
+
+#[test]
+fn test_remove_liquidity() {
+    let mut pair = TestPair::new(18);
+
+    pair.add_liquidity(dec!(100), dec!(100))
+        .expect_commit_success();
+    pair.remove_liquidity(dec!(50)).expect_commit_success();
+
+    let (lp, res_a, res_b) = (pair.lp, pair.res_a, pair.res_b);
+    assert_eq!(pair.balance(res_a), dec!(9950));
+    assert_eq!(pair.balance(res_b), dec!(9950));
+    assert_eq!(pair.balance(lp), dec!(50) - MINIMUM_LIQUIDITY);
+    assert_eq!(pair.get::<Decimal>("get_lp_supply"), dec!(50));
+}
+
+#[test]
+fn test_swap_keeps_the_fee_in_the_pool() {
+    let mut pair = TestPair::new(18);
+
+    pair.add_liquidity(dec!(1000), dec!(1000))
+        .expect_commit_success();
+    pair.swap(pair.res_a, dec!(100)).expect_commit_success();
+
+    // 99 remain after the 1% fee: 1000 * 99 / (1000 + 99)
+    let output_amount = dec!(1000) * dec!(99) / dec!(1099);
+
+    let (res_a, res_b) = (pair.res_a, pair.res_b);
+    assert_eq!(pair.balance(res_a), dec!(8900));
+    assert_eq!(pair.balance(res_b), dec!(9000) + output_amount);
+    assert_eq!(
+        pair.get::<(Decimal, Decimal)>("get_reserves"),
+        (dec!(1100), dec!(1000) - output_amount)
+    );
+}
+
+#[test]
+fn test_swap_without_liquidity_fails() {
+    let mut pair = TestPair::new(18);
+
+    pair.swap(pair.res_a, dec!(100)).expect_commit_failure();
+}
+
+#[test]
+fn test_set_swap_fee() {
+    let mut pair = TestPair::new(18);
+    let pair_address = pair.pair;
+
+    let builder = ManifestBuilder::new().call_method(
+        pair_address,
+        "set_swap_fee",
+        manifest_args!(dec!("0.003")),
+    );
+    pair.execute(builder).expect_commit_success();
+    assert_eq!(pair.get::<Decimal>("get_swap_fee"), dec!("0.003"));
+
+    let builder = ManifestBuilder::new().call_method(
+        pair_address,
+        "set_swap_fee",
+        manifest_args!(dec!("1.1")),
+    );
+    pair.execute(builder).expect_commit_failure();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(pair_address, "set_swap_fee", manifest_args!(dec!("0.5")))
+        .build();
+    pair.env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![])
+        .expect_commit_failure();
+}
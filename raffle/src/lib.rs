@@ -0,0 +1,362 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::{assert_fungible_res_address, draw, mix_entropy, reveal_seed};
+use scrypto::prelude::*;
+
+// RANDOMNESS
+//
+// The winner is drawn with the commit-reveal scheme of `blueprint_commons` (see
+// `randomness.rs`): the operator commits at instantiation, ticket purchases mix the entropy,
+// and the operator reveals the secret once the sale has ended. If the secret is not revealed
+// before the reveal deadline, the raffle is cancelled: ticket holders get refunded and the
+// operator loses the ticket proceeds.
+
+pub use blueprint_commons::commitment;
+
+/// Winning ticket number, between 1 and `tickets_sold`
+pub fn draw_ticket(secret: &[u8], entropy: Hash, tickets_sold: u64) -> u64 {
+    draw(reveal_seed(secret, entropy), tickets_sold) + 1
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct Ticket {
+    pub price: Decimal,
+}
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RaffleState {
+    /// Tickets can be bought until the end epoch
+    Open,
+    /// The secret has been revealed and the winner drawn
+    Drawn { winning_ticket: u64 },
+    /// The secret has not been revealed in time, tickets are refunded
+    Cancelled,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct TicketsBoughtEvent {
+    pub first_ticket: u64,
+    pub count: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct WinnerDrawnEvent {
+    pub winning_ticket: u64,
+    pub seed_entropy: Hash,
+}
+
+#[blueprint]
+#[events(TicketsBoughtEvent, WinnerDrawnEvent)]
+pub mod raffle {
+
+    enable_method_auth! {
+        methods {
+
+            reveal => restrict_to :[OWNER];
+            withdraw_proceeds => restrict_to :[OWNER];
+            reclaim_prize => restrict_to :[OWNER];
+
+            buy_tickets => PUBLIC;
+            cancel => PUBLIC;
+            claim_prize => PUBLIC;
+            refund => PUBLIC;
+
+            get_state => PUBLIC;
+            get_tickets_sold => PUBLIC;
+            get_entropy => PUBLIC;
+
+        }
+    }
+
+    pub struct Raffle {
+        /// Prize paid out to the winning ticket
+        prize: Vault,
+
+        /// Ticket sales
+        proceeds: Vault,
+        ticket_price: Decimal,
+        max_tickets: u64,
+        tickets_sold: u64,
+
+        /// Ticket non-fungible resource manager
+        ticket_res_manager: ResourceManager,
+
+        /// Operator commitment to the secret
+        commitment: Hash,
+
+        /// Public entropy accumulated from ticket purchases
+        entropy: Hash,
+
+        end_epoch: u64,
+
+        /// Last epoch at which the operator can reveal the secret
+        reveal_deadline_epoch: u64,
+
+        state: RaffleState,
+    }
+
+    impl Raffle {
+        /// Instantiate a raffle selling tickets until the end epoch. Returns the raffle
+        /// component, the operator badge and the ticket resource address.
+        pub fn instantiate(
+            prize: Bucket,
+            payment_res_address: ResourceAddress,
+            ticket_price: Decimal,
+            max_tickets: u64,
+            commitment: Hash,
+            end_epoch: u64,
+            reveal_period_epochs: u64,
+        ) -> (Global<Raffle>, Bucket, ResourceAddress) {
+            /* CHECK INPUT */
+            assert!(!prize.is_empty(), "Prize must not be empty");
+            assert_fungible_res_address(
+                payment_res_address,
+                Some("Payment resource must be fungible".to_string()),
+            );
+            assert!(
+                ticket_price > Decimal::ZERO,
+                "Ticket price must be greater than zero"
+            );
+            assert!(max_tickets > 0, "Max tickets must be greater than zero");
+            assert!(
+                end_epoch > Runtime::current_epoch().number(),
+                "End epoch must be in the future"
+            );
+            assert!(
+                reveal_period_epochs > 0,
+                "Reveal period must be greater than zero"
+            );
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(Raffle::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let operator_badge = ResourceBuilder::new_fungible(OwnerRole::None)
+                .divisibility(DIVISIBILITY_NONE)
+                .mint_initial_supply(1);
+
+            let owner_role = OwnerRole::Fixed(rule!(require(operator_badge.resource_address())));
+
+            let ticket_res_manager =
+                ResourceBuilder::new_integer_non_fungible::<Ticket>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule;
+                        burner_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let raffle_component = Self {
+                prize: Vault::with_bucket(prize),
+                proceeds: Vault::new(payment_res_address),
+                ticket_price,
+                max_tickets,
+                tickets_sold: 0,
+                ticket_res_manager,
+                commitment,
+                entropy: commitment,
+                end_epoch,
+                reveal_deadline_epoch: end_epoch + reveal_period_epochs,
+                state: RaffleState::Open,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (
+                raffle_component,
+                operator_badge.into(),
+                ticket_res_manager.address(),
+            )
+        }
+
+        /* OPERATOR METHODS */
+
+        /// Reveal the committed secret once the sale has ended, drawing the winning ticket
+        pub fn reveal(&mut self, secret: Vec<u8>) {
+            let current_epoch = Runtime::current_epoch().number();
+
+            assert!(self.state == RaffleState::Open, "Raffle is not open");
+            assert!(current_epoch > self.end_epoch, "Sale has not ended yet");
+            assert!(
+                current_epoch <= self.reveal_deadline_epoch,
+                "Reveal deadline has passed"
+            );
+            assert!(
+                commitment(&secret) == self.commitment,
+                "Secret does not match the commitment"
+            );
+            assert!(self.tickets_sold > 0, "No ticket has been sold");
+
+            let winning_ticket = draw_ticket(&secret, self.entropy, self.tickets_sold);
+
+            self.state = RaffleState::Drawn { winning_ticket };
+
+            Runtime::emit_event(WinnerDrawnEvent {
+                winning_ticket,
+                seed_entropy: self.entropy,
+            });
+        }
+
+        /// Withdraw the ticket sales once the winner has been drawn
+        pub fn withdraw_proceeds(&mut self) -> Bucket {
+            assert!(
+                matches!(self.state, RaffleState::Drawn { .. }),
+                "Winner has not been drawn"
+            );
+
+            self.proceeds.take_all()
+        }
+
+        /// Reclaim the prize if the raffle has been cancelled or no ticket has been sold
+        pub fn reclaim_prize(&mut self) -> Bucket {
+            assert!(
+                self.state == RaffleState::Cancelled
+                    || (self.tickets_sold == 0
+                        && Runtime::current_epoch().number() > self.end_epoch),
+                "Prize cannot be reclaimed"
+            );
+
+            self.prize.take_all()
+        }
+
+        /* PARTICIPANT METHODS */
+
+        /// Buy the given number of tickets. Returns the tickets and the change.
+        pub fn buy_tickets(&mut self, mut payment: Bucket, count: u64) -> (Bucket, Bucket) {
+            /* CHECK INPUT */
+            assert!(self.state == RaffleState::Open, "Raffle is not open");
+            assert!(
+                Runtime::current_epoch().number() <= self.end_epoch,
+                "Sale has ended"
+            );
+            assert!(
+                payment.resource_address() == self.proceeds.resource_address(),
+                "Payment resource address mismatch"
+            );
+
+            assert!(count > 0, "Ticket count must be greater than zero");
+            assert!(
+                count <= self.max_tickets - self.tickets_sold,
+                "Not enough tickets left"
+            );
+
+            self.proceeds
+                .put(payment.take(self.ticket_price * Decimal::from(count)));
+
+            let first_ticket = self.tickets_sold + 1;
+            let mut tickets = Bucket::new(self.ticket_res_manager.address());
+
+            for ticket_number in first_ticket..first_ticket + count {
+                tickets.put(self.ticket_res_manager.mint_non_fungible(
+                    &NonFungibleLocalId::integer(ticket_number),
+                    Ticket {
+                        price: self.ticket_price,
+                    },
+                ));
+            }
+
+            self.tickets_sold += count;
+            self.entropy = mix_entropy(self.entropy);
+
+            Runtime::emit_event(TicketsBoughtEvent {
+                first_ticket,
+                count,
+            });
+
+            (tickets, payment)
+        }
+
+        /// Cancel the raffle if the operator has not revealed the secret before the deadline
+        pub fn cancel(&mut self) {
+            assert!(self.state == RaffleState::Open, "Raffle is not open");
+            assert!(
+                Runtime::current_epoch().number() > self.reveal_deadline_epoch,
+                "Reveal deadline has not passed yet"
+            );
+
+            self.state = RaffleState::Cancelled;
+        }
+
+        /// Claim the prize with the winning ticket
+        pub fn claim_prize(&mut self, ticket_proof: Proof) -> Bucket {
+            let ticket_id = ticket_proof
+                .check(self.ticket_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id();
+
+            match self.state {
+                RaffleState::Drawn { winning_ticket } => assert!(
+                    ticket_id == NonFungibleLocalId::integer(winning_ticket),
+                    "Ticket is not the winning ticket"
+                ),
+                _ => panic!("Winner has not been drawn"),
+            }
+
+            self.prize.take_all()
+        }
+
+        /// Burn tickets of a cancelled raffle in exchange for their price
+        pub fn refund(&mut self, tickets: Bucket) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                tickets.resource_address() == self.ticket_res_manager.address(),
+                "Ticket resource address mismatch"
+            );
+            assert!(
+                self.state == RaffleState::Cancelled,
+                "Raffle has not been cancelled"
+            );
+
+            let refund_amount = tickets
+                .as_non_fungible()
+                .non_fungibles::<Ticket>()
+                .iter()
+                .fold(Decimal::ZERO, |total, ticket| total + ticket.data().price);
+
+            tickets.burn();
+
+            self.proceeds.take(refund_amount)
+        }
+
+        /* GETTERS */
+
+        pub fn get_state(&self) -> RaffleState {
+            self.state
+        }
+
+        pub fn get_tickets_sold(&self) -> u64 {
+            self.tickets_sold
+        }
+
+        pub fn get_entropy(&self) -> Hash {
+            self.entropy
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use raffle::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+const SECRET: &[u8] = b"raffle secret";
+
+#[test]
+fn test_draw_ticket_is_deterministic_and_in_range() {
+    let entropy = hash("entropy");
+
+    for tickets_sold in [1, 2, 5, 1000] {
+        let ticket = draw_ticket(SECRET, entropy, tickets_sold);
+
+        assert!(ticket >= 1 && ticket <= tickets_sold);
+        assert_eq!(draw_ticket(SECRET, entropy, tickets_sold), ticket);
+    }
+
+    assert_eq!(draw_ticket(SECRET, entropy, 1), 1);
+}
+
+#[test]
+fn test_draw_ticket_depends_on_the_secret_and_the_entropy() {
+    let entropy = hash("entropy");
+    let draws: IndexSet<u64> = (0..20u8)
+        .map(|i| draw_ticket(&[SECRET, &[i]].concat(), entropy, 1000))
+        .collect();
+    assert!(draws.len() > 1);
+
+    let draws: IndexSet<u64> = (0..20u8)
+        .map(|i| draw_ticket(SECRET, hash([i]), 1000))
+        .collect();
+    assert!(draws.len() > 1);
+}
+
+/// Holds the prize tokens and the operator badge
+const OPERATOR: usize = 0;
+/// Participants holding 1000 payment tokens each
+const ALICE: usize = 1;
+const BOB: usize = 2;
+
+/// Raffle of 100 prize tokens selling at most 5 tickets at a price of 10 until epoch 20, with
+/// a reveal period of 5 epochs
+struct TestRaffle {
+    env: TestEnv,
+    raffle: ComponentAddress,
+    operator_badge: ResourceAddress,
+    ticket: ResourceAddress,
+    prize: ResourceAddress,
+    payment: ResourceAddress,
+}
+
+impl TestRaffle {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let prize = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[OPERATOR]);
+        let payment = env
+            .test_runner
+            .create_fungible_resource(dec!(2000), 18, env.accounts[ALICE]);
+
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account(env.accounts[OPERATOR], prize, dec!(100))
+            .take_all_from_worktop(prize, "prize")
+            .call_function_with_name_lookup(
+                env.package_address,
+                "Raffle",
+                "instantiate",
+                |lookup| {
+                    (
+                        lookup.bucket("prize"),
+                        payment,
+                        dec!(10),
+                        5u64,
+                        commitment(SECRET),
+                        20u64,
+                        5u64,
+                    )
+                },
+            )
+            .withdraw_from_account(env.accounts[ALICE], payment, dec!(1000))
+            .take_all_from_worktop(payment, "bob")
+            .call_method_with_name_lookup(env.accounts[BOB], "deposit", |lookup| {
+                (lookup.bucket("bob"),)
+            })
+            .deposit_batch(env.accounts[OPERATOR])
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            raffle: commit.new_component_addresses()[0],
+            operator_badge: commit.new_resource_addresses()[0],
+            ticket: commit.new_resource_addresses()[1],
+            env,
+            prize,
+            payment,
+        }
+    }
+
+    /// Execute the manifest with the keys of every account, then deposit the worktop into the
+    /// given account
+    fn execute(&mut self, account: usize, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute_with_all_keys(account, builder)
+    }
+
+    fn buy_tickets(&mut self, buyer: usize, amount: Decimal, count: u64) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[buyer], self.payment, amount)
+            .take_all_from_worktop(self.payment, "payment")
+            .call_method_with_name_lookup(self.raffle, "buy_tickets", |lookup| {
+                (lookup.bucket("payment"), count)
+            });
+
+        self.execute(buyer, builder)
+    }
+
+    /// Call an operator method with a proof of the operator badge
+    fn call_as_operator(
+        &mut self,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(
+                self.env.accounts[OPERATOR],
+                self.operator_badge,
+                dec!(1),
+            )
+            .call_method(self.raffle, method, args);
+
+        self.execute(OPERATOR, builder)
+    }
+
+    fn reveal(&mut self, secret: &[u8]) -> TransactionReceipt {
+        self.call_as_operator("reveal", manifest_args!(secret.to_vec()))
+    }
+
+    fn claim_prize(&mut self, holder: usize, ticket_number: u64) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_non_fungibles(
+                self.env.accounts[holder],
+                self.ticket,
+                &BTreeSet::from([NonFungibleLocalId::integer(ticket_number)]),
+            )
+            .pop_from_auth_zone("ticket")
+            .call_method_with_name_lookup(self.raffle, "claim_prize", |lookup| {
+                (lookup.proof("ticket"),)
+            });
+
+        self.execute(holder, builder)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str) -> T {
+        let builder = ManifestBuilder::new().call_method(self.raffle, method, manifest_args!());
+
+        self.execute(OPERATOR, builder)
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_winning_ticket_claims_the_prize() {
+    let mut raffle = TestRaffle::new();
+    let (prize, payment, ticket) = (raffle.prize, raffle.payment, raffle.ticket);
+
+    raffle
+        .buy_tickets(ALICE, dec!(35), 3)
+        .expect_commit_success();
+    raffle.buy_tickets(BOB, dec!(20), 2).expect_commit_success();
+    raffle.buy_tickets(BOB, dec!(10), 1).expect_commit_failure();
+
+    assert_eq!(raffle.env.balance(ALICE, payment), dec!(970));
+    assert_eq!(raffle.env.balance(ALICE, ticket), dec!(3));
+    assert_eq!(raffle.get::<u64>("get_tickets_sold"), 5);
+
+    raffle.reveal(SECRET).expect_commit_failure();
+
+    raffle.env.set_epoch(21);
+    raffle.reveal(b"wrong secret").expect_commit_failure();
+    raffle
+        .call_as_operator("withdraw_proceeds", manifest_args!())
+        .expect_commit_failure();
+
+    let entropy: Hash = raffle.get("get_entropy");
+    raffle.reveal(SECRET).expect_commit_success();
+
+    let winning_ticket = draw_ticket(SECRET, entropy, 5);
+    assert_eq!(
+        raffle.get::<RaffleState>("get_state"),
+        RaffleState::Drawn { winning_ticket }
+    );
+
+    let (winner, loser, losing_ticket) = if winning_ticket <= 3 {
+        (ALICE, BOB, 4)
+    } else {
+        (BOB, ALICE, 1)
+    };
+    raffle
+        .claim_prize(loser, losing_ticket)
+        .expect_commit_failure();
+    raffle
+        .claim_prize(winner, winning_ticket)
+        .expect_commit_success();
+    assert_eq!(raffle.env.balance(winner, prize), dec!(100));
+
+    raffle
+        .call_as_operator("withdraw_proceeds", manifest_args!())
+        .expect_commit_success();
+    assert_eq!(raffle.env.balance(OPERATOR, payment), dec!(50));
+}
+
+#[test]
+fn test_reveal_requires_the_operator_badge() {
+    let mut raffle = TestRaffle::new();
+    let raffle_address = raffle.raffle;
+
+    raffle
+        .buy_tickets(ALICE, dec!(10), 1)
+        .expect_commit_success();
+    raffle.env.set_epoch(21);
+
+    let builder = ManifestBuilder::new().call_method(
+        raffle_address,
+        "reveal",
+        manifest_args!(SECRET.to_vec()),
+    );
+    raffle.execute(ALICE, builder).expect_commit_failure();
+}
+
+#[test]
+fn test_raffle_is_cancelled_without_reveal() {
+    let mut raffle = TestRaffle::new();
+    let (prize, payment, ticket, raffle_address) =
+        (raffle.prize, raffle.payment, raffle.ticket, raffle.raffle);
+
+    raffle
+        .buy_tickets(ALICE, dec!(20), 2)
+        .expect_commit_success();
+    raffle
+        .call_as_operator("reclaim_prize", manifest_args!())
+        .expect_commit_failure();
+
+    let cancel = || ManifestBuilder::new().call_method(raffle_address, "cancel", manifest_args!());
+    raffle.env.set_epoch(25);
+    raffle.execute(BOB, cancel()).expect_commit_failure();
+
+    raffle.env.set_epoch(26);
+    raffle.execute(BOB, cancel()).expect_commit_success();
+    assert_eq!(
+        raffle.get::<RaffleState>("get_state"),
+        RaffleState::Cancelled
+    );
+    raffle.reveal(SECRET).expect_commit_failure();
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(raffle.env.accounts[ALICE], ticket, dec!(2))
+        .take_all_from_worktop(ticket, "tickets")
+        .call_method_with_name_lookup(raffle_address, "refund", |lookup| {
+            (lookup.bucket("tickets"),)
+        });
+    raffle.execute(ALICE, builder).expect_commit_success();
+    assert_eq!(raffle.env.balance(ALICE, payment), dec!(1000));
+
+    raffle
+        .call_as_operator("reclaim_prize", manifest_args!())
+        .expect_commit_success();
+    assert_eq!(raffle.env.balance(OPERATOR, prize), dec!(100));
+}
+
+#[test]
+fn test_prize_is_reclaimed_when_no_ticket_is_sold() {
+    let mut raffle = TestRaffle::new();
+    let prize = raffle.prize;
+
+    raffle.env.set_epoch(21);
+    raffle.reveal(SECRET).expect_commit_failure();
+    raffle
+        .call_as_operator("reclaim_prize", manifest_args!())
+        .expect_commit_success();
+
+    assert_eq!(raffle.env.balance(OPERATOR, prize), dec!(100));
+}
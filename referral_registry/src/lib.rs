@@ -0,0 +1,310 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use radiswap_pair::radiswap_pair::RadiswapPair;
+use scrypto::prelude::*;
+
+/// Maximum fee taken on wrapped calls
+pub const MAX_FEE_RATE: Decimal = dec!("0.01");
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct ReferrerBadge {
+    pub code: String,
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct Referrer {
+    pub badge_id: NonFungibleLocalId,
+
+    /// Share of the fees paid to the referrer, overriding the default share when set
+    pub share_override: Option<Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ReferralFeeEvent {
+    pub code: String,
+    pub res_address: ResourceAddress,
+    pub fee_amount: Decimal,
+    pub referrer_amount: Decimal,
+}
+
+#[blueprint]
+#[events(ReferralFeeEvent)]
+pub mod referral_registry {
+
+    enable_method_auth! {
+        methods {
+
+            set_fee_rate => restrict_to :[OWNER];
+            set_default_share => restrict_to :[OWNER];
+            set_share_override => restrict_to :[OWNER];
+            withdraw_protocol_fees => restrict_to :[OWNER];
+
+            register_code => PUBLIC;
+            claim => PUBLIC;
+
+            collect_fees => PUBLIC;
+            swap => PUBLIC;
+
+            get_referrer => PUBLIC;
+            get_referrer_share => PUBLIC;
+            get_claimable => PUBLIC;
+
+        }
+    }
+
+    pub struct ReferralRegistry {
+        referrers: KeyValueStore<String, Referrer>,
+
+        /// Referrer badge non-fungible resource manager
+        badge_res_manager: ResourceManager,
+
+        /// Fees owed to each referrer, indexed by code and resource
+        referrer_fees: KeyValueStore<(String, ResourceAddress), Vault>,
+
+        /// Fees kept by the protocol, indexed by resource
+        protocol_fees: KeyValueStore<ResourceAddress, Vault>,
+
+        /// Fee taken on the input of wrapped calls
+        fee_rate: Decimal,
+
+        /// Share of the fees paid to the referrer
+        default_share: Decimal,
+    }
+
+    impl ReferralRegistry {
+        pub fn instantiate(
+            fee_rate: Decimal,
+            default_share: Decimal,
+            owner_role: OwnerRole,
+        ) -> (Global<ReferralRegistry>, ResourceAddress) {
+            /* CHECK INPUT */
+            ReferralRegistry::_assert_valid_fee_rate(fee_rate);
+            ReferralRegistry::_assert_valid_share(default_share);
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(ReferralRegistry::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let badge_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<ReferrerBadge>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule;
+                        minter_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let registry_component = Self {
+                referrers: KeyValueStore::new(),
+                badge_res_manager,
+                referrer_fees: KeyValueStore::new(),
+                protocol_fees: KeyValueStore::new(),
+                fee_rate,
+                default_share,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (registry_component, badge_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn set_fee_rate(&mut self, fee_rate: Decimal) {
+            ReferralRegistry::_assert_valid_fee_rate(fee_rate);
+
+            self.fee_rate = fee_rate;
+        }
+
+        pub fn set_default_share(&mut self, default_share: Decimal) {
+            ReferralRegistry::_assert_valid_share(default_share);
+
+            self.default_share = default_share;
+        }
+
+        pub fn set_share_override(&mut self, code: String, share_override: Option<Decimal>) {
+            if let Some(share) = share_override {
+                ReferralRegistry::_assert_valid_share(share);
+            }
+
+            self.referrers
+                .get_mut(&code)
+                .expect("Referral code not found")
+                .share_override = share_override;
+        }
+
+        pub fn withdraw_protocol_fees(&mut self, res_address: ResourceAddress) -> Bucket {
+            self.protocol_fees
+                .get_mut(&res_address)
+                .expect("No fee for this resource")
+                .take_all()
+        }
+
+        /* REFERRER METHODS */
+
+        /// Register a new referral code. Returns the referrer badge, required to claim the
+        /// fees.
+        pub fn register_code(&mut self, code: String) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                !code.is_empty() && code.len() <= 32,
+                "Code must be between 1 and 32 characters"
+            );
+            assert!(
+                self.referrers.get(&code).is_none(),
+                "Referral code already registered"
+            );
+
+            let badge = self
+                .badge_res_manager
+                .mint_ruid_non_fungible(ReferrerBadge { code: code.clone() });
+
+            self.referrers.insert(
+                code,
+                Referrer {
+                    badge_id: badge.as_non_fungible().non_fungible_local_id(),
+                    share_override: None,
+                },
+            );
+
+            badge
+        }
+
+        pub fn claim(&mut self, badge_proof: Proof, res_address: ResourceAddress) -> Bucket {
+            let badge = badge_proof
+                .check(self.badge_res_manager.address())
+                .as_non_fungible()
+                .non_fungible::<ReferrerBadge>();
+
+            self.referrer_fees
+                .get_mut(&(badge.data().code, res_address))
+                .expect("No fee for this resource")
+                .take_all()
+        }
+
+        /* INTEGRATION METHODS */
+
+        /// Split fees collected by an integrating component between the referrer of the code,
+        /// if any, and the protocol
+        pub fn collect_fees(&mut self, mut fees: Bucket, code: Option<String>) {
+            let res_address = fees.resource_address();
+            let fee_amount = fees.amount();
+
+            let referrer = code.and_then(|code| {
+                self.referrers
+                    .get(&code)
+                    .map(|referrer| (code, referrer.share_override))
+            });
+
+            if let Some((code, share_override)) = referrer {
+                let share = share_override.unwrap_or(self.default_share);
+                let referrer_fees = fees.take_advanced(
+                    fee_amount * share,
+                    WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                );
+
+                Runtime::emit_event(ReferralFeeEvent {
+                    code: code.clone(),
+                    res_address,
+                    fee_amount,
+                    referrer_amount: referrer_fees.amount(),
+                });
+
+                let key = (code, res_address);
+                let vault_exists = self.referrer_fees.get(&key).is_some();
+
+                if vault_exists {
+                    self.referrer_fees.get_mut(&key).unwrap().put(referrer_fees);
+                } else {
+                    self.referrer_fees
+                        .insert(key, Vault::with_bucket(referrer_fees));
+                }
+            }
+
+            let vault_exists = self.protocol_fees.get(&res_address).is_some();
+
+            if vault_exists {
+                self.protocol_fees.get_mut(&res_address).unwrap().put(fees);
+            } else {
+                self.protocol_fees
+                    .insert(res_address, Vault::with_bucket(fees));
+            }
+        }
+
+        /// Swap through a RadiswapPair, taking the fee on the input
+        pub fn swap(
+            &mut self,
+            mut pair: Global<RadiswapPair>,
+            mut input: Bucket,
+            code: Option<String>,
+        ) -> Bucket {
+            let fees = input.take_advanced(
+                input.amount() * self.fee_rate,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+
+            self.collect_fees(fees, code);
+
+            pair.swap(input)
+        }
+
+        /* GETTERS */
+
+        pub fn get_referrer(&self, code: String) -> Option<Referrer> {
+            self.referrers.get(&code).map(|referrer| referrer.clone())
+        }
+
+        pub fn get_referrer_share(&self, code: String) -> Decimal {
+            self.get_referrer(code)
+                .and_then(|referrer| referrer.share_override)
+                .unwrap_or(self.default_share)
+        }
+
+        pub fn get_claimable(&self, code: String, res_address: ResourceAddress) -> Decimal {
+            self.referrer_fees
+                .get(&(code, res_address))
+                .map(|vault| vault.amount())
+                .unwrap_or(Decimal::ZERO)
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_fee_rate(fee_rate: Decimal) {
+            assert!(
+                fee_rate >= Decimal::ZERO && fee_rate <= MAX_FEE_RATE,
+                "Fee rate must be between 0 and {}",
+                MAX_FEE_RATE
+            );
+        }
+
+        fn _assert_valid_share(share: Decimal) {
+            assert!(
+                share >= Decimal::ZERO && share <= Decimal::ONE,
+                "Share must be between 0 and 1"
+            );
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Provides the pair liquidity
+const OWNER: usize = 0;
+/// Holds the referrer badge of the "alice" code
+const REFERRER: usize = 1;
+/// Holds 1000 A tokens
+const TRADER: usize = 2;
+
+/// Registry with a fee rate of 1% and a default referrer share of 50%, wrapping swaps on a
+/// Radiswap pair holding 1000 A and 1000 B. The pair blueprint is linked into the registry
+/// package, so it is instantiated from the same package.
+struct TestRegistry {
+    env: TestEnv,
+    registry: ComponentAddress,
+    referrer_badge: ResourceAddress,
+    pair: ComponentAddress,
+    res_a: ResourceAddress,
+    res_b: ResourceAddress,
+}
+
+impl TestRegistry {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        let res_a = env
+            .test_runner
+            .create_fungible_resource(dec!(2000), 18, env.accounts[OWNER]);
+        let res_b = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "ReferralRegistry",
+                "instantiate",
+                manifest_args!(dec!("0.01"), dec!("0.5"), owner_role.clone()),
+            )
+            .call_function(
+                env.package_address,
+                "RadiswapPair",
+                "instantiate",
+                manifest_args!(res_a, res_b, dec!(0), owner_role),
+            )
+            .withdraw_from_account(env.accounts[OWNER], res_a, dec!(1000))
+            .take_all_from_worktop(res_a, "trader")
+            .call_method_with_name_lookup(env.accounts[TRADER], "try_deposit_or_abort", |lookup| {
+                (lookup.bucket("trader"), None::<ResourceOrNonFungible>)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()]);
+        let commit = receipt.expect_commit_success();
+
+        let mut registry = Self {
+            registry: commit.new_component_addresses()[0],
+            referrer_badge: commit.new_resource_addresses()[0],
+            pair: commit.new_component_addresses()[1],
+            env,
+            res_a,
+            res_b,
+        };
+
+        let (owner, pair) = (registry.env.accounts[OWNER], registry.pair);
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(owner, res_a, dec!(1000))
+            .withdraw_from_account(owner, res_b, dec!(1000))
+            .take_all_from_worktop(res_a, "bucket_a")
+            .take_all_from_worktop(res_b, "bucket_b")
+            .call_method_with_name_lookup(pair, "add_liquidity", |lookup| {
+                (lookup.bucket("bucket_a"), lookup.bucket("bucket_b"))
+            });
+        registry.env.execute(OWNER, builder).expect_commit_success();
+
+        registry
+            .call(
+                REFERRER,
+                "register_code",
+                manifest_args!("alice".to_string()),
+            )
+            .expect_commit_success();
+
+        registry
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.registry, method, args)
+    }
+
+    /// Swap 100 A for B through the registry
+    fn swap(&mut self, code: Option<&str>) -> TransactionReceipt {
+        let (pair, res_a) = (self.pair, self.res_a);
+        let code = code.map(|code| code.to_string());
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[TRADER], res_a, dec!(100))
+            .take_all_from_worktop(res_a, "input")
+            .call_method_with_name_lookup(self.registry, "swap", |lookup| {
+                (pair, lookup.bucket("input"), code)
+            });
+
+        self.env.execute(TRADER, builder)
+    }
+
+    fn claim(&mut self, res_address: ResourceAddress) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(
+                self.env.accounts[REFERRER],
+                self.referrer_badge,
+                dec!(1),
+            )
+            .pop_from_auth_zone("badge")
+            .call_method_with_name_lookup(self.registry, "claim", |lookup| {
+                (lookup.proof("badge"), res_address)
+            });
+
+        self.env.execute(REFERRER, builder)
+    }
+
+    fn get_claimable(&mut self) -> Decimal {
+        let res_a = self.res_a;
+
+        self.call(
+            TRADER,
+            "get_claimable",
+            manifest_args!("alice".to_string(), res_a),
+        )
+        .expect_commit_success()
+        .output(0)
+    }
+}
+
+#[test]
+fn test_swap_fee_is_split_with_the_referrer() {
+    let mut registry = TestRegistry::new();
+    let (pair, res_a, res_b) = (registry.pair, registry.res_a, registry.res_b);
+
+    registry.swap(Some("alice")).expect_commit_success();
+    assert_eq!(registry.env.balance(TRADER, res_a), dec!(900));
+    assert!(registry.env.balance(TRADER, res_b) > dec!(0));
+
+    let builder = ManifestBuilder::new().call_method(pair, "get_reserves", manifest_args!());
+    let (reserve_a, _): (Decimal, Decimal) = registry
+        .env
+        .execute(TRADER, builder)
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(reserve_a, dec!(1099));
+
+    assert_eq!(registry.get_claimable(), dec!("0.5"));
+    registry.claim(res_a).expect_commit_success();
+    assert_eq!(registry.env.balance(REFERRER, res_a), dec!("0.5"));
+    registry.claim(res_b).expect_commit_failure();
+
+    // Unknown codes are ignored, the protocol keeps the whole fee
+    registry.swap(Some("bob")).expect_commit_success();
+    registry.swap(None).expect_commit_success();
+    assert_eq!(registry.get_claimable(), dec!(0));
+
+    registry
+        .call(REFERRER, "withdraw_protocol_fees", manifest_args!(res_a))
+        .expect_commit_failure();
+    registry
+        .call(OWNER, "withdraw_protocol_fees", manifest_args!(res_a))
+        .expect_commit_success();
+    assert_eq!(registry.env.balance(OWNER, res_a), dec!("2.5"));
+}
+
+#[test]
+fn test_register_code_rejects_invalid_codes() {
+    let mut registry = TestRegistry::new();
+
+    for code in ["alice", "", "a_code_that_is_longer_than_32_chars"] {
+        registry
+            .call(TRADER, "register_code", manifest_args!(code.to_string()))
+            .expect_commit_failure();
+    }
+
+    registry
+        .call(TRADER, "register_code", manifest_args!("bob".to_string()))
+        .expect_commit_success();
+}
+
+#[test]
+fn test_share_override_requires_the_owner() {
+    let mut registry = TestRegistry::new();
+
+    registry
+        .call(
+            REFERRER,
+            "set_share_override",
+            manifest_args!("alice".to_string(), Some(dec!(1))),
+        )
+        .expect_commit_failure();
+    registry
+        .call(
+            OWNER,
+            "set_share_override",
+            manifest_args!("alice".to_string(), Some(dec!("1.1"))),
+        )
+        .expect_commit_failure();
+    registry
+        .call(
+            OWNER,
+            "set_share_override",
+            manifest_args!("bob".to_string(), Some(dec!(1))),
+        )
+        .expect_commit_failure();
+    registry
+        .call(
+            OWNER,
+            "set_share_override",
+            manifest_args!("alice".to_string(), Some(dec!(1))),
+        )
+        .expect_commit_success();
+
+    registry.swap(Some("alice")).expect_commit_success();
+    assert_eq!(registry.get_claimable(), dec!(1));
+
+    registry
+        .call(OWNER, "set_fee_rate", manifest_args!(dec!("0.02")))
+        .expect_commit_failure();
+    registry
+        .call(OWNER, "set_fee_rate", manifest_args!(dec!(0)))
+        .expect_commit_success();
+
+    registry.swap(Some("alice")).expect_commit_success();
+    assert_eq!(registry.get_claimable(), dec!(1));
+}
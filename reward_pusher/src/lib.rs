@@ -0,0 +1,278 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+
+// The native AccountLocker is only available from Scrypto v1.2.0, which this package
+// depends on unlike the rest of the repository. It does not use blueprint_commons, which is
+// built on Scrypto v1.0.0, so that a single Scrypto version is linked into the package.
+
+/// Maximum number of recipients added or pushed in a single call
+pub const MAX_BATCH_SIZE: u64 = 100;
+
+#[derive(ScryptoSbor)]
+pub struct Distribution {
+    /// Rewards not pushed yet
+    funds: Vault,
+
+    /// Sum of the amounts of the recipients not pushed yet
+    pending_amount: Decimal,
+
+    recipient_count: u64,
+
+    /// Index of the next recipient to push, so that pushes resume where they stopped
+    next_index: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct DistributionPushedEvent {
+    pub distribution_id: u64,
+    pub first_index: u64,
+    pub count: u64,
+}
+
+#[blueprint]
+#[events(DistributionPushedEvent)]
+pub mod reward_pusher {
+
+    enable_method_auth! {
+        roles {
+            distributor => updatable_by: [OWNER];
+        },
+        methods {
+
+            create_distribution => restrict_to :[distributor, OWNER];
+            fund_distribution => restrict_to :[distributor, OWNER];
+            add_recipients => restrict_to :[distributor, OWNER];
+            push => restrict_to :[distributor, OWNER];
+            withdraw_surplus => restrict_to :[distributor, OWNER];
+
+            get_progress => PUBLIC;
+            get_locker => PUBLIC;
+
+        }
+    }
+
+    pub struct RewardPusher {
+        /// Locker holding rewards that could not be deposited directly, until claimed
+        locker: Global<AccountLocker>,
+
+        distributions: KeyValueStore<u64, Distribution>,
+        next_distribution_id: u64,
+
+        /// Recipient accounts and amounts, indexed by distribution and recipient index
+        recipients: KeyValueStore<(u64, u64), (ComponentAddress, Decimal)>,
+    }
+
+    impl RewardPusher {
+        pub fn instantiate(
+            owner_role: OwnerRole,
+            distributor_rule: AccessRule,
+        ) -> Global<RewardPusher> {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(RewardPusher::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let locker = Blueprint::<AccountLocker>::instantiate(
+                owner_role.clone(),
+                component_rule.clone(),
+                rule!(deny_all),
+                component_rule,
+                rule!(deny_all),
+                None,
+            );
+
+            Self {
+                locker,
+                distributions: KeyValueStore::new(),
+                next_distribution_id: 0,
+                recipients: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                distributor => distributor_rule;
+            ))
+            .with_address(address_reservation)
+            .globalize()
+        }
+
+        /* DISTRIBUTOR METHODS */
+
+        /// Create a distribution of fungible rewards. Returns the distribution id.
+        pub fn create_distribution(&mut self, funds: Bucket) -> u64 {
+            /* CHECK INPUT */
+            assert!(
+                ResourceManager::from_address(funds.resource_address())
+                    .resource_type()
+                    .is_fungible(),
+                "Rewards must be fungible"
+            );
+
+            let distribution_id = self.next_distribution_id;
+            self.next_distribution_id += 1;
+
+            self.distributions.insert(
+                distribution_id,
+                Distribution {
+                    funds: Vault::with_bucket(funds),
+                    pending_amount: Decimal::ZERO,
+                    recipient_count: 0,
+                    next_index: 0,
+                },
+            );
+
+            distribution_id
+        }
+
+        pub fn fund_distribution(&mut self, distribution_id: u64, funds: Bucket) {
+            self._get_distribution_mut(distribution_id).funds.put(funds);
+        }
+
+        /// Add a batch of recipients. The distribution funds must cover every recipient not
+        /// pushed yet.
+        pub fn add_recipients(
+            &mut self,
+            distribution_id: u64,
+            recipients: Vec<(ComponentAddress, Decimal)>,
+        ) {
+            /* CHECK INPUT */
+            assert!(
+                recipients.len() as u64 <= MAX_BATCH_SIZE,
+                "Too many recipients for a single batch"
+            );
+            assert!(
+                recipients.iter().all(|(_, amount)| *amount > Decimal::ZERO),
+                "Amounts must be greater than zero"
+            );
+
+            let mut distribution = self
+                .distributions
+                .get_mut(&distribution_id)
+                .expect("Distribution not found");
+
+            for recipient in recipients {
+                distribution.pending_amount += recipient.1;
+
+                self.recipients
+                    .insert((distribution_id, distribution.recipient_count), recipient);
+                distribution.recipient_count += 1;
+            }
+
+            assert!(
+                distribution.pending_amount <= distribution.funds.amount(),
+                "Not enough funds for the recipients"
+            );
+        }
+
+        /// Push rewards to the next recipients of a distribution. Rewards are deposited
+        /// directly when the recipient deposit rules allow it, and stored in the locker for
+        /// the recipient to claim otherwise.
+        pub fn push(&mut self, distribution_id: u64, batch_size: u64) {
+            /* CHECK INPUT */
+            assert!(
+                batch_size > 0 && batch_size <= MAX_BATCH_SIZE,
+                "Batch size must be between 1 and the max batch size"
+            );
+
+            let mut distribution = self
+                .distributions
+                .get_mut(&distribution_id)
+                .expect("Distribution not found");
+
+            let first_index = distribution.next_index;
+            let end_index = (first_index + batch_size).min(distribution.recipient_count);
+
+            assert!(first_index < end_index, "Every recipient has been pushed");
+
+            let mut claimants: IndexMap<Global<Account>, ResourceSpecifier> = IndexMap::new();
+            let mut batch_amount = Decimal::ZERO;
+
+            for index in first_index..end_index {
+                let (account, amount) = *self.recipients.get(&(distribution_id, index)).unwrap();
+                let claimant = Global::<Account>::from(account);
+
+                // Recipients listed several times in a batch receive the sum of their amounts
+                let total_amount = match claimants.get(&claimant) {
+                    Some(ResourceSpecifier::Fungible(previous_amount)) => *previous_amount + amount,
+                    _ => amount,
+                };
+
+                claimants.insert(claimant, ResourceSpecifier::Fungible(total_amount));
+                batch_amount += amount;
+            }
+
+            let rewards = distribution.funds.take(batch_amount);
+
+            if let Some(change) = self.locker.airdrop(claimants, rewards, true) {
+                distribution.funds.put(change);
+            }
+
+            distribution.pending_amount -= batch_amount;
+            distribution.next_index = end_index;
+
+            Runtime::emit_event(DistributionPushedEvent {
+                distribution_id,
+                first_index,
+                count: end_index - first_index,
+            });
+        }
+
+        /// Withdraw funds not needed by the recipients not pushed yet
+        pub fn withdraw_surplus(&mut self, distribution_id: u64) -> Bucket {
+            let mut distribution = self._get_distribution_mut(distribution_id);
+
+            let surplus = distribution.funds.amount() - distribution.pending_amount;
+
+            distribution.funds.take(surplus)
+        }
+
+        /* GETTERS */
+
+        /// Number of recipients pushed and number of recipients added
+        pub fn get_progress(&self, distribution_id: u64) -> (u64, u64) {
+            let distribution = self
+                .distributions
+                .get(&distribution_id)
+                .expect("Distribution not found");
+
+            (distribution.next_index, distribution.recipient_count)
+        }
+
+        pub fn get_locker(&self) -> ComponentAddress {
+            self.locker.address()
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _get_distribution_mut(
+            &mut self,
+            distribution_id: u64,
+        ) -> KeyValueEntryRefMut<Distribution> {
+            self.distributions
+                .get_mut(&distribution_id)
+                .expect("Distribution not found")
+        }
+    }
+}
@@ -0,0 +1,512 @@
+use scrypto_test::prelude::*;
+
+// This package depends on Scrypto v1.2.0, where the test runner is the ledger simulator of
+// scrypto-test and every manifest locks its fee from the faucet. The shared test_support
+// harness is built on Scrypto v1.0.0, so the suite keeps its own fixture.
+
+const OWNER: usize = 0;
+/// Distributes the rewards. Holds 1000 rewards.
+const DISTRIBUTOR: usize = 1;
+const ALICE: usize = 2;
+/// Rejects deposits by default
+const BOB: usize = 3;
+
+/// Reward pusher without distribution. The owner rule of the pusher is also the owner rule of
+/// its locker.
+struct TestPusher {
+    ledger: DefaultLedgerSimulator,
+    badges: Vec<NonFungibleGlobalId>,
+    accounts: Vec<ComponentAddress>,
+    pusher: ComponentAddress,
+    locker: ComponentAddress,
+    reward: ResourceAddress,
+}
+
+impl TestPusher {
+    fn new() -> Self {
+        let mut ledger = LedgerSimulatorBuilder::new().without_kernel_trace().build();
+
+        let mut badges = vec![];
+        let mut accounts = vec![];
+        for _ in 0..4 {
+            let (public_key, _, account) = ledger.new_allocated_account();
+            badges.push(NonFungibleGlobalId::from_public_key(&public_key));
+            accounts.push(account);
+        }
+
+        let package_address = ledger.compile_and_publish(this_package!());
+        let reward = ledger.create_fungible_resource(dec!(1000), 18, accounts[DISTRIBUTOR]);
+
+        let manifest = ManifestBuilder::new()
+            .lock_fee_from_faucet()
+            .call_function(
+                package_address,
+                "RewardPusher",
+                "instantiate",
+                manifest_args!(
+                    OwnerRole::Fixed(rule!(require(badges[OWNER].clone()))),
+                    rule!(require(badges[DISTRIBUTOR].clone()))
+                ),
+            )
+            .call_method(
+                accounts[BOB],
+                "set_default_deposit_rule",
+                manifest_args!(DefaultDepositRule::Reject),
+            )
+            .build();
+        let receipt = ledger.execute_manifest(manifest, vec![badges[BOB].clone()]);
+        let pusher = receipt.expect_commit_success().new_component_addresses()[0];
+
+        let manifest = ManifestBuilder::new()
+            .lock_fee_from_faucet()
+            .call_method(pusher, "get_locker", manifest_args!())
+            .build();
+        let receipt = ledger.execute_manifest(manifest, vec![]);
+        let locker = receipt.expect_commit_success().output(1);
+
+        Self {
+            ledger,
+            badges,
+            accounts,
+            pusher,
+            locker,
+            reward,
+        }
+    }
+
+    /// Execute the manifest with the key of the account only, then deposit the worktop into
+    /// the account
+    fn execute(&mut self, account: usize, builder: ManifestBuilder) -> TransactionReceipt {
+        let manifest = builder.deposit_batch(self.accounts[account]).build();
+
+        self.ledger
+            .execute_manifest(manifest, vec![self.badges[account].clone()])
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        let builder =
+            ManifestBuilder::new()
+                .lock_fee_from_faucet()
+                .call_method(self.pusher, method, args);
+
+        self.execute(account, builder)
+    }
+
+    /// Create a distribution with rewards of the caller, or fund an existing one
+    fn fund(
+        &mut self,
+        caller: usize,
+        distribution_id: Option<u64>,
+        amount: Decimal,
+    ) -> TransactionReceipt {
+        let (pusher, reward) = (self.pusher, self.reward);
+        let builder = ManifestBuilder::new()
+            .lock_fee_from_faucet()
+            .withdraw_from_account(self.accounts[caller], reward, amount)
+            .take_all_from_worktop(reward, "rewards");
+        let builder = match distribution_id {
+            Some(distribution_id) => {
+                builder.call_method_with_name_lookup(pusher, "fund_distribution", |lookup| {
+                    (distribution_id, lookup.bucket("rewards"))
+                })
+            }
+            None => builder.call_method_with_name_lookup(pusher, "create_distribution", |lookup| {
+                (lookup.bucket("rewards"),)
+            }),
+        };
+
+        self.execute(caller, builder)
+    }
+
+    /// Create a distribution with a non fungible resource of the distributor
+    fn fund_non_fungible(&mut self) -> TransactionReceipt {
+        let distributor = self.accounts[DISTRIBUTOR];
+        let non_fungible = self.ledger.create_non_fungible_resource(distributor);
+        let builder = ManifestBuilder::new()
+            .lock_fee_from_faucet()
+            .withdraw_from_account(distributor, non_fungible, dec!(1))
+            .take_all_from_worktop(non_fungible, "rewards")
+            .call_method_with_name_lookup(self.pusher, "create_distribution", |lookup| {
+                (lookup.bucket("rewards"),)
+            });
+
+        self.execute(DISTRIBUTOR, builder)
+    }
+
+    fn add_recipients(
+        &mut self,
+        caller: usize,
+        recipients: Vec<(usize, Decimal)>,
+    ) -> TransactionReceipt {
+        let recipients: Vec<(ComponentAddress, Decimal)> = recipients
+            .into_iter()
+            .map(|(account, amount)| (self.accounts[account], amount))
+            .collect();
+
+        self.call(caller, "add_recipients", manifest_args!(0u64, recipients))
+    }
+
+    fn push(&mut self, batch_size: u64) -> TransactionReceipt {
+        self.call(DISTRIBUTOR, "push", manifest_args!(0u64, batch_size))
+    }
+
+    /// Claim rewards stored in the locker for the account, signed by the caller
+    fn claim(&mut self, caller: usize, account: usize, amount: Decimal) -> TransactionReceipt {
+        let (locker, account, reward) = (self.locker, self.accounts[account], self.reward);
+        let builder = ManifestBuilder::new().lock_fee_from_faucet().call_method(
+            locker,
+            "claim",
+            manifest_args!(account, reward, amount),
+        );
+
+        self.execute(caller, builder)
+    }
+
+    /// Let Bob accept deposits of the reward
+    fn allow_reward(&mut self) {
+        let reward = self.reward;
+        let builder = ManifestBuilder::new().lock_fee_from_faucet().call_method(
+            self.accounts[BOB],
+            "set_resource_preference",
+            manifest_args!(reward, ResourcePreference::Allowed),
+        );
+
+        self.execute(BOB, builder).expect_commit_success();
+    }
+
+    fn get_progress(&mut self) -> (u64, u64) {
+        self.call(ALICE, "get_progress", manifest_args!(0u64))
+            .expect_commit_success()
+            .output(1)
+    }
+
+    /// Rewards stored in the locker for the account
+    fn get_stored_amount(&mut self, account: usize) -> Decimal {
+        let (locker, account, reward) = (self.locker, self.accounts[account], self.reward);
+        let builder = ManifestBuilder::new().lock_fee_from_faucet().call_method(
+            locker,
+            "get_amount",
+            manifest_args!(account, reward),
+        );
+
+        self.execute(ALICE, builder)
+            .expect_commit_success()
+            .output(1)
+    }
+
+    fn balance(&mut self, account: usize) -> Decimal {
+        self.ledger
+            .get_component_balance(self.accounts[account], self.reward)
+    }
+}
+
+/// Expect the transaction to fail with an error whose debug output contains `message`
+fn expect_failure_containing(receipt: TransactionReceipt, message: &str) {
+    receipt.expect_specific_failure(|error| format!("{:?}", error).contains(message));
+}
+
+#[test]
+fn test_rewards_are_pushed_in_batches() {
+    let mut pusher = TestPusher::new();
+
+    let distribution_id: u64 = pusher
+        .fund(DISTRIBUTOR, None, dec!(100))
+        .expect_commit_success()
+        .output(3);
+    assert_eq!(distribution_id, 0);
+
+    // Alice is listed twice, and Bob refuses the direct deposit of his rewards
+    pusher
+        .add_recipients(
+            DISTRIBUTOR,
+            vec![(ALICE, dec!(10)), (BOB, dec!(20)), (ALICE, dec!(5))],
+        )
+        .expect_commit_success();
+    assert_eq!(pusher.get_progress(), (0, 3));
+
+    pusher.push(2).expect_commit_success();
+    assert_eq!(pusher.get_progress(), (2, 3));
+    assert_eq!(pusher.balance(ALICE), dec!(10));
+    assert_eq!(pusher.balance(BOB), dec!(0));
+    assert_eq!(pusher.get_stored_amount(BOB), dec!(20));
+
+    pusher.push(2).expect_commit_success();
+    assert_eq!(pusher.get_progress(), (3, 3));
+    assert_eq!(pusher.balance(ALICE), dec!(15));
+    pusher.push(1).expect_commit_failure();
+
+    pusher
+        .call(DISTRIBUTOR, "withdraw_surplus", manifest_args!(0u64))
+        .expect_commit_success();
+    assert_eq!(pusher.balance(DISTRIBUTOR), dec!(965));
+}
+
+#[test]
+fn test_refused_rewards_are_claimed_from_the_locker() {
+    let mut pusher = TestPusher::new();
+
+    pusher
+        .fund(DISTRIBUTOR, None, dec!(100))
+        .expect_commit_success();
+    pusher
+        .add_recipients(DISTRIBUTOR, vec![(BOB, dec!(20)), (BOB, dec!(10))])
+        .expect_commit_success();
+    pusher.push(1).expect_commit_success();
+    pusher.push(1).expect_commit_success();
+    assert_eq!(pusher.get_stored_amount(BOB), dec!(30));
+    assert_eq!(pusher.get_stored_amount(ALICE), dec!(0));
+
+    // Only Bob can claim his rewards, and no more than stored
+    pusher.claim(ALICE, BOB, dec!(30)).expect_commit_failure();
+    pusher.claim(BOB, BOB, dec!(31)).expect_commit_failure();
+    pusher.claim(ALICE, ALICE, dec!(1)).expect_commit_failure();
+
+    pusher.claim(BOB, BOB, dec!(20)).expect_commit_success();
+    assert_eq!(pusher.balance(BOB), dec!(20));
+    assert_eq!(pusher.get_stored_amount(BOB), dec!(10));
+    pusher.claim(BOB, BOB, dec!(10)).expect_commit_success();
+    assert_eq!(pusher.balance(BOB), dec!(30));
+    assert_eq!(pusher.get_stored_amount(BOB), dec!(0));
+
+    // Later rewards are deposited directly once Bob accepts them
+    pusher.allow_reward();
+    pusher
+        .add_recipients(DISTRIBUTOR, vec![(BOB, dec!(5))])
+        .expect_commit_success();
+    pusher.push(1).expect_commit_success();
+    assert_eq!(pusher.balance(BOB), dec!(35));
+    assert_eq!(pusher.get_stored_amount(BOB), dec!(0));
+}
+
+#[test]
+fn test_refusing_recipients_do_not_block_a_batch() {
+    let mut pusher = TestPusher::new();
+
+    pusher
+        .fund(DISTRIBUTOR, None, dec!(100))
+        .expect_commit_success();
+    pusher
+        .add_recipients(
+            DISTRIBUTOR,
+            vec![(BOB, dec!(10)), (ALICE, dec!(20)), (BOB, dec!(30))],
+        )
+        .expect_commit_success();
+
+    pusher.push(3).expect_commit_success();
+    assert_eq!(pusher.get_progress(), (3, 3));
+    assert_eq!(pusher.balance(ALICE), dec!(20));
+    assert_eq!(pusher.get_stored_amount(BOB), dec!(40));
+}
+
+#[test]
+fn test_partial_pushes_resume_where_they_stopped() {
+    let mut pusher = TestPusher::new();
+
+    pusher
+        .fund(DISTRIBUTOR, None, dec!(100))
+        .expect_commit_success();
+    pusher
+        .add_recipients(DISTRIBUTOR, vec![(ALICE, dec!(1)); 5])
+        .expect_commit_success();
+
+    // A failed push does not move the progress
+    pusher
+        .call(ALICE, "push", manifest_args!(0u64, 2u64))
+        .expect_commit_failure();
+    assert_eq!(pusher.get_progress(), (0, 5));
+
+    pusher.push(2).expect_commit_success();
+    assert_eq!(pusher.get_progress(), (2, 5));
+    assert_eq!(pusher.balance(ALICE), dec!(2));
+
+    // Recipients added between pushes are pushed after the previous ones, and the surplus
+    // keeps the amounts of every recipient not pushed yet
+    pusher
+        .add_recipients(DISTRIBUTOR, vec![(ALICE, dec!(10))])
+        .expect_commit_success();
+    pusher
+        .call(DISTRIBUTOR, "withdraw_surplus", manifest_args!(0u64))
+        .expect_commit_success();
+    assert_eq!(pusher.balance(DISTRIBUTOR), dec!(985));
+    pusher
+        .add_recipients(DISTRIBUTOR, vec![(ALICE, dec!(1))])
+        .expect_commit_failure();
+
+    // A batch larger than the remaining recipients only pushes the remaining ones
+    pusher.push(100).expect_commit_success();
+    assert_eq!(pusher.get_progress(), (6, 6));
+    assert_eq!(pusher.balance(ALICE), dec!(15));
+    pusher.push(1).expect_commit_failure();
+}
+
+#[test]
+fn test_distributions_must_be_funded() {
+    let mut pusher = TestPusher::new();
+
+    pusher.fund(ALICE, None, dec!(0)).expect_commit_failure();
+    pusher.fund_non_fungible().expect_commit_failure();
+
+    pusher
+        .add_recipients(DISTRIBUTOR, vec![(ALICE, dec!(10))])
+        .expect_commit_failure();
+    pusher
+        .fund(DISTRIBUTOR, None, dec!(100))
+        .expect_commit_success();
+
+    pusher
+        .add_recipients(ALICE, vec![(ALICE, dec!(10))])
+        .expect_commit_failure();
+    pusher
+        .add_recipients(DISTRIBUTOR, vec![(ALICE, dec!(0))])
+        .expect_commit_failure();
+    pusher
+        .add_recipients(DISTRIBUTOR, vec![(ALICE, dec!(1)); 101])
+        .expect_commit_failure();
+    pusher
+        .add_recipients(DISTRIBUTOR, vec![(ALICE, dec!(60)), (BOB, dec!(41))])
+        .expect_commit_failure();
+
+    pusher
+        .fund(DISTRIBUTOR, Some(0), dec!(1))
+        .expect_commit_success();
+    pusher
+        .add_recipients(DISTRIBUTOR, vec![(ALICE, dec!(60)), (BOB, dec!(41))])
+        .expect_commit_success();
+
+    // The funds are all needed by the recipients not pushed yet
+    pusher
+        .call(DISTRIBUTOR, "withdraw_surplus", manifest_args!(0u64))
+        .expect_commit_success();
+    assert_eq!(pusher.balance(DISTRIBUTOR), dec!(899));
+
+    pusher.push(0).expect_commit_failure();
+    pusher.push(101).expect_commit_failure();
+    pusher
+        .call(DISTRIBUTOR, "push", manifest_args!(1u64, 1u64))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_distributions_are_pushed_independently() {
+    let mut pusher = TestPusher::new();
+    let (alice, bob) = (pusher.accounts[ALICE], pusher.accounts[BOB]);
+
+    pusher
+        .fund(DISTRIBUTOR, None, dec!(100))
+        .expect_commit_success();
+    let distribution_id: u64 = pusher
+        .fund(DISTRIBUTOR, None, dec!(50))
+        .expect_commit_success()
+        .output(3);
+    assert_eq!(distribution_id, 1);
+
+    pusher
+        .add_recipients(DISTRIBUTOR, vec![(BOB, dec!(10))])
+        .expect_commit_success();
+    pusher
+        .call(
+            DISTRIBUTOR,
+            "add_recipients",
+            manifest_args!(1u64, vec![(alice, dec!(20)), (bob, dec!(30))]),
+        )
+        .expect_commit_success();
+
+    // Pushing the second distribution does not move the first one
+    pusher
+        .call(DISTRIBUTOR, "push", manifest_args!(1u64, 10u64))
+        .expect_commit_success();
+    assert_eq!(pusher.get_progress(), (0, 1));
+    let progress: (u64, u64) = pusher
+        .call(ALICE, "get_progress", manifest_args!(1u64))
+        .expect_commit_success()
+        .output(1);
+    assert_eq!(progress, (2, 2));
+    assert_eq!(pusher.balance(ALICE), dec!(20));
+
+    // The rewards refused by Bob in both distributions are stored in the same locker
+    pusher.push(1).expect_commit_success();
+    assert_eq!(pusher.get_stored_amount(BOB), dec!(40));
+    pusher.claim(BOB, BOB, dec!(40)).expect_commit_success();
+    assert_eq!(pusher.balance(BOB), dec!(40));
+
+    // Each distribution keeps its own surplus
+    pusher
+        .call(DISTRIBUTOR, "withdraw_surplus", manifest_args!(1u64))
+        .expect_commit_success();
+    assert_eq!(pusher.balance(DISTRIBUTOR), dec!(850));
+    pusher
+        .call(DISTRIBUTOR, "withdraw_surplus", manifest_args!(0u64))
+        .expect_commit_success();
+    assert_eq!(pusher.balance(DISTRIBUTOR), dec!(940));
+}
+
+#[test]
+fn test_only_the_pusher_stores_in_the_locker() {
+    let mut pusher = TestPusher::new();
+    let (locker, reward, bob) = (pusher.locker, pusher.reward, pusher.accounts[BOB]);
+
+    let builder = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .withdraw_from_account(pusher.accounts[DISTRIBUTOR], reward, dec!(10))
+        .take_all_from_worktop(reward, "rewards")
+        .call_method_with_name_lookup(locker, "store", |lookup| {
+            (bob, lookup.bucket("rewards"), true)
+        });
+    pusher.execute(DISTRIBUTOR, builder).expect_commit_failure();
+}
+
+#[test]
+fn test_failures_carry_pusher_messages() {
+    let mut pusher = TestPusher::new();
+
+    expect_failure_containing(pusher.fund_non_fungible(), "Rewards must be fungible");
+
+    expect_failure_containing(
+        pusher.add_recipients(DISTRIBUTOR, vec![(ALICE, dec!(10))]),
+        "Distribution not found",
+    );
+    expect_failure_containing(pusher.push(1), "Distribution not found");
+    expect_failure_containing(
+        pusher.fund(DISTRIBUTOR, Some(0), dec!(10)),
+        "Distribution not found",
+    );
+    expect_failure_containing(
+        pusher.call(ALICE, "get_progress", manifest_args!(0u64)),
+        "Distribution not found",
+    );
+
+    pusher
+        .fund(DISTRIBUTOR, None, dec!(100))
+        .expect_commit_success();
+    expect_failure_containing(
+        pusher.add_recipients(DISTRIBUTOR, vec![(ALICE, dec!(0))]),
+        "Amounts must be greater than zero",
+    );
+    expect_failure_containing(
+        pusher.add_recipients(DISTRIBUTOR, vec![(ALICE, dec!(1)); 101]),
+        "Too many recipients for a single batch",
+    );
+    expect_failure_containing(
+        pusher.add_recipients(DISTRIBUTOR, vec![(ALICE, dec!(101))]),
+        "Not enough funds for the recipients",
+    );
+
+    pusher
+        .add_recipients(DISTRIBUTOR, vec![(ALICE, dec!(100))])
+        .expect_commit_success();
+    expect_failure_containing(
+        pusher.push(0),
+        "Batch size must be between 1 and the max batch size",
+    );
+    expect_failure_containing(
+        pusher.push(101),
+        "Batch size must be between 1 and the max batch size",
+    );
+    pusher.push(100).expect_commit_success();
+    expect_failure_containing(pusher.push(1), "Every recipient has been pushed");
+}
@@ -0,0 +1,312 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+
+/// Maximum number of configured assets
+pub const MAX_ASSETS: usize = 100;
+
+/// Maximum number of fee tiers
+pub const MAX_FEE_TIERS: usize = 10;
+
+#[derive(ScryptoSbor, Clone, Debug, PartialEq)]
+pub struct AssetRiskParams {
+    /// Maximum loan to value ratio when borrowing against the asset
+    pub ltv: Decimal,
+
+    /// Loan to value ratio above which positions are liquidated
+    pub liquidation_threshold: Decimal,
+
+    pub supply_cap: Option<Decimal>,
+    pub borrow_cap: Option<Decimal>,
+
+    /// Index of the fee rate in the fee tiers
+    pub fee_tier: u8,
+}
+
+/// Risk parameter update. Changes are queued and only applied once the timelock has
+/// elapsed, so users of the consuming protocols get notice before parameters change.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq)]
+pub enum RiskParamChange {
+    SetAssetParams(ResourceAddress, AssetRiskParams),
+    RemoveAsset(ResourceAddress),
+    FeeTiers(Vec<Decimal>),
+    TimelockEpochs(u64),
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct PendingRiskParamChange {
+    pub change: RiskParamChange,
+
+    /// Epoch from which the change can be applied
+    pub executable_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RiskParamChangeQueued {
+    pub change_id: u64,
+    pub change: RiskParamChange,
+    pub executable_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RiskParamChangeApplied {
+    pub change_id: u64,
+    pub change: RiskParamChange,
+}
+
+#[blueprint]
+#[events(RiskParamChangeQueued, RiskParamChangeApplied)]
+pub mod risk_config {
+
+    enable_method_auth! {
+        methods {
+
+            queue_change => restrict_to :[OWNER];
+            apply_change => restrict_to :[OWNER];
+            cancel_change => restrict_to :[OWNER];
+
+            get_asset_params => PUBLIC;
+            get_ltv => PUBLIC;
+            get_liquidation_threshold => PUBLIC;
+            get_supply_cap => PUBLIC;
+            get_borrow_cap => PUBLIC;
+            get_fee_rate => PUBLIC;
+            get_fee_tiers => PUBLIC;
+            get_assets => PUBLIC;
+            get_timelock_epochs => PUBLIC;
+            get_pending_changes => PUBLIC;
+
+        }
+    }
+
+    pub struct RiskConfig {
+        assets: IndexMap<ResourceAddress, AssetRiskParams>,
+
+        /// Fee rates referenced by the assets
+        fee_tiers: Vec<Decimal>,
+
+        timelock_epochs: u64,
+
+        pending_changes: IndexMap<u64, PendingRiskParamChange>,
+        next_change_id: u64,
+    }
+
+    impl RiskConfig {
+        pub fn instantiate(
+            fee_tiers: Vec<Decimal>,
+            timelock_epochs: u64,
+            owner_role: OwnerRole,
+        ) -> Global<RiskConfig> {
+            /* CHECK INPUT */
+            RiskConfig::_assert_valid_fee_tiers(&fee_tiers);
+
+            Self {
+                assets: IndexMap::new(),
+                fee_tiers,
+                timelock_epochs,
+                pending_changes: IndexMap::new(),
+                next_change_id: 0,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn queue_change(&mut self, change: RiskParamChange) -> u64 {
+            /* CHECK INPUT */
+            self._assert_valid_change(&change);
+
+            let change_id = self.next_change_id;
+            let executable_at_epoch = Runtime::current_epoch().number() + self.timelock_epochs;
+
+            self.pending_changes.insert(
+                change_id,
+                PendingRiskParamChange {
+                    change: change.clone(),
+                    executable_at_epoch,
+                },
+            );
+            self.next_change_id += 1;
+
+            Runtime::emit_event(RiskParamChangeQueued {
+                change_id,
+                change,
+                executable_at_epoch,
+            });
+
+            change_id
+        }
+
+        /// Apply a queued change once its timelock has elapsed. The change is checked again
+        /// since other changes may have been applied in the meantime.
+        pub fn apply_change(&mut self, change_id: u64) {
+            let pending_change = self
+                .pending_changes
+                .get(&change_id)
+                .cloned()
+                .expect("Risk parameter change not found");
+
+            assert!(
+                Runtime::current_epoch().number() >= pending_change.executable_at_epoch,
+                "Risk parameter change is still timelocked"
+            );
+
+            self._assert_valid_change(&pending_change.change);
+
+            self.pending_changes.shift_remove(&change_id);
+
+            match pending_change.change.clone() {
+                RiskParamChange::SetAssetParams(res_address, params) => {
+                    self.assets.insert(res_address, params);
+                }
+                RiskParamChange::RemoveAsset(res_address) => {
+                    self.assets.shift_remove(&res_address);
+                }
+                RiskParamChange::FeeTiers(fee_tiers) => {
+                    self.fee_tiers = fee_tiers;
+                }
+                RiskParamChange::TimelockEpochs(epochs) => {
+                    self.timelock_epochs = epochs;
+                }
+            }
+
+            Runtime::emit_event(RiskParamChangeApplied {
+                change_id,
+                change: pending_change.change,
+            });
+        }
+
+        pub fn cancel_change(&mut self, change_id: u64) {
+            assert!(
+                self.pending_changes.shift_remove(&change_id).is_some(),
+                "Risk parameter change not found"
+            );
+        }
+
+        /* GETTERS */
+
+        pub fn get_asset_params(&self, res_address: ResourceAddress) -> Option<AssetRiskParams> {
+            self.assets.get(&res_address).cloned()
+        }
+
+        pub fn get_ltv(&self, res_address: ResourceAddress) -> Decimal {
+            self._get_params(res_address).ltv
+        }
+
+        pub fn get_liquidation_threshold(&self, res_address: ResourceAddress) -> Decimal {
+            self._get_params(res_address).liquidation_threshold
+        }
+
+        pub fn get_supply_cap(&self, res_address: ResourceAddress) -> Option<Decimal> {
+            self._get_params(res_address).supply_cap
+        }
+
+        pub fn get_borrow_cap(&self, res_address: ResourceAddress) -> Option<Decimal> {
+            self._get_params(res_address).borrow_cap
+        }
+
+        pub fn get_fee_rate(&self, res_address: ResourceAddress) -> Decimal {
+            self.fee_tiers[self._get_params(res_address).fee_tier as usize]
+        }
+
+        pub fn get_fee_tiers(&self) -> Vec<Decimal> {
+            self.fee_tiers.clone()
+        }
+
+        pub fn get_assets(&self) -> IndexMap<ResourceAddress, AssetRiskParams> {
+            self.assets.clone()
+        }
+
+        pub fn get_timelock_epochs(&self) -> u64 {
+            self.timelock_epochs
+        }
+
+        pub fn get_pending_changes(&self) -> IndexMap<u64, PendingRiskParamChange> {
+            self.pending_changes.clone()
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _get_params(&self, res_address: ResourceAddress) -> &AssetRiskParams {
+            self.assets
+                .get(&res_address)
+                .expect("No risk parameters for this asset")
+        }
+
+        fn _assert_valid_change(&self, change: &RiskParamChange) {
+            match change {
+                RiskParamChange::SetAssetParams(res_address, params) => {
+                    assert!(
+                        self.assets.contains_key(res_address) || self.assets.len() < MAX_ASSETS,
+                        "Too many assets"
+                    );
+                    assert!(
+                        params.ltv >= Decimal::ZERO
+                            && params.ltv <= params.liquidation_threshold
+                            && params.liquidation_threshold <= Decimal::ONE,
+                        "LTV and liquidation threshold must verify 0 <= LTV <= threshold <= 1"
+                    );
+                    assert!(
+                        params.supply_cap.map_or(true, |cap| cap >= Decimal::ZERO)
+                            && params.borrow_cap.map_or(true, |cap| cap >= Decimal::ZERO),
+                        "Caps must not be negative"
+                    );
+                    assert!(
+                        (params.fee_tier as usize) < self.fee_tiers.len(),
+                        "Fee tier not found"
+                    );
+                }
+                RiskParamChange::RemoveAsset(res_address) => {
+                    assert!(
+                        self.assets.contains_key(res_address),
+                        "No risk parameters for this asset"
+                    );
+                }
+                RiskParamChange::FeeTiers(fee_tiers) => {
+                    RiskConfig::_assert_valid_fee_tiers(fee_tiers);
+                    assert!(
+                        self.assets
+                            .values()
+                            .all(|params| (params.fee_tier as usize) < fee_tiers.len()),
+                        "Fee tier used by an asset would be removed"
+                    );
+                }
+                RiskParamChange::TimelockEpochs(_) => {}
+            }
+        }
+
+        fn _assert_valid_fee_tiers(fee_tiers: &[Decimal]) {
+            assert!(!fee_tiers.is_empty(), "Fee tiers must not be empty");
+            assert!(fee_tiers.len() <= MAX_FEE_TIERS, "Too many fee tiers");
+            assert!(
+                fee_tiers
+                    .iter()
+                    .all(|fee_rate| *fee_rate >= Decimal::ZERO && *fee_rate < Decimal::ONE),
+                "Fee rates must be between 0 and 1"
+            );
+        }
+    }
+}
@@ -0,0 +1,402 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use risk_config::{AssetRiskParams, PendingRiskParamChange};
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+const OWNER: usize = 0;
+const ALICE: usize = 1;
+
+/// Manifest encoding of `RiskParamChange::SetAssetParams`
+fn set_asset_params(res_address: ResourceAddress, params: &AssetRiskParams) -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 0,
+        fields: vec![
+            to_manifest_value_and_unwrap!(&res_address),
+            ManifestValue::Tuple {
+                fields: vec![
+                    to_manifest_value_and_unwrap!(&params.ltv),
+                    to_manifest_value_and_unwrap!(&params.liquidation_threshold),
+                    to_manifest_value_and_unwrap!(&params.supply_cap),
+                    to_manifest_value_and_unwrap!(&params.borrow_cap),
+                    to_manifest_value_and_unwrap!(&params.fee_tier),
+                ],
+            },
+        ],
+    }
+}
+
+/// Manifest encoding of `RiskParamChange::RemoveAsset`
+fn remove_asset(res_address: ResourceAddress) -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 1,
+        fields: vec![to_manifest_value_and_unwrap!(&res_address)],
+    }
+}
+
+/// Manifest encoding of `RiskParamChange::FeeTiers`
+fn fee_tiers(fee_tiers: Vec<Decimal>) -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 2,
+        fields: vec![to_manifest_value_and_unwrap!(&fee_tiers)],
+    }
+}
+
+/// Manifest encoding of `RiskParamChange::TimelockEpochs`
+fn timelock_epochs(epochs: u64) -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 3,
+        fields: vec![to_manifest_value_and_unwrap!(&epochs)],
+    }
+}
+
+/// Parameters with a LTV of 50%, a liquidation threshold of 80%, a supply cap of 1000 and the
+/// given fee tier
+fn asset_params(fee_tier: u8) -> AssetRiskParams {
+    AssetRiskParams {
+        ltv: dec!("0.5"),
+        liquidation_threshold: dec!("0.8"),
+        supply_cap: Some(dec!(1000)),
+        borrow_cap: None,
+        fee_tier,
+    }
+}
+
+/// Risk config at epoch 10 with fee tiers of 1% and 2%, and a timelock of 5 epochs
+struct TestConfig {
+    env: TestEnv,
+    config: ComponentAddress,
+    res_x: ResourceAddress,
+}
+
+impl TestConfig {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 2);
+        env.set_epoch(10);
+        let res_x = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[OWNER]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "RiskConfig",
+                "instantiate",
+                manifest_args!(
+                    vec![dec!("0.01"), dec!("0.02")],
+                    5u64,
+                    OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())))
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let config = receipt.expect_commit_success().new_component_addresses()[0];
+
+        Self { env, config, res_x }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.config, method, args)
+    }
+
+    fn queue_change(&mut self, caller: usize, change: ManifestValue) -> TransactionReceipt {
+        self.call(caller, "queue_change", manifest_args!(change))
+    }
+
+    fn apply_change(&mut self, change_id: u64) -> TransactionReceipt {
+        self.call(OWNER, "apply_change", manifest_args!(change_id))
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str, args: impl ResolvableArguments) -> T {
+        self.call(ALICE, method, args)
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_changes_are_applied_after_the_timelock() {
+    let mut config = TestConfig::new();
+    let res_x = config.res_x;
+
+    config
+        .queue_change(ALICE, set_asset_params(res_x, &asset_params(1)))
+        .expect_commit_failure();
+    let change_id: u64 = config
+        .queue_change(OWNER, set_asset_params(res_x, &asset_params(1)))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(change_id, 0);
+
+    let pending_changes: IndexMap<u64, PendingRiskParamChange> =
+        config.get("get_pending_changes", manifest_args!());
+    assert_eq!(pending_changes.get(&0).unwrap().executable_at_epoch, 15);
+
+    config.env.set_epoch(14);
+    config.apply_change(0).expect_commit_failure();
+
+    config.env.set_epoch(15);
+    config.apply_change(0).expect_commit_success();
+    config.apply_change(0).expect_commit_failure();
+
+    let params: Option<AssetRiskParams> = config.get("get_asset_params", manifest_args!(res_x));
+    assert_eq!(params, Some(asset_params(1)));
+    assert_eq!(
+        config.get::<Decimal>("get_ltv", manifest_args!(res_x)),
+        dec!("0.5")
+    );
+    assert_eq!(
+        config.get::<Decimal>("get_fee_rate", manifest_args!(res_x)),
+        dec!("0.02")
+    );
+    assert_eq!(
+        config.get::<Option<Decimal>>("get_borrow_cap", manifest_args!(res_x)),
+        None
+    );
+
+    // Once applied, the shorter timelock applies to the next queued changes
+    config
+        .queue_change(OWNER, timelock_epochs(0))
+        .expect_commit_success();
+    config.env.set_epoch(20);
+    config.apply_change(1).expect_commit_success();
+    assert_eq!(
+        config.get::<u64>("get_timelock_epochs", manifest_args!()),
+        0
+    );
+
+    config
+        .queue_change(OWNER, remove_asset(res_x))
+        .expect_commit_success();
+    config.apply_change(2).expect_commit_success();
+    config
+        .call(ALICE, "get_ltv", manifest_args!(res_x))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_invalid_changes_are_rejected() {
+    let mut config = TestConfig::new();
+    let res_x = config.res_x;
+
+    let mut params = asset_params(1);
+    params.ltv = dec!("0.9");
+    config
+        .queue_change(OWNER, set_asset_params(res_x, &params))
+        .expect_commit_failure();
+    params = asset_params(2);
+    config
+        .queue_change(OWNER, set_asset_params(res_x, &params))
+        .expect_commit_failure();
+    params = asset_params(1);
+    params.borrow_cap = Some(dec!("-1"));
+    config
+        .queue_change(OWNER, set_asset_params(res_x, &params))
+        .expect_commit_failure();
+
+    config
+        .queue_change(OWNER, remove_asset(res_x))
+        .expect_commit_failure();
+    config
+        .queue_change(OWNER, fee_tiers(vec![]))
+        .expect_commit_failure();
+    config
+        .queue_change(OWNER, fee_tiers(vec![dec!(1)]))
+        .expect_commit_failure();
+
+    // Removing the fee tier 1 is valid until the asset using it is configured
+    config
+        .queue_change(OWNER, set_asset_params(res_x, &asset_params(1)))
+        .expect_commit_success();
+    config
+        .queue_change(OWNER, fee_tiers(vec![dec!("0.01")]))
+        .expect_commit_success();
+
+    config.env.set_epoch(15);
+    config.apply_change(0).expect_commit_success();
+    config.apply_change(1).expect_commit_failure();
+
+    config
+        .call(ALICE, "cancel_change", manifest_args!(1u64))
+        .expect_commit_failure();
+    config
+        .call(OWNER, "cancel_change", manifest_args!(1u64))
+        .expect_commit_success();
+    config
+        .call(OWNER, "cancel_change", manifest_args!(1u64))
+        .expect_commit_failure();
+
+    let pending_changes: IndexMap<u64, PendingRiskParamChange> =
+        config.get("get_pending_changes", manifest_args!());
+    assert!(pending_changes.is_empty());
+    assert_eq!(
+        config.get::<Vec<Decimal>>("get_fee_tiers", manifest_args!()),
+        vec![dec!("0.01"), dec!("0.02")]
+    );
+}
+
+#[test]
+fn test_boundary_parameters_are_accepted() {
+    let mut config = TestConfig::new();
+    let res_x = config.res_x;
+    let res_y =
+        config
+            .env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, config.env.accounts[OWNER]);
+
+    // Up to 10 fee tiers, with a zero fee rate
+    let tiers = vec![dec!(0); 10];
+    config
+        .queue_change(OWNER, fee_tiers(tiers.clone()))
+        .expect_commit_success();
+
+    // LTV equal to the liquidation threshold of 100%, and zero caps
+    let params = AssetRiskParams {
+        ltv: dec!(1),
+        liquidation_threshold: dec!(1),
+        supply_cap: Some(dec!(0)),
+        borrow_cap: Some(dec!(0)),
+        fee_tier: 1,
+    };
+    config
+        .queue_change(OWNER, set_asset_params(res_x, &params))
+        .expect_commit_success();
+    config
+        .queue_change(OWNER, set_asset_params(res_y, &asset_params(0)))
+        .expect_commit_success();
+
+    // Cancelled change ids are not reused
+    config
+        .call(OWNER, "cancel_change", manifest_args!(2u64))
+        .expect_commit_success();
+    let change_id: u64 = config
+        .queue_change(OWNER, timelock_epochs(5))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(change_id, 3);
+
+    config.env.set_epoch(15);
+    for change_id in [0u64, 1, 3] {
+        config.apply_change(change_id).expect_commit_success();
+    }
+    assert_eq!(
+        config.get::<Vec<Decimal>>("get_fee_tiers", manifest_args!()),
+        tiers
+    );
+    assert_eq!(
+        config.get::<Decimal>("get_liquidation_threshold", manifest_args!(res_x)),
+        dec!(1)
+    );
+    assert_eq!(
+        config.get::<Option<Decimal>>("get_supply_cap", manifest_args!(res_x)),
+        Some(dec!(0))
+    );
+    assert_eq!(
+        config.get::<Decimal>("get_fee_rate", manifest_args!(res_x)),
+        dec!(0)
+    );
+
+    // Setting the parameters of a configured asset replaces them
+    config
+        .queue_change(OWNER, set_asset_params(res_x, &asset_params(9)))
+        .expect_commit_success();
+    config.env.set_epoch(20);
+    config.apply_change(4).expect_commit_success();
+    let assets: IndexMap<ResourceAddress, AssetRiskParams> =
+        config.get("get_assets", manifest_args!());
+    assert_eq!(assets.len(), 1);
+    assert_eq!(assets.get(&res_x), Some(&asset_params(9)));
+}
+
+#[test]
+fn test_failures_carry_risk_config_messages() {
+    let mut config = TestConfig::new();
+    let res_x = config.res_x;
+
+    let mut params = asset_params(1);
+    params.ltv = dec!("0.9");
+    expect_failure_containing(
+        config.queue_change(OWNER, set_asset_params(res_x, &params)),
+        "LTV and liquidation threshold must verify 0 <= LTV <= threshold <= 1",
+    );
+    params = asset_params(1);
+    params.liquidation_threshold = dec!("1.1");
+    expect_failure_containing(
+        config.queue_change(OWNER, set_asset_params(res_x, &params)),
+        "LTV and liquidation threshold must verify 0 <= LTV <= threshold <= 1",
+    );
+    params = asset_params(1);
+    params.supply_cap = Some(dec!("-1"));
+    expect_failure_containing(
+        config.queue_change(OWNER, set_asset_params(res_x, &params)),
+        "Caps must not be negative",
+    );
+    expect_failure_containing(
+        config.queue_change(OWNER, set_asset_params(res_x, &asset_params(2))),
+        "Fee tier not found",
+    );
+
+    expect_failure_containing(
+        config.queue_change(OWNER, remove_asset(res_x)),
+        "No risk parameters for this asset",
+    );
+    expect_failure_containing(
+        config.call(ALICE, "get_fee_rate", manifest_args!(res_x)),
+        "No risk parameters for this asset",
+    );
+    expect_failure_containing(
+        config.queue_change(OWNER, fee_tiers(vec![])),
+        "Fee tiers must not be empty",
+    );
+    expect_failure_containing(
+        config.queue_change(OWNER, fee_tiers(vec![dec!("0.01"); 11])),
+        "Too many fee tiers",
+    );
+    expect_failure_containing(
+        config.queue_change(OWNER, fee_tiers(vec![dec!("-0.01")])),
+        "Fee rates must be between 0 and 1",
+    );
+
+    expect_failure_containing(config.apply_change(0), "Risk parameter change not found");
+    expect_failure_containing(
+        config.call(OWNER, "cancel_change", manifest_args!(0u64)),
+        "Risk parameter change not found",
+    );
+
+    // The removal of the fee tier 1 is checked again once the asset using it is configured
+    config
+        .queue_change(OWNER, set_asset_params(res_x, &asset_params(1)))
+        .expect_commit_success();
+    config
+        .queue_change(OWNER, fee_tiers(vec![dec!("0.01")]))
+        .expect_commit_success();
+    expect_failure_containing(
+        config.apply_change(0),
+        "Risk parameter change is still timelocked",
+    );
+    config.env.set_epoch(15);
+    config.apply_change(0).expect_commit_success();
+    expect_failure_containing(
+        config.apply_change(1),
+        "Fee tier used by an asset would be removed",
+    );
+
+    expect_failure_containing(
+        config.env.call_function(
+            OWNER,
+            "RiskConfig",
+            "instantiate",
+            manifest_args!(Vec::<Decimal>::new(), 5u64, OwnerRole::None),
+        ),
+        "Fee tiers must not be empty",
+    );
+}
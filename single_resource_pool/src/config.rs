@@ -0,0 +1,166 @@
+use crate::InterestAccrualMode;
+use asset_pool_interface::ConfigChange;
+use scrypto::prelude::*;
+
+/// Instantiation parameters of a pool. Defaults are defined in `PoolConfig::new` and can be
+/// overridden with the builder methods.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct PoolConfig {
+    pub owner_role: OwnerRole,
+
+    /// Rule of the admin role. Ignored for locally instantiated pools, which are only
+    /// accessible to the component owning them
+    pub admin_rule: AccessRule,
+
+    pub max_external_liquidity_ratio: Option<Decimal>,
+    pub circuit_breaker_threshold: Option<Decimal>,
+    pub config_timelock_epochs: u64,
+    pub protected_withdraw_epoch_limit: Option<Decimal>,
+    pub max_pool_unit_supply: Option<Decimal>,
+    pub flashloan_fee_rate: Decimal,
+
+    /// Interest rate per epoch accrued on the liquidity used outside the pool, and how it
+    /// compounds between accruals. The accrual mode can not be changed after instantiation
+    pub external_liquidity_interest_rate: Decimal,
+    pub interest_accrual_mode: InterestAccrualMode,
+
+    /// Dapp definition account set in the metadata of the pool and of its resources so they
+    /// pass wallet and dashboard verification
+    pub dapp_definition: Option<ComponentAddress>,
+
+    /// Badge (e.g. a KYC credential) that must be present in the caller's auth zone to
+    /// contribute and redeem. Contributions are permissionless when not set
+    pub contribution_badge: Option<ResourceAddress>,
+
+    /// Badge letting its holders use the pool while it is paused
+    pub pause_exempt_badge: Option<ResourceAddress>,
+
+    /// Make pool units non-transferable: only the pool component (or the owning component for
+    /// local pools) can withdraw and deposit them, preventing markets for pool positions.
+    /// Global pools keep them in positions with `contribute_soulbound`
+    pub soulbound_pool_units: bool,
+}
+
+impl PoolConfig {
+    pub fn new(owner_role: OwnerRole, admin_rule: AccessRule) -> Self {
+        Self {
+            owner_role,
+            admin_rule,
+            max_external_liquidity_ratio: None,
+            circuit_breaker_threshold: None,
+            config_timelock_epochs: 0,
+            protected_withdraw_epoch_limit: None,
+            max_pool_unit_supply: None,
+            flashloan_fee_rate: Decimal::ZERO,
+            external_liquidity_interest_rate: Decimal::ZERO,
+            interest_accrual_mode: InterestAccrualMode::Linear,
+            dapp_definition: None,
+            contribution_badge: None,
+            pause_exempt_badge: None,
+            soulbound_pool_units: false,
+        }
+    }
+
+    pub fn max_external_liquidity_ratio(mut self, max_ratio: Decimal) -> Self {
+        self.max_external_liquidity_ratio = Some(max_ratio);
+        self
+    }
+
+    pub fn circuit_breaker_threshold(mut self, threshold: Decimal) -> Self {
+        self.circuit_breaker_threshold = Some(threshold);
+        self
+    }
+
+    pub fn config_timelock_epochs(mut self, epochs: u64) -> Self {
+        self.config_timelock_epochs = epochs;
+        self
+    }
+
+    pub fn protected_withdraw_epoch_limit(mut self, limit: Decimal) -> Self {
+        self.protected_withdraw_epoch_limit = Some(limit);
+        self
+    }
+
+    pub fn max_pool_unit_supply(mut self, max_supply: Decimal) -> Self {
+        self.max_pool_unit_supply = Some(max_supply);
+        self
+    }
+
+    pub fn flashloan_fee_rate(mut self, fee_rate: Decimal) -> Self {
+        self.flashloan_fee_rate = fee_rate;
+        self
+    }
+
+    pub fn external_liquidity_interest(
+        mut self,
+        rate_per_epoch: Decimal,
+        accrual_mode: InterestAccrualMode,
+    ) -> Self {
+        self.external_liquidity_interest_rate = rate_per_epoch;
+        self.interest_accrual_mode = accrual_mode;
+        self
+    }
+
+    pub fn dapp_definition(mut self, dapp_definition: ComponentAddress) -> Self {
+        self.dapp_definition = Some(dapp_definition);
+        self
+    }
+
+    pub fn contribution_badge(mut self, badge: ResourceAddress) -> Self {
+        self.contribution_badge = Some(badge);
+        self
+    }
+
+    pub fn pause_exempt_badge(mut self, badge: ResourceAddress) -> Self {
+        self.pause_exempt_badge = Some(badge);
+        self
+    }
+
+    pub fn soulbound_pool_units(mut self) -> Self {
+        self.soulbound_pool_units = true;
+        self
+    }
+
+    /// Config changes equivalent to the parameters of this config, used for validation
+    pub fn as_config_changes(&self) -> Vec<ConfigChange> {
+        vec![
+            ConfigChange::MaxExternalLiquidityRatio(self.max_external_liquidity_ratio),
+            ConfigChange::CircuitBreakerThreshold(self.circuit_breaker_threshold),
+            ConfigChange::ConfigTimelockEpochs(self.config_timelock_epochs),
+            ConfigChange::ProtectedWithdrawEpochLimit(self.protected_withdraw_epoch_limit),
+            ConfigChange::MaxPoolUnitSupply(self.max_pool_unit_supply),
+            ConfigChange::ContributionBadge(self.contribution_badge),
+            ConfigChange::FlashloanFeeRate(self.flashloan_fee_rate),
+            ConfigChange::PauseExemptBadge(self.pause_exempt_badge),
+            ConfigChange::ExternalLiquidityInterestRate(self.external_liquidity_interest_rate),
+        ]
+    }
+}
+
+/// Metadata of the pool component: `dapp_definition` is set when a dapp definition is provided
+pub fn component_metadata(dapp_definition: Option<ComponentAddress>) -> ModuleConfig<MetadataInit> {
+    match dapp_definition {
+        Some(dapp_definition) => metadata! {
+            init {
+                "dapp_definition" => GlobalAddress::from(dapp_definition), updatable;
+            }
+        },
+        None => metadata! {
+            init {}
+        },
+    }
+}
+
+/// Metadata of the pool resources: `dapp_definitions` is set when a dapp definition is provided
+pub fn resource_metadata(dapp_definition: Option<ComponentAddress>) -> ModuleConfig<MetadataInit> {
+    match dapp_definition {
+        Some(dapp_definition) => metadata! {
+            init {
+                "dapp_definitions" => vec![GlobalAddress::from(dapp_definition)], updatable;
+            }
+        },
+        None => metadata! {
+            init {}
+        },
+    }
+}
@@ -0,0 +1,95 @@
+use scrypto::prelude::*;
+
+/// Errors raised by the pool. They are rendered into panic messages with a stable numeric
+/// code (e.g. `POOL_ERR_003: insufficient liquidity`) so that frontends and integration
+/// tests can match on failures reliably. Codes must never be reused or renumbered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolError {
+    ResourceMismatch,
+    PoolUnitMismatch,
+    InsufficientLiquidity,
+    NegativeAmount,
+    ExternalLiquidityUnderflow,
+    InvalidLoanAmount,
+    InsufficientRepayment,
+    ResourceNotFungible,
+    ResourceNotNonFungible,
+    InvalidParameter,
+    ExternalLiquidityCapExceeded,
+    PoolPaused,
+    ConfigChangeNotFound,
+    ConfigChangeTimelocked,
+    EpochWithdrawLimitExceeded,
+    PoolUnitSupplyCapExceeded,
+    AllowanceNotFound,
+    AllowanceExceeded,
+    InexactAmount,
+    InsufficientPoolUnits,
+    PositionNotFound,
+    FlashloanFeeTooLow,
+    ResourceNotFound,
+}
+
+impl PoolError {
+    pub fn code(&self) -> u16 {
+        match self {
+            PoolError::ResourceMismatch => 1,
+            PoolError::PoolUnitMismatch => 2,
+            PoolError::InsufficientLiquidity => 3,
+            PoolError::NegativeAmount => 4,
+            PoolError::ExternalLiquidityUnderflow => 5,
+            PoolError::InvalidLoanAmount => 6,
+            PoolError::InsufficientRepayment => 7,
+            PoolError::ResourceNotFungible => 8,
+            PoolError::ResourceNotNonFungible => 9,
+            PoolError::InvalidParameter => 10,
+            PoolError::ExternalLiquidityCapExceeded => 11,
+            PoolError::PoolPaused => 12,
+            PoolError::ConfigChangeNotFound => 13,
+            PoolError::ConfigChangeTimelocked => 14,
+            PoolError::EpochWithdrawLimitExceeded => 15,
+            PoolError::PoolUnitSupplyCapExceeded => 16,
+            PoolError::AllowanceNotFound => 17,
+            PoolError::AllowanceExceeded => 18,
+            PoolError::InexactAmount => 19,
+            PoolError::InsufficientPoolUnits => 20,
+            PoolError::PositionNotFound => 21,
+            PoolError::FlashloanFeeTooLow => 22,
+            PoolError::ResourceNotFound => 23,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            PoolError::ResourceMismatch => "pool resource address mismatch",
+            PoolError::PoolUnitMismatch => "pool unit resource address mismatch",
+            PoolError::InsufficientLiquidity => "insufficient liquidity",
+            PoolError::NegativeAmount => "amount must not be negative",
+            PoolError::ExternalLiquidityUnderflow => "amount exceeds external liquidity",
+            PoolError::InvalidLoanAmount => "loan amount must be greater than zero",
+            PoolError::InsufficientRepayment => "insufficient flashloan repayment",
+            PoolError::ResourceNotFungible => "resource must be fungible",
+            PoolError::ResourceNotNonFungible => "resource must be non fungible",
+            PoolError::InvalidParameter => "invalid configuration parameter",
+            PoolError::ExternalLiquidityCapExceeded => "external liquidity cap exceeded",
+            PoolError::PoolPaused => "pool is paused",
+            PoolError::ConfigChangeNotFound => "config change not found",
+            PoolError::ConfigChangeTimelocked => "config change is still timelocked",
+            PoolError::EpochWithdrawLimitExceeded => "protected withdraw epoch limit exceeded",
+            PoolError::PoolUnitSupplyCapExceeded => "pool unit supply cap exceeded",
+            PoolError::AllowanceNotFound => "allowance not found",
+            PoolError::AllowanceExceeded => "units exceed the allowance",
+            PoolError::InexactAmount => "amount can not be withdrawn exactly",
+            PoolError::InsufficientPoolUnits => "pool units do not back the redeemed amount",
+            PoolError::PositionNotFound => "soulbound position not found",
+            PoolError::FlashloanFeeTooLow => "flashloan fee below the pool fee rate",
+            PoolError::ResourceNotFound => "no such resource held by the pool",
+        }
+    }
+}
+
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "POOL_ERR_{:03}: {}", self.code(), self.description())
+    }
+}
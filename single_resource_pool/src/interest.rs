@@ -0,0 +1,26 @@
+use fixed_point_math::exp;
+use scrypto::prelude::*;
+
+/// How the interest on liquidity used outside the pool compounds between two accruals
+#[derive(ScryptoSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InterestAccrualMode {
+    /// Debts grow by `1 + rate * elapsed_epochs`, so interest only compounds on accruals
+    Linear,
+
+    /// Debts grow by `e^(rate * elapsed_epochs)`, independently of the accrual frequency
+    Continuous,
+}
+
+impl InterestAccrualMode {
+    /// Factor by which a debt grows over `elapsed_epochs` epochs at `rate_per_epoch`
+    pub fn growth_factor(&self, rate_per_epoch: Decimal, elapsed_epochs: u64) -> Decimal {
+        match self {
+            InterestAccrualMode::Linear => Decimal::ONE + rate_per_epoch * elapsed_epochs,
+            InterestAccrualMode::Continuous => {
+                exp(PreciseDecimal::from(rate_per_epoch) * PreciseDecimal::from(elapsed_epochs))
+                    .checked_truncate(RoundingMode::ToZero)
+                    .expect("Interest growth factor overflow")
+            }
+        }
+    }
+}
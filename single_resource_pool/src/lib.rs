@@ -23,45 +23,128 @@
 
 use scrypto::prelude::*;
 
-#[derive(ScryptoSbor, NonFungibleData)]
-pub struct FlashloanTerm {
-    pub loan_amount: Decimal,
-    pub fee_amount: Decimal,
+mod config;
+mod errors;
+mod interest;
+
+pub use config::*;
+pub use errors::*;
+pub use interest::*;
+
+// Re-exported for the packages importing the shared utilities from the pool package
+pub use blueprint_commons::{
+    assert_fungible_res_address, assert_non_fungible_res_address, checked_div_to_decimal,
+    checked_mul_to_decimal, ReentrancyGuard,
+};
+
+// Types of the pool method signatures, shared with the typed stubs of the interface package
+pub use asset_pool_interface::{
+    ConfigChange, DepositType, FlashloanTerm, PendingConfigChange, WithdrawType,
+    FLASHLOAN_CALLBACK_METHOD,
+};
+
+/// Maximum number of ratio observations kept for the TWAP computation
+pub const MAX_RATIO_OBSERVATIONS: usize = 32;
+
+#[derive(ScryptoSbor, Clone)]
+pub struct RatioObservation {
+    /// Epoch from which the ratio applies
+    pub epoch: u64,
+    pub ratio: PreciseDecimal,
 }
 
-#[derive(ScryptoSbor, PartialEq)]
-pub enum WithdrawType {
-    ForTemporaryUse,
-    LiquidityWithdrawal,
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CircuitBreakerTripped {
+    pub previous_ratio: PreciseDecimal,
+    pub new_ratio: PreciseDecimal,
 }
 
-#[derive(ScryptoSbor, PartialEq)]
-pub enum DepositType {
-    FromTemporaryUse,
-    LiquidityAddition,
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ConfigChangeQueued {
+    pub change_id: u64,
+    pub change: ConfigChange,
+    pub executable_at_epoch: u64,
 }
 
-pub fn assert_fungible_res_address(address: ResourceAddress, message: Option<String>) {
-    assert!(
-        ResourceManager::from_address(address)
-            .resource_type()
-            .is_fungible(),
-        "{}",
-        message.unwrap_or("Resource must be fungible".to_string())
-    );
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ConfigChangeApplied {
+    pub change_id: u64,
+    pub change: ConfigChange,
 }
 
-pub fn assert_non_fungible_res_address(address: ResourceAddress, message: Option<String>) {
-    assert!(
-        !ResourceManager::from_address(address)
-            .resource_type()
-            .is_fungible(),
-        "{}",
-        message.unwrap_or("Resource must be non fungible".to_string())
-    );
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ConfigChangeCancelled {
+    pub change_id: u64,
+    pub change: ConfigChange,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RepaymentMismatch {
+    pub external_liquidity_amount: Decimal,
+    pub repaid_amount: Decimal,
+}
+
+/// Pool units escrowed by a holder that a spender can redeem on their behalf
+#[derive(ScryptoSbor)]
+pub struct Allowance {
+    /// Badge allowed to revoke the allowance and get the remaining pool units back
+    pub holder: NonFungibleGlobalId,
+
+    /// Badge allowed to redeem the escrowed pool units
+    pub spender: NonFungibleGlobalId,
+
+    /// Pool units the spender can still redeem
+    pub pool_units: Vault,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct AllowanceGranted {
+    pub allowance_id: u64,
+    pub holder: NonFungibleGlobalId,
+    pub spender: NonFungibleGlobalId,
+    pub units: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct AllowanceUsed {
+    pub allowance_id: u64,
+    pub units: Decimal,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct AllowanceRevoked {
+    pub allowance_id: u64,
+    pub returned_units: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SoulboundContribution {
+    pub holder: NonFungibleGlobalId,
+    pub amount: Decimal,
+    pub units: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SoulboundRedemption {
+    pub holder: NonFungibleGlobalId,
+    pub units: Decimal,
+    pub amount: Decimal,
 }
 
 #[blueprint]
+#[events(
+    CircuitBreakerTripped,
+    ConfigChangeQueued,
+    ConfigChangeApplied,
+    ConfigChangeCancelled,
+    RepaymentMismatch,
+    AllowanceGranted,
+    AllowanceUsed,
+    AllowanceRevoked,
+    SoulboundContribution,
+    SoulboundRedemption
+)]
 pub mod pool {
 
     enable_method_auth! {
@@ -73,18 +156,49 @@ pub mod pool {
             protected_deposit => restrict_to :[admin];
             protected_withdraw => restrict_to :[admin];
 
+            deposit_other => restrict_to :[admin];
+            withdraw_other => restrict_to :[admin];
+
             decrease_external_liquidity => restrict_to :[admin];
             increase_external_liquidity => restrict_to :[admin];
 
             contribute => restrict_to :[admin];
             redeem  => restrict_to :[admin];
+            redeem_advanced => restrict_to :[admin];
+
+            grant_allowance => restrict_to :[admin];
+            redeem_with_allowance => restrict_to :[admin];
+            revoke_allowance => restrict_to :[admin];
+
+            contribute_soulbound => restrict_to :[admin];
+            redeem_soulbound => restrict_to :[admin];
 
             take_flashloan => restrict_to :[admin];
             repay_flashloan => restrict_to :[admin];
+            flashloan_and_call => restrict_to :[admin];
+
+            queue_config_change => restrict_to :[OWNER];
+            apply_config_change => restrict_to :[OWNER];
+            cancel_config_change => restrict_to :[OWNER];
+            pause => restrict_to :[OWNER];
+            resume => restrict_to :[OWNER];
+
+            accrue_interest => PUBLIC;
 
             get_pool_unit_ratio => PUBLIC;
+            get_current_pool_unit_ratio => PUBLIC;
             get_pool_unit_supply => PUBLIC;
             get_pooled_amount => PUBLIC;
+            get_units_for_amount => PUBLIC;
+            get_amount_for_units => PUBLIC;
+            get_flashloan_fee_rate => PUBLIC;
+            get_flashloan_capacity => PUBLIC;
+            get_other_amount => PUBLIC;
+            is_paused => PUBLIC;
+            get_ratio_twap => PUBLIC;
+            get_pending_config_changes => PUBLIC;
+            get_allowance => PUBLIC;
+            get_soulbound_units => PUBLIC;
 
         }
     }
@@ -93,9 +207,18 @@ pub mod pool {
         /// Vault containing the pooled token
         liquidity: Vault,
 
+        /// Divisibility of the pooled token, also used for the pool units
+        divisibility: u8,
+
         /// Amount taken from the pool and not yet returned
         external_liquidity_amount: Decimal,
 
+        /// Principal of the flashloans taken from the liquidity vault and not yet repaid
+        flashloan_outstanding_amount: Decimal,
+
+        /// Vaults holding resources that are not part of the pooled liquidity
+        other_vaults: KeyValueStore<ResourceAddress, Vault>,
+
         /// Flashloan term non-fungible resource manager
         flashloan_term_res_manager: ResourceManager,
 
@@ -104,6 +227,73 @@ pub mod pool {
 
         /// Ratio between the pool unit and the pooled token
         unit_to_asset_ratio: PreciseDecimal,
+
+        /// Badge allowing the pool to mint and burn the pooled token. When set, flashloans are
+        /// minted instead of being withdrawn from the liquidity vault
+        flash_mint_badge: Option<Vault>,
+
+        /// Maximum fraction of the total pooled amount that can be used outside the pool
+        max_external_liquidity_ratio: Option<Decimal>,
+
+        /// Maximum relative change of the unit to asset ratio allowed in a single operation
+        /// before the pool pauses itself
+        circuit_breaker_threshold: Option<Decimal>,
+
+        /// Whether state changing methods are paused
+        is_paused: bool,
+
+        /// Buffer of the latest ratio observations, ordered by epoch
+        ratio_observations: Vec<RatioObservation>,
+
+        /// Number of epochs a config change must wait before it can be applied
+        config_timelock_epochs: u64,
+
+        /// Queued config changes indexed by their id
+        pending_config_changes: IndexMap<u64, PendingConfigChange>,
+
+        /// Id of the next queued config change
+        next_config_change_id: u64,
+
+        /// Maximum amount that can be taken through `protected_withdraw` in a single epoch
+        protected_withdraw_epoch_limit: Option<Decimal>,
+
+        /// Epoch and amount taken through `protected_withdraw` during that epoch
+        protected_withdraw_epoch_usage: (u64, Decimal),
+
+        /// Maximum pool unit supply that can be reached through `contribute`
+        max_pool_unit_supply: Option<Decimal>,
+
+        /// Badge required to contribute and redeem, for permissioned pools
+        contribution_badge: Option<ResourceAddress>,
+
+        /// Minimum flashloan fee, as a share of the loan amount
+        flashloan_fee_rate: Decimal,
+
+        /// Badge letting its holders use the pool while it is paused
+        pause_exempt_badge: Option<ResourceAddress>,
+
+        /// Interest rate per epoch accrued on the external liquidity amount
+        external_liquidity_interest_rate: Decimal,
+
+        /// Whether the external liquidity interest compounds on accruals or continuously
+        interest_accrual_mode: InterestAccrualMode,
+
+        /// Epoch of the last external liquidity interest accrual
+        last_interest_accrual_epoch: u64,
+
+        /// Pool units escrowed for spenders, indexed by allowance id
+        allowances: KeyValueStore<u64, Allowance>,
+
+        /// Id of the next granted allowance
+        next_allowance_id: u64,
+
+        /// Pool units kept by the pool on behalf of holder badges, for soulbound pool units
+        /// that can not be held in accounts
+        soulbound_positions: KeyValueStore<NonFungibleGlobalId, Vault>,
+
+        /// Rejects nested calls into state changing methods while the pool calls other
+        /// components
+        reentrancy_guard: ReentrancyGuard,
     }
 
     impl AssetPool {
@@ -112,50 +302,11 @@ pub mod pool {
             owner_role: OwnerRole,
             component_rule: AccessRule,
         ) -> (Owned<AssetPool>, ResourceAddress, ResourceAddress) {
-            /* CHECK INPUTS */
-            assert_fungible_res_address(pool_res_address, None);
-
-            let pool_unit_res_manager = ResourceBuilder::new_fungible(owner_role.clone())
-                .mint_roles(mint_roles! {
-                    minter => component_rule.clone();
-                    minter_updater => rule!(deny_all);
-                })
-                .burn_roles(burn_roles! {
-                    burner => component_rule.clone();
-                    burner_updater => rule!(deny_all);
-                })
-                .create_with_no_initial_supply();
-
-            let flashloan_term_res_manager =
-                ResourceBuilder::new_ruid_non_fungible::<FlashloanTerm>(owner_role)
-                    .mint_roles(mint_roles! {
-                        minter => component_rule.clone();
-                        minter_updater => rule!(deny_all);
-                    })
-                    .burn_roles(burn_roles! {
-                        burner => component_rule.clone();
-                        burner_updater => rule!(deny_all);
-                    })
-                    // ! critical
-                    .deposit_roles(deposit_roles! {
-                        depositor => rule!(deny_all);
-                        depositor_updater => rule!(deny_all);
-                    })
-                    .create_with_no_initial_supply();
-
-            let pool_component = Self {
-                liquidity: Vault::new(pool_res_address),
-                flashloan_term_res_manager,
-                pool_unit_res_manager,
-                external_liquidity_amount: 0.into(),
-                unit_to_asset_ratio: 1.into(),
-            }
-            .instantiate();
-
-            (
-                pool_component,
-                pool_unit_res_manager.address(),
-                flashloan_term_res_manager.address(),
+            AssetPool::_instantiate_locally(
+                pool_res_address,
+                PoolConfig::new(owner_role, rule!(deny_all)),
+                component_rule,
+                None,
             )
         }
 
@@ -164,150 +315,742 @@ pub mod pool {
             owner_role: OwnerRole,
             admin_rule: AccessRule,
         ) -> (Global<AssetPool>, ResourceAddress, ResourceAddress) {
-            /* CHECK INPUT */
-            assert_fungible_res_address(pool_res_address, None);
-
-            let (address_reservation, component_address) =
-                Runtime::allocate_component_address(AssetPool::blueprint_id());
+            AssetPool::_instantiate(
+                pool_res_address,
+                PoolConfig::new(owner_role, admin_rule),
+                None,
+                None,
+            )
+        }
 
-            let component_rule = rule!(require(global_caller(component_address)));
+        pub fn instantiate_locally_with_config(
+            pool_res_address: ResourceAddress,
+            config: PoolConfig,
+            component_rule: AccessRule,
+        ) -> (Owned<AssetPool>, ResourceAddress, ResourceAddress) {
+            AssetPool::_instantiate_locally(pool_res_address, config, component_rule, None)
+        }
 
-            let (owned_pool_component, pool_unit_res_manager, flashloan_term_res_manager) =
-                AssetPool::instantiate_locally(pool_res_address, owner_role.clone(), component_rule);
+        pub fn instantiate_with_config(
+            pool_res_address: ResourceAddress,
+            config: PoolConfig,
+        ) -> (Global<AssetPool>, ResourceAddress, ResourceAddress) {
+            AssetPool::_instantiate(pool_res_address, config, None, None)
+        }
 
-            let pool_component = owned_pool_component
-                .prepare_to_globalize(owner_role)
-                .roles(roles!(
-                    admin => admin_rule;
-                ))
-                .with_address(address_reservation)
-                .globalize();
+        /// Instantiate the pool at an address reserved beforehand, allowing a factory or a
+        /// deployment manifest to reference the pool address before it is globalized
+        pub fn instantiate_with_address_reservation(
+            address_reservation: GlobalAddressReservation,
+            pool_res_address: ResourceAddress,
+            config: PoolConfig,
+        ) -> (Global<AssetPool>, ResourceAddress, ResourceAddress) {
+            AssetPool::_instantiate(pool_res_address, config, None, Some(address_reservation))
+        }
 
-            (
-                pool_component,
-                pool_unit_res_manager,
-                flashloan_term_res_manager,
+        /// Flash-mint variant of `instantiate_locally`. The provided badge must be allowed to mint
+        /// and burn the pooled resource: flashloans are then minted on demand and burned on
+        /// repayment, so no idle liquidity is needed to serve them.
+        pub fn instantiate_flash_mint_locally(
+            pool_res_address: ResourceAddress,
+            flash_mint_badge: Bucket,
+            config: PoolConfig,
+            component_rule: AccessRule,
+        ) -> (Owned<AssetPool>, ResourceAddress, ResourceAddress) {
+            AssetPool::_instantiate_locally(
+                pool_res_address,
+                config,
+                component_rule,
+                Some(flash_mint_badge),
             )
         }
 
-        pub fn get_pool_unit_ratio(&mut self) -> PreciseDecimal {
+        /// Flash-mint variant of `instantiate`. See `instantiate_flash_mint_locally`.
+        pub fn instantiate_flash_mint(
+            pool_res_address: ResourceAddress,
+            flash_mint_badge: Bucket,
+            config: PoolConfig,
+        ) -> (Global<AssetPool>, ResourceAddress, ResourceAddress) {
+            AssetPool::_instantiate(pool_res_address, config, Some(flash_mint_badge), None)
+        }
+
+        pub fn get_pool_unit_ratio(&self) -> PreciseDecimal {
             self.unit_to_asset_ratio
         }
 
+        /// Ratio computed from the current pool state, as opposed to the cached ratio returned
+        /// by `get_pool_unit_ratio` which is only refreshed on liquidity updates
+        pub fn get_current_pool_unit_ratio(&self) -> PreciseDecimal {
+            self._get_unit_to_asset_ratio()
+        }
+
         pub fn get_pool_unit_supply(&self) -> Decimal {
             self.pool_unit_res_manager.total_supply().unwrap_or(dec!(0))
         }
 
-        pub fn get_pooled_amount(&mut self) -> (Decimal, Decimal) {
+        pub fn get_pooled_amount(&self) -> (Decimal, Decimal) {
             (self.liquidity.amount(), self.external_liquidity_amount)
         }
 
+        /// Pool units minted by `contribute` for the given amount of pooled token
+        pub fn get_units_for_amount(&self, amount: Decimal) -> Decimal {
+            self._get_units_for_amount(amount)
+        }
+
+        /// Amount of pooled token returned by `redeem` for the given amount of pool units
+        pub fn get_amount_for_units(&self, units: Decimal) -> Decimal {
+            self._get_amount_for_units(units, RoundingMode::ToZero)
+        }
+
+        /// Minimum fee rate charged on flashloans
+        pub fn get_flashloan_fee_rate(&self) -> Decimal {
+            self.flashloan_fee_rate
+        }
+
+        /// Maximum amount of a flashloan, `None` for flash-mint pools which mint loans on
+        /// demand
+        pub fn get_flashloan_capacity(&self) -> Option<Decimal> {
+            match self.flash_mint_badge {
+                Some(_) => None,
+                None => Some(self.liquidity.amount()),
+            }
+        }
+
+        pub fn is_paused(&self) -> bool {
+            self.is_paused
+        }
+
+        /// Time weighted average of the unit to asset ratio over the last `window_epochs`
+        /// epochs. Ratio changes made during the current epoch are not taken into account,
+        /// making the returned value resistant to same-epoch manipulation.
+        /// Returns `None` for an empty window, and for a window starting before the oldest
+        /// kept observation, which happens when the pool is younger than the window or when
+        /// the window is longer than the last `MAX_RATIO_OBSERVATIONS` observations cover.
+        pub fn get_ratio_twap(&self, window_epochs: u64) -> Option<PreciseDecimal> {
+            let current_epoch = Runtime::current_epoch().number();
+            let window_start = current_epoch.checked_sub(window_epochs)?;
+
+            if window_epochs == 0 || self.ratio_observations.first()?.epoch > window_start {
+                return None;
+            }
+
+            let mut weighted_ratio_sum = PreciseDecimal::ZERO;
+            let mut total_weight: u64 = 0;
+            let mut period_end = current_epoch;
+
+            for observation in self.ratio_observations.iter().rev() {
+                let period_start = observation.epoch.max(window_start);
+
+                if period_end > period_start {
+                    let weight = period_end - period_start;
+                    weighted_ratio_sum += observation.ratio * PreciseDecimal::from(weight);
+                    total_weight += weight;
+                }
+
+                if observation.epoch <= window_start {
+                    break;
+                }
+
+                period_end = observation.epoch;
+            }
+
+            Some(weighted_ratio_sum / PreciseDecimal::from(total_weight))
+        }
+
         // Handle request to increase liquidity.
-        // Add liquidity to the pool and get pool units back
-        pub fn contribute(&mut self, assets: Bucket) -> Bucket {
+        // Add liquidity to the pool and get pool units back. Pool units are quantized to the
+        // pooled token divisibility and the part of the assets that can not be represented by
+        // a whole pool unit amount is returned along with the pool units
+        pub fn contribute(&mut self, mut assets: Bucket) -> (Bucket, Bucket) {
+            self.reentrancy_guard.assert_not_entered();
+
+            self._assert_not_paused();
+
+            self._accrue_interest();
+
+            self._assert_contribution_allowed();
+
             /* CHECK INPUT */
             assert!(
                 assets.resource_address() == self.liquidity.resource_address(),
-                "Pool resource address mismatch"
+                "{}",
+                PoolError::ResourceMismatch
             );
 
-            let unit_amount = (assets.amount() * self.unit_to_asset_ratio) //
-                .checked_truncate(RoundingMode::ToZero)
-                .unwrap();
+            let unit_amount = self._get_units_for_amount(assets.amount());
 
-            self.liquidity.put(assets);
+            if let Some(max_supply) = self.max_pool_unit_supply {
+                assert!(
+                    self.get_pool_unit_supply() + unit_amount <= max_supply,
+                    "{}",
+                    PoolError::PoolUnitSupplyCapExceeded
+                );
+            }
+
+            // Amount of assets actually backing the minted pool units
+            let deposit_amount = if unit_amount == 0.into() {
+                Decimal::ZERO
+            } else {
+                checked_div_to_decimal(
+                    unit_amount,
+                    self.unit_to_asset_ratio,
+                    self.divisibility,
+                    RoundingMode::AwayFromZero,
+                )
+                .min(assets.amount())
+            };
+
+            self.liquidity.put(assets.take(deposit_amount));
 
             let pool_units = self.pool_unit_res_manager.mint(unit_amount);
 
-            pool_units
+            (pool_units, assets)
         }
 
         // Handle request to decrease liquidity.
         // Remove liquidity from the pool and and burn corresponding pool units
-        pub fn redeem(&mut self, pool_units: Bucket) -> Bucket {
+        pub fn redeem(&mut self, pool_units: Bucket) -> (Bucket, Bucket) {
+            self.redeem_advanced(pool_units, WithdrawStrategy::Rounded(RoundingMode::ToZero))
+        }
+
+        // Same as `redeem` but lets the caller choose how the redeemed amount is rounded
+        // to the pooled resource divisibility. Pool units that are not needed to back the
+        // rounded amount are returned along with the assets. Rounding can not make the caller
+        // receive more than its pool units are worth: the redemption fails when they do not
+        // back the rounded amount, and `Exact` fails when the amount needs rounding
+        pub fn redeem_advanced(
+            &mut self,
+            mut pool_units: Bucket,
+            withdraw_strategy: WithdrawStrategy,
+        ) -> (Bucket, Bucket) {
+            self.reentrancy_guard.assert_not_entered();
+
+            self._assert_not_paused();
+
+            self._accrue_interest();
+
+            self._assert_contribution_allowed();
+
             /* INPUT CHECK */
             assert!(
                 pool_units.resource_address() == self.pool_unit_res_manager.address(),
-                "Pool unit resource address mismatch"
+                "{}",
+                PoolError::PoolUnitMismatch
             );
 
-            let amount = (pool_units.amount() / self.unit_to_asset_ratio) //
-                .checked_truncate(RoundingMode::ToZero)
-                .unwrap();
+            let amount = match withdraw_strategy {
+                WithdrawStrategy::Exact => {
+                    let amount =
+                        self._get_amount_for_units(pool_units.amount(), RoundingMode::ToZero);
+
+                    assert!(
+                        amount
+                            == self._get_amount_for_units(
+                                pool_units.amount(),
+                                RoundingMode::AwayFromZero
+                            ),
+                        "{}",
+                        PoolError::InexactAmount
+                    );
+
+                    amount
+                }
+                WithdrawStrategy::Rounded(rounding_mode) => {
+                    self._get_amount_for_units(pool_units.amount(), rounding_mode)
+                }
+            };
 
-            self.pool_unit_res_manager.burn(pool_units);
+            // Pool units actually backing the rounded amount
+            let burn_amount = checked_mul_to_decimal(
+                amount,
+                self.unit_to_asset_ratio,
+                self.divisibility,
+                RoundingMode::AwayFromZero,
+            );
+
+            assert!(
+                burn_amount <= pool_units.amount(),
+                "{}",
+                PoolError::InsufficientPoolUnits
+            );
+
+            self.pool_unit_res_manager
+                .burn(pool_units.take(burn_amount));
 
             assert!(
                 amount <= self.liquidity.amount(),
-                "Not enough liquidity to withdraw this amount"
+                "{}",
+                PoolError::InsufficientLiquidity
             );
 
-            let assets = self
-                .liquidity
-                .take_advanced(amount, WithdrawStrategy::Rounded(RoundingMode::ToZero));
+            let assets = self.liquidity.take(amount);
+
+            (assets, pool_units)
+        }
+
+        /// Escrow pool units that the `spender` badge holder can redeem on behalf of the
+        /// `holder` badge holder, enabling pull based integrations without handing the pool
+        /// units over. Allowances are not supported for soulbound pool units, which can not
+        /// leave the pool.
+        pub fn grant_allowance(
+            &mut self,
+            holder: NonFungibleGlobalId,
+            spender: NonFungibleGlobalId,
+            pool_units: Bucket,
+        ) -> u64 {
+            self.reentrancy_guard.assert_not_entered();
+
+            /* INPUT CHECK */
+            assert!(
+                pool_units.resource_address() == self.pool_unit_res_manager.address(),
+                "{}",
+                PoolError::PoolUnitMismatch
+            );
+
+            let allowance_id = self.next_allowance_id;
+            self.next_allowance_id += 1;
+
+            Runtime::emit_event(AllowanceGranted {
+                allowance_id,
+                holder: holder.clone(),
+                spender: spender.clone(),
+                units: pool_units.amount(),
+            });
+
+            self.allowances.insert(
+                allowance_id,
+                Allowance {
+                    holder,
+                    spender,
+                    pool_units: Vault::with_bucket(pool_units),
+                },
+            );
+
+            allowance_id
+        }
+
+        /// Redeem up to the escrowed pool units of an allowance. The spender badge proof must
+        /// be in the auth zone of the caller. Pool units not needed to back the redeemed
+        /// amount stay in the allowance.
+        pub fn redeem_with_allowance(&mut self, allowance_id: u64, units: Decimal) -> Bucket {
+            /* INPUT CHECK */
+            assert!(units >= 0.into(), "{}", PoolError::NegativeAmount);
+
+            let pool_units = {
+                let mut allowance = self
+                    .allowances
+                    .get_mut(&allowance_id)
+                    .unwrap_or_else(|| panic!("{}", PoolError::AllowanceNotFound));
+
+                Runtime::assert_access_rule(rule!(require(allowance.spender.clone())));
+
+                assert!(
+                    units <= allowance.pool_units.amount(),
+                    "{}",
+                    PoolError::AllowanceExceeded
+                );
+
+                allowance.pool_units.take(units)
+            };
+
+            let (assets, remaining_units) =
+                self.redeem_advanced(pool_units, WithdrawStrategy::Rounded(RoundingMode::ToZero));
+
+            Runtime::emit_event(AllowanceUsed {
+                allowance_id,
+                units: units - remaining_units.amount(),
+                amount: assets.amount(),
+            });
+
+            self.allowances
+                .get_mut(&allowance_id)
+                .unwrap()
+                .pool_units
+                .put(remaining_units);
 
             assets
         }
 
+        /// Return the pool units remaining in an allowance. The holder badge proof must be in
+        /// the auth zone of the caller.
+        pub fn revoke_allowance(&mut self, allowance_id: u64) -> Bucket {
+            self.reentrancy_guard.assert_not_entered();
+
+            let mut allowance = self
+                .allowances
+                .get_mut(&allowance_id)
+                .unwrap_or_else(|| panic!("{}", PoolError::AllowanceNotFound));
+
+            Runtime::assert_access_rule(rule!(require(allowance.holder.clone())));
+
+            let pool_units = allowance.pool_units.take_all();
+
+            Runtime::emit_event(AllowanceRevoked {
+                allowance_id,
+                returned_units: pool_units.amount(),
+            });
+
+            pool_units
+        }
+
+        /// Holder, spender and pool units remaining in an allowance
+        pub fn get_allowance(
+            &self,
+            allowance_id: u64,
+        ) -> (NonFungibleGlobalId, NonFungibleGlobalId, Decimal) {
+            let allowance = self
+                .allowances
+                .get(&allowance_id)
+                .unwrap_or_else(|| panic!("{}", PoolError::AllowanceNotFound));
+
+            (
+                allowance.holder.clone(),
+                allowance.spender.clone(),
+                allowance.pool_units.amount(),
+            )
+        }
+
+        /// Contribute on behalf of the `holder` badge. The minted pool units are kept by the
+        /// pool in the position of the holder, which is how soulbound pool units are held for
+        /// global pools: only the pool component can move them. The change of the
+        /// contribution is returned.
+        pub fn contribute_soulbound(
+            &mut self,
+            assets: Bucket,
+            holder: NonFungibleGlobalId,
+        ) -> Bucket {
+            let amount = assets.amount();
+
+            let (pool_units, change) = self.contribute(assets);
+
+            Runtime::emit_event(SoulboundContribution {
+                holder: holder.clone(),
+                amount: amount - change.amount(),
+                units: pool_units.amount(),
+            });
+
+            let position_exists = self.soulbound_positions.get(&holder).is_some();
+
+            if position_exists {
+                self.soulbound_positions
+                    .get_mut(&holder)
+                    .unwrap()
+                    .put(pool_units);
+            } else {
+                self.soulbound_positions
+                    .insert(holder, Vault::with_bucket(pool_units));
+            }
+
+            change
+        }
+
+        /// Redeem pool units of the position of the `holder` badge. Like every other method
+        /// moving liquidity, it is restricted to the admin, and the holder badge proof must also
+        /// be in the auth zone of the caller. Pool units not needed to back the redeemed
+        /// amount stay in the position.
+        pub fn redeem_soulbound(&mut self, holder: NonFungibleGlobalId, units: Decimal) -> Bucket {
+            /* INPUT CHECK */
+            assert!(units >= 0.into(), "{}", PoolError::NegativeAmount);
+
+            Runtime::assert_access_rule(rule!(require(holder.clone())));
+
+            let pool_units = {
+                let mut position = self
+                    .soulbound_positions
+                    .get_mut(&holder)
+                    .unwrap_or_else(|| panic!("{}", PoolError::PositionNotFound));
+
+                assert!(
+                    units <= position.amount(),
+                    "{}",
+                    PoolError::InsufficientPoolUnits
+                );
+
+                position.take(units)
+            };
+
+            let (assets, remaining_units) =
+                self.redeem_advanced(pool_units, WithdrawStrategy::Rounded(RoundingMode::ToZero));
+
+            Runtime::emit_event(SoulboundRedemption {
+                holder: holder.clone(),
+                units: units - remaining_units.amount(),
+                amount: assets.amount(),
+            });
+
+            self.soulbound_positions
+                .get_mut(&holder)
+                .unwrap()
+                .put(remaining_units);
+
+            assets
+        }
+
+        /// Pool units in the position of the `holder` badge
+        pub fn get_soulbound_units(&self, holder: NonFungibleGlobalId) -> Decimal {
+            self.soulbound_positions
+                .get(&holder)
+                .map(|position| position.amount())
+                .unwrap_or(dec!(0))
+        }
+
         pub fn protected_withdraw(
             &mut self,
             amount: Decimal,
             withdraw_type: WithdrawType,
             withdraw_strategy: WithdrawStrategy,
         ) -> Bucket {
+            self.reentrancy_guard.assert_not_entered();
+
+            self._assert_not_paused();
+
+            self._accrue_interest();
+
             /* INPUT CHECK */
-            assert!(amount >= 0.into(), "Withdraw amount must not be negative!");
+            assert!(amount >= 0.into(), "{}", PoolError::NegativeAmount);
+
+            self._track_protected_withdraw(amount);
 
             let assets = self.liquidity.take_advanced(amount, withdraw_strategy);
 
             if withdraw_type == WithdrawType::ForTemporaryUse {
                 self.external_liquidity_amount += amount;
+
+                self._assert_external_liquidity_cap();
             } else {
-                self.unit_to_asset_ratio = self._get_unit_to_asset_ratio();
+                self._update_unit_to_asset_ratio();
             }
 
             assets
         }
 
         pub fn protected_deposit(&mut self, assets: Bucket, deposit_type: DepositType) {
+            self.reentrancy_guard.assert_not_entered();
+
+            self._assert_not_paused();
+
+            self._accrue_interest();
+
             /* INPUT CHECK */
-            assert_fungible_res_address(assets.resource_address(), None);
+            assert!(
+                assets.resource_address() == self.liquidity.resource_address(),
+                "{}",
+                PoolError::ResourceMismatch
+            );
 
             let amount = assets.amount();
             self.liquidity.put(assets);
 
             if deposit_type == DepositType::FromTemporaryUse {
-                self.external_liquidity_amount -= amount;
+                if amount > self.external_liquidity_amount {
+                    // More than what was taken is returned: the excess is recorded as yield
+                    Runtime::emit_event(RepaymentMismatch {
+                        external_liquidity_amount: self.external_liquidity_amount,
+                        repaid_amount: amount,
+                    });
+
+                    self.external_liquidity_amount = 0.into();
+
+                    self._update_unit_to_asset_ratio();
+                } else {
+                    self.external_liquidity_amount -= amount;
+                }
             } else {
-                self.unit_to_asset_ratio = self._get_unit_to_asset_ratio();
+                self._update_unit_to_asset_ratio();
             }
         }
 
-        pub fn increase_external_liquidity(&mut self, amount: Decimal) {
+        /// Deposit resources that are not part of the pooled liquidity (e.g. treasury-style
+        /// holdings). They have no effect on the pool unit ratio.
+        pub fn deposit_other(&mut self, assets: Bucket) {
+            self.reentrancy_guard.assert_not_entered();
+
+            let res_address = assets.resource_address();
+
             assert!(
-                amount >= 0.into(),
-                "External liquidity amount must not be negative!"
+                res_address != self.liquidity.resource_address(),
+                "{}",
+                PoolError::ResourceMismatch
             );
 
+            let vault_exists = self.other_vaults.get(&res_address).is_some();
+
+            if vault_exists {
+                self.other_vaults.get_mut(&res_address).unwrap().put(assets);
+            } else {
+                self.other_vaults
+                    .insert(res_address, Vault::with_bucket(assets));
+            }
+        }
+
+        pub fn withdraw_other(&mut self, res_address: ResourceAddress, amount: Decimal) -> Bucket {
+            self.reentrancy_guard.assert_not_entered();
+
+            assert!(amount >= 0.into(), "{}", PoolError::NegativeAmount);
+
+            let mut vault = self
+                .other_vaults
+                .get_mut(&res_address)
+                .unwrap_or_else(|| panic!("{}", PoolError::ResourceNotFound));
+
+            vault.take(amount)
+        }
+
+        pub fn get_other_amount(&self, res_address: ResourceAddress) -> Decimal {
+            self.other_vaults
+                .get(&res_address)
+                .map(|vault| vault.amount())
+                .unwrap_or(dec!(0))
+        }
+
+        pub fn increase_external_liquidity(&mut self, amount: Decimal) {
+            self.reentrancy_guard.assert_not_entered();
+
+            self._assert_not_paused();
+
+            self._accrue_interest();
+
+            assert!(amount >= 0.into(), "{}", PoolError::NegativeAmount);
+
             self.external_liquidity_amount += amount;
 
-            self.unit_to_asset_ratio = self._get_unit_to_asset_ratio();
+            self._assert_external_liquidity_cap();
+
+            self._update_unit_to_asset_ratio();
         }
 
         pub fn decrease_external_liquidity(&mut self, amount: Decimal) {
+            self.reentrancy_guard.assert_not_entered();
+
+            self._assert_not_paused();
+
+            self._accrue_interest();
+
             /* INPUT CHECK */
-            assert!(
-                amount >= 0.into(),
-                "External liquidity amount must not be negative!"
-            );
+            assert!(amount >= 0.into(), "{}", PoolError::NegativeAmount);
             assert!(
                 amount <= self.external_liquidity_amount,
-                "Provided amount is greater than the external liquidity amount!"
+                "{}",
+                PoolError::ExternalLiquidityUnderflow
             );
 
             self.external_liquidity_amount -= amount;
 
-            self.unit_to_asset_ratio = self._get_unit_to_asset_ratio();
+            self._update_unit_to_asset_ratio();
+        }
+
+        pub fn queue_config_change(&mut self, change: ConfigChange) -> u64 {
+            /* INPUT CHECK */
+            AssetPool::_assert_valid_config_change(&change);
+
+            let change_id = self.next_config_change_id;
+            let executable_at_epoch =
+                Runtime::current_epoch().number() + self.config_timelock_epochs;
+
+            self.pending_config_changes.insert(
+                change_id,
+                PendingConfigChange {
+                    change: change.clone(),
+                    executable_at_epoch,
+                },
+            );
+            self.next_config_change_id += 1;
+
+            Runtime::emit_event(ConfigChangeQueued {
+                change_id,
+                change,
+                executable_at_epoch,
+            });
+
+            change_id
+        }
+
+        pub fn apply_config_change(&mut self, change_id: u64) {
+            let pending_change = self
+                .pending_config_changes
+                .get(&change_id)
+                .cloned()
+                .unwrap_or_else(|| panic!("{}", PoolError::ConfigChangeNotFound));
+
+            assert!(
+                Runtime::current_epoch().number() >= pending_change.executable_at_epoch,
+                "{}",
+                PoolError::ConfigChangeTimelocked
+            );
+
+            self.pending_config_changes.remove(&change_id);
+
+            match pending_change.change.clone() {
+                ConfigChange::MaxExternalLiquidityRatio(max_ratio) => {
+                    self.max_external_liquidity_ratio = max_ratio;
+                }
+                ConfigChange::CircuitBreakerThreshold(threshold) => {
+                    self.circuit_breaker_threshold = threshold;
+                }
+                ConfigChange::ConfigTimelockEpochs(epochs) => {
+                    self.config_timelock_epochs = epochs;
+                }
+                ConfigChange::ProtectedWithdrawEpochLimit(limit) => {
+                    self.protected_withdraw_epoch_limit = limit;
+                }
+                ConfigChange::MaxPoolUnitSupply(max_supply) => {
+                    self.max_pool_unit_supply = max_supply;
+                }
+                ConfigChange::ContributionBadge(badge) => {
+                    self.contribution_badge = badge;
+                }
+                ConfigChange::FlashloanFeeRate(fee_rate) => {
+                    self.flashloan_fee_rate = fee_rate;
+                }
+                ConfigChange::PauseExemptBadge(badge) => {
+                    self.pause_exempt_badge = badge;
+                }
+                ConfigChange::ExternalLiquidityInterestRate(rate_per_epoch) => {
+                    // Interest accrued so far uses the previous rate
+                    self._accrue_interest();
+
+                    self.external_liquidity_interest_rate = rate_per_epoch;
+                }
+            }
+
+            Runtime::emit_event(ConfigChangeApplied {
+                change_id,
+                change: pending_change.change,
+            });
+        }
+
+        pub fn cancel_config_change(&mut self, change_id: u64) {
+            let pending_change = self
+                .pending_config_changes
+                .remove(&change_id)
+                .unwrap_or_else(|| panic!("{}", PoolError::ConfigChangeNotFound));
+
+            Runtime::emit_event(ConfigChangeCancelled {
+                change_id,
+                change: pending_change.change,
+            });
+        }
+
+        pub fn get_pending_config_changes(&self) -> IndexMap<u64, PendingConfigChange> {
+            self.pending_config_changes.clone()
+        }
+
+        pub fn pause(&mut self) {
+            self.is_paused = true;
+        }
+
+        pub fn resume(&mut self) {
+            self.is_paused = false;
+        }
+
+        /// Add the external liquidity interest accrued since the last accrual. Methods using
+        /// the pool value accrue it first, getters value the external liquidity as of the last
+        /// accrual.
+        pub fn accrue_interest(&mut self) {
+            self.reentrancy_guard.assert_not_entered();
+            self._assert_not_paused();
+
+            self._accrue_interest();
         }
 
         pub fn take_flashloan(
@@ -315,19 +1058,21 @@ pub mod pool {
             loan_amount: Decimal,
             fee_amount: Decimal,
         ) -> (Bucket, Bucket) {
+            // The guard stays entered until `repay_flashloan`, so the components called with
+            // the loan can not call back into the state changing methods of the pool
+            self.reentrancy_guard.enter();
+
+            self._assert_not_paused();
+
             /* INPUT CHECK */
+            assert!(loan_amount > 0.into(), "{}", PoolError::InvalidLoanAmount);
+            assert!(fee_amount >= 0.into(), "{}", PoolError::NegativeAmount);
             assert!(
-                loan_amount > 0.into(),
-                "Loan amount must be greater than zero!"
-            );
-            assert!(
-                fee_amount >= 0.into(),
-                "Fee amount must be greater than zero!"
-            );
-            assert!(
-                loan_amount <= self.liquidity.amount(),
-                "Not enough liquidity to supply this loan!"
+                self.flash_mint_badge.is_some() || loan_amount <= self.liquidity.amount(),
+                "{}",
+                PoolError::InsufficientLiquidity
             );
+            self._assert_flashloan_fee(loan_amount, fee_amount);
 
             // Mint the loan term. it can be deposited to account so, it will need to be return with the repayment and burn for the transaction to be able to succeed
             let loan_terms =
@@ -337,47 +1082,519 @@ pub mod pool {
                         fee_amount,
                         loan_amount,
                     });
+            let loan = self._take_loan(loan_amount);
+
+            (loan, loan_terms)
+        }
+
+        pub fn repay_flashloan(&mut self, loan_repayment: Bucket, loan_terms: Bucket) -> Bucket {
+            /* INPUT CHECK */
+            assert_fungible_res_address(
+                loan_repayment.resource_address(),
+                Some(PoolError::ResourceNotFungible.to_string()),
+            );
+            assert_non_fungible_res_address(
+                loan_terms.resource_address(),
+                Some(PoolError::ResourceNotNonFungible.to_string()),
+            );
+            assert!(
+                loan_terms.resource_address() == self.flashloan_term_res_manager.address(),
+                "{}",
+                PoolError::ResourceMismatch
+            );
+
+            let terms: FlashloanTerm = loan_terms.as_non_fungible().non_fungible().data();
+
+            let change =
+                self._settle_repayment(loan_repayment, terms.loan_amount, terms.fee_amount);
+
+            //Burn the transient token
+            loan_terms.burn();
+
+            self.reentrancy_guard.exit();
+
+            //Return the change to the work top
+            change
+        }
+
+        /// Alternative to the transient loan term pattern for borrowers that are components
+        /// rather than manifests. The loan is passed to the `on_flashloan` method of the
+        /// borrower component, and the repayment it returns is verified before this method
+        /// returns. The change of the repayment is returned to the caller.
+        pub fn flashloan_and_call(
+            &mut self,
+            borrower: ComponentAddress,
+            loan_amount: Decimal,
+            fee_amount: Decimal,
+            callback_args: ScryptoValue,
+        ) -> Bucket {
+            self.reentrancy_guard.assert_not_entered();
+
+            self._assert_not_paused();
+
+            /* INPUT CHECK */
+            assert!(loan_amount > 0.into(), "{}", PoolError::InvalidLoanAmount);
+            assert!(fee_amount >= 0.into(), "{}", PoolError::NegativeAmount);
+            assert!(
+                self.flash_mint_badge.is_some() || loan_amount <= self.liquidity.amount(),
+                "{}",
+                PoolError::InsufficientLiquidity
+            );
+            self._assert_flashloan_fee(loan_amount, fee_amount);
+
+            let loan = self._take_loan(loan_amount);
+
+            // The borrower can not call back into the state changing methods of the pool
+            let loan_repayment = self.reentrancy_guard.scope(|| {
+                Global::<AnyComponent>::from(borrower).call_raw::<Bucket>(
+                    FLASHLOAN_CALLBACK_METHOD,
+                    scrypto_args!(loan, fee_amount, callback_args),
+                )
+            });
+
+            self._settle_repayment(loan_repayment, loan_amount, fee_amount)
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        // `component_rule` is the only rule allowed to move soulbound pool units: the owning
+        // component for local pools and the pool component itself for global ones
+        fn _instantiate_locally(
+            pool_res_address: ResourceAddress,
+            config: PoolConfig,
+            component_rule: AccessRule,
+            flash_mint_badge: Option<Bucket>,
+        ) -> (Owned<AssetPool>, ResourceAddress, ResourceAddress) {
+            /* CHECK INPUTS */
+            assert_fungible_res_address(
+                pool_res_address,
+                Some(PoolError::ResourceNotFungible.to_string()),
+            );
+
+            config
+                .as_config_changes()
+                .iter()
+                .for_each(AssetPool::_assert_valid_config_change);
+
+            if let Some(badge) = &flash_mint_badge {
+                assert_fungible_res_address(
+                    badge.resource_address(),
+                    Some(PoolError::ResourceNotFungible.to_string()),
+                );
+            }
+
+            let divisibility = match ResourceManager::from_address(pool_res_address).resource_type()
+            {
+                ResourceType::Fungible { divisibility } => divisibility,
+                _ => panic!("{}", PoolError::ResourceNotFungible),
+            };
+
+            let pool_unit_res_manager = ResourceBuilder::new_fungible(config.owner_role.clone())
+                .divisibility(divisibility)
+                .metadata(resource_metadata(config.dapp_definition))
+                .mint_roles(mint_roles! {
+                    minter => component_rule.clone();
+                    minter_updater => rule!(deny_all);
+                })
+                .burn_roles(burn_roles! {
+                    burner => component_rule.clone();
+                    burner_updater => rule!(deny_all);
+                })
+                .withdraw_roles(if config.soulbound_pool_units {
+                    withdraw_roles! {
+                        withdrawer => component_rule.clone();
+                        withdrawer_updater => rule!(deny_all);
+                    }
+                } else {
+                    None
+                })
+                .deposit_roles(if config.soulbound_pool_units {
+                    deposit_roles! {
+                        depositor => component_rule.clone();
+                        depositor_updater => rule!(deny_all);
+                    }
+                } else {
+                    None
+                })
+                .create_with_no_initial_supply();
+
+            let flashloan_term_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<FlashloanTerm>(config.owner_role.clone())
+                    .metadata(resource_metadata(config.dapp_definition))
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule.clone();
+                        burner_updater => rule!(deny_all);
+                    })
+                    // ! critical
+                    .deposit_roles(deposit_roles! {
+                        depositor => rule!(deny_all);
+                        depositor_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let pool_component = Self {
+                liquidity: Vault::new(pool_res_address),
+                divisibility,
+                flashloan_term_res_manager,
+                pool_unit_res_manager,
+                external_liquidity_amount: 0.into(),
+                flashloan_outstanding_amount: 0.into(),
+                other_vaults: KeyValueStore::new(),
+                unit_to_asset_ratio: 1.into(),
+                flash_mint_badge: flash_mint_badge.map(Vault::with_bucket),
+                max_external_liquidity_ratio: config.max_external_liquidity_ratio,
+                circuit_breaker_threshold: config.circuit_breaker_threshold,
+                is_paused: false,
+                ratio_observations: vec![RatioObservation {
+                    epoch: Runtime::current_epoch().number(),
+                    ratio: 1.into(),
+                }],
+                config_timelock_epochs: config.config_timelock_epochs,
+                pending_config_changes: IndexMap::default(),
+                next_config_change_id: 0,
+                protected_withdraw_epoch_limit: config.protected_withdraw_epoch_limit,
+                protected_withdraw_epoch_usage: (Runtime::current_epoch().number(), 0.into()),
+                max_pool_unit_supply: config.max_pool_unit_supply,
+                contribution_badge: config.contribution_badge,
+                flashloan_fee_rate: config.flashloan_fee_rate,
+                pause_exempt_badge: config.pause_exempt_badge,
+                external_liquidity_interest_rate: config.external_liquidity_interest_rate,
+                interest_accrual_mode: config.interest_accrual_mode,
+                last_interest_accrual_epoch: Runtime::current_epoch().number(),
+                allowances: KeyValueStore::new(),
+                next_allowance_id: 0,
+                soulbound_positions: KeyValueStore::new(),
+                reentrancy_guard: ReentrancyGuard::new(),
+            }
+            .instantiate();
+
             (
-                self.liquidity
-                    .take_advanced(loan_amount, WithdrawStrategy::Rounded(RoundingMode::ToZero)),
-                loan_terms,
+                pool_component,
+                pool_unit_res_manager.address(),
+                flashloan_term_res_manager.address(),
             )
         }
 
-        pub fn repay_flashloan(
+        fn _instantiate(
+            pool_res_address: ResourceAddress,
+            config: PoolConfig,
+            flash_mint_badge: Option<Bucket>,
+            address_reservation: Option<GlobalAddressReservation>,
+        ) -> (Global<AssetPool>, ResourceAddress, ResourceAddress) {
+            let owner_role = config.owner_role.clone();
+            let admin_rule = config.admin_rule.clone();
+            let dapp_definition = config.dapp_definition;
+
+            let (address_reservation, component_address) = match address_reservation {
+                Some(address_reservation) => {
+                    let component_address = ComponentAddress::new_or_panic(
+                        Runtime::get_reservation_address(&address_reservation)
+                            .as_node_id()
+                            .0,
+                    );
+
+                    (address_reservation, component_address)
+                }
+                None => Runtime::allocate_component_address(AssetPool::blueprint_id()),
+            };
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let (owned_pool_component, pool_unit_res_manager, flashloan_term_res_manager) =
+                AssetPool::_instantiate_locally(
+                    pool_res_address,
+                    config,
+                    component_rule,
+                    flash_mint_badge,
+                );
+
+            let pool_component = owned_pool_component
+                .prepare_to_globalize(owner_role)
+                .roles(roles!(
+                    admin => admin_rule;
+                ))
+                .metadata(component_metadata(dapp_definition))
+                .with_address(address_reservation)
+                .globalize();
+
+            (
+                pool_component,
+                pool_unit_res_manager,
+                flashloan_term_res_manager,
+            )
+        }
+
+        fn _take_loan(&mut self, loan_amount: Decimal) -> Bucket {
+            if self.flash_mint_badge.is_some() {
+                self._mint_pooled_resource(loan_amount)
+            } else {
+                self.flashloan_outstanding_amount += loan_amount;
+
+                self.liquidity
+                    .take_advanced(loan_amount, WithdrawStrategy::Rounded(RoundingMode::ToZero))
+            }
+        }
+
+        // Put the amount due back into the pool and return the change of the repayment
+        fn _settle_repayment(
             &mut self,
             mut loan_repayment: Bucket,
-            loan_terms: Bucket,
+            loan_amount: Decimal,
+            fee_amount: Decimal,
         ) -> Bucket {
-            /* INPUT CHECK */
-            assert_fungible_res_address(loan_repayment.resource_address(), None);
-            assert_non_fungible_res_address(loan_terms.resource_address(), None);
+            assert!(
+                loan_repayment.resource_address() == self.liquidity.resource_address(),
+                "{}",
+                PoolError::ResourceMismatch
+            );
 
             // Verify we are being sent at least the amount due
-            let terms: FlashloanTerm = loan_terms.as_non_fungible().non_fungible().data();
-            let amount_due = terms.fee_amount + terms.loan_amount;
+            let amount_due = fee_amount + loan_amount;
             assert!(
                 loan_repayment.amount() >= amount_due,
-                "Insufficient repayment given for your loan!"
+                "{}",
+                PoolError::InsufficientRepayment
             );
 
             // put the repayment back into the pool
-            self.liquidity.put(
-                loan_repayment
-                    .take_advanced(amount_due, WithdrawStrategy::Rounded(RoundingMode::ToZero)),
-            );
+            let mut repayment = loan_repayment
+                .take_advanced(amount_due, WithdrawStrategy::Rounded(RoundingMode::ToZero));
 
-            //Burn the transient token
-            loan_terms.burn();
+            // Flash-minted principal is burned, only the fee stays in the pool
+            if self.flash_mint_badge.is_some() {
+                self._burn_pooled_resource(repayment.take(loan_amount));
+            } else {
+                self.flashloan_outstanding_amount -= loan_amount;
+            }
+
+            self.liquidity.put(repayment);
 
-            //Return the change to the work top
             loan_repayment
         }
 
-        /* PRIVATE UTILITY METHODS */
+        fn _mint_pooled_resource(&self, amount: Decimal) -> Bucket {
+            let badge_vault = self.flash_mint_badge.as_ref().unwrap();
+            let pool_res_manager = ResourceManager::from_address(self.liquidity.resource_address());
 
-        fn _get_unit_to_asset_ratio(&mut self) -> PreciseDecimal {
-            let total_liquidity_amount = self.liquidity.amount() + self.external_liquidity_amount;
+            badge_vault
+                .as_fungible()
+                .authorize_with_amount(badge_vault.amount(), || pool_res_manager.mint(amount))
+        }
+
+        fn _burn_pooled_resource(&self, assets: Bucket) {
+            let badge_vault = self.flash_mint_badge.as_ref().unwrap();
+
+            badge_vault
+                .as_fungible()
+                .authorize_with_amount(badge_vault.amount(), || assets.burn());
+        }
+
+        fn _assert_external_liquidity_cap(&self) {
+            if let Some(max_ratio) = self.max_external_liquidity_ratio {
+                let total_liquidity_amount = self._get_total_liquidity_amount();
+
+                assert!(
+                    self.external_liquidity_amount <= total_liquidity_amount * max_ratio,
+                    "{}",
+                    PoolError::ExternalLiquidityCapExceeded
+                );
+            }
+        }
+
+        fn _assert_valid_config_change(change: &ConfigChange) {
+            match change {
+                ConfigChange::MaxExternalLiquidityRatio(Some(max_ratio)) => {
+                    assert!(
+                        *max_ratio >= 0.into() && *max_ratio <= 1.into(),
+                        "{}",
+                        PoolError::InvalidParameter
+                    );
+                }
+                ConfigChange::CircuitBreakerThreshold(Some(threshold)) => {
+                    assert!(*threshold > 0.into(), "{}", PoolError::InvalidParameter);
+                }
+                ConfigChange::ProtectedWithdrawEpochLimit(Some(limit)) => {
+                    assert!(*limit >= 0.into(), "{}", PoolError::InvalidParameter);
+                }
+                ConfigChange::MaxPoolUnitSupply(Some(max_supply)) => {
+                    assert!(*max_supply >= 0.into(), "{}", PoolError::InvalidParameter);
+                }
+                ConfigChange::ExternalLiquidityInterestRate(rate_per_epoch) => {
+                    assert!(
+                        *rate_per_epoch >= 0.into(),
+                        "{}",
+                        PoolError::InvalidParameter
+                    );
+                }
+                ConfigChange::FlashloanFeeRate(fee_rate) => {
+                    assert!(
+                        *fee_rate >= 0.into() && *fee_rate <= 1.into(),
+                        "{}",
+                        PoolError::InvalidParameter
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        fn _track_protected_withdraw(&mut self, amount: Decimal) {
+            let current_epoch = Runtime::current_epoch().number();
+
+            // Usage is reset at the start of every epoch
+            if self.protected_withdraw_epoch_usage.0 != current_epoch {
+                self.protected_withdraw_epoch_usage = (current_epoch, 0.into());
+            }
+
+            self.protected_withdraw_epoch_usage.1 += amount;
+
+            if let Some(limit) = self.protected_withdraw_epoch_limit {
+                assert!(
+                    self.protected_withdraw_epoch_usage.1 <= limit,
+                    "{}",
+                    PoolError::EpochWithdrawLimitExceeded
+                );
+            }
+        }
+
+        // The badge proof must be in the auth zone of the caller: the admin component is
+        // expected to push its user's proof before calling `contribute` or `redeem`
+        fn _assert_contribution_allowed(&self) {
+            if let Some(badge) = self.contribution_badge {
+                Runtime::assert_access_rule(rule!(require(badge)));
+            }
+        }
+
+        // While the pool is paused, only the callers with the pause exempt badge in their auth
+        // zone can use it
+        fn _assert_not_paused(&self) {
+            if !self.is_paused {
+                return;
+            }
+
+            match self.pause_exempt_badge {
+                Some(badge) => Runtime::assert_access_rule(rule!(require(badge))),
+                None => panic!("{}", PoolError::PoolPaused),
+            }
+        }
+
+        fn _assert_flashloan_fee(&self, loan_amount: Decimal, fee_amount: Decimal) {
+            let min_fee_amount = (loan_amount * self.flashloan_fee_rate)
+                .checked_round(self.divisibility, RoundingMode::AwayFromZero)
+                .unwrap();
+
+            assert!(
+                fee_amount >= min_fee_amount,
+                "{}",
+                PoolError::FlashloanFeeTooLow
+            );
+        }
+
+        // Add the interest accrued since the last accrual to the external liquidity amount,
+        // which increases the value of the pool units
+        fn _accrue_interest(&mut self) {
+            let current_epoch = Runtime::current_epoch().number();
+            let elapsed_epochs = current_epoch - self.last_interest_accrual_epoch;
+
+            if elapsed_epochs == 0 {
+                return;
+            }
+
+            self.last_interest_accrual_epoch = current_epoch;
+
+            let growth_factor = self
+                .interest_accrual_mode
+                .growth_factor(self.external_liquidity_interest_rate, elapsed_epochs);
+            let interest = (self.external_liquidity_amount * (growth_factor - Decimal::ONE))
+                .checked_round(self.divisibility, RoundingMode::ToZero)
+                .unwrap();
+
+            if interest > 0.into() {
+                self.external_liquidity_amount += interest;
+
+                self._update_unit_to_asset_ratio();
+            }
+        }
+
+        fn _update_unit_to_asset_ratio(&mut self) {
+            let previous_ratio = self.unit_to_asset_ratio;
+            let new_ratio = self._get_unit_to_asset_ratio();
+
+            self.unit_to_asset_ratio = new_ratio;
+
+            self._record_ratio_observation(new_ratio);
+
+            // Trip the circuit breaker without reverting the operation so the pause persists
+            if let Some(threshold) = self.circuit_breaker_threshold {
+                if previous_ratio == 0.into() {
+                    return;
+                }
+
+                let ratio_change = if new_ratio > previous_ratio {
+                    new_ratio - previous_ratio
+                } else {
+                    previous_ratio - new_ratio
+                };
+
+                if ratio_change / previous_ratio > PreciseDecimal::from(threshold) {
+                    self.is_paused = true;
+
+                    Runtime::emit_event(CircuitBreakerTripped {
+                        previous_ratio,
+                        new_ratio,
+                    });
+                }
+            }
+        }
+
+        fn _record_ratio_observation(&mut self, ratio: PreciseDecimal) {
+            let epoch = Runtime::current_epoch().number();
+
+            match self.ratio_observations.last_mut() {
+                Some(last_observation) if last_observation.epoch == epoch => {
+                    last_observation.ratio = ratio;
+                }
+                _ => {
+                    if self.ratio_observations.len() >= MAX_RATIO_OBSERVATIONS {
+                        self.ratio_observations.remove(0);
+                    }
+
+                    self.ratio_observations
+                        .push(RatioObservation { epoch, ratio });
+                }
+            }
+        }
+
+        fn _get_units_for_amount(&self, amount: Decimal) -> Decimal {
+            checked_mul_to_decimal(
+                amount,
+                self.unit_to_asset_ratio,
+                self.divisibility,
+                RoundingMode::ToZero,
+            )
+        }
+
+        fn _get_amount_for_units(&self, units: Decimal, rounding_mode: RoundingMode) -> Decimal {
+            checked_div_to_decimal(
+                units,
+                self.unit_to_asset_ratio,
+                self.divisibility,
+                rounding_mode,
+            )
+        }
+
+        // Pooled amount including liquidity used outside the pool and flashloans not yet repaid
+        fn _get_total_liquidity_amount(&self) -> Decimal {
+            self.liquidity.amount()
+                + self.external_liquidity_amount
+                + self.flashloan_outstanding_amount
+        }
+
+        fn _get_unit_to_asset_ratio(&self) -> PreciseDecimal {
+            let total_liquidity_amount = self._get_total_liquidity_amount();
 
             let total_supply = self.pool_unit_res_manager.total_supply().unwrap_or(dec!(0));
 
@@ -391,3 +1608,9 @@ pub mod pool {
         }
     }
 }
+
+// Global AssetPool components, flash-mint ones included, implement the standard pool interface
+asset_pool_interface::impl_single_resource_pool!(pool::AssetPool);
+
+// Fails to compile when the methods no longer match the signatures called by `AssetPoolStub`
+asset_pool_interface::assert_asset_pool_signatures!(pool::AssetPool);
@@ -0,0 +1,245 @@
+use asset_pool_interface::*;
+use scrypto::prelude::*;
+
+/// Calls the trait methods through a generic pool, as routers and vaults do. The pool must be
+/// empty and the admin role satisfied by the caller. Returns the remaining assets and pool units.
+fn exercise_pool_interface<P: SingleResourcePoolInterface>(
+    pool: &P,
+    mut assets: Bucket,
+) -> Vec<Bucket> {
+    assert_eq!(pool.get_pool_unit_ratio(), PreciseDecimal::ONE);
+    assert_eq!(pool.get_units_for_amount(dec!(10)), dec!(10));
+    assert_eq!(pool.get_amount_for_units(dec!(10)), dec!(10));
+    assert_eq!(pool.get_flashloan_fee_rate(), dec!(0));
+
+    let (mut pool_units, change) = pool.contribute(assets.take(60));
+    assert_eq!(pool_units.amount(), dec!(60));
+    assert!(change.is_empty());
+    assert_eq!(pool.get_pooled_amount(), (dec!(60), dec!(0)));
+    assert_eq!(pool.get_flashloan_capacity(), Some(dec!(60)));
+
+    let (mut loan, loan_terms) = pool.take_flashloan(dec!(10), dec!(1));
+    assert_eq!(loan.amount(), dec!(10));
+    assert_eq!(pool.get_pooled_amount(), (dec!(50), dec!(0)));
+
+    loan.put(assets.take(1));
+    let repayment_change = pool.repay_flashloan(loan, loan_terms);
+    assert!(repayment_change.is_empty());
+
+    // The fee stays in the pool
+    assert_eq!(pool.get_pooled_amount(), (dec!(61), dec!(0)));
+
+    let expected_amount = pool.get_amount_for_units(dec!(30));
+    let (redeemed, remaining_units) = pool.redeem(pool_units.take(30));
+    assert_eq!(redeemed.amount(), expected_amount);
+
+    vec![
+        assets,
+        pool_units,
+        change,
+        repayment_change,
+        redeemed,
+        remaining_units,
+    ]
+}
+
+#[blueprint]
+mod asset_pool_probe {
+    struct AssetPoolProbe {
+        pool: AssetPoolStub,
+
+        /// Satisfies the owner and admin roles of the pool
+        admin_badge: Vault,
+
+        /// Holder and spender of the allowances and soulbound positions
+        holder_badge: Vault,
+        holder: NonFungibleGlobalId,
+    }
+
+    impl AssetPoolProbe {
+        pub fn instantiate(
+            pool: ComponentAddress,
+            admin_badge: Bucket,
+            holder_badge: Bucket,
+        ) -> Global<AssetPoolProbe> {
+            let holder = NonFungibleGlobalId::new(
+                holder_badge.resource_address(),
+                holder_badge.as_non_fungible().non_fungible_local_id(),
+            );
+
+            Self {
+                pool: AssetPoolStub::from(pool),
+                admin_badge: Vault::with_bucket(admin_badge),
+                holder_badge: Vault::with_bucket(holder_badge),
+                holder,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .globalize()
+        }
+
+        /// Methods of `SingleResourcePoolInterface` and the ratio and supply getters, on an empty
+        /// pool
+        pub fn probe_pool_interface(&mut self, assets: Bucket) -> Vec<Bucket> {
+            let pool = self.pool;
+
+            self.admin_badge.as_fungible().authorize_with_amount(1, || {
+                assert_eq!(pool.get_current_pool_unit_ratio(), PreciseDecimal::ONE);
+
+                let buckets = exercise_pool_interface(&pool, assets);
+                assert_eq!(pool.get_pool_unit_supply(), dec!(30));
+
+                buckets
+            })
+        }
+
+        /// Methods restricted to the admin role and their getters. `borrower` is a component
+        /// paying the flashloan fee from its `on_flashloan` callback.
+        pub fn probe_admin_methods(
+            &mut self,
+            mut assets: Bucket,
+            other: Bucket,
+            borrower: ComponentAddress,
+        ) -> Vec<Bucket> {
+            let pool = self.pool;
+            let holder = self.holder.clone();
+            let holder_badge = self.holder_badge.as_non_fungible();
+
+            self.admin_badge.as_fungible().authorize_with_amount(1, || {
+                let (mut pool_units, change) = pool.contribute(assets.take(50));
+                let (pooled, external) = pool.get_pooled_amount();
+
+                // Temporary use of the liquidity
+                let borrowed = pool.protected_withdraw(
+                    dec!(10),
+                    WithdrawType::ForTemporaryUse,
+                    WithdrawStrategy::Exact,
+                );
+                assert_eq!(borrowed.amount(), dec!(10));
+                assert_eq!(
+                    pool.get_pooled_amount(),
+                    (pooled - dec!(10), external + dec!(10))
+                );
+
+                pool.protected_deposit(borrowed, DepositType::FromTemporaryUse);
+                assert_eq!(pool.get_pooled_amount(), (pooled, external));
+
+                pool.increase_external_liquidity(dec!(5));
+                assert_eq!(pool.get_pooled_amount(), (pooled, external + dec!(5)));
+
+                pool.decrease_external_liquidity(dec!(5));
+                assert_eq!(pool.get_pooled_amount(), (pooled, external));
+
+                // Without interest rate, accruals do not change the external liquidity
+                pool.accrue_interest();
+                assert_eq!(pool.get_pooled_amount(), (pooled, external));
+
+                // Resources kept apart from the liquidity
+                let other_address = other.resource_address();
+                let other_amount = other.amount();
+
+                pool.deposit_other(other);
+                assert_eq!(pool.get_other_amount(other_address), other_amount);
+
+                let other = pool.withdraw_other(other_address, other_amount);
+                assert_eq!(other.amount(), other_amount);
+                assert_eq!(pool.get_other_amount(other_address), dec!(0));
+
+                let expected_amount = pool.get_amount_for_units(dec!(10));
+                let (redeemed, remaining_units) = pool.redeem_advanced(
+                    pool_units.take(10),
+                    WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                );
+                assert_eq!(redeemed.amount(), expected_amount);
+
+                // The borrower pays the fee of the loan
+                let (pooled, _) = pool.get_pooled_amount();
+                let loan_change = pool.flashloan_and_call(
+                    borrower,
+                    dec!(10),
+                    dec!(1),
+                    ScryptoValue::Tuple { fields: vec![] },
+                );
+                assert_eq!(pool.get_pooled_amount().0, pooled + dec!(1));
+
+                let allowance_id =
+                    pool.grant_allowance(holder.clone(), holder.clone(), pool_units.take(10));
+                assert_eq!(
+                    pool.get_allowance(allowance_id),
+                    (holder.clone(), holder.clone(), dec!(10))
+                );
+
+                let soulbound_change = pool.contribute_soulbound(assets.take(10), holder.clone());
+                let soulbound_units = pool.get_soulbound_units(holder.clone());
+                assert!(soulbound_units > dec!(0));
+
+                // Spender and holder methods check the holder badge
+                let (allowance_assets, allowance_units, revoked_units, soulbound_assets) =
+                    holder_badge.authorize_with_non_fungibles(
+                        &indexset!(holder.local_id().clone()),
+                        || {
+                            let allowance_assets =
+                                pool.redeem_with_allowance(allowance_id, dec!(4));
+                            let (_, _, allowance_units) = pool.get_allowance(allowance_id);
+                            let revoked_units = pool.revoke_allowance(allowance_id);
+                            let soulbound_assets =
+                                pool.redeem_soulbound(holder.clone(), soulbound_units);
+
+                            (
+                                allowance_assets,
+                                allowance_units,
+                                revoked_units,
+                                soulbound_assets,
+                            )
+                        },
+                    );
+                assert!(allowance_assets.amount() > dec!(0));
+                assert!(allowance_units < dec!(10));
+                assert_eq!(revoked_units.amount(), allowance_units);
+                assert!(soulbound_assets.amount() > dec!(0));
+                assert_eq!(pool.get_soulbound_units(holder.clone()), dec!(0));
+
+                vec![
+                    assets,
+                    pool_units,
+                    change,
+                    other,
+                    redeemed,
+                    remaining_units,
+                    loan_change,
+                    soulbound_change,
+                    allowance_assets,
+                    revoked_units,
+                    soulbound_assets,
+                ]
+            })
+        }
+
+        /// Methods restricted to the owner role and their getters
+        pub fn probe_owner_methods(&mut self) {
+            let pool = self.pool;
+
+            self.admin_badge.as_fungible().authorize_with_amount(1, || {
+                let change_id =
+                    pool.queue_config_change(ConfigChange::MaxPoolUnitSupply(Some(dec!(1000))));
+                assert!(pool.get_pending_config_changes().contains_key(&change_id));
+
+                pool.apply_config_change(change_id);
+                assert!(pool.get_pending_config_changes().is_empty());
+
+                let change_id = pool.queue_config_change(ConfigChange::MaxPoolUnitSupply(None));
+                pool.cancel_config_change(change_id);
+                assert!(pool.get_pending_config_changes().is_empty());
+
+                pool.pause();
+                assert!(pool.is_paused());
+
+                pool.resume();
+                assert!(!pool.is_paused());
+
+                // The pool has no observation before the window
+                assert_eq!(pool.get_ratio_twap(10), None);
+            })
+        }
+    }
+}
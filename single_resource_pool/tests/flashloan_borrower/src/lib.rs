@@ -0,0 +1,43 @@
+use scrypto::prelude::*;
+
+#[blueprint]
+mod flashloan_borrower {
+    struct FlashloanBorrower {
+        /// Pays the flashloan fees
+        fees: Vault,
+
+        /// Pool called back from `on_flashloan`, to check the reentrancy protection
+        reentrant_pool: Option<ComponentAddress>,
+    }
+
+    impl FlashloanBorrower {
+        pub fn instantiate(
+            fees: Bucket,
+            reentrant_pool: Option<ComponentAddress>,
+        ) -> Global<FlashloanBorrower> {
+            Self {
+                fees: Vault::with_bucket(fees),
+                reentrant_pool,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .globalize()
+        }
+
+        pub fn on_flashloan(
+            &mut self,
+            mut loan: Bucket,
+            fee_amount: Decimal,
+            _callback_args: ScryptoValue,
+        ) -> Bucket {
+            if let Some(pool) = self.reentrant_pool {
+                Global::<AnyComponent>::from(pool)
+                    .call_raw::<()>("increase_external_liquidity", scrypto_args!(Decimal::ZERO));
+            }
+
+            loan.put(self.fees.take(fee_amount));
+
+            loan
+        }
+    }
+}
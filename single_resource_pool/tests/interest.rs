@@ -0,0 +1,57 @@
+use scrypto::prelude::*;
+use single_asset_pool::InterestAccrualMode;
+
+#[test]
+fn test_linear_growth_factor() {
+    assert_eq!(
+        InterestAccrualMode::Linear.growth_factor(dec!("0.0001"), 1000),
+        dec!("1.1")
+    );
+    assert_eq!(
+        InterestAccrualMode::Linear.growth_factor(dec!("0.0001"), 0),
+        Decimal::ONE
+    );
+}
+
+// References computed with 60 significant digits and truncated to 18 decimal places
+#[test]
+fn test_continuous_growth_factor() {
+    let mode = InterestAccrualMode::Continuous;
+
+    assert_eq!(mode.growth_factor(dec!("0.0001"), 0), Decimal::ONE);
+    assert_eq!(
+        mode.growth_factor(dec!("0.00002"), 1),
+        dec!("1.000020000200001333")
+    );
+    assert_eq!(
+        mode.growth_factor(dec!("0.0001"), 1000),
+        dec!("1.105170918075647624")
+    );
+    assert_eq!(
+        mode.growth_factor(dec!("0.000001"), 105120),
+        dec!("1.110843903626384205")
+    );
+    assert_eq!(
+        mode.growth_factor(dec!("0.01"), 365),
+        dec!("38.474666049032124417")
+    );
+}
+
+#[test]
+fn test_continuous_growth_factor_does_not_depend_on_accrual_frequency() {
+    let mode = InterestAccrualMode::Continuous;
+    let rate = dec!("0.0001");
+
+    let single_accrual = mode.growth_factor(rate, 1000);
+    let ten_accruals = (0..10).fold(Decimal::ONE, |index, _| {
+        index * mode.growth_factor(rate, 100)
+    });
+
+    let difference = if single_accrual > ten_accruals {
+        single_accrual - ten_accruals
+    } else {
+        ten_accruals - single_accrual
+    };
+
+    assert!(difference < dec!("0.000000000000001"));
+}
@@ -1 +1,1642 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use single_asset_pool::{ConfigChange, ConfigChangeCancelled, PoolError};
+use test_support::*;
+use transaction::prelude::*;
 
+/// Manifest encoding of the `PoolConfig` struct of the package, fields in the same order
+#[derive(ManifestSbor, Clone)]
+struct TestPoolConfig {
+    owner_role: OwnerRole,
+    admin_rule: AccessRule,
+    max_external_liquidity_ratio: Option<Decimal>,
+    circuit_breaker_threshold: Option<Decimal>,
+    config_timelock_epochs: u64,
+    protected_withdraw_epoch_limit: Option<Decimal>,
+    max_pool_unit_supply: Option<Decimal>,
+    flashloan_fee_rate: Decimal,
+    external_liquidity_interest_rate: Decimal,
+    interest_accrual_mode: TestInterestAccrualMode,
+    dapp_definition: Option<ComponentAddress>,
+    contribution_badge: Option<ResourceAddress>,
+    pause_exempt_badge: Option<ResourceAddress>,
+    soulbound_pool_units: bool,
+}
+
+/// Manifest encoding of the `ConfigChange` enum, variants in the same order
+#[derive(ManifestSbor)]
+#[allow(dead_code)]
+enum TestConfigChange {
+    MaxExternalLiquidityRatio(Option<Decimal>),
+    CircuitBreakerThreshold(Option<Decimal>),
+    ConfigTimelockEpochs(u64),
+    ProtectedWithdrawEpochLimit(Option<Decimal>),
+    MaxPoolUnitSupply(Option<Decimal>),
+    ContributionBadge(Option<ResourceAddress>),
+    FlashloanFeeRate(Decimal),
+    PauseExemptBadge(Option<ResourceAddress>),
+    ExternalLiquidityInterestRate(Decimal),
+}
+
+/// Manifest encoding of the `InterestAccrualMode` enum
+#[derive(ManifestSbor)]
+enum TestInterestAccrualMode {
+    Linear,
+    Continuous,
+}
+
+impl TestPoolConfig {
+    /// Defaults of `PoolConfig::new`, with an admin rule open to everyone
+    fn new(owner_role: OwnerRole) -> Self {
+        Self {
+            owner_role,
+            admin_rule: rule!(allow_all),
+            max_external_liquidity_ratio: None,
+            circuit_breaker_threshold: None,
+            config_timelock_epochs: 0,
+            protected_withdraw_epoch_limit: None,
+            max_pool_unit_supply: None,
+            flashloan_fee_rate: dec!(0),
+            external_liquidity_interest_rate: dec!(0),
+            interest_accrual_mode: TestInterestAccrualMode::Linear,
+            dapp_definition: None,
+            contribution_badge: None,
+            pause_exempt_badge: None,
+            soulbound_pool_units: false,
+        }
+    }
+}
+
+/// Pool instantiated with `instantiate_with_config`, owned by the key of `account`, which
+/// holds 1000 units of the pooled asset. The config can be customized knowing the account
+struct TestPool {
+    env: TestEnv,
+    account: ComponentAddress,
+    asset: ResourceAddress,
+    pool: ComponentAddress,
+    pool_unit: ResourceAddress,
+    flashloan_term: ResourceAddress,
+}
+
+impl TestPool {
+    fn new(divisibility: u8) -> Self {
+        Self::with_config(divisibility, |config, _| config)
+    }
+
+    fn with_config(
+        divisibility: u8,
+        customize: impl FnOnce(TestPoolConfig, ComponentAddress) -> TestPoolConfig,
+    ) -> Self {
+        let mut env = TestEnv::new(this_package!(), 1);
+        let account = env.accounts[0];
+        let asset = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), divisibility, account);
+
+        let config = customize(
+            TestPoolConfig::new(OwnerRole::Fixed(rule!(require(env.badges[0].clone())))),
+            account,
+        );
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "AssetPool",
+                "instantiate_with_config",
+                manifest_args!(asset, config),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+        let pool = commit.new_component_addresses()[0];
+        let pool_unit = commit.new_resource_addresses()[0];
+        let flashloan_term = commit.new_resource_addresses()[1];
+
+        Self {
+            env,
+            account,
+            asset,
+            pool,
+            pool_unit,
+            flashloan_term,
+        }
+    }
+
+    fn proofs(&self) -> Vec<NonFungibleGlobalId> {
+        vec![self.env.badges[0].clone()]
+    }
+
+    /// Execute the manifest with the account key, then deposit the worktop into the account
+    fn execute(&mut self, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute(0, builder)
+    }
+
+    fn call(&mut self, method: &str, args: impl ResolvableArguments) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_method(self.pool, method, args);
+
+        self.execute(builder)
+    }
+
+    /// Output of a read-only pool method
+    /// Events of the given name emitted by a successful transaction
+    fn events<T: ScryptoDecode>(&self, receipt: &TransactionReceipt, name: &str) -> Vec<T> {
+        receipt
+            .expect_commit_success()
+            .application_events
+            .iter()
+            .filter(|(event_type, _)| self.env.test_runner.event_name(event_type) == name)
+            .map(|(_, event)| scrypto_decode(event).unwrap())
+            .collect()
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str, args: impl ResolvableArguments) -> T {
+        let manifest = ManifestBuilder::new()
+            .call_method(self.pool, method, args)
+            .build();
+
+        self.env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![])
+            .expect_commit_success()
+            .output(0)
+    }
+
+    fn contribute(&mut self, amount: Decimal) -> TransactionReceipt {
+        let builder = self.send_to_pool(self.asset, amount, "contribute");
+
+        self.execute(builder)
+    }
+
+    fn redeem(&mut self, units: Decimal) -> TransactionReceipt {
+        let builder = self.send_to_pool(self.pool_unit, units, "redeem");
+
+        self.execute(builder)
+    }
+
+    /// Withdraw `amount` of `res_address` from the account and pass it to a pool method
+    /// taking a single bucket
+    fn send_to_pool(
+        &self,
+        res_address: ResourceAddress,
+        amount: Decimal,
+        method: &str,
+    ) -> ManifestBuilder {
+        ManifestBuilder::new()
+            .withdraw_from_account(self.account, res_address, amount)
+            .take_all_from_worktop(res_address, "bucket")
+            .call_method_with_name_lookup(self.pool, method, |lookup| (lookup.bucket("bucket"),))
+    }
+
+    fn balance(&mut self, res_address: ResourceAddress) -> Decimal {
+        self.env.balance(0, res_address)
+    }
+}
+
+/// Manifest encoding of `WithdrawType::ForTemporaryUse`
+fn for_temporary_use() -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 0,
+        fields: vec![],
+    }
+}
+
+/// Manifest encoding of `WithdrawType::LiquidityWithdrawal`
+fn liquidity_withdrawal() -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 1,
+        fields: vec![],
+    }
+}
+
+/// Manifest encoding of `DepositType::FromTemporaryUse`
+fn from_temporary_use() -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 0,
+        fields: vec![],
+    }
+}
+
+/// Manifest encoding of `DepositType::LiquidityAddition`
+fn liquidity_addition() -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 1,
+        fields: vec![],
+    }
+}
+
+/// Manifest encoding of `WithdrawStrategy::Exact`
+fn exact() -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 0,
+        fields: vec![],
+    }
+}
+
+/// Manifest encoding of `WithdrawStrategy::Rounded(RoundingMode::AwayFromZero)`
+fn rounded_away_from_zero() -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 1,
+        fields: vec![ManifestValue::Enum {
+            discriminator: 3,
+            fields: vec![],
+        }],
+    }
+}
+
+fn contribute_manifest(
+    account: ComponentAddress,
+    pool: ComponentAddress,
+    asset: ResourceAddress,
+    amount: Decimal,
+) -> TransactionManifestV1 {
+    ManifestBuilder::new()
+        .withdraw_from_account(account, asset, amount)
+        .take_all_from_worktop(asset, "assets")
+        .call_method_with_name_lookup(pool, "contribute", |lookup| (lookup.bucket("assets"),))
+        .deposit_batch(account)
+        .build()
+}
+
+#[test]
+fn test_zero_divisibility_contribute_returns_remainder() {
+    let mut pool = TestPool::new(0);
+
+    // Ratio is 1: 3 assets give 3 pool units
+    pool.contribute(dec!(3)).expect_commit_success();
+
+    // 3 units for 6 assets, the ratio drops to 0.5
+    pool.call("increase_external_liquidity", manifest_args!(dec!(3)))
+        .expect_commit_success();
+
+    // 3 assets are worth 1.5 units: only 1 unit is minted and the asset not backing it is returned
+    pool.contribute(dec!(3)).expect_commit_success();
+
+    assert_eq!(pool.balance(pool.asset), dec!(995));
+    assert_eq!(pool.balance(pool.pool_unit), dec!(4));
+}
+
+#[test]
+fn test_zero_divisibility_redeem_returns_remainder() {
+    let mut pool = TestPool::new(0);
+    pool.contribute(dec!(4)).expect_commit_success();
+
+    // Withdraw half of the liquidity, the ratio goes up to 2
+    pool.call(
+        "protected_withdraw",
+        manifest_args!(dec!(2), liquidity_withdrawal(), exact()),
+    )
+    .expect_commit_success();
+
+    // 3 units are worth 1.5 assets: 1 asset is redeemed and the unit not backing it is returned
+    pool.redeem(dec!(3)).expect_commit_success();
+
+    assert_eq!(pool.balance(pool.asset), dec!(999));
+    assert_eq!(pool.balance(pool.pool_unit), dec!(2));
+}
+
+#[test]
+fn test_repay_flashloan_rejects_foreign_loan_terms() {
+    let mut pool = TestPool::new(18);
+    pool.contribute(dec!(100)).expect_commit_success();
+
+    let foreign_terms = pool
+        .env
+        .test_runner
+        .create_non_fungible_resource(pool.account);
+
+    let builder = ManifestBuilder::new()
+        .call_method(
+            pool.pool,
+            "take_flashloan",
+            manifest_args!(dec!(10), dec!(1)),
+        )
+        .withdraw_non_fungibles_from_account(
+            pool.account,
+            foreign_terms,
+            &BTreeSet::from([NonFungibleLocalId::integer(1)]),
+        )
+        .withdraw_from_account(pool.account, pool.asset, dec!(1))
+        .take_all_from_worktop(pool.asset, "repayment")
+        .take_all_from_worktop(foreign_terms, "terms")
+        .call_method_with_name_lookup(pool.pool, "repay_flashloan", |lookup| {
+            (lookup.bucket("repayment"), lookup.bucket("terms"))
+        });
+
+    expect_failure_containing(pool.execute(builder), "POOL_ERR_001");
+}
+
+/// Integer pool holding 2 assets for 4 pool units: a pool unit is worth half an asset
+fn half_asset_unit_pool() -> TestPool {
+    let mut pool = TestPool::new(0);
+    pool.contribute(dec!(4)).expect_commit_success();
+    pool.call(
+        "protected_withdraw",
+        manifest_args!(dec!(2), liquidity_withdrawal(), exact()),
+    )
+    .expect_commit_success();
+
+    pool
+}
+
+fn redeem_advanced(
+    pool: &mut TestPool,
+    units: Decimal,
+    strategy: ManifestValue,
+) -> TransactionReceipt {
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(pool.account, pool.pool_unit, units)
+        .take_all_from_worktop(pool.pool_unit, "pool_units")
+        .call_method_with_name_lookup(pool.pool, "redeem_advanced", |lookup| {
+            (lookup.bucket("pool_units"), strategy)
+        });
+
+    pool.execute(builder)
+}
+
+#[test]
+fn test_redeem_advanced_rounding_up_requires_backing_units() {
+    let mut pool = half_asset_unit_pool();
+
+    // 3 units are worth 1.5 assets: rounding up to 2 assets would need 4 units
+    expect_failure_containing(
+        redeem_advanced(&mut pool, dec!(3), rounded_away_from_zero()),
+        "POOL_ERR_020",
+    );
+
+    redeem_advanced(&mut pool, dec!(4), rounded_away_from_zero()).expect_commit_success();
+
+    assert_eq!(pool.balance(pool.asset), dec!(1000));
+    assert_eq!(pool.balance(pool.pool_unit), dec!(0));
+}
+
+#[test]
+fn test_redeem_advanced_exact_rejects_inexact_amounts() {
+    let mut pool = half_asset_unit_pool();
+
+    expect_failure_containing(redeem_advanced(&mut pool, dec!(3), exact()), "POOL_ERR_019");
+
+    redeem_advanced(&mut pool, dec!(2), exact()).expect_commit_success();
+
+    assert_eq!(pool.balance(pool.asset), dec!(999));
+    assert_eq!(pool.balance(pool.pool_unit), dec!(2));
+}
+
+#[test]
+fn test_soulbound_units_are_redeemed_through_the_pool() {
+    let mut pool = TestPool::with_config(18, |config, _| TestPoolConfig {
+        soulbound_pool_units: true,
+        ..config
+    });
+    let holder = pool.env.badges[0].clone();
+
+    // Soulbound units can not be deposited to an account
+    pool.contribute(dec!(10)).expect_commit_failure();
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(pool.account, pool.asset, dec!(10))
+        .take_all_from_worktop(pool.asset, "assets")
+        .call_method_with_name_lookup(pool.pool, "contribute_soulbound", |lookup| {
+            (lookup.bucket("assets"), holder.clone())
+        });
+    pool.execute(builder).expect_commit_success();
+
+    assert_eq!(
+        pool.get::<Decimal>("get_soulbound_units", manifest_args!(holder.clone())),
+        dec!(10)
+    );
+
+    // Only the holder badge can redeem the position
+    let (other_public_key, _, _) = pool.env.test_runner.new_allocated_account();
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            pool.pool,
+            "redeem_soulbound",
+            manifest_args!(holder.clone(), dec!(4)),
+        )
+        .deposit_batch(pool.account)
+        .build();
+    pool.env
+        .test_runner
+        .execute_manifest_ignoring_fee(
+            manifest,
+            vec![NonFungibleGlobalId::from_public_key(&other_public_key)],
+        )
+        .expect_commit_failure();
+
+    pool.call("redeem_soulbound", manifest_args!(holder.clone(), dec!(4)))
+        .expect_commit_success();
+
+    assert_eq!(pool.balance(pool.asset), dec!(994));
+    assert_eq!(
+        pool.get::<Decimal>("get_soulbound_units", manifest_args!(holder)),
+        dec!(6)
+    );
+}
+
+#[test]
+fn test_soulbound_redemptions_are_restricted_to_the_admin() {
+    let admin = NonFungibleGlobalId::from_public_key(
+        &Secp256k1PrivateKey::from_u64(99).unwrap().public_key(),
+    );
+    let mut pool = TestPool::with_config(18, |config, _| TestPoolConfig {
+        admin_rule: rule!(require(admin.clone())),
+        soulbound_pool_units: true,
+        ..config
+    });
+    let holder = pool.env.badges[0].clone();
+
+    let manifest = ManifestBuilder::new()
+        .withdraw_from_account(pool.account, pool.asset, dec!(10))
+        .take_all_from_worktop(pool.asset, "assets")
+        .call_method_with_name_lookup(pool.pool, "contribute_soulbound", |lookup| {
+            (lookup.bucket("assets"), holder.clone())
+        })
+        .deposit_batch(pool.account)
+        .build();
+    pool.env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![holder.clone(), admin.clone()])
+        .expect_commit_success();
+
+    // The holder badge alone does not pass the admin gate
+    pool.call("redeem_soulbound", manifest_args!(holder.clone(), dec!(4)))
+        .expect_commit_failure();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            pool.pool,
+            "redeem_soulbound",
+            manifest_args!(holder.clone(), dec!(4)),
+        )
+        .deposit_batch(pool.account)
+        .build();
+    pool.env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![holder.clone(), admin])
+        .expect_commit_success();
+
+    assert_eq!(pool.balance(pool.asset), dec!(994));
+    assert_eq!(
+        pool.get::<Decimal>("get_soulbound_units", manifest_args!(holder)),
+        dec!(6)
+    );
+}
+
+#[test]
+fn test_flash_mint_loans_are_minted_and_burned() {
+    let mut env = TestEnv::new(this_package!(), 1);
+    let account = env.accounts[0];
+    let proofs = vec![env.badges[0].clone()];
+
+    let badge = env
+        .test_runner
+        .create_fungible_resource(dec!(1), 0, account);
+
+    let manifest = ManifestBuilder::new()
+        .create_fungible_resource(
+            OwnerRole::None,
+            true,
+            18,
+            FungibleResourceRoles {
+                mint_roles: mint_roles! {
+                    minter => rule!(require(badge));
+                    minter_updater => rule!(deny_all);
+                },
+                burn_roles: burn_roles! {
+                    burner => rule!(require(badge));
+                    burner_updater => rule!(deny_all);
+                },
+                ..Default::default()
+            },
+            metadata!(),
+            Some(dec!(100)),
+        )
+        .deposit_batch(account)
+        .build();
+    let asset = env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![])
+        .expect_commit_success()
+        .new_resource_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .withdraw_from_account(account, badge, dec!(1))
+        .take_all_from_worktop(badge, "badge")
+        .call_function_with_name_lookup(
+            env.package_address,
+            "AssetPool",
+            "instantiate_flash_mint",
+            |lookup| {
+                (
+                    asset,
+                    lookup.bucket("badge"),
+                    TestPoolConfig::new(OwnerRole::None),
+                )
+            },
+        )
+        .build();
+    let receipt = env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, proofs.clone());
+    let commit = receipt.expect_commit_success();
+    let pool = commit.new_component_addresses()[0];
+    let flashloan_term = commit.new_resource_addresses()[1];
+
+    let manifest = contribute_manifest(account, pool, asset, dec!(10));
+    env.test_runner
+        .execute_manifest_ignoring_fee(manifest, proofs.clone())
+        .expect_commit_success();
+
+    // The loan exceeds the pooled liquidity: it is minted, and only the fee stays in the pool
+    assert_eq!(
+        env.get::<Option<Decimal>>(pool, "get_flashloan_capacity", manifest_args!()),
+        None
+    );
+    let manifest = ManifestBuilder::new()
+        .call_method(pool, "take_flashloan", manifest_args!(dec!(50), dec!(1)))
+        .withdraw_from_account(account, asset, dec!(1))
+        .take_all_from_worktop(asset, "repayment")
+        .take_all_from_worktop(flashloan_term, "terms")
+        .call_method_with_name_lookup(pool, "repay_flashloan", |lookup| {
+            (lookup.bucket("repayment"), lookup.bucket("terms"))
+        })
+        .deposit_batch(account)
+        .build();
+    env.test_runner
+        .execute_manifest_ignoring_fee(manifest, proofs)
+        .expect_commit_success();
+
+    assert_eq!(
+        env.test_runner.get_component_balance(account, asset),
+        dec!(89)
+    );
+    assert_eq!(env.test_runner.get_component_balance(pool, asset), dec!(11));
+}
+
+#[test]
+fn test_getters_report_pool_state() {
+    let mut pool = TestPool::new(18);
+    pool.contribute(dec!(100)).expect_commit_success();
+    pool.call(
+        "protected_withdraw",
+        manifest_args!(dec!(40), for_temporary_use(), exact()),
+    )
+    .expect_commit_success();
+    pool.call("increase_external_liquidity", manifest_args!(dec!(100)))
+        .expect_commit_success();
+
+    assert_eq!(
+        pool.get::<(Decimal, Decimal)>("get_pooled_amount", manifest_args!()),
+        (dec!(60), dec!(140))
+    );
+    assert_eq!(
+        pool.get::<Decimal>("get_pool_unit_supply", manifest_args!()),
+        dec!(100)
+    );
+    assert_eq!(
+        pool.get::<PreciseDecimal>("get_pool_unit_ratio", manifest_args!()),
+        pdec!("0.5")
+    );
+
+    // Only the liquidity held by the pool can be lent
+    assert_eq!(
+        pool.get::<Option<Decimal>>("get_flashloan_capacity", manifest_args!()),
+        Some(dec!(60))
+    );
+}
+
+#[test]
+fn test_external_liquidity_cap() {
+    let mut pool = TestPool::with_config(18, |config, _| TestPoolConfig {
+        max_external_liquidity_ratio: Some(dec!("0.5")),
+        ..config
+    });
+    pool.contribute(dec!(100)).expect_commit_success();
+
+    expect_failure_containing(
+        pool.call(
+            "protected_withdraw",
+            manifest_args!(dec!(60), for_temporary_use(), exact()),
+        ),
+        "POOL_ERR_011",
+    );
+
+    pool.call(
+        "protected_withdraw",
+        manifest_args!(dec!(50), for_temporary_use(), exact()),
+    )
+    .expect_commit_success();
+
+    expect_failure_containing(
+        pool.call("increase_external_liquidity", manifest_args!(dec!(1))),
+        "POOL_ERR_011",
+    );
+}
+
+#[test]
+fn test_circuit_breaker_pauses_the_pool() {
+    let mut pool = TestPool::with_config(18, |config, _| TestPoolConfig {
+        circuit_breaker_threshold: Some(dec!("0.1")),
+        ..config
+    });
+    pool.contribute(dec!(100)).expect_commit_success();
+
+    // A 5% ratio move is tolerated
+    pool.call("increase_external_liquidity", manifest_args!(dec!(5)))
+        .expect_commit_success();
+    assert!(!pool.get::<bool>("is_paused", manifest_args!()));
+
+    // The operation moving the ratio by a third commits, and the pool pauses itself
+    pool.call("increase_external_liquidity", manifest_args!(dec!(50)))
+        .expect_commit_success();
+    assert!(pool.get::<bool>("is_paused", manifest_args!()));
+
+    expect_failure_containing(pool.contribute(dec!(10)), "POOL_ERR_012");
+
+    pool.call("resume", manifest_args!())
+        .expect_commit_success();
+    pool.contribute(dec!(10)).expect_commit_success();
+}
+
+#[test]
+fn test_ratio_twap_ignores_current_epoch_changes() {
+    let mut pool = TestPool::new(18);
+    pool.contribute(dec!(100)).expect_commit_success();
+
+    // The ratio drops from 1 to 0.5 at epoch 100
+    pool.env.set_epoch(100);
+    pool.call("increase_external_liquidity", manifest_args!(dec!(100)))
+        .expect_commit_success();
+
+    // Same-epoch change at epoch 110: not taken into account
+    pool.env.set_epoch(110);
+    pool.call("increase_external_liquidity", manifest_args!(dec!(100)))
+        .expect_commit_success();
+
+    // 10 epochs at 1 and 10 epochs at 0.5
+    assert_eq!(
+        pool.get::<Option<PreciseDecimal>>("get_ratio_twap", manifest_args!(20u64)),
+        Some(pdec!("0.75"))
+    );
+    assert_eq!(
+        pool.get::<Option<PreciseDecimal>>("get_ratio_twap", manifest_args!(10u64)),
+        Some(pdec!("0.5"))
+    );
+}
+
+#[test]
+fn test_ratio_twap_rejects_uncovered_windows() {
+    let mut pool = TestPool::new(18);
+    pool.contribute(dec!(100)).expect_commit_success();
+
+    // Only the observation of the instantiation epoch exists
+    assert_eq!(
+        pool.get::<Option<PreciseDecimal>>("get_ratio_twap", manifest_args!(1u64)),
+        None
+    );
+
+    // One ratio change per epoch from epoch 10 to 49 evicts the oldest observations
+    for epoch in 10..50 {
+        pool.env.set_epoch(epoch);
+        pool.call("increase_external_liquidity", manifest_args!(dec!(1)))
+            .expect_commit_success();
+    }
+    pool.env.set_epoch(50);
+
+    assert_eq!(
+        pool.get::<Option<PreciseDecimal>>("get_ratio_twap", manifest_args!(0u64)),
+        None
+    );
+    // The 32 kept observations start at epoch 18
+    assert_eq!(
+        pool.get::<Option<PreciseDecimal>>("get_ratio_twap", manifest_args!(33u64)),
+        None
+    );
+    assert!(pool
+        .get::<Option<PreciseDecimal>>("get_ratio_twap", manifest_args!(32u64))
+        .is_some());
+}
+
+#[test]
+fn test_pool_errors_render_stable_codes() {
+    assert_eq!(
+        PoolError::InsufficientLiquidity.to_string(),
+        "POOL_ERR_003: insufficient liquidity"
+    );
+    assert_eq!(
+        PoolError::InsufficientPoolUnits.to_string(),
+        "POOL_ERR_020: pool units do not back the redeemed amount"
+    );
+}
+
+#[test]
+fn test_failures_carry_pool_error_codes() {
+    let mut pool = TestPool::new(18);
+    pool.contribute(dec!(10)).expect_commit_success();
+
+    expect_failure_containing(
+        pool.call("take_flashloan", manifest_args!(dec!(20), dec!(0))),
+        "POOL_ERR_003",
+    );
+    expect_failure_containing(
+        pool.call("take_flashloan", manifest_args!(dec!(0), dec!(0))),
+        "POOL_ERR_006",
+    );
+}
+
+#[test]
+fn test_config_changes_are_timelocked() {
+    let mut pool = TestPool::with_config(18, |config, _| TestPoolConfig {
+        config_timelock_epochs: 10,
+        ..config
+    });
+    pool.env.set_epoch(10);
+
+    pool.call(
+        "queue_config_change",
+        manifest_args!(TestConfigChange::MaxPoolUnitSupply(Some(dec!(50)))),
+    )
+    .expect_commit_success();
+
+    expect_failure_containing(
+        pool.call("apply_config_change", manifest_args!(0u64)),
+        "POOL_ERR_014",
+    );
+
+    pool.env.set_epoch(20);
+    pool.call("apply_config_change", manifest_args!(0u64))
+        .expect_commit_success();
+
+    expect_failure_containing(pool.contribute(dec!(60)), "POOL_ERR_016");
+
+    // Cancelled changes are announced like queued and applied ones
+    pool.call(
+        "queue_config_change",
+        manifest_args!(TestConfigChange::MaxPoolUnitSupply(None)),
+    )
+    .expect_commit_success();
+    let receipt = pool.call("cancel_config_change", manifest_args!(1u64));
+
+    let events: Vec<ConfigChangeCancelled> = pool.events(&receipt, "ConfigChangeCancelled");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].change_id, 1);
+    assert!(matches!(
+        events[0].change,
+        ConfigChange::MaxPoolUnitSupply(None)
+    ));
+}
+
+#[test]
+fn test_cancelled_config_changes_can_not_be_applied() {
+    let mut pool = TestPool::new(18);
+
+    pool.call(
+        "queue_config_change",
+        manifest_args!(TestConfigChange::MaxPoolUnitSupply(Some(dec!(50)))),
+    )
+    .expect_commit_success();
+    pool.call("cancel_config_change", manifest_args!(0u64))
+        .expect_commit_success();
+
+    expect_failure_containing(
+        pool.call("apply_config_change", manifest_args!(0u64)),
+        "POOL_ERR_013",
+    );
+}
+
+#[test]
+fn test_config_changes_are_restricted_to_the_owner() {
+    let mut pool = TestPool::new(18);
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            pool.pool,
+            "queue_config_change",
+            manifest_args!(TestConfigChange::MaxPoolUnitSupply(Some(dec!(50)))),
+        )
+        .build();
+
+    pool.env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![])
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_protected_withdraw_epoch_limit() {
+    let mut pool = TestPool::with_config(18, |config, _| TestPoolConfig {
+        protected_withdraw_epoch_limit: Some(dec!(30)),
+        ..config
+    });
+    pool.contribute(dec!(100)).expect_commit_success();
+    pool.env.set_epoch(10);
+
+    pool.call(
+        "protected_withdraw",
+        manifest_args!(dec!(20), for_temporary_use(), exact()),
+    )
+    .expect_commit_success();
+
+    expect_failure_containing(
+        pool.call(
+            "protected_withdraw",
+            manifest_args!(dec!(20), for_temporary_use(), exact()),
+        ),
+        "POOL_ERR_015",
+    );
+
+    // The usage is reset at the next epoch
+    pool.env.set_epoch(11);
+    pool.call(
+        "protected_withdraw",
+        manifest_args!(dec!(20), for_temporary_use(), exact()),
+    )
+    .expect_commit_success();
+}
+
+#[test]
+fn test_owner_can_pause_and_resume() {
+    let mut pool = TestPool::new(18);
+
+    pool.call("pause", manifest_args!()).expect_commit_success();
+
+    expect_failure_containing(pool.contribute(dec!(10)), "POOL_ERR_012");
+    expect_failure_containing(
+        pool.call("increase_external_liquidity", manifest_args!(dec!(1))),
+        "POOL_ERR_012",
+    );
+
+    // Accruals are public, but also stopped while paused
+    expect_failure_containing(
+        pool.call("accrue_interest", manifest_args!()),
+        "POOL_ERR_012",
+    );
+
+    pool.call("resume", manifest_args!())
+        .expect_commit_success();
+    pool.contribute(dec!(10)).expect_commit_success();
+    pool.call("accrue_interest", manifest_args!())
+        .expect_commit_success();
+}
+
+#[test]
+fn test_pause_exempt_badge_holders_use_the_paused_pool() {
+    let mut pool = TestPool::new(18);
+    let badge = pool
+        .env
+        .test_runner
+        .create_fungible_resource(dec!(1), 0, pool.account);
+
+    pool.call(
+        "queue_config_change",
+        manifest_args!(TestConfigChange::PauseExemptBadge(Some(badge))),
+    )
+    .expect_commit_success();
+    pool.call("apply_config_change", manifest_args!(0u64))
+        .expect_commit_success();
+    pool.call("pause", manifest_args!()).expect_commit_success();
+
+    pool.contribute(dec!(10)).expect_commit_failure();
+
+    let builder = ManifestBuilder::new()
+        .create_proof_from_account_of_amount(pool.account, badge, dec!(1))
+        .withdraw_from_account(pool.account, pool.asset, dec!(10))
+        .take_all_from_worktop(pool.asset, "assets")
+        .call_method_with_name_lookup(pool.pool, "contribute", |lookup| (lookup.bucket("assets"),));
+    pool.execute(builder).expect_commit_success();
+    assert_eq!(pool.balance(pool.pool_unit), dec!(10));
+
+    // Without exempt badge, the paused pool can not be used by anyone
+    pool.call(
+        "queue_config_change",
+        manifest_args!(TestConfigChange::PauseExemptBadge(None)),
+    )
+    .expect_commit_success();
+    pool.call("apply_config_change", manifest_args!(1u64))
+        .expect_commit_success();
+
+    let builder = ManifestBuilder::new()
+        .create_proof_from_account_of_amount(pool.account, badge, dec!(1))
+        .withdraw_from_account(pool.account, pool.asset, dec!(10))
+        .take_all_from_worktop(pool.asset, "assets")
+        .call_method_with_name_lookup(pool.pool, "contribute", |lookup| (lookup.bucket("assets"),));
+    expect_failure_containing(pool.execute(builder), "POOL_ERR_012");
+}
+
+#[test]
+fn test_repayment_excess_is_recorded_as_yield() {
+    let mut pool = TestPool::new(18);
+    pool.contribute(dec!(100)).expect_commit_success();
+    pool.call(
+        "protected_withdraw",
+        manifest_args!(dec!(10), for_temporary_use(), exact()),
+    )
+    .expect_commit_success();
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(pool.account, pool.asset, dec!(15))
+        .take_all_from_worktop(pool.asset, "assets")
+        .call_method_with_name_lookup(pool.pool, "protected_deposit", |lookup| {
+            (lookup.bucket("assets"), from_temporary_use())
+        });
+    pool.execute(builder).expect_commit_success();
+
+    assert_eq!(
+        pool.get::<(Decimal, Decimal)>("get_pooled_amount", manifest_args!()),
+        (dec!(105), dec!(0))
+    );
+    assert_eq!(
+        pool.get::<Decimal>("get_amount_for_units", manifest_args!(dec!(100))),
+        dec!(105)
+    );
+}
+
+/// Pool whose admin lent 50 out of 100 at epoch 10, with the given interest accrual mode
+fn lent_out_pool(accrual_mode: TestInterestAccrualMode) -> TestPool {
+    let mut pool = TestPool::with_config(18, |config, _| TestPoolConfig {
+        external_liquidity_interest_rate: dec!("0.0001"),
+        interest_accrual_mode: accrual_mode,
+        ..config
+    });
+
+    pool.env.set_epoch(10);
+    pool.contribute(dec!(100)).expect_commit_success();
+    pool.call(
+        "protected_withdraw",
+        manifest_args!(dec!(50), for_temporary_use(), exact()),
+    )
+    .expect_commit_success();
+
+    pool
+}
+
+#[test]
+fn test_external_liquidity_interest_accrues_linearly() {
+    let mut pool = lent_out_pool(TestInterestAccrualMode::Linear);
+
+    pool.env.set_epoch(1010);
+    pool.call("accrue_interest", manifest_args!())
+        .expect_commit_success();
+
+    assert_eq!(
+        pool.get::<(Decimal, Decimal)>("get_pooled_amount", manifest_args!()),
+        (dec!(50), dec!(55))
+    );
+    assert_eq!(
+        pool.get::<Decimal>("get_amount_for_units", manifest_args!(dec!(100))),
+        dec!(105)
+    );
+}
+
+// Reference computed with 60 significant digits: 50 * (e^0.1 - 1) = 5.25854590378238...
+#[test]
+fn test_external_liquidity_interest_accrues_continuously() {
+    let mut pool = lent_out_pool(TestInterestAccrualMode::Continuous);
+
+    pool.env.set_epoch(1010);
+    pool.call("accrue_interest", manifest_args!())
+        .expect_commit_success();
+
+    assert_eq!(
+        pool.get::<(Decimal, Decimal)>("get_pooled_amount", manifest_args!()),
+        (dec!(50), dec!("55.2585459037823812"))
+    );
+
+    // Accruing more often does not change the continuously compounded interest
+    let mut frequent_pool = lent_out_pool(TestInterestAccrualMode::Continuous);
+    for epoch in [110, 510, 1010] {
+        frequent_pool.env.set_epoch(epoch);
+        frequent_pool
+            .call("accrue_interest", manifest_args!())
+            .expect_commit_success();
+    }
+
+    let (_, external) =
+        frequent_pool.get::<(Decimal, Decimal)>("get_pooled_amount", manifest_args!());
+    assert!(external > dec!("55.258545903782") && external < dec!("55.258545903783"));
+}
+
+#[test]
+fn test_external_liquidity_interest_rate_changes_are_timelocked() {
+    let mut pool = lent_out_pool(TestInterestAccrualMode::Linear);
+
+    pool.call(
+        "queue_config_change",
+        manifest_args!(TestConfigChange::ExternalLiquidityInterestRate(dec!(
+            "0.0002"
+        ))),
+    )
+    .expect_commit_success();
+
+    // Interest accrued before the change uses the previous rate: 50 * 0.0001 * 500
+    pool.env.set_epoch(510);
+    pool.call("apply_config_change", manifest_args!(0u64))
+        .expect_commit_success();
+    assert_eq!(
+        pool.get::<(Decimal, Decimal)>("get_pooled_amount", manifest_args!()),
+        (dec!(50), dec!("52.5"))
+    );
+
+    // Contributions accrue the interest first: 52.5 * 0.0002 * 100
+    pool.env.set_epoch(610);
+    pool.contribute(dec!(10)).expect_commit_success();
+    let (_, external) = pool.get::<(Decimal, Decimal)>("get_pooled_amount", manifest_args!());
+    assert_eq!(external, dec!("53.55"));
+
+    expect_failure_containing(
+        pool.call(
+            "queue_config_change",
+            manifest_args!(TestConfigChange::ExternalLiquidityInterestRate(dec!(-1))),
+        ),
+        "POOL_ERR_010",
+    );
+}
+
+#[test]
+fn test_decrease_external_liquidity_underflow() {
+    let mut pool = TestPool::new(18);
+    pool.contribute(dec!(100)).expect_commit_success();
+    pool.call("increase_external_liquidity", manifest_args!(dec!(10)))
+        .expect_commit_success();
+
+    expect_failure_containing(
+        pool.call("decrease_external_liquidity", manifest_args!(dec!(11))),
+        "POOL_ERR_005",
+    );
+}
+
+#[test]
+fn test_protected_deposit_rejects_other_resources() {
+    let mut pool = TestPool::new(18);
+    let other = pool
+        .env
+        .test_runner
+        .create_fungible_resource(dec!(100), 18, pool.account);
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(pool.account, other, dec!(10))
+        .take_all_from_worktop(other, "assets")
+        .call_method_with_name_lookup(pool.pool, "protected_deposit", |lookup| {
+            (lookup.bucket("assets"), liquidity_addition())
+        });
+
+    expect_failure_containing(pool.execute(builder), "POOL_ERR_001");
+}
+
+#[test]
+fn test_other_resources_are_kept_apart() {
+    let mut pool = TestPool::new(18);
+    pool.contribute(dec!(100)).expect_commit_success();
+    let other = pool
+        .env
+        .test_runner
+        .create_fungible_resource(dec!(100), 18, pool.account);
+
+    let builder = pool.send_to_pool(other, dec!(10), "deposit_other");
+    pool.execute(builder).expect_commit_success();
+
+    assert_eq!(
+        pool.get::<Decimal>("get_other_amount", manifest_args!(other)),
+        dec!(10)
+    );
+    assert_eq!(
+        pool.get::<PreciseDecimal>("get_current_pool_unit_ratio", manifest_args!()),
+        pdec!(1)
+    );
+
+    // The pooled resource can only be deposited as liquidity
+    let builder = pool.send_to_pool(pool.asset, dec!(10), "deposit_other");
+    expect_failure_containing(pool.execute(builder), "POOL_ERR_001");
+
+    pool.call("withdraw_other", manifest_args!(other, dec!(4)))
+        .expect_commit_success();
+
+    assert_eq!(pool.balance(other), dec!(94));
+    expect_failure_containing(
+        pool.call("withdraw_other", manifest_args!(pool.pool_unit, dec!(1))),
+        "POOL_ERR_023: no such resource held by the pool",
+    );
+}
+
+#[test]
+fn test_ratio_is_unchanged_during_a_flashloan() {
+    let mut pool = TestPool::new(18);
+    pool.contribute(dec!(100)).expect_commit_success();
+
+    let builder = ManifestBuilder::new()
+        .call_method(
+            pool.pool,
+            "take_flashloan",
+            manifest_args!(dec!(60), dec!(0)),
+        )
+        .call_method(pool.pool, "get_current_pool_unit_ratio", manifest_args!())
+        .take_all_from_worktop(pool.asset, "repayment")
+        .take_all_from_worktop(pool.flashloan_term, "terms")
+        .call_method_with_name_lookup(pool.pool, "repay_flashloan", |lookup| {
+            (lookup.bucket("repayment"), lookup.bucket("terms"))
+        });
+    let receipt = pool.execute(builder);
+    let ratio: PreciseDecimal = receipt.expect_commit_success().output(1);
+
+    assert_eq!(ratio, pdec!(1));
+    assert_eq!(
+        pool.get::<(Decimal, Decimal)>("get_pooled_amount", manifest_args!()),
+        (dec!(100), dec!(0))
+    );
+}
+
+#[test]
+fn test_pool_unit_supply_cap() {
+    let mut pool = TestPool::with_config(18, |config, _| TestPoolConfig {
+        max_pool_unit_supply: Some(dec!(50)),
+        ..config
+    });
+
+    pool.contribute(dec!(40)).expect_commit_success();
+    expect_failure_containing(pool.contribute(dec!(20)), "POOL_ERR_016");
+    pool.contribute(dec!(10)).expect_commit_success();
+
+    assert_eq!(pool.balance(pool.pool_unit), dec!(50));
+}
+
+#[test]
+fn test_conversion_getters_match_contribute_and_redeem() {
+    let mut pool = TestPool::new(18);
+    pool.contribute(dec!(100)).expect_commit_success();
+    pool.call("increase_external_liquidity", manifest_args!(dec!(200)))
+        .expect_commit_success();
+
+    let expected_units = pool.get::<Decimal>("get_units_for_amount", manifest_args!(dec!(10)));
+    pool.contribute(dec!(10)).expect_commit_success();
+    assert_eq!(pool.balance(pool.pool_unit), dec!(100) + expected_units);
+
+    let expected_amount = pool.get::<Decimal>("get_amount_for_units", manifest_args!(dec!(3)));
+    let asset_balance = pool.balance(pool.asset);
+    pool.redeem(dec!(3)).expect_commit_success();
+    assert_eq!(pool.balance(pool.asset), asset_balance + expected_amount);
+}
+
+#[test]
+fn test_instantiate_at_a_reserved_address() {
+    let mut pool = TestPool::new(18);
+    let config = TestPoolConfig::new(OwnerRole::None);
+
+    let manifest = ManifestBuilder::new()
+        .allocate_global_address(
+            pool.env.package_address,
+            "AssetPool",
+            "pool_reservation",
+            "pool_address",
+        )
+        .call_function_with_name_lookup(
+            pool.env.package_address,
+            "AssetPool",
+            "instantiate_with_address_reservation",
+            |lookup| {
+                (
+                    lookup.address_reservation("pool_reservation"),
+                    pool.asset,
+                    config,
+                )
+            },
+        )
+        .build();
+    let receipt = pool
+        .env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![]);
+    let commit = receipt.expect_commit_success();
+
+    let (reserved_pool, _, _): (ComponentAddress, ResourceAddress, ResourceAddress) =
+        commit.output(1);
+
+    assert_eq!(reserved_pool, commit.new_component_addresses()[0]);
+}
+
+#[test]
+fn test_dapp_definition_metadata() {
+    let mut pool = TestPool::with_config(18, |config, account| TestPoolConfig {
+        dapp_definition: Some(account),
+        ..config
+    });
+    let dapp_definition = pool.account;
+
+    assert_eq!(
+        pool.env
+            .test_runner
+            .get_metadata(pool.pool.into(), "dapp_definition"),
+        Some(MetadataValue::GlobalAddress(dapp_definition.into()))
+    );
+
+    for resource in [pool.pool_unit, pool.flashloan_term] {
+        assert_eq!(
+            pool.env
+                .test_runner
+                .get_metadata(resource.into(), "dapp_definitions"),
+            Some(MetadataValue::GlobalAddressArray(vec![
+                dapp_definition.into()
+            ]))
+        );
+    }
+}
+
+#[test]
+fn test_contribution_badge_is_required() {
+    let mut pool = TestPool::new(18);
+    let badge = pool
+        .env
+        .test_runner
+        .create_fungible_resource(dec!(1), 0, pool.account);
+
+    pool.call(
+        "queue_config_change",
+        manifest_args!(TestConfigChange::ContributionBadge(Some(badge))),
+    )
+    .expect_commit_success();
+    pool.call("apply_config_change", manifest_args!(0u64))
+        .expect_commit_success();
+
+    pool.contribute(dec!(10)).expect_commit_failure();
+
+    let builder = ManifestBuilder::new()
+        .create_proof_from_account_of_amount(pool.account, badge, dec!(1))
+        .withdraw_from_account(pool.account, pool.asset, dec!(10))
+        .take_all_from_worktop(pool.asset, "assets")
+        .call_method_with_name_lookup(pool.pool, "contribute", |lookup| (lookup.bucket("assets"),));
+    pool.execute(builder).expect_commit_success();
+
+    assert_eq!(pool.balance(pool.pool_unit), dec!(10));
+}
+
+#[test]
+fn test_withdrawal_allowances() {
+    let mut pool = TestPool::new(18);
+    pool.contribute(dec!(100)).expect_commit_success();
+
+    let holder = pool.env.badges[0].clone();
+    let (spender_public_key, _, spender_account) = pool.env.test_runner.new_allocated_account();
+    let spender = NonFungibleGlobalId::from_public_key(&spender_public_key);
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(pool.account, pool.pool_unit, dec!(10))
+        .take_all_from_worktop(pool.pool_unit, "pool_units")
+        .call_method_with_name_lookup(pool.pool, "grant_allowance", |lookup| {
+            (holder.clone(), spender.clone(), lookup.bucket("pool_units"))
+        });
+    pool.execute(builder).expect_commit_success();
+
+    let redeem_as_spender = |pool: &mut TestPool, units: Decimal| {
+        let manifest = ManifestBuilder::new()
+            .call_method(
+                pool.pool,
+                "redeem_with_allowance",
+                manifest_args!(0u64, units),
+            )
+            .deposit_batch(spender_account)
+            .build();
+
+        pool.env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![spender.clone()])
+    };
+
+    // Only the spender can use the allowance, up to the escrowed units
+    pool.call("redeem_with_allowance", manifest_args!(0u64, dec!(4)))
+        .expect_commit_failure();
+    expect_failure_containing(redeem_as_spender(&mut pool, dec!(11)), "POOL_ERR_018");
+    redeem_as_spender(&mut pool, dec!(4)).expect_commit_success();
+
+    assert_eq!(
+        pool.env
+            .test_runner
+            .get_component_balance(spender_account, pool.asset),
+        dec!(4)
+    );
+
+    // Only the holder can revoke it and get the remaining units back
+    let manifest = ManifestBuilder::new()
+        .call_method(pool.pool, "revoke_allowance", manifest_args!(0u64))
+        .deposit_batch(spender_account)
+        .build();
+    pool.env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![spender.clone()])
+        .expect_commit_failure();
+
+    pool.call("revoke_allowance", manifest_args!(0u64))
+        .expect_commit_success();
+
+    assert_eq!(pool.balance(pool.pool_unit), dec!(96));
+    expect_failure_containing(redeem_as_spender(&mut pool, dec!(1)), "POOL_ERR_018");
+}
+
+/// Borrower component of the `flashloan_borrower` test package, holding 10 units of the
+/// pooled asset to pay the fees
+fn instantiate_borrower(
+    pool: &mut TestPool,
+    reentrant_pool: Option<ComponentAddress>,
+) -> ComponentAddress {
+    let package_address = pool.env.test_runner.compile_and_publish(format!(
+        "{}/tests/flashloan_borrower",
+        env!("CARGO_MANIFEST_DIR")
+    ));
+
+    let manifest = ManifestBuilder::new()
+        .withdraw_from_account(pool.account, pool.asset, dec!(10))
+        .take_all_from_worktop(pool.asset, "fees")
+        .call_function_with_name_lookup(
+            package_address,
+            "FlashloanBorrower",
+            "instantiate",
+            |lookup| (lookup.bucket("fees"), reentrant_pool),
+        )
+        .build();
+    let proofs = pool.proofs();
+
+    pool.env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, proofs)
+        .expect_commit_success()
+        .new_component_addresses()[0]
+}
+
+#[test]
+fn test_flashloan_and_call_collects_the_fee() {
+    let mut pool = TestPool::new(18);
+    pool.contribute(dec!(100)).expect_commit_success();
+    let borrower = instantiate_borrower(&mut pool, None);
+
+    pool.call(
+        "flashloan_and_call",
+        manifest_args!(borrower, dec!(50), dec!(1), ()),
+    )
+    .expect_commit_success();
+
+    assert_eq!(
+        pool.get::<(Decimal, Decimal)>("get_pooled_amount", manifest_args!()),
+        (dec!(101), dec!(0))
+    );
+
+    // The borrower can not repay more than it holds
+    pool.call(
+        "flashloan_and_call",
+        manifest_args!(borrower, dec!(50), dec!(20), ()),
+    )
+    .expect_commit_failure();
+}
+
+#[test]
+fn test_flashloan_fee_rate_changes_are_timelocked() {
+    let mut pool = TestPool::with_config(18, |mut config, _| {
+        config.config_timelock_epochs = 10;
+        config
+    });
+    pool.contribute(dec!(100)).expect_commit_success();
+    let borrower = instantiate_borrower(&mut pool, None);
+
+    pool.call(
+        "queue_config_change",
+        manifest_args!(TestConfigChange::FlashloanFeeRate(dec!("0.01"))),
+    )
+    .expect_commit_success();
+    assert_eq!(
+        pool.get::<Decimal>("get_flashloan_fee_rate", manifest_args!()),
+        dec!(0)
+    );
+
+    // The previous rate applies until the change is applied
+    pool.call(
+        "flashloan_and_call",
+        manifest_args!(borrower, dec!(50), dec!(0), ()),
+    )
+    .expect_commit_success();
+
+    pool.env.set_epoch(20);
+    pool.call("apply_config_change", manifest_args!(0u64))
+        .expect_commit_success();
+    assert_eq!(
+        pool.get::<Decimal>("get_flashloan_fee_rate", manifest_args!()),
+        dec!("0.01")
+    );
+
+    expect_failure_containing(
+        pool.call(
+            "flashloan_and_call",
+            manifest_args!(borrower, dec!(50), dec!("0.4"), ()),
+        ),
+        "POOL_ERR_022",
+    );
+    pool.call(
+        "flashloan_and_call",
+        manifest_args!(borrower, dec!(50), dec!("0.5"), ()),
+    )
+    .expect_commit_success();
+
+    assert_eq!(
+        pool.get::<(Decimal, Decimal)>("get_pooled_amount", manifest_args!()),
+        (dec!("100.5"), dec!(0))
+    );
+
+    // Rates above 100% are rejected when queued
+    expect_failure_containing(
+        pool.call(
+            "queue_config_change",
+            manifest_args!(TestConfigChange::FlashloanFeeRate(dec!("1.1"))),
+        ),
+        "POOL_ERR_010",
+    );
+}
+
+#[test]
+fn test_flashloan_callback_can_not_reenter_the_pool() {
+    let mut pool = TestPool::new(18);
+    pool.contribute(dec!(100)).expect_commit_success();
+    let pool_address = pool.pool;
+    let borrower = instantiate_borrower(&mut pool, Some(pool_address));
+
+    // The call made by the borrower is allowed on its own
+    pool.call("increase_external_liquidity", manifest_args!(dec!(0)))
+        .expect_commit_success();
+
+    // During the callback it is rejected by the guard, or by the engine when it still holds
+    // the lock on the pool state
+    pool.call(
+        "flashloan_and_call",
+        manifest_args!(borrower, dec!(50), dec!(1), ()),
+    )
+    .expect_specific_failure(|error| {
+        let error = format!("{:?}", error);
+        error.contains("Reentrant call") || error.contains("SubstateLocked")
+    });
+}
+
+#[test]
+fn test_pool_can_not_be_reentered_while_a_flashloan_is_outstanding() {
+    let mut pool = TestPool::new(18);
+    pool.contribute(dec!(100)).expect_commit_success();
+    let asset = pool.asset;
+
+    // The pool state is not locked between the calls of the manifest, the guard rejects the
+    // contribution made with the loan
+    let builder = ManifestBuilder::new()
+        .call_method(
+            pool.pool,
+            "take_flashloan",
+            manifest_args!(dec!(60), dec!(0)),
+        )
+        .take_from_worktop(asset, dec!(10), "assets")
+        .call_method_with_name_lookup(pool.pool, "contribute", |lookup| (lookup.bucket("assets"),));
+    expect_failure_containing(pool.execute(builder), "Reentrant call");
+
+    // The guard is released by the repayment
+    let builder = ManifestBuilder::new()
+        .call_method(
+            pool.pool,
+            "take_flashloan",
+            manifest_args!(dec!(60), dec!(0)),
+        )
+        .take_all_from_worktop(pool.asset, "repayment")
+        .take_all_from_worktop(pool.flashloan_term, "terms")
+        .call_method_with_name_lookup(pool.pool, "repay_flashloan", |lookup| {
+            (lookup.bucket("repayment"), lookup.bucket("terms"))
+        })
+        .call_method(pool.pool, "accrue_interest", manifest_args!());
+    pool.execute(builder).expect_commit_success();
+
+    pool.contribute(dec!(10)).expect_commit_success();
+}
+
+#[test]
+fn test_instantiate_with_config_rejects_invalid_parameters() {
+    let mut env = TestEnv::new(this_package!(), 1);
+    let account = env.accounts[0];
+    let asset = env
+        .test_runner
+        .create_fungible_resource(dec!(100), 18, account);
+
+    let config = TestPoolConfig {
+        max_external_liquidity_ratio: Some(dec!("1.5")),
+        ..TestPoolConfig::new(OwnerRole::None)
+    };
+    let manifest = ManifestBuilder::new()
+        .call_function(
+            env.package_address,
+            "AssetPool",
+            "instantiate_with_config",
+            manifest_args!(asset, config),
+        )
+        .build();
+
+    expect_failure_containing(
+        env.test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]),
+        "POOL_ERR_010",
+    );
+}
+
+#[test]
+fn test_asset_pool_stub_calls_every_pool_method() {
+    let mut env = TestEnv::new(this_package!(), 1);
+    let account = env.accounts[0];
+    let asset = env
+        .test_runner
+        .create_fungible_resource(dec!(1000), 18, account);
+    let other = env
+        .test_runner
+        .create_fungible_resource(dec!(100), 18, account);
+    let admin_badge = env
+        .test_runner
+        .create_fungible_resource(dec!(1), 0, account);
+    let holder_badge = env.test_runner.create_non_fungible_resource(account);
+
+    let config = TestPoolConfig {
+        admin_rule: rule!(require(admin_badge)),
+        ..TestPoolConfig::new(OwnerRole::Fixed(rule!(require(admin_badge))))
+    };
+    let manifest = ManifestBuilder::new()
+        .call_function(
+            env.package_address,
+            "AssetPool",
+            "instantiate_with_config",
+            manifest_args!(asset, config),
+        )
+        .build();
+    let receipt = env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![]);
+    let commit = receipt.expect_commit_success();
+
+    let mut pool = TestPool {
+        pool: commit.new_component_addresses()[0],
+        pool_unit: commit.new_resource_addresses()[0],
+        flashloan_term: commit.new_resource_addresses()[1],
+        env,
+        account,
+        asset,
+    };
+    let borrower = instantiate_borrower(&mut pool, None);
+
+    // The probe holds the badges, every pool method is called through the stub
+    let probe_package_address = pool.env.test_runner.compile_and_publish(format!(
+        "{}/tests/asset_pool_probe",
+        env!("CARGO_MANIFEST_DIR")
+    ));
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(account, admin_badge, dec!(1))
+        .withdraw_non_fungibles_from_account(
+            account,
+            holder_badge,
+            &BTreeSet::from([NonFungibleLocalId::integer(1)]),
+        )
+        .take_all_from_worktop(admin_badge, "admin_badge")
+        .take_all_from_worktop(holder_badge, "holder_badge")
+        .call_function_with_name_lookup(
+            probe_package_address,
+            "AssetPoolProbe",
+            "instantiate",
+            |lookup| {
+                (
+                    pool.pool,
+                    lookup.bucket("admin_badge"),
+                    lookup.bucket("holder_badge"),
+                )
+            },
+        );
+    let receipt = pool.execute(builder);
+    let probe = receipt.expect_commit_success().new_component_addresses()[0];
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(account, asset, dec!(100))
+        .take_all_from_worktop(asset, "assets")
+        .call_method_with_name_lookup(probe, "probe_pool_interface", |lookup| {
+            (lookup.bucket("assets"),)
+        });
+    pool.execute(builder).expect_commit_success();
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(account, asset, dec!(100))
+        .withdraw_from_account(account, other, dec!(100))
+        .take_all_from_worktop(asset, "assets")
+        .take_all_from_worktop(other, "other")
+        .call_method_with_name_lookup(probe, "probe_admin_methods", |lookup| {
+            (lookup.bucket("assets"), lookup.bucket("other"), borrower)
+        });
+    pool.execute(builder).expect_commit_success();
+
+    let builder =
+        ManifestBuilder::new().call_method(probe, "probe_owner_methods", manifest_args!());
+    pool.execute(builder).expect_commit_success();
+
+    // Everything the probe got back was returned to the account
+    let (pooled_amount, external_amount) =
+        pool.get::<(Decimal, Decimal)>("get_pooled_amount", manifest_args!());
+    let pool_unit_supply = pool.get::<Decimal>("get_pool_unit_supply", manifest_args!());
+
+    assert_eq!(external_amount, dec!(0));
+    assert_eq!(pool.balance(asset) + pooled_amount, dec!(991));
+    assert_eq!(pool.balance(pool.pool_unit), pool_unit_supply);
+    assert_eq!(pool.balance(other), dec!(100));
+}
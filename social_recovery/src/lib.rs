@@ -0,0 +1,396 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use scrypto::prelude::*;
+
+/// Maximum number of guardians
+pub const MAX_GUARDIANS: usize = 20;
+
+/// Badge of the current owner. Only the badge of the latest generation is valid.
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct ControllerBadge {
+    pub generation: u64,
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct GuardianBadge {
+    pub added_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct PendingRecovery {
+    pub new_owner: ComponentAddress,
+    pub executable_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RecoveryInitiatedEvent {
+    pub new_owner: ComponentAddress,
+    pub executable_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RecoveryVetoedEvent {
+    pub new_owner: ComponentAddress,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct OwnerRotatedEvent {
+    pub new_owner: ComponentAddress,
+    pub generation: u64,
+}
+
+#[blueprint]
+#[events(RecoveryInitiatedEvent, RecoveryVetoedEvent, OwnerRotatedEvent)]
+pub mod social_recovery {
+
+    enable_method_auth! {
+        methods {
+
+            create_owner_proof => PUBLIC;
+            withdraw_owner_badge => PUBLIC;
+            add_guardian => PUBLIC;
+            remove_guardian => PUBLIC;
+            set_threshold => PUBLIC;
+            set_delay => PUBLIC;
+            veto => PUBLIC;
+
+            vote => PUBLIC;
+            execute_recovery => PUBLIC;
+
+            get_guardians => PUBLIC;
+            get_pending_recovery => PUBLIC;
+            get_generation => PUBLIC;
+
+        }
+    }
+
+    pub struct SocialRecovery {
+        /// Owner badge of the protected component or account
+        owner_badge: Vault,
+
+        /// Controller badge non-fungible resource manager
+        controller_res_manager: ResourceManager,
+        generation: u64,
+
+        /// Guardian badge non-fungible resource manager
+        guardian_res_manager: ResourceManager,
+
+        /// Ids of the badges of the current guardians
+        guardians: IndexSet<NonFungibleLocalId>,
+        next_guardian_id: u64,
+
+        /// Number of guardian votes for the same new owner required to initiate a recovery
+        threshold: u64,
+
+        /// Number of epochs during which the current owner can veto a recovery
+        delay_epochs: u64,
+
+        /// New owner voted by each guardian
+        votes: IndexMap<NonFungibleLocalId, ComponentAddress>,
+
+        pending_recovery: Option<PendingRecovery>,
+    }
+
+    impl SocialRecovery {
+        /// Protect an owner badge with guardians. Guardian badges are deposited to the guardian
+        /// accounts. Returns the component and the controller badge of the current owner.
+        pub fn instantiate(
+            owner_badge: Bucket,
+            guardians: Vec<Global<Account>>,
+            threshold: u64,
+            delay_epochs: u64,
+        ) -> (Global<SocialRecovery>, Bucket) {
+            /* CHECK INPUT */
+            assert!(!owner_badge.is_empty(), "Owner badge must not be empty");
+            assert!(guardians.len() <= MAX_GUARDIANS, "Too many guardians");
+            assert!(
+                threshold > 0 && threshold <= guardians.len() as u64,
+                "Threshold must be between 1 and the number of guardians"
+            );
+            assert!(delay_epochs > 0, "Delay must be greater than zero");
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(SocialRecovery::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let controller_badge =
+                ResourceBuilder::new_integer_non_fungible::<ControllerBadge>(OwnerRole::None)
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .mint_initial_supply([(
+                        NonFungibleLocalId::integer(1),
+                        ControllerBadge { generation: 1 },
+                    )]);
+
+            let guardian_count = guardians.len() as u64;
+            let added_at_epoch = Runtime::current_epoch().number();
+
+            let mut guardian_badges =
+                ResourceBuilder::new_integer_non_fungible::<GuardianBadge>(OwnerRole::None)
+                    .mint_roles(mint_roles! {
+                        minter => component_rule;
+                        minter_updater => rule!(deny_all);
+                    })
+                    .mint_initial_supply((1..=guardian_count).map(|id| {
+                        (
+                            NonFungibleLocalId::integer(id),
+                            GuardianBadge { added_at_epoch },
+                        )
+                    }));
+
+            let guardian_res_manager = guardian_badges.resource_manager();
+
+            for (id, mut guardian) in (1..=guardian_count).zip(guardians) {
+                let badge = guardian_badges.take_non_fungible(&NonFungibleLocalId::integer(id));
+                guardian.try_deposit_or_abort(badge.into(), None);
+            }
+
+            let recovery_component = Self {
+                owner_badge: Vault::with_bucket(owner_badge),
+                controller_res_manager: controller_badge.resource_manager(),
+                generation: 1,
+                guardian_res_manager,
+                guardians: (1..=guardian_count)
+                    .map(NonFungibleLocalId::integer)
+                    .collect(),
+                next_guardian_id: guardian_count + 1,
+                threshold,
+                delay_epochs,
+                votes: IndexMap::new(),
+                pending_recovery: None,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .with_address(address_reservation)
+            .globalize();
+
+            (recovery_component, controller_badge.into())
+        }
+
+        /* OWNER METHODS */
+
+        /// Create a proof of the protected owner badge, to act as the owner of the protected
+        /// component or account
+        pub fn create_owner_proof(&self, controller_proof: Proof) -> Proof {
+            self._check_controller_proof(controller_proof);
+
+            if self.owner_badge.resource_address().is_fungible() {
+                self.owner_badge
+                    .as_fungible()
+                    .create_proof_of_amount(self.owner_badge.amount())
+                    .into()
+            } else {
+                let owner_badge = self.owner_badge.as_non_fungible();
+
+                owner_badge
+                    .create_proof_of_non_fungibles(&owner_badge.non_fungible_local_ids(u32::MAX))
+                    .into()
+            }
+        }
+
+        /// Take the owner badge out of the recovery component
+        pub fn withdraw_owner_badge(&mut self, controller_proof: Proof) -> Bucket {
+            self._check_controller_proof(controller_proof);
+
+            self.owner_badge.take_all()
+        }
+
+        pub fn add_guardian(&mut self, controller_proof: Proof, guardian: Global<Account>) {
+            self._check_controller_proof(controller_proof);
+
+            /* CHECK INPUT */
+            assert!(self.guardians.len() < MAX_GUARDIANS, "Too many guardians");
+
+            let guardian_id = NonFungibleLocalId::integer(self.next_guardian_id);
+            self.next_guardian_id += 1;
+
+            let badge = self.guardian_res_manager.mint_non_fungible(
+                &guardian_id,
+                GuardianBadge {
+                    added_at_epoch: Runtime::current_epoch().number(),
+                },
+            );
+
+            self.guardians.insert(guardian_id);
+
+            let mut guardian = guardian;
+            guardian.try_deposit_or_abort(badge, None);
+        }
+
+        pub fn remove_guardian(
+            &mut self,
+            controller_proof: Proof,
+            guardian_id: NonFungibleLocalId,
+        ) {
+            self._check_controller_proof(controller_proof);
+
+            assert!(self.guardians.shift_remove(&guardian_id), "Not a guardian");
+            assert!(
+                self.guardians.len() as u64 >= self.threshold,
+                "Removing this guardian would make the threshold unreachable"
+            );
+
+            self.votes.shift_remove(&guardian_id);
+        }
+
+        pub fn set_threshold(&mut self, controller_proof: Proof, threshold: u64) {
+            self._check_controller_proof(controller_proof);
+
+            /* CHECK INPUT */
+            assert!(
+                threshold > 0 && threshold <= self.guardians.len() as u64,
+                "Threshold must be between 1 and the number of guardians"
+            );
+
+            self.threshold = threshold;
+        }
+
+        pub fn set_delay(&mut self, controller_proof: Proof, delay_epochs: u64) {
+            self._check_controller_proof(controller_proof);
+
+            /* CHECK INPUT */
+            assert!(delay_epochs > 0, "Delay must be greater than zero");
+
+            self.delay_epochs = delay_epochs;
+        }
+
+        /// Cancel the pending recovery and clear all guardian votes
+        pub fn veto(&mut self, controller_proof: Proof) {
+            self._check_controller_proof(controller_proof);
+
+            let pending_recovery = self.pending_recovery.take().expect("No pending recovery");
+
+            self.votes.clear();
+
+            Runtime::emit_event(RecoveryVetoedEvent {
+                new_owner: pending_recovery.new_owner,
+            });
+        }
+
+        /* GUARDIAN METHODS */
+
+        /// Vote for a new owner account. Once enough guardians vote for the same account, the
+        /// recovery can be executed after the delay unless the current owner vetoes it.
+        pub fn vote(&mut self, guardian_proof: Proof, new_owner: ComponentAddress) {
+            let guardian_id = guardian_proof
+                .check(self.guardian_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id();
+
+            assert!(self.guardians.contains(&guardian_id), "Not a guardian");
+            assert!(
+                self.pending_recovery.is_none(),
+                "A recovery is already pending"
+            );
+
+            self.votes.insert(guardian_id, new_owner);
+
+            let vote_count = self
+                .votes
+                .values()
+                .filter(|voted_owner| **voted_owner == new_owner)
+                .count() as u64;
+
+            if vote_count >= self.threshold {
+                let executable_at_epoch = Runtime::current_epoch().number() + self.delay_epochs;
+
+                self.pending_recovery = Some(PendingRecovery {
+                    new_owner,
+                    executable_at_epoch,
+                });
+
+                Runtime::emit_event(RecoveryInitiatedEvent {
+                    new_owner,
+                    executable_at_epoch,
+                });
+            }
+        }
+
+        /// Execute the pending recovery once the delay has passed. A controller badge of a new
+        /// generation is deposited to the new owner account, invalidating the previous one.
+        pub fn execute_recovery(&mut self, mut new_owner: Global<Account>) {
+            let pending_recovery = self.pending_recovery.clone().expect("No pending recovery");
+
+            /* CHECK INPUT */
+            assert!(
+                new_owner.address() == pending_recovery.new_owner,
+                "Account is not the new owner"
+            );
+            assert!(
+                Runtime::current_epoch().number() >= pending_recovery.executable_at_epoch,
+                "Recovery delay has not passed yet"
+            );
+
+            self.generation += 1;
+
+            let controller_badge = self.controller_res_manager.mint_non_fungible(
+                &NonFungibleLocalId::integer(self.generation),
+                ControllerBadge {
+                    generation: self.generation,
+                },
+            );
+
+            self.pending_recovery = None;
+            self.votes.clear();
+
+            Runtime::emit_event(OwnerRotatedEvent {
+                new_owner: pending_recovery.new_owner,
+                generation: self.generation,
+            });
+
+            new_owner.try_deposit_or_abort(controller_badge, None);
+        }
+
+        /* GETTERS */
+
+        pub fn get_guardians(&self) -> IndexSet<NonFungibleLocalId> {
+            self.guardians.clone()
+        }
+
+        pub fn get_pending_recovery(&self) -> Option<PendingRecovery> {
+            self.pending_recovery.clone()
+        }
+
+        pub fn get_generation(&self) -> u64 {
+            self.generation
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _check_controller_proof(&self, controller_proof: Proof) {
+            let controller_id = controller_proof
+                .check(self.controller_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id();
+
+            assert!(
+                controller_id == NonFungibleLocalId::integer(self.generation),
+                "Controller badge has been rotated"
+            );
+        }
+    }
+}
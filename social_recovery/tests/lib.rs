@@ -0,0 +1,382 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Holds the controller badge of the first generation
+const OWNER: usize = 0;
+/// Holds the guardian badge 1
+const GUARDIAN_1: usize = 1;
+/// Holds the guardian badge 2
+const GUARDIAN_2: usize = 2;
+/// Holds the guardian badge 3
+const GUARDIAN_3: usize = 3;
+/// New account of the owner
+const NEW_OWNER: usize = 4;
+
+/// Recovery component protecting an owner badge at epoch 10, with 3 guardians, a threshold of
+/// 2 votes and a veto delay of 5 epochs
+struct TestRecovery {
+    env: TestEnv,
+    recovery: ComponentAddress,
+    controller_badge: ResourceAddress,
+    guardian_badge: ResourceAddress,
+    owner_badge: ResourceAddress,
+}
+
+impl TestRecovery {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 5);
+        env.set_epoch(10);
+        let owner_badge = env
+            .test_runner
+            .create_fungible_resource(dec!(1), 0, env.accounts[OWNER]);
+
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account(env.accounts[OWNER], owner_badge, dec!(1))
+            .take_all_from_worktop(owner_badge, "owner_badge")
+            .call_function_with_name_lookup(
+                env.package_address,
+                "SocialRecovery",
+                "instantiate",
+                |lookup| {
+                    (
+                        lookup.bucket("owner_badge"),
+                        vec![
+                            env.accounts[GUARDIAN_1],
+                            env.accounts[GUARDIAN_2],
+                            env.accounts[GUARDIAN_3],
+                        ],
+                        2u64,
+                        5u64,
+                    )
+                },
+            )
+            .deposit_batch(env.accounts[OWNER])
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            recovery: commit.new_component_addresses()[0],
+            controller_badge: commit.new_resource_addresses()[0],
+            guardian_badge: commit.new_resource_addresses()[1],
+            env,
+            owner_badge,
+        }
+    }
+
+    /// Instantiate another recovery component protecting the given amount of a new badge of
+    /// the owner, with the guardian 1 repeated as every guardian
+    fn instantiate(
+        &mut self,
+        guardian_count: usize,
+        threshold: u64,
+        delay_epochs: u64,
+        badge_amount: Decimal,
+    ) -> TransactionReceipt {
+        let owner_badge =
+            self.env
+                .test_runner
+                .create_fungible_resource(dec!(1), 0, self.env.accounts[OWNER]);
+        let guardians = vec![self.env.accounts[GUARDIAN_1]; guardian_count];
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[OWNER], owner_badge, badge_amount)
+            .take_all_from_worktop(owner_badge, "owner_badge")
+            .call_function_with_name_lookup(
+                self.env.package_address,
+                "SocialRecovery",
+                "instantiate",
+                |lookup| {
+                    (
+                        lookup.bucket("owner_badge"),
+                        guardians,
+                        threshold,
+                        delay_epochs,
+                    )
+                },
+            );
+
+        self.env.execute(OWNER, builder)
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.recovery, method, args)
+    }
+
+    /// Call a method with a proof of the given badge of the account
+    fn call_with_badge<A: ResolvableArguments>(
+        &mut self,
+        account: usize,
+        res_address: ResourceAddress,
+        method: &str,
+        args: impl FnOnce(ManifestProof) -> A,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(self.env.accounts[account], res_address, dec!(1))
+            .pop_from_auth_zone("badge")
+            .call_method_with_name_lookup(self.recovery, method, |lookup| {
+                args(lookup.proof("badge"))
+            });
+
+        self.env.execute(account, builder)
+    }
+
+    /// Call a method with a proof of the controller badge of the account
+    fn call_as_controller<A: ResolvableArguments>(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl FnOnce(ManifestProof) -> A,
+    ) -> TransactionReceipt {
+        self.call_with_badge(account, self.controller_badge, method, args)
+    }
+
+    fn vote(&mut self, guardian: usize, new_owner: usize) -> TransactionReceipt {
+        let new_owner = self.env.accounts[new_owner];
+
+        self.call_with_badge(guardian, self.guardian_badge, "vote", |proof| {
+            (proof, new_owner)
+        })
+    }
+
+    fn execute_recovery(&mut self, new_owner: usize) -> TransactionReceipt {
+        let new_owner = self.env.accounts[new_owner];
+
+        self.call(GUARDIAN_1, "execute_recovery", manifest_args!(new_owner))
+    }
+
+    /// New owner and executable epoch of the pending recovery
+    fn get_pending_recovery(&mut self) -> Option<(ComponentAddress, u64)> {
+        self.call(OWNER, "get_pending_recovery", manifest_args!())
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_guardians_recover_the_controller() {
+    let mut recovery = TestRecovery::new();
+    let (controller_badge, owner_badge) = (recovery.controller_badge, recovery.owner_badge);
+    let new_owner = recovery.env.accounts[NEW_OWNER];
+
+    recovery
+        .call_as_controller(OWNER, "create_owner_proof", |proof| (proof,))
+        .expect_commit_success();
+
+    recovery.vote(GUARDIAN_1, NEW_OWNER).expect_commit_success();
+    assert_eq!(recovery.get_pending_recovery(), None);
+    recovery.vote(GUARDIAN_2, NEW_OWNER).expect_commit_success();
+    assert_eq!(recovery.get_pending_recovery(), Some((new_owner, 15)));
+    recovery.vote(GUARDIAN_3, OWNER).expect_commit_failure();
+
+    recovery.env.set_epoch(14);
+    recovery.execute_recovery(NEW_OWNER).expect_commit_failure();
+
+    recovery.env.set_epoch(15);
+    recovery.execute_recovery(OWNER).expect_commit_failure();
+    recovery.execute_recovery(NEW_OWNER).expect_commit_success();
+    assert_eq!(recovery.env.balance(NEW_OWNER, controller_badge), dec!(1));
+
+    let generation: u64 = recovery
+        .call(OWNER, "get_generation", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(generation, 2);
+
+    // The controller badge of the first generation is no longer valid
+    recovery
+        .call_as_controller(OWNER, "withdraw_owner_badge", |proof| (proof,))
+        .expect_commit_failure();
+    recovery
+        .call_as_controller(NEW_OWNER, "withdraw_owner_badge", |proof| (proof,))
+        .expect_commit_success();
+    assert_eq!(recovery.env.balance(NEW_OWNER, owner_badge), dec!(1));
+}
+
+#[test]
+fn test_owner_vetoes_and_manages_guardians() {
+    let mut recovery = TestRecovery::new();
+    let (guardian_badge, new_owner) = (recovery.guardian_badge, recovery.env.accounts[NEW_OWNER]);
+
+    recovery
+        .call_as_controller(OWNER, "veto", |proof| (proof,))
+        .expect_commit_failure();
+
+    recovery.vote(GUARDIAN_1, NEW_OWNER).expect_commit_success();
+    recovery.vote(GUARDIAN_2, NEW_OWNER).expect_commit_success();
+    recovery
+        .call_with_badge(GUARDIAN_1, guardian_badge, "veto", |proof| (proof,))
+        .expect_commit_failure();
+    recovery
+        .call_as_controller(OWNER, "veto", |proof| (proof,))
+        .expect_commit_success();
+    assert_eq!(recovery.get_pending_recovery(), None);
+
+    // The votes have been cleared by the veto
+    recovery.vote(GUARDIAN_3, NEW_OWNER).expect_commit_success();
+    assert_eq!(recovery.get_pending_recovery(), None);
+
+    recovery.env.set_epoch(20);
+    recovery.execute_recovery(NEW_OWNER).expect_commit_failure();
+
+    recovery
+        .call_as_controller(OWNER, "set_threshold", |proof| (proof, 4u64))
+        .expect_commit_failure();
+    recovery
+        .call_as_controller(OWNER, "set_threshold", |proof| (proof, 3u64))
+        .expect_commit_success();
+    recovery
+        .call_as_controller(OWNER, "remove_guardian", |proof| {
+            (proof, NonFungibleLocalId::integer(1))
+        })
+        .expect_commit_failure();
+    recovery
+        .call_as_controller(OWNER, "set_threshold", |proof| (proof, 2u64))
+        .expect_commit_success();
+    recovery
+        .call_as_controller(OWNER, "remove_guardian", |proof| {
+            (proof, NonFungibleLocalId::integer(1))
+        })
+        .expect_commit_success();
+    recovery.vote(GUARDIAN_1, NEW_OWNER).expect_commit_failure();
+
+    recovery
+        .call_as_controller(OWNER, "set_delay", |proof| (proof, 0u64))
+        .expect_commit_failure();
+    recovery
+        .call_as_controller(OWNER, "add_guardian", |proof| (proof, new_owner))
+        .expect_commit_success();
+    assert_eq!(recovery.env.balance(NEW_OWNER, guardian_badge), dec!(1));
+
+    let guardians: IndexSet<NonFungibleLocalId> = recovery
+        .call(OWNER, "get_guardians", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(
+        guardians,
+        (2..=4)
+            .map(NonFungibleLocalId::integer)
+            .collect::<IndexSet<NonFungibleLocalId>>()
+    );
+
+    // Guardian 3 already voted for the new owner
+    recovery.vote(GUARDIAN_2, NEW_OWNER).expect_commit_success();
+    assert_eq!(recovery.get_pending_recovery(), Some((new_owner, 25)));
+}
+
+#[test]
+fn test_only_the_latest_votes_of_current_guardians_count() {
+    let mut recovery = TestRecovery::new();
+    let new_owner = recovery.env.accounts[NEW_OWNER];
+
+    // Guardian 1 changes its vote to the account voted by guardian 2
+    recovery.vote(GUARDIAN_1, OWNER).expect_commit_success();
+    recovery.vote(GUARDIAN_2, NEW_OWNER).expect_commit_success();
+    assert_eq!(recovery.get_pending_recovery(), None);
+    recovery.vote(GUARDIAN_1, NEW_OWNER).expect_commit_success();
+    assert_eq!(recovery.get_pending_recovery(), Some((new_owner, 15)));
+
+    recovery
+        .call_as_controller(OWNER, "veto", |proof| (proof,))
+        .expect_commit_success();
+
+    // The vote of a removed guardian is dropped
+    recovery.vote(GUARDIAN_1, NEW_OWNER).expect_commit_success();
+    recovery
+        .call_as_controller(OWNER, "remove_guardian", |proof| {
+            (proof, NonFungibleLocalId::integer(1))
+        })
+        .expect_commit_success();
+    recovery.vote(GUARDIAN_2, NEW_OWNER).expect_commit_success();
+    assert_eq!(recovery.get_pending_recovery(), None);
+
+    recovery.env.set_epoch(12);
+    recovery.vote(GUARDIAN_3, NEW_OWNER).expect_commit_success();
+    assert_eq!(recovery.get_pending_recovery(), Some((new_owner, 17)));
+
+    // A guardian can not vote with the controller badge
+    let controller_badge = recovery.controller_badge;
+    recovery
+        .call_with_badge(OWNER, controller_badge, "vote", |proof| (proof, new_owner))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_failures_carry_recovery_messages() {
+    let mut recovery = TestRecovery::new();
+
+    expect_failure_containing(
+        recovery.instantiate(3, 0, 5, dec!(1)),
+        "Threshold must be between 1 and the number of guardians",
+    );
+    expect_failure_containing(
+        recovery.instantiate(3, 4, 5, dec!(1)),
+        "Threshold must be between 1 and the number of guardians",
+    );
+    expect_failure_containing(
+        recovery.instantiate(3, 2, 0, dec!(1)),
+        "Delay must be greater than zero",
+    );
+    expect_failure_containing(
+        recovery.instantiate(21, 2, 5, dec!(1)),
+        "Too many guardians",
+    );
+    expect_failure_containing(
+        recovery.instantiate(3, 2, 5, dec!(0)),
+        "Owner badge must not be empty",
+    );
+
+    expect_failure_containing(
+        recovery.call_as_controller(OWNER, "veto", |proof| (proof,)),
+        "No pending recovery",
+    );
+    expect_failure_containing(recovery.execute_recovery(NEW_OWNER), "No pending recovery");
+    expect_failure_containing(
+        recovery.call_as_controller(OWNER, "remove_guardian", |proof| {
+            (proof, NonFungibleLocalId::integer(9))
+        }),
+        "Not a guardian",
+    );
+
+    recovery
+        .call_as_controller(OWNER, "set_threshold", |proof| (proof, 3u64))
+        .expect_commit_success();
+    expect_failure_containing(
+        recovery.call_as_controller(OWNER, "remove_guardian", |proof| {
+            (proof, NonFungibleLocalId::integer(1))
+        }),
+        "Removing this guardian would make the threshold unreachable",
+    );
+
+    for guardian in [GUARDIAN_1, GUARDIAN_2, GUARDIAN_3] {
+        recovery.vote(guardian, NEW_OWNER).expect_commit_success();
+    }
+    expect_failure_containing(
+        recovery.vote(GUARDIAN_3, OWNER),
+        "A recovery is already pending",
+    );
+    expect_failure_containing(
+        recovery.execute_recovery(OWNER),
+        "Account is not the new owner",
+    );
+    expect_failure_containing(
+        recovery.execute_recovery(NEW_OWNER),
+        "Recovery delay has not passed yet",
+    );
+
+    recovery.env.set_epoch(15);
+    recovery.execute_recovery(NEW_OWNER).expect_commit_success();
+    expect_failure_containing(
+        recovery.call_as_controller(OWNER, "create_owner_proof", |proof| (proof,)),
+        "Controller badge has been rotated",
+    );
+}
@@ -0,0 +1,457 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::{assert_fungible_res_address, ReentrancyGuard};
+use scrypto::prelude::*;
+
+mod math;
+
+pub use math::*;
+
+/// Maximum amplification coefficient accepted by the pool
+pub const MAX_AMPLIFICATION: Decimal = dec!(10000);
+
+/// LP amount locked in the pool on the first deposit
+pub const MINIMUM_LIQUIDITY: Decimal = dec!("0.000000000000001");
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SwapEvent {
+    pub input_res_address: ResourceAddress,
+    pub input_amount: Decimal,
+    pub output_res_address: ResourceAddress,
+    pub output_amount: Decimal,
+    pub fee_amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct AddLiquidityEvent {
+    pub amounts: IndexMap<ResourceAddress, Decimal>,
+    pub lp_amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RemoveLiquidityEvent {
+    pub amounts: IndexMap<ResourceAddress, Decimal>,
+    pub lp_amount: Decimal,
+}
+
+#[blueprint]
+#[events(SwapEvent, AddLiquidityEvent, RemoveLiquidityEvent)]
+pub mod stableswap_pool {
+
+    enable_method_auth! {
+        methods {
+
+            set_amplification => restrict_to :[OWNER];
+            set_swap_fee => restrict_to :[OWNER];
+
+            add_liquidity => PUBLIC;
+            remove_liquidity => PUBLIC;
+            swap => PUBLIC;
+
+            get_reserves => PUBLIC;
+            get_output_amount => PUBLIC;
+            get_virtual_price => PUBLIC;
+
+        }
+    }
+
+    pub struct StableswapPool {
+        /// Pooled resources, in the order given at instantiation
+        vaults: IndexMap<ResourceAddress, Vault>,
+
+        /// LP token resource manager
+        lp_res_manager: ResourceManager,
+
+        /// LP tokens minted on the first deposit that can never be redeemed
+        locked_liquidity: Vault,
+
+        /// Amplification coefficient. Higher values flatten the curve around the balanced point
+        amplification: Decimal,
+
+        /// Share of the output amount kept in the pool on each swap
+        swap_fee: Decimal,
+
+        /// Rejects nested calls into the state changing user methods
+        reentrancy_guard: ReentrancyGuard,
+    }
+
+    impl StableswapPool {
+        pub fn instantiate(
+            res_addresses: Vec<ResourceAddress>,
+            amplification: Decimal,
+            swap_fee: Decimal,
+            owner_role: OwnerRole,
+        ) -> (Global<StableswapPool>, ResourceAddress) {
+            /* CHECK INPUT */
+            assert!(
+                res_addresses.len() == 2 || res_addresses.len() == 3,
+                "Stableswap pool must have 2 or 3 resources"
+            );
+
+            let mut vaults = IndexMap::default();
+            for res_address in res_addresses {
+                assert_fungible_res_address(res_address, None);
+                assert!(
+                    vaults
+                        .insert(res_address, Vault::new(res_address))
+                        .is_none(),
+                    "Duplicate resource address"
+                );
+            }
+
+            StableswapPool::_assert_valid_amplification(amplification);
+            StableswapPool::_assert_valid_swap_fee(swap_fee);
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(StableswapPool::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let lp_res_manager = ResourceBuilder::new_fungible(owner_role.clone())
+                .mint_roles(mint_roles! {
+                    minter => component_rule.clone();
+                    minter_updater => rule!(deny_all);
+                })
+                .burn_roles(burn_roles! {
+                    burner => component_rule;
+                    burner_updater => rule!(deny_all);
+                })
+                .create_with_no_initial_supply();
+
+            let pool_component = Self {
+                vaults,
+                lp_res_manager,
+                locked_liquidity: Vault::new(lp_res_manager.address()),
+                amplification,
+                swap_fee,
+                reentrancy_guard: ReentrancyGuard::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (pool_component, lp_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn set_amplification(&mut self, amplification: Decimal) {
+            StableswapPool::_assert_valid_amplification(amplification);
+
+            self.amplification = amplification;
+        }
+
+        pub fn set_swap_fee(&mut self, swap_fee: Decimal) {
+            StableswapPool::_assert_valid_swap_fee(swap_fee);
+
+            self.swap_fee = swap_fee;
+        }
+
+        /* LIQUIDITY METHODS */
+
+        /// Add liquidity in any proportion. The first deposit must include every pooled
+        /// resource and `MINIMUM_LIQUIDITY` of its LP tokens are locked in the pool. Imbalanced
+        /// deposits pay the swap fee on the part that deviates from the
+        /// current pool composition, so they cannot be used to swap without fees.
+        pub fn add_liquidity(&mut self, buckets: Vec<Bucket>) -> Bucket {
+            self.reentrancy_guard.enter();
+
+            let old_reserves = self._get_precise_reserves();
+            let old_d = self._compute_d(&old_reserves);
+            let lp_supply = self._get_lp_supply();
+
+            let mut amounts: IndexMap<ResourceAddress, Decimal> = IndexMap::default();
+
+            for bucket in buckets {
+                let res_address = bucket.resource_address();
+                *amounts.entry(res_address).or_insert(Decimal::ZERO) += bucket.amount();
+
+                self.vaults
+                    .get_mut(&res_address)
+                    .expect("Resource does not belong to the pool")
+                    .put(bucket);
+            }
+
+            let new_reserves = self._get_precise_reserves();
+
+            if lp_supply == Decimal::ZERO {
+                assert!(
+                    new_reserves
+                        .iter()
+                        .all(|reserve| *reserve > PreciseDecimal::ZERO),
+                    "First deposit must include every pooled resource"
+                );
+            }
+
+            let new_d = self._compute_d(&new_reserves);
+            assert!(new_d > old_d, "Deposit must increase the pool invariant");
+
+            let lp_amount = if lp_supply == Decimal::ZERO {
+                let lp_amount = StableswapPool::_to_decimal(new_d);
+
+                assert!(
+                    lp_amount > MINIMUM_LIQUIDITY,
+                    "Initial liquidity is too small"
+                );
+
+                lp_amount
+            } else {
+                // Imbalance fee, as a share of the deviation from the ideal balanced deposit
+                let n = PreciseDecimal::from(new_reserves.len() as u32);
+                let fee_per_resource = PreciseDecimal::from(self.swap_fee) * n
+                    / (PreciseDecimal::from(4) * (n - PreciseDecimal::ONE));
+
+                let reserves_after_fee: Vec<PreciseDecimal> = old_reserves
+                    .iter()
+                    .zip(new_reserves.iter())
+                    .map(|(old_reserve, new_reserve)| {
+                        let ideal_reserve = new_d * *old_reserve / old_d;
+                        let deviation = (ideal_reserve - *new_reserve).checked_abs().unwrap();
+
+                        *new_reserve - fee_per_resource * deviation
+                    })
+                    .collect();
+
+                let d_after_fee = self._compute_d(&reserves_after_fee);
+
+                StableswapPool::_to_decimal(
+                    PreciseDecimal::from(lp_supply) * (d_after_fee - old_d) / old_d,
+                )
+            };
+
+            assert!(lp_amount > Decimal::ZERO, "Liquidity amount is too small");
+
+            Runtime::emit_event(AddLiquidityEvent { amounts, lp_amount });
+
+            let mut lp_tokens = self.lp_res_manager.mint(lp_amount);
+
+            // The first LP tokens are locked forever so the supply can never be
+            // brought back to a dust amount to inflate the value of a share
+            if lp_supply == Decimal::ZERO {
+                self.locked_liquidity.put(lp_tokens.take(MINIMUM_LIQUIDITY));
+            }
+
+            self.reentrancy_guard.exit();
+
+            lp_tokens
+        }
+
+        /// Remove liquidity in proportion to the current pool composition
+        pub fn remove_liquidity(&mut self, lp_tokens: Bucket) -> Vec<Bucket> {
+            self.reentrancy_guard.enter();
+
+            /* CHECK INPUT */
+            assert!(
+                lp_tokens.resource_address() == self.lp_res_manager.address(),
+                "LP token resource address mismatch"
+            );
+
+            let lp_amount = lp_tokens.amount();
+            let share = lp_amount / self._get_lp_supply();
+
+            lp_tokens.burn();
+
+            let mut amounts: IndexMap<ResourceAddress, Decimal> = IndexMap::default();
+
+            let buckets: Vec<Bucket> = self
+                .vaults
+                .iter_mut()
+                .map(|(res_address, vault)| {
+                    let bucket = vault.take_advanced(
+                        vault.amount() * share,
+                        WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                    );
+
+                    amounts.insert(*res_address, bucket.amount());
+
+                    bucket
+                })
+                .collect();
+
+            Runtime::emit_event(RemoveLiquidityEvent { amounts, lp_amount });
+
+            self.reentrancy_guard.exit();
+
+            buckets
+        }
+
+        /* SWAP METHODS */
+
+        pub fn swap(&mut self, input: Bucket, output_res_address: ResourceAddress) -> Bucket {
+            self.reentrancy_guard.enter();
+
+            let input_res_address = input.resource_address();
+            let input_amount = input.amount();
+
+            let (output_amount, fee_amount) =
+                self._get_output_amount(input_res_address, input_amount, output_res_address);
+
+            assert!(output_amount > Decimal::ZERO, "Output amount is too small");
+
+            self.vaults.get_mut(&input_res_address).unwrap().put(input);
+
+            let output = self
+                .vaults
+                .get_mut(&output_res_address)
+                .unwrap()
+                .take_advanced(
+                    output_amount,
+                    WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                );
+
+            Runtime::emit_event(SwapEvent {
+                input_res_address,
+                input_amount,
+                output_res_address,
+                output_amount: output.amount(),
+                fee_amount,
+            });
+
+            self.reentrancy_guard.exit();
+
+            output
+        }
+
+        /* GETTERS */
+
+        pub fn get_reserves(&self) -> IndexMap<ResourceAddress, Decimal> {
+            self.vaults
+                .iter()
+                .map(|(res_address, vault)| (*res_address, vault.amount()))
+                .collect()
+        }
+
+        pub fn get_output_amount(
+            &self,
+            input_res_address: ResourceAddress,
+            input_amount: Decimal,
+            output_res_address: ResourceAddress,
+        ) -> Decimal {
+            self._get_output_amount(input_res_address, input_amount, output_res_address)
+                .0
+        }
+
+        /// Value of one LP token in units of the pooled resources, which only increases as fees
+        /// are collected
+        pub fn get_virtual_price(&self) -> Decimal {
+            let lp_supply = self._get_lp_supply();
+
+            if lp_supply == Decimal::ZERO {
+                return Decimal::ONE;
+            }
+
+            let d = self._compute_d(&self._get_precise_reserves());
+
+            StableswapPool::_to_decimal(d / PreciseDecimal::from(lp_supply))
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_amplification(amplification: Decimal) {
+            assert!(
+                amplification >= Decimal::ONE && amplification <= MAX_AMPLIFICATION,
+                "Amplification must be between 1 and {}",
+                MAX_AMPLIFICATION
+            );
+        }
+
+        fn _assert_valid_swap_fee(swap_fee: Decimal) {
+            assert!(
+                swap_fee >= Decimal::ZERO && swap_fee < Decimal::ONE,
+                "Swap fee must be between 0 and 1"
+            );
+        }
+
+        /// Output amount and fee amount of a swap
+        fn _get_output_amount(
+            &self,
+            input_res_address: ResourceAddress,
+            input_amount: Decimal,
+            output_res_address: ResourceAddress,
+        ) -> (Decimal, Decimal) {
+            /* CHECK INPUT */
+            assert!(
+                input_amount > Decimal::ZERO,
+                "Amount must be greater than zero!"
+            );
+
+            let input_index = self
+                .vaults
+                .get_index_of(&input_res_address)
+                .expect("Input resource does not belong to the pool");
+            let output_index = self
+                .vaults
+                .get_index_of(&output_res_address)
+                .expect("Output resource does not belong to the pool");
+
+            let reserves = self._get_precise_reserves();
+            assert!(
+                reserves
+                    .iter()
+                    .all(|reserve| *reserve > PreciseDecimal::ZERO),
+                "Pool has no liquidity"
+            );
+
+            let d = self._compute_d(&reserves);
+            let new_output_reserve = compute_y(
+                &reserves,
+                PreciseDecimal::from(self.amplification),
+                input_index,
+                output_index,
+                reserves[input_index] + input_amount,
+                d,
+            );
+
+            let output_amount_before_fee = reserves[output_index] - new_output_reserve;
+            let fee_amount = output_amount_before_fee * self.swap_fee;
+
+            (
+                StableswapPool::_to_decimal(output_amount_before_fee - fee_amount),
+                StableswapPool::_to_decimal(fee_amount),
+            )
+        }
+
+        fn _compute_d(&self, reserves: &[PreciseDecimal]) -> PreciseDecimal {
+            compute_d(reserves, PreciseDecimal::from(self.amplification))
+        }
+
+        fn _get_precise_reserves(&self) -> Vec<PreciseDecimal> {
+            self.vaults
+                .values()
+                .map(|vault| PreciseDecimal::from(vault.amount()))
+                .collect()
+        }
+
+        fn _get_lp_supply(&self) -> Decimal {
+            self.lp_res_manager.total_supply().unwrap_or(Decimal::ZERO)
+        }
+
+        fn _to_decimal(value: PreciseDecimal) -> Decimal {
+            value
+                .checked_truncate(RoundingMode::ToZero)
+                .expect("Stableswap math overflow")
+        }
+    }
+}
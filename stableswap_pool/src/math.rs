@@ -0,0 +1,100 @@
+use scrypto::prelude::*;
+
+/// Maximum number of Newton iterations before the computation is considered divergent
+pub const MAX_ITERATIONS: u8 = 255;
+
+/// Convergence threshold of the Newton iterations
+pub const CONVERGENCE_THRESHOLD: PreciseDecimal = pdec!("0.000000000000000001");
+
+/// Computes the stableswap invariant `D` of the given reserves, solving
+/// `A * n^n * sum(x) + D = A * D * n^n + D^(n+1) / (n^n * prod(x))` with Newton iterations.
+pub fn compute_d(reserves: &[PreciseDecimal], amplification: PreciseDecimal) -> PreciseDecimal {
+    let n = PreciseDecimal::from(reserves.len() as u32);
+    let sum = reserves
+        .iter()
+        .fold(PreciseDecimal::ZERO, |sum, reserve| sum + *reserve);
+
+    if sum == PreciseDecimal::ZERO {
+        return PreciseDecimal::ZERO;
+    }
+
+    let ann = amplification * n_pow_n(reserves.len());
+    let mut d = sum;
+
+    for _ in 0..MAX_ITERATIONS {
+        let mut d_p = d;
+        for reserve in reserves {
+            d_p = d_p * d / (*reserve * n);
+        }
+
+        let previous_d = d;
+        d = (ann * sum + d_p * n) * d
+            / ((ann - PreciseDecimal::ONE) * d + (n + PreciseDecimal::ONE) * d_p);
+
+        if (d - previous_d).checked_abs().unwrap() <= CONVERGENCE_THRESHOLD {
+            return d;
+        }
+    }
+
+    panic!("Stableswap invariant computation did not converge")
+}
+
+/// Computes the reserve of the resource at `output_index` keeping the invariant `d`
+/// constant, given the reserves with the resource at `input_index` set to `new_input_reserve`.
+pub fn compute_y(
+    reserves: &[PreciseDecimal],
+    amplification: PreciseDecimal,
+    input_index: usize,
+    output_index: usize,
+    new_input_reserve: PreciseDecimal,
+    d: PreciseDecimal,
+) -> PreciseDecimal {
+    assert!(
+        input_index != output_index,
+        "Input and output resources must be different"
+    );
+
+    let n = PreciseDecimal::from(reserves.len() as u32);
+    let ann = amplification * n_pow_n(reserves.len());
+
+    let mut c = d;
+    let mut sum = PreciseDecimal::ZERO;
+
+    for (index, reserve) in reserves.iter().enumerate() {
+        if index == output_index {
+            continue;
+        }
+
+        let x = if index == input_index {
+            new_input_reserve
+        } else {
+            *reserve
+        };
+
+        sum += x;
+        c = c * d / (x * n);
+    }
+
+    c = c * d / (ann * n);
+    let b = sum + d / ann;
+
+    let mut y = d;
+
+    for _ in 0..MAX_ITERATIONS {
+        let previous_y = y;
+        y = (y * y + c) / (PreciseDecimal::from(2) * y + b - d);
+
+        if (y - previous_y).checked_abs().unwrap() <= CONVERGENCE_THRESHOLD {
+            return y;
+        }
+    }
+
+    panic!("Stableswap reserve computation did not converge")
+}
+
+/// Computes `n^n` for the number of resources of the pool
+fn n_pow_n(n: usize) -> PreciseDecimal {
+    (0..n).fold(PreciseDecimal::ONE, |product, _| {
+        product * PreciseDecimal::from(n as u32)
+    })
+}
@@ -0,0 +1,362 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use stableswap_pool::*;
+use test_support::*;
+use transaction::prelude::*;
+
+fn assert_close(value: PreciseDecimal, expected: PreciseDecimal, tolerance: PreciseDecimal) {
+    let error = if value > expected {
+        value - expected
+    } else {
+        expected - value
+    };
+
+    assert!(
+        error <= tolerance,
+        "Expected {} but got {} (error: {})",
+        expected,
+        value,
+        error
+    );
+}
+
+#[test]
+fn test_compute_d_of_balanced_reserves_is_their_sum() {
+    assert_eq!(compute_d(&[pdec!(100), pdec!(100)], pdec!(100)), pdec!(200));
+    assert_eq!(
+        compute_d(&[pdec!(5), pdec!(5), pdec!(5)], pdec!(1)),
+        pdec!(15)
+    );
+}
+
+#[test]
+fn test_compute_d() {
+    let tolerance = CONVERGENCE_THRESHOLD;
+
+    assert_close(
+        compute_d(&[pdec!(100), pdec!(200)], pdec!(10)),
+        pdec!("299.114836439664548230804080731973658191"),
+        tolerance,
+    );
+    assert_close(
+        compute_d(&[pdec!(100), pdec!(50), pdec!(200)], pdec!(100)),
+        pdec!("349.923933297480315106688620918155506069"),
+        tolerance,
+    );
+}
+
+#[test]
+fn test_compute_d_of_empty_reserves_is_zero() {
+    assert_eq!(
+        compute_d(&[PreciseDecimal::ZERO, PreciseDecimal::ZERO], pdec!(100)),
+        PreciseDecimal::ZERO
+    );
+}
+
+#[test]
+fn test_compute_d_converges_on_imbalanced_reserves() {
+    let tolerance = CONVERGENCE_THRESHOLD;
+
+    assert_close(
+        compute_d(&[pdec!(1), pdec!(1000000)], pdec!(1)),
+        pdec!("25039.691458177365098180935244654257141690"),
+        tolerance,
+    );
+    assert_close(
+        compute_d(&[pdec!("0.000001"), pdec!(1000000)], pdec!(100)),
+        pdec!("1169.152241706659870728368328546404548786"),
+        tolerance,
+    );
+}
+
+#[test]
+fn test_compute_y() {
+    let reserves = [pdec!(100), pdec!(100)];
+    let d = compute_d(&reserves, pdec!(100));
+
+    assert_close(
+        compute_y(&reserves, pdec!(100), 0, 1, pdec!(110), d),
+        pdec!("90.005022322992455403306712545598473974"),
+        CONVERGENCE_THRESHOLD,
+    );
+}
+
+#[test]
+fn test_compute_y_keeps_the_invariant() {
+    let amplification = pdec!(100);
+    let reserves = [pdec!(100), pdec!(50), pdec!(200)];
+    let d = compute_d(&reserves, amplification);
+
+    let y = compute_y(&reserves, amplification, 0, 2, pdec!(110), d);
+
+    assert_close(
+        compute_d(&[pdec!(110), pdec!(50), y], amplification),
+        d,
+        pdec!("0.000000000001"),
+    );
+}
+
+#[test]
+fn test_compute_y_of_unchanged_reserves_is_the_output_reserve() {
+    let reserves = [pdec!(100), pdec!(200)];
+    let d = compute_d(&reserves, pdec!(10));
+
+    assert_close(
+        compute_y(&reserves, pdec!(10), 0, 1, pdec!(100), d),
+        pdec!(200),
+        pdec!("0.000000000001"),
+    );
+}
+
+#[test]
+fn test_higher_amplification_gives_a_flatter_curve() {
+    let reserves = [pdec!(100), pdec!(100)];
+    let output = |amplification: PreciseDecimal| {
+        let d = compute_d(&reserves, amplification);
+        reserves[1] - compute_y(&reserves, amplification, 0, 1, pdec!(110), d)
+    };
+
+    let low_amplification_output = output(pdec!(1));
+    let high_amplification_output = output(pdec!(1000));
+
+    assert!(low_amplification_output < high_amplification_output);
+    assert!(high_amplification_output < pdec!(10));
+}
+
+#[test]
+#[should_panic(expected = "Input and output resources must be different")]
+fn test_compute_y_with_same_indexes_panics() {
+    let reserves = [pdec!(100), pdec!(100)];
+
+    compute_y(&reserves, pdec!(100), 1, 1, pdec!(110), pdec!(200));
+}
+
+/// Pool of two resources with an amplification of 100 and a swap fee of 1%, owned by the
+/// account holding 10000 of each resource
+struct TestPool {
+    env: TestEnv,
+    account: ComponentAddress,
+    pool: ComponentAddress,
+    lp: ResourceAddress,
+    res_a: ResourceAddress,
+    res_b: ResourceAddress,
+}
+
+impl TestPool {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 1);
+        let account = env.accounts[0];
+        let res_a = env
+            .test_runner
+            .create_fungible_resource(dec!(10000), 18, account);
+        let res_b = env
+            .test_runner
+            .create_fungible_resource(dec!(10000), 18, account);
+        let owner_badge = env.badges[0].clone();
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "StableswapPool",
+                "instantiate",
+                manifest_args!(
+                    vec![res_a, res_b],
+                    dec!(100),
+                    dec!("0.01"),
+                    OwnerRole::Fixed(rule!(require(owner_badge)))
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            pool: commit.new_component_addresses()[0],
+            lp: commit.new_resource_addresses()[0],
+            env,
+            account,
+            res_a,
+            res_b,
+        }
+    }
+
+    /// Execute the manifest with the account key, then deposit the worktop into the account
+    fn execute(&mut self, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute(0, builder)
+    }
+
+    fn add_liquidity(&mut self, amount_a: Decimal, amount_b: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, self.res_a, amount_a)
+            .withdraw_from_account(self.account, self.res_b, amount_b)
+            .take_all_from_worktop(self.res_a, "bucket_a")
+            .take_all_from_worktop(self.res_b, "bucket_b")
+            .call_method_with_name_lookup(self.pool, "add_liquidity", |lookup| {
+                (vec![lookup.bucket("bucket_a"), lookup.bucket("bucket_b")],)
+            });
+
+        self.execute(builder)
+    }
+
+    fn remove_liquidity(&mut self, lp_amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, self.lp, lp_amount)
+            .take_all_from_worktop(self.lp, "lp_tokens")
+            .call_method_with_name_lookup(self.pool, "remove_liquidity", |lookup| {
+                (lookup.bucket("lp_tokens"),)
+            });
+
+        self.execute(builder)
+    }
+
+    fn swap(&mut self, res_address: ResourceAddress, amount: Decimal) -> TransactionReceipt {
+        let output_res_address = if res_address == self.res_a {
+            self.res_b
+        } else {
+            self.res_a
+        };
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, res_address, amount)
+            .take_all_from_worktop(res_address, "input")
+            .call_method_with_name_lookup(self.pool, "swap", |lookup| {
+                (lookup.bucket("input"), output_res_address)
+            });
+
+        self.execute(builder)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str) -> T {
+        let builder = ManifestBuilder::new().call_method(self.pool, method, manifest_args!());
+
+        self.execute(builder).expect_commit_success().output(0)
+    }
+
+    fn get_reserves(&mut self) -> (Decimal, Decimal) {
+        let reserves = self.get::<IndexMap<ResourceAddress, Decimal>>("get_reserves");
+
+        (reserves[&self.res_a], reserves[&self.res_b])
+    }
+
+    fn balance(&mut self, res_address: ResourceAddress) -> Decimal {
+        self.env.balance(0, res_address)
+    }
+}
+
+#[test]
+fn test_first_deposit_locks_minimum_liquidity() {
+    let mut pool = TestPool::new();
+
+    pool.add_liquidity(dec!(100), dec!(100))
+        .expect_commit_success();
+
+    let lp = pool.lp;
+    assert_eq!(pool.balance(lp), dec!(200) - MINIMUM_LIQUIDITY);
+    assert_eq!(pool.get::<Decimal>("get_virtual_price"), dec!(1));
+}
+
+#[test]
+fn test_first_deposit_below_minimum_liquidity_fails() {
+    let mut pool = TestPool::new();
+    let half_minimum_liquidity = MINIMUM_LIQUIDITY / 2;
+
+    pool.add_liquidity(half_minimum_liquidity, half_minimum_liquidity)
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_remove_liquidity_is_proportional() {
+    let mut pool = TestPool::new();
+
+    pool.add_liquidity(dec!(100), dec!(100))
+        .expect_commit_success();
+    pool.remove_liquidity(dec!(50)).expect_commit_success();
+
+    let (res_a, res_b) = (pool.res_a, pool.res_b);
+    assert_eq!(pool.balance(res_a), dec!(9925));
+    assert_eq!(pool.balance(res_b), dec!(9925));
+    assert_eq!(pool.get_reserves(), (dec!(75), dec!(75)));
+}
+
+#[test]
+fn test_full_exit_leaves_the_locked_liquidity() {
+    let mut pool = TestPool::new();
+
+    pool.add_liquidity(dec!(100), dec!(100))
+        .expect_commit_success();
+
+    let lp = pool.lp;
+    let lp_amount = pool.balance(lp);
+    pool.remove_liquidity(lp_amount).expect_commit_success();
+
+    let (reserve_a, reserve_b) = pool.get_reserves();
+    assert!(reserve_a > dec!(0) && reserve_a <= MINIMUM_LIQUIDITY);
+    assert!(reserve_b > dec!(0) && reserve_b <= MINIMUM_LIQUIDITY);
+}
+
+#[test]
+fn test_imbalanced_deposit_pays_the_fee() {
+    let mut pool = TestPool::new();
+    let (lp, res_a, res_b) = (pool.lp, pool.res_a, pool.res_b);
+
+    pool.add_liquidity(dec!(100), dec!(100))
+        .expect_commit_success();
+
+    // A balanced deposit does not pay the fee: 200 LP tokens for an invariant of 200
+    pool.add_liquidity(dec!(5), dec!(5)).expect_commit_success();
+    assert_eq!(pool.balance(lp), dec!(210) - MINIMUM_LIQUIDITY);
+
+    // The same value deposited in a single resource gets fewer LP tokens
+    let lp_before = pool.balance(lp);
+    pool.add_liquidity(dec!(10), dec!(0))
+        .expect_commit_success();
+    let imbalanced_lp_amount = pool.balance(lp) - lp_before;
+    assert!(imbalanced_lp_amount < dec!(10));
+    assert!(imbalanced_lp_amount > dec!("9.9"));
+
+    // Exiting right away gives back less than deposited, so deposits and exits can not be
+    // used to swap without fees
+    let (balance_a, balance_b) = (pool.balance(res_a), pool.balance(res_b));
+    pool.remove_liquidity(imbalanced_lp_amount)
+        .expect_commit_success();
+    let returned_amount = pool.balance(res_a) - balance_a + pool.balance(res_b) - balance_b;
+    assert!(returned_amount < dec!(10));
+}
+
+#[test]
+fn test_swaps_do_not_decrease_the_invariant() {
+    let mut pool = TestPool::new();
+    let (res_a, res_b) = (pool.res_a, pool.res_b);
+
+    pool.add_liquidity(dec!(1000), dec!(1000))
+        .expect_commit_success();
+
+    // The fees kept in the pool increase the invariant per LP token on every swap
+    let mut virtual_price = pool.get::<Decimal>("get_virtual_price");
+    for (res_address, amount) in [(res_a, dec!(100)), (res_b, dec!(250)), (res_a, dec!(1))] {
+        pool.swap(res_address, amount).expect_commit_success();
+
+        let new_virtual_price = pool.get::<Decimal>("get_virtual_price");
+        assert!(new_virtual_price > virtual_price);
+        virtual_price = new_virtual_price;
+    }
+
+    // The invariant of the reserves matches the virtual price of the LP supply
+    let (reserve_a, reserve_b) = pool.get_reserves();
+    let d = compute_d(
+        &[
+            PreciseDecimal::from(reserve_a),
+            PreciseDecimal::from(reserve_b),
+        ],
+        pdec!(100),
+    );
+    assert_close(
+        d / pdec!(2000),
+        PreciseDecimal::from(virtual_price),
+        pdec!("0.000000000001"),
+    );
+
+    pool.swap(res_a, dec!(0)).expect_commit_failure();
+}
@@ -0,0 +1,356 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct StakeReceipt {
+    pub created_at_epoch: u64,
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub struct StakePosition {
+    pub staked_amount: Decimal,
+
+    /// Value of the reward per token accumulator when the rewards were last computed
+    pub reward_per_token_paid: PreciseDecimal,
+
+    /// Rewards computed but not claimed yet
+    pub pending_rewards: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RewardsClaimedEvent {
+    pub position_id: NonFungibleLocalId,
+    pub amount: Decimal,
+}
+
+#[blueprint]
+#[events(RewardsClaimedEvent)]
+pub mod staking_rewards {
+
+    enable_method_auth! {
+        roles {
+            admin => updatable_by: [];
+        },
+        methods {
+
+            set_reward_rate => restrict_to :[admin, OWNER];
+            top_up_rewards => PUBLIC;
+
+            stake => PUBLIC;
+            add_stake => PUBLIC;
+            unstake => PUBLIC;
+            claim => PUBLIC;
+
+            get_pending_rewards => PUBLIC;
+            get_position => PUBLIC;
+            get_total_staked => PUBLIC;
+            get_reward_rate => PUBLIC;
+
+        }
+    }
+
+    pub struct StakingRewards {
+        staked: Vault,
+
+        /// Rewards funded but not distributed yet, and distributed rewards not claimed yet
+        rewards: Vault,
+
+        /// Part of the rewards vault not distributed yet
+        reward_budget: Decimal,
+
+        /// Rewards distributed to all stakers every epoch, while the budget lasts
+        reward_rate_per_epoch: Decimal,
+
+        /// Rewards distributed per staked token since instantiation
+        reward_per_token: PreciseDecimal,
+        last_update_epoch: u64,
+
+        /// Stake receipt non-fungible resource manager
+        receipt_res_manager: ResourceManager,
+        positions: KeyValueStore<NonFungibleLocalId, StakePosition>,
+    }
+
+    impl StakingRewards {
+        pub fn instantiate(
+            staked_res_address: ResourceAddress,
+            reward_res_address: ResourceAddress,
+            reward_rate_per_epoch: Decimal,
+            owner_role: OwnerRole,
+            admin_rule: AccessRule,
+        ) -> (Global<StakingRewards>, ResourceAddress) {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                staked_res_address,
+                Some("Staked resource must be fungible".to_string()),
+            );
+            assert_fungible_res_address(
+                reward_res_address,
+                Some("Reward resource must be fungible".to_string()),
+            );
+            assert!(
+                reward_rate_per_epoch >= Decimal::ZERO,
+                "Reward rate must not be negative"
+            );
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(StakingRewards::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let receipt_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<StakeReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule;
+                        minter_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let staking_component = Self {
+                staked: Vault::new(staked_res_address),
+                rewards: Vault::new(reward_res_address),
+                reward_budget: Decimal::ZERO,
+                reward_rate_per_epoch,
+                reward_per_token: PreciseDecimal::ZERO,
+                last_update_epoch: Runtime::current_epoch().number(),
+                receipt_res_manager,
+                positions: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                admin => admin_rule;
+            ))
+            .with_address(address_reservation)
+            .globalize();
+
+            (staking_component, receipt_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn set_reward_rate(&mut self, reward_rate_per_epoch: Decimal) {
+            assert!(
+                reward_rate_per_epoch >= Decimal::ZERO,
+                "Reward rate must not be negative"
+            );
+
+            // Rewards distributed so far use the previous rate
+            self._update_reward_per_token();
+
+            self.reward_rate_per_epoch = reward_rate_per_epoch;
+        }
+
+        /// Add rewards to be distributed. Anyone can fund the rewards.
+        pub fn top_up_rewards(&mut self, rewards: Bucket) {
+            /* CHECK INPUT */
+            assert!(
+                rewards.resource_address() == self.rewards.resource_address(),
+                "Reward resource address mismatch"
+            );
+
+            self._update_reward_per_token();
+
+            self.reward_budget += rewards.amount();
+            self.rewards.put(rewards);
+        }
+
+        /* STAKER METHODS */
+
+        /// Stake tokens in a new position. Returns the stake receipt.
+        pub fn stake(&mut self, tokens: Bucket) -> Bucket {
+            self._update_reward_per_token();
+
+            let receipt = self
+                .receipt_res_manager
+                .mint_ruid_non_fungible(StakeReceipt {
+                    created_at_epoch: Runtime::current_epoch().number(),
+                });
+
+            self.positions.insert(
+                receipt.as_non_fungible().non_fungible_local_id(),
+                StakePosition {
+                    staked_amount: Decimal::ZERO,
+                    reward_per_token_paid: self.reward_per_token,
+                    pending_rewards: Decimal::ZERO,
+                },
+            );
+
+            self._add_stake(receipt.as_non_fungible().non_fungible_local_id(), tokens);
+
+            receipt
+        }
+
+        pub fn add_stake(&mut self, receipt_proof: Proof, tokens: Bucket) {
+            let position_id = self._check_receipt_proof(receipt_proof);
+
+            self._update_reward_per_token();
+
+            self._add_stake(position_id, tokens);
+        }
+
+        pub fn unstake(&mut self, receipt_proof: Proof, amount: Decimal) -> Bucket {
+            let position_id = self._check_receipt_proof(receipt_proof);
+
+            /* CHECK INPUT */
+            assert!(amount > Decimal::ZERO, "Amount must be greater than zero!");
+
+            self._update_reward_per_token();
+            self._update_position_rewards(&position_id);
+
+            let mut position = self.positions.get_mut(&position_id).unwrap();
+            assert!(amount <= position.staked_amount, "Not enough staked tokens");
+
+            position.staked_amount -= amount;
+
+            self.staked.take(amount)
+        }
+
+        pub fn claim(&mut self, receipt_proof: Proof) -> Bucket {
+            let position_id = self._check_receipt_proof(receipt_proof);
+
+            self._update_reward_per_token();
+            self._update_position_rewards(&position_id);
+
+            let amount = {
+                let mut position = self.positions.get_mut(&position_id).unwrap();
+                let amount = position.pending_rewards;
+                position.pending_rewards = Decimal::ZERO;
+                amount
+            };
+
+            Runtime::emit_event(RewardsClaimedEvent {
+                position_id,
+                amount,
+            });
+
+            self.rewards.take(amount)
+        }
+
+        /* GETTERS */
+
+        pub fn get_pending_rewards(&self, position_id: NonFungibleLocalId) -> Decimal {
+            let position = self
+                .positions
+                .get(&position_id)
+                .expect("Position not found");
+
+            position.pending_rewards
+                + StakingRewards::_compute_rewards(
+                    position.staked_amount,
+                    self._get_current_reward_per_token().0 - position.reward_per_token_paid,
+                )
+        }
+
+        pub fn get_position(&self, position_id: NonFungibleLocalId) -> StakePosition {
+            self.positions
+                .get(&position_id)
+                .expect("Position not found")
+                .clone()
+        }
+
+        pub fn get_total_staked(&self) -> Decimal {
+            self.staked.amount()
+        }
+
+        pub fn get_reward_rate(&self) -> Decimal {
+            self.reward_rate_per_epoch
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _add_stake(&mut self, position_id: NonFungibleLocalId, tokens: Bucket) {
+            /* CHECK INPUT */
+            assert!(
+                tokens.resource_address() == self.staked.resource_address(),
+                "Staked resource address mismatch"
+            );
+            assert!(
+                tokens.amount() > Decimal::ZERO,
+                "Amount must be greater than zero!"
+            );
+
+            self._update_position_rewards(&position_id);
+
+            self.positions.get_mut(&position_id).unwrap().staked_amount += tokens.amount();
+            self.staked.put(tokens);
+        }
+
+        /// Reward per token accumulator and remaining budget at the current epoch
+        fn _get_current_reward_per_token(&self) -> (PreciseDecimal, Decimal) {
+            let total_staked = self.staked.amount();
+            let elapsed_epochs = Runtime::current_epoch().number() - self.last_update_epoch;
+
+            if total_staked == Decimal::ZERO || elapsed_epochs == 0 {
+                return (self.reward_per_token, self.reward_budget);
+            }
+
+            let distributed = (self.reward_rate_per_epoch * elapsed_epochs).min(self.reward_budget);
+
+            (
+                self.reward_per_token
+                    + PreciseDecimal::from(distributed) / PreciseDecimal::from(total_staked),
+                self.reward_budget - distributed,
+            )
+        }
+
+        fn _update_reward_per_token(&mut self) {
+            let (reward_per_token, reward_budget) = self._get_current_reward_per_token();
+
+            self.reward_per_token = reward_per_token;
+            self.reward_budget = reward_budget;
+            self.last_update_epoch = Runtime::current_epoch().number();
+        }
+
+        /// Move the rewards earned since the last update of a position to its pending rewards.
+        /// The reward per token accumulator must be up to date.
+        fn _update_position_rewards(&mut self, position_id: &NonFungibleLocalId) {
+            let reward_per_token = self.reward_per_token;
+            let mut position = self.positions.get_mut(position_id).unwrap();
+
+            position.pending_rewards += StakingRewards::_compute_rewards(
+                position.staked_amount,
+                reward_per_token - position.reward_per_token_paid,
+            );
+            position.reward_per_token_paid = reward_per_token;
+        }
+
+        fn _compute_rewards(
+            staked_amount: Decimal,
+            reward_per_token_delta: PreciseDecimal,
+        ) -> Decimal {
+            (PreciseDecimal::from(staked_amount) * reward_per_token_delta)
+                .checked_truncate(RoundingMode::ToZero)
+                .unwrap()
+        }
+
+        fn _check_receipt_proof(&self, receipt_proof: Proof) -> NonFungibleLocalId {
+            receipt_proof
+                .check(self.receipt_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+    }
+}
@@ -0,0 +1,322 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use staking_rewards::StakePosition;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owner of the staking component, holding 10000 reward tokens
+const OWNER: usize = 0;
+/// Stakers, holding 1000 staked tokens each
+const ALICE: usize = 1;
+const BOB: usize = 2;
+
+/// Staking component instantiated at epoch 10, distributing 10 rewards per epoch
+struct TestStaking {
+    env: TestEnv,
+    staking: ComponentAddress,
+    receipt: ResourceAddress,
+    staked: ResourceAddress,
+    reward: ResourceAddress,
+}
+
+impl TestStaking {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let staked = env
+            .test_runner
+            .create_fungible_resource(dec!(2000), 18, env.accounts[OWNER]);
+        let reward = env
+            .test_runner
+            .create_fungible_resource(dec!(10000), 18, env.accounts[OWNER]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "StakingRewards",
+                "instantiate",
+                manifest_args!(
+                    staked,
+                    reward,
+                    dec!(10),
+                    OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone()))),
+                    rule!(deny_all)
+                ),
+            )
+            .withdraw_from_account(env.accounts[OWNER], staked, dec!(2000))
+            .take_from_worktop(staked, dec!(1000), "alice")
+            .call_method_with_name_lookup(env.accounts[ALICE], "deposit", |lookup| {
+                (lookup.bucket("alice"),)
+            })
+            .take_from_worktop(staked, dec!(1000), "bob")
+            .call_method_with_name_lookup(env.accounts[BOB], "deposit", |lookup| {
+                (lookup.bucket("bob"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            staking: commit.new_component_addresses()[0],
+            receipt: commit.new_resource_addresses()[0],
+            env,
+            staked,
+            reward,
+        }
+    }
+
+    /// Staking component funded with the given amount of rewards
+    fn funded(amount: Decimal) -> Self {
+        let mut staking = Self::new();
+
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(staking.env.accounts[OWNER], staking.reward, amount)
+            .take_all_from_worktop(staking.reward, "rewards")
+            .call_method_with_name_lookup(staking.staking, "top_up_rewards", |lookup| {
+                (lookup.bucket("rewards"),)
+            });
+        staking.execute(OWNER, builder).expect_commit_success();
+
+        staking
+    }
+
+    /// Execute the manifest with the keys of every account, then deposit the worktop into the
+    /// given account
+    fn execute(&mut self, account: usize, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute_with_all_keys(account, builder)
+    }
+
+    fn stake(&mut self, account: usize, amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[account], self.staked, amount)
+            .take_all_from_worktop(self.staked, "tokens")
+            .call_method_with_name_lookup(self.staking, "stake", |lookup| {
+                (lookup.bucket("tokens"),)
+            });
+
+        self.execute(account, builder)
+    }
+
+    /// Proof of the single stake receipt held by the account, popped as "receipt"
+    fn with_receipt_proof(&self, account: usize) -> ManifestBuilder {
+        ManifestBuilder::new()
+            .create_proof_from_account_of_amount(self.env.accounts[account], self.receipt, dec!(1))
+            .pop_from_auth_zone("receipt")
+    }
+
+    fn add_stake(&mut self, account: usize, amount: Decimal) -> TransactionReceipt {
+        let builder = self
+            .with_receipt_proof(account)
+            .withdraw_from_account(self.env.accounts[account], self.staked, amount)
+            .take_all_from_worktop(self.staked, "tokens")
+            .call_method_with_name_lookup(self.staking, "add_stake", |lookup| {
+                (lookup.proof("receipt"), lookup.bucket("tokens"))
+            });
+
+        self.execute(account, builder)
+    }
+
+    fn unstake(&mut self, account: usize, amount: Decimal) -> TransactionReceipt {
+        let builder = self
+            .with_receipt_proof(account)
+            .call_method_with_name_lookup(self.staking, "unstake", |lookup| {
+                (lookup.proof("receipt"), amount)
+            });
+
+        self.execute(account, builder)
+    }
+
+    fn claim(&mut self, account: usize) -> TransactionReceipt {
+        let builder = self
+            .with_receipt_proof(account)
+            .call_method_with_name_lookup(self.staking, "claim", |lookup| {
+                (lookup.proof("receipt"),)
+            });
+
+        self.execute(account, builder)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str, args: impl ResolvableArguments) -> T {
+        let builder = ManifestBuilder::new().call_method(self.staking, method, args);
+
+        self.execute(OWNER, builder)
+            .expect_commit_success()
+            .output(0)
+    }
+
+    /// Id of the single position of the account
+    fn position_id(&mut self, account: usize) -> NonFungibleLocalId {
+        let vault_id = self
+            .env
+            .test_runner
+            .get_component_vaults(self.env.accounts[account], self.receipt)[0];
+        let (_, mut ids) = self
+            .env
+            .test_runner
+            .inspect_non_fungible_vault(vault_id)
+            .unwrap();
+
+        ids.next().unwrap()
+    }
+
+    fn pending_rewards(&mut self, account: usize) -> Decimal {
+        let position_id = self.position_id(account);
+
+        self.get("get_pending_rewards", manifest_args!(position_id))
+    }
+}
+
+#[test]
+fn test_rewards_are_shared_pro_rata() {
+    let mut staking = TestStaking::funded(dec!(1000));
+
+    staking.stake(ALICE, dec!(100)).expect_commit_success();
+
+    staking.env.set_epoch(12);
+    staking.stake(BOB, dec!(300)).expect_commit_success();
+
+    // Alice gets everything for 2 epochs, then a quarter of the rewards for 4 epochs
+    staking.env.set_epoch(16);
+    assert_eq!(staking.pending_rewards(ALICE), dec!(30));
+    assert_eq!(staking.pending_rewards(BOB), dec!(30));
+    assert_eq!(
+        staking.get::<Decimal>("get_total_staked", manifest_args!()),
+        dec!(400)
+    );
+}
+
+#[test]
+fn test_claim_pays_the_pending_rewards() {
+    let mut staking = TestStaking::funded(dec!(1000));
+    let reward = staking.reward;
+
+    staking.stake(ALICE, dec!(100)).expect_commit_success();
+
+    staking.env.set_epoch(15);
+    staking.claim(ALICE).expect_commit_success();
+
+    assert_eq!(staking.env.balance(ALICE, reward), dec!(50));
+    assert_eq!(staking.pending_rewards(ALICE), dec!(0));
+
+    staking.claim(ALICE).expect_commit_success();
+    assert_eq!(staking.env.balance(ALICE, reward), dec!(50));
+}
+
+#[test]
+fn test_distribution_stops_when_the_budget_is_spent() {
+    let mut staking = TestStaking::funded(dec!(25));
+    let reward = staking.reward;
+
+    staking.stake(ALICE, dec!(100)).expect_commit_success();
+
+    staking.env.set_epoch(20);
+    assert_eq!(staking.pending_rewards(ALICE), dec!(25));
+
+    staking.claim(ALICE).expect_commit_success();
+    assert_eq!(staking.env.balance(ALICE, reward), dec!(25));
+}
+
+#[test]
+fn test_rewards_are_not_distributed_without_stakers() {
+    let mut staking = TestStaking::funded(dec!(1000));
+
+    staking.env.set_epoch(20);
+    staking.stake(ALICE, dec!(100)).expect_commit_success();
+
+    staking.env.set_epoch(21);
+    assert_eq!(staking.pending_rewards(ALICE), dec!(10));
+}
+
+#[test]
+fn test_unstake_keeps_the_earned_rewards() {
+    let mut staking = TestStaking::funded(dec!(1000));
+    let staked = staking.staked;
+
+    staking.stake(ALICE, dec!(100)).expect_commit_success();
+    staking.env.set_epoch(12);
+    staking.stake(BOB, dec!(100)).expect_commit_success();
+
+    staking.unstake(ALICE, dec!(101)).expect_commit_failure();
+    staking.unstake(ALICE, dec!(100)).expect_commit_success();
+    assert_eq!(staking.env.balance(ALICE, staked), dec!(1000));
+
+    // Bob gets all the rewards once Alice has unstaked
+    staking.env.set_epoch(14);
+    assert_eq!(staking.pending_rewards(ALICE), dec!(20));
+    assert_eq!(staking.pending_rewards(BOB), dec!(20));
+
+    let position_id = staking.position_id(ALICE);
+    let position = staking.get::<StakePosition>("get_position", manifest_args!(position_id));
+    assert_eq!(position.staked_amount, dec!(0));
+}
+
+#[test]
+fn test_add_stake() {
+    let mut staking = TestStaking::funded(dec!(1000));
+    let receipt = staking.receipt;
+
+    staking.stake(ALICE, dec!(100)).expect_commit_success();
+    staking.env.set_epoch(12);
+    staking.stake(BOB, dec!(100)).expect_commit_success();
+    staking.add_stake(ALICE, dec!(200)).expect_commit_success();
+
+    // A single position: 20 alone, then three quarters of 20
+    staking.env.set_epoch(14);
+    assert_eq!(staking.env.balance(ALICE, receipt), dec!(1));
+    assert_eq!(staking.pending_rewards(ALICE), dec!(35));
+    assert_eq!(staking.pending_rewards(BOB), dec!(5));
+}
+
+#[test]
+fn test_stake_of_another_resource_fails() {
+    let mut staking = TestStaking::new();
+    let (account, reward) = (staking.env.accounts[OWNER], staking.reward);
+
+    let builder =
+        ManifestBuilder::new()
+            .withdraw_from_account(account, reward, dec!(100))
+            .take_all_from_worktop(reward, "tokens")
+            .call_method_with_name_lookup(staking.staking, "stake", |lookup| {
+                (lookup.bucket("tokens"),)
+            });
+    staking.execute(OWNER, builder).expect_commit_failure();
+}
+
+#[test]
+fn test_set_reward_rate() {
+    let mut staking = TestStaking::funded(dec!(1000));
+    let staking_address = staking.staking;
+
+    staking.stake(ALICE, dec!(100)).expect_commit_success();
+
+    staking.env.set_epoch(12);
+    let builder = ManifestBuilder::new().call_method(
+        staking_address,
+        "set_reward_rate",
+        manifest_args!(dec!(20)),
+    );
+    staking.execute(OWNER, builder).expect_commit_success();
+
+    // The previous rate applies to the epochs before the change
+    staking.env.set_epoch(14);
+    assert_eq!(staking.pending_rewards(ALICE), dec!(60));
+
+    let builder = ManifestBuilder::new().call_method(
+        staking_address,
+        "set_reward_rate",
+        manifest_args!(dec!(-1)),
+    );
+    staking.execute(OWNER, builder).expect_commit_failure();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(staking_address, "set_reward_rate", manifest_args!(dec!(5)))
+        .build();
+    staking
+        .env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![])
+        .expect_commit_failure();
+}
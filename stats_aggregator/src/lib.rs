@@ -0,0 +1,195 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use lending_market::lending_market::LendingMarket;
+use scrypto::prelude::*;
+use single_asset_pool::pool::AssetPool;
+
+/// Maximum number of registered sources, bounding the cost of `refresh_all`
+pub const MAX_SOURCES: usize = 50;
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub enum StatsSource {
+    AssetPool(Global<AssetPool>),
+    /// Market of the given pooled resource in a lending market
+    LendingMarket(Global<LendingMarket>, ResourceAddress),
+}
+
+impl StatsSource {
+    /// Liquidity, external liquidity (e.g. borrowed amounts) and pool unit ratio
+    pub fn read(&self) -> (Decimal, Decimal, PreciseDecimal) {
+        match self {
+            StatsSource::AssetPool(pool) => {
+                let (liquidity, external) = pool.get_pooled_amount();
+
+                (liquidity, external, pool.get_pool_unit_ratio())
+            }
+            StatsSource::LendingMarket(market, pool_res_address) => {
+                let (liquidity, external) = market.get_pooled_amount(*pool_res_address);
+
+                (
+                    liquidity,
+                    external,
+                    market.get_pool_unit_ratio(*pool_res_address),
+                )
+            }
+        }
+    }
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct SourceStats {
+    /// Liquidity and external liquidity
+    pub total_value_locked: Decimal,
+
+    /// Share of the total value used as external liquidity
+    pub utilization: Decimal,
+
+    pub pool_unit_ratio: PreciseDecimal,
+
+    pub updated_at_epoch: u64,
+}
+
+#[blueprint]
+pub mod stats_aggregator {
+
+    enable_method_auth! {
+        roles {
+            keeper => updatable_by: [OWNER];
+        },
+        methods {
+
+            add_source => restrict_to :[OWNER];
+            remove_source => restrict_to :[OWNER];
+
+            refresh_all => restrict_to :[keeper, OWNER];
+            refresh => restrict_to :[keeper, OWNER];
+
+            get_stats => PUBLIC;
+            get_all_stats => PUBLIC;
+            get_sources => PUBLIC;
+
+        }
+    }
+
+    pub struct StatsAggregator {
+        sources: IndexMap<u64, StatsSource>,
+        next_source_id: u64,
+
+        /// Cached statistics, indexed by source
+        stats: IndexMap<u64, SourceStats>,
+    }
+
+    impl StatsAggregator {
+        pub fn instantiate(
+            owner_role: OwnerRole,
+            keeper_rule: AccessRule,
+        ) -> Global<StatsAggregator> {
+            Self {
+                sources: IndexMap::new(),
+                next_source_id: 0,
+                stats: IndexMap::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                keeper => keeper_rule;
+            ))
+            .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        /// Register a source, reading its statistics right away. Returns the source id.
+        pub fn add_source(&mut self, source: StatsSource) -> u64 {
+            assert!(self.sources.len() < MAX_SOURCES, "Too many sources");
+
+            let source_id = self.next_source_id;
+            self.next_source_id += 1;
+
+            self.sources.insert(source_id, source);
+            self.refresh(source_id);
+
+            source_id
+        }
+
+        pub fn remove_source(&mut self, source_id: u64) {
+            assert!(
+                self.sources.shift_remove(&source_id).is_some(),
+                "Source not found"
+            );
+
+            self.stats.shift_remove(&source_id);
+        }
+
+        /* KEEPER METHODS */
+
+        pub fn refresh_all(&mut self) {
+            let source_ids: Vec<u64> = self.sources.keys().cloned().collect();
+
+            for source_id in source_ids {
+                self.refresh(source_id);
+            }
+        }
+
+        pub fn refresh(&mut self, source_id: u64) {
+            let (liquidity, external, pool_unit_ratio) = self
+                .sources
+                .get(&source_id)
+                .expect("Source not found")
+                .read();
+
+            let total_value_locked = liquidity + external;
+            let utilization = if total_value_locked > Decimal::ZERO {
+                external / total_value_locked
+            } else {
+                Decimal::ZERO
+            };
+
+            self.stats.insert(
+                source_id,
+                SourceStats {
+                    total_value_locked,
+                    utilization,
+                    pool_unit_ratio,
+                    updated_at_epoch: Runtime::current_epoch().number(),
+                },
+            );
+        }
+
+        /* GETTERS */
+
+        pub fn get_stats(&self, source_id: u64) -> Option<SourceStats> {
+            self.stats.get(&source_id).cloned()
+        }
+
+        /// Cached statistics of every source, in a single call
+        pub fn get_all_stats(&self) -> IndexMap<u64, SourceStats> {
+            self.stats.clone()
+        }
+
+        pub fn get_sources(&self) -> IndexMap<u64, StatsSource> {
+            self.sources.clone()
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use stats_aggregator::{SourceStats, StatsSource};
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the aggregator, and administrates the pools. Holds 100 assets left after the
+/// contribution.
+const OWNER: usize = 0;
+const KEEPER: usize = 1;
+const ALICE: usize = 2;
+
+/// Manifest encoding of `StatsSource::AssetPool`
+fn asset_pool_source(pool: ComponentAddress) -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 0,
+        fields: vec![to_manifest_value_and_unwrap!(&pool)],
+    }
+}
+
+/// Aggregator at epoch 10 without source, and two pools of the same asset, A holding 100
+/// assets and B being empty. The AssetPool blueprint is linked into the aggregator package, so
+/// the pools are instantiated from the same package.
+struct TestAggregator {
+    env: TestEnv,
+    aggregator: ComponentAddress,
+    pool_a: ComponentAddress,
+    pool_b: ComponentAddress,
+}
+
+impl TestAggregator {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let asset = env
+            .test_runner
+            .create_fungible_resource(dec!(200), 18, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+        let admin_rule = rule!(require(env.badges[OWNER].clone()));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "StatsAggregator",
+                "instantiate",
+                manifest_args!(
+                    owner_role.clone(),
+                    rule!(require(env.badges[KEEPER].clone()))
+                ),
+            )
+            .call_function(
+                env.package_address,
+                "AssetPool",
+                "instantiate",
+                manifest_args!(asset, owner_role.clone(), admin_rule.clone()),
+            )
+            .call_function(
+                env.package_address,
+                "AssetPool",
+                "instantiate",
+                manifest_args!(asset, owner_role, admin_rule),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+        let (aggregator, pool_a, pool_b) = (
+            commit.new_component_addresses()[0],
+            commit.new_component_addresses()[1],
+            commit.new_component_addresses()[2],
+        );
+
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account(env.accounts[OWNER], asset, dec!(100))
+            .take_all_from_worktop(asset, "assets")
+            .call_method_with_name_lookup(pool_a, "contribute", |lookup| (lookup.bucket("assets"),))
+            .deposit_batch(env.accounts[OWNER])
+            .build();
+        env.test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()])
+            .expect_commit_success();
+
+        Self {
+            env,
+            aggregator,
+            pool_a,
+            pool_b,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.aggregator, method, args)
+    }
+
+    fn add_source(&mut self, caller: usize, pool: ComponentAddress) -> TransactionReceipt {
+        self.call(
+            caller,
+            "add_source",
+            manifest_args!(asset_pool_source(pool)),
+        )
+    }
+
+    fn get_stats(&mut self, source_id: u64) -> Option<SourceStats> {
+        self.call(ALICE, "get_stats", manifest_args!(source_id))
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_keeper_refreshes_the_statistics() {
+    let mut aggregator = TestAggregator::new();
+    let (pool_a, pool_b) = (aggregator.pool_a, aggregator.pool_b);
+
+    aggregator.add_source(ALICE, pool_a).expect_commit_failure();
+    let source_id: u64 = aggregator
+        .add_source(OWNER, pool_a)
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(source_id, 0);
+
+    let stats = aggregator.get_stats(0).unwrap();
+    assert_eq!(stats.total_value_locked, dec!(100));
+    assert_eq!(stats.utilization, dec!(0));
+    assert_eq!(stats.pool_unit_ratio, PreciseDecimal::ONE);
+    assert_eq!(stats.updated_at_epoch, 10);
+
+    // 25 assets are used outside of the pool, with `WithdrawType::ForTemporaryUse` and
+    // `WithdrawStrategy::Exact`
+    let builder = ManifestBuilder::new().call_method(
+        pool_a,
+        "protected_withdraw",
+        manifest_args!(
+            dec!(25),
+            ManifestValue::Enum {
+                discriminator: 0,
+                fields: vec![],
+            },
+            ManifestValue::Enum {
+                discriminator: 0,
+                fields: vec![],
+            }
+        ),
+    );
+    aggregator
+        .env
+        .execute(OWNER, builder)
+        .expect_commit_success();
+    assert_eq!(aggregator.get_stats(0).unwrap().utilization, dec!(0));
+
+    aggregator.env.set_epoch(11);
+    aggregator
+        .call(ALICE, "refresh", manifest_args!(0u64))
+        .expect_commit_failure();
+    aggregator
+        .call(KEEPER, "refresh", manifest_args!(0u64))
+        .expect_commit_success();
+
+    let stats = aggregator.get_stats(0).unwrap();
+    assert_eq!(stats.total_value_locked, dec!(100));
+    assert_eq!(stats.utilization, dec!("0.25"));
+    assert_eq!(stats.updated_at_epoch, 11);
+
+    aggregator.add_source(OWNER, pool_b).expect_commit_success();
+    aggregator.env.set_epoch(12);
+    aggregator
+        .call(ALICE, "refresh_all", manifest_args!())
+        .expect_commit_failure();
+    aggregator
+        .call(KEEPER, "refresh_all", manifest_args!())
+        .expect_commit_success();
+
+    let all_stats: IndexMap<u64, SourceStats> = aggregator
+        .call(ALICE, "get_all_stats", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(all_stats.len(), 2);
+    assert!(all_stats.values().all(|stats| stats.updated_at_epoch == 12));
+    assert_eq!(all_stats.get(&1).unwrap().total_value_locked, dec!(0));
+    assert_eq!(all_stats.get(&1).unwrap().utilization, dec!(0));
+}
+
+#[test]
+fn test_owner_removes_sources() {
+    let mut aggregator = TestAggregator::new();
+    let (pool_a, pool_b) = (aggregator.pool_a, aggregator.pool_b);
+
+    aggregator.add_source(OWNER, pool_a).expect_commit_success();
+    aggregator.add_source(OWNER, pool_b).expect_commit_success();
+
+    aggregator
+        .call(ALICE, "remove_source", manifest_args!(0u64))
+        .expect_commit_failure();
+    aggregator
+        .call(OWNER, "remove_source", manifest_args!(0u64))
+        .expect_commit_success();
+    aggregator
+        .call(OWNER, "remove_source", manifest_args!(0u64))
+        .expect_commit_failure();
+    aggregator
+        .call(KEEPER, "refresh", manifest_args!(0u64))
+        .expect_commit_failure();
+    assert!(aggregator.get_stats(0).is_none());
+
+    let sources: IndexMap<u64, StatsSource> = aggregator
+        .call(ALICE, "get_sources", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(sources.keys().cloned().collect::<Vec<u64>>(), vec![1]);
+
+    // Source ids are not reused
+    let source_id: u64 = aggregator
+        .add_source(OWNER, pool_a)
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(source_id, 2);
+}
+
+#[test]
+fn test_fully_used_pool_has_full_utilization() {
+    let mut aggregator = TestAggregator::new();
+    let pool_a = aggregator.pool_a;
+
+    // The same pool can be registered twice, each source keeping its own statistics
+    aggregator.add_source(OWNER, pool_a).expect_commit_success();
+
+    // Every asset is used outside of the pool
+    let builder = ManifestBuilder::new().call_method(
+        pool_a,
+        "protected_withdraw",
+        manifest_args!(
+            dec!(100),
+            ManifestValue::Enum {
+                discriminator: 0,
+                fields: vec![],
+            },
+            ManifestValue::Enum {
+                discriminator: 0,
+                fields: vec![],
+            }
+        ),
+    );
+    aggregator
+        .env
+        .execute(OWNER, builder)
+        .expect_commit_success();
+
+    aggregator.env.set_epoch(11);
+    aggregator.add_source(OWNER, pool_a).expect_commit_success();
+
+    let stats = aggregator.get_stats(1).unwrap();
+    assert_eq!(stats.total_value_locked, dec!(100));
+    assert_eq!(stats.utilization, dec!(1));
+    assert_eq!(stats.updated_at_epoch, 11);
+
+    let stats = aggregator.get_stats(0).unwrap();
+    assert_eq!(stats.utilization, dec!(0));
+    assert_eq!(stats.updated_at_epoch, 10);
+}
+
+#[test]
+fn test_failures_carry_aggregator_messages() {
+    let mut aggregator = TestAggregator::new();
+    let (aggregator_address, pool_a) = (aggregator.aggregator, aggregator.pool_a);
+
+    // Refreshing without source does nothing
+    aggregator
+        .call(KEEPER, "refresh_all", manifest_args!())
+        .expect_commit_success();
+    expect_failure_containing(
+        aggregator.call(KEEPER, "refresh", manifest_args!(0u64)),
+        "Source not found",
+    );
+    expect_failure_containing(
+        aggregator.call(OWNER, "remove_source", manifest_args!(0u64)),
+        "Source not found",
+    );
+
+    let builder = (0..50).fold(ManifestBuilder::new(), |builder, _| {
+        builder.call_method(
+            aggregator_address,
+            "add_source",
+            manifest_args!(asset_pool_source(pool_a)),
+        )
+    });
+    aggregator
+        .env
+        .execute(OWNER, builder)
+        .expect_commit_success();
+    expect_failure_containing(aggregator.add_source(OWNER, pool_a), "Too many sources");
+
+    // A removed source frees a slot
+    aggregator
+        .call(OWNER, "remove_source", manifest_args!(0u64))
+        .expect_commit_success();
+    aggregator.add_source(OWNER, pool_a).expect_commit_success();
+}
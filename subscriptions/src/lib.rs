@@ -0,0 +1,304 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::assert_fungible_res_address;
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, Clone)]
+pub struct Plan {
+    pub payment_res_address: ResourceAddress,
+    /// Amount pulled every period
+    pub price: Decimal,
+    pub period_epochs: u64,
+    /// Inactive plans can not be subscribed to anymore
+    pub active: bool,
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct SubscriptionReceipt {
+    pub plan_id: u64,
+}
+
+#[derive(ScryptoSbor)]
+pub struct Subscription {
+    pub plan_id: u64,
+    /// Pre-funded payments
+    pub escrow: Vault,
+    /// First epoch at which the next payment can be pulled
+    pub next_payment_epoch: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PaymentCollectedEvent {
+    pub subscription_id: NonFungibleLocalId,
+    pub plan_id: u64,
+    pub amount: Decimal,
+}
+
+#[blueprint]
+#[events(PaymentCollectedEvent)]
+pub mod subscriptions {
+
+    enable_method_auth! {
+        roles {
+            keeper => updatable_by: [OWNER];
+        },
+        methods {
+
+            create_plan => restrict_to :[OWNER];
+            deactivate_plan => restrict_to :[OWNER];
+            withdraw_revenue => restrict_to :[OWNER];
+
+            collect => restrict_to :[keeper, OWNER];
+
+            subscribe => PUBLIC;
+            top_up => PUBLIC;
+            cancel => PUBLIC;
+
+            get_plan => PUBLIC;
+            get_subscription => PUBLIC;
+
+        }
+    }
+
+    pub struct Subscriptions {
+        plans: KeyValueStore<u64, Plan>,
+        next_plan_id: u64,
+
+        /// Subscription receipt non-fungible resource manager
+        subscription_res_manager: ResourceManager,
+        subscriptions: KeyValueStore<NonFungibleLocalId, Subscription>,
+
+        /// Collected payments, indexed by resource
+        revenue: KeyValueStore<ResourceAddress, Vault>,
+    }
+
+    impl Subscriptions {
+        pub fn instantiate(
+            owner_role: OwnerRole,
+            keeper_rule: AccessRule,
+        ) -> (Global<Subscriptions>, ResourceAddress) {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(Subscriptions::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let subscription_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<SubscriptionReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule;
+                        burner_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let subscriptions_component = Self {
+                plans: KeyValueStore::new(),
+                next_plan_id: 1,
+                subscription_res_manager,
+                subscriptions: KeyValueStore::new(),
+                revenue: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                keeper => keeper_rule;
+            ))
+            .with_address(address_reservation)
+            .globalize();
+
+            (subscriptions_component, subscription_res_manager.address())
+        }
+
+        /* MERCHANT METHODS */
+
+        pub fn create_plan(
+            &mut self,
+            payment_res_address: ResourceAddress,
+            price: Decimal,
+            period_epochs: u64,
+        ) -> u64 {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                payment_res_address,
+                Some("Payment resource must be fungible".to_string()),
+            );
+            assert!(price > Decimal::ZERO, "Price must be greater than zero!");
+            assert!(period_epochs > 0, "Period must be greater than zero");
+
+            let plan_id = self.next_plan_id;
+            self.next_plan_id += 1;
+
+            self.plans.insert(
+                plan_id,
+                Plan {
+                    payment_res_address,
+                    price,
+                    period_epochs,
+                    active: true,
+                },
+            );
+
+            plan_id
+        }
+
+        /// Stop new subscriptions to a plan. Existing subscriptions keep being collected.
+        pub fn deactivate_plan(&mut self, plan_id: u64) {
+            self.plans.get_mut(&plan_id).expect("Plan not found").active = false;
+        }
+
+        pub fn withdraw_revenue(&mut self, payment_res_address: ResourceAddress) -> Bucket {
+            self.revenue
+                .get_mut(&payment_res_address)
+                .expect("No revenue for this resource")
+                .take_all()
+        }
+
+        /// Pull the payment of the current period of a subscription. A payment can be pulled
+        /// at most once per period.
+        pub fn collect(&mut self, subscription_id: NonFungibleLocalId) {
+            let mut subscription = self
+                .subscriptions
+                .get_mut(&subscription_id)
+                .expect("Subscription not found");
+            let plan = self.plans.get(&subscription.plan_id).unwrap().clone();
+
+            assert!(
+                Runtime::current_epoch().number() >= subscription.next_payment_epoch,
+                "Payment of the current period already collected"
+            );
+            assert!(
+                subscription.escrow.amount() >= plan.price,
+                "Insufficient subscription funds"
+            );
+
+            let payment = subscription.escrow.take(plan.price);
+            subscription.next_payment_epoch += plan.period_epochs;
+
+            Runtime::emit_event(PaymentCollectedEvent {
+                subscription_id,
+                plan_id: subscription.plan_id,
+                amount: plan.price,
+            });
+
+            let vault_exists = self.revenue.get(&plan.payment_res_address).is_some();
+
+            if vault_exists {
+                self.revenue
+                    .get_mut(&plan.payment_res_address)
+                    .unwrap()
+                    .put(payment);
+            } else {
+                self.revenue
+                    .insert(plan.payment_res_address, Vault::with_bucket(payment));
+            }
+        }
+
+        /* SUBSCRIBER METHODS */
+
+        /// Subscribe to a plan, pre-funding its payments. The first payment can be pulled
+        /// immediately. Returns the subscription receipt.
+        pub fn subscribe(&mut self, plan_id: u64, funds: Bucket) -> Bucket {
+            let plan = self.plans.get(&plan_id).expect("Plan not found").clone();
+
+            /* CHECK INPUT */
+            assert!(plan.active, "Plan is not active");
+            assert!(
+                funds.resource_address() == plan.payment_res_address,
+                "Payment resource address mismatch"
+            );
+
+            let receipt = self
+                .subscription_res_manager
+                .mint_ruid_non_fungible(SubscriptionReceipt { plan_id });
+
+            self.subscriptions.insert(
+                receipt.as_non_fungible().non_fungible_local_id(),
+                Subscription {
+                    plan_id,
+                    escrow: Vault::with_bucket(funds),
+                    next_payment_epoch: Runtime::current_epoch().number(),
+                },
+            );
+
+            receipt
+        }
+
+        pub fn top_up(&mut self, subscription_proof: Proof, funds: Bucket) {
+            let subscription_id = subscription_proof
+                .check(self.subscription_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id();
+
+            self.subscriptions
+                .get_mut(&subscription_id)
+                .unwrap()
+                .escrow
+                .put(funds);
+        }
+
+        /// Cancel a subscription, burning its receipt. Returns the remaining pre-funded
+        /// payments.
+        pub fn cancel(&mut self, subscription_receipt: Bucket) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                subscription_receipt.resource_address() == self.subscription_res_manager.address(),
+                "Subscription receipt resource address mismatch"
+            );
+
+            let subscription_id = subscription_receipt
+                .as_non_fungible()
+                .non_fungible_local_id();
+            subscription_receipt.burn();
+
+            self.subscriptions
+                .get_mut(&subscription_id)
+                .unwrap()
+                .escrow
+                .take_all()
+        }
+
+        /* GETTERS */
+
+        pub fn get_plan(&self, plan_id: u64) -> Plan {
+            self.plans.get(&plan_id).expect("Plan not found").clone()
+        }
+
+        /// Plan id, escrowed amount and next payment epoch of a subscription
+        pub fn get_subscription(&self, subscription_id: NonFungibleLocalId) -> (u64, Decimal, u64) {
+            let subscription = self
+                .subscriptions
+                .get(&subscription_id)
+                .expect("Subscription not found");
+
+            (
+                subscription.plan_id,
+                subscription.escrow.amount(),
+                subscription.next_payment_epoch,
+            )
+        }
+    }
+}
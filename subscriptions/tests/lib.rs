@@ -0,0 +1,312 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owner of the subscriptions component
+const MERCHANT: usize = 0;
+/// Holds 1000 payment tokens
+const SUBSCRIBER: usize = 1;
+const KEEPER: usize = 2;
+
+/// Subscriptions component at epoch 10, with a plan of 10 tokens every 5 epochs
+struct TestSubscriptions {
+    env: TestEnv,
+    subscriptions: ComponentAddress,
+    receipt: ResourceAddress,
+    payment: ResourceAddress,
+}
+
+impl TestSubscriptions {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let payment =
+            env.test_runner
+                .create_fungible_resource(dec!(1000), 18, env.accounts[SUBSCRIBER]);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "Subscriptions",
+                "instantiate",
+                manifest_args!(
+                    OwnerRole::Fixed(rule!(require(env.badges[MERCHANT].clone()))),
+                    rule!(require(env.badges[KEEPER].clone()))
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        let mut subscriptions = Self {
+            subscriptions: commit.new_component_addresses()[0],
+            receipt: commit.new_resource_addresses()[0],
+            env,
+            payment,
+        };
+
+        let builder = ManifestBuilder::new().call_method(
+            subscriptions.subscriptions,
+            "create_plan",
+            manifest_args!(payment, dec!(10), 5u64),
+        );
+        let plan_id: u64 = subscriptions
+            .execute(MERCHANT, builder)
+            .expect_commit_success()
+            .output(0);
+        assert_eq!(plan_id, 1);
+
+        subscriptions
+    }
+
+    /// Execute the manifest with the keys of every account, then deposit the worktop into the
+    /// given account
+    fn execute(&mut self, account: usize, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute_with_all_keys(account, builder)
+    }
+
+    fn subscribe(&mut self, plan_id: u64, amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[SUBSCRIBER], self.payment, amount)
+            .take_all_from_worktop(self.payment, "funds")
+            .call_method_with_name_lookup(self.subscriptions, "subscribe", |lookup| {
+                (plan_id, lookup.bucket("funds"))
+            });
+
+        self.execute(SUBSCRIBER, builder)
+    }
+
+    fn top_up(&mut self, amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .create_proof_from_account_of_amount(
+                self.env.accounts[SUBSCRIBER],
+                self.receipt,
+                dec!(1),
+            )
+            .pop_from_auth_zone("receipt")
+            .withdraw_from_account(self.env.accounts[SUBSCRIBER], self.payment, amount)
+            .take_all_from_worktop(self.payment, "funds")
+            .call_method_with_name_lookup(self.subscriptions, "top_up", |lookup| {
+                (lookup.proof("receipt"), lookup.bucket("funds"))
+            });
+
+        self.execute(SUBSCRIBER, builder)
+    }
+
+    fn cancel(&mut self) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[SUBSCRIBER], self.receipt, dec!(1))
+            .take_all_from_worktop(self.receipt, "receipt")
+            .call_method_with_name_lookup(self.subscriptions, "cancel", |lookup| {
+                (lookup.bucket("receipt"),)
+            });
+
+        self.execute(SUBSCRIBER, builder)
+    }
+
+    /// Collect the payment of a subscription with the key of the keeper only
+    fn collect(&mut self, subscription_id: NonFungibleLocalId) -> TransactionReceipt {
+        let manifest = ManifestBuilder::new()
+            .call_method(
+                self.subscriptions,
+                "collect",
+                manifest_args!(subscription_id),
+            )
+            .build();
+
+        self.env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![self.env.badges[KEEPER].clone()])
+    }
+
+    /// Id of the single subscription of the subscriber
+    fn subscription_id(&mut self) -> NonFungibleLocalId {
+        let vault_id = self
+            .env
+            .test_runner
+            .get_component_vaults(self.env.accounts[SUBSCRIBER], self.receipt)[0];
+        let (_, mut ids) = self
+            .env
+            .test_runner
+            .inspect_non_fungible_vault(vault_id)
+            .unwrap();
+
+        ids.next().unwrap()
+    }
+
+    fn balance(&mut self, account: usize) -> Decimal {
+        self.env.balance(account, self.payment)
+    }
+}
+
+#[test]
+fn test_keeper_collects_one_payment_per_period() {
+    let mut subscriptions = TestSubscriptions::new();
+    let (subscriptions_address, payment) = (subscriptions.subscriptions, subscriptions.payment);
+
+    subscriptions.subscribe(1, dec!(30)).expect_commit_success();
+    let subscription_id = subscriptions.subscription_id();
+
+    // The first payment can be collected immediately
+    subscriptions
+        .collect(subscription_id.clone())
+        .expect_commit_success();
+
+    subscriptions.env.set_epoch(14);
+    subscriptions
+        .collect(subscription_id.clone())
+        .expect_commit_failure();
+
+    subscriptions.env.set_epoch(15);
+    subscriptions
+        .collect(subscription_id.clone())
+        .expect_commit_success();
+
+    let builder = ManifestBuilder::new().call_method(
+        subscriptions_address,
+        "get_subscription",
+        manifest_args!(subscription_id),
+    );
+    let state: (u64, Decimal, u64) = subscriptions
+        .execute(MERCHANT, builder)
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(state, (1, dec!(10), 20));
+
+    let builder = ManifestBuilder::new().call_method(
+        subscriptions_address,
+        "withdraw_revenue",
+        manifest_args!(payment),
+    );
+    subscriptions
+        .execute(MERCHANT, builder)
+        .expect_commit_success();
+    assert_eq!(subscriptions.balance(MERCHANT), dec!(20));
+}
+
+#[test]
+fn test_collect_without_funds_fails_until_top_up() {
+    let mut subscriptions = TestSubscriptions::new();
+
+    subscriptions.subscribe(1, dec!(15)).expect_commit_success();
+    let subscription_id = subscriptions.subscription_id();
+
+    subscriptions
+        .collect(subscription_id.clone())
+        .expect_commit_success();
+
+    subscriptions.env.set_epoch(15);
+    subscriptions
+        .collect(subscription_id.clone())
+        .expect_commit_failure();
+
+    subscriptions.top_up(dec!(5)).expect_commit_success();
+    subscriptions
+        .collect(subscription_id)
+        .expect_commit_success();
+}
+
+#[test]
+fn test_collect_requires_the_keeper() {
+    let mut subscriptions = TestSubscriptions::new();
+
+    subscriptions.subscribe(1, dec!(30)).expect_commit_success();
+    let subscription_id = subscriptions.subscription_id();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            subscriptions.subscriptions,
+            "collect",
+            manifest_args!(subscription_id),
+        )
+        .build();
+    let subscriber_badge = subscriptions.env.badges[SUBSCRIBER].clone();
+    subscriptions
+        .env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![subscriber_badge])
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_cancel_returns_the_remaining_funds() {
+    let mut subscriptions = TestSubscriptions::new();
+    let receipt = subscriptions.receipt;
+
+    subscriptions.subscribe(1, dec!(30)).expect_commit_success();
+    let subscription_id = subscriptions.subscription_id();
+    subscriptions
+        .collect(subscription_id.clone())
+        .expect_commit_success();
+
+    subscriptions.cancel().expect_commit_success();
+    assert_eq!(subscriptions.balance(SUBSCRIBER), dec!(990));
+    assert_eq!(subscriptions.env.balance(SUBSCRIBER, receipt), dec!(0));
+
+    subscriptions.env.set_epoch(15);
+    subscriptions
+        .collect(subscription_id)
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_subscribe_to_an_inactive_plan_fails() {
+    let mut subscriptions = TestSubscriptions::new();
+    let subscriptions_address = subscriptions.subscriptions;
+
+    subscriptions.subscribe(2, dec!(30)).expect_commit_failure();
+
+    let builder = ManifestBuilder::new().call_method(
+        subscriptions_address,
+        "deactivate_plan",
+        manifest_args!(1u64),
+    );
+    subscriptions
+        .execute(MERCHANT, builder)
+        .expect_commit_success();
+
+    subscriptions.subscribe(1, dec!(30)).expect_commit_failure();
+}
+
+#[test]
+fn test_subscribe_with_another_resource_fails() {
+    let mut subscriptions = TestSubscriptions::new();
+    let account = subscriptions.env.accounts[SUBSCRIBER];
+    let other = subscriptions
+        .env
+        .test_runner
+        .create_fungible_resource(dec!(100), 18, account);
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(account, other, dec!(30))
+        .take_all_from_worktop(other, "funds")
+        .call_method_with_name_lookup(subscriptions.subscriptions, "subscribe", |lookup| {
+            (1u64, lookup.bucket("funds"))
+        });
+    subscriptions
+        .execute(SUBSCRIBER, builder)
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_create_plan_requires_the_owner() {
+    let mut subscriptions = TestSubscriptions::new();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            subscriptions.subscriptions,
+            "create_plan",
+            manifest_args!(subscriptions.payment, dec!(10), 5u64),
+        )
+        .build();
+    let keeper_badge = subscriptions.env.badges[KEEPER].clone();
+    subscriptions
+        .env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![keeper_badge])
+        .expect_commit_failure();
+}
@@ -0,0 +1,237 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use radiswap_pair::radiswap_pair::RadiswapPair;
+use scrypto::prelude::*;
+use stableswap_pool::stableswap_pool::StableswapPool;
+use weighted_pool::weighted_pool::WeightedPool;
+
+/// Maximum number of hops of a swap path
+pub const MAX_PATH_LENGTH: usize = 5;
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub enum AmmPool {
+    Radiswap(Global<RadiswapPair>),
+    Stableswap(Global<StableswapPool>),
+    Weighted(Global<WeightedPool>),
+}
+
+/// AMM component traded by the router between two of its resources
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct RegisteredPair {
+    pub pool: AmmPool,
+    pub res_a: ResourceAddress,
+    pub res_b: ResourceAddress,
+}
+
+impl RegisteredPair {
+    pub fn output_res_address(&self, input_res_address: ResourceAddress) -> ResourceAddress {
+        if input_res_address == self.res_a {
+            self.res_b
+        } else if input_res_address == self.res_b {
+            self.res_a
+        } else {
+            panic!("Input resource is not traded by the pair")
+        }
+    }
+
+    pub fn swap(&self, input: Bucket) -> Bucket {
+        let output_res_address = self.output_res_address(input.resource_address());
+
+        match self.pool.clone() {
+            AmmPool::Radiswap(mut pair) => pair.swap(input),
+            AmmPool::Stableswap(mut pool) => pool.swap(input, output_res_address),
+            AmmPool::Weighted(mut pool) => pool.swap(input, output_res_address),
+        }
+    }
+
+    pub fn get_output_amount(
+        &self,
+        input_res_address: ResourceAddress,
+        input_amount: Decimal,
+    ) -> Decimal {
+        let output_res_address = self.output_res_address(input_res_address);
+
+        match &self.pool {
+            AmmPool::Radiswap(pair) => pair.get_output_amount(input_res_address, input_amount),
+            AmmPool::Stableswap(pool) => {
+                pool.get_output_amount(input_res_address, input_amount, output_res_address)
+            }
+            AmmPool::Weighted(pool) => {
+                pool.get_output_amount(input_res_address, input_amount, output_res_address)
+            }
+        }
+    }
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SwapRoutedEvent {
+    pub path: Vec<ComponentAddress>,
+    pub input_res_address: ResourceAddress,
+    pub input_amount: Decimal,
+    pub output_res_address: ResourceAddress,
+    pub output_amount: Decimal,
+}
+
+#[blueprint]
+#[events(SwapRoutedEvent)]
+pub mod swap_router {
+
+    enable_method_auth! {
+        methods {
+
+            register_pair => restrict_to :[OWNER];
+            unregister_pair => restrict_to :[OWNER];
+
+            swap_via => PUBLIC;
+
+            get_amount_out => PUBLIC;
+            get_pair => PUBLIC;
+
+        }
+    }
+
+    pub struct SwapRouter {
+        pairs: KeyValueStore<ComponentAddress, RegisteredPair>,
+    }
+
+    impl SwapRouter {
+        pub fn instantiate(owner_role: OwnerRole) -> Global<SwapRouter> {
+            Self {
+                pairs: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        /// Register an AMM component, traded between the two given resources. Registering a
+        /// component again replaces its traded resources.
+        pub fn register_pair(
+            &mut self,
+            pool: AmmPool,
+            res_a: ResourceAddress,
+            res_b: ResourceAddress,
+        ) {
+            /* CHECK INPUT */
+            assert!(res_a != res_b, "Pair resources must be different");
+
+            let component_address = match &pool {
+                AmmPool::Radiswap(pair) => pair.address(),
+                AmmPool::Stableswap(pool) => pool.address(),
+                AmmPool::Weighted(pool) => pool.address(),
+            };
+
+            self.pairs
+                .insert(component_address, RegisteredPair { pool, res_a, res_b });
+        }
+
+        pub fn unregister_pair(&mut self, component_address: ComponentAddress) {
+            assert!(
+                self.pairs.get(&component_address).is_some(),
+                "Pair not registered"
+            );
+
+            self.pairs.remove(&component_address);
+        }
+
+        /* SWAP METHODS */
+
+        /// Swap the input through each registered pair of the path in order, failing if the
+        /// final output is below `min_out`
+        pub fn swap_via(
+            &mut self,
+            input: Bucket,
+            path: Vec<ComponentAddress>,
+            min_out: Decimal,
+        ) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                !path.is_empty() && path.len() <= MAX_PATH_LENGTH,
+                "Path must have between 1 and {} pairs",
+                MAX_PATH_LENGTH
+            );
+
+            let input_res_address = input.resource_address();
+            let input_amount = input.amount();
+
+            let output = path.iter().fold(input, |bucket, component_address| {
+                self._get_pair(component_address).swap(bucket)
+            });
+
+            assert!(
+                output.amount() >= min_out,
+                "Output amount is below the minimum output"
+            );
+
+            Runtime::emit_event(SwapRoutedEvent {
+                path,
+                input_res_address,
+                input_amount,
+                output_res_address: output.resource_address(),
+                output_amount: output.amount(),
+            });
+
+            output
+        }
+
+        /* GETTERS */
+
+        /// Output of `swap_via` for the given input, at the current reserves
+        pub fn get_amount_out(
+            &self,
+            input_res_address: ResourceAddress,
+            input_amount: Decimal,
+            path: Vec<ComponentAddress>,
+        ) -> Decimal {
+            path.iter()
+                .fold(
+                    (input_res_address, input_amount),
+                    |(res_address, amount), component_address| {
+                        let pair = self._get_pair(component_address);
+
+                        (
+                            pair.output_res_address(res_address),
+                            pair.get_output_amount(res_address, amount),
+                        )
+                    },
+                )
+                .1
+        }
+
+        pub fn get_pair(&self, component_address: ComponentAddress) -> Option<RegisteredPair> {
+            self.pairs.get(&component_address).map(|pair| pair.clone())
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _get_pair(&self, component_address: &ComponentAddress) -> RegisteredPair {
+            self.pairs
+                .get(component_address)
+                .expect("Pair not registered")
+                .clone()
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use swap_router::RegisteredPair;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Provides the pairs liquidity
+const OWNER: usize = 0;
+/// Holds 1000 A tokens
+const ALICE: usize = 1;
+
+/// Manifest encoding of `AmmPool::Radiswap`
+fn radiswap_pool(component_address: ComponentAddress) -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 0,
+        fields: vec![to_manifest_value_and_unwrap!(&component_address)],
+    }
+}
+
+/// Router over two Radiswap pairs without fee, A/B holding 1000 A and 1000 B, and B/C
+/// holding 500 B and 1000 C. The pair blueprint is linked into the router package, so it is
+/// instantiated from the same package.
+struct TestRouter {
+    env: TestEnv,
+    router: ComponentAddress,
+    pair_ab: ComponentAddress,
+    pair_bc: ComponentAddress,
+    res_a: ResourceAddress,
+    res_b: ResourceAddress,
+    res_c: ResourceAddress,
+}
+
+impl TestRouter {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 2);
+        let res_a = env
+            .test_runner
+            .create_fungible_resource(dec!(2000), 18, env.accounts[OWNER]);
+        let res_b = env
+            .test_runner
+            .create_fungible_resource(dec!(1500), 18, env.accounts[OWNER]);
+        let res_c = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "SwapRouter",
+                "instantiate",
+                manifest_args!(owner_role.clone()),
+            )
+            .call_function(
+                env.package_address,
+                "RadiswapPair",
+                "instantiate",
+                manifest_args!(res_a, res_b, dec!(0), owner_role.clone()),
+            )
+            .call_function(
+                env.package_address,
+                "RadiswapPair",
+                "instantiate",
+                manifest_args!(res_b, res_c, dec!(0), owner_role),
+            )
+            .withdraw_from_account(env.accounts[OWNER], res_a, dec!(1000))
+            .take_all_from_worktop(res_a, "alice")
+            .call_method_with_name_lookup(env.accounts[ALICE], "deposit", |lookup| {
+                (lookup.bucket("alice"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+
+        let mut router = Self {
+            router: commit.new_component_addresses()[0],
+            pair_ab: commit.new_component_addresses()[1],
+            pair_bc: commit.new_component_addresses()[2],
+            env,
+            res_a,
+            res_b,
+            res_c,
+        };
+
+        let (pair_ab, pair_bc) = (router.pair_ab, router.pair_bc);
+        router.add_liquidity(pair_ab, (res_a, dec!(1000)), (res_b, dec!(1000)));
+        router.add_liquidity(pair_bc, (res_b, dec!(500)), (res_c, dec!(1000)));
+
+        router
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.router, method, args)
+    }
+
+    fn add_liquidity(
+        &mut self,
+        pair: ComponentAddress,
+        (res_a, amount_a): (ResourceAddress, Decimal),
+        (res_b, amount_b): (ResourceAddress, Decimal),
+    ) {
+        let owner = self.env.accounts[OWNER];
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(owner, res_a, amount_a)
+            .withdraw_from_account(owner, res_b, amount_b)
+            .take_all_from_worktop(res_a, "bucket_a")
+            .take_all_from_worktop(res_b, "bucket_b")
+            .call_method_with_name_lookup(pair, "add_liquidity", |lookup| {
+                (lookup.bucket("bucket_a"), lookup.bucket("bucket_b"))
+            });
+
+        self.env.execute(OWNER, builder).expect_commit_success();
+    }
+
+    fn register_pair(
+        &mut self,
+        caller: usize,
+        pair: ComponentAddress,
+        res_a: ResourceAddress,
+        res_b: ResourceAddress,
+    ) -> TransactionReceipt {
+        self.call(
+            caller,
+            "register_pair",
+            manifest_args!(radiswap_pool(pair), res_a, res_b),
+        )
+    }
+
+    /// Register both pairs of the router
+    fn register_pairs(&mut self) {
+        let (pair_ab, pair_bc) = (self.pair_ab, self.pair_bc);
+        let (res_a, res_b, res_c) = (self.res_a, self.res_b, self.res_c);
+
+        self.register_pair(OWNER, pair_ab, res_a, res_b)
+            .expect_commit_success();
+        self.register_pair(OWNER, pair_bc, res_b, res_c)
+            .expect_commit_success();
+    }
+
+    /// Swap the given amount of A tokens of Alice along the path
+    fn swap_via(
+        &mut self,
+        amount: Decimal,
+        path: Vec<ComponentAddress>,
+        min_out: Decimal,
+    ) -> TransactionReceipt {
+        let res_a = self.res_a;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[ALICE], res_a, amount)
+            .take_all_from_worktop(res_a, "input")
+            .call_method_with_name_lookup(self.router, "swap_via", |lookup| {
+                (lookup.bucket("input"), path, min_out)
+            });
+
+        self.env.execute(ALICE, builder)
+    }
+}
+
+#[test]
+fn test_swap_via_two_pairs() {
+    let mut router = TestRouter::new();
+    let (pair_ab, pair_bc) = (router.pair_ab, router.pair_bc);
+    let (res_a, res_b, res_c) = (router.res_a, router.res_b, router.res_c);
+
+    router.register_pairs();
+
+    let registered_pair: Option<RegisteredPair> = router
+        .call(ALICE, "get_pair", manifest_args!(pair_bc))
+        .expect_commit_success()
+        .output(0);
+    let registered_pair = registered_pair.unwrap();
+    assert_eq!(registered_pair.output_res_address(res_b), res_c);
+
+    // 1000 A give 500 B, which give 500 C
+    let amount_out: Decimal = router
+        .call(
+            ALICE,
+            "get_amount_out",
+            manifest_args!(res_a, dec!(1000), vec![pair_ab, pair_bc]),
+        )
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(amount_out, dec!(500));
+
+    router
+        .swap_via(dec!(1000), vec![pair_ab, pair_bc], dec!(500))
+        .expect_commit_success();
+    assert_eq!(router.env.balance(ALICE, res_a), dec!(0));
+    assert_eq!(router.env.balance(ALICE, res_b), dec!(0));
+    assert_eq!(router.env.balance(ALICE, res_c), dec!(500));
+}
+
+#[test]
+fn test_swap_via_rejects_invalid_paths() {
+    let mut router = TestRouter::new();
+    let (pair_ab, pair_bc) = (router.pair_ab, router.pair_bc);
+    let res_a = router.res_a;
+
+    router.register_pairs();
+
+    router
+        .swap_via(dec!(1000), vec![pair_ab, pair_bc], dec!(501))
+        .expect_commit_failure();
+    router
+        .swap_via(dec!(100), vec![], dec!(0))
+        .expect_commit_failure();
+    router
+        .swap_via(dec!(100), vec![pair_ab; 6], dec!(0))
+        .expect_commit_failure();
+
+    // The B/C pair does not trade A tokens
+    router
+        .swap_via(dec!(100), vec![pair_bc], dec!(0))
+        .expect_commit_failure();
+
+    assert_eq!(router.env.balance(ALICE, res_a), dec!(1000));
+}
+
+#[test]
+fn test_pair_registry_requires_the_owner() {
+    let mut router = TestRouter::new();
+    let (pair_ab, pair_bc) = (router.pair_ab, router.pair_bc);
+    let (res_a, res_b) = (router.res_a, router.res_b);
+
+    router
+        .register_pair(ALICE, pair_ab, res_a, res_b)
+        .expect_commit_failure();
+    router
+        .register_pair(OWNER, pair_ab, res_a, res_a)
+        .expect_commit_failure();
+
+    router.register_pairs();
+
+    router
+        .call(ALICE, "unregister_pair", manifest_args!(pair_bc))
+        .expect_commit_failure();
+    router
+        .call(OWNER, "unregister_pair", manifest_args!(pair_bc))
+        .expect_commit_success();
+    router
+        .call(OWNER, "unregister_pair", manifest_args!(pair_bc))
+        .expect_commit_failure();
+
+    router
+        .swap_via(dec!(100), vec![pair_ab, pair_bc], dec!(0))
+        .expect_commit_failure();
+    router
+        .swap_via(dec!(100), vec![pair_ab], dec!(0))
+        .expect_commit_success();
+}
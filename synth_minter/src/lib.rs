@@ -0,0 +1,596 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use price_oracle::price_oracle::PriceOracle;
+use scrypto::prelude::*;
+
+/// Maximum number of synths, bounding the cost of the global debt computation
+pub const MAX_SYNTHS: usize = 20;
+
+#[derive(ScryptoSbor, Clone)]
+pub struct Synth {
+    /// Resource whose oracle price the synth tracks. The synth tracking the quote resource
+    /// is priced at 1.
+    pub tracked_res_address: ResourceAddress,
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct PositionReceipt {}
+
+#[derive(ScryptoSbor)]
+pub struct Position {
+    pub collateral: Vault,
+
+    /// Share of the global debt owed by the position
+    pub debt_shares: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SynthMintedEvent {
+    pub position_id: NonFungibleLocalId,
+    pub synth_res_address: ResourceAddress,
+    pub amount: Decimal,
+    pub debt_shares: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SynthBurnedEvent {
+    pub position_id: NonFungibleLocalId,
+    pub synth_res_address: ResourceAddress,
+    pub amount: Decimal,
+    pub debt_shares: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PositionLiquidatedEvent {
+    pub position_id: NonFungibleLocalId,
+    pub repaid_value: Decimal,
+    pub seized_collateral: Decimal,
+}
+
+#[blueprint]
+#[events(SynthMintedEvent, SynthBurnedEvent, PositionLiquidatedEvent)]
+pub mod synth_minter {
+
+    enable_method_auth! {
+        methods {
+
+            add_synth => restrict_to :[OWNER];
+            set_parameters => restrict_to :[OWNER];
+
+            open_position => PUBLIC;
+            deposit_collateral => PUBLIC;
+            withdraw_collateral => PUBLIC;
+            mint_synth => PUBLIC;
+            burn_synth => PUBLIC;
+            exchange => PUBLIC;
+            liquidate => PUBLIC;
+
+            get_total_debt_value => PUBLIC;
+            get_synth_debt_shares => PUBLIC;
+            get_position => PUBLIC;
+
+        }
+    }
+
+    pub struct SynthMinter {
+        /// Rule satisfied by calls made from this component
+        component_rule: AccessRule,
+
+        /// Synths, indexed by synth resource
+        synths: IndexMap<ResourceAddress, Synth>,
+
+        collateral_res_address: ResourceAddress,
+
+        /// Position receipt non-fungible resource manager
+        position_res_manager: ResourceManager,
+        positions: KeyValueStore<NonFungibleLocalId, Position>,
+
+        /// Sum of the debt shares of all positions
+        total_debt_shares: Decimal,
+
+        /// Minimum collateral value to debt value ratio to mint synths or withdraw collateral
+        issuance_ratio: Decimal,
+
+        /// Collateral value to debt value ratio below which a position can be liquidated
+        liquidation_ratio: Decimal,
+
+        /// Extra collateral value given to liquidators
+        liquidation_bonus: Decimal,
+
+        /// Oracle pricing the collateral and the tracked resources in the quote resource
+        oracle: Global<PriceOracle>,
+        quote_res_address: ResourceAddress,
+        max_price_age_seconds: i64,
+    }
+
+    impl SynthMinter {
+        #[allow(clippy::too_many_arguments)]
+        pub fn instantiate(
+            collateral_res_address: ResourceAddress,
+            issuance_ratio: Decimal,
+            liquidation_ratio: Decimal,
+            liquidation_bonus: Decimal,
+            oracle: Global<PriceOracle>,
+            quote_res_address: ResourceAddress,
+            max_price_age_seconds: i64,
+            owner_role: OwnerRole,
+        ) -> (Global<SynthMinter>, ResourceAddress) {
+            /* CHECK INPUT */
+            SynthMinter::_assert_valid_parameters(
+                issuance_ratio,
+                liquidation_ratio,
+                liquidation_bonus,
+            );
+            assert!(
+                max_price_age_seconds > 0,
+                "Max price age must be greater than zero"
+            );
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(SynthMinter::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let position_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<PositionReceipt>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let minter_component = Self {
+                component_rule,
+                synths: IndexMap::default(),
+                collateral_res_address,
+                position_res_manager,
+                positions: KeyValueStore::new(),
+                total_debt_shares: Decimal::ZERO,
+                issuance_ratio,
+                liquidation_ratio,
+                liquidation_bonus,
+                oracle,
+                quote_res_address,
+                max_price_age_seconds,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (minter_component, position_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        /// Create a synth tracking the oracle price of the given resource. Returns the synth
+        /// resource address.
+        pub fn add_synth(
+            &mut self,
+            tracked_res_address: ResourceAddress,
+            symbol: String,
+        ) -> ResourceAddress {
+            /* CHECK INPUT */
+            assert!(
+                self.synths.len() < MAX_SYNTHS,
+                "Maximum number of synths reached"
+            );
+            assert!(
+                self.synths
+                    .values()
+                    .all(|synth| synth.tracked_res_address != tracked_res_address),
+                "A synth already tracks this resource"
+            );
+
+            let synth_res_manager = ResourceBuilder::new_fungible(OwnerRole::None)
+                .metadata(metadata! {
+                    init {
+                        "symbol" => symbol, locked;
+                    }
+                })
+                .mint_roles(mint_roles! {
+                    minter => self.component_rule.clone();
+                    minter_updater => rule!(deny_all);
+                })
+                .burn_roles(burn_roles! {
+                    burner => self.component_rule.clone();
+                    burner_updater => rule!(deny_all);
+                })
+                .create_with_no_initial_supply();
+
+            self.synths.insert(
+                synth_res_manager.address(),
+                Synth {
+                    tracked_res_address,
+                },
+            );
+
+            synth_res_manager.address()
+        }
+
+        pub fn set_parameters(
+            &mut self,
+            issuance_ratio: Decimal,
+            liquidation_ratio: Decimal,
+            liquidation_bonus: Decimal,
+        ) {
+            SynthMinter::_assert_valid_parameters(
+                issuance_ratio,
+                liquidation_ratio,
+                liquidation_bonus,
+            );
+
+            self.issuance_ratio = issuance_ratio;
+            self.liquidation_ratio = liquidation_ratio;
+            self.liquidation_bonus = liquidation_bonus;
+        }
+
+        /* USER METHODS */
+
+        /// Open a position with the given collateral. Returns the position receipt.
+        pub fn open_position(&mut self, collateral: Bucket) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                collateral.resource_address() == self.collateral_res_address,
+                "Collateral resource address mismatch"
+            );
+
+            let receipt = self
+                .position_res_manager
+                .mint_ruid_non_fungible(PositionReceipt {});
+
+            self.positions.insert(
+                receipt.as_non_fungible().non_fungible_local_id(),
+                Position {
+                    collateral: Vault::with_bucket(collateral),
+                    debt_shares: Decimal::ZERO,
+                },
+            );
+
+            receipt
+        }
+
+        pub fn deposit_collateral(&mut self, position_proof: Proof, collateral: Bucket) {
+            let position_id = self._check_position_proof(position_proof);
+
+            self.positions
+                .get_mut(&position_id)
+                .unwrap()
+                .collateral
+                .put(collateral);
+        }
+
+        pub fn withdraw_collateral(&mut self, position_proof: Proof, amount: Decimal) -> Bucket {
+            let position_id = self._check_position_proof(position_proof);
+
+            /* INPUT CHECK */
+            assert!(amount > 0.into(), "Amount must be greater than zero!");
+
+            let collateral = self
+                .positions
+                .get_mut(&position_id)
+                .unwrap()
+                .collateral
+                .take_advanced(amount, WithdrawStrategy::Rounded(RoundingMode::ToZero));
+
+            self._assert_above_ratio(&position_id, self.issuance_ratio);
+
+            collateral
+        }
+
+        /// Mint synths against the collateral of a position. The position takes debt shares
+        /// worth the value of the minted synths.
+        pub fn mint_synth(
+            &mut self,
+            position_proof: Proof,
+            synth_res_address: ResourceAddress,
+            amount: Decimal,
+        ) -> Bucket {
+            let position_id = self._check_position_proof(position_proof);
+
+            /* INPUT CHECK */
+            assert!(amount > 0.into(), "Amount must be greater than zero!");
+
+            let value = amount * self._get_synth_price(&synth_res_address);
+            let debt_shares = self._get_debt_shares_for_value(value);
+
+            self.positions.get_mut(&position_id).unwrap().debt_shares += debt_shares;
+            self.total_debt_shares += debt_shares;
+
+            let synths = ResourceManager::from_address(synth_res_address).mint(amount);
+
+            self._assert_above_ratio(&position_id, self.issuance_ratio);
+
+            Runtime::emit_event(SynthMintedEvent {
+                position_id,
+                synth_res_address,
+                amount,
+                debt_shares,
+            });
+
+            synths
+        }
+
+        /// Burn synths to reduce the debt of a position. Synths worth more than the position
+        /// debt are returned.
+        pub fn burn_synth(&mut self, position_proof: Proof, mut synths: Bucket) -> Bucket {
+            let position_id = self._check_position_proof(position_proof);
+
+            let synth_res_address = synths.resource_address();
+            let price = self._get_synth_price(&synth_res_address);
+
+            let (debt_value, position_debt_shares) = self._get_position_debt(&position_id);
+            let burn_amount = synths.amount().min(debt_value / price);
+
+            let debt_shares = if burn_amount * price == debt_value {
+                position_debt_shares
+            } else {
+                self._get_debt_shares_for_value(burn_amount * price)
+                    .min(position_debt_shares)
+            };
+
+            synths
+                .take_advanced(burn_amount, WithdrawStrategy::Rounded(RoundingMode::ToZero))
+                .burn();
+
+            self.positions.get_mut(&position_id).unwrap().debt_shares -= debt_shares;
+            self.total_debt_shares -= debt_shares;
+
+            Runtime::emit_event(SynthBurnedEvent {
+                position_id,
+                synth_res_address,
+                amount: burn_amount,
+                debt_shares,
+            });
+
+            synths
+        }
+
+        /// Exchange a synth for another at oracle prices. The global debt is unchanged.
+        pub fn exchange(&mut self, synths: Bucket, output_res_address: ResourceAddress) -> Bucket {
+            let input_res_address = synths.resource_address();
+
+            /* CHECK INPUT */
+            assert!(
+                input_res_address != output_res_address,
+                "Input and output synths must be different"
+            );
+
+            let value = synths.amount() * self._get_synth_price(&input_res_address);
+            let output_amount = value / self._get_synth_price(&output_res_address);
+
+            synths.burn();
+
+            ResourceManager::from_address(output_res_address).mint(output_amount)
+        }
+
+        /// Burn synths to repay the debt of a position below the liquidation ratio, seizing its
+        /// collateral with a bonus. Returns the seized collateral and the synths change.
+        pub fn liquidate(
+            &mut self,
+            position_id: NonFungibleLocalId,
+            mut synths: Bucket,
+        ) -> (Bucket, Bucket) {
+            let (collateral_ratio, debt_value, position_debt_shares) =
+                self._get_position_health(&position_id);
+
+            assert!(
+                collateral_ratio < self.liquidation_ratio,
+                "Position is sufficiently collateralized"
+            );
+
+            let synth_res_address = synths.resource_address();
+            let price = self._get_synth_price(&synth_res_address);
+            let collateral_price = self._get_price(self.collateral_res_address);
+
+            let burn_amount = synths.amount().min(debt_value / price);
+            let repaid_value = burn_amount * price;
+
+            let debt_shares = if repaid_value == debt_value {
+                position_debt_shares
+            } else {
+                self._get_debt_shares_for_value(repaid_value)
+                    .min(position_debt_shares)
+            };
+
+            synths
+                .take_advanced(burn_amount, WithdrawStrategy::Rounded(RoundingMode::ToZero))
+                .burn();
+
+            self.total_debt_shares -= debt_shares;
+
+            let mut position = self.positions.get_mut(&position_id).unwrap();
+            position.debt_shares -= debt_shares;
+
+            let seized_amount = (repaid_value * (Decimal::ONE + self.liquidation_bonus)
+                / collateral_price)
+                .min(position.collateral.amount());
+            let collateral = position.collateral.take_advanced(
+                seized_amount,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+
+            drop(position);
+
+            Runtime::emit_event(PositionLiquidatedEvent {
+                position_id,
+                repaid_value,
+                seized_collateral: collateral.amount(),
+            });
+
+            (collateral, synths)
+        }
+
+        /* GETTERS */
+
+        /// Value of all synths in the quote resource, owed by the positions in proportion to
+        /// their debt shares
+        pub fn get_total_debt_value(&self) -> Decimal {
+            self.synths
+                .keys()
+                .fold(Decimal::ZERO, |total, synth_res_address| {
+                    total + self._get_synth_value(synth_res_address)
+                })
+        }
+
+        /// Share of the global debt backing each synth
+        pub fn get_synth_debt_shares(&self) -> IndexMap<ResourceAddress, Decimal> {
+            let total_debt_value = self.get_total_debt_value();
+
+            self.synths
+                .keys()
+                .map(|synth_res_address| {
+                    let share = if total_debt_value == Decimal::ZERO {
+                        Decimal::ZERO
+                    } else {
+                        self._get_synth_value(synth_res_address) / total_debt_value
+                    };
+
+                    (*synth_res_address, share)
+                })
+                .collect()
+        }
+
+        /// Collateral amount, debt shares and debt value of a position
+        pub fn get_position(&self, position_id: NonFungibleLocalId) -> (Decimal, Decimal, Decimal) {
+            let collateral_amount = self
+                .positions
+                .get(&position_id)
+                .expect("Position not found")
+                .collateral
+                .amount();
+            let (debt_value, debt_shares) = self._get_position_debt(&position_id);
+
+            (collateral_amount, debt_shares, debt_value)
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_parameters(
+            issuance_ratio: Decimal,
+            liquidation_ratio: Decimal,
+            liquidation_bonus: Decimal,
+        ) {
+            assert!(
+                liquidation_ratio >= Decimal::ONE && liquidation_ratio <= issuance_ratio,
+                "Liquidation ratio must be between 1 and the issuance ratio"
+            );
+            assert!(
+                liquidation_bonus >= Decimal::ZERO,
+                "Liquidation bonus must not be negative"
+            );
+        }
+
+        /// Debt shares issued for a debt increase of the given value
+        fn _get_debt_shares_for_value(&self, value: Decimal) -> Decimal {
+            let total_debt_value = self.get_total_debt_value();
+
+            if self.total_debt_shares == Decimal::ZERO || total_debt_value == Decimal::ZERO {
+                value
+            } else {
+                value * self.total_debt_shares / total_debt_value
+            }
+        }
+
+        /// Returns the debt value and the debt shares of a position
+        fn _get_position_debt(&self, position_id: &NonFungibleLocalId) -> (Decimal, Decimal) {
+            let debt_shares = self
+                .positions
+                .get(position_id)
+                .expect("Position not found")
+                .debt_shares;
+
+            if debt_shares == Decimal::ZERO {
+                return (Decimal::ZERO, debt_shares);
+            }
+
+            (
+                self.get_total_debt_value() * debt_shares / self.total_debt_shares,
+                debt_shares,
+            )
+        }
+
+        /// Returns the collateral ratio, the debt value and the debt shares of a position. The
+        /// ratio is `Decimal::MAX` when the position has no debt.
+        fn _get_position_health(
+            &self,
+            position_id: &NonFungibleLocalId,
+        ) -> (Decimal, Decimal, Decimal) {
+            let (debt_value, debt_shares) = self._get_position_debt(position_id);
+
+            if debt_value == Decimal::ZERO {
+                return (Decimal::MAX, debt_value, debt_shares);
+            }
+
+            let collateral_value = self.positions.get(position_id).unwrap().collateral.amount()
+                * self._get_price(self.collateral_res_address);
+
+            (collateral_value / debt_value, debt_value, debt_shares)
+        }
+
+        fn _assert_above_ratio(&self, position_id: &NonFungibleLocalId, min_ratio: Decimal) {
+            assert!(
+                self._get_position_health(position_id).0 >= min_ratio,
+                "Position would be undercollateralized"
+            );
+        }
+
+        fn _get_synth_value(&self, synth_res_address: &ResourceAddress) -> Decimal {
+            let supply = ResourceManager::from_address(*synth_res_address)
+                .total_supply()
+                .unwrap_or(dec!(0));
+
+            if supply == Decimal::ZERO {
+                Decimal::ZERO
+            } else {
+                supply * self._get_synth_price(synth_res_address)
+            }
+        }
+
+        fn _get_synth_price(&self, synth_res_address: &ResourceAddress) -> Decimal {
+            let synth = self.synths.get(synth_res_address).expect("Synth not found");
+
+            self._get_price(synth.tracked_res_address)
+        }
+
+        fn _get_price(&self, res_address: ResourceAddress) -> Decimal {
+            if res_address == self.quote_res_address {
+                Decimal::ONE
+            } else {
+                self.oracle.get_price(
+                    res_address,
+                    self.quote_res_address,
+                    self.max_price_age_seconds,
+                )
+            }
+        }
+
+        fn _check_position_proof(&self, position_proof: Proof) -> NonFungibleLocalId {
+            position_proof
+                .check(self.position_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+    }
+}
@@ -0,0 +1,424 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Owns the minter and feeds the oracle
+const OWNER: usize = 0;
+/// Holds 1000 collateral tokens
+const ALICE: usize = 1;
+/// Holds 1000 collateral tokens
+const BOB: usize = 2;
+
+/// Ledger time of the tests, in seconds, rounded to the minute
+const NOW: i64 = 1_700_000_040;
+
+/// Minter with an issuance ratio of 200%, a liquidation ratio of 150% and a liquidation bonus
+/// of 10%, issuing a synth tracking the quote resource and a synth tracking a BTC token. The
+/// oracle prices the collateral 2 and BTC 100. The oracle blueprint is linked into the minter
+/// package, so it is instantiated from the same package.
+struct TestMinter {
+    env: TestEnv,
+    minter: ComponentAddress,
+    receipt: ResourceAddress,
+    oracle: ComponentAddress,
+    collateral: ResourceAddress,
+    quote: ResourceAddress,
+    btc: ResourceAddress,
+    s_usd: ResourceAddress,
+    s_btc: ResourceAddress,
+}
+
+impl TestMinter {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.test_runner
+            .advance_to_round_at_timestamp(Round::of(1), NOW * 1000);
+        let collateral =
+            env.test_runner
+                .create_fungible_resource(dec!(2000), 18, env.accounts[ALICE]);
+        let quote = env
+            .test_runner
+            .create_fungible_resource(dec!(1), 18, env.accounts[OWNER]);
+        let btc = env
+            .test_runner
+            .create_fungible_resource(dec!(1), 18, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "PriceOracle",
+                "instantiate",
+                manifest_args!(
+                    owner_role.clone(),
+                    rule!(require(env.badges[OWNER].clone())),
+                    2u32
+                ),
+            )
+            .withdraw_from_account(env.accounts[ALICE], collateral, dec!(1000))
+            .take_all_from_worktop(collateral, "bob")
+            .call_method_with_name_lookup(env.accounts[BOB], "deposit", |lookup| {
+                (lookup.bucket("bob"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let oracle = receipt.expect_commit_success().new_component_addresses()[0];
+
+        let manifest = ManifestBuilder::new()
+            .call_method(
+                oracle,
+                "push_price",
+                manifest_args!(collateral, quote, dec!(2), NOW - 60),
+            )
+            .call_method(
+                oracle,
+                "push_price",
+                manifest_args!(btc, quote, dec!(100), NOW - 60),
+            )
+            .call_function(
+                env.package_address,
+                "SynthMinter",
+                "instantiate",
+                manifest_args!(
+                    collateral,
+                    dec!(2),
+                    dec!("1.5"),
+                    dec!("0.1"),
+                    oracle,
+                    quote,
+                    3600i64,
+                    owner_role
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()]);
+        let (minter, position_receipt): (ComponentAddress, ResourceAddress) =
+            receipt.expect_commit_success().output(2);
+
+        let manifest = ManifestBuilder::new()
+            .call_method(minter, "add_synth", manifest_args!(quote, "sUSD"))
+            .call_method(minter, "add_synth", manifest_args!(btc, "sBTC"))
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![env.badges[OWNER].clone()]);
+        let commit = receipt.expect_commit_success();
+        let (s_usd, s_btc) = (commit.output(0), commit.output(1));
+
+        Self {
+            env,
+            minter,
+            receipt: position_receipt,
+            oracle,
+            collateral,
+            quote,
+            btc,
+            s_usd,
+            s_btc,
+        }
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.minter, method, args)
+    }
+
+    fn push_price(&mut self, res_address: ResourceAddress, price: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_method(
+            self.oracle,
+            "push_price",
+            manifest_args!(res_address, self.quote, price, NOW),
+        );
+
+        self.env.execute(OWNER, builder)
+    }
+
+    fn open_position(&mut self, account: usize, amount: Decimal) -> TransactionReceipt {
+        let collateral = self.collateral;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[account], collateral, amount)
+            .take_all_from_worktop(collateral, "collateral")
+            .call_method_with_name_lookup(self.minter, "open_position", |lookup| {
+                (lookup.bucket("collateral"),)
+            });
+
+        self.env.execute(account, builder)
+    }
+
+    /// Id of the only position of the account
+    fn position_id(&mut self, account: usize) -> NonFungibleLocalId {
+        let vault_id = self
+            .env
+            .test_runner
+            .get_component_vaults(self.env.accounts[account], self.receipt)[0];
+        let (_, mut ids) = self
+            .env
+            .test_runner
+            .inspect_non_fungible_vault(vault_id)
+            .unwrap();
+
+        ids.next().unwrap()
+    }
+
+    /// Call a method with a proof of the position of the account, prepending the manifest
+    /// of the builder
+    fn call_with_position<A: ResolvableArguments>(
+        &mut self,
+        account: usize,
+        builder: ManifestBuilder,
+        method: &str,
+        args: impl FnOnce(&ManifestNameLookup) -> A,
+    ) -> TransactionReceipt {
+        let builder = builder
+            .create_proof_from_account_of_amount(self.env.accounts[account], self.receipt, dec!(1))
+            .pop_from_auth_zone("position")
+            .call_method_with_name_lookup(self.minter, method, |lookup| args(lookup));
+
+        self.env.execute(account, builder)
+    }
+
+    fn mint_synth(
+        &mut self,
+        account: usize,
+        synth_res_address: ResourceAddress,
+        amount: Decimal,
+    ) -> TransactionReceipt {
+        self.call_with_position(account, ManifestBuilder::new(), "mint_synth", |lookup| {
+            (lookup.proof("position"), synth_res_address, amount)
+        })
+    }
+
+    fn burn_synth(
+        &mut self,
+        account: usize,
+        synth_res_address: ResourceAddress,
+        amount: Decimal,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[account], synth_res_address, amount)
+            .take_all_from_worktop(synth_res_address, "synths");
+
+        self.call_with_position(account, builder, "burn_synth", |lookup| {
+            (lookup.proof("position"), lookup.bucket("synths"))
+        })
+    }
+
+    fn withdraw_collateral(&mut self, account: usize, amount: Decimal) -> TransactionReceipt {
+        self.call_with_position(
+            account,
+            ManifestBuilder::new(),
+            "withdraw_collateral",
+            |lookup| (lookup.proof("position"), amount),
+        )
+    }
+
+    /// Collateral amount, debt shares and debt value of the position of the account
+    fn get_position(&mut self, account: usize) -> (Decimal, Decimal, Decimal) {
+        let position_id = self.position_id(account);
+
+        self.call(OWNER, "get_position", manifest_args!(position_id))
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_mint_exchange_and_burn_synths() {
+    let mut minter = TestMinter::new();
+    let (s_usd, s_btc) = (minter.s_usd, minter.s_btc);
+
+    // 100 collateral are worth 200, backing up to 100 sUSD
+    minter
+        .open_position(ALICE, dec!(100))
+        .expect_commit_success();
+    minter
+        .mint_synth(ALICE, s_usd, dec!(100))
+        .expect_commit_success();
+    minter
+        .mint_synth(ALICE, s_btc, dec!("0.01"))
+        .expect_commit_failure();
+    minter
+        .withdraw_collateral(ALICE, dec!(1))
+        .expect_commit_failure();
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(minter.env.accounts[ALICE], s_usd, dec!(50))
+        .take_all_from_worktop(s_usd, "synths")
+        .call_method_with_name_lookup(minter.minter, "exchange", |lookup| {
+            (lookup.bucket("synths"), s_btc)
+        });
+    minter.env.execute(ALICE, builder).expect_commit_success();
+    assert_eq!(minter.env.balance(ALICE, s_btc), dec!("0.5"));
+    assert_eq!(
+        minter.get_position(ALICE),
+        (dec!(100), dec!(100), dec!(100))
+    );
+
+    minter
+        .burn_synth(ALICE, s_btc, dec!("0.5"))
+        .expect_commit_success();
+    assert_eq!(minter.get_position(ALICE), (dec!(100), dec!(50), dec!(50)));
+
+    minter
+        .withdraw_collateral(ALICE, dec!(50))
+        .expect_commit_success();
+    minter
+        .withdraw_collateral(ALICE, dec!(1))
+        .expect_commit_failure();
+
+    minter
+        .burn_synth(ALICE, s_usd, dec!(50))
+        .expect_commit_success();
+    assert_eq!(minter.get_position(ALICE), (dec!(50), dec!(0), dec!(0)));
+    assert_eq!(minter.env.balance(ALICE, s_usd), dec!(0));
+}
+
+#[test]
+fn test_positions_share_the_global_debt() {
+    let mut minter = TestMinter::new();
+    let (btc, s_usd, s_btc) = (minter.btc, minter.s_usd, minter.s_btc);
+
+    minter
+        .open_position(ALICE, dec!(500))
+        .expect_commit_success();
+    minter
+        .mint_synth(ALICE, s_usd, dec!(100))
+        .expect_commit_success();
+    minter.open_position(BOB, dec!(500)).expect_commit_success();
+    minter
+        .mint_synth(BOB, s_btc, dec!(1))
+        .expect_commit_success();
+
+    // The BTC price rise is owed by both positions
+    minter.push_price(btc, dec!(300)).expect_commit_success();
+
+    let total_debt_value: Decimal = minter
+        .call(ALICE, "get_total_debt_value", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(total_debt_value, dec!(400));
+
+    let debt_shares: IndexMap<ResourceAddress, Decimal> = minter
+        .call(ALICE, "get_synth_debt_shares", manifest_args!())
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(debt_shares[&s_usd], dec!("0.25"));
+    assert_eq!(debt_shares[&s_btc], dec!("0.75"));
+
+    assert_eq!(
+        minter.get_position(ALICE),
+        (dec!(500), dec!(100), dec!(200))
+    );
+    assert_eq!(minter.get_position(BOB), (dec!(500), dec!(100), dec!(200)));
+}
+
+#[test]
+fn test_undercollateralized_position_is_liquidated() {
+    let mut minter = TestMinter::new();
+    let (collateral, s_usd) = (minter.collateral, minter.s_usd);
+
+    minter
+        .open_position(ALICE, dec!(100))
+        .expect_commit_success();
+    minter
+        .mint_synth(ALICE, s_usd, dec!(100))
+        .expect_commit_success();
+    minter
+        .open_position(BOB, dec!(1000))
+        .expect_commit_success();
+    minter
+        .mint_synth(BOB, s_usd, dec!(100))
+        .expect_commit_success();
+
+    let (bob, component) = (minter.env.accounts[BOB], minter.minter);
+    let alice_position = minter.position_id(ALICE);
+    let liquidate = move |position_id: NonFungibleLocalId| {
+        ManifestBuilder::new()
+            .withdraw_from_account(bob, s_usd, dec!(50))
+            .take_all_from_worktop(s_usd, "synths")
+            .call_method_with_name_lookup(component, "liquidate", |lookup| {
+                (position_id, lookup.bucket("synths"))
+            })
+    };
+
+    let builder = liquidate(alice_position.clone());
+    minter.env.execute(BOB, builder).expect_commit_failure();
+
+    // 100 collateral are now worth 125, below 150% of the debt
+    minter
+        .push_price(collateral, dec!("1.25"))
+        .expect_commit_success();
+
+    let builder = liquidate(alice_position);
+    minter.env.execute(BOB, builder).expect_commit_success();
+
+    // 50 repaid plus a 10% bonus, at 1.25 per collateral
+    assert_eq!(minter.env.balance(BOB, collateral), dec!(44));
+    assert_eq!(minter.env.balance(BOB, s_usd), dec!(50));
+    assert_eq!(minter.get_position(ALICE), (dec!(56), dec!(50), dec!(50)));
+}
+
+#[test]
+fn test_admin_methods_require_the_owner() {
+    let mut minter = TestMinter::new();
+    let (collateral, btc, s_usd) = (minter.collateral, minter.btc, minter.s_usd);
+
+    minter
+        .call(ALICE, "add_synth", manifest_args!(collateral, "sCOL"))
+        .expect_commit_failure();
+    minter
+        .call(OWNER, "add_synth", manifest_args!(btc, "sBTC2"))
+        .expect_commit_failure();
+
+    minter
+        .call(
+            ALICE,
+            "set_parameters",
+            manifest_args!(dec!(4), dec!("1.5"), dec!("0.1")),
+        )
+        .expect_commit_failure();
+    minter
+        .call(
+            OWNER,
+            "set_parameters",
+            manifest_args!(dec!(2), dec!(3), dec!("0.1")),
+        )
+        .expect_commit_failure();
+    minter
+        .call(
+            OWNER,
+            "set_parameters",
+            manifest_args!(dec!(4), dec!("1.5"), dec!("0.1")),
+        )
+        .expect_commit_success();
+
+    // 100 collateral now back up to 50 sUSD
+    minter
+        .open_position(ALICE, dec!(100))
+        .expect_commit_success();
+    minter
+        .mint_synth(ALICE, s_usd, dec!(51))
+        .expect_commit_failure();
+    minter
+        .mint_synth(ALICE, s_usd, dec!(50))
+        .expect_commit_success();
+
+    // Only the collateral resource opens a position
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(minter.env.accounts[OWNER], btc, dec!(1))
+        .take_all_from_worktop(btc, "collateral")
+        .call_method_with_name_lookup(minter.minter, "open_position", |lookup| {
+            (lookup.bucket("collateral"),)
+        });
+    minter.env.execute(OWNER, builder).expect_commit_failure();
+}
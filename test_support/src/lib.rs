@@ -0,0 +1,157 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto_unit::*;
+use std::path::Path;
+use transaction::prelude::*;
+
+/// Ledger simulator with the package under test published and allocated accounts. Each
+/// account is controlled by the key whose virtual badge is at the same index in `badges`.
+pub struct TestEnv {
+    pub test_runner: DefaultTestRunner,
+    pub package_address: PackageAddress,
+    pub badges: Vec<NonFungibleGlobalId>,
+    pub accounts: Vec<ComponentAddress>,
+}
+
+impl TestEnv {
+    /// Allocate `account_count` accounts and publish the package at `package_dir`, usually
+    /// `this_package!()`
+    pub fn new<P: AsRef<Path>>(package_dir: P, account_count: usize) -> Self {
+        let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+
+        let mut badges = vec![];
+        let mut accounts = vec![];
+        for _ in 0..account_count {
+            let (public_key, _, account) = test_runner.new_allocated_account();
+            badges.push(NonFungibleGlobalId::from_public_key(&public_key));
+            accounts.push(account);
+        }
+
+        let package_address = test_runner.compile_and_publish(package_dir);
+
+        Self {
+            test_runner,
+            package_address,
+            badges,
+            accounts,
+        }
+    }
+
+    /// Execute the manifest with the key of the account only, then deposit the worktop into
+    /// the account
+    pub fn execute(&mut self, account: usize, builder: ManifestBuilder) -> TransactionReceipt {
+        let proofs = vec![self.badges[account].clone()];
+
+        self._execute_with_proofs(account, builder, proofs)
+    }
+
+    /// Execute the manifest with the keys of every account, then deposit the worktop into the
+    /// given account
+    pub fn execute_with_all_keys(
+        &mut self,
+        account: usize,
+        builder: ManifestBuilder,
+    ) -> TransactionReceipt {
+        let proofs = self.badges.clone();
+
+        self._execute_with_proofs(account, builder, proofs)
+    }
+
+    /// Call a method of the component as the account, which receives the returned buckets
+    pub fn call(
+        &mut self,
+        account: usize,
+        component: ComponentAddress,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_method(component, method, args);
+
+        self.execute(account, builder)
+    }
+
+    /// Call a function of a blueprint of the published package as the account
+    pub fn call_function(
+        &mut self,
+        account: usize,
+        blueprint_name: &str,
+        function_name: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_function(
+            self.package_address,
+            blueprint_name,
+            function_name,
+            args,
+        );
+
+        self.execute(account, builder)
+    }
+
+    /// Output of a method called without any proof, which must succeed
+    pub fn get<T: ScryptoDecode>(
+        &mut self,
+        component: ComponentAddress,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> T {
+        let manifest = ManifestBuilder::new()
+            .call_method(component, method, args)
+            .build();
+
+        self.test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![])
+            .expect_commit_success()
+            .output(0)
+    }
+
+    pub fn balance(&mut self, account: usize, res_address: ResourceAddress) -> Decimal {
+        self.test_runner
+            .get_component_balance(self.accounts[account], res_address)
+    }
+
+    pub fn set_epoch(&mut self, epoch: u64) {
+        self.test_runner.set_current_epoch(Epoch::of(epoch));
+    }
+
+    fn _execute_with_proofs(
+        &mut self,
+        account: usize,
+        builder: ManifestBuilder,
+        proofs: Vec<NonFungibleGlobalId>,
+    ) -> TransactionReceipt {
+        let manifest = builder.deposit_batch(self.accounts[account]).build();
+
+        self.test_runner
+            .execute_manifest_ignoring_fee(manifest, proofs)
+    }
+}
+
+/// Expect the transaction to fail with an error whose debug output contains `message`, such
+/// as a panic message or an error code
+pub fn expect_failure_containing(receipt: TransactionReceipt, message: &str) {
+    receipt.expect_specific_failure(|error| format!("{:?}", error).contains(message));
+}
@@ -0,0 +1,314 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::{assert_fungible_res_address, assert_non_fungible_res_address};
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SaleMode {
+    /// Contributions are accepted first come first served until every token is sold
+    FixedPrice,
+    /// Contributions are accepted without limit. If the sale is oversubscribed, tokens are
+    /// allocated pro-rata and the excess contributions are refunded.
+    Overflow,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ContributionEvent {
+    pub participant: NonFungibleGlobalId,
+    pub amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ClaimEvent {
+    pub participant: NonFungibleGlobalId,
+    pub token_amount: Decimal,
+    pub refund_amount: Decimal,
+}
+
+#[blueprint]
+#[events(ContributionEvent, ClaimEvent)]
+pub mod token_sale {
+
+    enable_method_auth! {
+        methods {
+
+            contribute => PUBLIC;
+            claim => PUBLIC;
+            finalize => PUBLIC;
+
+            get_total_raised => PUBLIC;
+            get_contribution => PUBLIC;
+            get_allocation => PUBLIC;
+
+        }
+    }
+
+    pub struct TokenSale {
+        mode: SaleMode,
+
+        /// Tokens for sale
+        tokens: Vault,
+        tokens_for_sale: Decimal,
+
+        /// Payment resource amount per token
+        price: Decimal,
+
+        /// Contributions
+        raised: Vault,
+        total_raised: Decimal,
+
+        start_epoch: u64,
+        end_epoch: u64,
+
+        /// Non fungible resource identifying the participants, e.g. a KYC badge. Caps are
+        /// enforced per participant badge.
+        participant_badge: ResourceAddress,
+        max_contribution_per_participant: Decimal,
+        contributions: KeyValueStore<NonFungibleGlobalId, Decimal>,
+        claimed: KeyValueStore<NonFungibleGlobalId, ()>,
+
+        /// Account receiving the raised funds and the unsold tokens
+        treasury: Global<Account>,
+        finalized: bool,
+    }
+
+    impl TokenSale {
+        pub fn instantiate(
+            mode: SaleMode,
+            tokens: Bucket,
+            payment_res_address: ResourceAddress,
+            price: Decimal,
+            start_epoch: u64,
+            end_epoch: u64,
+            participant_badge: ResourceAddress,
+            max_contribution_per_participant: Decimal,
+            treasury: Global<Account>,
+        ) -> Global<TokenSale> {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                payment_res_address,
+                Some("Payment resource must be fungible".to_string()),
+            );
+            assert_non_fungible_res_address(
+                participant_badge,
+                Some("Participant badge must be non fungible".to_string()),
+            );
+            assert!(!tokens.is_empty(), "No token to sell");
+            assert!(price > Decimal::ZERO, "Price must be greater than zero!");
+            assert!(
+                end_epoch > start_epoch,
+                "End epoch must be after the start epoch"
+            );
+            assert!(
+                max_contribution_per_participant > Decimal::ZERO,
+                "Contribution cap must be greater than zero"
+            );
+
+            Self {
+                mode,
+                tokens_for_sale: tokens.amount(),
+                tokens: Vault::with_bucket(tokens),
+                price,
+                raised: Vault::new(payment_res_address),
+                total_raised: Decimal::ZERO,
+                start_epoch,
+                end_epoch,
+                participant_badge,
+                max_contribution_per_participant,
+                contributions: KeyValueStore::new(),
+                claimed: KeyValueStore::new(),
+                treasury,
+                finalized: false,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .globalize()
+        }
+
+        /* PARTICIPANT METHODS */
+
+        /// Contribute to the sale. Contributions above the participant cap, or above the
+        /// remaining tokens in fixed price mode, are returned immediately.
+        pub fn contribute(&mut self, participant_proof: Proof, mut payment: Bucket) -> Bucket {
+            let participant = self._check_participant_proof(participant_proof);
+            let current_epoch = Runtime::current_epoch().number();
+
+            /* CHECK INPUT */
+            assert!(
+                current_epoch >= self.start_epoch && current_epoch <= self.end_epoch,
+                "Sale is not open"
+            );
+            assert!(
+                payment.resource_address() == self.raised.resource_address(),
+                "Payment resource address mismatch"
+            );
+
+            let contributed = self._get_contribution(&participant);
+
+            let mut accepted_amount = payment
+                .amount()
+                .min(self.max_contribution_per_participant - contributed);
+
+            if self.mode == SaleMode::FixedPrice {
+                accepted_amount = accepted_amount.min(self._get_target_raise() - self.total_raised);
+            }
+
+            assert!(accepted_amount > Decimal::ZERO, "Contribution cap reached");
+
+            let accepted = payment.take_advanced(
+                accepted_amount,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+
+            self.total_raised += accepted.amount();
+            self.contributions
+                .insert(participant.clone(), contributed + accepted.amount());
+
+            Runtime::emit_event(ContributionEvent {
+                participant,
+                amount: accepted.amount(),
+            });
+
+            self.raised.put(accepted);
+
+            payment
+        }
+
+        /// Claim the allocated tokens and the refund of the excess contribution once the sale
+        /// has ended
+        pub fn claim(&mut self, participant_proof: Proof) -> (Bucket, Bucket) {
+            let participant = self._check_participant_proof(participant_proof);
+
+            assert!(
+                Runtime::current_epoch().number() > self.end_epoch,
+                "Sale has not ended yet"
+            );
+            assert!(
+                self.claimed.get(&participant).is_none(),
+                "Allocation already claimed"
+            );
+
+            let (token_amount, refund_amount) = self._get_allocation(&participant);
+
+            self.claimed.insert(participant.clone(), ());
+
+            let tokens = self.tokens.take_advanced(
+                token_amount,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+            let refund = self.raised.take_advanced(
+                refund_amount,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+
+            Runtime::emit_event(ClaimEvent {
+                participant,
+                token_amount: tokens.amount(),
+                refund_amount: refund.amount(),
+            });
+
+            (tokens, refund)
+        }
+
+        /// Send the raised funds and the unsold tokens to the treasury once the sale has ended.
+        /// Anyone can finalize the sale.
+        pub fn finalize(&mut self) {
+            assert!(
+                Runtime::current_epoch().number() > self.end_epoch,
+                "Sale has not ended yet"
+            );
+            assert!(!self.finalized, "Sale already finalized");
+
+            self.finalized = true;
+
+            let target_raise = self._get_target_raise();
+            let spent_amount = self.total_raised.min(target_raise);
+            let sold_amount = self.tokens_for_sale * spent_amount / target_raise;
+
+            let funds = self.raised.take_advanced(
+                spent_amount,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+            let unsold_tokens = self.tokens.take_advanced(
+                self.tokens_for_sale - sold_amount,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+
+            self.treasury.try_deposit_or_abort(funds, None);
+            self.treasury.try_deposit_or_abort(unsold_tokens, None);
+        }
+
+        /* GETTERS */
+
+        pub fn get_total_raised(&self) -> Decimal {
+            self.total_raised
+        }
+
+        pub fn get_contribution(&self, participant: NonFungibleGlobalId) -> Decimal {
+            self._get_contribution(&participant)
+        }
+
+        /// Token amount and refund amount of a participant, based on the current contributions
+        pub fn get_allocation(&self, participant: NonFungibleGlobalId) -> (Decimal, Decimal) {
+            self._get_allocation(&participant)
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        /// Amount raised when every token is sold at the sale price
+        fn _get_target_raise(&self) -> Decimal {
+            self.tokens_for_sale * self.price
+        }
+
+        fn _get_contribution(&self, participant: &NonFungibleGlobalId) -> Decimal {
+            self.contributions
+                .get(participant)
+                .map(|amount| *amount)
+                .unwrap_or(Decimal::ZERO)
+        }
+
+        fn _get_allocation(&self, participant: &NonFungibleGlobalId) -> (Decimal, Decimal) {
+            let contributed = self._get_contribution(participant);
+            let target_raise = self._get_target_raise();
+
+            if self.total_raised <= target_raise {
+                return (contributed / self.price, Decimal::ZERO);
+            }
+
+            // Oversubscribed: every participant gets the same share of its contribution
+            let spent_amount = contributed * target_raise / self.total_raised;
+
+            (spent_amount / self.price, contributed - spent_amount)
+        }
+
+        fn _check_participant_proof(&self, participant_proof: Proof) -> NonFungibleGlobalId {
+            let participant_id = participant_proof
+                .check(self.participant_badge)
+                .as_non_fungible()
+                .non_fungible_local_id();
+
+            NonFungibleGlobalId::new(self.participant_badge, participant_id)
+        }
+    }
+}
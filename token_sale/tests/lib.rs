@@ -0,0 +1,250 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Participants holding the participant badges 1 and 2, and 1000 payment tokens each
+const ALICE: usize = 0;
+const BOB: usize = 1;
+/// Receives the raised funds and the unsold tokens
+const TREASURY: usize = 2;
+
+/// Manifest encoding of `SaleMode::FixedPrice`
+fn fixed_price() -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 0,
+        fields: vec![],
+    }
+}
+
+/// Manifest encoding of `SaleMode::Overflow`
+fn overflow() -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 1,
+        fields: vec![],
+    }
+}
+
+/// Sale of 100 tokens at a price of 2 from epoch 20 to epoch 30, with a contribution cap of
+/// 150 per participant
+struct TestSale {
+    env: TestEnv,
+    sale: ComponentAddress,
+    participant_badge: ResourceAddress,
+    token: ResourceAddress,
+    payment: ResourceAddress,
+}
+
+impl TestSale {
+    fn new(mode: ManifestValue) -> Self {
+        let mut env = TestEnv::new(this_package!(), 3);
+        env.set_epoch(10);
+        let participant_badge = env
+            .test_runner
+            .create_non_fungible_resource(env.accounts[ALICE]);
+        let token = env
+            .test_runner
+            .create_fungible_resource(dec!(100), 18, env.accounts[TREASURY]);
+        let payment = env
+            .test_runner
+            .create_fungible_resource(dec!(2000), 18, env.accounts[ALICE]);
+
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account(env.accounts[TREASURY], token, dec!(100))
+            .take_all_from_worktop(token, "tokens")
+            .call_function_with_name_lookup(
+                env.package_address,
+                "TokenSale",
+                "instantiate",
+                |lookup| {
+                    (
+                        mode,
+                        lookup.bucket("tokens"),
+                        payment,
+                        dec!(2),
+                        20u64,
+                        30u64,
+                        participant_badge,
+                        dec!(150),
+                        env.accounts[TREASURY],
+                    )
+                },
+            )
+            .withdraw_non_fungibles_from_account(
+                env.accounts[ALICE],
+                participant_badge,
+                &BTreeSet::from([NonFungibleLocalId::integer(2)]),
+            )
+            .withdraw_from_account(env.accounts[ALICE], payment, dec!(1000))
+            .deposit_batch(env.accounts[BOB])
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            sale: commit.new_component_addresses()[0],
+            env,
+            participant_badge,
+            token,
+            payment,
+        }
+    }
+
+    /// Execute the manifest with the keys of every account, then deposit the worktop into the
+    /// given account
+    fn execute(&mut self, account: usize, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute_with_all_keys(account, builder)
+    }
+
+    /// Proof of the participant badge of the account, popped as "participant"
+    fn with_participant_proof(&self, participant: usize) -> ManifestBuilder {
+        ManifestBuilder::new()
+            .create_proof_from_account_of_non_fungibles(
+                self.env.accounts[participant],
+                self.participant_badge,
+                &BTreeSet::from([NonFungibleLocalId::integer(participant as u64 + 1)]),
+            )
+            .pop_from_auth_zone("participant")
+    }
+
+    fn contribute(&mut self, participant: usize, amount: Decimal) -> TransactionReceipt {
+        let builder = self
+            .with_participant_proof(participant)
+            .withdraw_from_account(self.env.accounts[participant], self.payment, amount)
+            .take_all_from_worktop(self.payment, "payment")
+            .call_method_with_name_lookup(self.sale, "contribute", |lookup| {
+                (lookup.proof("participant"), lookup.bucket("payment"))
+            });
+
+        self.execute(participant, builder)
+    }
+
+    fn claim(&mut self, participant: usize) -> TransactionReceipt {
+        let builder = self
+            .with_participant_proof(participant)
+            .call_method_with_name_lookup(self.sale, "claim", |lookup| {
+                (lookup.proof("participant"),)
+            });
+
+        self.execute(participant, builder)
+    }
+
+    fn finalize(&mut self) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_method(self.sale, "finalize", manifest_args!());
+
+        self.execute(ALICE, builder)
+    }
+}
+
+#[test]
+fn test_contribute_only_while_the_sale_is_open() {
+    let mut sale = TestSale::new(fixed_price());
+
+    sale.contribute(ALICE, dec!(10)).expect_commit_failure();
+
+    sale.env.set_epoch(30);
+    sale.contribute(ALICE, dec!(10)).expect_commit_success();
+
+    sale.env.set_epoch(31);
+    sale.contribute(ALICE, dec!(10)).expect_commit_failure();
+}
+
+#[test]
+fn test_contribution_above_the_cap_is_returned() {
+    let mut sale = TestSale::new(overflow());
+    let payment = sale.payment;
+
+    sale.env.set_epoch(20);
+    sale.contribute(ALICE, dec!(200)).expect_commit_success();
+    assert_eq!(sale.env.balance(ALICE, payment), dec!(850));
+
+    sale.contribute(ALICE, dec!(10)).expect_commit_failure();
+}
+
+#[test]
+fn test_fixed_price_sale_is_first_come_first_served() {
+    let mut sale = TestSale::new(fixed_price());
+    let (token, payment) = (sale.token, sale.payment);
+
+    sale.env.set_epoch(20);
+    sale.contribute(ALICE, dec!(150)).expect_commit_success();
+    sale.contribute(BOB, dec!(100)).expect_commit_success();
+    assert_eq!(sale.env.balance(BOB, payment), dec!(950));
+
+    sale.claim(ALICE).expect_commit_failure();
+
+    sale.env.set_epoch(31);
+    sale.claim(ALICE).expect_commit_success();
+    sale.claim(BOB).expect_commit_success();
+    sale.claim(BOB).expect_commit_failure();
+
+    assert_eq!(sale.env.balance(ALICE, token), dec!(75));
+    assert_eq!(sale.env.balance(BOB, token), dec!(25));
+
+    sale.finalize().expect_commit_success();
+    sale.finalize().expect_commit_failure();
+    assert_eq!(sale.env.balance(TREASURY, payment), dec!(200));
+    assert_eq!(sale.env.balance(TREASURY, token), dec!(0));
+}
+
+#[test]
+fn test_unsold_tokens_go_to_the_treasury() {
+    let mut sale = TestSale::new(fixed_price());
+    let (token, payment) = (sale.token, sale.payment);
+
+    sale.env.set_epoch(20);
+    sale.contribute(ALICE, dec!(100)).expect_commit_success();
+
+    sale.finalize().expect_commit_failure();
+
+    sale.env.set_epoch(31);
+    sale.finalize().expect_commit_success();
+    assert_eq!(sale.env.balance(TREASURY, payment), dec!(100));
+    assert_eq!(sale.env.balance(TREASURY, token), dec!(50));
+
+    sale.claim(ALICE).expect_commit_success();
+    assert_eq!(sale.env.balance(ALICE, token), dec!(50));
+}
+
+#[test]
+fn test_oversubscribed_sale_is_allocated_pro_rata() {
+    let mut sale = TestSale::new(overflow());
+    let (token, payment) = (sale.token, sale.payment);
+
+    sale.env.set_epoch(20);
+    sale.contribute(ALICE, dec!(150)).expect_commit_success();
+    sale.contribute(BOB, dec!(50)).expect_commit_success();
+    sale.contribute(BOB, dec!(100)).expect_commit_success();
+
+    sale.env.set_epoch(31);
+    sale.claim(ALICE).expect_commit_success();
+    sale.claim(BOB).expect_commit_success();
+    sale.finalize().expect_commit_success();
+
+    assert_eq!(sale.env.balance(ALICE, token), dec!(50));
+    assert_eq!(sale.env.balance(ALICE, payment), dec!(900));
+    assert_eq!(sale.env.balance(BOB, token), dec!(50));
+    assert_eq!(sale.env.balance(BOB, payment), dec!(900));
+    assert_eq!(sale.env.balance(TREASURY, payment), dec!(200));
+}
+
+#[test]
+fn test_contribute_requires_the_participant_badge() {
+    let mut sale = TestSale::new(fixed_price());
+    let (account, payment, sale_address) = (sale.env.accounts[ALICE], sale.payment, sale.sale);
+
+    sale.env.set_epoch(20);
+
+    let builder = ManifestBuilder::new()
+        .create_proof_from_account_of_amount(account, payment, dec!(1))
+        .pop_from_auth_zone("participant")
+        .withdraw_from_account(account, payment, dec!(10))
+        .take_all_from_worktop(payment, "payment")
+        .call_method_with_name_lookup(sale_address, "contribute", |lookup| {
+            (lookup.proof("participant"), lookup.bucket("payment"))
+        });
+    sale.execute(ALICE, builder).expect_commit_failure();
+}
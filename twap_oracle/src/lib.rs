@@ -0,0 +1,265 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use radiswap_pair::radiswap_pair::RadiswapPair;
+use scrypto::prelude::*;
+use single_asset_pool::pool::AssetPool;
+
+/// Maximum number of observations kept per source
+pub const MAX_OBSERVATIONS: usize = 64;
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub enum PriceSource {
+    /// Price of the pair first resource, in the second resource, from the pair reserves
+    RadiswapPair(Global<RadiswapPair>),
+    /// Amount of pooled asset per pool unit
+    PoolUnitRatio(Global<AssetPool>),
+}
+
+impl PriceSource {
+    pub fn spot_price(&self) -> Decimal {
+        match self {
+            PriceSource::RadiswapPair(pair) => {
+                let (reserve_a, reserve_b) = pair.get_reserves();
+
+                assert!(reserve_a > Decimal::ZERO, "Pair has no liquidity");
+
+                reserve_b / reserve_a
+            }
+            PriceSource::PoolUnitRatio(pool) => {
+                let ratio = pool.get_pool_unit_ratio();
+
+                assert!(ratio > PreciseDecimal::ZERO, "Pool has no liquidity");
+
+                (PreciseDecimal::ONE / ratio)
+                    .checked_truncate(RoundingMode::ToZero)
+                    .unwrap()
+            }
+        }
+    }
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct PriceObservation {
+    pub epoch: u64,
+
+    /// Spot price read at the checkpoint, applying until the next checkpoint
+    pub price: Decimal,
+
+    /// Sum of the price over every epoch since the first checkpoint
+    pub cumulative_price: PreciseDecimal,
+}
+
+#[derive(ScryptoSbor)]
+pub struct SourceState {
+    pub source: PriceSource,
+
+    /// Buffer of the latest observations, ordered by epoch
+    pub observations: Vec<PriceObservation>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct CheckpointEvent {
+    pub source_id: u64,
+    pub price: Decimal,
+    pub epoch: u64,
+}
+
+#[blueprint]
+#[events(CheckpointEvent)]
+pub mod twap_oracle {
+
+    enable_method_auth! {
+        methods {
+
+            register_source => restrict_to :[OWNER];
+            set_min_checkpoint_interval => restrict_to :[OWNER];
+
+            checkpoint => PUBLIC;
+
+            get_twap => PUBLIC;
+            get_observations => PUBLIC;
+            get_source => PUBLIC;
+
+        }
+    }
+
+    pub struct TwapOracle {
+        sources: KeyValueStore<u64, SourceState>,
+        next_source_id: u64,
+
+        /// Minimum number of epochs between two checkpoints of a source
+        min_checkpoint_interval: u64,
+    }
+
+    impl TwapOracle {
+        pub fn instantiate(
+            owner_role: OwnerRole,
+            min_checkpoint_interval: u64,
+        ) -> Global<TwapOracle> {
+            /* CHECK INPUT */
+            assert!(
+                min_checkpoint_interval > 0,
+                "Checkpoint interval must be greater than zero"
+            );
+
+            Self {
+                sources: KeyValueStore::new(),
+                next_source_id: 1,
+                min_checkpoint_interval,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .globalize()
+        }
+
+        /* ADMIN METHODS */
+
+        /// Register a price source and record its first observation. Returns the source id.
+        pub fn register_source(&mut self, source: PriceSource) -> u64 {
+            let source_id = self.next_source_id;
+            self.next_source_id += 1;
+
+            let price = source.spot_price();
+            let epoch = Runtime::current_epoch().number();
+
+            self.sources.insert(
+                source_id,
+                SourceState {
+                    source,
+                    observations: vec![PriceObservation {
+                        epoch,
+                        price,
+                        cumulative_price: PreciseDecimal::ZERO,
+                    }],
+                },
+            );
+
+            Runtime::emit_event(CheckpointEvent {
+                source_id,
+                price,
+                epoch,
+            });
+
+            source_id
+        }
+
+        pub fn set_min_checkpoint_interval(&mut self, min_checkpoint_interval: u64) {
+            assert!(
+                min_checkpoint_interval > 0,
+                "Checkpoint interval must be greater than zero"
+            );
+
+            self.min_checkpoint_interval = min_checkpoint_interval;
+        }
+
+        /* KEEPER METHODS */
+
+        /// Record the current spot price of a source. Callable by anyone, at most once every
+        /// `min_checkpoint_interval` epochs.
+        pub fn checkpoint(&mut self, source_id: u64) {
+            let epoch = Runtime::current_epoch().number();
+
+            let mut state = self.sources.get_mut(&source_id).expect("Source not found");
+
+            let last_observation = state.observations.last().unwrap().clone();
+
+            assert!(
+                epoch >= last_observation.epoch + self.min_checkpoint_interval,
+                "Checkpoint interval has not elapsed"
+            );
+
+            let price = state.source.spot_price();
+
+            if state.observations.len() >= MAX_OBSERVATIONS {
+                state.observations.remove(0);
+            }
+
+            state.observations.push(PriceObservation {
+                epoch,
+                price,
+                cumulative_price: TwapOracle::_cumulative_price_at(&last_observation, epoch),
+            });
+
+            Runtime::emit_event(CheckpointEvent {
+                source_id,
+                price,
+                epoch,
+            });
+        }
+
+        /* GETTERS */
+
+        /// Time weighted average price of a source over the last `window_epochs` epochs. The
+        /// window must be covered by the kept observations.
+        pub fn get_twap(&self, source_id: u64, window_epochs: u64) -> Decimal {
+            assert!(window_epochs > 0, "Window must be greater than zero");
+
+            let state = self.sources.get(&source_id).expect("Source not found");
+
+            let current_epoch = Runtime::current_epoch().number();
+            let window_start = current_epoch
+                .checked_sub(window_epochs)
+                .expect("Window exceeds the observation history");
+
+            let start_observation = state
+                .observations
+                .iter()
+                .rev()
+                .find(|observation| observation.epoch <= window_start)
+                .expect("Window exceeds the observation history");
+            let last_observation = state.observations.last().unwrap();
+
+            let cumulative_price_delta =
+                TwapOracle::_cumulative_price_at(last_observation, current_epoch)
+                    - TwapOracle::_cumulative_price_at(start_observation, window_start);
+
+            (cumulative_price_delta / PreciseDecimal::from(window_epochs))
+                .checked_truncate(RoundingMode::ToZero)
+                .unwrap()
+        }
+
+        pub fn get_observations(&self, source_id: u64) -> Vec<PriceObservation> {
+            self.sources
+                .get(&source_id)
+                .map(|state| state.observations.clone())
+                .unwrap_or_default()
+        }
+
+        pub fn get_source(&self, source_id: u64) -> Option<PriceSource> {
+            self.sources
+                .get(&source_id)
+                .map(|state| state.source.clone())
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        /// Cumulative price at an epoch after the observation, the observed price applying
+        /// until then
+        fn _cumulative_price_at(observation: &PriceObservation, epoch: u64) -> PreciseDecimal {
+            observation.cumulative_price
+                + PreciseDecimal::from(observation.price)
+                    * PreciseDecimal::from(epoch - observation.epoch)
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+use twap_oracle::PriceObservation;
+
+/// Oracle with a checkpoint interval of 2 epochs, tracking a Radiswap pair holding 100 A and
+/// 200 B registered at epoch 10. The pair blueprint is linked into the oracle package, so it
+/// is instantiated from the same package.
+struct TestOracle {
+    env: TestEnv,
+    account: ComponentAddress,
+    oracle: ComponentAddress,
+    pair: ComponentAddress,
+    res_a: ResourceAddress,
+}
+
+impl TestOracle {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 1);
+        env.set_epoch(10);
+        let account = env.accounts[0];
+
+        let res_a = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, account);
+        let res_b = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, account);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[0].clone())));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "TwapOracle",
+                "instantiate",
+                manifest_args!(owner_role.clone(), 2u64),
+            )
+            .call_function(
+                env.package_address,
+                "RadiswapPair",
+                "instantiate",
+                manifest_args!(res_a, res_b, dec!(0), owner_role),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        let mut oracle = Self {
+            oracle: commit.new_component_addresses()[0],
+            pair: commit.new_component_addresses()[1],
+            env,
+            account,
+            res_a,
+        };
+
+        let pair = oracle.pair;
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(account, res_a, dec!(100))
+            .withdraw_from_account(account, res_b, dec!(200))
+            .take_all_from_worktop(res_a, "bucket_a")
+            .take_all_from_worktop(res_b, "bucket_b")
+            .call_method_with_name_lookup(pair, "add_liquidity", |lookup| {
+                (lookup.bucket("bucket_a"), lookup.bucket("bucket_b"))
+            });
+        oracle.execute(builder).expect_commit_success();
+
+        let source_id: u64 = oracle
+            .call("register_source", manifest_args!(radiswap_source(pair)))
+            .expect_commit_success()
+            .output(0);
+        assert_eq!(source_id, 1);
+
+        oracle
+    }
+
+    /// Execute the manifest with the account key, then deposit the worktop into the account
+    fn execute(&mut self, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute(0, builder)
+    }
+
+    fn call(&mut self, method: &str, args: impl ResolvableArguments) -> TransactionReceipt {
+        let builder = ManifestBuilder::new().call_method(self.oracle, method, args);
+
+        self.execute(builder)
+    }
+
+    /// Swap 100 A for B, moving the pair price from 2 to 0.5
+    fn swap(&mut self) {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, self.res_a, dec!(100))
+            .take_all_from_worktop(self.res_a, "input")
+            .call_method_with_name_lookup(self.pair, "swap", |lookup| (lookup.bucket("input"),));
+
+        self.execute(builder).expect_commit_success();
+    }
+
+    fn get_twap(&mut self, window_epochs: u64) -> TransactionReceipt {
+        self.call("get_twap", manifest_args!(1u64, window_epochs))
+    }
+}
+
+/// Manifest encoding of `PriceSource::RadiswapPair`
+fn radiswap_source(pair: ComponentAddress) -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 0,
+        fields: vec![to_manifest_value_and_unwrap!(&pair)],
+    }
+}
+
+#[test]
+fn test_twap_weights_the_prices_by_epoch() {
+    let mut oracle = TestOracle::new();
+
+    oracle.env.set_epoch(12);
+    oracle.swap();
+    oracle
+        .call("checkpoint", manifest_args!(1u64))
+        .expect_commit_success();
+
+    oracle.env.set_epoch(14);
+
+    // Price of 2 from epoch 10 to 12, then 0.5 from epoch 12 to 14
+    let twap: Decimal = oracle.get_twap(4).expect_commit_success().output(0);
+    assert_eq!(twap, dec!("1.25"));
+
+    let twap: Decimal = oracle.get_twap(2).expect_commit_success().output(0);
+    assert_eq!(twap, dec!("0.5"));
+
+    let twap: Decimal = oracle.get_twap(3).expect_commit_success().output(0);
+    assert_eq!(twap, dec!(1));
+
+    oracle.get_twap(5).expect_commit_failure();
+    oracle.get_twap(0).expect_commit_failure();
+
+    let observations: Vec<PriceObservation> = oracle
+        .call("get_observations", manifest_args!(1u64))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(observations.len(), 2);
+    assert_eq!(observations[1].price, dec!("0.5"));
+    assert_eq!(observations[1].cumulative_price, pdec!(4));
+}
+
+#[test]
+fn test_checkpoint_respects_the_interval() {
+    let mut oracle = TestOracle::new();
+
+    oracle.env.set_epoch(11);
+    oracle
+        .call("checkpoint", manifest_args!(1u64))
+        .expect_commit_failure();
+    oracle
+        .call("checkpoint", manifest_args!(2u64))
+        .expect_commit_failure();
+
+    oracle.env.set_epoch(12);
+    oracle
+        .call("checkpoint", manifest_args!(1u64))
+        .expect_commit_success();
+    oracle
+        .call("checkpoint", manifest_args!(1u64))
+        .expect_commit_failure();
+
+    oracle
+        .call("set_min_checkpoint_interval", manifest_args!(0u64))
+        .expect_commit_failure();
+    oracle
+        .call("set_min_checkpoint_interval", manifest_args!(1u64))
+        .expect_commit_success();
+
+    oracle.env.set_epoch(13);
+    oracle
+        .call("checkpoint", manifest_args!(1u64))
+        .expect_commit_success();
+}
+
+#[test]
+fn test_register_source_requires_the_owner() {
+    let mut oracle = TestOracle::new();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            oracle.oracle,
+            "register_source",
+            manifest_args!(radiswap_source(oracle.pair)),
+        )
+        .build();
+    oracle
+        .env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![])
+        .expect_commit_failure();
+}
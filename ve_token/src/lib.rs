@@ -0,0 +1,341 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::{assert_fungible_res_address, Checkpoints};
+use scrypto::prelude::*;
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct LockPosition {
+    #[mutable]
+    pub locked_amount: Decimal,
+
+    /// Epoch from which the locked tokens can be withdrawn
+    #[mutable]
+    pub unlock_epoch: u64,
+}
+
+/// History of a lock position. The voting power at an epoch is computed from the locked amount
+/// and the locked amount times the unlock epoch, recorded without rounding.
+#[derive(ScryptoSbor)]
+pub struct LockHistory {
+    /// Checkpoints of the locked amount
+    pub locked_amounts: Checkpoints,
+
+    /// Checkpoints of the locked amount times the unlock epoch
+    pub weighted_unlock_epochs: Checkpoints,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct LockUpdatedEvent {
+    pub position_id: NonFungibleLocalId,
+    pub locked_amount: Decimal,
+    pub unlock_epoch: u64,
+}
+
+#[blueprint]
+#[events(LockUpdatedEvent)]
+pub mod ve_token {
+
+    enable_method_auth! {
+        methods {
+
+            create_lock => PUBLIC;
+            increase_amount => PUBLIC;
+            extend_lock => PUBLIC;
+            withdraw => PUBLIC;
+
+            get_voting_power => PUBLIC;
+            get_voting_power_at => PUBLIC;
+            get_lock => PUBLIC;
+            get_max_lock_epochs => PUBLIC;
+
+        }
+    }
+
+    pub struct VeToken {
+        locked: Vault,
+
+        /// Lock position non-fungible resource manager
+        position_res_manager: ResourceManager,
+
+        /// History of each lock position, kept after withdrawal
+        lock_histories: KeyValueStore<NonFungibleLocalId, LockHistory>,
+
+        /// Lock duration giving a voting power equal to the locked amount
+        max_lock_epochs: u64,
+    }
+
+    impl VeToken {
+        pub fn instantiate(
+            locked_res_address: ResourceAddress,
+            max_lock_epochs: u64,
+            owner_role: OwnerRole,
+        ) -> (Global<VeToken>, ResourceAddress) {
+            /* CHECK INPUT */
+            assert_fungible_res_address(
+                locked_res_address,
+                Some("Locked resource must be fungible".to_string()),
+            );
+            assert!(
+                max_lock_epochs > 0,
+                "Maximum lock duration must be greater than zero"
+            );
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(VeToken::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let position_res_manager =
+                ResourceBuilder::new_ruid_non_fungible::<LockPosition>(owner_role.clone())
+                    .mint_roles(mint_roles! {
+                        minter => component_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .burn_roles(burn_roles! {
+                        burner => component_rule.clone();
+                        burner_updater => rule!(deny_all);
+                    })
+                    .non_fungible_data_update_roles(non_fungible_data_update_roles! {
+                        non_fungible_data_updater => component_rule;
+                        non_fungible_data_updater_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply();
+
+            let ve_token_component = Self {
+                locked: Vault::new(locked_res_address),
+                position_res_manager,
+                lock_histories: KeyValueStore::new(),
+                max_lock_epochs,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (ve_token_component, position_res_manager.address())
+        }
+
+        /* LOCK METHODS */
+
+        /// Lock tokens for the given number of epochs. Returns the lock position NFT.
+        pub fn create_lock(&mut self, tokens: Bucket, duration_epochs: u64) -> Bucket {
+            let locked_amount = tokens.amount();
+            let unlock_epoch = Runtime::current_epoch().number() + duration_epochs;
+
+            /* CHECK INPUT */
+            self._assert_valid_tokens(&tokens);
+            self._assert_valid_unlock_epoch(unlock_epoch);
+
+            self.locked.put(tokens);
+
+            let position = self
+                .position_res_manager
+                .mint_ruid_non_fungible(LockPosition {
+                    locked_amount,
+                    unlock_epoch,
+                });
+
+            let position_id = position.as_non_fungible().non_fungible_local_id();
+
+            self.lock_histories.insert(
+                position_id.clone(),
+                LockHistory {
+                    locked_amounts: Checkpoints::new(),
+                    weighted_unlock_epochs: Checkpoints::new(),
+                },
+            );
+            self._record_lock(position_id, locked_amount, unlock_epoch);
+
+            position
+        }
+
+        /// Add tokens to an unexpired lock, keeping its unlock epoch
+        pub fn increase_amount(&mut self, position_proof: Proof, tokens: Bucket) {
+            let position_id = self._check_position_proof(position_proof);
+            let position: LockPosition = self
+                .position_res_manager
+                .get_non_fungible_data(&position_id);
+
+            /* CHECK INPUT */
+            self._assert_valid_tokens(&tokens);
+            assert!(
+                position.unlock_epoch > Runtime::current_epoch().number(),
+                "Lock has expired"
+            );
+
+            let locked_amount = position.locked_amount + tokens.amount();
+            self.locked.put(tokens);
+
+            self._update_position(position_id, locked_amount, position.unlock_epoch);
+        }
+
+        /// Push back the unlock epoch of a lock
+        pub fn extend_lock(&mut self, position_proof: Proof, unlock_epoch: u64) {
+            let position_id = self._check_position_proof(position_proof);
+            let position: LockPosition = self
+                .position_res_manager
+                .get_non_fungible_data(&position_id);
+
+            /* CHECK INPUT */
+            assert!(
+                unlock_epoch > position.unlock_epoch,
+                "Unlock epoch must be later than the current unlock epoch"
+            );
+            self._assert_valid_unlock_epoch(unlock_epoch);
+
+            self._update_position(position_id, position.locked_amount, unlock_epoch);
+        }
+
+        /// Withdraw the locked tokens after the unlock epoch, burning the lock position
+        pub fn withdraw(&mut self, position: Bucket) -> Bucket {
+            /* CHECK INPUT */
+            assert!(
+                position.resource_address() == self.position_res_manager.address(),
+                "Lock position resource address mismatch"
+            );
+
+            let data: LockPosition = position.as_non_fungible().non_fungible().data();
+
+            assert!(
+                Runtime::current_epoch().number() >= data.unlock_epoch,
+                "Lock has not expired yet"
+            );
+
+            position.burn();
+
+            self.locked.take(data.locked_amount)
+        }
+
+        /* GETTERS */
+
+        /// Current voting power of a lock position
+        pub fn get_voting_power(&self, position_id: NonFungibleLocalId) -> Decimal {
+            self.get_voting_power_at(position_id, Runtime::current_epoch().number())
+        }
+
+        /// Voting power of a lock position at the given epoch, from the lock as it was at the end
+        /// of that epoch. It decreases linearly from the locked amount times the remaining share
+        /// of the maximum lock duration to zero at the unlock epoch, and is zero before the lock
+        /// was created. Future epochs use the current lock.
+        pub fn get_voting_power_at(&self, position_id: NonFungibleLocalId, epoch: u64) -> Decimal {
+            let history = self
+                .lock_histories
+                .get(&position_id)
+                .expect("Lock position not found");
+
+            let locked_amount = history.locked_amounts.value_before(epoch + 1);
+            let remaining_amount =
+                history.weighted_unlock_epochs.value_before(epoch + 1) - locked_amount * epoch;
+
+            if remaining_amount <= Decimal::ZERO {
+                return Decimal::ZERO;
+            }
+
+            remaining_amount / self.max_lock_epochs
+        }
+
+        pub fn get_lock(&self, position_id: NonFungibleLocalId) -> LockPosition {
+            self.position_res_manager
+                .get_non_fungible_data(&position_id)
+        }
+
+        pub fn get_max_lock_epochs(&self) -> u64 {
+            self.max_lock_epochs
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_tokens(&self, tokens: &Bucket) {
+            assert!(
+                tokens.resource_address() == self.locked.resource_address(),
+                "Locked resource address mismatch"
+            );
+            assert!(
+                tokens.amount() > Decimal::ZERO,
+                "Amount must be greater than zero!"
+            );
+        }
+
+        fn _assert_valid_unlock_epoch(&self, unlock_epoch: u64) {
+            let current_epoch = Runtime::current_epoch().number();
+
+            assert!(
+                unlock_epoch > current_epoch
+                    && unlock_epoch <= current_epoch + self.max_lock_epochs,
+                "Lock duration must be between 1 and {} epochs",
+                self.max_lock_epochs
+            );
+        }
+
+        fn _update_position(
+            &mut self,
+            position_id: NonFungibleLocalId,
+            locked_amount: Decimal,
+            unlock_epoch: u64,
+        ) {
+            self.position_res_manager.update_non_fungible_data(
+                &position_id,
+                "locked_amount",
+                locked_amount,
+            );
+            self.position_res_manager.update_non_fungible_data(
+                &position_id,
+                "unlock_epoch",
+                unlock_epoch,
+            );
+
+            self._record_lock(position_id, locked_amount, unlock_epoch);
+        }
+
+        fn _record_lock(
+            &mut self,
+            position_id: NonFungibleLocalId,
+            locked_amount: Decimal,
+            unlock_epoch: u64,
+        ) {
+            let current_epoch = Runtime::current_epoch().number();
+
+            {
+                let mut history = self.lock_histories.get_mut(&position_id).unwrap();
+                history.locked_amounts.push(current_epoch, locked_amount);
+                history
+                    .weighted_unlock_epochs
+                    .push(current_epoch, locked_amount * unlock_epoch);
+            }
+
+            Runtime::emit_event(LockUpdatedEvent {
+                position_id,
+                locked_amount,
+                unlock_epoch,
+            });
+        }
+
+        fn _check_position_proof(&self, position_proof: Proof) -> NonFungibleLocalId {
+            position_proof
+                .check(self.position_res_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+use ve_token::LockPosition;
+
+/// VeToken instantiated at epoch 10 with a maximum lock duration of 100 epochs, for an account
+/// holding 1000 tokens
+struct TestVeToken {
+    env: TestEnv,
+    account: ComponentAddress,
+    ve_token: ComponentAddress,
+    position: ResourceAddress,
+    token: ResourceAddress,
+}
+
+impl TestVeToken {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 1);
+        let account = env.accounts[0];
+        let token = env
+            .test_runner
+            .create_fungible_resource(dec!(1000), 18, account);
+        env.set_epoch(10);
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "VeToken",
+                "instantiate",
+                manifest_args!(token, 100u64, OwnerRole::None),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            ve_token: commit.new_component_addresses()[0],
+            position: commit.new_resource_addresses()[0],
+            env,
+            account,
+            token,
+        }
+    }
+
+    /// Execute the manifest with the account key, then deposit the worktop into the account
+    fn execute(&mut self, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute(0, builder)
+    }
+
+    fn create_lock(&mut self, amount: Decimal, duration_epochs: u64) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, self.token, amount)
+            .take_all_from_worktop(self.token, "tokens")
+            .call_method_with_name_lookup(self.ve_token, "create_lock", |lookup| {
+                (lookup.bucket("tokens"), duration_epochs)
+            });
+
+        self.execute(builder)
+    }
+
+    /// Proof of the single lock position held by the account, popped as "position"
+    fn with_position_proof(&self) -> ManifestBuilder {
+        ManifestBuilder::new()
+            .create_proof_from_account_of_amount(self.account, self.position, dec!(1))
+            .pop_from_auth_zone("position")
+    }
+
+    fn increase_amount(&mut self, amount: Decimal) -> TransactionReceipt {
+        let builder = self
+            .with_position_proof()
+            .withdraw_from_account(self.account, self.token, amount)
+            .take_all_from_worktop(self.token, "tokens")
+            .call_method_with_name_lookup(self.ve_token, "increase_amount", |lookup| {
+                (lookup.proof("position"), lookup.bucket("tokens"))
+            });
+
+        self.execute(builder)
+    }
+
+    fn extend_lock(&mut self, unlock_epoch: u64) -> TransactionReceipt {
+        let builder = self.with_position_proof().call_method_with_name_lookup(
+            self.ve_token,
+            "extend_lock",
+            |lookup| (lookup.proof("position"), unlock_epoch),
+        );
+
+        self.execute(builder)
+    }
+
+    fn withdraw(&mut self) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, self.position, dec!(1))
+            .take_all_from_worktop(self.position, "position")
+            .call_method_with_name_lookup(self.ve_token, "withdraw", |lookup| {
+                (lookup.bucket("position"),)
+            });
+
+        self.execute(builder)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str, args: impl ResolvableArguments) -> T {
+        let builder = ManifestBuilder::new().call_method(self.ve_token, method, args);
+
+        self.execute(builder).expect_commit_success().output(0)
+    }
+
+    /// Id of the single lock position of the account
+    fn position_id(&mut self) -> NonFungibleLocalId {
+        let vault_id = self
+            .env
+            .test_runner
+            .get_component_vaults(self.account, self.position)[0];
+        let (_, mut ids) = self
+            .env
+            .test_runner
+            .inspect_non_fungible_vault(vault_id)
+            .unwrap();
+
+        ids.next().unwrap()
+    }
+
+    fn voting_power(&mut self) -> Decimal {
+        let position_id = self.position_id();
+
+        self.get("get_voting_power", manifest_args!(position_id))
+    }
+
+    fn voting_power_at(&mut self, epoch: u64) -> Decimal {
+        let position_id = self.position_id();
+
+        self.get("get_voting_power_at", manifest_args!(position_id, epoch))
+    }
+
+    fn lock(&mut self) -> LockPosition {
+        let position_id = self.position_id();
+
+        self.get("get_lock", manifest_args!(position_id))
+    }
+
+    fn balance(&mut self, res_address: ResourceAddress) -> Decimal {
+        self.env.balance(0, res_address)
+    }
+}
+
+#[test]
+fn test_voting_power_decreases_linearly() {
+    let mut ve_token = TestVeToken::new();
+
+    ve_token.create_lock(dec!(100), 50).expect_commit_success();
+    assert_eq!(ve_token.voting_power(), dec!(50));
+
+    assert_eq!(ve_token.voting_power_at(35), dec!(25));
+
+    ve_token.env.set_epoch(60);
+    assert_eq!(ve_token.voting_power(), dec!(0));
+}
+
+#[test]
+fn test_voting_power_at_past_epochs_is_not_changed_by_lock_updates() {
+    let mut ve_token = TestVeToken::new();
+
+    ve_token.create_lock(dec!(100), 50).expect_commit_success();
+
+    ve_token.env.set_epoch(20);
+    ve_token.increase_amount(dec!(100)).expect_commit_success();
+
+    ve_token.env.set_epoch(30);
+    ve_token.extend_lock(130).expect_commit_success();
+
+    // No voting power before the lock was created
+    assert_eq!(ve_token.voting_power_at(9), dec!(0));
+
+    // 100 tokens locked until epoch 60, then 200 tokens from epoch 20
+    assert_eq!(ve_token.voting_power_at(10), dec!(50));
+    assert_eq!(ve_token.voting_power_at(15), dec!(45));
+    assert_eq!(ve_token.voting_power_at(20), dec!(80));
+    assert_eq!(ve_token.voting_power_at(29), dec!(62));
+
+    // 200 tokens locked until epoch 130 from epoch 30
+    assert_eq!(ve_token.voting_power_at(30), dec!(200));
+    assert_eq!(ve_token.voting_power(), dec!(200));
+    assert_eq!(ve_token.voting_power_at(80), dec!(100));
+    assert_eq!(ve_token.voting_power_at(130), dec!(0));
+}
+
+#[test]
+fn test_voting_power_is_kept_after_withdrawal() {
+    let mut ve_token = TestVeToken::new();
+
+    ve_token.create_lock(dec!(100), 50).expect_commit_success();
+    let position_id = ve_token.position_id();
+
+    ve_token.env.set_epoch(60);
+    ve_token.withdraw().expect_commit_success();
+
+    assert_eq!(
+        ve_token.get::<Decimal>("get_voting_power_at", manifest_args!(position_id, 35u64)),
+        dec!(25)
+    );
+    assert_eq!(
+        ve_token.get::<Decimal>("get_voting_power", manifest_args!(position_id)),
+        dec!(0)
+    );
+}
+
+#[test]
+fn test_lock_duration_is_bounded() {
+    let mut ve_token = TestVeToken::new();
+
+    ve_token.create_lock(dec!(100), 0).expect_commit_failure();
+    ve_token.create_lock(dec!(100), 101).expect_commit_failure();
+
+    // A maximum lock gives a voting power equal to the locked amount
+    ve_token.create_lock(dec!(100), 100).expect_commit_success();
+    assert_eq!(ve_token.voting_power(), dec!(100));
+}
+
+#[test]
+fn test_lock_of_another_resource_fails() {
+    let mut ve_token = TestVeToken::new();
+    let account = ve_token.account;
+    let other = ve_token
+        .env
+        .test_runner
+        .create_fungible_resource(dec!(100), 18, account);
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(account, other, dec!(100))
+        .take_all_from_worktop(other, "tokens")
+        .call_method_with_name_lookup(ve_token.ve_token, "create_lock", |lookup| {
+            (lookup.bucket("tokens"), 50u64)
+        });
+    ve_token.execute(builder).expect_commit_failure();
+}
+
+#[test]
+fn test_increase_amount_keeps_the_unlock_epoch() {
+    let mut ve_token = TestVeToken::new();
+
+    ve_token.create_lock(dec!(100), 50).expect_commit_success();
+    ve_token.increase_amount(dec!(100)).expect_commit_success();
+
+    let lock = ve_token.lock();
+    assert_eq!(lock.locked_amount, dec!(200));
+    assert_eq!(lock.unlock_epoch, 60);
+    assert_eq!(ve_token.voting_power(), dec!(100));
+
+    ve_token.env.set_epoch(60);
+    ve_token.increase_amount(dec!(100)).expect_commit_failure();
+}
+
+#[test]
+fn test_extend_lock() {
+    let mut ve_token = TestVeToken::new();
+
+    ve_token.create_lock(dec!(100), 50).expect_commit_success();
+
+    ve_token.extend_lock(60).expect_commit_failure();
+    ve_token.extend_lock(111).expect_commit_failure();
+    ve_token.extend_lock(110).expect_commit_success();
+
+    assert_eq!(ve_token.lock().unlock_epoch, 110);
+    assert_eq!(ve_token.voting_power(), dec!(100));
+}
+
+#[test]
+fn test_withdraw_after_the_unlock_epoch() {
+    let mut ve_token = TestVeToken::new();
+    let (token, position) = (ve_token.token, ve_token.position);
+
+    ve_token.create_lock(dec!(100), 50).expect_commit_success();
+    assert_eq!(ve_token.balance(token), dec!(900));
+
+    ve_token.env.set_epoch(59);
+    ve_token.withdraw().expect_commit_failure();
+
+    ve_token.env.set_epoch(60);
+    ve_token.withdraw().expect_commit_success();
+
+    assert_eq!(ve_token.balance(token), dec!(1000));
+    assert_eq!(ve_token.balance(position), dec!(0));
+}
@@ -0,0 +1,518 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use blueprint_commons::{assert_fungible_res_address, ReentrancyGuard};
+use scrypto::prelude::*;
+
+pub use fixed_point_math::*;
+
+/// Maximum number of resources in a weighted pool
+pub const MAX_RESOURCES: usize = 8;
+
+/// Minimum weight of a resource
+pub const MIN_WEIGHT: Decimal = dec!("0.01");
+
+/// Pool units minted for the first deposit, per unit of the value function
+pub const INITIAL_POOL_UNIT_FACTOR: Decimal = dec!(100);
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SwapEvent {
+    pub input_res_address: ResourceAddress,
+    pub input_amount: Decimal,
+    pub output_res_address: ResourceAddress,
+    pub output_amount: Decimal,
+    pub fee_amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct JoinEvent {
+    pub amounts: IndexMap<ResourceAddress, Decimal>,
+    pub pool_unit_amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ExitEvent {
+    pub amounts: IndexMap<ResourceAddress, Decimal>,
+    pub pool_unit_amount: Decimal,
+}
+
+#[blueprint]
+#[events(SwapEvent, JoinEvent, ExitEvent)]
+pub mod weighted_pool {
+
+    enable_method_auth! {
+        methods {
+
+            set_swap_fee => restrict_to :[OWNER];
+
+            contribute => PUBLIC;
+            contribute_single => PUBLIC;
+            redeem => PUBLIC;
+            swap => PUBLIC;
+
+            get_reserves => PUBLIC;
+            get_weights => PUBLIC;
+            get_spot_price => PUBLIC;
+            get_output_amount => PUBLIC;
+            get_pool_unit_supply => PUBLIC;
+
+        }
+    }
+
+    pub struct WeightedPool {
+        /// Pooled resources, in the order given at instantiation
+        vaults: IndexMap<ResourceAddress, Vault>,
+
+        /// Normalized weight of each pooled resource. Weights sum to one.
+        weights: IndexMap<ResourceAddress, Decimal>,
+
+        /// Pool unit resource manager
+        pool_unit_res_manager: ResourceManager,
+
+        /// Share of the input amount kept in the pool on each swap
+        swap_fee: Decimal,
+
+        /// Rejects nested calls into the state changing user methods
+        reentrancy_guard: ReentrancyGuard,
+    }
+
+    impl WeightedPool {
+        pub fn instantiate(
+            weights: IndexMap<ResourceAddress, Decimal>,
+            swap_fee: Decimal,
+            owner_role: OwnerRole,
+        ) -> (Global<WeightedPool>, ResourceAddress) {
+            /* CHECK INPUT */
+            assert!(
+                weights.len() >= 2 && weights.len() <= MAX_RESOURCES,
+                "Weighted pool must have between 2 and {} resources",
+                MAX_RESOURCES
+            );
+
+            let mut total_weight = Decimal::ZERO;
+            for (res_address, weight) in weights.iter() {
+                assert_fungible_res_address(*res_address, None);
+                assert!(
+                    *weight >= MIN_WEIGHT,
+                    "Weights must be at least {}",
+                    MIN_WEIGHT
+                );
+                total_weight += *weight;
+            }
+            assert!(total_weight == Decimal::ONE, "Weights must sum to one");
+
+            WeightedPool::_assert_valid_swap_fee(swap_fee);
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(WeightedPool::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let pool_unit_res_manager = ResourceBuilder::new_fungible(owner_role.clone())
+                .mint_roles(mint_roles! {
+                    minter => component_rule.clone();
+                    minter_updater => rule!(deny_all);
+                })
+                .burn_roles(burn_roles! {
+                    burner => component_rule;
+                    burner_updater => rule!(deny_all);
+                })
+                .create_with_no_initial_supply();
+
+            let vaults = weights
+                .keys()
+                .map(|res_address| (*res_address, Vault::new(*res_address)))
+                .collect();
+
+            let pool_component = Self {
+                vaults,
+                weights,
+                pool_unit_res_manager,
+                swap_fee,
+                reentrancy_guard: ReentrancyGuard::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .with_address(address_reservation)
+            .globalize();
+
+            (pool_component, pool_unit_res_manager.address())
+        }
+
+        /* ADMIN METHODS */
+
+        pub fn set_swap_fee(&mut self, swap_fee: Decimal) {
+            WeightedPool::_assert_valid_swap_fee(swap_fee);
+
+            self.swap_fee = swap_fee;
+        }
+
+        /* LIQUIDITY METHODS */
+
+        /// Contribute every pooled resource, each in a single bucket, in proportion to the
+        /// current reserves. The first contribution sets the pool prices. Returns the pool
+        /// units and the unused assets.
+        pub fn contribute(&mut self, mut buckets: Vec<Bucket>) -> (Bucket, Vec<Bucket>) {
+            self.reentrancy_guard.enter();
+
+            /* CHECK INPUT */
+            assert!(
+                buckets.len() == self.vaults.len(),
+                "A bucket of every pooled resource must be provided"
+            );
+            let mut res_addresses = IndexSet::new();
+            for bucket in buckets.iter() {
+                assert!(
+                    self.vaults.contains_key(&bucket.resource_address()),
+                    "Resource does not belong to the pool"
+                );
+                assert!(
+                    res_addresses.insert(bucket.resource_address()),
+                    "Each pooled resource must be provided exactly once"
+                );
+                assert!(
+                    bucket.amount() > Decimal::ZERO,
+                    "Amounts must be greater than zero!"
+                );
+            }
+
+            let pool_unit_supply = self.get_pool_unit_supply();
+
+            let (share, pool_unit_amount) = if pool_unit_supply == Decimal::ZERO {
+                let amounts: IndexMap<ResourceAddress, Decimal> = buckets
+                    .iter()
+                    .map(|bucket| (bucket.resource_address(), bucket.amount()))
+                    .collect();
+
+                let invariant = self._compute_invariant(&amounts);
+
+                (
+                    None,
+                    WeightedPool::_to_decimal(invariant * INITIAL_POOL_UNIT_FACTOR),
+                )
+            } else {
+                let share = buckets
+                    .iter()
+                    .map(|bucket| {
+                        bucket.amount()
+                            / self
+                                .vaults
+                                .get(&bucket.resource_address())
+                                .unwrap()
+                                .amount()
+                    })
+                    .min()
+                    .unwrap();
+
+                (Some(share), pool_unit_supply * share)
+            };
+
+            assert!(
+                pool_unit_amount > Decimal::ZERO,
+                "Pool unit amount is too small"
+            );
+
+            let mut amounts: IndexMap<ResourceAddress, Decimal> = IndexMap::default();
+
+            for bucket in buckets.iter_mut() {
+                let res_address = bucket.resource_address();
+                let vault = self.vaults.get_mut(&res_address).unwrap();
+
+                let amount = match share {
+                    Some(share) => vault.amount() * share,
+                    None => bucket.amount(),
+                };
+
+                vault.put(
+                    bucket.take_advanced(amount, WithdrawStrategy::Rounded(RoundingMode::ToZero)),
+                );
+
+                amounts.insert(res_address, amount);
+            }
+
+            Runtime::emit_event(JoinEvent {
+                amounts,
+                pool_unit_amount,
+            });
+
+            self.reentrancy_guard.exit();
+
+            (self.pool_unit_res_manager.mint(pool_unit_amount), buckets)
+        }
+
+        /// Contribute a single pooled resource. The part of the deposit that would have to be
+        /// swapped to keep the pool composition pays the swap fee.
+        pub fn contribute_single(&mut self, assets: Bucket) -> Bucket {
+            self.reentrancy_guard.enter();
+
+            let res_address = assets.resource_address();
+            let amount = assets.amount();
+
+            /* CHECK INPUT */
+            assert!(amount > Decimal::ZERO, "Amount must be greater than zero!");
+
+            let pool_unit_supply = self.get_pool_unit_supply();
+            assert!(
+                pool_unit_supply > Decimal::ZERO,
+                "Pool must be initialized with a contribution of every resource"
+            );
+
+            let weight = *self
+                .weights
+                .get(&res_address)
+                .expect("Resource does not belong to the pool");
+            let vault = self.vaults.get_mut(&res_address).unwrap();
+
+            let amount_after_fee = PreciseDecimal::from(amount)
+                * (PreciseDecimal::ONE
+                    - (PreciseDecimal::ONE - weight) * PreciseDecimal::from(self.swap_fee));
+
+            let growth = pow(
+                PreciseDecimal::ONE + amount_after_fee / PreciseDecimal::from(vault.amount()),
+                PreciseDecimal::from(weight),
+            );
+
+            let pool_unit_amount = WeightedPool::_to_decimal(
+                PreciseDecimal::from(pool_unit_supply) * (growth - PreciseDecimal::ONE),
+            );
+
+            assert!(
+                pool_unit_amount > Decimal::ZERO,
+                "Pool unit amount is too small"
+            );
+
+            vault.put(assets);
+
+            Runtime::emit_event(JoinEvent {
+                amounts: indexmap!(res_address => amount),
+                pool_unit_amount,
+            });
+
+            self.reentrancy_guard.exit();
+
+            self.pool_unit_res_manager.mint(pool_unit_amount)
+        }
+
+        /// Redeem pool units for every pooled resource, in proportion to the current reserves
+        pub fn redeem(&mut self, pool_units: Bucket) -> Vec<Bucket> {
+            self.reentrancy_guard.enter();
+
+            /* CHECK INPUT */
+            assert!(
+                pool_units.resource_address() == self.pool_unit_res_manager.address(),
+                "Pool unit resource address mismatch"
+            );
+
+            let pool_unit_amount = pool_units.amount();
+            let share = pool_unit_amount / self.get_pool_unit_supply();
+
+            pool_units.burn();
+
+            let mut amounts: IndexMap<ResourceAddress, Decimal> = IndexMap::default();
+
+            let buckets: Vec<Bucket> = self
+                .vaults
+                .iter_mut()
+                .map(|(res_address, vault)| {
+                    let bucket = vault.take_advanced(
+                        vault.amount() * share,
+                        WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                    );
+
+                    amounts.insert(*res_address, bucket.amount());
+
+                    bucket
+                })
+                .collect();
+
+            Runtime::emit_event(ExitEvent {
+                amounts,
+                pool_unit_amount,
+            });
+
+            self.reentrancy_guard.exit();
+
+            buckets
+        }
+
+        /* SWAP METHODS */
+
+        pub fn swap(&mut self, input: Bucket, output_res_address: ResourceAddress) -> Bucket {
+            self.reentrancy_guard.enter();
+
+            let input_res_address = input.resource_address();
+            let input_amount = input.amount();
+
+            let (output_amount, fee_amount) =
+                self._get_output_amount(input_res_address, input_amount, output_res_address);
+
+            assert!(output_amount > Decimal::ZERO, "Output amount is too small");
+
+            self.vaults.get_mut(&input_res_address).unwrap().put(input);
+
+            let output = self
+                .vaults
+                .get_mut(&output_res_address)
+                .unwrap()
+                .take_advanced(
+                    output_amount,
+                    WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                );
+
+            Runtime::emit_event(SwapEvent {
+                input_res_address,
+                input_amount,
+                output_res_address,
+                output_amount: output.amount(),
+                fee_amount,
+            });
+
+            self.reentrancy_guard.exit();
+
+            output
+        }
+
+        /* GETTERS */
+
+        pub fn get_reserves(&self) -> IndexMap<ResourceAddress, Decimal> {
+            self.vaults
+                .iter()
+                .map(|(res_address, vault)| (*res_address, vault.amount()))
+                .collect()
+        }
+
+        pub fn get_weights(&self) -> IndexMap<ResourceAddress, Decimal> {
+            self.weights.clone()
+        }
+
+        /// Price of the input resource in units of the output resource, without fee
+        pub fn get_spot_price(
+            &self,
+            input_res_address: ResourceAddress,
+            output_res_address: ResourceAddress,
+        ) -> Decimal {
+            let (input_reserve, input_weight) = self._get_reserve_and_weight(&input_res_address);
+            let (output_reserve, output_weight) = self._get_reserve_and_weight(&output_res_address);
+
+            (output_reserve / output_weight) / (input_reserve / input_weight)
+        }
+
+        pub fn get_output_amount(
+            &self,
+            input_res_address: ResourceAddress,
+            input_amount: Decimal,
+            output_res_address: ResourceAddress,
+        ) -> Decimal {
+            self._get_output_amount(input_res_address, input_amount, output_res_address)
+                .0
+        }
+
+        pub fn get_pool_unit_supply(&self) -> Decimal {
+            self.pool_unit_res_manager
+                .total_supply()
+                .unwrap_or(Decimal::ZERO)
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _assert_valid_swap_fee(swap_fee: Decimal) {
+            assert!(
+                swap_fee >= Decimal::ZERO && swap_fee < Decimal::ONE,
+                "Swap fee must be between 0 and 1"
+            );
+        }
+
+        /// Output amount and fee amount of a swap, keeping the value function
+        /// `prod(reserve ^ weight)` constant on the input amount after fee
+        fn _get_output_amount(
+            &self,
+            input_res_address: ResourceAddress,
+            input_amount: Decimal,
+            output_res_address: ResourceAddress,
+        ) -> (Decimal, Decimal) {
+            /* CHECK INPUT */
+            assert!(
+                input_amount > Decimal::ZERO,
+                "Amount must be greater than zero!"
+            );
+            assert!(
+                input_res_address != output_res_address,
+                "Input and output resources must be different"
+            );
+
+            let (input_reserve, input_weight) = self._get_reserve_and_weight(&input_res_address);
+            let (output_reserve, output_weight) = self._get_reserve_and_weight(&output_res_address);
+
+            assert!(
+                input_reserve > Decimal::ZERO && output_reserve > Decimal::ZERO,
+                "Pool has no liquidity"
+            );
+
+            let fee_amount = input_amount * self.swap_fee;
+            let input_amount_after_fee = PreciseDecimal::from(input_amount - fee_amount);
+
+            let reserve_ratio = PreciseDecimal::from(input_reserve)
+                / (PreciseDecimal::from(input_reserve) + input_amount_after_fee);
+
+            let output_amount = PreciseDecimal::from(output_reserve)
+                * (PreciseDecimal::ONE
+                    - pow(
+                        reserve_ratio,
+                        PreciseDecimal::from(input_weight) / PreciseDecimal::from(output_weight),
+                    ));
+
+            (WeightedPool::_to_decimal(output_amount), fee_amount)
+        }
+
+        /// Value function `prod(reserve ^ weight)` of the given reserves
+        fn _compute_invariant(
+            &self,
+            reserves: &IndexMap<ResourceAddress, Decimal>,
+        ) -> PreciseDecimal {
+            reserves
+                .iter()
+                .fold(PreciseDecimal::ONE, |invariant, (res_address, reserve)| {
+                    invariant
+                        * pow(
+                            PreciseDecimal::from(*reserve),
+                            PreciseDecimal::from(*self.weights.get(res_address).unwrap()),
+                        )
+                })
+        }
+
+        fn _get_reserve_and_weight(&self, res_address: &ResourceAddress) -> (Decimal, Decimal) {
+            let weight = *self
+                .weights
+                .get(res_address)
+                .expect("Resource does not belong to the pool");
+
+            (self.vaults.get(res_address).unwrap().amount(), weight)
+        }
+
+        fn _to_decimal(value: PreciseDecimal) -> Decimal {
+            value
+                .checked_truncate(RoundingMode::ToZero)
+                .expect("Weighted pool math overflow")
+        }
+    }
+}
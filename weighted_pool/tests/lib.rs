@@ -0,0 +1,333 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+use weighted_pool::pow;
+
+fn assert_close(value: Decimal, expected: Decimal, tolerance: Decimal) {
+    let error = if value > expected {
+        value - expected
+    } else {
+        expected - value
+    };
+
+    assert!(
+        error <= tolerance,
+        "Expected {} but got {} (error: {})",
+        expected,
+        value,
+        error
+    );
+}
+
+/// 80/20 pool of two resources with a swap fee of 1%, owned by the account holding 10000 of
+/// each resource
+struct TestPool {
+    env: TestEnv,
+    account: ComponentAddress,
+    pool: ComponentAddress,
+    pool_unit: ResourceAddress,
+    res_a: ResourceAddress,
+    res_b: ResourceAddress,
+}
+
+impl TestPool {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 1);
+        let account = env.accounts[0];
+        let res_a = env
+            .test_runner
+            .create_fungible_resource(dec!(10000), 18, account);
+        let res_b = env
+            .test_runner
+            .create_fungible_resource(dec!(10000), 18, account);
+        let owner_badge = env.badges[0].clone();
+
+        let weights: IndexMap<ResourceAddress, Decimal> =
+            indexmap!(res_a => dec!("0.8"), res_b => dec!("0.2"));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "WeightedPool",
+                "instantiate",
+                manifest_args!(
+                    weights,
+                    dec!("0.01"),
+                    OwnerRole::Fixed(rule!(require(owner_badge)))
+                ),
+            )
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, vec![]);
+        let commit = receipt.expect_commit_success();
+
+        Self {
+            pool: commit.new_component_addresses()[0],
+            pool_unit: commit.new_resource_addresses()[0],
+            env,
+            account,
+            res_a,
+            res_b,
+        }
+    }
+
+    /// Pool initialized with 800 of the first resource and 200 of the second, so both
+    /// resources have the same price
+    fn initialized() -> Self {
+        let mut pool = Self::new();
+
+        let (res_a, res_b) = (pool.res_a, pool.res_b);
+        pool.contribute(vec![(res_a, dec!(800)), (res_b, dec!(200))])
+            .expect_commit_success();
+
+        pool
+    }
+
+    /// Execute the manifest with the account key, then deposit the worktop into the account
+    fn execute(&mut self, builder: ManifestBuilder) -> TransactionReceipt {
+        self.env.execute(0, builder)
+    }
+
+    /// Contribute one bucket per given resource and amount
+    fn contribute(&mut self, amounts: Vec<(ResourceAddress, Decimal)>) -> TransactionReceipt {
+        let mut builder = ManifestBuilder::new();
+        let mut names = vec![];
+
+        for (index, (res_address, amount)) in amounts.into_iter().enumerate() {
+            let name = format!("bucket_{}", index);
+            builder = builder
+                .withdraw_from_account(self.account, res_address, amount)
+                .take_from_worktop(res_address, amount, name.as_str());
+            names.push(name);
+        }
+
+        let builder = builder.call_method_with_name_lookup(self.pool, "contribute", |lookup| {
+            (names
+                .iter()
+                .map(|name| lookup.bucket(name.as_str()))
+                .collect::<Vec<_>>(),)
+        });
+
+        self.execute(builder)
+    }
+
+    fn contribute_single(
+        &mut self,
+        res_address: ResourceAddress,
+        amount: Decimal,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, res_address, amount)
+            .take_all_from_worktop(res_address, "assets")
+            .call_method_with_name_lookup(self.pool, "contribute_single", |lookup| {
+                (lookup.bucket("assets"),)
+            });
+
+        self.execute(builder)
+    }
+
+    fn redeem(&mut self, amount: Decimal) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, self.pool_unit, amount)
+            .take_all_from_worktop(self.pool_unit, "pool_units")
+            .call_method_with_name_lookup(self.pool, "redeem", |lookup| {
+                (lookup.bucket("pool_units"),)
+            });
+
+        self.execute(builder)
+    }
+
+    fn swap(
+        &mut self,
+        input_res_address: ResourceAddress,
+        input_amount: Decimal,
+        output_res_address: ResourceAddress,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.account, input_res_address, input_amount)
+            .take_all_from_worktop(input_res_address, "input")
+            .call_method_with_name_lookup(self.pool, "swap", |lookup| {
+                (lookup.bucket("input"), output_res_address)
+            });
+
+        self.execute(builder)
+    }
+
+    fn get<T: ScryptoDecode>(&mut self, method: &str, args: impl ResolvableArguments) -> T {
+        let builder = ManifestBuilder::new().call_method(self.pool, method, args);
+
+        self.execute(builder).expect_commit_success().output(0)
+    }
+
+    fn balance(&mut self, res_address: ResourceAddress) -> Decimal {
+        self.env.balance(0, res_address)
+    }
+}
+
+#[test]
+fn test_value_function_math() {
+    // 800 ^ 0.8 * 200 ^ 0.2
+    let invariant = pow(pdec!(800), pdec!("0.8")) * pow(pdec!(200), pdec!("0.2"));
+
+    assert_close(
+        invariant.checked_truncate(RoundingMode::ToZero).unwrap(),
+        dec!("606.286626604159232938"),
+        dec!("0.000000000001"),
+    );
+}
+
+#[test]
+fn test_first_contribution_mints_the_value_function() {
+    let mut pool = TestPool::initialized();
+
+    let pool_unit = pool.pool_unit;
+    assert_close(
+        pool.balance(pool_unit),
+        dec!("60628.662660415923293890"),
+        dec!("0.000000001"),
+    );
+    assert_eq!(
+        pool.get::<IndexMap<ResourceAddress, Decimal>>("get_reserves", manifest_args!()),
+        indexmap!(pool.res_a => dec!(800), pool.res_b => dec!(200))
+    );
+}
+
+#[test]
+fn test_contribute_returns_unused_assets() {
+    let mut pool = TestPool::initialized();
+    let (res_a, res_b, pool_unit) = (pool.res_a, pool.res_b, pool.pool_unit);
+    let supply = pool.get::<Decimal>("get_pool_unit_supply", manifest_args!());
+
+    pool.contribute(vec![(res_b, dec!(40)), (res_a, dec!(80))])
+        .expect_commit_success();
+
+    assert_eq!(pool.balance(res_a), dec!(9120));
+    assert_eq!(pool.balance(res_b), dec!(9780));
+    assert_eq!(pool.balance(pool_unit), supply + supply * dec!("0.1"));
+}
+
+#[test]
+fn test_contribute_with_duplicate_resources_fails() {
+    let mut pool = TestPool::initialized();
+    let res_a = pool.res_a;
+
+    pool.contribute(vec![(res_a, dec!(80)), (res_a, dec!(80))])
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_first_contribution_with_duplicate_resources_fails() {
+    let mut pool = TestPool::new();
+    let res_a = pool.res_a;
+
+    pool.contribute(vec![(res_a, dec!(800)), (res_a, dec!(200))])
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_contribute_without_every_resource_fails() {
+    let mut pool = TestPool::initialized();
+    let res_a = pool.res_a;
+
+    pool.contribute(vec![(res_a, dec!(80))])
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_contribute_single() {
+    let mut pool = TestPool::initialized();
+    let (res_a, pool_unit) = (pool.res_a, pool.pool_unit);
+    let supply = pool.balance(pool_unit);
+
+    pool.contribute_single(res_a, dec!(80))
+        .expect_commit_success();
+
+    // The fee applies to the 20% of the deposit that would be swapped:
+    // supply * ((1 + 80 * (1 - 0.2 * 0.01) / 800) ^ 0.8 - 1)
+    assert_close(
+        pool.balance(pool_unit) - supply,
+        dec!("4794.112280168887955878"),
+        dec!("0.000000001"),
+    );
+}
+
+#[test]
+fn test_contribute_single_before_initialization_fails() {
+    let mut pool = TestPool::new();
+    let res_a = pool.res_a;
+
+    pool.contribute_single(res_a, dec!(80))
+        .expect_commit_failure();
+}
+
+#[test]
+fn test_redeem() {
+    let mut pool = TestPool::initialized();
+    let (res_a, res_b, pool_unit) = (pool.res_a, pool.res_b, pool.pool_unit);
+    let supply = pool.balance(pool_unit);
+
+    pool.redeem(supply / 2).expect_commit_success();
+
+    let tolerance = dec!("0.000000000001");
+    assert_close(pool.balance(res_a), dec!(9600), tolerance);
+    assert_close(pool.balance(res_b), dec!(9900), tolerance);
+}
+
+#[test]
+fn test_swap() {
+    let mut pool = TestPool::initialized();
+    let (res_a, res_b) = (pool.res_a, pool.res_b);
+
+    let output_amount =
+        pool.get::<Decimal>("get_output_amount", manifest_args!(res_a, dec!(100), res_b));
+
+    // 200 * (1 - (800 / (800 + 99)) ^ (0.8 / 0.2))
+    assert_close(
+        output_amount,
+        dec!("74.584510747212765048"),
+        dec!("0.000000001"),
+    );
+
+    pool.swap(res_a, dec!(100), res_b).expect_commit_success();
+
+    assert_eq!(pool.balance(res_a), dec!(9100));
+    assert_eq!(pool.balance(res_b), dec!(9800) + output_amount);
+}
+
+#[test]
+fn test_swap_to_the_heavier_resource() {
+    let mut pool = TestPool::initialized();
+    let (res_a, res_b) = (pool.res_a, pool.res_b);
+
+    pool.swap(res_b, dec!(10), res_a).expect_commit_success();
+
+    // 800 * (1 - (200 / (200 + 9.9)) ^ (0.2 / 0.8))
+    assert_close(
+        pool.balance(res_a) - dec!(9200),
+        dec!("9.604650569793591857"),
+        dec!("0.000000001"),
+    );
+}
+
+#[test]
+fn test_swap_to_the_input_resource_fails() {
+    let mut pool = TestPool::initialized();
+    let res_a = pool.res_a;
+
+    pool.swap(res_a, dec!(100), res_a).expect_commit_failure();
+}
+
+#[test]
+fn test_swap_of_foreign_resource_fails() {
+    let mut pool = TestPool::initialized();
+    let (account, res_b) = (pool.account, pool.res_b);
+    let foreign = pool
+        .env
+        .test_runner
+        .create_fungible_resource(dec!(100), 18, account);
+
+    pool.swap(foreign, dec!(100), res_b).expect_commit_failure();
+}
@@ -0,0 +1,370 @@
+//
+// MIT License
+//
+// Copyright (c) 2023 @WeftFinance
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use asset_pool_interface::SingleResourcePoolInterface;
+use native_pool_adapter::adapter::NativePoolAdapter;
+use scrypto::prelude::*;
+use single_asset_pool::pool::AssetPool;
+use single_asset_pool::{DepositType, WithdrawType};
+use staking_rewards::staking_rewards::StakingRewards;
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub enum Strategy {
+    /// Contribute to an AssetPool component, earning its yield through the pool unit ratio. The
+    /// pool admin rule must allow the vault component.
+    AssetPool(Global<AssetPool>),
+    /// Contribute to a NativePoolAdapter component, like an AssetPool
+    NativePoolAdapter(Global<NativePoolAdapter>),
+    /// Stake in a StakingRewards component, claiming and restaking its rewards
+    StakingRewards(Global<StakingRewards>),
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct HarvestEvent {
+    /// Yield compounded into the vault, negative if the strategy lost value
+    pub gain: Decimal,
+    pub share_ratio: PreciseDecimal,
+}
+
+#[blueprint]
+#[events(HarvestEvent)]
+pub mod yield_vault {
+
+    enable_method_auth! {
+        roles {
+            keeper => updatable_by: [OWNER];
+        },
+        methods {
+
+            set_strategy => restrict_to :[OWNER];
+            withdraw_other_rewards => restrict_to :[OWNER];
+
+            invest => restrict_to :[keeper, OWNER];
+            divest => restrict_to :[keeper, OWNER];
+            harvest => restrict_to :[keeper, OWNER];
+            compound => restrict_to :[keeper, OWNER];
+
+            deposit => PUBLIC;
+            redeem => PUBLIC;
+
+            get_total_assets => PUBLIC;
+            get_share_ratio => PUBLIC;
+            convert_to_shares => PUBLIC;
+            convert_to_assets => PUBLIC;
+
+        }
+    }
+
+    pub struct YieldVault {
+        /// Pool tracking the vault shares. Assets invested in the strategy are recorded as
+        /// its external liquidity, so shares use the same ratio math as AssetPool.
+        shares_pool: Owned<AssetPool>,
+        asset_res_address: ResourceAddress,
+
+        strategy: Option<Strategy>,
+
+        /// Strategy positions, i.e. pool units or stake receipts, indexed by resource
+        holdings: KeyValueStore<ResourceAddress, Vault>,
+
+        /// Resource of the current strategy position, known once assets are first invested
+        holding_res_address: Option<ResourceAddress>,
+    }
+
+    impl YieldVault {
+        /// Instantiate a vault without strategy. The strategy is set afterwards, once the vault
+        /// address can be allowed by the strategy. Returns the vault component and the share
+        /// resource address.
+        pub fn instantiate(
+            asset_res_address: ResourceAddress,
+            owner_role: OwnerRole,
+            keeper_rule: AccessRule,
+        ) -> (Global<YieldVault>, ResourceAddress) {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(YieldVault::blueprint_id());
+
+            let component_rule = rule!(require(global_caller(component_address)));
+
+            let (shares_pool, share_res_address, _) =
+                AssetPool::instantiate_locally(asset_res_address, OwnerRole::None, component_rule);
+
+            let vault_component = Self {
+                shares_pool,
+                asset_res_address,
+                strategy: None,
+                holdings: KeyValueStore::new(),
+                holding_res_address: None,
+            }
+            .instantiate()
+            .prepare_to_globalize(owner_role)
+            .roles(roles!(
+                keeper => keeper_rule;
+            ))
+            .with_address(address_reservation)
+            .globalize();
+
+            (vault_component, share_res_address)
+        }
+
+        /* ADMIN METHODS */
+
+        /// Set the strategy. Any asset invested in the current strategy must be divested
+        /// first.
+        pub fn set_strategy(&mut self, strategy: Strategy) {
+            assert!(
+                self.shares_pool.get_pooled_amount().1 == Decimal::ZERO,
+                "Assets are still invested in the current strategy"
+            );
+
+            self.strategy = Some(strategy);
+            self.holding_res_address = None;
+        }
+
+        /// Withdraw strategy rewards that are not in the vault asset, to be converted and
+        /// compounded
+        pub fn withdraw_other_rewards(&mut self, res_address: ResourceAddress) -> Bucket {
+            let amount = self.shares_pool.get_other_amount(res_address);
+
+            self.shares_pool.withdraw_other(res_address, amount)
+        }
+
+        /* KEEPER METHODS */
+
+        /// Move idle assets to the strategy
+        pub fn invest(&mut self, amount: Decimal) {
+            let assets = self.shares_pool.protected_withdraw(
+                amount,
+                WithdrawType::ForTemporaryUse,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+
+            match self._get_strategy() {
+                Strategy::AssetPool(pool) => self._invest_in_pool(&pool, assets),
+                Strategy::NativePoolAdapter(pool) => self._invest_in_pool(&pool, assets),
+                Strategy::StakingRewards(mut staking) => match self.holding_res_address {
+                    Some(_) => staking.add_stake(self._stake_receipt_proof(), assets),
+                    None => {
+                        let receipt = staking.stake(assets);
+                        self._put_holding(receipt);
+                    }
+                },
+            }
+        }
+
+        /// Move assets back from the strategy to the idle liquidity
+        pub fn divest(&mut self, amount: Decimal) {
+            assert!(self.holding_res_address.is_some(), "Nothing invested");
+
+            let assets = match self._get_strategy() {
+                Strategy::AssetPool(pool) => self._divest_from_pool(&pool, amount),
+                Strategy::NativePoolAdapter(pool) => self._divest_from_pool(&pool, amount),
+                Strategy::StakingRewards(mut staking) => {
+                    staking.unstake(self._stake_receipt_proof(), amount)
+                }
+            };
+
+            self.shares_pool
+                .protected_deposit(assets, DepositType::FromTemporaryUse);
+        }
+
+        /// Compound the strategy yield into the vault, increasing the value of every share
+        pub fn harvest(&mut self) {
+            self._harvest();
+        }
+
+        /// Add assets to the vault without minting shares, e.g. converted rewards
+        pub fn compound(&mut self, assets: Bucket) {
+            let gain = assets.amount();
+
+            self.shares_pool
+                .protected_deposit(assets, DepositType::LiquidityAddition);
+
+            Runtime::emit_event(HarvestEvent {
+                gain,
+                share_ratio: self.shares_pool.get_pool_unit_ratio(),
+            });
+        }
+
+        /* DEPOSITOR METHODS */
+
+        /// Deposit assets and receive vault shares, priced after compounding the pending
+        /// strategy yield. Returns the shares and the change.
+        pub fn deposit(&mut self, assets: Bucket) -> (Bucket, Bucket) {
+            self._harvest();
+
+            self.shares_pool.contribute(assets)
+        }
+
+        /// Redeem vault shares for assets, priced after compounding the pending strategy yield,
+        /// divesting from the strategy if the idle liquidity is not enough. Returns the assets
+        /// and the unused shares.
+        pub fn redeem(&mut self, shares: Bucket) -> (Bucket, Bucket) {
+            self._harvest();
+
+            let amount = self.shares_pool.get_amount_for_units(shares.amount());
+            let (idle, _) = self.shares_pool.get_pooled_amount();
+
+            if amount > idle {
+                self.divest(amount - idle);
+            }
+
+            self.shares_pool.redeem(shares)
+        }
+
+        /* GETTERS */
+
+        // Getters value the strategy as of the last harvest, deposit or redemption
+
+        /// Idle and invested assets
+        pub fn get_total_assets(&self) -> Decimal {
+            let (idle, invested) = self.shares_pool.get_pooled_amount();
+
+            idle + invested
+        }
+
+        pub fn get_share_ratio(&self) -> PreciseDecimal {
+            self.shares_pool.get_pool_unit_ratio()
+        }
+
+        pub fn convert_to_shares(&self, assets: Decimal) -> Decimal {
+            self.shares_pool.get_units_for_amount(assets)
+        }
+
+        pub fn convert_to_assets(&self, shares: Decimal) -> Decimal {
+            self.shares_pool.get_amount_for_units(shares)
+        }
+
+        /* PRIVATE UTILITY METHODS */
+
+        fn _get_strategy(&self) -> Strategy {
+            self.strategy.clone().expect("No strategy set")
+        }
+
+        /// Compound the yield of the strategy since the last harvest, so that the share ratio
+        /// reflects the current value of the strategy
+        fn _harvest(&mut self) {
+            let (_, invested) = self.shares_pool.get_pooled_amount();
+
+            let gain = match (self.strategy.clone(), self.holding_res_address) {
+                (None, _) | (_, None) => Decimal::ZERO,
+                (Some(Strategy::AssetPool(pool)), Some(_)) => {
+                    self._get_pool_value(&pool) - invested
+                }
+                (Some(Strategy::NativePoolAdapter(pool)), Some(_)) => {
+                    self._get_pool_value(&pool) - invested
+                }
+                (Some(Strategy::StakingRewards(mut staking)), Some(_)) => {
+                    let rewards = staking.claim(self._stake_receipt_proof());
+                    let amount = rewards.amount();
+
+                    if rewards.resource_address() != self.asset_res_address {
+                        // Rewards in another resource are kept until converted and compounded
+                        self.shares_pool.deposit_other(rewards);
+
+                        Decimal::ZERO
+                    } else if amount > Decimal::ZERO {
+                        staking.add_stake(self._stake_receipt_proof(), rewards);
+
+                        amount
+                    } else {
+                        rewards.drop_empty();
+
+                        Decimal::ZERO
+                    }
+                }
+            };
+
+            if gain > Decimal::ZERO {
+                self.shares_pool.increase_external_liquidity(gain);
+            } else if gain < Decimal::ZERO {
+                self.shares_pool.decrease_external_liquidity(-gain);
+            }
+
+            Runtime::emit_event(HarvestEvent {
+                gain,
+                share_ratio: self.shares_pool.get_pool_unit_ratio(),
+            });
+        }
+
+        /// Contribute to a pool strategy, keeping the pool units
+        fn _invest_in_pool(&mut self, pool: &dyn SingleResourcePoolInterface, assets: Bucket) {
+            let (units, change) = pool.contribute(assets);
+
+            self.shares_pool
+                .protected_deposit(change, DepositType::FromTemporaryUse);
+            self._put_holding(units);
+        }
+
+        /// Redeem the pool units worth the given amount from a pool strategy, or all of them
+        fn _divest_from_pool(
+            &mut self,
+            pool: &dyn SingleResourcePoolInterface,
+            amount: Decimal,
+        ) -> Bucket {
+            let holding_res_address = self.holding_res_address.unwrap();
+
+            let units = {
+                let mut holding = self.holdings.get_mut(&holding_res_address).unwrap();
+                let unit_amount = pool.get_units_for_amount(amount).min(holding.amount());
+
+                holding.take(unit_amount)
+            };
+
+            let (assets, remaining_units) = pool.redeem(units);
+            self._put_holding(remaining_units);
+
+            assets
+        }
+
+        /// Current value of the pool units held in a pool strategy
+        fn _get_pool_value(&self, pool: &dyn SingleResourcePoolInterface) -> Decimal {
+            let holding_res_address = self.holding_res_address.unwrap();
+            let units = self.holdings.get(&holding_res_address).unwrap().amount();
+
+            pool.get_amount_for_units(units)
+        }
+
+        fn _put_holding(&mut self, holding: Bucket) {
+            let res_address = holding.resource_address();
+            let vault_exists = self.holdings.get(&res_address).is_some();
+
+            if vault_exists {
+                self.holdings.get_mut(&res_address).unwrap().put(holding);
+            } else {
+                self.holdings
+                    .insert(res_address, Vault::with_bucket(holding));
+            }
+
+            self.holding_res_address = Some(res_address);
+        }
+
+        fn _stake_receipt_proof(&self) -> Proof {
+            let holding_res_address = self.holding_res_address.expect("Nothing invested");
+            let receipt = self.holdings.get(&holding_res_address).unwrap();
+            let receipt = receipt.as_non_fungible();
+
+            receipt
+                .create_proof_of_non_fungibles(&receipt.non_fungible_local_ids(1))
+                .into()
+        }
+    }
+}
@@ -0,0 +1,341 @@
+use radix_engine::transaction::TransactionReceipt;
+use radix_engine_interface::prelude::*;
+use scrypto::this_package;
+use test_support::*;
+use transaction::prelude::*;
+
+/// Holds the asset tokens left after funding the depositors and the staking rewards
+const OWNER: usize = 0;
+const KEEPER: usize = 1;
+/// Depositors holding 1000 asset tokens each
+const ALICE: usize = 2;
+const BOB: usize = 3;
+
+/// Manifest encoding of `Strategy::NativePoolAdapter`
+fn adapter_strategy(adapter: ComponentAddress) -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 1,
+        fields: vec![to_manifest_value_and_unwrap!(&adapter)],
+    }
+}
+
+/// Manifest encoding of `Strategy::StakingRewards`
+fn staking_strategy(staking: ComponentAddress) -> ManifestValue {
+    ManifestValue::Enum {
+        discriminator: 2,
+        fields: vec![to_manifest_value_and_unwrap!(&staking)],
+    }
+}
+
+/// Vault at epoch 10, with a StakingRewards strategy distributing 10 asset tokens per epoch.
+/// The strategy blueprints are linked into the vault package, so they are instantiated from
+/// the same package.
+struct TestVault {
+    env: TestEnv,
+    vault: ComponentAddress,
+    staking: ComponentAddress,
+    shares: ResourceAddress,
+    asset: ResourceAddress,
+}
+
+impl TestVault {
+    fn new() -> Self {
+        let mut env = TestEnv::new(this_package!(), 4);
+        env.set_epoch(10);
+        let asset = env
+            .test_runner
+            .create_fungible_resource(dec!(10000), 18, env.accounts[OWNER]);
+        let owner_role = OwnerRole::Fixed(rule!(require(env.badges[OWNER].clone())));
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                env.package_address,
+                "YieldVault",
+                "instantiate",
+                manifest_args!(
+                    asset,
+                    owner_role.clone(),
+                    rule!(require(env.badges[KEEPER].clone()))
+                ),
+            )
+            .call_function(
+                env.package_address,
+                "StakingRewards",
+                "instantiate",
+                manifest_args!(asset, asset, dec!(10), owner_role, rule!(deny_all)),
+            )
+            .withdraw_from_account(env.accounts[OWNER], asset, dec!(2000))
+            .take_from_worktop(asset, dec!(1000), "alice")
+            .call_method_with_name_lookup(env.accounts[ALICE], "deposit", |lookup| {
+                (lookup.bucket("alice"),)
+            })
+            .take_from_worktop(asset, dec!(1000), "bob")
+            .call_method_with_name_lookup(env.accounts[BOB], "deposit", |lookup| {
+                (lookup.bucket("bob"),)
+            })
+            .build();
+        let receipt = env
+            .test_runner
+            .execute_manifest_ignoring_fee(manifest, env.badges.clone());
+        let commit = receipt.expect_commit_success();
+        let (vault, shares): (ComponentAddress, ResourceAddress) = commit.output(0);
+        let staking: ComponentAddress = commit.new_component_addresses()[1];
+
+        let mut vault = Self {
+            env,
+            vault,
+            staking,
+            shares,
+            asset,
+        };
+
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(vault.env.accounts[OWNER], asset, dec!(1000))
+            .take_all_from_worktop(asset, "rewards")
+            .call_method_with_name_lookup(staking, "top_up_rewards", |lookup| {
+                (lookup.bucket("rewards"),)
+            })
+            .call_method(
+                vault.vault,
+                "set_strategy",
+                manifest_args!(staking_strategy(staking)),
+            );
+        vault.env.execute(OWNER, builder).expect_commit_success();
+
+        vault
+    }
+
+    /// Call `deposit` with assets or `redeem` with shares of the depositor
+    fn call_with_bucket(
+        &mut self,
+        depositor: usize,
+        method: &str,
+        res_address: ResourceAddress,
+        amount: Decimal,
+    ) -> TransactionReceipt {
+        let builder = ManifestBuilder::new()
+            .withdraw_from_account(self.env.accounts[depositor], res_address, amount)
+            .take_all_from_worktop(res_address, "bucket")
+            .call_method_with_name_lookup(self.vault, method, |lookup| (lookup.bucket("bucket"),));
+
+        self.env.execute(depositor, builder)
+    }
+
+    fn deposit(&mut self, depositor: usize, amount: Decimal) -> TransactionReceipt {
+        let asset = self.asset;
+
+        self.call_with_bucket(depositor, "deposit", asset, amount)
+    }
+
+    fn redeem(&mut self, depositor: usize, shares: Decimal) -> TransactionReceipt {
+        let share_res_address = self.shares;
+
+        self.call_with_bucket(depositor, "redeem", share_res_address, shares)
+    }
+
+    fn call(
+        &mut self,
+        account: usize,
+        method: &str,
+        args: impl ResolvableArguments,
+    ) -> TransactionReceipt {
+        self.env.call(account, self.vault, method, args)
+    }
+
+    fn get_total_assets(&mut self) -> Decimal {
+        self.call(ALICE, "get_total_assets", manifest_args!())
+            .expect_commit_success()
+            .output(0)
+    }
+}
+
+#[test]
+fn test_deposit_and_redeem_idle_assets() {
+    let mut vault = TestVault::new();
+    let (asset, shares) = (vault.asset, vault.shares);
+
+    vault.deposit(ALICE, dec!(100)).expect_commit_success();
+    assert_eq!(vault.env.balance(ALICE, shares), dec!(100));
+    assert_eq!(vault.get_total_assets(), dec!(100));
+
+    vault.redeem(ALICE, dec!(40)).expect_commit_success();
+    assert_eq!(vault.env.balance(ALICE, asset), dec!(940));
+    assert_eq!(vault.get_total_assets(), dec!(60));
+}
+
+#[test]
+fn test_harvest_compounds_the_strategy_yield() {
+    let mut vault = TestVault::new();
+    let (asset, shares) = (vault.asset, vault.shares);
+
+    vault.deposit(ALICE, dec!(100)).expect_commit_success();
+    vault
+        .call(KEEPER, "invest", manifest_args!(dec!(100)))
+        .expect_commit_success();
+    assert_eq!(vault.get_total_assets(), dec!(100));
+
+    vault.env.set_epoch(15);
+    vault
+        .call(KEEPER, "harvest", manifest_args!())
+        .expect_commit_success();
+    assert_eq!(vault.get_total_assets(), dec!(150));
+
+    // Later depositors get shares at the compounded ratio
+    vault.deposit(BOB, dec!(150)).expect_commit_success();
+    assert_eq!(vault.env.balance(BOB, shares), dec!(100));
+
+    vault.redeem(ALICE, dec!(100)).expect_commit_success();
+    assert_eq!(vault.env.balance(ALICE, asset), dec!(1050));
+
+    // The idle liquidity is not enough, the rest is divested from the strategy
+    vault.redeem(BOB, dec!(100)).expect_commit_success();
+    assert_eq!(vault.env.balance(BOB, asset), dec!(1000));
+    assert_eq!(vault.get_total_assets(), dec!(0));
+}
+
+#[test]
+fn test_deposits_and_redemptions_compound_the_pending_yield() {
+    let mut vault = TestVault::new();
+    let (asset, shares) = (vault.asset, vault.shares);
+
+    vault.deposit(ALICE, dec!(100)).expect_commit_success();
+    vault
+        .call(KEEPER, "invest", manifest_args!(dec!(100)))
+        .expect_commit_success();
+
+    // Without harvest by the keeper, Bob's shares are priced with the 50 tokens of rewards
+    vault.env.set_epoch(15);
+    vault.deposit(BOB, dec!(150)).expect_commit_success();
+    assert_eq!(vault.env.balance(BOB, shares), dec!(100));
+    assert_eq!(vault.get_total_assets(), dec!(300));
+
+    // Alice's redemption includes the 30 tokens of rewards since Bob's deposit
+    vault.env.set_epoch(18);
+    vault.redeem(ALICE, dec!(100)).expect_commit_success();
+    assert_eq!(vault.env.balance(ALICE, asset), dec!(1065));
+    assert_eq!(vault.get_total_assets(), dec!(165));
+}
+
+#[test]
+fn test_native_pool_adapter_strategy() {
+    let mut vault = TestVault::new();
+    let (asset, vault_address) = (vault.asset, vault.vault);
+
+    // The adapter can be used by the vault, and by the owner to simulate the pool yield
+    let admin_rule =
+        rule!(require(global_caller(vault_address)) || require(vault.env.badges[OWNER].clone()));
+    let manifest = ManifestBuilder::new()
+        .call_function(
+            vault.env.package_address,
+            "NativePoolAdapter",
+            "instantiate",
+            manifest_args!(asset, OwnerRole::None, admin_rule),
+        )
+        .build();
+    let adapter = vault
+        .env
+        .test_runner
+        .execute_manifest_ignoring_fee(manifest, vec![])
+        .expect_commit_success()
+        .new_component_addresses()[0];
+
+    vault
+        .call(
+            OWNER,
+            "set_strategy",
+            manifest_args!(adapter_strategy(adapter)),
+        )
+        .expect_commit_success();
+
+    vault.deposit(ALICE, dec!(100)).expect_commit_success();
+    vault
+        .call(KEEPER, "invest", manifest_args!(dec!(100)))
+        .expect_commit_success();
+
+    let builder = ManifestBuilder::new().call_method(
+        adapter,
+        "increase_external_liquidity",
+        manifest_args!(dec!(10)),
+    );
+    vault.env.execute(OWNER, builder).expect_commit_success();
+
+    vault
+        .call(KEEPER, "harvest", manifest_args!())
+        .expect_commit_success();
+    assert_eq!(vault.get_total_assets(), dec!(110));
+
+    // Half of the shares are redeemed from the adapter at the compounded ratio
+    vault.redeem(ALICE, dec!(50)).expect_commit_success();
+    assert_eq!(vault.env.balance(ALICE, asset), dec!(955));
+    assert_eq!(vault.get_total_assets(), dec!(55));
+}
+
+#[test]
+fn test_compound_increases_the_share_value() {
+    let mut vault = TestVault::new();
+    let (asset, vault_address, owner) = (vault.asset, vault.vault, vault.env.accounts[OWNER]);
+
+    vault.deposit(ALICE, dec!(100)).expect_commit_success();
+
+    let builder = ManifestBuilder::new()
+        .withdraw_from_account(owner, asset, dec!(20))
+        .take_all_from_worktop(asset, "assets")
+        .call_method_with_name_lookup(vault_address, "compound", |lookup| {
+            (lookup.bucket("assets"),)
+        });
+    vault.env.execute(OWNER, builder).expect_commit_success();
+
+    let value: Decimal = vault
+        .call(ALICE, "convert_to_assets", manifest_args!(dec!(100)))
+        .expect_commit_success()
+        .output(0);
+    assert_eq!(value, dec!(120));
+
+    vault.redeem(ALICE, dec!(100)).expect_commit_success();
+    assert_eq!(vault.env.balance(ALICE, asset), dec!(1020));
+}
+
+#[test]
+fn test_strategy_methods_require_their_role() {
+    let mut vault = TestVault::new();
+    let staking = vault.staking;
+
+    vault.deposit(ALICE, dec!(100)).expect_commit_success();
+
+    vault
+        .call(ALICE, "invest", manifest_args!(dec!(100)))
+        .expect_commit_failure();
+    vault
+        .call(KEEPER, "invest", manifest_args!(dec!(100)))
+        .expect_commit_success();
+
+    vault
+        .call(ALICE, "divest", manifest_args!(dec!(50)))
+        .expect_commit_failure();
+    vault
+        .call(
+            KEEPER,
+            "set_strategy",
+            manifest_args!(staking_strategy(staking)),
+        )
+        .expect_commit_failure();
+
+    // The strategy can only be changed once everything is divested
+    vault
+        .call(
+            OWNER,
+            "set_strategy",
+            manifest_args!(staking_strategy(staking)),
+        )
+        .expect_commit_failure();
+    vault
+        .call(KEEPER, "divest", manifest_args!(dec!(100)))
+        .expect_commit_success();
+    vault
+        .call(
+            OWNER,
+            "set_strategy",
+            manifest_args!(staking_strategy(staking)),
+        )
+        .expect_commit_success();
+}